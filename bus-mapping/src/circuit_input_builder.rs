@@ -1,19 +1,28 @@
 //! This module contains the CircuitInputBuilder, which is an object that takes
 //! types from geth / web3 and outputs the circuit inputs.
 use crate::eth_types::{
-    self, Address, GethExecStep, GethExecTrace, ToAddress, ToBigEndian, Word,
-    H256,
+    self, Address, EIP1186ProofResponse, GethExecStep, GethExecTrace,
+    ToAddress, ToBigEndian, ToWord, Word, H256,
+};
+use crate::evm::{
+    memory_expansion, memory_expansion_gas_cost, memory_word_size,
+    tx_intrinsic_gas, Gas, GasCost, GlobalCounter, OpcodeId, ProgramCounter,
+    StackAddress, MAX_CODE_SIZE, MAX_REFUND_QUOTIENT_OF_GAS_USED,
 };
-use crate::evm::{Gas, GasCost, GlobalCounter, OpcodeId, ProgramCounter};
 use crate::exec_trace::OperationRef;
 use crate::geth_errors::*;
 use crate::operation::container::OperationContainer;
 use crate::operation::RW;
-use crate::operation::{Op, Operation};
+use crate::operation::{
+    AccountBalanceOp, AccountCodeHashOp, AccountNonceOp, CallContextField,
+    CallContextOp, Op, OpEnum, Operation, TxRefundOp,
+};
 use crate::state_db::StateDB;
 use crate::{BlockConstants, Error};
 use core::fmt::Debug;
-use ethers_core::utils::{get_contract_address, get_create2_address};
+use ethers_core::utils::{
+    get_contract_address, get_create2_address, keccak256,
+};
 use std::collections::{hash_map::Entry, HashMap, HashSet};
 
 /// Out of Gas errors by opcode
@@ -34,6 +43,9 @@ pub enum OogError {
     ExtCodeCopy,
     /// Out of Gas for RETURNDATACOPY
     ReturnDataCopy,
+    /// Out of Gas for SLOAD and SSTORE, including SSTORE with less gas than
+    /// the reentrancy sentry (EIP-2200)
+    SloadSstore,
     /// Out of Gas for LOG
     Log,
     /// Out of Gas for CALL
@@ -46,6 +58,9 @@ pub enum OogError {
     Create2,
     /// Out of Gas for STATICCALL
     StaticCall,
+    /// Out of Gas for SELFDESTRUCT, whose gas cost depends on the warm status
+    /// of the beneficiary and on whether the transfer creates it
+    SelfDestruct,
 }
 
 /// EVM Execution Error
@@ -96,7 +111,7 @@ pub struct ExecStep {
     pub pc: ProgramCounter,
     /// Stack size
     pub stack_size: usize,
-    /// Memory size
+    /// Memory size in words after the expansion done by the step
     pub memory_size: usize,
     /// Gas left
     pub gas_left: Gas,
@@ -115,7 +130,8 @@ pub struct ExecStep {
     pub bus_mapping_instance: Vec<OperationRef>,
     /// Error generated by this step
     pub error: Option<ExecError>,
-    /// The step has been reverted
+    /// The state changes of the step are undone, as it's executed by a call
+    /// that fails or by a callee of one.
     pub reverted: bool,
 }
 
@@ -131,7 +147,8 @@ impl ExecStep {
             op: step.op,
             pc: step.pc,
             stack_size: step.stack.0.len(),
-            memory_size: step.memory.0.len(),
+            memory_size: memory_word_size(0, step.memory.0.len() as u64)
+                as usize,
             gas_left: step.gas,
             gas_cost: step.gas_cost,
             call_index,
@@ -144,6 +161,81 @@ impl ExecStep {
     }
 }
 
+/// Returns the memory size in words after the step, which is expanded from
+/// `curr_memory_word_size` words to fit the memory regions accessed by its
+/// opcode.
+fn next_memory_word_size(
+    step: &GethExecStep,
+    curr_memory_word_size: u64,
+) -> u64 {
+    // A missing argument makes the step fail with a stack underflow, which
+    // doesn't access the memory.
+    let arg =
+        |n: usize| step.stack.nth_last(n).unwrap_or_else(|_| Word::zero());
+    let regions = match step.op {
+        OpcodeId::MLOAD | OpcodeId::MSTORE => vec![(arg(0), Word::from(32))],
+        OpcodeId::MSTORE8 => vec![(arg(0), Word::one())],
+        OpcodeId::SHA3
+        | OpcodeId::RETURN
+        | OpcodeId::REVERT
+        | OpcodeId::LOG0
+        | OpcodeId::LOG1
+        | OpcodeId::LOG2
+        | OpcodeId::LOG3
+        | OpcodeId::LOG4 => vec![(arg(0), arg(1))],
+        OpcodeId::CALLDATACOPY
+        | OpcodeId::CODECOPY
+        | OpcodeId::RETURNDATACOPY => vec![(arg(0), arg(2))],
+        OpcodeId::EXTCODECOPY => vec![(arg(1), arg(3))],
+        OpcodeId::CREATE | OpcodeId::CREATE2 => vec![(arg(1), arg(2))],
+        OpcodeId::CALL | OpcodeId::CALLCODE => {
+            vec![(arg(3), arg(4)), (arg(5), arg(6))]
+        }
+        OpcodeId::DELEGATECALL | OpcodeId::STATICCALL => {
+            vec![(arg(2), arg(3)), (arg(4), arg(5))]
+        }
+        _ => vec![],
+    };
+
+    // An access beyond the 64-bit address space runs out of gas, so the
+    // address is saturated instead of expanding the memory past it.
+    regions.into_iter().fold(
+        curr_memory_word_size,
+        |memory_word_size, (offset, length)| {
+            let address = if length.is_zero() {
+                0
+            } else {
+                offset
+                    .saturating_add(length)
+                    .min(Word::from(u64::MAX - 31))
+                    .low_u64()
+            };
+            memory_expansion(memory_word_size, address).0
+        },
+    )
+}
+
+/// A step of a [`Transaction`] which doesn't execute any opcode, where the
+/// transaction itself updates the state before its first [`ExecStep`] or after
+/// its last one.
+#[derive(Debug, Default)]
+pub struct TxStep {
+    /// The global counter when this step was executed.
+    pub gc: GlobalCounter,
+    /// The list of references to Operations in the container
+    pub bus_mapping_instance: Vec<OperationRef>,
+}
+
+impl TxStep {
+    /// Create a new empty Self executed at the global counter `gc`.
+    pub fn new(gc: GlobalCounter) -> Self {
+        Self {
+            gc,
+            bus_mapping_instance: Vec::new(),
+        }
+    }
+}
+
 /// Context of a [`Block`] which can mutate in a [`Transaction`].
 #[derive(Debug)]
 pub struct BlockContext {
@@ -169,6 +261,20 @@ impl BlockContext {
 /// Circuit Input related to a block.
 #[derive(Debug)]
 pub struct Block {
+    /// Hash of the parent block.
+    pub parent_hash: H256,
+    /// State root of the parent block, from which the transactions of this
+    /// block are applied.  It's not part of the header of this block, so
+    /// it's `None` until set by the caller, and the public inputs and the
+    /// updates of the state trie of the block can't be computed without it.
+    pub prev_state_root: Option<H256>,
+    /// EIP-1186 proofs at `prev_state_root` of the accounts and storage slots
+    /// accessed by the transactions, from which the updates of the state trie
+    /// are proven.  They aren't given by the traces, so they're empty until
+    /// set by the caller.
+    pub state_proofs: Vec<EIP1186ProofResponse>,
+    /// State root after the transactions of this block.
+    pub state_root: H256,
     /// Constants associated to this block and the chain.
     pub constants: BlockConstants,
     /// Container of operations done in this block.
@@ -180,10 +286,14 @@ pub struct Block {
 impl Block {
     /// Create a new block.
     pub fn new<TX>(
-        _eth_block: &eth_types::Block<TX>,
+        eth_block: &eth_types::Block<TX>,
         constants: BlockConstants,
     ) -> Self {
         Self {
+            parent_hash: eth_block.parent_hash,
+            prev_state_root: None,
+            state_proofs: Vec::new(),
+            state_root: eth_block.state_root,
             constants,
             container: OperationContainer::new(),
             txs: Vec::new(),
@@ -200,10 +310,22 @@ impl Block {
     pub fn txs_mut(&mut self) -> &mut Vec<Transaction> {
         &mut self.txs
     }
+
+    /// Return the code deployed in this block with the given `code_hash`.
+    pub fn code(&self, code_hash: &H256) -> Option<&[u8]> {
+        self.code.get(code_hash).map(|code| code.as_slice())
+    }
+
+    /// Store the `code` deployed in this block, and return its hash.
+    pub(crate) fn insert_code(&mut self, code: Vec<u8>) -> H256 {
+        let code_hash = H256(keccak256(&code));
+        self.code.insert(code_hash, code);
+        code_hash
+    }
 }
 
 /// Type of a *CALL* Function.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum CallKind {
     /// CALL
     Call,
@@ -242,18 +364,48 @@ impl TryFrom<OpcodeId> for CallKind {
 }
 
 /// Circuit Input related to an Ethereum Call
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Call {
+    /// Unique identifier of the call in the block, which is the
+    /// [`GlobalCounter`] at its first step.
+    pub call_id: usize,
+    /// Identifier of the caller, or 0 for the root call.
+    pub caller_id: usize,
     /// Type of call
     kind: CallKind,
     /// This call is being executed without write access (STATIC)
     is_static: bool,
     /// This call generated implicity by a Transaction.
     is_root: bool,
+    /// The call ends without an error or a REVERT.
+    pub is_success: bool,
+    /// The state changes of this call are kept, which is the case when it
+    /// and all its callers succeed.
+    pub is_persistent: bool,
+    /// Depth of the call, starting at 1 for the root call.
+    pub depth: usize,
+    /// Address of the caller
+    pub caller_address: Address,
     /// Address where this call is being executed
     pub address: Address,
+    /// Address of the executed code, which differs from `address` in CALLCODE
+    /// and DELEGATECALL
+    pub code_address: Address,
     /// Code Hash
     code_hash: H256,
+    /// Value sent with the call
+    pub value: Word,
+    /// Offset of the call data in the memory of the caller
+    pub call_data_offset: u64,
+    /// Length of the call data
+    pub call_data_length: u64,
+    /// Offset in the memory of the caller where the return data is copied
+    pub return_data_offset: u64,
+    /// Length of the memory region of the caller where the return data is
+    /// copied
+    pub return_data_length: u64,
+    /// Gas available at the beginning of the call
+    pub gas: u64,
 }
 
 impl Call {
@@ -262,15 +414,71 @@ impl Call {
     pub fn is_create(&self) -> bool {
         self.kind.is_create()
     }
+
+    /// This call is being executed without write access (STATIC)
+    pub fn is_static(&self) -> bool {
+        self.is_static
+    }
+
+    /// This call is the root call of its transaction, whose call data is the
+    /// input of the transaction.
+    pub fn is_root(&self) -> bool {
+        self.is_root
+    }
+
+    /// Hash of the code executed by this call, which is the init code in a
+    /// contract creation
+    pub fn code_hash(&self) -> H256 {
+        self.code_hash
+    }
 }
 
 /// Context of a [`Call`].
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct CallContext {
     /// State Write Counter tracks the count of state write operations in the
     /// call.  When a subcall in this call succeeds, the `swc` increases by the
     /// number of successful state writes in the subcall.
     pub swc: usize,
+    /// Writes undoing the state writes counted by `swc`, in the order of
+    /// these.  They're done in reverse order at the end of the call if it
+    /// fails.
+    pub reversible_write_ops: Vec<OpEnum>,
+    /// Fields of the call context written when this call creates a subcall,
+    /// which are read back to restore the state of the call when the subcall
+    /// returns.
+    pub saved_fields: Vec<(CallContextField, Word)>,
+    /// Id of the last callee of this call, whose return data is accessed by
+    /// RETURNDATASIZE and RETURNDATACOPY.
+    pub last_callee_id: usize,
+    /// Offset of the return data of the last callee in its memory.
+    pub last_callee_return_data_offset: u64,
+    /// Return data of the last callee.
+    pub last_callee_return_data: Vec<u8>,
+}
+
+impl CallContext {
+    /// Count state writes of the call by the writes undoing them.
+    pub fn push_reversible_write_ops(
+        &mut self,
+        ops: impl IntoIterator<Item = OpEnum>,
+    ) {
+        for op in ops {
+            self.swc += 1;
+            self.reversible_write_ops.push(op);
+        }
+    }
+
+    /// Stop counting the state writes of the call starting at the one counted
+    /// when `swc` was `index`, returning the writes undoing them.
+    pub fn split_off_reversible_write_ops(
+        &mut self,
+        index: usize,
+    ) -> Vec<OpEnum> {
+        let ops = self.reversible_write_ops.split_off(index);
+        self.swc -= ops.len();
+        ops
+    }
 }
 
 #[derive(Debug)]
@@ -280,13 +488,20 @@ pub struct TransactionContext {
     /// The call_stack will always have a fixed element at index 0 which
     /// corresponds to the call implicitly created by the transaction.
     call_stack: Vec<(usize, CallContext)>,
+    /// Number of logs emitted so far by the transaction.
+    log_id: usize,
+    /// Whether each call of the transaction succeeds, by call index, which is
+    /// known from the trace before handling it.
+    calls_success: Vec<bool>,
 }
 
 impl TransactionContext {
     /// Create a new Self.
     pub fn new(_eth_tx: &eth_types::Transaction) -> Self {
         Self {
-            call_stack: vec![(0, CallContext { swc: 0 })],
+            call_stack: vec![(0, CallContext::default())],
+            log_id: 0,
+            calls_success: Vec::new(),
         }
     }
 
@@ -309,6 +524,25 @@ impl TransactionContext {
         call_ctx
     }
 
+    /// Return the context of the caller of the current call.
+    fn caller_ctx(&self) -> Option<&CallContext> {
+        let len = self.call_stack.len();
+        if len < 2 {
+            return None;
+        }
+        let (_, call_ctx) = &self.call_stack[len - 2];
+        Some(call_ctx)
+    }
+
+    fn caller_ctx_mut(&mut self) -> Option<&mut CallContext> {
+        let len = self.call_stack.len();
+        if len < 2 {
+            return None;
+        }
+        let (_, call_ctx) = &mut self.call_stack[len - 2];
+        Some(call_ctx)
+    }
+
     /// Push a new call index and context into the call stack.
     fn push_call_index_ctx(&mut self, index: usize, call_ctx: CallContext) {
         self.call_stack.push((index, call_ctx));
@@ -323,6 +557,8 @@ impl TransactionContext {
 #[derive(Debug)]
 /// Result of the parsing of an Ethereum Transaction.
 pub struct Transaction {
+    /// Hash
+    pub hash: H256,
     /// Nonce
     pub nonce: u64,
     /// Gas
@@ -333,44 +569,79 @@ pub struct Transaction {
     pub to: Address, // callee_address
     /// Value
     pub value: Word,
+    /// Gas Price
+    pub gas_price: Word,
     /// Input / Call Data
     pub input: Vec<u8>, // call_data
+    /// Gas charged before the execution of the transaction
+    pub intrinsic_gas: u64,
     calls: Vec<Call>,
+    begin_tx_step: TxStep,
     steps: Vec<ExecStep>,
+    end_tx_step: TxStep,
 }
 
 impl Transaction {
-    /// Create a new Self.
-    pub fn new(eth_tx: &eth_types::Transaction) -> Self {
-        let mut calls = Vec::new();
-        let code_hash = H256::zero();
-        if let Some(address) = eth_tx.to {
-            calls.push(Call {
-                kind: CallKind::Call,
-                is_static: false,
-                is_root: true,
-                address,
-                code_hash,
-            });
-        } else {
-            calls.push(Call {
-                kind: CallKind::Create,
-                is_static: false,
-                is_root: true,
-                address: Address::zero(),
-                code_hash,
-            });
-        }
+    /// Create a new Self, where `call_id` is the identifier of its root call.
+    pub fn new(call_id: usize, eth_tx: &eth_types::Transaction) -> Self {
+        let (kind, address) = match eth_tx.to {
+            Some(address) => (CallKind::Call, address),
+            None => (CallKind::Create, Address::zero()),
+        };
+        let (call_data_length, gas) =
+            (eth_tx.input.0.len() as u64, eth_tx.gas.as_u64());
+        let (access_list_addresses, access_list_storage_keys) = eth_tx
+            .access_list
+            .as_ref()
+            .map(|access_list| {
+                access_list
+                    .0
+                    .iter()
+                    .fold((0, 0), |(addresses, keys), item| {
+                        (addresses + 1, keys + item.storage_keys.len() as u64)
+                    })
+            })
+            .unwrap_or_default();
+        let calls = vec![Call {
+            call_id,
+            caller_id: 0,
+            kind,
+            is_static: false,
+            is_root: true,
+            is_success: true,
+            is_persistent: true,
+            depth: 1,
+            caller_address: eth_tx.from,
+            address,
+            code_address: address,
+            code_hash: H256::zero(),
+            value: eth_tx.value,
+            call_data_offset: 0,
+            call_data_length,
+            return_data_offset: 0,
+            return_data_length: 0,
+            gas,
+        }];
         Self {
+            hash: eth_tx.hash,
             nonce: eth_tx.nonce.as_u64(),
             gas: eth_tx.gas.as_u64(),
             from: eth_tx.from,
             to: eth_tx.to.unwrap_or_default(),
             value: eth_tx.value,
+            gas_price: eth_tx.gas_price.unwrap_or_default(),
             input: eth_tx.input.to_vec(),
+            intrinsic_gas: tx_intrinsic_gas(
+                eth_tx.to.is_none(),
+                &eth_tx.input.0,
+                access_list_addresses,
+                access_list_storage_keys,
+            ),
 
             calls,
+            begin_tx_step: TxStep::default(),
             steps: Vec::new(),
+            end_tx_step: TxStep::default(),
         }
     }
 
@@ -379,32 +650,44 @@ impl Transaction {
         self.calls[0].is_create()
     }
 
+    /// Return the list of calls of this transaction.
+    pub fn calls(&self) -> &[Call] {
+        &self.calls
+    }
+
+    /// Return the step at the beginning of this transaction, before its
+    /// execution.
+    pub fn begin_tx_step(&self) -> &TxStep {
+        &self.begin_tx_step
+    }
+
     /// Return the list of execution steps of this transaction.
     pub fn steps(&self) -> &[ExecStep] {
         &self.steps
     }
 
+    /// Return the step at the end of this transaction, after its execution.
+    pub fn end_tx_step(&self) -> &TxStep {
+        &self.end_tx_step
+    }
+
+    /// Return the memory size in words of the call at `call_index` after its
+    /// last step so far, which is zero before its first step.
+    pub fn call_memory_word_size(&self, call_index: usize) -> u64 {
+        self.steps
+            .iter()
+            .rev()
+            .find(|step| step.call_index == call_index)
+            .map_or(0, |step| step.memory_size as u64)
+    }
+
     #[cfg(test)]
     pub fn steps_mut(&mut self) -> &mut Vec<ExecStep> {
         &mut self.steps
     }
 
-    fn push_call(
-        &mut self,
-        parent_index: usize,
-        kind: CallKind,
-        address: Address,
-    ) -> usize {
-        let is_static =
-            kind == CallKind::StaticCall || self.calls[parent_index].is_static;
-        let code_hash = H256::zero();
-        self.calls.push(Call {
-            kind,
-            is_static,
-            is_root: false,
-            address,
-            code_hash,
-        });
+    fn push_call(&mut self, call: Call) -> usize {
+        self.calls.push(call);
         self.calls.len() - 1
     }
 }
@@ -430,12 +713,10 @@ pub struct CircuitInputStateRef<'a> {
 fn push_call(
     tx: &mut Transaction,
     tx_ctx: &mut TransactionContext,
-    kind: CallKind,
-    address: Address,
+    call: Call,
 ) {
-    let parent_index = tx_ctx.call_index();
-    let index = tx.push_call(parent_index, kind, address);
-    tx_ctx.push_call_index_ctx(index, CallContext { swc: 0 });
+    let index = tx.push_call(call);
+    tx_ctx.push_call_index_ctx(index, CallContext::default());
 }
 
 impl<'a> CircuitInputStateRef<'a> {
@@ -443,14 +724,33 @@ impl<'a> CircuitInputStateRef<'a> {
     /// [`GlobalCounter`] and then adds a reference to the stored operation
     /// ([`OperationRef`]) inside the bus-mapping instance of the current
     /// [`ExecStep`].  Then increase the block_ctx [`GlobalCounter`] by one.
+    /// State writes are counted in the current [`CallContext`] together with
+    /// the writes undoing them.
     pub fn push_op<T: Op>(&mut self, op: T) {
-        let op_ref = self
-            .block
-            .container
-            .insert(Operation::new(self.block_ctx.gc.inc_pre(), op));
+        let op = op.into_enum();
+        if let Some(reverse_op) = op.reverse() {
+            self.call_ctx_mut().push_reversible_write_ops([reverse_op]);
+        }
+        let op_ref = self.block.container.insert_op_enum(
+            self.block_ctx.gc.inc_pre(),
+            false,
+            op,
+        );
         self.step.bus_mapping_instance.push(op_ref);
     }
 
+    /// Index of the current transaction in the block, starting at 1.
+    pub fn tx_id(&self) -> usize {
+        self.block.txs.len() + 1
+    }
+
+    /// Increase the number of logs emitted by the transaction and return it,
+    /// which is the identifier of the new log, starting at 1.
+    pub fn next_log_id(&mut self) -> usize {
+        self.tx_ctx.log_id += 1;
+        self.tx_ctx.log_id
+    }
+
     /// Reference to the current Call
     pub fn call(&self) -> &Call {
         &self.tx.calls[self.tx_ctx.call_index()]
@@ -461,6 +761,11 @@ impl<'a> CircuitInputStateRef<'a> {
         self.tx_ctx.call_ctx()
     }
 
+    /// Mutable reference to the current CallContext
+    pub fn call_ctx_mut(&mut self) -> &mut CallContext {
+        self.tx_ctx.call_ctx_mut()
+    }
+
     /// Mutable reference to the current Call
     pub fn call_mut(&mut self) -> &mut Call {
         &mut self.tx.calls[self.tx_ctx.call_index()]
@@ -468,8 +773,505 @@ impl<'a> CircuitInputStateRef<'a> {
 
     /// Push a new [`Call`] into the [`Transaction`], and add its index and
     /// [`CallContext`] in the `call_stack` of the [`TransactionContext`]
-    pub fn push_call(&mut self, kind: CallKind, address: Address) {
-        push_call(self.tx, self.tx_ctx, kind, address)
+    pub fn push_call(&mut self, call: Call) {
+        push_call(self.tx, self.tx_ctx, call)
+    }
+
+    /// Transfer `value` from the balance of `sender` to the balance of
+    /// `receiver`, updating the [`StateDB`] and generating the
+    /// [`AccountBalanceOp`] writes of both accounts.
+    pub fn transfer(
+        &mut self,
+        sender: Address,
+        receiver: Address,
+        value: Word,
+    ) {
+        for (address, is_sender) in [(sender, true), (receiver, false)] {
+            let (_, account) = self.sdb.get_account_mut(&address);
+            let balance_prev = account.balance;
+            if is_sender {
+                account.balance -= value;
+            } else {
+                account.balance += value;
+            }
+            let balance = account.balance;
+            self.push_op(AccountBalanceOp::new(
+                RW::WRITE,
+                address,
+                balance,
+                balance_prev,
+            ));
+        }
+    }
+
+    /// Parse the [`Call`] created by a *CALL* or CREATE* `step` of the current
+    /// call, which is identified by `call_id`.  The gas forwarded to the
+    /// callee follows the "all but one 64th" rule (EIP-150).
+    pub fn parse_call(
+        &self,
+        step: &GethExecStep,
+        call_id: usize,
+    ) -> Result<Call, Error> {
+        let kind = CallKind::try_from(step.op)?;
+        let caller = self.call();
+
+        let (caller_address, address, code_address, value) = match kind {
+            CallKind::Call | CallKind::StaticCall => {
+                let to = step.stack.nth_last(1)?.to_address();
+                let value = match kind {
+                    CallKind::Call => step.stack.nth_last(2)?,
+                    _ => Word::zero(),
+                };
+                (caller.address, to, to, value)
+            }
+            CallKind::CallCode => {
+                let to = step.stack.nth_last(1)?.to_address();
+                (caller.address, caller.address, to, step.stack.nth_last(2)?)
+            }
+            CallKind::DelegateCall => {
+                let to = step.stack.nth_last(1)?.to_address();
+                (caller.caller_address, caller.address, to, caller.value)
+            }
+            CallKind::Create | CallKind::Create2 => {
+                let address = match kind {
+                    CallKind::Create => self.create_address()?,
+                    _ => self.create2_address(step)?,
+                };
+                (caller.address, address, address, step.stack.nth_last(0)?)
+            }
+        };
+
+        // Memory regions of the caller holding the call data, or the init code
+        // of CREATE and CREATE2, and receiving the return data
+        let (input, return_data) = match kind {
+            CallKind::Call | CallKind::CallCode => ((3, 4), Some((5, 6))),
+            CallKind::DelegateCall | CallKind::StaticCall => {
+                ((2, 3), Some((4, 5)))
+            }
+            CallKind::Create | CallKind::Create2 => ((1, 2), None),
+        };
+        let memory_region = |(offset, length): (usize, usize)| {
+            Ok::<_, Error>((
+                step.stack.nth_last(offset)?.low_u64(),
+                step.stack.nth_last(length)?.low_u64(),
+            ))
+        };
+        let (input_offset, input_length) = memory_region(input)?;
+        let (return_data_offset, return_data_length) = match return_data {
+            Some(return_data) => memory_region(return_data)?,
+            None => (0, 0),
+        };
+
+        // The memory of the caller is expanded to fit both regions, from its
+        // size after its previous step.  An expansion whose cost exceeds 64
+        // bits runs out of gas, which leaves no gas to the callee.
+        let curr_memory_word_size =
+            self.tx.call_memory_word_size(self.step.call_index);
+        let mut gas_cost = memory_expansion_gas_cost(
+            curr_memory_word_size,
+            self.step.memory_size as u64,
+        )
+        .unwrap_or(step.gas.0);
+
+        let gas = if kind.is_create() {
+            gas_cost += GasCost::CREATE.as_u64();
+            if kind == CallKind::Create2 {
+                gas_cost +=
+                    GasCost::SHA3_WORD.as_u64() * ((input_length + 31) / 32);
+            }
+
+            let gas_available = step.gas.0.saturating_sub(gas_cost);
+            gas_available - gas_available / 64
+        } else {
+            let has_value = !value.is_zero()
+                && matches!(kind, CallKind::Call | CallKind::CallCode);
+            gas_cost += if self.sdb.check_account_in_access_list(&code_address)
+            {
+                GasCost::WARM_STORAGE_READ_COST.as_u64()
+            } else {
+                GasCost::COLD_ACCOUNT_ACCESS_COST.as_u64()
+            };
+            if has_value {
+                gas_cost += GasCost::CALL_WITH_VALUE.as_u64();
+                if kind == CallKind::Call
+                    && self.sdb.get_account(&address).1.is_empty()
+                {
+                    gas_cost += GasCost::NEW_ACCOUNT.as_u64();
+                }
+            }
+
+            let gas_available = step.gas.0.saturating_sub(gas_cost);
+            let gas_requested = step.stack.nth_last(0)?;
+            let gas_requested = if gas_requested.bits() > 64 {
+                u64::MAX
+            } else {
+                gas_requested.as_u64()
+            };
+            let gas = gas_requested.min(gas_available - gas_available / 64);
+            if has_value {
+                gas + GasCost::CALL_STIPEND.as_u64()
+            } else {
+                gas
+            }
+        };
+
+        // The init code of CREATE and CREATE2 is executed instead of the code
+        // of the account, and it's not passed as call data.
+        let (code_hash, call_data_offset, call_data_length) =
+            if kind.is_create() {
+                let init_code = get_create_init_code(step)?;
+                (H256(keccak256(init_code)), 0, 0)
+            } else {
+                let code_hash = self.sdb.get_account(&code_address).1.codeHash;
+                (code_hash, input_offset, input_length)
+            };
+
+        Ok(Call {
+            call_id,
+            caller_id: caller.call_id,
+            kind,
+            is_static: kind == CallKind::StaticCall || caller.is_static,
+            is_root: false,
+            is_success: true,
+            is_persistent: true,
+            depth: caller.depth + 1,
+            caller_address,
+            address,
+            code_address,
+            code_hash,
+            value,
+            call_data_offset,
+            call_data_length,
+            return_data_offset,
+            return_data_length,
+            gas,
+        })
+    }
+
+    /// Generate the operations that switch from the current call to the
+    /// `callee` created by the *CALL* or CREATE* `step`: read the context of
+    /// the caller required by the callee, save the state of the caller, which
+    /// is restored when the callee returns, and set up the context of the
+    /// callee.  `stack_pointer` is the position of the result of the call in
+    /// the stack of the caller, and `memory_size` is the size of the memory of
+    /// the caller after the call.  The callee is identified by the global
+    /// counter at its first step.
+    pub fn handle_call(
+        &mut self,
+        step: &GethExecStep,
+        mut callee: Call,
+        stack_pointer: StackAddress,
+        memory_size: u64,
+    ) -> Result<(), Error> {
+        let caller = self.call().clone();
+        let tx_id = self.tx_id();
+
+        // The callee is persistent when it succeeds and its caller is
+        // persistent.  A callee not found in the trace succeeds.
+        callee.is_success = self
+            .tx_ctx
+            .calls_success
+            .get(self.tx.calls.len())
+            .copied()
+            .unwrap_or(true);
+        callee.is_persistent = caller.is_persistent && callee.is_success;
+
+        for (field, value) in [
+            (CallContextField::TxId, tx_id.into()),
+            (CallContextField::Depth, caller.depth.into()),
+            (
+                CallContextField::CallerAddress,
+                caller.caller_address.to_word(),
+            ),
+            (CallContextField::CalleeAddress, caller.address.to_word()),
+            (CallContextField::Value, caller.value),
+            (
+                CallContextField::IsStatic,
+                (caller.is_static() as u64).into(),
+            ),
+        ] {
+            self.push_op(CallContextOp::new(
+                RW::READ,
+                caller.call_id,
+                field,
+                value,
+            ));
+        }
+
+        let saved_fields = vec![
+            (CallContextField::ProgramCounter, (step.pc.0 + 1).into()),
+            (CallContextField::StackPointer, stack_pointer.0.into()),
+            (
+                CallContextField::GasLeft,
+                (step.gas.0 - self.step.gas_cost.as_u64()).into(),
+            ),
+            (CallContextField::MemorySize, memory_size.into()),
+            (
+                CallContextField::StateWriteCounter,
+                self.call_ctx().swc.into(),
+            ),
+            (
+                CallContextField::CodeHash,
+                Word::from_big_endian(caller.code_hash().as_bytes()),
+            ),
+        ];
+        for (field, value) in saved_fields.iter() {
+            self.push_op(CallContextOp::new(
+                RW::WRITE,
+                caller.call_id,
+                *field,
+                *value,
+            ));
+        }
+        self.call_ctx_mut().saved_fields = saved_fields;
+
+        let callee_fields = [
+            (CallContextField::CallerId, caller.call_id.into()),
+            (CallContextField::TxId, tx_id.into()),
+            (CallContextField::Depth, callee.depth.into()),
+            (
+                CallContextField::CallerAddress,
+                callee.caller_address.to_word(),
+            ),
+            (CallContextField::CalleeAddress, callee.address.to_word()),
+            (CallContextField::CodeAddress, callee.code_address.to_word()),
+            (
+                CallContextField::CallDataOffset,
+                callee.call_data_offset.into(),
+            ),
+            (
+                CallContextField::CallDataLength,
+                callee.call_data_length.into(),
+            ),
+            (
+                CallContextField::ReturnDataOffset,
+                callee.return_data_offset.into(),
+            ),
+            (
+                CallContextField::ReturnDataLength,
+                callee.return_data_length.into(),
+            ),
+            (CallContextField::Value, callee.value),
+            (
+                CallContextField::IsStatic,
+                (callee.is_static() as u64).into(),
+            ),
+            (
+                CallContextField::IsSuccess,
+                (callee.is_success as u64).into(),
+            ),
+            (CallContextField::Gas, callee.gas.into()),
+        ];
+        callee.call_id = usize::from(self.block_ctx.gc) + callee_fields.len();
+        for (field, value) in callee_fields {
+            self.push_op(CallContextOp::new(
+                RW::WRITE,
+                callee.call_id,
+                field,
+                value,
+            ));
+        }
+        self.push_call(callee);
+
+        Ok(())
+    }
+
+    /// Generate the operations that restore the state of the caller when the
+    /// current call returns: read the success of the call, which the caller
+    /// pushed on its stack, the caller id, the gas given to the call and the
+    /// fields saved in the context of the caller, and set the current call as
+    /// its last callee, whose return data is the memory returned by RETURN or
+    /// REVERT.  A contract creation returning its deployed code, a STOP and
+    /// an error leave no return data.
+    pub fn handle_return(&mut self, step: &GethExecStep) -> Result<(), Error> {
+        let (call_id, caller_id) = (self.call().call_id, self.call().caller_id);
+        let saved_fields = self
+            .tx_ctx
+            .caller_ctx()
+            .ok_or_else(|| {
+                Error::InvalidGethExecStep(
+                    "handle_return: call stack will be empty",
+                    Box::new(step.clone()),
+                )
+            })?
+            .saved_fields
+            .clone();
+
+        let has_return_data = match step.op {
+            OpcodeId::RETURN => {
+                self.step.error.is_none() && !self.call().is_create()
+            }
+            OpcodeId::REVERT => true,
+            _ => false,
+        };
+        let (return_data_offset, return_data) = if has_return_data {
+            let offset = step.stack.nth_last(0)?.low_u64();
+            let length = step.stack.nth_last(1)?.low_u64() as usize;
+            (offset, memory_bytes(step, offset as usize, length))
+        } else {
+            (0, vec![])
+        };
+
+        // STOP, RETURN, REVERT and SELFDESTRUCT read the caller id before, to
+        // know whether they end the root call.
+        let is_success = self.call().is_success;
+        let mut fields =
+            vec![(CallContextField::IsSuccess, (is_success as u64).into())];
+        if !matches!(
+            step.op,
+            OpcodeId::STOP
+                | OpcodeId::RETURN
+                | OpcodeId::REVERT
+                | OpcodeId::SELFDESTRUCT
+        ) {
+            fields.push((CallContextField::CallerId, caller_id.into()));
+        }
+        fields.push((CallContextField::Gas, self.call().gas.into()));
+        for (field, value) in fields {
+            self.push_op(CallContextOp::new(RW::READ, call_id, field, value));
+        }
+        for (field, value) in saved_fields {
+            self.push_op(CallContextOp::new(RW::READ, caller_id, field, value));
+        }
+        for (field, value) in [
+            (CallContextField::LastCalleeId, call_id.into()),
+            (
+                CallContextField::LastCalleeReturnDataOffset,
+                return_data_offset.into(),
+            ),
+            (
+                CallContextField::LastCalleeReturnDataLength,
+                return_data.len().into(),
+            ),
+        ] {
+            self.push_op(CallContextOp::new(
+                RW::WRITE,
+                caller_id,
+                field,
+                value,
+            ));
+        }
+
+        let caller_ctx = self
+            .tx_ctx
+            .caller_ctx_mut()
+            .expect("caller context checked above");
+        caller_ctx.last_callee_id = call_id;
+        caller_ctx.last_callee_return_data_offset = return_data_offset;
+        caller_ctx.last_callee_return_data = return_data;
+
+        Ok(())
+    }
+
+    /// Generate the writes that undo the state writes of the current call,
+    /// which fails, including those of its successful callees, from the last
+    /// one to the first one, and apply them to the [`StateDB`].
+    pub fn handle_reversion(&mut self) {
+        let reversible_write_ops =
+            self.call_ctx_mut().split_off_reversible_write_ops(0);
+        for op in reversible_write_ops.into_iter().rev() {
+            match &op {
+                OpEnum::Storage(op) => {
+                    self.sdb.set_storage(op.address(), op.key(), op.value())
+                }
+                OpEnum::TxAccessListAccountStorage(op) => {
+                    if !op.value() {
+                        self.sdb.remove_account_storage_from_access_list((
+                            op.address(),
+                            op.key(),
+                        ));
+                    }
+                }
+                OpEnum::TxAccessListAccount(op) => {
+                    if !op.value() {
+                        self.sdb.remove_account_from_access_list(op.address());
+                    }
+                }
+                OpEnum::TxRefund(op) => self.sdb.set_refund(op.value()),
+                OpEnum::AccountNonce(op) => {
+                    self.sdb.get_account_mut(op.address()).1.nonce = *op.value()
+                }
+                OpEnum::AccountBalance(op) => {
+                    self.sdb.get_account_mut(op.address()).1.balance =
+                        *op.value()
+                }
+                OpEnum::AccountCodeHash(op) => {
+                    self.sdb.get_account_mut(op.address()).1.codeHash =
+                        *op.value()
+                }
+                // The stack, the memory and the context of a call don't
+                // outlive it, and the logs of a failed call are never written
+                OpEnum::Stack(_)
+                | OpEnum::Memory(_)
+                | OpEnum::CallContext(_)
+                | OpEnum::TxLog(_) => {
+                    unreachable!("only state writes are reversible")
+                }
+            }
+            let op_ref = self.block.container.insert_op_enum(
+                self.block_ctx.gc.inc_pre(),
+                true,
+                op,
+            );
+            self.step.bus_mapping_instance.push(op_ref);
+        }
+    }
+}
+
+/// Returns whether each call in `geth_trace` succeeds, by call index, where
+/// the calls are indexed in the order in which they start.  A subcall succeeds
+/// when it pushes a non-zero result to the stack of its caller, and the root
+/// call when the transaction doesn't end with an error or a REVERT.
+fn get_calls_success(geth_trace: &GethExecTrace) -> Result<Vec<bool>, Error> {
+    let steps = &geth_trace.struct_logs;
+    let root_success = !geth_trace.failed
+        && steps.last().map_or(true, |step| {
+            step.error.is_none() && step.op != OpcodeId::REVERT
+        });
+    let mut calls_success = vec![root_success];
+    let mut call_stack = vec![0];
+    for (step, next_step) in steps.iter().zip(steps.iter().skip(1)) {
+        if step.depth + 1 == next_step.depth {
+            call_stack.push(calls_success.len());
+            calls_success.push(true);
+        } else if step.depth - 1 == next_step.depth {
+            let call_index = call_stack.pop().expect("call stack is empty");
+            calls_success[call_index] = !next_step.stack.last()?.is_zero();
+        }
+    }
+    Ok(calls_success)
+}
+
+/// Returns `length` bytes of the memory of `step` from `offset`, where the
+/// bytes past the end of the memory are zeros like after a memory expansion.
+pub(crate) fn memory_bytes(
+    step: &GethExecStep,
+    offset: usize,
+    length: usize,
+) -> Vec<u8> {
+    let mut bytes = vec![0u8; length];
+    if offset < step.memory.0.len() {
+        let end = step.memory.0.len().min(offset + length);
+        bytes[..end - offset].copy_from_slice(&step.memory.0[offset..end]);
+    }
+    bytes
+}
+
+/// Opcodes and geth reported errors found in an execution trace which are not
+/// supported yet by the [`CircuitInputBuilder`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UnsupportedTraceItems {
+    /// Unsupported opcodes, in order of first appearance in the trace.
+    pub opcodes: Vec<OpcodeId>,
+    /// Geth reported errors that don't correspond to any [`ExecError`], in
+    /// order of first appearance in the trace.
+    pub geth_errors: Vec<String>,
+}
+
+impl UnsupportedTraceItems {
+    /// Returns `true` if the whole trace is supported.
+    pub fn is_empty(&self) -> bool {
+        self.opcodes.is_empty() && self.geth_errors.is_empty()
     }
 }
 
@@ -487,11 +1289,14 @@ impl<'a> CircuitInputStateRef<'a> {
 ///
 /// The generated bus-mapping operations are:
 /// [`StackOp`](crate::operation::StackOp)s,
-/// [`MemoryOp`](crate::operation::MemoryOp)s and
-/// [`StorageOp`](crate::operation::StorageOp), which correspond to each
-/// [`OpcodeId`](crate::evm::OpcodeId)s used in each `ExecTrace` step so that
-/// the State Proof witnesses are already generated on a structured manner and
-/// ready to be added into the State circuit.
+/// [`MemoryOp`](crate::operation::MemoryOp)s,
+/// [`StorageOp`](crate::operation::StorageOp)s,
+/// [`TxAccessListAccountStorageOp`](crate::operation::
+/// TxAccessListAccountStorageOp)s and [`TxRefundOp`](crate::operation::
+/// TxRefundOp)s, which correspond to each [`OpcodeId`](crate::evm::OpcodeId)s
+/// used in each `ExecTrace` step so that the State Proof witnesses are already
+/// generated on a structured manner and ready to be added into the State
+/// circuit.
 pub struct CircuitInputBuilder {
     /// StateDB key-value DB
     pub sdb: StateDB,
@@ -515,6 +1320,15 @@ impl<'a> CircuitInputBuilder {
         }
     }
 
+    /// Set the code of the account at `address` in the state before the
+    /// transactions of the block, keeping the code in the block.  Returns the
+    /// hash of the code.
+    pub fn set_code(&mut self, address: &Address, code: Vec<u8>) -> H256 {
+        let code_hash = self.block.insert_code(code);
+        self.sdb.get_account_mut(address).1.codeHash = code_hash;
+        code_hash
+    }
+
     /// Obtain a mutable reference to the state that the `CircuitInputBuilder`
     /// maintains, contextualized to a particular transaction and a
     /// particular execution step in that transaction.
@@ -534,6 +1348,31 @@ impl<'a> CircuitInputBuilder {
         }
     }
 
+    /// Return the opcodes and the geth reported errors of `geth_trace` which
+    /// are not supported yet, so that callers can tell whether
+    /// [`CircuitInputBuilder::handle_tx`] will be able to handle the trace
+    /// before calling it.
+    pub fn unsupported_trace_items(
+        geth_trace: &GethExecTrace,
+    ) -> UnsupportedTraceItems {
+        let mut items = UnsupportedTraceItems::default();
+        for geth_step in geth_trace.struct_logs.iter() {
+            if !geth_step.op.is_supported()
+                && !items.opcodes.contains(&geth_step.op)
+            {
+                items.opcodes.push(geth_step.op);
+            }
+            if let Some(error) = &geth_step.error {
+                if get_step_reported_error(&geth_step.op, error).is_err()
+                    && !items.geth_errors.contains(error)
+                {
+                    items.geth_errors.push(error.clone());
+                }
+            }
+        }
+        items
+    }
+
     /// Handle a transaction with its corresponding execution trace to generate
     /// all the associated operations.  Each operation is registered in
     /// `self.block.container`, and each step stores the [`OperationRef`] to
@@ -543,8 +1382,61 @@ impl<'a> CircuitInputBuilder {
         eth_tx: &eth_types::Transaction,
         geth_trace: &GethExecTrace,
     ) -> Result<(), Error> {
-        let mut tx = Transaction::new(eth_tx);
+        // Reject the trace before modifying any state if it needs an opcode
+        // or an error case that is not supported yet.
+        for geth_step in geth_trace.struct_logs.iter() {
+            if !geth_step.op.is_supported() {
+                return Err(Error::UnsupportedOpcode(
+                    geth_step.op,
+                    geth_step.pc,
+                ));
+            }
+            if let Some(error) = &geth_step.error {
+                get_step_reported_error(&geth_step.op, error)?;
+            }
+        }
+
+        let mut tx = Transaction::new(usize::from(self.block_ctx.gc), eth_tx);
         let mut tx_ctx = TransactionContext::new(eth_tx);
+        tx_ctx.calls_success = get_calls_success(geth_trace)?;
+        tx.calls[0].is_success = tx_ctx.calls_success[0];
+        tx.calls[0].is_persistent = tx.calls[0].is_success;
+        // The root call executes the code of the receiver
+        if let Some(to) = eth_tx.to {
+            tx.calls[0].code_hash = self.sdb.get_account(&to).1.codeHash;
+        }
+
+        self.begin_tx(&mut tx)?;
+
+        // The sender, the receiver and the precompiled contracts are in the
+        // accessed addresses from the beginning of the transaction (EIP-2929).
+        self.sdb.add_account_to_access_list(&eth_tx.from);
+        if let Some(to) = eth_tx.to {
+            self.sdb.add_account_to_access_list(&to);
+        }
+        for precompile in 1..=9 {
+            self.sdb
+                .add_account_to_access_list(&Address::from_low_u64_be(
+                    precompile,
+                ));
+        }
+
+        // The value transfer is undone when the root call fails, unlike the
+        // purchase of the gas.
+        let container = &self.block.container;
+        tx_ctx.call_ctx_mut().push_reversible_write_ops(
+            tx.begin_tx_step.bus_mapping_instance[2..4]
+                .iter()
+                .map(|op_ref| {
+                    container.account_balance[op_ref.as_usize() - 1]
+                        .op()
+                        .clone()
+                        .into_enum()
+                        .reverse()
+                        .expect("balance write is reversible")
+                }),
+        );
+
         for (index, geth_step) in geth_trace.struct_logs.iter().enumerate() {
             let mut step = ExecStep::new(
                 geth_step,
@@ -552,48 +1444,240 @@ impl<'a> CircuitInputBuilder {
                 self.block_ctx.gc,
                 tx_ctx.call_ctx().swc,
             );
+            // The memory of the call grows from its size after its previous
+            // step, rather than from the memory in the trace, which is empty
+            // when the tracer doesn't record it.  A failing step doesn't
+            // expand the memory.
+            let curr_memory_word_size =
+                tx.call_memory_word_size(tx_ctx.call_index());
+            step.memory_size = if geth_step.error.is_some() {
+                curr_memory_word_size
+            } else {
+                next_memory_word_size(geth_step, curr_memory_word_size)
+            } as usize;
+            step.reverted = !tx.calls[tx_ctx.call_index()].is_persistent;
             let mut state_ref = self.state_ref(&mut tx, &mut tx_ctx, &mut step);
             geth_step.op.gen_associated_ops(
                 &mut state_ref,
                 &geth_trace.struct_logs[index..],
             )?;
-            tx.steps.push(step);
 
-            if let Some(geth_next_step) = geth_trace.struct_logs.get(index + 1)
-            {
-                if geth_step.depth + 1 == geth_next_step.depth {
-                    // Handle *CALL*
-                    // TODO: Set the proper address according to the call kind.
-                    let address = Address::zero();
-                    let kind = CallKind::try_from(geth_step.op)?;
-                    push_call(&mut tx, &mut tx_ctx, kind, address);
-                } else if geth_step.depth - 1 == geth_next_step.depth {
+            // The state writes of a failed call are undone at its end, before
+            // returning to the caller.
+            let geth_next_step = geth_trace.struct_logs.get(index + 1);
+            let is_call_end = geth_next_step
+                .map(|geth_next_step| {
+                    geth_step.depth - 1 == geth_next_step.depth
+                })
+                .unwrap_or(true);
+            if is_call_end && !state_ref.call().is_success {
+                state_ref.handle_reversion();
+            }
+            if let Some(geth_next_step) = geth_next_step {
+                if geth_step.depth - 1 == geth_next_step.depth {
                     // Handle *CALL* return
-                    if tx_ctx.call_stack.len() == 1 {
-                        return Err(Error::InvalidGethExecStep(
-                            "handle_tx: call stack will be empty",
-                            Box::new(geth_step.clone()),
-                        ));
-                    }
-                    let (_, call_ctx) = tx_ctx
+                    state_ref.handle_return(geth_step)?;
+                }
+            }
+            tx.steps.push(step);
+
+            if let Some(geth_next_step) = geth_next_step {
+                if geth_step.depth - 1 == geth_next_step.depth {
+                    let (call_index, call_ctx) = tx_ctx
                         .pop_call_index_ctx()
                         .expect("call stack is empty");
-                    // If the return was successful, accumulate the swc from the
-                    // subcall.
-                    if !geth_next_step.stack.last()?.is_zero() {
-                        tx_ctx.call_ctx_mut().swc += call_ctx.swc;
+                    // If the return was successful, accumulate the state
+                    // writes from the subcall, which are undone if the
+                    // caller fails.
+                    if tx.calls[call_index].is_success {
+                        tx_ctx.call_ctx_mut().push_reversible_write_ops(
+                            call_ctx.reversible_write_ops,
+                        );
                     }
                 }
             }
         }
+        self.end_tx(&mut tx, geth_trace);
         self.block.txs.push(tx);
+        self.sdb.commit_tx();
         Ok(())
     }
+
+    /// Generate the operations done by the transaction `tx` before the
+    /// execution of its first step, into its begin step: increase the nonce
+    /// of the sender, buy the gas of the transaction, transfer its value to
+    /// the receiver and read the code hash of the receiver of a call, which
+    /// is the code executed by the root call.  Returns
+    /// [`Error::InsufficientBalance`], before generating any operation, when
+    /// the sender can't pay for the gas and the value of the transaction.
+    pub fn begin_tx(&mut self, tx: &mut Transaction) -> Result<(), Error> {
+        // The sender pays upfront for all the gas of the transaction, the gas
+        // left is refunded at the end.
+        let sender_balance_prev = self.sdb.get_account(&tx.from).1.balance;
+        let sender_balance_after_gas = tx
+            .gas_price
+            .checked_mul(Word::from(tx.gas))
+            .and_then(|gas_fee| sender_balance_prev.checked_sub(gas_fee))
+            .ok_or(Error::InsufficientBalance(tx.from))?;
+        let sender_balance = sender_balance_after_gas
+            .checked_sub(tx.value)
+            .ok_or(Error::InsufficientBalance(tx.from))?;
+
+        let mut step = TxStep::new(self.block_ctx.gc);
+
+        let (_, sender) = self.sdb.get_account_mut(&tx.from);
+        let nonce_prev = sender.nonce;
+        sender.nonce += Word::one();
+        let nonce = sender.nonce;
+        self.push_tx_op(
+            &mut step,
+            AccountNonceOp::new(RW::WRITE, tx.from, nonce, nonce_prev),
+        );
+
+        self.write_balance(&mut step, tx.from, sender_balance_after_gas);
+
+        let receiver = if tx.is_create() {
+            get_contract_address(tx.from, nonce_prev)
+        } else {
+            tx.to
+        };
+        self.write_balance(&mut step, tx.from, sender_balance);
+        // The value taken from the sender fits in the balance of the receiver
+        let balance = self.sdb.get_account(&receiver).1.balance + tx.value;
+        self.write_balance(&mut step, receiver, balance);
+
+        if !tx.is_create() {
+            let code_hash = self.sdb.get_account(&receiver).1.codeHash;
+            self.push_tx_op(
+                &mut step,
+                AccountCodeHashOp::new(
+                    RW::READ,
+                    receiver,
+                    code_hash,
+                    code_hash,
+                ),
+            );
+        }
+
+        tx.begin_tx_step = step;
+        Ok(())
+    }
+
+    /// Generate the operations done by the transaction `tx` after the
+    /// execution of its last step in `geth_trace`, into its end step: read
+    /// the id of the transaction from the context of its root call, refund
+    /// the gas left and part of the gas used (EIP-3529) to the sender, pay
+    /// the priority fee of the rest of the gas used to the coinbase, and
+    /// delete the accounts destroyed by SELFDESTRUCT.
+    pub fn end_tx(&mut self, tx: &mut Transaction, geth_trace: &GethExecTrace) {
+        let mut step = TxStep::new(self.block_ctx.gc);
+
+        let tx_id = self.block.txs.len() + 1;
+        self.push_tx_op(
+            &mut step,
+            CallContextOp::new(
+                RW::READ,
+                tx.calls[0].call_id,
+                CallContextField::TxId,
+                Word::from(tx_id),
+            ),
+        );
+
+        let refund = self.sdb.refund();
+        self.push_tx_op(
+            &mut step,
+            TxRefundOp::new(RW::READ, tx_id, refund, refund),
+        );
+
+        // All the gas is consumed when the execution halts with an error.
+        let gas_left = match geth_trace.struct_logs.last() {
+            None => tx.gas - tx.intrinsic_gas,
+            Some(last_step) if last_step.error.is_some() => 0,
+            Some(last_step) => last_step.gas.0 - last_step.gas_cost.as_u64(),
+        };
+        let gas_used = tx.gas - gas_left;
+        let refund = refund.min(gas_used / MAX_REFUND_QUOTIENT_OF_GAS_USED);
+
+        let balance = self.sdb.get_account(&tx.from).1.balance
+            + tx.gas_price * Word::from(gas_left + refund);
+        self.write_balance(&mut step, tx.from, balance);
+
+        let coinbase = *self.block.constants.coinbase();
+        let effective_tip = tx
+            .gas_price
+            .saturating_sub(*self.block.constants.base_fee());
+        let balance = self.sdb.get_account(&coinbase).1.balance
+            + effective_tip * Word::from(gas_used - refund);
+        self.write_balance(&mut step, coinbase, balance);
+
+        // The nonce, balance and code hash of a destroyed account are reset,
+        // and its storage is dropped when the transaction is committed.
+        for address in self.sdb.destructed_accounts() {
+            let (_, account) = self.sdb.get_account_mut(&address);
+            let nonce_prev =
+                std::mem::replace(&mut account.nonce, Word::zero());
+            let code_hash_prev =
+                std::mem::replace(&mut account.codeHash, H256::zero());
+            self.push_tx_op(
+                &mut step,
+                AccountNonceOp::new(
+                    RW::WRITE,
+                    address,
+                    Word::zero(),
+                    nonce_prev,
+                ),
+            );
+            self.write_balance(&mut step, address, Word::zero());
+            self.push_tx_op(
+                &mut step,
+                AccountCodeHashOp::new(
+                    RW::WRITE,
+                    address,
+                    H256::zero(),
+                    code_hash_prev,
+                ),
+            );
+        }
+
+        tx.end_tx_step = step;
+    }
+
+    /// Push an [`Operation`] done by a transaction outside of its execution
+    /// steps into the [`OperationContainer`] with the next [`GlobalCounter`],
+    /// and add a reference to it inside the bus-mapping instance of `step`.
+    fn push_tx_op<T: Op>(&mut self, step: &mut TxStep, op: T) {
+        let op_ref = self
+            .block
+            .container
+            .insert(Operation::new(self.block_ctx.gc.inc_pre(), op));
+        step.bus_mapping_instance.push(op_ref);
+    }
+
+    /// Set the balance of `address` in the [`StateDB`] and generate the
+    /// corresponding [`AccountBalanceOp`] write in `step`.
+    fn write_balance(
+        &mut self,
+        step: &mut TxStep,
+        address: Address,
+        balance: Word,
+    ) {
+        let (_, account) = self.sdb.get_account_mut(&address);
+        let balance_prev = std::mem::replace(&mut account.balance, balance);
+        self.push_tx_op(
+            step,
+            AccountBalanceOp::new(RW::WRITE, address, balance, balance_prev),
+        );
+    }
 }
 
-fn get_step_reported_error(op: &OpcodeId, error: &str) -> ExecError {
-    if error == GETH_ERR_WRITE_PROTECTION {
+fn get_step_reported_error(
+    op: &OpcodeId,
+    error: &str,
+) -> Result<ExecError, Error> {
+    Ok(if error == GETH_ERR_WRITE_PROTECTION {
         ExecError::WriteProtection
+    } else if error == GETH_ERR_SSTORE_SENTRY {
+        ExecError::OutOfGas(OogError::SloadSstore)
     } else if error == GETH_ERR_OUT_OF_GAS
         || error == GETH_ERR_GAS_UINT_OVERFLOW
     {
@@ -604,7 +1688,9 @@ fn get_step_reported_error(op: &OpcodeId, error: &str) -> ExecError {
             OpcodeId::CODECOPY => OogError::CodeCopy,
             OpcodeId::EXTCODECOPY => OogError::ExtCodeCopy,
             OpcodeId::RETURNDATACOPY => OogError::ReturnDataCopy,
+            OpcodeId::SLOAD | OpcodeId::SSTORE => OogError::SloadSstore,
             OpcodeId::LOG0
+            | OpcodeId::LOG1
             | OpcodeId::LOG2
             | OpcodeId::LOG3
             | OpcodeId::LOG4 => OogError::Log,
@@ -613,6 +1699,7 @@ fn get_step_reported_error(op: &OpcodeId, error: &str) -> ExecError {
             OpcodeId::DELEGATECALL => OogError::DelegateCall,
             OpcodeId::CREATE2 => OogError::Create2,
             OpcodeId::STATICCALL => OogError::StaticCall,
+            OpcodeId::SELFDESTRUCT => OogError::SelfDestruct,
             OpcodeId::MLOAD
             | OpcodeId::MSTORE
             | OpcodeId::MSTORE8
@@ -629,8 +1716,8 @@ fn get_step_reported_error(op: &OpcodeId, error: &str) -> ExecError {
     } else if error.starts_with(GETH_ERR_INVALID_OPCODE) {
         ExecError::InvalidOpcode
     } else {
-        panic!("Unknown GethExecStep.error: {}", error);
-    }
+        return Err(Error::UnknownGethError(error.to_string()));
+    })
 }
 
 /// Retreive the init_code from memory for {CREATE, CREATE2}
@@ -661,13 +1748,15 @@ impl<'a> CircuitInputStateRef<'a> {
         ))
     }
 
-    fn get_step_err(
+    /// Return the [`ExecError`] of the `step`, which is deduced from the
+    /// reported error of geth or from the `next_step` in the trace.
+    pub(crate) fn get_step_err(
         &self,
         step: &GethExecStep,
         next_step: Option<&GethExecStep>,
     ) -> Result<Option<ExecError>, Error> {
         if let Some(error) = &step.error {
-            return Ok(Some(get_step_reported_error(&step.op, error)));
+            return Ok(Some(get_step_reported_error(&step.op, error)?));
         }
 
         // When last step is RETURN or STOP there's no error.
@@ -707,7 +1796,7 @@ impl<'a> CircuitInputStateRef<'a> {
                 if !call.is_root && call.is_create() {
                     let offset = step.stack.nth_last(0)?;
                     let length = step.stack.nth_last(1)?;
-                    if length > Word::from(0x6000u64) {
+                    if length > Word::from(MAX_CODE_SIZE) {
                         return Ok(Some(ExecError::MaxCodeSizeExceeded));
                     } else if length > Word::zero()
                         && !step.memory.0.is_empty()
@@ -715,7 +1804,9 @@ impl<'a> CircuitInputStateRef<'a> {
                             == Some(&0xef)
                     {
                         return Ok(Some(ExecError::InvalidCode));
-                    } else if Word::from(200u64) * length
+                    } else if Word::from(
+                        GasCost::CODE_DEPOSIT_BYTE_COST.as_u64(),
+                    ) * length
                         > Word::from(step.gas.0)
                     {
                         return Ok(Some(ExecError::CodeStoreOutOfGas));
@@ -836,7 +1927,7 @@ impl Access {
 
 /// Given a trace and assuming that the first step is a *CALL* kind opcode,
 /// return the result if found.
-fn get_call_result(trace: &[GethExecStep]) -> Option<Word> {
+pub(crate) fn get_call_result(trace: &[GethExecStep]) -> Option<Word> {
     let depth = trace[0].depth;
     trace[1..]
         .iter()
@@ -1021,6 +2112,7 @@ mod tracer_tests {
         eth_types::{ToWord, Word},
         evm::{stack::Stack, Gas, OpcodeId},
         mock,
+        operation::Target,
         state_db::Account,
         word,
     };
@@ -1046,7 +2138,7 @@ mod tracer_tests {
                     block.eth_block.clone(),
                     block.block_ctants.clone(),
                 ),
-                tx: Transaction::new(&block.eth_tx),
+                tx: Transaction::new(0, &block.eth_tx),
                 tx_ctx: TransactionContext::new(&block.eth_tx),
                 step: ExecStep::new(geth_step, 0, GlobalCounter(0), 0),
             }
@@ -1059,6 +2151,24 @@ mod tracer_tests {
                 &mut self.step,
             )
         }
+
+        // Push a call of `kind` executing at `address` as a subcall of the
+        // current call.
+        fn push_call(&mut self, kind: CallKind, address: Address) {
+            let caller = self.state_ref().call().clone();
+            let call = Call {
+                caller_id: caller.call_id,
+                kind,
+                is_static: kind == CallKind::StaticCall || caller.is_static,
+                is_root: false,
+                depth: caller.depth + 1,
+                caller_address: caller.address,
+                address,
+                code_address: address,
+                ..caller
+            };
+            self.state_ref().push_call(call);
+        }
     }
 
     lazy_static! {
@@ -1289,7 +2399,7 @@ mod tracer_tests {
 
         let mut builder = CircuitInputBuilderTx::new(&block, step);
         // Set up call context at CREATE2
-        builder.state_ref().push_call(CallKind::Create, *ADDR_B);
+        builder.push_call(CallKind::Create, *ADDR_B);
         // Set up account and contract that exist during the second CREATE2
         builder.builder.sdb.set_account(
             &ADDR_B,
@@ -1396,7 +2506,7 @@ mod tracer_tests {
 
         let mut builder = CircuitInputBuilderTx::new(&block, step);
         // Set up call context at CREATE
-        builder.state_ref().push_call(CallKind::Create, *ADDR_B);
+        builder.push_call(CallKind::Create, *ADDR_B);
         assert_eq!(
             builder.state_ref().get_step_err(step, next_step).unwrap(),
             Some(ExecError::CodeStoreOutOfGas)
@@ -1485,7 +2595,7 @@ mod tracer_tests {
 
         let mut builder = CircuitInputBuilderTx::new(&block, step);
         // Set up call context at RETURN
-        builder.state_ref().push_call(CallKind::Create, *ADDR_B);
+        builder.push_call(CallKind::Create, *ADDR_B);
         assert_eq!(
             builder.state_ref().get_step_err(step, next_step).unwrap(),
             Some(ExecError::InvalidCode)
@@ -1572,7 +2682,7 @@ mod tracer_tests {
 
         let mut builder = CircuitInputBuilderTx::new(&block, step);
         // Set up call context at RETURN
-        builder.state_ref().push_call(CallKind::Create, *ADDR_B);
+        builder.push_call(CallKind::Create, *ADDR_B);
         assert_eq!(
             builder.state_ref().get_step_err(step, next_step).unwrap(),
             Some(ExecError::MaxCodeSizeExceeded)
@@ -1644,7 +2754,7 @@ mod tracer_tests {
 
         let mut builder = CircuitInputBuilderTx::new(&block, step);
         // Set up call context at STOP
-        builder.state_ref().push_call(CallKind::Create, *ADDR_B);
+        builder.push_call(CallKind::Create, *ADDR_B);
         assert_eq!(
             builder.state_ref().get_step_err(step, next_step).unwrap(),
             None
@@ -1917,6 +3027,31 @@ mod tracer_tests {
         );
     }
 
+    #[test]
+    fn memory_not_expanded_on_error() {
+        // MSTORE runs out of gas to expand the memory
+        let code = bytecode! {
+            PUSH1(0x42) // value
+            PUSH32(0x10_0000) // offset
+            MSTORE
+        };
+        let block = mock::BlockData::new_single_tx_trace_code(&code).unwrap();
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        assert!(block.geth_trace.struct_logs[2].error.is_some());
+        let step = &builder.block.txs()[0].steps()[2];
+        assert_eq!(step.op, OpcodeId::MSTORE);
+        assert_eq!(step.memory_size, 0);
+
+        // The cost of an expansion up to the end of the 64-bit address space
+        // doesn't fit in 64 bits
+        assert_eq!(memory_expansion_gas_cost(0, u64::MAX / 32), None);
+    }
+
     #[test]
     fn tracer_err_invalid_opcode() {
         // The second opcode is invalid (0x0f)
@@ -1984,15 +3119,18 @@ mod tracer_tests {
 
     #[test]
     fn tracer_err_out_of_gas() {
-        // Do 3 PUSH1 with gas = 4, which causes out of gas
+        // Do 3 PUSH1 with gas = 4 left after the intrinsic gas, which causes
+        // out of gas
         let code = bytecode! {
             PUSH1(0x0)
             PUSH1(0x1)
             PUSH1(0x2)
         };
-        let block =
-            mock::BlockData::new_single_tx_trace_code_gas(&code, Gas(4))
-                .unwrap();
+        let block = mock::BlockData::new_single_tx_trace_code_gas(
+            &code,
+            Gas(GasCost::TX.as_u64() + 4),
+        )
+        .unwrap();
         let struct_logs = block.geth_trace.struct_logs;
 
         assert_eq!(struct_logs[1].error, Some(GETH_ERR_OUT_OF_GAS.to_string()));
@@ -2124,7 +3262,7 @@ mod tracer_tests {
             .unwrap();
         let mut builder = CircuitInputBuilderTx::new(&block, step_create2);
         // Set up call context at CREATE2
-        builder.state_ref().push_call(CallKind::Create, *ADDR_B);
+        builder.push_call(CallKind::Create, *ADDR_B);
         let addr = builder.state_ref().create2_address(step_create2).unwrap();
 
         assert_eq!(addr.to_word(), addr_expect);
@@ -2212,7 +3350,7 @@ mod tracer_tests {
             .unwrap();
         let mut builder = CircuitInputBuilderTx::new(&block, step_create);
         // Set up call context at CREATE
-        builder.state_ref().push_call(CallKind::Create, *ADDR_B);
+        builder.push_call(CallKind::Create, *ADDR_B);
         builder.builder.sdb.set_account(
             &ADDR_B,
             Account {
@@ -2336,4 +3474,215 @@ mod tracer_tests {
             }
         )
     }
+
+    #[test]
+    fn tx_begin_end_ops() {
+        // Setting a slot back to its original value refunds more than a fifth
+        // of the gas used, so the refund is capped.
+        let code = bytecode! {
+            PUSH1(0x01) // value
+            PUSH1(0x00) // key
+            SSTORE
+            PUSH1(0x00) // value
+            PUSH1(0x00) // key
+            SSTORE
+            STOP
+        };
+        let mut block =
+            mock::BlockData::new_single_tx_trace_code(&code).unwrap();
+        block.eth_tx.gas_price = Some(Word::from(100u64));
+        let sender = block.eth_tx.from;
+        let receiver = block.eth_tx.to.unwrap();
+        // The sender is also the coinbase of the mock block.
+        assert_eq!(*block.block_ctants.coinbase(), sender);
+
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.sdb.set_account(
+            &sender,
+            Account {
+                nonce: Word::zero(),
+                balance: Word::from(200_000_000u64),
+                storage: HashMap::new(),
+                codeHash: H256::zero(),
+            },
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let tx = &builder.block.txs()[0];
+        assert_eq!(tx.intrinsic_gas, GasCost::TX.as_u64());
+        // 21000 intrinsic + 4 PUSH1 + cold SSTORE_SET + warm SSTORE
+        let gas_used = 21000 + 4 * 3 + 22100 + 100;
+        // min(19900, gas_used / 5)
+        let refund = 8642;
+        let gas_left = 1_000_000 - gas_used;
+
+        let container = &builder.block.container;
+        assert_eq!(tx.begin_tx_step().gc, GlobalCounter(0));
+        assert_eq!(
+            tx.begin_tx_step()
+                .bus_mapping_instance
+                .iter()
+                .map(|op_ref| op_ref.target())
+                .collect::<Vec<_>>(),
+            vec![
+                Target::AccountNonce,
+                Target::AccountBalance,
+                Target::AccountBalance,
+                Target::AccountBalance,
+                Target::AccountCodeHash,
+            ]
+        );
+        assert_eq!(
+            container.account_nonce[0].op(),
+            &AccountNonceOp::new(RW::WRITE, sender, Word::one(), Word::zero())
+        );
+        let balances = [
+            (sender, 100_000_000u64, 200_000_000u64),
+            (sender, 100_000_000, 100_000_000),
+            (receiver, 0, 0),
+            (sender, 100_000_000 + (gas_left + refund) * 100, 100_000_000),
+            (
+                sender,
+                100_000_000
+                    + (gas_left + refund) * 100
+                    + (gas_used - refund) * 3,
+                100_000_000 + (gas_left + refund) * 100,
+            ),
+        ];
+        assert_eq!(
+            container
+                .account_balance
+                .iter()
+                .map(|op| op.op().clone())
+                .collect::<Vec<_>>(),
+            balances
+                .iter()
+                .map(|(address, balance, balance_prev)| {
+                    AccountBalanceOp::new(
+                        RW::WRITE,
+                        *address,
+                        Word::from(*balance),
+                        Word::from(*balance_prev),
+                    )
+                })
+                .collect::<Vec<_>>()
+        );
+
+        let steps_ops = tx
+            .steps()
+            .iter()
+            .map(|step| step.bus_mapping_instance.len())
+            .sum::<usize>();
+        assert_eq!(tx.end_tx_step().gc, GlobalCounter(5 + steps_ops));
+        assert_eq!(
+            tx.end_tx_step()
+                .bus_mapping_instance
+                .iter()
+                .map(|op_ref| op_ref.target())
+                .collect::<Vec<_>>(),
+            vec![
+                Target::CallContext,
+                Target::TxRefund,
+                Target::AccountBalance,
+                Target::AccountBalance,
+            ]
+        );
+        assert_eq!(
+            container.tx_refund.last().unwrap().op(),
+            &TxRefundOp::new(RW::READ, 1, 19900, 19900)
+        );
+    }
+
+    #[test]
+    fn tracer_unsupported_opcode() {
+        // The invalid opcodes are not supported yet
+        let mut code = bytecode::Bytecode::default();
+        code.write_op(OpcodeId::PC);
+        code.write(0x0f);
+        let block = mock::BlockData::new_single_tx_trace_code(&code).unwrap();
+
+        assert_eq!(
+            CircuitInputBuilder::unsupported_trace_items(&block.geth_trace),
+            UnsupportedTraceItems {
+                opcodes: vec![OpcodeId::INVALID(0x0f)],
+                geth_errors: vec![],
+            }
+        );
+
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        assert!(matches!(
+            builder.handle_tx(&block.eth_tx, &block.geth_trace),
+            Err(Error::UnsupportedOpcode(
+                OpcodeId::INVALID(0x0f),
+                ProgramCounter(1)
+            ))
+        ));
+        // The builder is left untouched
+        assert!(builder.block.txs().is_empty());
+        assert_eq!(builder.block_ctx.gc, GlobalCounter(0));
+    }
+
+    #[test]
+    fn tx_insufficient_balance() {
+        let code = bytecode! {
+            PUSH1(0x01)
+            STOP
+        };
+        let mut block =
+            mock::BlockData::new_single_tx_trace_code(&code).unwrap();
+        // The sender can't pay for the value on top of the gas
+        block.eth_tx.value = Word::MAX;
+
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        let from = block.eth_tx.from;
+        assert!(matches!(
+            builder.handle_tx(&block.eth_tx, &block.geth_trace),
+            Err(Error::InsufficientBalance(address)) if address == from
+        ));
+        // No operation is generated
+        assert!(builder.block.txs().is_empty());
+        assert_eq!(builder.block_ctx.gc, GlobalCounter(0));
+    }
+
+    #[test]
+    fn tracer_unknown_geth_error() {
+        let code = bytecode! {
+            PUSH1(0x01)
+            STOP
+        };
+        let mut block =
+            mock::BlockData::new_single_tx_trace_code(&code).unwrap();
+        assert!(CircuitInputBuilder::unsupported_trace_items(
+            &block.geth_trace
+        )
+        .is_empty());
+
+        let error = "unknown geth error".to_string();
+        block.geth_trace.struct_logs[0].error = Some(error.clone());
+        assert_eq!(
+            CircuitInputBuilder::unsupported_trace_items(&block.geth_trace),
+            UnsupportedTraceItems {
+                opcodes: vec![],
+                geth_errors: vec![error.clone()],
+            }
+        );
+
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        assert!(matches!(
+            builder.handle_tx(&block.eth_tx, &block.geth_trace),
+            Err(Error::UnknownGethError(e)) if e == error
+        ));
+    }
 }
@@ -1,6 +1,7 @@
 //! Error module for the bus-mapping crate
 
 use crate::eth_types::{Address, GethExecStep, Word};
+use crate::evm::{OpcodeId, ProgramCounter};
 use core::fmt::{Display, Formatter, Result as FmtResult};
 use ethers_providers::ProviderError;
 use std::error::Error as StdError;
@@ -41,6 +42,15 @@ pub enum Error {
     UnexpectedExecStepError(&'static str, Box<GethExecStep>),
     /// Invalid [`GethExecStep`] due to an invalid/unexpected value in it.
     InvalidGethExecStep(&'static str, Box<GethExecStep>),
+    /// The operations associated to the [`OpcodeId`] found at the
+    /// [`ProgramCounter`] can't be generated yet.
+    UnsupportedOpcode(OpcodeId, ProgramCounter),
+    /// Error reported by geth in a [`GethExecStep`] that doesn't correspond to
+    /// any known [`ExecError`](crate::circuit_input_builder::ExecError).
+    UnknownGethError(String),
+    /// The sender at the [`Address`] can't pay for the gas and the value of a
+    /// transaction.
+    InsufficientBalance(Address),
 }
 
 impl From<ProviderError> for Error {
@@ -5,6 +5,7 @@ pub(crate) mod opcodes;
 pub mod stack;
 pub mod storage;
 
+use core::convert::TryFrom;
 use serde::{Deserialize, Serialize};
 pub use {
     memory::{Memory, MemoryAddress},
@@ -121,14 +122,62 @@ impl GasCost {
     pub const SLOW: Self = Self(10);
     /// Constant cost for ext step
     pub const EXT: Self = Self(20);
+    /// Constant cost for every byte of the exponent in EXP
+    pub const EXP_BYTE_TIMES: Self = Self(50);
     /// Constant cost for every additional word when expanding memory
     pub const MEMORY: Self = Self(3);
+    /// Constant cost for every word copied into memory
+    pub const COPY: Self = Self(3);
     /// Constant cost for a cold SLOAD
     pub const COLD_SLOAD_COST: Self = Self(2100);
     /// Constant cost for a cold account access
     pub const COLD_ACCOUNT_ACCESS_COST: Self = Self(2600);
     /// Constant cost for a warm storage read
     pub const WARM_STORAGE_READ_COST: Self = Self(100);
+    /// Constant cost for a SSTORE which sets a clean slot from zero to
+    /// non-zero
+    pub const SSTORE_SET: Self = Self(20000);
+    /// Constant cost for a SSTORE which updates a clean non-zero slot
+    pub const SSTORE_RESET: Self = Self(2900);
+    /// Minimum gas left required to execute a SSTORE (EIP-2200)
+    pub const SSTORE_SENTRY: Self = Self(2300);
+    /// Refund given when a SSTORE clears a non-zero slot (EIP-3529)
+    pub const SSTORE_CLEARS_SCHEDULE: Self = Self(4800);
+    /// Constant cost for a *CALL* which transfers value
+    pub const CALL_WITH_VALUE: Self = Self(9000);
+    /// Constant cost for a CALL or SELFDESTRUCT which transfers value to an
+    /// empty account
+    pub const NEW_ACCOUNT: Self = Self(25000);
+    /// Gas given to the callee for free in a *CALL* which transfers value
+    pub const CALL_STIPEND: Self = Self(2300);
+    /// Constant cost for CREATE and CREATE2
+    pub const CREATE: Self = Self(32000);
+    /// Constant cost for SELFDESTRUCT
+    pub const SELFDESTRUCT: Self = Self(5000);
+    /// Constant cost for SHA3
+    pub const SHA3: Self = Self(30);
+    /// Cost per word of hashing, paid by SHA3 and CREATE2
+    pub const SHA3_WORD: Self = Self(6);
+    /// Cost per byte of the code deposited by CREATE and CREATE2
+    pub const CODE_DEPOSIT_BYTE_COST: Self = Self(200);
+    /// Constant cost for LOG0 to LOG4
+    pub const LOG: Self = Self(375);
+    /// Cost per topic of a log
+    pub const LOG_TOPIC: Self = Self(375);
+    /// Cost per byte of the data of a log
+    pub const LOG_DATA_BYTE: Self = Self(8);
+    /// Constant cost paid by every transaction
+    pub const TX: Self = Self(21000);
+    /// Constant cost added to a transaction which creates a contract
+    pub const CREATION_TX: Self = Self(32000);
+    /// Cost per zero byte of the calldata of a transaction
+    pub const TX_DATA_ZERO: Self = Self(4);
+    /// Cost per non-zero byte of the calldata of a transaction (EIP-2028)
+    pub const TX_DATA_NON_ZERO: Self = Self(16);
+    /// Cost per address in the access list of a transaction (EIP-2930)
+    pub const TX_ACCESS_LIST_ADDRESS: Self = Self(2400);
+    /// Cost per storage key in the access list of a transaction (EIP-2930)
+    pub const TX_ACCESS_LIST_STORAGE_KEY: Self = Self(1900);
 }
 
 impl GasCost {
@@ -145,6 +194,94 @@ impl GasCost {
     }
 }
 
+/// Maximum size in bytes of the code deployed by a contract creation
+/// (EIP-170).
+pub const MAX_CODE_SIZE: u64 = 0x6000;
+
+/// Maximum fraction of the gas used by a transaction which can be refunded,
+/// as the divisor of the gas used (EIP-3529).
+pub const MAX_REFUND_QUOTIENT_OF_GAS_USED: u64 = 5;
+
+/// Returns the intrinsic gas of a transaction, which is charged before its
+/// execution: the constant cost of a transaction (or of a contract creation),
+/// plus the cost of its `call_data` and of its access list, given by the
+/// number of addresses and storage keys in it.
+pub fn tx_intrinsic_gas(
+    is_create: bool,
+    call_data: &[u8],
+    access_list_addresses: u64,
+    access_list_storage_keys: u64,
+) -> u64 {
+    let mut gas = GasCost::TX.as_u64();
+    if is_create {
+        gas += GasCost::CREATION_TX.as_u64();
+    }
+    gas += call_data
+        .iter()
+        .map(|byte| {
+            if *byte == 0 {
+                GasCost::TX_DATA_ZERO.as_u64()
+            } else {
+                GasCost::TX_DATA_NON_ZERO.as_u64()
+            }
+        })
+        .sum::<u64>();
+    gas + access_list_addresses * GasCost::TX_ACCESS_LIST_ADDRESS.as_u64()
+        + access_list_storage_keys
+            * GasCost::TX_ACCESS_LIST_STORAGE_KEY.as_u64()
+}
+
+/// Returns the memory size in words required to access `length` bytes at
+/// `offset`, which is zero when `length` is zero.
+pub fn memory_word_size(offset: u64, length: u64) -> u64 {
+    if length == 0 {
+        0
+    } else {
+        (offset + length + 31) / 32
+    }
+}
+
+/// Returns the gas cost of expanding the memory from `curr_memory_word_size`
+/// to `next_memory_word_size` words, which is zero when the memory doesn't
+/// grow, or `None` when the cost doesn't fit in 64 bits, which runs out of
+/// gas.
+pub fn memory_expansion_gas_cost(
+    curr_memory_word_size: u64,
+    next_memory_word_size: u64,
+) -> Option<u64> {
+    let next_memory_word_size =
+        next_memory_word_size.max(curr_memory_word_size);
+    u64::try_from(
+        memory_gas_cost(next_memory_word_size)
+            - memory_gas_cost(curr_memory_word_size),
+    )
+    .ok()
+}
+
+/// Returns the memory size in words after an access to the memory up to
+/// `address`, which is zero when no memory is accessed, followed by the gas
+/// cost of the expansion from `curr_memory_word_size` words.  This is the
+/// computation done by the `MemoryExpansionGadget` of the EVM circuit, where
+/// the cost of an access far beyond the memory can exceed 64 bits.
+pub fn memory_expansion(
+    curr_memory_word_size: u64,
+    address: u64,
+) -> (u64, u128) {
+    let next_memory_word_size = curr_memory_word_size.max((address + 31) / 32);
+    (
+        next_memory_word_size,
+        memory_gas_cost(next_memory_word_size)
+            - memory_gas_cost(curr_memory_word_size),
+    )
+}
+
+// Gas cost of a memory of `word_size` words, which is linear up to 724 words
+// and quadratic after.
+fn memory_gas_cost(word_size: u64) -> u128 {
+    let word_size = word_size as u128;
+    GasCost::MEMORY.as_u64() as u128 * word_size + word_size * word_size / 512
+}
+
 impl From<u8> for GasCost {
     fn from(cost: u8) -> Self {
         GasCost(cost as u64)
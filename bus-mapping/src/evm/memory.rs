@@ -66,6 +66,12 @@ impl MemoryAddress {
     }
 }
 
+impl From<MemoryAddress> for usize {
+    fn from(addr: MemoryAddress) -> usize {
+        addr.0
+    }
+}
+
 impl TryFrom<Word> for MemoryAddress {
     type Error = Error;
 
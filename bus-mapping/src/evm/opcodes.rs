@@ -1,13 +1,35 @@
 //! Definition of each opcode of the EVM.
+mod balance;
+mod blockctx;
+mod call;
+mod callctx;
+mod calldatacopy;
+mod calldataload;
+mod calldatasize;
+mod codecopy;
+mod codesize;
+mod create;
 mod dup;
+mod extcodecopy;
+mod extcodehash;
+mod extcodesize;
 pub mod ids;
 mod jumpdest;
+mod logs;
 mod mload;
 mod mstore;
 mod pc;
 mod push;
+mod r#return;
+mod returndatacopy;
+mod returndatasize;
+mod selfbalance;
+mod selfdestruct;
+mod sha3;
 mod sload;
+mod sstore;
 mod stackonlyop;
+mod stackpoponlyop;
 mod stop;
 mod swap;
 use crate::circuit_input_builder::CircuitInputStateRef;
@@ -17,13 +39,35 @@ use core::fmt::Debug;
 use ids::OpcodeId;
 
 use self::push::Push;
+use balance::Balance;
+use blockctx::BlockCtx;
+use call::Call;
+use callctx::CallCtx;
+use calldatacopy::Calldatacopy;
+use calldataload::Calldataload;
+use calldatasize::Calldatasize;
+use codecopy::Codecopy;
+use codesize::Codesize;
+use create::Create;
 use dup::Dup;
+use extcodecopy::Extcodecopy;
+use extcodehash::Extcodehash;
+use extcodesize::Extcodesize;
 use jumpdest::Jumpdest;
+use logs::Log;
 use mload::Mload;
 use mstore::Mstore;
 use pc::Pc;
+use r#return::Return;
+use returndatacopy::Returndatacopy;
+use returndatasize::Returndatasize;
+use selfbalance::Selfbalance;
+use selfdestruct::Selfdestruct;
+use sha3::Sha3;
 use sload::Sload;
+use sstore::Sstore;
 use stackonlyop::StackOnlyOpcode;
+use stackpoponlyop::StackPopOnlyOpcode;
 use stop::Stop;
 use swap::Swap;
 
@@ -33,9 +77,9 @@ use swap::Swap;
 /// [`OpcodeId`] it contains.
 pub trait Opcode: Debug {
     /// Generate the associated [`MemoryOp`](crate::operation::MemoryOp)s,
-    /// [`StackOp`](crate::operation::StackOp)s, and
-    /// [`StorageOp`](crate::operation::StorageOp)s associated to the Opcode
-    /// is implemented for.
+    /// [`StackOp`](crate::operation::StackOp)s,
+    /// [`StorageOp`](crate::operation::StorageOp)s, and transaction-level
+    /// operations associated to the Opcode is implemented for.
     fn gen_associated_ops(
         state: &mut CircuitInputStateRef,
         next_steps: &[GethExecStep],
@@ -48,8 +92,8 @@ type FnGenAssociatedOps = fn(
 ) -> Result<(), Error>;
 
 impl OpcodeId {
-    fn fn_gen_associated_ops(&self) -> FnGenAssociatedOps {
-        match *self {
+    fn fn_gen_associated_ops(&self) -> Option<FnGenAssociatedOps> {
+        Some(match *self {
             OpcodeId::STOP => Stop::gen_associated_ops,
             OpcodeId::ADD => StackOnlyOpcode::<2>::gen_associated_ops,
             OpcodeId::MUL => StackOnlyOpcode::<2>::gen_associated_ops,
@@ -76,43 +120,43 @@ impl OpcodeId {
             OpcodeId::SHL => StackOnlyOpcode::<2>::gen_associated_ops,
             OpcodeId::SHR => StackOnlyOpcode::<2>::gen_associated_ops,
             OpcodeId::SAR => StackOnlyOpcode::<2>::gen_associated_ops,
-            // OpcodeId::SHA3 => {},
-            // OpcodeId::ADDRESS => {},
-            // OpcodeId::BALANCE => {},
-            // OpcodeId::ORIGIN => {},
-            // OpcodeId::CALLER => {},
-            // OpcodeId::CALLVALUE => {},
-            // OpcodeId::CALLDATALOAD => {},
-            // OpcodeId::CALLDATASIZE => {},
-            // OpcodeId::CALLDATACOPY => {},
-            // OpcodeId::CODESIZE => {},
-            // OpcodeId::CODECOPY => {},
-            // OpcodeId::GASPRICE => {},
-            // OpcodeId::EXTCODESIZE => {},
-            // OpcodeId::EXTCODECOPY => {},
-            // OpcodeId::RETURNDATASIZE => {},
-            // OpcodeId::RETURNDATACOPY => {},
-            // OpcodeId::EXTCODEHASH => {},
-            // OpcodeId::BLOCKHASH => {},
-            // OpcodeId::COINBASE => {},
-            // OpcodeId::TIMESTAMP => {},
-            // OpcodeId::NUMBER => {},
-            // OpcodeId::DIFFICULTY => {},
-            // OpcodeId::GASLIMIT => {},
-            // OpcodeId::CHAINID => {},
-            // OpcodeId::SELFBALANCE => {},
-            // OpcodeId::BASEFEE => {},
-            // OpcodeId::POP => {},
+            OpcodeId::SHA3 => Sha3::gen_associated_ops,
+            OpcodeId::ADDRESS => CallCtx::gen_associated_ops,
+            OpcodeId::BALANCE => Balance::gen_associated_ops,
+            OpcodeId::ORIGIN => CallCtx::gen_associated_ops,
+            OpcodeId::CALLER => CallCtx::gen_associated_ops,
+            OpcodeId::CALLVALUE => CallCtx::gen_associated_ops,
+            OpcodeId::CALLDATALOAD => Calldataload::gen_associated_ops,
+            OpcodeId::CALLDATASIZE => Calldatasize::gen_associated_ops,
+            OpcodeId::CALLDATACOPY => Calldatacopy::gen_associated_ops,
+            OpcodeId::CODESIZE => Codesize::gen_associated_ops,
+            OpcodeId::CODECOPY => Codecopy::gen_associated_ops,
+            OpcodeId::GASPRICE => CallCtx::gen_associated_ops,
+            OpcodeId::EXTCODESIZE => Extcodesize::gen_associated_ops,
+            OpcodeId::EXTCODECOPY => Extcodecopy::gen_associated_ops,
+            OpcodeId::RETURNDATASIZE => Returndatasize::gen_associated_ops,
+            OpcodeId::RETURNDATACOPY => Returndatacopy::gen_associated_ops,
+            OpcodeId::EXTCODEHASH => Extcodehash::gen_associated_ops,
+            OpcodeId::BLOCKHASH => StackOnlyOpcode::<1>::gen_associated_ops,
+            OpcodeId::COINBASE => BlockCtx::gen_associated_ops,
+            OpcodeId::TIMESTAMP => BlockCtx::gen_associated_ops,
+            OpcodeId::NUMBER => BlockCtx::gen_associated_ops,
+            OpcodeId::DIFFICULTY => BlockCtx::gen_associated_ops,
+            OpcodeId::GASLIMIT => BlockCtx::gen_associated_ops,
+            OpcodeId::CHAINID => BlockCtx::gen_associated_ops,
+            OpcodeId::SELFBALANCE => Selfbalance::gen_associated_ops,
+            OpcodeId::BASEFEE => BlockCtx::gen_associated_ops,
+            OpcodeId::POP => StackPopOnlyOpcode::<1>::gen_associated_ops,
             OpcodeId::MLOAD => Mload::gen_associated_ops,
-            OpcodeId::MSTORE => Mstore::gen_associated_ops,
-            // OpcodeId::MSTORE8 => {}
+            OpcodeId::MSTORE => Mstore::<false>::gen_associated_ops,
+            OpcodeId::MSTORE8 => Mstore::<true>::gen_associated_ops,
             OpcodeId::SLOAD => Sload::gen_associated_ops,
-            // OpcodeId::SSTORE => {},
-            // OpcodeId::JUMP => {},
-            // OpcodeId::JUMPI => {},
+            OpcodeId::SSTORE => Sstore::gen_associated_ops,
+            OpcodeId::JUMP => StackPopOnlyOpcode::<1>::gen_associated_ops,
+            OpcodeId::JUMPI => StackPopOnlyOpcode::<2>::gen_associated_ops,
             OpcodeId::PC => Pc::gen_associated_ops,
-            // OpcodeId::MSIZE => {},
-            // OpcodeId::GAS => {},
+            OpcodeId::MSIZE => Pc::gen_associated_ops,
+            OpcodeId::GAS => Pc::gen_associated_ops,
             OpcodeId::JUMPDEST => Jumpdest::gen_associated_ops,
             OpcodeId::PUSH1 => Push::<1>::gen_associated_ops,
             OpcodeId::PUSH2 => Push::<2>::gen_associated_ops,
@@ -178,32 +222,41 @@ impl OpcodeId {
             OpcodeId::SWAP14 => Swap::<14>::gen_associated_ops,
             OpcodeId::SWAP15 => Swap::<15>::gen_associated_ops,
             OpcodeId::SWAP16 => Swap::<16>::gen_associated_ops,
-            // OpcodeId::LOG0 => {},
-            // OpcodeId::LOG1 => {},
-            // OpcodeId::LOG2 => {},
-            // OpcodeId::LOG3 => {},
-            // OpcodeId::LOG4 => {},
-            // OpcodeId::CREATE => {},
-            // OpcodeId::CALL => {},
-            // OpcodeId::CALLCODE => {},
-            // OpcodeId::RETURN => {},
-            // OpcodeId::DELEGATECALL => {},
-            // OpcodeId::CREATE2 => {},
-            // OpcodeId::STATICCALL => {},
-            // OpcodeId::REVERT => {},
-            // OpcodeId::SELFDESTRUCT => {},
-            _ => unimplemented!(),
-        }
+            OpcodeId::LOG0 => Log::<0>::gen_associated_ops,
+            OpcodeId::LOG1 => Log::<1>::gen_associated_ops,
+            OpcodeId::LOG2 => Log::<2>::gen_associated_ops,
+            OpcodeId::LOG3 => Log::<3>::gen_associated_ops,
+            OpcodeId::LOG4 => Log::<4>::gen_associated_ops,
+            OpcodeId::CREATE => Create::<false>::gen_associated_ops,
+            OpcodeId::CALL => Call::<7>::gen_associated_ops,
+            OpcodeId::CALLCODE => Call::<7>::gen_associated_ops,
+            OpcodeId::RETURN => Return::<false>::gen_associated_ops,
+            OpcodeId::DELEGATECALL => Call::<6>::gen_associated_ops,
+            OpcodeId::CREATE2 => Create::<true>::gen_associated_ops,
+            OpcodeId::STATICCALL => Call::<6>::gen_associated_ops,
+            OpcodeId::REVERT => Return::<true>::gen_associated_ops,
+            OpcodeId::SELFDESTRUCT => Selfdestruct::gen_associated_ops,
+            _ => return None,
+        })
+    }
+
+    /// Returns `true` if the operations associated to this [`OpcodeId`] can
+    /// be generated, and `false` if the opcode is not supported yet.
+    pub fn is_supported(&self) -> bool {
+        self.fn_gen_associated_ops().is_some()
     }
 
     /// Generate the associated operations according to the particular
-    /// [`OpcodeId`].
+    /// [`OpcodeId`].  Returns [`Error::UnsupportedOpcode`] if the opcode is
+    /// not supported yet.
     pub fn gen_associated_ops(
         &self,
         state: &mut CircuitInputStateRef,
         next_steps: &[GethExecStep],
     ) -> Result<(), Error> {
-        let fn_gen_associated_ops = self.fn_gen_associated_ops();
+        let fn_gen_associated_ops = self
+            .fn_gen_associated_ops()
+            .ok_or_else(|| Error::UnsupportedOpcode(*self, next_steps[0].pc))?;
         fn_gen_associated_ops(state, next_steps)
     }
 }
@@ -0,0 +1,163 @@
+use super::Opcode;
+use crate::circuit_input_builder::CircuitInputStateRef;
+use crate::eth_types::GethExecStep;
+use crate::{
+    operation::{StackOp, RW},
+    Error,
+};
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the block context opcodes, from
+/// [`OpcodeId::COINBASE`](crate::evm::OpcodeId::COINBASE) to
+/// [`OpcodeId::BASEFEE`](crate::evm::OpcodeId::BASEFEE), which push a field of
+/// the [`BlockConstants`](crate::BlockConstants) of the block.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct BlockCtx;
+
+impl Opcode for BlockCtx {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        steps: &[GethExecStep],
+    ) -> Result<(), Error> {
+        let step = &steps[0];
+        // Get value result from next step and do stack write
+        let value = steps[1].stack.last()?;
+        state.push_op(StackOp::new(
+            RW::WRITE,
+            state.call().call_id,
+            step.stack.last_filled().map(|a| a - 1),
+            value,
+        ));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod blockctx_tests {
+    use super::*;
+    use crate::{
+        bytecode,
+        circuit_input_builder::CircuitInputBuilder,
+        eth_types::{ToWord, Word},
+        evm::{OpcodeId, StackAddress},
+        mock,
+        operation::Target,
+    };
+    use pretty_assertions::assert_eq;
+
+    fn stack_writes(builder: &CircuitInputBuilder) -> Vec<(OpcodeId, StackOp)> {
+        let container = &builder.block.container;
+        builder.block.txs()[0]
+            .steps()
+            .iter()
+            .flat_map(|step| {
+                step.bus_mapping_instance
+                    .iter()
+                    .filter(|op_ref| op_ref.target() == Target::Stack)
+                    .map(|op_ref| {
+                        (step.op, container.stack[op_ref.as_usize() - 1].op())
+                    })
+                    .filter(|(_, op)| op.rw().is_write())
+                    .map(|(opcode, op)| (opcode, op.clone()))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn blockctx_opcode_impl() {
+        let code = bytecode! {
+            COINBASE
+            TIMESTAMP
+            NUMBER
+            DIFFICULTY
+            GASLIMIT
+            CHAINID
+            BASEFEE
+            STOP
+        };
+
+        // Get the execution steps from the external tracer
+        let block = mock::BlockData::new_single_tx_trace_code(&code).unwrap();
+
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        // Each field of the block is pushed on top of the previous one
+        let call_id = builder.block.txs()[0].calls()[0].call_id;
+        let ctants = &block.block_ctants;
+        assert_eq!(
+            stack_writes(&builder),
+            [
+                (OpcodeId::COINBASE, ctants.coinbase().to_word()),
+                (OpcodeId::TIMESTAMP, *ctants.timestamp()),
+                (OpcodeId::NUMBER, Word::from(ctants.number().as_u64())),
+                (OpcodeId::DIFFICULTY, *ctants.difficulty()),
+                (OpcodeId::GASLIMIT, *ctants.gas_limit()),
+                (OpcodeId::CHAINID, *ctants.chain_id()),
+                (OpcodeId::BASEFEE, *ctants.base_fee()),
+            ]
+            .iter()
+            .enumerate()
+            .map(|(idx, (opcode, value))| {
+                (
+                    *opcode,
+                    StackOp::new(
+                        RW::WRITE,
+                        call_id,
+                        StackAddress::from(1023 - idx),
+                        *value,
+                    ),
+                )
+            })
+            .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn blockhash_opcode_impl() {
+        // The parent block, one out of the window of the previous 256 blocks
+        // and the current one
+        let number = mock::new_block().number.unwrap().as_u64();
+        let code = bytecode! {
+            PUSH32(number - 1)
+            BLOCKHASH
+            PUSH32(number - 257)
+            BLOCKHASH
+            PUSH32(number)
+            BLOCKHASH
+            STOP
+        };
+
+        // Get the execution steps from the external tracer
+        let block = mock::BlockData::new_single_tx_trace_code(&code).unwrap();
+
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let hashes = stack_writes(&builder)
+            .into_iter()
+            .filter(|(opcode, _)| *opcode == OpcodeId::BLOCKHASH)
+            .map(|(_, op)| *op.value())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            hashes,
+            vec![Word::from(number - 1), Word::zero(), Word::zero()]
+        );
+        for (idx, n) in [number - 1, number - 257, number].iter().enumerate() {
+            assert_eq!(
+                hashes[idx],
+                Word::from_big_endian(
+                    block.block_ctants.block_hash(Word::from(*n)).as_bytes()
+                )
+            );
+        }
+    }
+}
@@ -0,0 +1,326 @@
+use super::Opcode;
+use crate::circuit_input_builder::{get_call_result, CircuitInputStateRef};
+use crate::eth_types::{GethExecStep, Word};
+use crate::{
+    evm::OpcodeId,
+    operation::{AccountCodeHashOp, StackOp, TxAccessListAccountOp, RW},
+    Error,
+};
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the [`OpcodeId::CALL`](crate::evm::OpcodeId::CALL),
+/// [`OpcodeId::CALLCODE`](crate::evm::OpcodeId::CALLCODE),
+/// [`OpcodeId::DELEGATECALL`](crate::evm::OpcodeId::DELEGATECALL) and
+/// [`OpcodeId::STATICCALL`](crate::evm::OpcodeId::STATICCALL) `OpcodeId`s.
+/// `N_ARGS` is the number of arguments popped from the stack.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Call<const N_ARGS: usize>;
+
+impl<const N_ARGS: usize> Opcode for Call<N_ARGS> {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        steps: &[GethExecStep],
+    ) -> Result<(), Error> {
+        let step = &steps[0];
+        let caller = state.call().clone();
+
+        // N_ARGS stack reads
+        for i in 0..N_ARGS {
+            state.push_op(StackOp::new(
+                RW::READ,
+                caller.call_id,
+                step.stack.nth_last_filled(i),
+                step.stack.nth_last(i)?,
+            ));
+        }
+
+        // Stack write of the success of the call
+        let result = get_call_result(steps).unwrap_or_else(Word::zero);
+        let result_pos = step.stack.nth_last_filled(N_ARGS - 1);
+        state.push_op(StackOp::new(
+            RW::WRITE,
+            caller.call_id,
+            result_pos,
+            result,
+        ));
+
+        // The code address is warm after the call (EIP-2929)
+        let callee = state.parse_call(step, 0)?;
+        let is_warm =
+            state.sdb.check_account_in_access_list(&callee.code_address);
+        state.push_op(TxAccessListAccountOp::new(
+            RW::WRITE,
+            state.tx_id(),
+            callee.code_address,
+            true,
+            is_warm,
+        ));
+        state.sdb.add_account_to_access_list(&callee.code_address);
+
+        // Read the code hash of the code address, which is the code run by the
+        // callee
+        let code_hash = state.sdb.get_account(&callee.code_address).1.codeHash;
+        state.push_op(AccountCodeHashOp::new(
+            RW::READ,
+            callee.code_address,
+            code_hash,
+            code_hash,
+        ));
+
+        // Transfer the value of a successful CALL.  CALLCODE sends the value
+        // to the caller itself, and DELEGATECALL and STATICCALL don't transfer
+        // any value.
+        if step.op == OpcodeId::CALL
+            && !result.is_zero()
+            && !callee.value.is_zero()
+        {
+            state.transfer(callee.caller_address, callee.address, callee.value);
+        }
+
+        // A call to an account without code, or to a precompiled contract,
+        // finishes within this step.
+        // TODO: Handle the calls to precompiled contracts, and clear the
+        // return data of the last callee, which such calls leave empty.
+        let callee_executed = steps
+            .get(1)
+            .map(|next_step| next_step.depth == step.depth + 1)
+            .unwrap_or(false);
+        if !callee_executed {
+            return Ok(());
+        }
+
+        let memory_size = 32 * state.step.memory_size as u64;
+        state.handle_call(step, callee, result_pos, memory_size)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod call_tests {
+    use super::*;
+    use crate::{
+        bytecode,
+        circuit_input_builder::CircuitInputBuilder,
+        eth_types::{Address, ToWord},
+        evm::StackAddress,
+        mock,
+        operation::{CallContextField, Target},
+    };
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn call_opcode_impl() -> Result<(), Error> {
+        let code_a = bytecode! {
+            PUSH1(0x00) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x00) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH1(0x00) // value
+            PUSH2(0x123) // addr
+            PUSH2(0x1000) // gas
+            CALL
+            STOP
+        };
+        let code_b = bytecode! {
+            PUSH1(0x42)
+            STOP
+        };
+
+        // Get the execution steps from the external tracer
+        let block =
+            mock::BlockData::new_single_tx_trace_code_2(&code_a, &code_b)
+                .unwrap();
+
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let tx = &builder.block.txs()[0];
+        let (call_index, call_step) = tx
+            .steps()
+            .iter()
+            .enumerate()
+            .find(|(_, step)| step.op == OpcodeId::CALL)
+            .unwrap();
+        let callee_step = &tx.steps()[call_index + 1];
+        let caller_id = 0;
+        let callee_id = usize::from(callee_step.gc);
+        let addr_b = Address::from_low_u64_be(0x123);
+
+        // The callee is pushed into the calls of the transaction
+        let callee = &tx.calls()[callee_step.call_index];
+        assert_eq!(callee.call_id, callee_id);
+        assert_eq!(callee.caller_id, caller_id);
+        assert_eq!(callee.depth, 2);
+        assert_eq!(callee.address, addr_b);
+        assert_eq!(callee.gas, callee_step.gas_left.0);
+
+        // Stack reads of the arguments and stack write of the result
+        let stack_ops = (0..7)
+            .map(|i| {
+                StackOp::new(
+                    RW::READ,
+                    caller_id,
+                    StackAddress::from(1017 + i),
+                    block.geth_trace.struct_logs[call_index]
+                        .stack
+                        .nth_last(i)
+                        .unwrap(),
+                )
+            })
+            .chain(std::iter::once(StackOp::new(
+                RW::WRITE,
+                caller_id,
+                StackAddress::from(1023),
+                Word::one(),
+            )))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            call_step.bus_mapping_instance[..8]
+                .iter()
+                .map(|op_ref| builder.block.container.stack
+                    [op_ref.as_usize() - 1]
+                    .op()
+                    .clone())
+                .collect::<Vec<_>>(),
+            stack_ops
+        );
+
+        // The code address becomes warm
+        let access_list_op_ref = call_step.bus_mapping_instance[8];
+        assert_eq!(access_list_op_ref.target(), Target::TxAccessListAccount);
+        assert_eq!(
+            builder.block.container.tx_access_list_account
+                [access_list_op_ref.as_usize() - 1]
+                .op(),
+            &TxAccessListAccountOp::new(RW::WRITE, 1, addr_b, true, false)
+        );
+
+        // The code hash of the code address is read
+        let code_hash_op_ref = call_step.bus_mapping_instance[9];
+        assert_eq!(code_hash_op_ref.target(), Target::AccountCodeHash);
+        assert_eq!(
+            builder.block.container.account_code_hash
+                [code_hash_op_ref.as_usize() - 1]
+                .op(),
+            &AccountCodeHashOp::new(
+                RW::READ,
+                addr_b,
+                callee.code_hash(),
+                callee.code_hash()
+            )
+        );
+
+        // Context of the caller read and saved, and context of the callee set
+        // up
+        let call_context_ops = call_step.bus_mapping_instance[10..]
+            .iter()
+            .map(|op_ref| {
+                let op = builder.block.container.call_context
+                    [op_ref.as_usize() - 1]
+                    .op();
+                (op.rw(), op.call_id(), op.field(), *op.value())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(call_context_ops.len(), 6 + 6 + 14);
+        assert_eq!(
+            call_context_ops[6..12]
+                .iter()
+                .map(|(rw, call_id, field, _)| (*rw, *call_id, *field))
+                .collect::<Vec<_>>(),
+            [
+                CallContextField::ProgramCounter,
+                CallContextField::StackPointer,
+                CallContextField::GasLeft,
+                CallContextField::MemorySize,
+                CallContextField::StateWriteCounter,
+                CallContextField::CodeHash,
+            ]
+            .iter()
+            .map(|field| (RW::WRITE, caller_id, *field))
+            .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            call_context_ops[12..]
+                .iter()
+                .filter(|(_, _, field, _)| [
+                    CallContextField::CallerId,
+                    CallContextField::CalleeAddress,
+                    CallContextField::IsSuccess,
+                    CallContextField::Gas
+                ]
+                .contains(field))
+                .cloned()
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    RW::WRITE,
+                    callee_id,
+                    CallContextField::CallerId,
+                    caller_id.into()
+                ),
+                (
+                    RW::WRITE,
+                    callee_id,
+                    CallContextField::CalleeAddress,
+                    addr_b.to_word()
+                ),
+                (
+                    RW::WRITE,
+                    callee_id,
+                    CallContextField::IsSuccess,
+                    Word::one()
+                ),
+                (
+                    RW::WRITE,
+                    callee_id,
+                    CallContextField::Gas,
+                    callee_step.gas_left.0.into()
+                ),
+            ]
+        );
+
+        // The callee restores the context of the caller when it returns
+        let return_step = &tx.steps()[call_index + 2];
+        assert_eq!(
+            return_step
+                .bus_mapping_instance
+                .iter()
+                .filter(|op_ref| op_ref.target() == Target::CallContext)
+                .map(|op_ref| {
+                    let op = builder.block.container.call_context
+                        [op_ref.as_usize() - 1]
+                        .op();
+                    (op.rw(), op.call_id(), op.field())
+                })
+                .collect::<Vec<_>>(),
+            vec![
+                (RW::READ, callee_id, CallContextField::IsSuccess),
+                (RW::READ, callee_id, CallContextField::CallerId),
+                (RW::READ, callee_id, CallContextField::Gas),
+                (RW::READ, caller_id, CallContextField::ProgramCounter),
+                (RW::READ, caller_id, CallContextField::StackPointer),
+                (RW::READ, caller_id, CallContextField::GasLeft),
+                (RW::READ, caller_id, CallContextField::MemorySize),
+                (RW::READ, caller_id, CallContextField::StateWriteCounter),
+                (RW::READ, caller_id, CallContextField::CodeHash),
+                (RW::WRITE, caller_id, CallContextField::LastCalleeId),
+                (
+                    RW::WRITE,
+                    caller_id,
+                    CallContextField::LastCalleeReturnDataOffset
+                ),
+                (
+                    RW::WRITE,
+                    caller_id,
+                    CallContextField::LastCalleeReturnDataLength
+                ),
+            ]
+        );
+
+        Ok(())
+    }
+}
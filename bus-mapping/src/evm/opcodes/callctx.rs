@@ -0,0 +1,166 @@
+use super::Opcode;
+use crate::circuit_input_builder::CircuitInputStateRef;
+use crate::eth_types::{GethExecStep, Word};
+use crate::{
+    evm::OpcodeId,
+    operation::{CallContextField, CallContextOp, StackOp, RW},
+    Error,
+};
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the opcodes which push a value of the current call
+/// ([`OpcodeId::CALLER`](crate::evm::OpcodeId::CALLER),
+/// [`OpcodeId::CALLVALUE`](crate::evm::OpcodeId::CALLVALUE) and
+/// [`OpcodeId::ADDRESS`](crate::evm::OpcodeId::ADDRESS)) or of its
+/// transaction ([`OpcodeId::ORIGIN`](crate::evm::OpcodeId::ORIGIN) and
+/// [`OpcodeId::GASPRICE`](crate::evm::OpcodeId::GASPRICE)).
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct CallCtx;
+
+impl Opcode for CallCtx {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        steps: &[GethExecStep],
+    ) -> Result<(), Error> {
+        let step = &steps[0];
+        let value = steps[1].stack.last()?;
+
+        // Call context read of the field, or of the id of the transaction
+        // whose field is pushed
+        let (field, field_value) = match step.op {
+            OpcodeId::CALLER => (CallContextField::CallerAddress, value),
+            OpcodeId::CALLVALUE => (CallContextField::Value, value),
+            OpcodeId::ADDRESS => (CallContextField::CalleeAddress, value),
+            OpcodeId::ORIGIN | OpcodeId::GASPRICE => {
+                (CallContextField::TxId, Word::from(state.tx_id()))
+            }
+            _ => unreachable!(),
+        };
+        state.push_op(CallContextOp::new(
+            RW::READ,
+            state.call().call_id,
+            field,
+            field_value,
+        ));
+
+        // Stack write of the value
+        state.push_op(StackOp::new(
+            RW::WRITE,
+            state.call().call_id,
+            step.stack.last_filled().map(|a| a - 1),
+            value,
+        ));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod callctx_tests {
+    use super::*;
+    use crate::{
+        bytecode,
+        circuit_input_builder::CircuitInputBuilder,
+        eth_types::ToWord,
+        evm::{GasCost, StackAddress},
+        mock,
+        operation::Target,
+    };
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn callctx_opcode_impl() {
+        let code = bytecode! {
+            PUSH1(0x01)
+            PUSH1(0x20)
+            MSTORE
+            CALLER
+            CALLVALUE
+            ADDRESS
+            ORIGIN
+            GASPRICE
+            MSIZE
+            GAS
+            STOP
+        };
+
+        // Get the execution steps from the external tracer
+        let block = mock::BlockData::new_single_tx_trace_code(&code).unwrap();
+
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let tx = &builder.block.txs()[0];
+        let call_id = tx.calls()[0].call_id;
+        let container = &builder.block.container;
+        let eth_tx = &block.eth_tx;
+        // The gas left after GAS is paid
+        let gas_step = &block.geth_trace.struct_logs[9];
+        let gas = Word::from(gas_step.gas.0 - GasCost::QUICK.as_u64());
+
+        let steps = &tx.steps()[3..10];
+        for (idx, (step, (opcode, field, value))) in steps
+            .iter()
+            .zip([
+                (
+                    OpcodeId::CALLER,
+                    Some((
+                        CallContextField::CallerAddress,
+                        eth_tx.from.to_word(),
+                    )),
+                    eth_tx.from.to_word(),
+                ),
+                (
+                    OpcodeId::CALLVALUE,
+                    Some((CallContextField::Value, eth_tx.value)),
+                    eth_tx.value,
+                ),
+                (
+                    OpcodeId::ADDRESS,
+                    Some((
+                        CallContextField::CalleeAddress,
+                        eth_tx.to.unwrap().to_word(),
+                    )),
+                    eth_tx.to.unwrap().to_word(),
+                ),
+                (
+                    OpcodeId::ORIGIN,
+                    Some((CallContextField::TxId, Word::one())),
+                    eth_tx.from.to_word(),
+                ),
+                (
+                    OpcodeId::GASPRICE,
+                    Some((CallContextField::TxId, Word::one())),
+                    eth_tx.gas_price.unwrap(),
+                ),
+                (OpcodeId::MSIZE, None, Word::from(0x40)),
+                (OpcodeId::GAS, None, gas),
+            ])
+            .enumerate()
+        {
+            assert_eq!(step.op, opcode);
+            let ops = &step.bus_mapping_instance;
+            if let Some((field, field_value)) = field {
+                assert_eq!(ops[0].target(), Target::CallContext);
+                assert_eq!(
+                    container.call_context[ops[0].as_usize() - 1].op(),
+                    &CallContextOp::new(RW::READ, call_id, field, field_value)
+                );
+            }
+            let stack_op = ops.last().unwrap();
+            assert_eq!(stack_op.target(), Target::Stack);
+            assert_eq!(
+                container.stack[stack_op.as_usize() - 1].op(),
+                &StackOp::new(
+                    RW::WRITE,
+                    call_id,
+                    StackAddress::from(1023 - idx),
+                    value
+                )
+            );
+        }
+    }
+}
@@ -0,0 +1,328 @@
+use super::calldataload::{call_data_bytes_read, push_call_data_reads};
+use super::Opcode;
+use crate::circuit_input_builder::CircuitInputStateRef;
+use crate::eth_types::GethExecStep;
+use crate::{
+    evm::MemoryAddress,
+    operation::{MemoryOp, StackOp, RW},
+    Error,
+};
+use core::convert::TryInto;
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the
+/// [`OpcodeId::CALLDATACOPY`](crate::evm::OpcodeId::CALLDATACOPY) `OpcodeId`.
+/// Without enough gas to expand the memory and copy the bytes, only the
+/// arguments are read.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Calldatacopy;
+
+impl Opcode for Calldatacopy {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        steps: &[GethExecStep],
+    ) -> Result<(), Error> {
+        let step = &steps[0];
+        let call = state.call().clone();
+
+        // Stack reads of the memory offset, the call data offset and the
+        // length
+        for i in 0..3 {
+            state.push_op(StackOp::new(
+                RW::READ,
+                call.call_id,
+                step.stack.nth_last_filled(i),
+                step.stack.nth_last(i)?,
+            ));
+        }
+
+        if step.error.is_some() {
+            state.step.error = state.get_step_err(step, steps.get(1))?;
+            return Ok(());
+        }
+
+        let data_offset = step.stack.nth_last(1)?;
+        let length = step.stack.nth_last(2)?.as_usize();
+
+        push_call_data_reads(state, &call);
+
+        if length == 0 {
+            return Ok(());
+        }
+
+        // Copy of each byte, read from the memory of the caller for an
+        // internal call when it is within the call data, and written into the
+        // memory of the current call.
+        let memory_offset: MemoryAddress =
+            step.stack.nth_last(0)?.try_into()?;
+        let bytes = steps[1]
+            .memory
+            .0
+            .get(memory_offset.0..memory_offset.0 + length)
+            .ok_or(Error::InvalidMemoryPointer)?;
+        let num_bytes_read = if call.is_root() {
+            0
+        } else {
+            call_data_bytes_read(&call, data_offset, length)
+        };
+        for (i, byte) in bytes.iter().enumerate() {
+            if i < num_bytes_read {
+                state.push_op(MemoryOp::new(
+                    RW::READ,
+                    call.caller_id,
+                    MemoryAddress::from(
+                        call.call_data_offset as usize
+                            + data_offset.as_usize()
+                            + i,
+                    ),
+                    *byte,
+                ));
+            }
+            state.push_op(MemoryOp::new(
+                RW::WRITE,
+                call.call_id,
+                MemoryAddress::from(memory_offset.0 + i),
+                *byte,
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod calldatacopy_tests {
+    use super::*;
+    use crate::{
+        bytecode,
+        circuit_input_builder::{
+            CircuitInputBuilder, ExecError, ExecStep, OogError, Transaction,
+            TransactionContext,
+        },
+        eth_types::{Bytes, Word},
+        evm::{OpcodeId, StackAddress},
+        mock,
+        operation::{CallContextField, CallContextOp, Target},
+    };
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn calldatacopy_opcode_impl() -> Result<(), Error> {
+        let code = bytecode! {
+            PUSH1(0x04) // length
+            PUSH1(0x02) // offset
+            PUSH1(0x20) // destOffset
+            #[start]
+            CALLDATACOPY
+            STOP
+        };
+        let call_data = Bytes::from(vec![0x01, 0x02, 0x03, 0x04, 0x05]);
+
+        // Get the execution steps from the external tracer
+        let block =
+            mock::BlockData::new_single_tx_trace_code_calldata_at_start(
+                &code, call_data,
+            )
+            .unwrap();
+
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let mut test_builder = CircuitInputBuilder::new(
+            block.eth_block,
+            block.block_ctants.clone(),
+        );
+        let mut tx = Transaction::new(0, &block.eth_tx);
+        let mut tx_ctx = TransactionContext::new(&block.eth_tx);
+        test_builder.begin_tx(&mut tx).unwrap();
+
+        // Generate step corresponding to CALLDATACOPY
+        let mut step = ExecStep::new(
+            &block.geth_trace.struct_logs[0],
+            0,
+            test_builder.block_ctx.gc,
+            0,
+        );
+        let mut state_ref =
+            test_builder.state_ref(&mut tx, &mut tx_ctx, &mut step);
+        // Add StackOps associated to the reads of the arguments.
+        for (i, value) in [0x20u64, 0x02, 0x04].iter().enumerate() {
+            state_ref.push_op(StackOp::new(
+                RW::READ,
+                0,
+                StackAddress::from(1021 + i),
+                Word::from(*value),
+            ));
+        }
+        // Add CallContextOps associated to the location of the call data of
+        // the root call, which is the input of the first transaction.
+        for (field, value) in [
+            (CallContextField::TxId, 1u64),
+            (CallContextField::Depth, 1),
+            (CallContextField::CallerId, 0),
+            (CallContextField::CallDataOffset, 0),
+            (CallContextField::CallDataLength, 5),
+        ] {
+            state_ref.push_op(CallContextOp::new(
+                RW::READ,
+                0,
+                field,
+                Word::from(value),
+            ));
+        }
+        // Add MemoryOps associated to the writes of the last 3 bytes of the
+        // call data and of a padding zero.
+        for (i, byte) in [0x03, 0x04, 0x05, 0x00].iter().enumerate() {
+            state_ref.push_op(MemoryOp::new(
+                RW::WRITE,
+                0,
+                MemoryAddress::from(0x20 + i),
+                *byte,
+            ));
+        }
+        tx.steps_mut().push(step);
+        test_builder.end_tx(&mut tx, &block.geth_trace);
+        test_builder.block.txs_mut().push(tx);
+
+        assert_eq!(
+            builder.block.txs()[0].steps()[0].bus_mapping_instance,
+            test_builder.block.txs()[0].steps()[0].bus_mapping_instance
+        );
+        assert_eq!(builder.block.container, test_builder.block.container);
+
+        Ok(())
+    }
+
+    #[test]
+    fn calldatacopy_opcode_internal_call() -> Result<(), Error> {
+        let code_a = bytecode! {
+            PUSH32(Word::MAX)
+            PUSH1(0x00)
+            MSTORE
+            PUSH1(0x00) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x08) // argsLength
+            PUSH1(0x04) // argsOffset
+            PUSH1(0x00) // value
+            PUSH2(0x123) // addr
+            PUSH2(0x1000) // gas
+            CALL
+            STOP
+        };
+        let code_b = bytecode! {
+            PUSH1(0x04) // length
+            PUSH1(0x06) // offset
+            PUSH1(0x00) // destOffset
+            CALLDATACOPY
+            STOP
+        };
+
+        // Get the execution steps from the external tracer
+        let block =
+            mock::BlockData::new_single_tx_trace_code_2(&code_a, &code_b)
+                .unwrap();
+
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let tx = &builder.block.txs()[0];
+        let step = tx
+            .steps()
+            .iter()
+            .find(|step| step.op == OpcodeId::CALLDATACOPY)
+            .unwrap();
+        let callee_id = tx.calls()[step.call_index].call_id;
+
+        // The last 2 bytes of the call data are read from the memory of the
+        // caller and written into the memory of the callee, followed by 2
+        // padding zeros.
+        let memory_ops = step.bus_mapping_instance[8..]
+            .iter()
+            .map(|op_ref| {
+                assert_eq!(op_ref.target(), Target::Memory);
+                let op =
+                    builder.block.container.memory[op_ref.as_usize() - 1].op();
+                (op.rw(), op.call_id(), op.address().0, op.value())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            memory_ops,
+            vec![
+                (RW::READ, 0, 10, 0xff),
+                (RW::WRITE, callee_id, 0, 0xff),
+                (RW::READ, 0, 11, 0xff),
+                (RW::WRITE, callee_id, 1, 0xff),
+                (RW::WRITE, callee_id, 2, 0x00),
+                (RW::WRITE, callee_id, 3, 0x00),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn calldatacopy_out_of_gas() {
+        // The callee doesn't have the gas to expand the memory up to the
+        // copied bytes
+        let code = bytecode! {
+            PUSH1(0x00) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x00) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH1(0x00) // value
+            PUSH2(0x123) // addr
+            PUSH2(0x800) // gas
+            CALL
+            STOP
+        };
+        let callee_code = bytecode! {
+            PUSH1(0x04) // length
+            PUSH1(0x00) // offset
+            PUSH3(0x10000) // destOffset
+            CALLDATACOPY
+            STOP
+        };
+        let block =
+            mock::BlockData::new_single_tx_trace_code_2(&code, &callee_code)
+                .unwrap();
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        // Only the arguments are read
+        let step = builder.block.txs()[0]
+            .steps()
+            .iter()
+            .find(|step| step.op == OpcodeId::CALLDATACOPY)
+            .unwrap();
+        assert_eq!(
+            step.error,
+            Some(ExecError::OutOfGas(OogError::CallDataCopy))
+        );
+        // The reads are followed by the operations that return to the caller
+        assert_eq!(
+            step.bus_mapping_instance[..4]
+                .iter()
+                .map(|op_ref| op_ref.target())
+                .collect::<Vec<_>>(),
+            vec![
+                Target::Stack,
+                Target::Stack,
+                Target::Stack,
+                Target::CallContext
+            ]
+        );
+        assert!(step
+            .bus_mapping_instance
+            .iter()
+            .all(|op_ref| op_ref.target() != Target::Memory));
+    }
+}
@@ -0,0 +1,282 @@
+use super::Opcode;
+use crate::circuit_input_builder::{Call, CircuitInputStateRef};
+use crate::eth_types::{GethExecStep, ToBigEndian, Word};
+use crate::{
+    evm::MemoryAddress,
+    operation::{CallContextField, CallContextOp, MemoryOp, StackOp, RW},
+    Error,
+};
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the
+/// [`OpcodeId::CALLDATALOAD`](crate::evm::OpcodeId::CALLDATALOAD) `OpcodeId`.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Calldataload;
+
+impl Opcode for Calldataload {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        steps: &[GethExecStep],
+    ) -> Result<(), Error> {
+        let step = &steps[0];
+        let call = state.call().clone();
+
+        // Stack read of the offset
+        let offset = step.stack.last()?;
+        state.push_op(StackOp::new(
+            RW::READ,
+            call.call_id,
+            step.stack.last_filled(),
+            offset,
+        ));
+
+        push_call_data_reads(state, &call);
+
+        // Memory reads of the call data in the memory of the caller for an
+        // internal call, where the bytes past the end of the call data are
+        // zeros that are not read.
+        let value = steps[1].stack.last()?;
+        if !call.is_root() {
+            let num_bytes_read = call_data_bytes_read(&call, offset, 32);
+            let address = call.call_data_offset as usize + offset.as_usize();
+            for (i, byte) in
+                value.to_be_bytes().iter().enumerate().take(num_bytes_read)
+            {
+                state.push_op(MemoryOp::new(
+                    RW::READ,
+                    call.caller_id,
+                    MemoryAddress::from(address + i),
+                    *byte,
+                ));
+            }
+        }
+
+        // Stack write of the loaded word
+        state.push_op(StackOp::new(
+            RW::WRITE,
+            call.call_id,
+            step.stack.last_filled(),
+            value,
+        ));
+
+        Ok(())
+    }
+}
+
+/// Pushes the call context reads of the location of the call data of `call`,
+/// which is either the input of the transaction for a root call or a region
+/// of the memory of the caller for an internal call.
+pub(super) fn push_call_data_reads(
+    state: &mut CircuitInputStateRef,
+    call: &Call,
+) {
+    let tx_id = state.tx_id();
+    for (field, value) in [
+        (CallContextField::TxId, tx_id),
+        (CallContextField::Depth, call.depth),
+        (CallContextField::CallerId, call.caller_id),
+        (
+            CallContextField::CallDataOffset,
+            call.call_data_offset as usize,
+        ),
+        (
+            CallContextField::CallDataLength,
+            call.call_data_length as usize,
+        ),
+    ] {
+        state.push_op(CallContextOp::new(
+            RW::READ,
+            call.call_id,
+            field,
+            value.into(),
+        ));
+    }
+}
+
+/// Returns how many of the `length` bytes of the call data of `call` starting
+/// at `offset` are within the call data.
+pub(super) fn call_data_bytes_read(
+    call: &Call,
+    offset: Word,
+    length: usize,
+) -> usize {
+    if offset >= Word::from(call.call_data_length) {
+        0
+    } else {
+        length.min(call.call_data_length as usize - offset.as_usize())
+    }
+}
+
+#[cfg(test)]
+mod calldataload_tests {
+    use super::*;
+    use crate::{
+        bytecode,
+        circuit_input_builder::{
+            CircuitInputBuilder, ExecStep, Transaction, TransactionContext,
+        },
+        eth_types::Bytes,
+        evm::{OpcodeId, StackAddress},
+        mock,
+        operation::Target,
+    };
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn calldataload_opcode_impl() -> Result<(), Error> {
+        let code = bytecode! {
+            PUSH1(0x10)
+            #[start]
+            CALLDATALOAD
+            STOP
+        };
+        let call_data = Bytes::from((1..=0x20).collect::<Vec<u8>>());
+
+        // Get the execution steps from the external tracer
+        let block =
+            mock::BlockData::new_single_tx_trace_code_calldata_at_start(
+                &code, call_data,
+            )
+            .unwrap();
+
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let mut test_builder = CircuitInputBuilder::new(
+            block.eth_block,
+            block.block_ctants.clone(),
+        );
+        let mut tx = Transaction::new(0, &block.eth_tx);
+        let mut tx_ctx = TransactionContext::new(&block.eth_tx);
+        test_builder.begin_tx(&mut tx).unwrap();
+
+        // Generate step corresponding to CALLDATALOAD
+        let mut step = ExecStep::new(
+            &block.geth_trace.struct_logs[0],
+            0,
+            test_builder.block_ctx.gc,
+            0,
+        );
+        let mut state_ref =
+            test_builder.state_ref(&mut tx, &mut tx_ctx, &mut step);
+        // Add StackOp associated to the offset read.
+        state_ref.push_op(StackOp::new(
+            RW::READ,
+            0,
+            StackAddress::from(1023),
+            Word::from(0x10),
+        ));
+        // Add CallContextOps associated to the location of the call data of
+        // the root call, which is the input of the first transaction.
+        for (field, value) in [
+            (CallContextField::TxId, 1u64),
+            (CallContextField::Depth, 1),
+            (CallContextField::CallerId, 0),
+            (CallContextField::CallDataOffset, 0),
+            (CallContextField::CallDataLength, 0x20),
+        ] {
+            state_ref.push_op(CallContextOp::new(
+                RW::READ,
+                0,
+                field,
+                Word::from(value),
+            ));
+        }
+        // Add StackOp associated to the stack write of the last 16 bytes of
+        // the call data, padded with zeros.
+        let mut value = [0u8; 32];
+        value[..16].copy_from_slice(&(0x11..=0x20).collect::<Vec<u8>>());
+        state_ref.push_op(StackOp::new(
+            RW::WRITE,
+            0,
+            StackAddress::from(1023),
+            Word::from_big_endian(&value),
+        ));
+        tx.steps_mut().push(step);
+        test_builder.end_tx(&mut tx, &block.geth_trace);
+        test_builder.block.txs_mut().push(tx);
+
+        assert_eq!(
+            builder.block.txs()[0].steps()[0].bus_mapping_instance,
+            test_builder.block.txs()[0].steps()[0].bus_mapping_instance
+        );
+        assert_eq!(builder.block.container, test_builder.block.container);
+
+        Ok(())
+    }
+
+    #[test]
+    fn calldataload_opcode_internal_call() -> Result<(), Error> {
+        let code_a = bytecode! {
+            PUSH32(Word::MAX)
+            PUSH1(0x00)
+            MSTORE
+            PUSH1(0x00) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x08) // argsLength
+            PUSH1(0x04) // argsOffset
+            PUSH1(0x00) // value
+            PUSH2(0x123) // addr
+            PUSH2(0x1000) // gas
+            CALL
+            STOP
+        };
+        let code_b = bytecode! {
+            PUSH1(0x02)
+            CALLDATALOAD
+            STOP
+        };
+
+        // Get the execution steps from the external tracer
+        let block =
+            mock::BlockData::new_single_tx_trace_code_2(&code_a, &code_b)
+                .unwrap();
+
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let tx = &builder.block.txs()[0];
+        let step = tx
+            .steps()
+            .iter()
+            .find(|step| step.op == OpcodeId::CALLDATALOAD)
+            .unwrap();
+        let callee_id = tx.calls()[step.call_index].call_id;
+
+        // The 6 bytes of the call data from the offset are read from the
+        // memory of the caller, after the reads of the call context.
+        let memory_reads = step.bus_mapping_instance[6..12]
+            .iter()
+            .map(|op_ref| {
+                assert_eq!(op_ref.target(), Target::Memory);
+                let op =
+                    builder.block.container.memory[op_ref.as_usize() - 1].op();
+                (op.rw(), op.call_id(), op.address().0, op.value())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            memory_reads,
+            (6..12)
+                .map(|address| (RW::READ, 0, address, 0xff))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(step.bus_mapping_instance.len(), 13);
+
+        // The call data is padded with zeros
+        let value = builder.block.container.stack
+            [step.bus_mapping_instance[12].as_usize() - 1]
+            .op();
+        assert_eq!(value.call_id(), callee_id);
+        let mut bytes = [0u8; 32];
+        bytes[..6].copy_from_slice(&[0xff; 6]);
+        assert_eq!(*value.value(), Word::from_big_endian(&bytes));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,122 @@
+use super::Opcode;
+use crate::circuit_input_builder::CircuitInputStateRef;
+use crate::eth_types::GethExecStep;
+use crate::{
+    operation::{CallContextField, CallContextOp, StackOp, RW},
+    Error,
+};
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the
+/// [`OpcodeId::CALLDATASIZE`](crate::evm::OpcodeId::CALLDATASIZE) `OpcodeId`.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Calldatasize;
+
+impl Opcode for Calldatasize {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        steps: &[GethExecStep],
+    ) -> Result<(), Error> {
+        let step = &steps[0];
+
+        // Call context read of the length of the call data
+        let call_data_length = steps[1].stack.last()?;
+        state.push_op(CallContextOp::new(
+            RW::READ,
+            state.call().call_id,
+            CallContextField::CallDataLength,
+            call_data_length,
+        ));
+
+        // Stack write of the length
+        state.push_op(StackOp::new(
+            RW::WRITE,
+            state.call().call_id,
+            step.stack.last_filled().map(|a| a - 1),
+            call_data_length,
+        ));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod calldatasize_tests {
+    use super::*;
+    use crate::{
+        bytecode,
+        circuit_input_builder::{
+            CircuitInputBuilder, ExecStep, Transaction, TransactionContext,
+        },
+        eth_types::{Bytes, Word},
+        evm::StackAddress,
+        mock,
+    };
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn calldatasize_opcode_impl() -> Result<(), Error> {
+        let code = bytecode! {
+            #[start]
+            CALLDATASIZE
+            STOP
+        };
+        let call_data = Bytes::from(vec![0xffu8; 0x23]);
+
+        // Get the execution steps from the external tracer
+        let block =
+            mock::BlockData::new_single_tx_trace_code_calldata_at_start(
+                &code, call_data,
+            )
+            .unwrap();
+
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let mut test_builder = CircuitInputBuilder::new(
+            block.eth_block,
+            block.block_ctants.clone(),
+        );
+        let mut tx = Transaction::new(0, &block.eth_tx);
+        let mut tx_ctx = TransactionContext::new(&block.eth_tx);
+        test_builder.begin_tx(&mut tx).unwrap();
+
+        // Generate step corresponding to CALLDATASIZE
+        let mut step = ExecStep::new(
+            &block.geth_trace.struct_logs[0],
+            0,
+            test_builder.block_ctx.gc,
+            0,
+        );
+        let mut state_ref =
+            test_builder.state_ref(&mut tx, &mut tx_ctx, &mut step);
+        // Add CallContextOp associated to the call data length read.
+        state_ref.push_op(CallContextOp::new(
+            RW::READ,
+            0,
+            CallContextField::CallDataLength,
+            Word::from(0x23),
+        ));
+        // Add StackOp associated to the stack push.
+        state_ref.push_op(StackOp::new(
+            RW::WRITE,
+            0,
+            StackAddress::from(1023),
+            Word::from(0x23),
+        ));
+        tx.steps_mut().push(step);
+        test_builder.end_tx(&mut tx, &block.geth_trace);
+        test_builder.block.txs_mut().push(tx);
+
+        assert_eq!(
+            builder.block.txs()[0].steps()[0].bus_mapping_instance,
+            test_builder.block.txs()[0].steps()[0].bus_mapping_instance
+        );
+        assert_eq!(builder.block.container, test_builder.block.container);
+
+        Ok(())
+    }
+}
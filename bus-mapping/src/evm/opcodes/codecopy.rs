@@ -0,0 +1,223 @@
+use super::Opcode;
+use crate::circuit_input_builder::CircuitInputStateRef;
+use crate::eth_types::{GethExecStep, Word};
+use crate::{
+    evm::MemoryAddress,
+    operation::{MemoryOp, StackOp, RW},
+    Error,
+};
+use core::convert::TryInto;
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the [`OpcodeId::CODECOPY`](crate::evm::OpcodeId::CODECOPY)
+/// `OpcodeId`.  Without enough gas to copy the code, only the arguments are
+/// read.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Codecopy;
+
+impl Opcode for Codecopy {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        steps: &[GethExecStep],
+    ) -> Result<(), Error> {
+        let step = &steps[0];
+
+        // Stack reads of the memory offset, the code offset and the length
+        for i in 0..3 {
+            state.push_op(StackOp::new(
+                RW::READ,
+                state.call().call_id,
+                step.stack.nth_last_filled(i),
+                step.stack.nth_last(i)?,
+            ));
+        }
+
+        if step.error.is_some() {
+            state.step.error = state.get_step_err(step, steps.get(1))?;
+            return Ok(());
+        }
+
+        push_code_copy_writes(
+            state,
+            steps,
+            step.stack.nth_last(0)?,
+            step.stack.nth_last(2)?,
+        )
+    }
+}
+
+/// Generate the memory writes of the bytes copied from a code by CODECOPY or
+/// EXTCODECOPY, taken from the memory after the copy.  The bytes past the end
+/// of the code are copied as zeros.
+pub(super) fn push_code_copy_writes(
+    state: &mut CircuitInputStateRef,
+    steps: &[GethExecStep],
+    memory_offset: Word,
+    length: Word,
+) -> Result<(), Error> {
+    let length = length.as_usize();
+    if length == 0 {
+        return Ok(());
+    }
+
+    let memory_offset: MemoryAddress = memory_offset.try_into()?;
+    let bytes = steps[1]
+        .memory
+        .0
+        .get(memory_offset.0..memory_offset.0 + length)
+        .ok_or(Error::InvalidMemoryPointer)?;
+    for (i, byte) in bytes.iter().enumerate() {
+        state.push_op(MemoryOp::new(
+            RW::WRITE,
+            state.call().call_id,
+            MemoryAddress::from(memory_offset.0 + i),
+            *byte,
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod codecopy_tests {
+    use super::*;
+    use crate::{
+        bytecode,
+        circuit_input_builder::{
+            CircuitInputBuilder, ExecError, ExecStep, OogError, Transaction,
+            TransactionContext,
+        },
+        evm::{OpcodeId, StackAddress},
+        mock,
+        operation::Target,
+    };
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn codecopy_opcode_impl() -> Result<(), Error> {
+        let code = bytecode! {
+            PUSH1(0x04) // length
+            PUSH1(0x05) // offset
+            PUSH1(0x20) // destOffset
+            #[start]
+            CODECOPY
+            STOP
+        };
+
+        // Get the execution steps from the external tracer
+        let block =
+            mock::BlockData::new_single_tx_trace_code_at_start(&code).unwrap();
+
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let mut test_builder = CircuitInputBuilder::new(
+            block.eth_block,
+            block.block_ctants.clone(),
+        );
+        let mut tx = Transaction::new(0, &block.eth_tx);
+        let mut tx_ctx = TransactionContext::new(&block.eth_tx);
+        test_builder.begin_tx(&mut tx).unwrap();
+
+        // Generate step corresponding to CODECOPY
+        let mut step = ExecStep::new(
+            &block.geth_trace.struct_logs[0],
+            0,
+            test_builder.block_ctx.gc,
+            0,
+        );
+        let mut state_ref =
+            test_builder.state_ref(&mut tx, &mut tx_ctx, &mut step);
+        // Add StackOps associated to the reads of the arguments.
+        for (i, value) in [0x20u64, 0x05, 0x04].iter().enumerate() {
+            state_ref.push_op(StackOp::new(
+                RW::READ,
+                0,
+                StackAddress::from(1021 + i),
+                Word::from(*value),
+            ));
+        }
+        // Add MemoryOps associated to the writes of the last 3 bytes of the
+        // code, which are the data of PUSH1(0x20), CODECOPY and STOP, and of
+        // a padding zero.
+        for (i, byte) in [0x20, 0x39, 0x00, 0x00].iter().enumerate() {
+            state_ref.push_op(MemoryOp::new(
+                RW::WRITE,
+                0,
+                MemoryAddress::from(0x20 + i),
+                *byte,
+            ));
+        }
+        tx.steps_mut().push(step);
+        test_builder.end_tx(&mut tx, &block.geth_trace);
+        test_builder.block.txs_mut().push(tx);
+
+        assert_eq!(
+            builder.block.txs()[0].steps()[0].bus_mapping_instance,
+            test_builder.block.txs()[0].steps()[0].bus_mapping_instance
+        );
+        assert_eq!(builder.block.container, test_builder.block.container);
+
+        Ok(())
+    }
+
+    #[test]
+    fn codecopy_out_of_gas() {
+        // The callee doesn't have the gas to expand the memory up to the
+        // copied bytes
+        let code = bytecode! {
+            PUSH1(0x00) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x00) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH1(0x00) // value
+            PUSH2(0x123) // addr
+            PUSH2(0x800) // gas
+            CALL
+            STOP
+        };
+        let callee_code = bytecode! {
+            PUSH1(0x04) // length
+            PUSH1(0x00) // offset
+            PUSH3(0x10000) // destOffset
+            CODECOPY
+            STOP
+        };
+        let block =
+            mock::BlockData::new_single_tx_trace_code_2(&code, &callee_code)
+                .unwrap();
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        // Only the arguments are read
+        let step = builder.block.txs()[0]
+            .steps()
+            .iter()
+            .find(|step| step.op == OpcodeId::CODECOPY)
+            .unwrap();
+        assert_eq!(step.error, Some(ExecError::OutOfGas(OogError::CodeCopy)));
+        // The reads are followed by the operations that return to the caller
+        assert_eq!(
+            step.bus_mapping_instance[..4]
+                .iter()
+                .map(|op_ref| op_ref.target())
+                .collect::<Vec<_>>(),
+            vec![
+                Target::Stack,
+                Target::Stack,
+                Target::Stack,
+                Target::CallContext
+            ]
+        );
+        assert!(step
+            .bus_mapping_instance
+            .iter()
+            .all(|op_ref| op_ref.target() != Target::Memory));
+    }
+}
@@ -0,0 +1,320 @@
+use super::Opcode;
+use crate::circuit_input_builder::{
+    get_call_result, CircuitInputStateRef, ExecError,
+};
+use crate::eth_types::{Address, GethExecStep, ToWord, Word};
+use crate::{
+    evm::GasCost,
+    operation::{
+        AccountCodeHashOp, AccountNonceOp, StackOp, TxAccessListAccountOp, RW,
+    },
+    Error,
+};
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the [`OpcodeId::CREATE`](crate::evm::OpcodeId::CREATE)
+/// and [`OpcodeId::CREATE2`](crate::evm::OpcodeId::CREATE2) `OpcodeId`s.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Create<const IS_CREATE2: bool>;
+
+impl<const IS_CREATE2: bool> Opcode for Create<IS_CREATE2> {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        steps: &[GethExecStep],
+    ) -> Result<(), Error> {
+        let step = &steps[0];
+        let call_id = state.call().call_id;
+        let n_args = if IS_CREATE2 { 4 } else { 3 };
+
+        // n_args stack reads
+        for i in 0..n_args {
+            state.push_op(StackOp::new(
+                RW::READ,
+                call_id,
+                step.stack.nth_last_filled(i),
+                step.stack.nth_last(i)?,
+            ));
+        }
+
+        // Stack write of the address of the created contract, or 0 on failure
+        let address = get_call_result(steps).unwrap_or_else(Word::zero);
+        let result_pos = step.stack.nth_last_filled(n_args - 1);
+        state.push_op(StackOp::new(RW::WRITE, call_id, result_pos, address));
+
+        // The address of CREATE depends on the nonce of the sender, so the
+        // callee is parsed before the nonce is incremented.
+        let callee = state.parse_call(step, 0)?;
+        let callee_executed = steps
+            .get(1)
+            .map(|next_step| next_step.depth == step.depth + 1)
+            .unwrap_or(false);
+
+        // A contract creation which fails before executing the init code only
+        // increments the nonce of the sender on an address collision.
+        if !callee_executed && address.is_zero() {
+            let error = state.get_step_err(step, steps.get(1))?;
+            if error == Some(ExecError::ContractAddressCollision) {
+                increase_nonce(state, callee.caller_address);
+                add_callee_to_access_list(state, callee.address);
+            }
+            state.step.error = error;
+            return Ok(());
+        }
+        // The result is 0 when the init code fails
+        debug_assert!(address.is_zero() || address == callee.address.to_word());
+
+        // Create the account of the contract, which has no code yet, and
+        // transfer the value to it.  These writes are done on behalf of the
+        // callee, so they're undone when the init code fails.
+        increase_nonce(state, callee.caller_address);
+        let code_hash = state.sdb.get_account(&callee.address).1.codeHash;
+        state.push_op(AccountCodeHashOp::new(
+            RW::READ,
+            callee.address,
+            code_hash,
+            code_hash,
+        ));
+        let swc = state.call_ctx().swc;
+        let nonce_prev = {
+            let (_, account) = state.sdb.get_account_mut(&callee.address);
+            std::mem::replace(&mut account.nonce, Word::one())
+        };
+        state.push_op(AccountNonceOp::new(
+            RW::WRITE,
+            callee.address,
+            Word::one(),
+            nonce_prev,
+        ));
+        add_callee_to_access_list(state, callee.address);
+        if !callee.value.is_zero() {
+            state.transfer(callee.caller_address, callee.address, callee.value);
+        }
+
+        // An empty init code deploys an empty contract within this step.
+        if !callee_executed {
+            return Ok(());
+        }
+
+        // The gas given to the callee is paid by the step, while the gas cost
+        // of the trace only covers the creation and the memory expansion.
+        state.step.gas_cost = GasCost(step.gas_cost.as_u64() + callee.gas);
+
+        let callee_write_ops =
+            state.call_ctx_mut().split_off_reversible_write_ops(swc);
+        let memory_size = 32 * state.step.memory_size as u64;
+        state.handle_call(step, callee, result_pos, memory_size)?;
+        state
+            .call_ctx_mut()
+            .push_reversible_write_ops(callee_write_ops);
+        Ok(())
+    }
+}
+
+/// Increment the nonce of the account at `address`, generating its
+/// [`AccountNonceOp`] write.
+fn increase_nonce(state: &mut CircuitInputStateRef, address: Address) {
+    let (_, account) = state.sdb.get_account_mut(&address);
+    let nonce_prev = account.nonce;
+    account.nonce += Word::one();
+    let nonce = account.nonce;
+    state.push_op(AccountNonceOp::new(RW::WRITE, address, nonce, nonce_prev));
+}
+
+/// Mark the contract at `address` as warm (EIP-2929), generating its
+/// [`TxAccessListAccountOp`] write.
+fn add_callee_to_access_list(
+    state: &mut CircuitInputStateRef,
+    address: Address,
+) {
+    let tx_id = state.tx_id();
+    let is_warm = state.sdb.check_account_in_access_list(&address);
+    state.push_op(TxAccessListAccountOp::new(
+        RW::WRITE,
+        tx_id,
+        address,
+        true,
+        is_warm,
+    ));
+    state.sdb.add_account_to_access_list(&address);
+}
+
+#[cfg(test)]
+mod create_tests {
+    use super::*;
+    use crate::{
+        bytecode,
+        circuit_input_builder::CircuitInputBuilder,
+        eth_types::H256,
+        evm::{OpcodeId, StackAddress},
+        mock,
+        operation::CallContextField,
+        state_db::Account,
+    };
+    use ethers_core::utils::{get_contract_address, keccak256};
+    use pretty_assertions::assert_eq;
+    use std::collections::HashMap;
+
+    #[test]
+    fn create_opcode_impl() -> Result<(), Error> {
+        // The init code is the single zero byte of memory, which is STOP.
+        let code = bytecode! {
+            PUSH1(0x01) // length
+            PUSH1(0x00) // offset
+            PUSH1(0x00) // value
+            CREATE
+            STOP
+        };
+
+        // Get the execution steps from the external tracer
+        let block = mock::BlockData::new_single_tx_trace_code(&code).unwrap();
+
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.sdb.set_account(
+            &Address::zero(),
+            Account {
+                nonce: Word::zero(),
+                balance: Word::from(555u64), /* same value as in
+                                              * `mock::new_tracer_account` */
+                storage: HashMap::new(),
+                codeHash: H256::zero(),
+            },
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let tx = &builder.block.txs()[0];
+        let (create_index, create_step) = tx
+            .steps()
+            .iter()
+            .enumerate()
+            .find(|(_, step)| step.op == OpcodeId::CREATE)
+            .unwrap();
+        let callee_step = &tx.steps()[create_index + 1];
+        let address = get_contract_address(Address::zero(), Word::zero());
+
+        // The callee runs the init code at the address of the contract
+        let callee = &tx.calls()[callee_step.call_index];
+        assert!(callee.is_create());
+        assert_eq!(callee.call_id, usize::from(callee_step.gc));
+        assert_eq!(callee.address, address);
+        assert_eq!(callee.code_hash(), H256(keccak256(&[0u8])));
+        assert_eq!(callee.gas, callee_step.gas_left.0);
+
+        // The step pays for the gas given to the callee, which the trace
+        // doesn't include in the gas cost of CREATE
+        let geth_step = &block.geth_trace.struct_logs[create_index];
+        assert_eq!(
+            create_step.gas_cost,
+            GasCost(geth_step.gas_cost.as_u64() + callee.gas)
+        );
+        assert_eq!((callee.call_data_offset, callee.call_data_length), (0, 0));
+
+        // Stack reads of the arguments and stack write of the address
+        let stack_ops = (0..3)
+            .map(|i| {
+                StackOp::new(
+                    RW::READ,
+                    0,
+                    StackAddress::from(1021 + i),
+                    block.geth_trace.struct_logs[create_index]
+                        .stack
+                        .nth_last(i)
+                        .unwrap(),
+                )
+            })
+            .chain(std::iter::once(StackOp::new(
+                RW::WRITE,
+                0,
+                StackAddress::from(1023),
+                address.to_word(),
+            )))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            create_step.bus_mapping_instance[..4]
+                .iter()
+                .map(|op_ref| builder.block.container.stack
+                    [op_ref.as_usize() - 1]
+                    .op()
+                    .clone())
+                .collect::<Vec<_>>(),
+            stack_ops
+        );
+
+        // The nonce of the sender is incremented and the contract, which has
+        // no code yet, is created with nonce 1, without any value transfer
+        let nonce_ops = [4, 6]
+            .iter()
+            .map(|idx| {
+                let op_ref = create_step.bus_mapping_instance[*idx];
+                builder.block.container.account_nonce[op_ref.as_usize() - 1]
+                    .op()
+                    .clone()
+            })
+            .collect::<Vec<_>>();
+        let code_hash_op_ref = create_step.bus_mapping_instance[5];
+        assert_eq!(
+            builder.block.container.account_code_hash
+                [code_hash_op_ref.as_usize() - 1]
+                .op(),
+            &AccountCodeHashOp::new(
+                RW::READ,
+                address,
+                H256::zero(),
+                H256::zero()
+            )
+        );
+        assert_eq!(
+            nonce_ops,
+            vec![
+                AccountNonceOp::new(
+                    RW::WRITE,
+                    Address::zero(),
+                    Word::one(),
+                    Word::zero()
+                ),
+                AccountNonceOp::new(
+                    RW::WRITE,
+                    address,
+                    Word::one(),
+                    Word::zero()
+                ),
+            ]
+        );
+
+        // The contract is marked as warm
+        let access_list_op_ref = create_step.bus_mapping_instance[7];
+        assert_eq!(
+            builder.block.container.tx_access_list_account
+                [access_list_op_ref.as_usize() - 1]
+                .op(),
+            &TxAccessListAccountOp::new(RW::WRITE, 1, address, true, false)
+        );
+
+        // The callee context is set up as in the *CALL*s
+        let callee_fields = create_step.bus_mapping_instance[4 + 4 + 6 + 6..]
+            .iter()
+            .map(|op_ref| {
+                let op = builder.block.container.call_context
+                    [op_ref.as_usize() - 1]
+                    .op();
+                (op.call_id(), op.field())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(callee_fields.len(), 14);
+        assert_eq!(
+            callee_fields[0],
+            (callee.call_id, CallContextField::CallerId)
+        );
+
+        // The nonce of the sender is incremented and the contract is created
+        assert_eq!(
+            builder.sdb.get_account(&Address::zero()).1.nonce,
+            Word::one()
+        );
+        assert_eq!(builder.sdb.get_account(&address).1.nonce, Word::one());
+
+        Ok(())
+    }
+}
@@ -20,10 +20,16 @@ impl<const N: usize> Opcode for Dup<N> {
 
         let stack_value_read = step.stack.nth_last(N - 1)?;
         let stack_position = step.stack.nth_last_filled(N - 1);
-        state.push_op(StackOp::new(RW::READ, stack_position, stack_value_read));
+        state.push_op(StackOp::new(
+            RW::READ,
+            state.call().call_id,
+            stack_position,
+            stack_value_read,
+        ));
 
         state.push_op(StackOp::new(
             RW::WRITE,
+            state.call().call_id,
             step.stack.last_filled().map(|a| a - 1),
             stack_value_read,
         ));
@@ -72,8 +78,9 @@ mod dup_tests {
             block.eth_block,
             block.block_ctants.clone(),
         );
-        let mut tx = Transaction::new(&block.eth_tx);
+        let mut tx = Transaction::new(0, &block.eth_tx);
         let mut tx_ctx = TransactionContext::new(&block.eth_tx);
+        test_builder.begin_tx(&mut tx).unwrap();
 
         // Generate steps corresponding to DUP1, DUP3, DUP5
         for (i, word) in [word!("0x3"), word!("0x2"), word!("0x1")]
@@ -91,12 +98,14 @@ mod dup_tests {
 
             state_ref.push_op(StackOp::new(
                 RW::READ,
+                0,
                 StackAddress(1024 - 3 + i),
                 *word,
             ));
 
             state_ref.push_op(StackOp::new(
                 RW::WRITE,
+                0,
                 StackAddress(1024 - 4 - i),
                 *word,
             ));
@@ -104,6 +113,7 @@ mod dup_tests {
             tx.steps_mut().push(step);
         }
 
+        test_builder.end_tx(&mut tx, &block.geth_trace);
         test_builder.block.txs_mut().push(tx);
 
         // Compare first 3 steps bus mapping instance
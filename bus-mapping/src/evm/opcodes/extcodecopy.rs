@@ -0,0 +1,209 @@
+use super::codecopy::push_code_copy_writes;
+use super::Opcode;
+use crate::circuit_input_builder::CircuitInputStateRef;
+use crate::eth_types::{GethExecStep, ToAddress, Word};
+use crate::{
+    operation::{
+        AccountCodeHashOp, CallContextField, CallContextOp, StackOp,
+        TxAccessListAccountOp, RW,
+    },
+    Error,
+};
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the
+/// [`OpcodeId::EXTCODECOPY`](crate::evm::OpcodeId::EXTCODECOPY) `OpcodeId`.
+/// Without enough gas to access the account and copy its code, only the warm
+/// status of the account is read.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Extcodecopy;
+
+impl Opcode for Extcodecopy {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        steps: &[GethExecStep],
+    ) -> Result<(), Error> {
+        let step = &steps[0];
+        let call_id = state.call().call_id;
+        let tx_id = state.tx_id();
+
+        // Call context read of the transaction, whose access list holds the
+        // account
+        state.push_op(CallContextOp::new(
+            RW::READ,
+            call_id,
+            CallContextField::TxId,
+            Word::from(tx_id),
+        ));
+
+        // Stack reads of the address, the memory offset, the code offset and
+        // the length
+        for i in 0..4 {
+            state.push_op(StackOp::new(
+                RW::READ,
+                call_id,
+                step.stack.nth_last_filled(i),
+                step.stack.nth_last(i)?,
+            ));
+        }
+
+        // The account is warm after the step (EIP-2929)
+        let address = step.stack.nth_last(0)?.to_address();
+        let is_warm = state.sdb.check_account_in_access_list(&address);
+        if step.error.is_some() {
+            state.push_op(TxAccessListAccountOp::new(
+                RW::READ,
+                tx_id,
+                address,
+                is_warm,
+                is_warm,
+            ));
+            state.step.error = state.get_step_err(step, steps.get(1))?;
+            return Ok(());
+        }
+        state.push_op(TxAccessListAccountOp::new(
+            RW::WRITE,
+            tx_id,
+            address,
+            true,
+            is_warm,
+        ));
+        state.sdb.add_account_to_access_list(&address);
+
+        // Account code hash read, which identifies the copied code.  The code
+        // hash of an empty account is 0.
+        let code_hash = state.sdb.get_account(&address).1.codeHash;
+        state.push_op(AccountCodeHashOp::new(
+            RW::READ,
+            address,
+            code_hash,
+            code_hash,
+        ));
+
+        push_code_copy_writes(
+            state,
+            steps,
+            step.stack.nth_last(1)?,
+            step.stack.nth_last(3)?,
+        )
+    }
+}
+
+#[cfg(test)]
+mod extcodecopy_tests {
+    use super::*;
+    use crate::{
+        bytecode,
+        circuit_input_builder::{
+            CircuitInputBuilder, ExecStep, Transaction, TransactionContext,
+        },
+        eth_types::Address,
+        evm::{MemoryAddress, StackAddress},
+        mock,
+        operation::MemoryOp,
+    };
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn extcodecopy_opcode_impl() -> Result<(), Error> {
+        let code_a = bytecode! {
+            PUSH1(0x03) // length
+            PUSH1(0x01) // offset
+            PUSH1(0x00) // destOffset
+            PUSH2(0x123) // address
+            #[start]
+            EXTCODECOPY
+            STOP
+        };
+        let code_b = bytecode! {
+            PUSH2(0xaabb)
+            STOP
+        };
+
+        // Get the execution steps from the external tracer
+        let mut block =
+            mock::BlockData::new_single_tx_trace_code_2(&code_a, &code_b)
+                .unwrap();
+        block.geth_trace.struct_logs =
+            block.geth_trace.struct_logs[code_a.get_pos("start")..].to_vec();
+
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        let code_hash = builder
+            .set_code(&Address::from_low_u64_be(0x123), code_b.to_bytes());
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let mut test_builder = CircuitInputBuilder::new(
+            block.eth_block,
+            block.block_ctants.clone(),
+        );
+        let mut tx = Transaction::new(0, &block.eth_tx);
+        let mut tx_ctx = TransactionContext::new(&block.eth_tx);
+        test_builder.begin_tx(&mut tx).unwrap();
+
+        // Generate step corresponding to EXTCODECOPY
+        let mut step = ExecStep::new(
+            &block.geth_trace.struct_logs[0],
+            0,
+            test_builder.block_ctx.gc,
+            0,
+        );
+        let mut state_ref =
+            test_builder.state_ref(&mut tx, &mut tx_ctx, &mut step);
+        // Add CallContextOp associated to the read of the transaction id.
+        state_ref.push_op(CallContextOp::new(
+            RW::READ,
+            0,
+            CallContextField::TxId,
+            Word::one(),
+        ));
+        // Add StackOps associated to the reads of the arguments.
+        for (i, value) in [0x123u64, 0x00, 0x01, 0x03].iter().enumerate() {
+            state_ref.push_op(StackOp::new(
+                RW::READ,
+                0,
+                StackAddress::from(1020 + i),
+                Word::from(*value),
+            ));
+        }
+        // Add TxAccessListAccountOp associated to the account becoming warm.
+        state_ref.push_op(TxAccessListAccountOp::new(
+            RW::WRITE,
+            1,
+            Address::from_low_u64_be(0x123),
+            true,
+            false,
+        ));
+        // Add AccountCodeHashOp associated to the code hash read of the
+        // account holding the copied code.
+        state_ref.push_op(AccountCodeHashOp::new(
+            RW::READ,
+            Address::from_low_u64_be(0x123),
+            code_hash,
+            code_hash,
+        ));
+        // Add MemoryOps associated to the writes of the PUSH2 data and of
+        // STOP.
+        for (i, byte) in [0xaa, 0xbb, 0x00].iter().enumerate() {
+            state_ref.push_op(MemoryOp::new(
+                RW::WRITE,
+                0,
+                MemoryAddress::from(i),
+                *byte,
+            ));
+        }
+        tx.steps_mut().push(step);
+        test_builder.end_tx(&mut tx, &block.geth_trace);
+        test_builder.block.txs_mut().push(tx);
+
+        assert_eq!(
+            builder.block.txs()[0].steps()[0].bus_mapping_instance,
+            test_builder.block.txs()[0].steps()[0].bus_mapping_instance
+        );
+        assert_eq!(builder.block.container, test_builder.block.container);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,241 @@
+use super::Opcode;
+use crate::circuit_input_builder::CircuitInputStateRef;
+use crate::eth_types::{GethExecStep, ToAddress, Word};
+use crate::{
+    operation::{
+        AccountCodeHashOp, CallContextField, CallContextOp, StackOp,
+        TxAccessListAccountOp, RW,
+    },
+    Error,
+};
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the
+/// [`OpcodeId::EXTCODESIZE`](crate::evm::OpcodeId::EXTCODESIZE) `OpcodeId`.
+/// Without enough gas to access the account, only its warm status is read.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Extcodesize;
+
+impl Opcode for Extcodesize {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        steps: &[GethExecStep],
+    ) -> Result<(), Error> {
+        let step = &steps[0];
+        let call_id = state.call().call_id;
+        let tx_id = state.tx_id();
+
+        // Call context read of the transaction, whose access list holds the
+        // account
+        state.push_op(CallContextOp::new(
+            RW::READ,
+            call_id,
+            CallContextField::TxId,
+            Word::from(tx_id),
+        ));
+
+        // Stack read of the address
+        let stack_value_read = step.stack.last()?;
+        let stack_position = step.stack.last_filled();
+        state.push_op(StackOp::new(
+            RW::READ,
+            call_id,
+            stack_position,
+            stack_value_read,
+        ));
+
+        // The account is warm after the step (EIP-2929)
+        let address = stack_value_read.to_address();
+        let is_warm = state.sdb.check_account_in_access_list(&address);
+        if step.error.is_some() {
+            state.push_op(TxAccessListAccountOp::new(
+                RW::READ,
+                tx_id,
+                address,
+                is_warm,
+                is_warm,
+            ));
+            state.step.error = state.get_step_err(step, steps.get(1))?;
+            return Ok(());
+        }
+        state.push_op(TxAccessListAccountOp::new(
+            RW::WRITE,
+            tx_id,
+            address,
+            true,
+            is_warm,
+        ));
+        state.sdb.add_account_to_access_list(&address);
+
+        // Account code hash read, which identifies the code whose size is
+        // pushed.  The code hash of an empty account is 0.
+        let code_hash = state.sdb.get_account(&address).1.codeHash;
+        state.push_op(AccountCodeHashOp::new(
+            RW::READ,
+            address,
+            code_hash,
+            code_hash,
+        ));
+
+        // Stack write of the code size
+        state.push_op(StackOp::new(
+            RW::WRITE,
+            call_id,
+            stack_position,
+            steps[1].stack.last()?,
+        ));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod extcodesize_tests {
+    use super::*;
+    use crate::{
+        bytecode,
+        circuit_input_builder::{
+            CircuitInputBuilder, ExecError, ExecStep, OogError, Transaction,
+            TransactionContext,
+        },
+        eth_types::{Address, H256},
+        evm::{OpcodeId, StackAddress},
+        mock,
+        operation::Target,
+    };
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn extcodesize_opcode_impl() -> Result<(), Error> {
+        let code = bytecode! {
+            PUSH2(0x123)
+            #[start]
+            EXTCODESIZE
+            STOP
+        };
+
+        // Get the execution steps from the external tracer
+        let block =
+            mock::BlockData::new_single_tx_trace_code_at_start(&code).unwrap();
+
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let mut test_builder = CircuitInputBuilder::new(
+            block.eth_block,
+            block.block_ctants.clone(),
+        );
+        let mut tx = Transaction::new(0, &block.eth_tx);
+        let mut tx_ctx = TransactionContext::new(&block.eth_tx);
+        test_builder.begin_tx(&mut tx).unwrap();
+
+        // Generate step corresponding to EXTCODESIZE
+        let mut step = ExecStep::new(
+            &block.geth_trace.struct_logs[0],
+            0,
+            test_builder.block_ctx.gc,
+            0,
+        );
+        let mut state_ref =
+            test_builder.state_ref(&mut tx, &mut tx_ctx, &mut step);
+        // Add CallContextOp associated to the read of the transaction id.
+        state_ref.push_op(CallContextOp::new(
+            RW::READ,
+            0,
+            CallContextField::TxId,
+            Word::one(),
+        ));
+        // Add StackOp associated to the stack pop.
+        state_ref.push_op(StackOp::new(
+            RW::READ,
+            0,
+            StackAddress::from(1023),
+            Word::from(0x123u32),
+        ));
+        // Add TxAccessListAccountOp associated to the account becoming warm.
+        state_ref.push_op(TxAccessListAccountOp::new(
+            RW::WRITE,
+            1,
+            Address::from_low_u64_be(0x123),
+            true,
+            false,
+        ));
+        // Add AccountCodeHashOp associated to the code hash read of the
+        // empty account.
+        state_ref.push_op(AccountCodeHashOp::new(
+            RW::READ,
+            Address::from_low_u64_be(0x123),
+            H256::zero(),
+            H256::zero(),
+        ));
+        // Add StackOp associated to the stack push of the size of its empty
+        // code.
+        state_ref.push_op(StackOp::new(
+            RW::WRITE,
+            0,
+            StackAddress::from(1023),
+            Word::zero(),
+        ));
+        tx.steps_mut().push(step);
+        test_builder.end_tx(&mut tx, &block.geth_trace);
+        test_builder.block.txs_mut().push(tx);
+
+        assert_eq!(
+            builder.block.txs()[0].steps()[0].bus_mapping_instance,
+            test_builder.block.txs()[0].steps()[0].bus_mapping_instance
+        );
+        assert_eq!(builder.block.container, test_builder.block.container);
+
+        Ok(())
+    }
+
+    #[test]
+    fn extcodesize_out_of_gas() {
+        // The callee doesn't have the gas to access a cold account
+        let code = bytecode! {
+            PUSH1(0x00) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x00) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH1(0x00) // value
+            PUSH2(0x123) // addr
+            PUSH2(0x800) // gas
+            CALL
+            STOP
+        };
+        let callee_code = bytecode! {
+            PUSH2(0x456)
+            EXTCODESIZE
+            STOP
+        };
+        let block =
+            mock::BlockData::new_single_tx_trace_code_2(&code, &callee_code)
+                .unwrap();
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let step = builder.block.txs()[0]
+            .steps()
+            .iter()
+            .find(|step| step.op == OpcodeId::EXTCODESIZE)
+            .unwrap();
+        assert_eq!(step.error, Some(ExecError::OutOfGas(OogError::Constant)));
+
+        // Only the warm status of the account is read after the address,
+        // before the operations that return to the caller
+        assert_eq!(step.bus_mapping_instance[3].target(), Target::CallContext);
+        let op_ref = &step.bus_mapping_instance[2];
+        assert_eq!(op_ref.target(), Target::TxAccessListAccount);
+        let op = builder.block.container.tx_access_list_account
+            [op_ref.as_usize() - 1]
+            .op();
+        assert!(!op.rw().is_write());
+        assert!(!op.value());
+    }
+}
@@ -330,6 +330,11 @@ impl OpcodeId {
         self.as_u8() >= Self::SWAP1.as_u8()
             && self.as_u8() <= Self::SWAP16.as_u8()
     }
+
+    /// Returns `true` if the `OpcodeId` is a `LOGn`.
+    pub fn is_log(&self) -> bool {
+        self.as_u8() >= Self::LOG0.as_u8() && self.as_u8() <= Self::LOG4.as_u8()
+    }
 }
 
 impl OpcodeId {
@@ -0,0 +1,425 @@
+use super::Opcode;
+use crate::circuit_input_builder::{
+    memory_bytes, CircuitInputStateRef, ExecError,
+};
+use crate::eth_types::{GethExecStep, ToWord, Word};
+use crate::{
+    evm::MemoryAddress,
+    operation::{
+        CallContextField, CallContextOp, MemoryOp, StackOp, TxLogField,
+        TxLogOp, RW,
+    },
+    Error,
+};
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the [`OpcodeId::LOG0`](crate::evm::OpcodeId::LOG0) to
+/// [`OpcodeId::LOG4`](crate::evm::OpcodeId::LOG4) `OpcodeId`s, where `N` is
+/// the number of topics.  The address of the current call, the topics and the
+/// data bytes read from memory are written into a new log of the
+/// transaction when the call is persistent, and are dropped when the call or
+/// one of its callers reverts.  A static call fails with
+/// [`ExecError::WriteProtection`], and without enough gas only the memory
+/// offset and the length are read.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Log<const N: usize>;
+
+impl<const N: usize> Opcode for Log<N> {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        steps: &[GethExecStep],
+    ) -> Result<(), Error> {
+        let step = &steps[0];
+        let call = state.call().clone();
+
+        state.push_op(CallContextOp::new(
+            RW::READ,
+            call.call_id,
+            CallContextField::IsStatic,
+            (call.is_static() as u64).into(),
+        ));
+        if call.is_static() {
+            state.step.error = Some(ExecError::WriteProtection);
+            return Ok(());
+        }
+
+        // Without enough gas to expand the memory and log the data, only the
+        // memory offset and the length are read
+        if step.error.is_some() {
+            for i in 0..2 {
+                state.push_op(StackOp::new(
+                    RW::READ,
+                    call.call_id,
+                    step.stack.nth_last_filled(i),
+                    step.stack.nth_last(i)?,
+                ));
+            }
+            state.step.error = state.get_step_err(step, steps.get(1))?;
+            return Ok(());
+        }
+
+        state.push_op(CallContextOp::new(
+            RW::READ,
+            call.call_id,
+            CallContextField::CalleeAddress,
+            call.address.to_word(),
+        ));
+        state.push_op(CallContextOp::new(
+            RW::READ,
+            call.call_id,
+            CallContextField::IsPersistent,
+            (call.is_persistent as u64).into(),
+        ));
+
+        // Stack reads of the memory offset, the length and the topics
+        for i in 0..2 + N {
+            state.push_op(StackOp::new(
+                RW::READ,
+                call.call_id,
+                step.stack.nth_last_filled(i),
+                step.stack.nth_last(i)?,
+            ));
+        }
+
+        // The log only gets an id when it's written
+        let tx_id = state.tx_id();
+        let log_id = if call.is_persistent {
+            let log_id = state.next_log_id();
+            state.push_op(TxLogOp::new(
+                RW::WRITE,
+                tx_id,
+                log_id,
+                TxLogField::Address,
+                0,
+                call.address.to_word(),
+            ));
+            for i in 0..N {
+                state.push_op(TxLogOp::new(
+                    RW::WRITE,
+                    tx_id,
+                    log_id,
+                    TxLogField::Topic,
+                    i,
+                    step.stack.nth_last(2 + i)?,
+                ));
+            }
+            Some(log_id)
+        } else {
+            None
+        };
+
+        // Copy of each data byte, read from the memory of the current call and
+        // written into the log when the call is persistent.
+        let offset = step.stack.nth_last(0)?.low_u64() as usize;
+        let length = step.stack.nth_last(1)?.low_u64() as usize;
+        for (i, byte) in
+            memory_bytes(step, offset, length).into_iter().enumerate()
+        {
+            state.push_op(MemoryOp::new(
+                RW::READ,
+                call.call_id,
+                MemoryAddress::from(offset + i),
+                byte,
+            ));
+            if let Some(log_id) = log_id {
+                state.push_op(TxLogOp::new(
+                    RW::WRITE,
+                    tx_id,
+                    log_id,
+                    TxLogField::Data,
+                    i,
+                    Word::from(byte),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod log_tests {
+    use super::*;
+    use crate::{
+        bytecode,
+        circuit_input_builder::{CircuitInputBuilder, OogError},
+        evm::OpcodeId,
+        mock,
+        operation::Target,
+    };
+    use pretty_assertions::assert_eq;
+
+    fn log_ops(builder: &CircuitInputBuilder) -> Vec<Vec<TxLogOp>> {
+        let container = &builder.block.container;
+        builder.block.txs()[0]
+            .steps()
+            .iter()
+            .filter(|step| step.op.is_log())
+            .map(|step| {
+                step.bus_mapping_instance
+                    .iter()
+                    .filter(|op_ref| op_ref.target() == Target::TxLog)
+                    .map(|op_ref| {
+                        container.tx_log[op_ref.as_usize() - 1].op().clone()
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn log_opcode_impl() {
+        let code = bytecode! {
+            PUSH2(0xaabb)
+            PUSH1(0x00)
+            MSTORE
+            PUSH1(0x01) // topic 1
+            PUSH1(0x02) // topic 0
+            PUSH1(0x02) // length
+            PUSH1(0x1e) // offset
+            LOG2
+            PUSH1(0x00) // length
+            PUSH1(0x00) // offset
+            LOG0
+            STOP
+        };
+
+        // Get the execution steps from the external tracer
+        let block = mock::BlockData::new_single_tx_trace_code(&code).unwrap();
+
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let tx = &builder.block.txs()[0];
+        let (call_id, address) = (tx.calls()[0].call_id, tx.to.to_word());
+        let log = |log_id, field, index, value: u64| {
+            TxLogOp::new(RW::WRITE, 1, log_id, field, index, value.into())
+        };
+        assert_eq!(
+            log_ops(&builder),
+            vec![
+                vec![
+                    TxLogOp::new(
+                        RW::WRITE,
+                        1,
+                        1,
+                        TxLogField::Address,
+                        0,
+                        address
+                    ),
+                    log(1, TxLogField::Topic, 0, 0x02),
+                    log(1, TxLogField::Topic, 1, 0x01),
+                    log(1, TxLogField::Data, 0, 0xaa),
+                    log(1, TxLogField::Data, 1, 0xbb),
+                ],
+                vec![TxLogOp::new(
+                    RW::WRITE,
+                    1,
+                    2,
+                    TxLogField::Address,
+                    0,
+                    address
+                )],
+            ]
+        );
+
+        // The data bytes are read from memory
+        let step = tx
+            .steps()
+            .iter()
+            .find(|step| step.op == OpcodeId::LOG2)
+            .unwrap();
+        let memory_ops = step
+            .bus_mapping_instance
+            .iter()
+            .filter(|op_ref| op_ref.target() == Target::Memory)
+            .map(|op_ref| {
+                builder.block.container.memory[op_ref.as_usize() - 1]
+                    .op()
+                    .clone()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            memory_ops,
+            vec![
+                MemoryOp::new(
+                    RW::READ,
+                    call_id,
+                    MemoryAddress::from(0x1e),
+                    0xaa
+                ),
+                MemoryOp::new(
+                    RW::READ,
+                    call_id,
+                    MemoryAddress::from(0x1f),
+                    0xbb
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn log_write_protection() {
+        let code_a = bytecode! {
+            PUSH1(0x00) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x00) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH2(0x123) // addr
+            PUSH2(0x1000) // gas
+            STATICCALL
+            STOP
+        };
+        let code_b = bytecode! {
+            PUSH1(0x00) // topic 0
+            PUSH1(0x00) // length
+            PUSH1(0x00) // offset
+            LOG1
+            STOP
+        };
+
+        // Get the execution steps from the external tracer
+        let block =
+            mock::BlockData::new_single_tx_trace_code_2(&code_a, &code_b)
+                .unwrap();
+
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let tx = &builder.block.txs()[0];
+        let step = tx
+            .steps()
+            .iter()
+            .find(|step| step.op == OpcodeId::LOG1)
+            .unwrap();
+        assert_eq!(step.error, Some(ExecError::WriteProtection));
+
+        // Only the static flag is read before the callee fails and returns
+        let op_ref = &step.bus_mapping_instance[0];
+        assert_eq!(op_ref.target(), Target::CallContext);
+        let op =
+            builder.block.container.call_context[op_ref.as_usize() - 1].op();
+        assert_eq!(
+            (op.field(), *op.value()),
+            (CallContextField::IsStatic, Word::one())
+        );
+        assert!(step.bus_mapping_instance[1..]
+            .iter()
+            .all(|op_ref| op_ref.target() == Target::CallContext));
+        assert!(builder.block.container.tx_log.is_empty());
+    }
+
+    #[test]
+    fn log_of_reverted_call_is_dropped() {
+        let code_a = bytecode! {
+            PUSH1(0x00) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x00) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH1(0x00) // value
+            PUSH2(0x123) // addr
+            PUSH2(0x1000) // gas
+            CALL
+            PUSH1(0x00) // length
+            PUSH1(0x00) // offset
+            LOG0
+            STOP
+        };
+        let code_b = bytecode! {
+            PUSH1(0x00) // length
+            PUSH1(0x00) // offset
+            LOG0
+            PUSH1(0x00) // length
+            PUSH1(0x00) // offset
+            REVERT
+        };
+
+        // Get the execution steps from the external tracer
+        let block =
+            mock::BlockData::new_single_tx_trace_code_2(&code_a, &code_b)
+                .unwrap();
+
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        // The log of the callee isn't written and takes no id, so the log of
+        // the caller is the first one of the tx
+        let tx = &builder.block.txs()[0];
+        let is_persistent = tx
+            .steps()
+            .iter()
+            .filter(|step| step.op.is_log())
+            .map(|step| {
+                let op_ref = &step.bus_mapping_instance[2];
+                let op = builder.block.container.call_context
+                    [op_ref.as_usize() - 1]
+                    .op();
+                assert_eq!(op.field(), CallContextField::IsPersistent);
+                *op.value()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(is_persistent, vec![Word::zero(), Word::one()]);
+        assert_eq!(
+            log_ops(&builder),
+            vec![
+                vec![],
+                vec![TxLogOp::new(
+                    RW::WRITE,
+                    1,
+                    1,
+                    TxLogField::Address,
+                    0,
+                    tx.to.to_word()
+                )],
+            ]
+        );
+    }
+
+    #[test]
+    fn log_out_of_gas() {
+        // The data would be logged from out of the addressable memory
+        let code = bytecode! {
+            PUSH1(0x00) // topic 0
+            PUSH1(0x01) // length
+            PUSH32(1u64 << 40) // offset
+            LOG1
+            STOP
+        };
+
+        // Get the execution steps from the external tracer
+        let block = mock::BlockData::new_single_tx_trace_code(&code).unwrap();
+
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let step = builder.block.txs()[0]
+            .steps()
+            .iter()
+            .find(|step| step.op == OpcodeId::LOG1)
+            .unwrap();
+        assert_eq!(step.error, Some(ExecError::OutOfGas(OogError::Log)));
+
+        // Only the static flag, the offset and the length are read before
+        // the transfer of the tx value is undone
+        assert_eq!(
+            step.bus_mapping_instance[..3]
+                .iter()
+                .map(|op_ref| op_ref.target())
+                .collect::<Vec<_>>(),
+            vec![Target::CallContext, Target::Stack, Target::Stack]
+        );
+        assert!(builder.block.container.tx_log.is_empty());
+    }
+}
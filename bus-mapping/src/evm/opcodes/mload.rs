@@ -29,7 +29,12 @@ impl Opcode for Mload {
         let stack_position = step.stack.last_filled();
 
         // Manage first stack read at latest stack position
-        state.push_op(StackOp::new(RW::READ, stack_position, stack_value_read));
+        state.push_op(StackOp::new(
+            RW::READ,
+            state.call().call_id,
+            stack_position,
+            stack_value_read,
+        ));
 
         // Read the memory
         let mut mem_read_addr: MemoryAddress = stack_value_read.try_into()?;
@@ -38,14 +43,24 @@ impl Opcode for Mload {
         //
         // First stack write
         //
-        state.push_op(StackOp::new(RW::WRITE, stack_position, mem_read_value));
+        state.push_op(StackOp::new(
+            RW::WRITE,
+            state.call().call_id,
+            stack_position,
+            mem_read_value,
+        ));
 
         //
         // First mem read -> 32 MemoryOp generated.
         //
         let bytes = mem_read_value.to_be_bytes();
         bytes.iter().for_each(|value_byte| {
-            state.push_op(MemoryOp::new(RW::READ, mem_read_addr, *value_byte));
+            state.push_op(MemoryOp::new(
+                RW::READ,
+                state.call().call_id,
+                mem_read_addr,
+                *value_byte,
+            ));
 
             // Update mem_read_addr to next byte's one
             mem_read_addr += MemoryAddress::from(1);
@@ -94,8 +109,9 @@ mod mload_tests {
             block.eth_block,
             block.block_ctants.clone(),
         );
-        let mut tx = Transaction::new(&block.eth_tx);
+        let mut tx = Transaction::new(0, &block.eth_tx);
         let mut tx_ctx = TransactionContext::new(&block.eth_tx);
+        test_builder.begin_tx(&mut tx).unwrap();
 
         // Generate step corresponding to MLOAD
         let mut step = ExecStep::new(
@@ -110,6 +126,7 @@ mod mload_tests {
         // Add StackOp associated to the 0x40 read from the latest Stack pos.
         state_ref.push_op(StackOp::new(
             RW::READ,
+            0,
             StackAddress::from(1023),
             Word::from(0x40),
         ));
@@ -117,6 +134,7 @@ mod mload_tests {
         // Add the last Stack write
         state_ref.push_op(StackOp::new(
             RW::WRITE,
+            0,
             StackAddress::from(1023),
             Word::from(0x80),
         ));
@@ -129,10 +147,16 @@ mod mload_tests {
             .enumerate()
             .map(|(idx, byte)| (idx + 0x40, byte))
             .for_each(|(idx, byte)| {
-                state_ref.push_op(MemoryOp::new(RW::READ, idx.into(), *byte));
+                state_ref.push_op(MemoryOp::new(
+                    RW::READ,
+                    0,
+                    idx.into(),
+                    *byte,
+                ));
             });
 
         tx.steps_mut().push(step);
+        test_builder.end_tx(&mut tx, &block.geth_trace);
         test_builder.block.txs_mut().push(tx);
 
         // Compare first step bus mapping instance
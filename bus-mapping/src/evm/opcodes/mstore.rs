@@ -10,11 +10,12 @@ use core::convert::TryInto;
 
 /// Placeholder structure used to implement [`Opcode`] trait over it
 /// corresponding to the [`OpcodeId::MSTORE`](crate::evm::OpcodeId::MSTORE)
-/// `OpcodeId`.
+/// and [`OpcodeId::MSTORE8`](crate::evm::OpcodeId::MSTORE8) `OpcodeId`s.
+/// MSTORE8 only writes the least significant byte of the value.
 #[derive(Debug, Copy, Clone)]
-pub(crate) struct Mstore;
+pub(crate) struct Mstore<const IS_MSTORE8: bool>;
 
-impl Opcode for Mstore {
+impl<const IS_MSTORE8: bool> Opcode for Mstore<IS_MSTORE8> {
     fn gen_associated_ops(
         state: &mut CircuitInputStateRef,
         steps: &[GethExecStep],
@@ -23,19 +24,35 @@ impl Opcode for Mstore {
         // First stack read (offset)
         let offset = step.stack.nth_last(0)?;
         let offset_pos = step.stack.nth_last_filled(0);
-        state.push_op(StackOp::new(RW::READ, offset_pos, offset));
+        state.push_op(StackOp::new(
+            RW::READ,
+            state.call().call_id,
+            offset_pos,
+            offset,
+        ));
 
         // Second stack read (value)
         let value = step.stack.nth_last(1)?;
         let value_pos = step.stack.nth_last_filled(1);
-        state.push_op(StackOp::new(RW::READ, value_pos, value));
+        state.push_op(StackOp::new(
+            RW::READ,
+            state.call().call_id,
+            value_pos,
+            value,
+        ));
 
-        // First mem write -> 32 MemoryOp generated.
+        // Memory writes -> 32 MemoryOp generated for MSTORE, and 1 for
+        // MSTORE8.
         let offset_addr: MemoryAddress = offset.try_into()?;
-        let bytes = value.to_be_bytes();
+        let bytes = if IS_MSTORE8 {
+            vec![value.low_u64() as u8]
+        } else {
+            value.to_be_bytes().to_vec()
+        };
         for (i, byte) in bytes.iter().enumerate() {
             state.push_op(MemoryOp::new(
                 RW::WRITE,
+                state.call().call_id,
                 offset_addr.map(|a| a + i),
                 *byte,
             ));
@@ -54,8 +71,9 @@ mod mstore_tests {
             CircuitInputBuilder, ExecStep, Transaction, TransactionContext,
         },
         eth_types::Word,
-        evm::{MemoryAddress, StackAddress},
+        evm::{MemoryAddress, OpcodeId, StackAddress},
         mock,
+        operation::Target,
     };
     use pretty_assertions::assert_eq;
 
@@ -84,8 +102,9 @@ mod mstore_tests {
             block.eth_block,
             block.block_ctants.clone(),
         );
-        let mut tx = Transaction::new(&block.eth_tx);
+        let mut tx = Transaction::new(0, &block.eth_tx);
         let mut tx_ctx = TransactionContext::new(&block.eth_tx);
+        test_builder.begin_tx(&mut tx).unwrap();
 
         // Generate step corresponding to MSTORE
         let mut step = ExecStep::new(
@@ -101,11 +120,13 @@ mod mstore_tests {
         // stack position.
         state_ref.push_op(StackOp::new(
             RW::READ,
+            0,
             StackAddress::from(1022),
             Word::from(0x100),
         ));
         state_ref.push_op(StackOp::new(
             RW::READ,
+            0,
             StackAddress::from(1023),
             Word::from(0x1234),
         ));
@@ -115,12 +136,14 @@ mod mstore_tests {
         for (i, byte) in Word::from(0x1234).to_be_bytes().iter().enumerate() {
             state_ref.push_op(MemoryOp::new(
                 RW::WRITE,
+                0,
                 MemoryAddress(0x100 + i),
                 *byte,
             ));
         }
 
         tx.steps_mut().push(step);
+        test_builder.end_tx(&mut tx, &block.geth_trace);
         test_builder.block.txs_mut().push(tx);
 
         // Compare first step bus mapping instance
@@ -132,6 +155,58 @@ mod mstore_tests {
         // Compare containers
         assert_eq!(builder.block.container, test_builder.block.container);
 
+        // The memory is expanded to 0x120 bytes
+        assert_eq!(builder.block.txs()[0].steps()[0].memory_size, 9);
+
         Ok(())
     }
+
+    #[test]
+    fn mstore8_opcode_impl() {
+        let code = bytecode! {
+            PUSH2(0x1234)
+            PUSH2(0x100)
+            MSTORE8
+            STOP
+        };
+
+        // Get the execution steps from the external tracer
+        let block = mock::BlockData::new_single_tx_trace_code(&code).unwrap();
+
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let tx = &builder.block.txs()[0];
+        let step = tx
+            .steps()
+            .iter()
+            .find(|step| step.op == OpcodeId::MSTORE8)
+            .unwrap();
+
+        // Only the least significant byte is written, which expands the
+        // memory to 0x101 bytes
+        let memory_ops = step
+            .bus_mapping_instance
+            .iter()
+            .filter(|op_ref| op_ref.target() == Target::Memory)
+            .map(|op_ref| {
+                builder.block.container.memory[op_ref.as_usize() - 1]
+                    .op()
+                    .clone()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            memory_ops,
+            vec![MemoryOp::new(
+                RW::WRITE,
+                tx.calls()[0].call_id,
+                MemoryAddress(0x100),
+                0x34
+            )]
+        );
+        assert_eq!(step.memory_size, 9);
+    }
 }
@@ -8,6 +8,9 @@ use crate::{
 
 /// Placeholder structure used to implement [`Opcode`] trait over it
 /// corresponding to the [`OpcodeId::PC`](crate::evm::OpcodeId::PC) `OpcodeId`.
+/// It's also used by [`OpcodeId::MSIZE`](crate::evm::OpcodeId::MSIZE) and
+/// [`OpcodeId::GAS`](crate::evm::OpcodeId::GAS), which push a value of the
+/// step state as well.
 #[derive(Debug, Copy, Clone)]
 pub(crate) struct Pc;
 
@@ -21,6 +24,7 @@ impl Opcode for Pc {
         let value = steps[1].stack.last()?;
         state.push_op(StackOp::new(
             RW::WRITE,
+            state.call().call_id,
             step.stack.last_filled().map(|a| a - 1),
             value,
         ));
@@ -67,8 +71,9 @@ mod pc_tests {
             block.eth_block,
             block.block_ctants.clone(),
         );
-        let mut tx = Transaction::new(&block.eth_tx);
+        let mut tx = Transaction::new(0, &block.eth_tx);
         let mut tx_ctx = TransactionContext::new(&block.eth_tx);
+        test_builder.begin_tx(&mut tx).unwrap();
 
         // Generate step corresponding to MLOAD
         let mut step = ExecStep::new(
@@ -83,11 +88,13 @@ mod pc_tests {
         // Add the last Stack write
         state_ref.push_op(StackOp::new(
             RW::WRITE,
+            0,
             StackAddress::from(1024 - 3),
             Word::from(0x4),
         ));
 
         tx.steps_mut().push(step);
+        test_builder.end_tx(&mut tx, &block.geth_trace);
         test_builder.block.txs_mut().push(tx);
 
         // Compare first step bus mapping instance
@@ -22,6 +22,7 @@ impl<const N: usize> Opcode for Push<N> {
         let step = &steps[0];
         state.push_op(StackOp::new(
             RW::WRITE,
+            state.call().call_id,
             // Get the value and addr from the next step. Being the last
             // position filled with an element in the stack
             step.stack.last_filled().map(|a| a - 1),
@@ -69,8 +70,9 @@ mod push_tests {
             block.eth_block,
             block.block_ctants.clone(),
         );
-        let mut tx = Transaction::new(&block.eth_tx);
+        let mut tx = Transaction::new(0, &block.eth_tx);
         let mut tx_ctx = TransactionContext::new(&block.eth_tx);
+        test_builder.begin_tx(&mut tx).unwrap();
 
         // Generate steps corresponding to PUSH1 80, PUSH2 1234,
         // PUSH16 0x00112233445566778899aabbccddeeff
@@ -94,12 +96,14 @@ mod push_tests {
             // Add StackOp associated to the push at the latest Stack pos.
             state_ref.push_op(StackOp::new(
                 RW::WRITE,
+                0,
                 StackAddress::from(1023 - i),
                 *word,
             ));
             tx.steps_mut().push(step);
         }
 
+        test_builder.end_tx(&mut tx, &block.geth_trace);
         test_builder.block.txs_mut().push(tx);
 
         // Compare first 3 steps bus mapping instance
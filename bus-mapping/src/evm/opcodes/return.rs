@@ -0,0 +1,579 @@
+use super::Opcode;
+use crate::circuit_input_builder::{memory_bytes, CircuitInputStateRef};
+use crate::eth_types::GethExecStep;
+use crate::{
+    evm::MemoryAddress,
+    operation::{
+        AccountCodeHashOp, CallContextField, CallContextOp, MemoryOp, StackOp,
+        RW,
+    },
+    Error,
+};
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the [`OpcodeId::RETURN`](crate::evm::OpcodeId::RETURN)
+/// and [`OpcodeId::REVERT`](crate::evm::OpcodeId::REVERT) `OpcodeId`s.  The
+/// returned bytes of an internal call are copied into the memory of the
+/// caller, up to the length of the region the caller reserved for them.  The
+/// RETURN of a contract creation deploys the returned bytes as the code of the
+/// created contract, and REVERT makes the call non-persistent.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Return<const IS_REVERT: bool>;
+
+impl<const IS_REVERT: bool> Opcode for Return<IS_REVERT> {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        steps: &[GethExecStep],
+    ) -> Result<(), Error> {
+        let step = &steps[0];
+        let call = state.call().clone();
+
+        // Stack reads of the offset and length of the returned memory
+        for i in 0..2 {
+            state.push_op(StackOp::new(
+                RW::READ,
+                call.call_id,
+                step.stack.nth_last_filled(i),
+                step.stack.nth_last(i)?,
+            ));
+        }
+        let offset = step.stack.nth_last(0)?.low_u64() as usize;
+        let length = step.stack.nth_last(1)?.low_u64() as usize;
+
+        // Call context reads of the depth, which is 1 when the root call ends
+        // the tx, and of the caller id
+        for (field, value) in [
+            (CallContextField::Depth, call.depth),
+            (CallContextField::CallerId, call.caller_id),
+        ] {
+            state.push_op(CallContextOp::new(
+                RW::READ,
+                call.call_id,
+                field,
+                value.into(),
+            ));
+        }
+
+        if call.is_create() && !IS_REVERT {
+            state.step.error = state.get_step_err(step, steps.get(1))?;
+        }
+
+        // Call context reads of the memory region of the caller receiving the
+        // returned bytes, and copy of each byte, read from the memory of the
+        // current call and written into the memory of the caller.
+        if !call.is_root() {
+            for (field, value) in [
+                (CallContextField::ReturnDataOffset, call.return_data_offset),
+                (CallContextField::ReturnDataLength, call.return_data_length),
+            ] {
+                state.push_op(CallContextOp::new(
+                    RW::READ,
+                    call.call_id,
+                    field,
+                    value.into(),
+                ));
+            }
+
+            let num_bytes = length.min(call.return_data_length as usize);
+            let bytes = memory_bytes(step, offset, num_bytes);
+            for (i, byte) in bytes.into_iter().enumerate() {
+                state.push_op(MemoryOp::new(
+                    RW::READ,
+                    call.call_id,
+                    MemoryAddress::from(offset + i),
+                    byte,
+                ));
+                state.push_op(MemoryOp::new(
+                    RW::WRITE,
+                    call.caller_id,
+                    MemoryAddress::from(call.return_data_offset as usize + i),
+                    byte,
+                ));
+            }
+        }
+
+        if IS_REVERT {
+            state.push_op(CallContextOp::new(
+                RW::WRITE,
+                call.call_id,
+                CallContextField::IsPersistent,
+                0.into(),
+            ));
+        } else if call.is_create() && state.step.error.is_none() {
+            // The memory is expanded with zeros up to the returned bytes
+            let code = memory_bytes(step, offset, length);
+            let code_hash = state.block.insert_code(code);
+            let code_hash_prev = std::mem::replace(
+                &mut state.sdb.get_account_mut(&call.address).1.codeHash,
+                code_hash,
+            );
+            state.push_op(AccountCodeHashOp::new(
+                RW::WRITE,
+                call.address,
+                code_hash,
+                code_hash_prev,
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod return_tests {
+    use super::*;
+    use crate::{
+        bytecode,
+        circuit_input_builder::{CircuitInputBuilder, ExecError},
+        eth_types::{Address, Word, H256},
+        evm::OpcodeId,
+        exec_trace::OperationRef,
+        mock,
+        operation::{
+            StorageOp, Target, TxAccessListAccountOp,
+            TxAccessListAccountStorageOp, TxRefundOp,
+        },
+        state_db::Account,
+    };
+    use ethers_core::utils::{get_contract_address, keccak256};
+    use pretty_assertions::assert_eq;
+    use std::collections::HashMap;
+
+    fn create_and_return(deployed_code: &[u8]) -> CircuitInputBuilder {
+        // The init code returns the `deployed_code`, which is stored at the end
+        // of the first word of memory.
+        let length = deployed_code.len();
+        let init_code = bytecode! {
+            .push(length, Word::from_big_endian(deployed_code))
+            PUSH1(0x00)
+            MSTORE
+            PUSH1(length) // length
+            PUSH1(32 - length) // offset
+            RETURN
+        }
+        .to_bytes();
+        let mut init_code_word = [0u8; 32];
+        init_code_word[..init_code.len()].copy_from_slice(&init_code);
+
+        let code = bytecode! {
+            PUSH32(Word::from_big_endian(&init_code_word))
+            PUSH1(0x00)
+            MSTORE
+            PUSH1(init_code.len()) // length
+            PUSH1(0x00) // offset
+            PUSH1(0x00) // value
+            CREATE
+            STOP
+        };
+
+        // Get the execution steps from the external tracer
+        let block = mock::BlockData::new_single_tx_trace_code(&code).unwrap();
+
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.sdb.set_account(
+            &Address::zero(),
+            Account {
+                nonce: Word::zero(),
+                balance: Word::from(555u64), /* same value as in
+                                              * `mock::new_tracer_account` */
+                storage: HashMap::new(),
+                codeHash: H256::zero(),
+            },
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+        builder
+    }
+
+    fn return_step_error(builder: &CircuitInputBuilder) -> Option<&ExecError> {
+        builder.block.txs()[0]
+            .steps()
+            .iter()
+            .find(|step| step.op == OpcodeId::RETURN)
+            .unwrap()
+            .error
+            .as_ref()
+    }
+
+    #[test]
+    fn return_deploys_code() {
+        let deployed_code = [OpcodeId::STOP.as_u8(); 2];
+        let builder = create_and_return(&deployed_code);
+
+        assert_eq!(return_step_error(&builder), None);
+        let code_hash = H256(keccak256(&deployed_code));
+        assert_eq!(builder.block.code(&code_hash), Some(&deployed_code[..]));
+        let address = get_contract_address(Address::zero(), Word::zero());
+        assert_eq!(builder.sdb.get_account(&address).1.codeHash, code_hash);
+    }
+
+    #[test]
+    fn return_invalid_code() {
+        let builder = create_and_return(&[0xef]);
+
+        assert_eq!(return_step_error(&builder), Some(&ExecError::InvalidCode));
+        let code_hash = H256(keccak256(&[0xef]));
+        assert_eq!(builder.block.code(&code_hash), None);
+    }
+
+    fn call_and_return(op: OpcodeId) -> CircuitInputBuilder {
+        // The callee returns 4 bytes, of which the caller keeps 2 at the
+        // offset 0x20 of its memory
+        let code_a = bytecode! {
+            PUSH1(0x02) // retLength
+            PUSH1(0x20) // retOffset
+            PUSH1(0x00) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH1(0x00) // value
+            PUSH2(0x123) // addr
+            PUSH2(0x1000) // gas
+            CALL
+            STOP
+        };
+        let mut code_b = bytecode! {
+            PUSH4(0xaabbccddu64)
+            PUSH1(0x00)
+            MSTORE
+            PUSH1(0x04) // length
+            PUSH1(0x1c) // offset
+        };
+        code_b.write_op(op);
+
+        // Get the execution steps from the external tracer
+        let block =
+            mock::BlockData::new_single_tx_trace_code_2(&code_a, &code_b)
+                .unwrap();
+
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+        builder
+    }
+
+    fn return_step_ops(
+        builder: &CircuitInputBuilder,
+        op: OpcodeId,
+    ) -> (usize, Vec<OperationRef>) {
+        let tx = &builder.block.txs()[0];
+        let step = tx.steps().iter().find(|step| step.op == op).unwrap();
+        (
+            tx.calls()[step.call_index].call_id,
+            step.bus_mapping_instance.clone(),
+        )
+    }
+
+    #[test]
+    fn return_copies_into_caller_memory() {
+        for op in [OpcodeId::RETURN, OpcodeId::REVERT] {
+            let builder = call_and_return(op);
+            let (callee_id, ops) = return_step_ops(&builder, op);
+            let container = &builder.block.container;
+
+            // The first 2 returned bytes are read from the memory of the
+            // callee and written into the memory region of the caller
+            // receiving them
+            assert_eq!(
+                ops[6..10]
+                    .iter()
+                    .map(|op_ref| container.memory[op_ref.as_usize() - 1]
+                        .op()
+                        .clone())
+                    .collect::<Vec<_>>(),
+                vec![
+                    MemoryOp::new(
+                        RW::READ,
+                        callee_id,
+                        MemoryAddress::from(0x1c),
+                        0xaa
+                    ),
+                    MemoryOp::new(
+                        RW::WRITE,
+                        0,
+                        MemoryAddress::from(0x20),
+                        0xaa
+                    ),
+                    MemoryOp::new(
+                        RW::READ,
+                        callee_id,
+                        MemoryAddress::from(0x1d),
+                        0xbb
+                    ),
+                    MemoryOp::new(
+                        RW::WRITE,
+                        0,
+                        MemoryAddress::from(0x21),
+                        0xbb
+                    ),
+                ]
+            );
+
+            // All the returned bytes are the return data of the last callee
+            // of the caller
+            let call_context_ops = ops[ops.len() - 3..]
+                .iter()
+                .map(|op_ref| {
+                    let op = container.call_context[op_ref.as_usize() - 1].op();
+                    (op.field(), *op.value())
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(
+                call_context_ops,
+                vec![
+                    (CallContextField::LastCalleeId, callee_id.into()),
+                    (
+                        CallContextField::LastCalleeReturnDataOffset,
+                        Word::from(0x1c)
+                    ),
+                    (
+                        CallContextField::LastCalleeReturnDataLength,
+                        Word::from(4)
+                    ),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn revert_marks_call_non_persistent() {
+        for (op, is_persistent) in
+            [(OpcodeId::RETURN, true), (OpcodeId::REVERT, false)]
+        {
+            let builder = call_and_return(op);
+            let tx = &builder.block.txs()[0];
+            assert!(tx.calls()[0].is_persistent);
+            assert_eq!(tx.calls()[1].is_persistent, is_persistent);
+
+            let (callee_id, ops) = return_step_ops(&builder, op);
+            let is_persistent_write = CallContextOp::new(
+                RW::WRITE,
+                callee_id,
+                CallContextField::IsPersistent,
+                Word::zero(),
+            );
+            assert_eq!(
+                ops.iter()
+                    .any(|op_ref| op_ref.target() == Target::CallContext
+                        && builder.block.container.call_context
+                            [op_ref.as_usize() - 1]
+                            .op()
+                            == &is_persistent_write),
+                !is_persistent
+            );
+        }
+    }
+
+    #[test]
+    fn return_ends_root_call() {
+        for op in [OpcodeId::RETURN, OpcodeId::REVERT] {
+            let mut code = bytecode! {
+                PUSH1(0x02) // length
+                PUSH1(0x00) // offset
+            };
+            code.write_op(op);
+            let block =
+                mock::BlockData::new_single_tx_trace_code(&code).unwrap();
+            let mut builder = CircuitInputBuilder::new(
+                block.eth_block.clone(),
+                block.block_ctants.clone(),
+            );
+            builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+            // The root call has no caller receiving the returned bytes, so
+            // the stack reads are only followed by the reads of its depth and
+            // of its caller id, and by the non-persistent write of REVERT and
+            // the undoing of the transfer of the tx value
+            let (call_id, ops) = return_step_ops(&builder, op);
+            let container = &builder.block.container;
+            let reversion = [
+                Target::CallContext,
+                Target::AccountBalance,
+                Target::AccountBalance,
+            ];
+            assert_eq!(
+                ops.iter().map(|op_ref| op_ref.target()).collect::<Vec<_>>(),
+                [
+                    Target::Stack,
+                    Target::Stack,
+                    Target::CallContext,
+                    Target::CallContext
+                ]
+                .into_iter()
+                .chain(reversion.into_iter().filter(|_| op == OpcodeId::REVERT))
+                .collect::<Vec<_>>()
+            );
+            assert_eq!(
+                ops[2..4]
+                    .iter()
+                    .map(|op_ref| container.call_context[op_ref.as_usize() - 1]
+                        .op()
+                        .clone())
+                    .collect::<Vec<_>>(),
+                vec![
+                    CallContextOp::new(
+                        RW::READ,
+                        call_id,
+                        CallContextField::Depth,
+                        Word::one()
+                    ),
+                    CallContextOp::new(
+                        RW::READ,
+                        call_id,
+                        CallContextField::CallerId,
+                        Word::zero()
+                    ),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn revert_undoes_state_writes() {
+        let code_a = bytecode! {
+            PUSH1(0x00) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x00) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH1(0x00) // value
+            PUSH2(0x123) // addr
+            PUSH3(0x10000) // gas
+            CALL
+            STOP
+        };
+        let code_b = bytecode! {
+            PUSH1(0x2a) // value
+            PUSH1(0x01) // key
+            SSTORE
+            PUSH1(0x00) // length
+            PUSH1(0x00) // offset
+            REVERT
+        };
+
+        // Get the execution steps from the external tracer
+        let block =
+            mock::BlockData::new_single_tx_trace_code_2(&code_a, &code_b)
+                .unwrap();
+
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let tx = &builder.block.txs()[0];
+        let step = |op| tx.steps().iter().find(|step| step.op == op).unwrap();
+        assert!(step(OpcodeId::SSTORE).reverted);
+        assert!(!step(OpcodeId::STOP).reverted);
+
+        // The storage write, the access list write and the refund write of
+        // SSTORE are undone in reverse order after the REVERT
+        let container = &builder.block.container;
+        let address = Address::from_low_u64_be(0x123);
+        let ops = &step(OpcodeId::REVERT).bus_mapping_instance[7..10];
+        assert_eq!(
+            ops.iter().map(|op_ref| op_ref.target()).collect::<Vec<_>>(),
+            vec![
+                Target::TxRefund,
+                Target::TxAccessListAccountStorage,
+                Target::Storage
+            ]
+        );
+        let refund_op = &container.tx_refund[ops[0].as_usize() - 1];
+        assert!(refund_op.revert());
+        assert_eq!(refund_op.op(), &TxRefundOp::new(RW::WRITE, 1, 0, 0));
+        let access_list_op =
+            &container.tx_access_list_account_storage[ops[1].as_usize() - 1];
+        assert!(access_list_op.revert());
+        assert_eq!(
+            access_list_op.op(),
+            &TxAccessListAccountStorageOp::new(
+                RW::WRITE,
+                1,
+                address,
+                Word::one(),
+                false,
+                true
+            )
+        );
+        let storage_op = &container.storage[ops[2].as_usize() - 1];
+        assert!(storage_op.revert());
+        assert_eq!(
+            storage_op.op(),
+            &StorageOp::new(
+                RW::WRITE,
+                address,
+                Word::one(),
+                Word::zero(),
+                Word::from(0x2a),
+                1,
+                Word::zero()
+            )
+        );
+        assert_eq!(
+            builder.sdb.get_storage(&address, &Word::one()).1,
+            &Word::zero()
+        );
+    }
+
+    #[test]
+    fn revert_removes_accounts_from_access_list() {
+        let code_a = bytecode! {
+            PUSH1(0x00) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x00) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH1(0x00) // value
+            PUSH2(0x123) // addr
+            PUSH3(0x10000) // gas
+            CALL
+            STOP
+        };
+        let code_b = bytecode! {
+            PUSH2(0x456) // address
+            BALANCE
+            POP
+            PUSH1(0x00) // length
+            PUSH1(0x00) // offset
+            REVERT
+        };
+
+        // Get the execution steps from the external tracer
+        let block =
+            mock::BlockData::new_single_tx_trace_code_2(&code_a, &code_b)
+                .unwrap();
+
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        // The account made warm by BALANCE is cold again after the REVERT
+        let tx = &builder.block.txs()[0];
+        let step = tx
+            .steps()
+            .iter()
+            .find(|step| step.op == OpcodeId::REVERT)
+            .unwrap();
+        let address = Address::from_low_u64_be(0x456);
+        let access_list_ops = step
+            .bus_mapping_instance
+            .iter()
+            .filter(|op_ref| op_ref.target() == Target::TxAccessListAccount)
+            .map(|op_ref| {
+                &builder.block.container.tx_access_list_account
+                    [op_ref.as_usize() - 1]
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(access_list_ops.len(), 1);
+        assert!(access_list_ops[0].revert());
+        assert_eq!(
+            access_list_ops[0].op(),
+            &TxAccessListAccountOp::new(RW::WRITE, 1, address, false, true)
+        );
+        assert!(!builder.sdb.check_account_in_access_list(&address));
+    }
+}
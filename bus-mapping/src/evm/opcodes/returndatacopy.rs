@@ -0,0 +1,286 @@
+use super::Opcode;
+use crate::circuit_input_builder::{CircuitInputStateRef, ExecError};
+use crate::eth_types::{GethExecStep, Word};
+use crate::{
+    evm::MemoryAddress,
+    operation::{CallContextField, CallContextOp, MemoryOp, StackOp, RW},
+    Error,
+};
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the
+/// [`OpcodeId::RETURNDATACOPY`](crate::evm::OpcodeId::RETURNDATACOPY)
+/// `OpcodeId`.  Copying bytes past the end of the return data of the last
+/// callee fails with [`ExecError::ReturnDataOutOfBounds`].
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Returndatacopy;
+
+impl Opcode for Returndatacopy {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        steps: &[GethExecStep],
+    ) -> Result<(), Error> {
+        let step = &steps[0];
+        let call_id = state.call().call_id;
+
+        // Stack reads of the memory offset, the return data offset and the
+        // length
+        for i in 0..3 {
+            state.push_op(StackOp::new(
+                RW::READ,
+                call_id,
+                step.stack.nth_last_filled(i),
+                step.stack.nth_last(i)?,
+            ));
+        }
+
+        // Without enough gas to expand the memory and copy the bytes, which
+        // is checked before the bounds of the return data, only the arguments
+        // are read
+        if step.error.is_some() {
+            let error = state.get_step_err(step, steps.get(1))?;
+            if let Some(ExecError::OutOfGas(_)) = error {
+                state.step.error = error;
+                return Ok(());
+            }
+        }
+
+        // Call context reads of where the return data of the last callee is
+        let (callee_id, return_data_offset, return_data) = {
+            let call_ctx = state.call_ctx();
+            (
+                call_ctx.last_callee_id,
+                call_ctx.last_callee_return_data_offset,
+                call_ctx.last_callee_return_data.clone(),
+            )
+        };
+        for (field, value) in [
+            (CallContextField::LastCalleeId, callee_id.into()),
+            (
+                CallContextField::LastCalleeReturnDataOffset,
+                return_data_offset.into(),
+            ),
+            (
+                CallContextField::LastCalleeReturnDataLength,
+                return_data.len().into(),
+            ),
+        ] {
+            state.push_op(CallContextOp::new(RW::READ, call_id, field, value));
+        }
+
+        let data_offset = step.stack.nth_last(1)?;
+        let length = step.stack.nth_last(2)?;
+        let is_out_of_bounds = match data_offset.checked_add(length) {
+            Some(end) => end > Word::from(return_data.len()),
+            None => true,
+        };
+        if is_out_of_bounds {
+            state.step.error = Some(ExecError::ReturnDataOutOfBounds);
+            return Ok(());
+        }
+
+        // Copy of each byte, read from the memory of the last callee and
+        // written into the memory of the current call.
+        let memory_offset = step.stack.nth_last(0)?.as_usize();
+        let data_offset = data_offset.as_usize();
+        for (i, byte) in return_data
+            [data_offset..data_offset + length.as_usize()]
+            .iter()
+            .enumerate()
+        {
+            state.push_op(MemoryOp::new(
+                RW::READ,
+                callee_id,
+                MemoryAddress::from(
+                    return_data_offset as usize + data_offset + i,
+                ),
+                *byte,
+            ));
+            state.push_op(MemoryOp::new(
+                RW::WRITE,
+                call_id,
+                MemoryAddress::from(memory_offset + i),
+                *byte,
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod returndatacopy_tests {
+    use super::*;
+    use crate::{
+        bytecode,
+        circuit_input_builder::{CircuitInputBuilder, OogError},
+        evm::OpcodeId,
+        mock,
+        operation::Target,
+    };
+    use pretty_assertions::assert_eq;
+
+    fn returndatacopy_builder(
+        length: u64,
+        dest_offset: u64,
+    ) -> CircuitInputBuilder {
+        // The callee returns the bytes 0xaa, 0xbb and 0xcc stored at the
+        // offset 0x1d of its memory, and the caller copies `length` of them
+        // from the second one on to `dest_offset`.
+        let code_a = bytecode! {
+            PUSH1(0x00) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x00) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH1(0x00) // value
+            PUSH2(0x123) // addr
+            PUSH2(0x1000) // gas
+            CALL
+            PUSH1(length) // length
+            PUSH1(0x01) // offset
+            PUSH32(dest_offset) // destOffset
+            RETURNDATACOPY
+            STOP
+        };
+        let code_b = bytecode! {
+            PUSH3(0xaabbcc)
+            PUSH1(0x00)
+            MSTORE
+            PUSH1(0x03) // length
+            PUSH1(0x1d) // offset
+            RETURN
+        };
+
+        // Get the execution steps from the external tracer
+        let block =
+            mock::BlockData::new_single_tx_trace_code_2(&code_a, &code_b)
+                .unwrap();
+
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+        builder
+    }
+
+    #[test]
+    fn returndatacopy_opcode_impl() -> Result<(), Error> {
+        let builder = returndatacopy_builder(2, 0x40);
+
+        let tx = &builder.block.txs()[0];
+        let step = tx
+            .steps()
+            .iter()
+            .find(|step| step.op == OpcodeId::RETURNDATACOPY)
+            .unwrap();
+        let callee_id = tx.calls()[1].call_id;
+        let container = &builder.block.container;
+        assert_eq!(step.error, None);
+
+        // Call context reads of the return data of the callee
+        assert_eq!(
+            step.bus_mapping_instance[3..6]
+                .iter()
+                .map(|op_ref| container.call_context[op_ref.as_usize() - 1]
+                    .op()
+                    .clone())
+                .collect::<Vec<_>>(),
+            vec![
+                CallContextOp::new(
+                    RW::READ,
+                    0,
+                    CallContextField::LastCalleeId,
+                    callee_id.into()
+                ),
+                CallContextOp::new(
+                    RW::READ,
+                    0,
+                    CallContextField::LastCalleeReturnDataOffset,
+                    Word::from(0x1d)
+                ),
+                CallContextOp::new(
+                    RW::READ,
+                    0,
+                    CallContextField::LastCalleeReturnDataLength,
+                    Word::from(3)
+                ),
+            ]
+        );
+
+        // The last 2 bytes of the return data are read from the memory of
+        // the callee and written into the memory of the caller.
+        assert_eq!(
+            step.bus_mapping_instance[6..]
+                .iter()
+                .map(|op_ref| container.memory[op_ref.as_usize() - 1]
+                    .op()
+                    .clone())
+                .collect::<Vec<_>>(),
+            vec![
+                MemoryOp::new(
+                    RW::READ,
+                    callee_id,
+                    MemoryAddress::from(0x1e),
+                    0xbb
+                ),
+                MemoryOp::new(RW::WRITE, 0, MemoryAddress::from(0x40), 0xbb),
+                MemoryOp::new(
+                    RW::READ,
+                    callee_id,
+                    MemoryAddress::from(0x1f),
+                    0xcc
+                ),
+                MemoryOp::new(RW::WRITE, 0, MemoryAddress::from(0x41), 0xcc),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn returndatacopy_out_of_bounds() {
+        let builder = returndatacopy_builder(3, 0x40);
+
+        let step = builder.block.txs()[0]
+            .steps()
+            .iter()
+            .find(|step| step.op == OpcodeId::RETURNDATACOPY)
+            .unwrap();
+        assert_eq!(step.error, Some(ExecError::ReturnDataOutOfBounds));
+        assert_eq!(step.bus_mapping_instance.len(), 6);
+    }
+
+    #[test]
+    fn returndatacopy_out_of_gas() {
+        // The bytes would be copied out of the addressable memory, and out of
+        // the bounds of the return data, which is checked after the gas
+        let builder = returndatacopy_builder(3, 1 << 40);
+
+        let step = builder.block.txs()[0]
+            .steps()
+            .iter()
+            .find(|step| step.op == OpcodeId::RETURNDATACOPY)
+            .unwrap();
+        assert_eq!(
+            step.error,
+            Some(ExecError::OutOfGas(OogError::ReturnDataCopy))
+        );
+        // Only the arguments are read, and not the return data
+        assert!(step.bus_mapping_instance[..3]
+            .iter()
+            .all(|op_ref| op_ref.target() == Target::Stack));
+        assert!(step.bus_mapping_instance.iter().all(|op_ref| {
+            match op_ref.target() {
+                Target::Memory => false,
+                Target::CallContext => {
+                    builder.block.container.call_context[op_ref.as_usize() - 1]
+                        .op()
+                        .field()
+                        != CallContextField::LastCalleeId
+                }
+                _ => true,
+            }
+        }));
+    }
+}
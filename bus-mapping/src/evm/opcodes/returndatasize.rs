@@ -0,0 +1,121 @@
+use super::Opcode;
+use crate::circuit_input_builder::CircuitInputStateRef;
+use crate::eth_types::GethExecStep;
+use crate::{
+    operation::{CallContextField, CallContextOp, StackOp, RW},
+    Error,
+};
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the
+/// [`OpcodeId::RETURNDATASIZE`](crate::evm::OpcodeId::RETURNDATASIZE)
+/// `OpcodeId`.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Returndatasize;
+
+impl Opcode for Returndatasize {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        steps: &[GethExecStep],
+    ) -> Result<(), Error> {
+        let step = &steps[0];
+        let call_id = state.call().call_id;
+
+        // Call context read of the length of the return data of the last
+        // callee
+        let return_data_length =
+            state.call_ctx().last_callee_return_data.len().into();
+        state.push_op(CallContextOp::new(
+            RW::READ,
+            call_id,
+            CallContextField::LastCalleeReturnDataLength,
+            return_data_length,
+        ));
+
+        // Stack write of the length
+        state.push_op(StackOp::new(
+            RW::WRITE,
+            call_id,
+            step.stack.last_filled().map(|a| a - 1),
+            return_data_length,
+        ));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod returndatasize_tests {
+    use super::*;
+    use crate::{
+        bytecode,
+        circuit_input_builder::CircuitInputBuilder,
+        eth_types::Word,
+        evm::{OpcodeId, StackAddress},
+        mock,
+    };
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn returndatasize_opcode_impl() -> Result<(), Error> {
+        // The callee returns 3 bytes, while the caller only keeps 2 of them
+        // in its memory
+        let code_a = bytecode! {
+            PUSH1(0x02) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x00) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH1(0x00) // value
+            PUSH2(0x123) // addr
+            PUSH2(0x1000) // gas
+            CALL
+            RETURNDATASIZE
+            STOP
+        };
+        let code_b = bytecode! {
+            PUSH1(0x03) // length
+            PUSH1(0x00) // offset
+            RETURN
+        };
+
+        // Get the execution steps from the external tracer
+        let block =
+            mock::BlockData::new_single_tx_trace_code_2(&code_a, &code_b)
+                .unwrap();
+
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let tx = &builder.block.txs()[0];
+        let step = tx
+            .steps()
+            .iter()
+            .find(|step| step.op == OpcodeId::RETURNDATASIZE)
+            .unwrap();
+        let container = &builder.block.container;
+        assert_eq!(
+            container.call_context[step.bus_mapping_instance[0].as_usize() - 1]
+                .op(),
+            &CallContextOp::new(
+                RW::READ,
+                0,
+                CallContextField::LastCalleeReturnDataLength,
+                Word::from(3),
+            )
+        );
+        assert_eq!(
+            container.stack[step.bus_mapping_instance[1].as_usize() - 1].op(),
+            &StackOp::new(
+                RW::WRITE,
+                0,
+                StackAddress::from(1022),
+                Word::from(3),
+            )
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,137 @@
+use super::Opcode;
+use crate::circuit_input_builder::CircuitInputStateRef;
+use crate::eth_types::{GethExecStep, ToWord};
+use crate::{
+    operation::{
+        AccountBalanceOp, CallContextField, CallContextOp, StackOp, RW,
+    },
+    Error,
+};
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the
+/// [`OpcodeId::SELFBALANCE`](crate::evm::OpcodeId::SELFBALANCE) `OpcodeId`.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Selfbalance;
+
+impl Opcode for Selfbalance {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        steps: &[GethExecStep],
+    ) -> Result<(), Error> {
+        let step = &steps[0];
+
+        // Call context read of the address of the current account
+        let address = state.call().address;
+        state.push_op(CallContextOp::new(
+            RW::READ,
+            state.call().call_id,
+            CallContextField::CalleeAddress,
+            address.to_word(),
+        ));
+
+        // Account balance read of the current account
+        let balance = steps[1].stack.last()?;
+        state.push_op(AccountBalanceOp::new(
+            RW::READ,
+            address,
+            balance,
+            balance,
+        ));
+
+        // Stack write of the balance
+        state.push_op(StackOp::new(
+            RW::WRITE,
+            state.call().call_id,
+            step.stack.last_filled().map(|a| a - 1),
+            balance,
+        ));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod selfbalance_tests {
+    use super::*;
+    use crate::{
+        bytecode,
+        circuit_input_builder::{
+            CircuitInputBuilder, ExecStep, Transaction, TransactionContext,
+        },
+        eth_types::{Address, Word},
+        evm::StackAddress,
+        mock,
+    };
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn selfbalance_opcode_impl() -> Result<(), Error> {
+        let code = bytecode! {
+            #[start]
+            SELFBALANCE
+            STOP
+        };
+
+        // Get the execution steps from the external tracer
+        let block =
+            mock::BlockData::new_single_tx_trace_code_at_start(&code).unwrap();
+
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let mut test_builder = CircuitInputBuilder::new(
+            block.eth_block,
+            block.block_ctants.clone(),
+        );
+        let mut tx = Transaction::new(0, &block.eth_tx);
+        let mut tx_ctx = TransactionContext::new(&block.eth_tx);
+        test_builder.begin_tx(&mut tx).unwrap();
+
+        // Generate step corresponding to SELFBALANCE
+        let mut step = ExecStep::new(
+            &block.geth_trace.struct_logs[0],
+            0,
+            test_builder.block_ctx.gc,
+            0,
+        );
+        let mut state_ref =
+            test_builder.state_ref(&mut tx, &mut tx_ctx, &mut step);
+        // Add CallContextOp associated to the address read.
+        state_ref.push_op(CallContextOp::new(
+            RW::READ,
+            0,
+            CallContextField::CalleeAddress,
+            Word::zero(),
+        ));
+        // Add AccountBalanceOp associated to the balance read.  The balance
+        // is the one given to the account by `mock::new_tracer_account`.
+        state_ref.push_op(AccountBalanceOp::new(
+            RW::READ,
+            Address::zero(),
+            Word::from(555u32),
+            Word::from(555u32),
+        ));
+        // Add StackOp associated to the stack push.
+        state_ref.push_op(StackOp::new(
+            RW::WRITE,
+            0,
+            StackAddress::from(1023),
+            Word::from(555u32),
+        ));
+        tx.steps_mut().push(step);
+        test_builder.end_tx(&mut tx, &block.geth_trace);
+        test_builder.block.txs_mut().push(tx);
+
+        assert_eq!(
+            builder.block.txs()[0].steps()[0].bus_mapping_instance,
+            test_builder.block.txs()[0].steps()[0].bus_mapping_instance
+        );
+        assert_eq!(builder.block.container, test_builder.block.container);
+
+        Ok(())
+    }
+}
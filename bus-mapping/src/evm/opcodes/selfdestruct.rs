@@ -0,0 +1,425 @@
+use super::Opcode;
+use crate::circuit_input_builder::{CircuitInputStateRef, ExecError};
+use crate::eth_types::{GethExecStep, ToAddress, ToWord, Word};
+use crate::{
+    operation::{
+        AccountBalanceOp, AccountCodeHashOp, AccountNonceOp, CallContextField,
+        CallContextOp, StackOp, TxAccessListAccountOp, RW,
+    },
+    Error,
+};
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the
+/// [`OpcodeId::SELFDESTRUCT`](crate::evm::OpcodeId::SELFDESTRUCT) `OpcodeId`.
+/// The balance of the current account is moved to the beneficiary popped from
+/// the stack, and the account is deleted at the end of the transaction,
+/// unless the call is static, which fails with [`ExecError::WriteProtection`].
+/// The beneficiary is marked as warm, and its nonce and code hash are read
+/// with its balance to know whether the transfer creates it.  Without enough
+/// gas, the warm status and the balances are only read.  No gas is refunded
+/// for the destroyed account (EIP-3529).
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Selfdestruct;
+
+impl Opcode for Selfdestruct {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        steps: &[GethExecStep],
+    ) -> Result<(), Error> {
+        let step = &steps[0];
+        let call = state.call().clone();
+        let tx_id = state.tx_id();
+
+        state.push_op(CallContextOp::new(
+            RW::READ,
+            call.call_id,
+            CallContextField::IsStatic,
+            (call.is_static() as u64).into(),
+        ));
+        if call.is_static() {
+            state.step.error = Some(ExecError::WriteProtection);
+            return Ok(());
+        }
+
+        // Call context reads of the account, of the transaction, whose access
+        // list holds the beneficiary, of the depth, which is 1 when the root
+        // call ends the tx, and of the caller id
+        for (field, value) in [
+            (CallContextField::CalleeAddress, call.address.to_word()),
+            (CallContextField::TxId, Word::from(tx_id)),
+            (CallContextField::Depth, call.depth.into()),
+            (CallContextField::CallerId, call.caller_id.into()),
+        ] {
+            state.push_op(CallContextOp::new(
+                RW::READ,
+                call.call_id,
+                field,
+                value,
+            ));
+        }
+
+        // Stack read of the beneficiary
+        let beneficiary = step.stack.last()?.to_address();
+        state.push_op(StackOp::new(
+            RW::READ,
+            call.call_id,
+            step.stack.last_filled(),
+            step.stack.last()?,
+        ));
+
+        // The beneficiary is warm after the step (EIP-2929)
+        let is_warm = state.sdb.check_account_in_access_list(&beneficiary);
+        if step.error.is_some() {
+            state.push_op(TxAccessListAccountOp::new(
+                RW::READ,
+                tx_id,
+                beneficiary,
+                is_warm,
+                is_warm,
+            ));
+        } else {
+            state.push_op(TxAccessListAccountOp::new(
+                RW::WRITE,
+                tx_id,
+                beneficiary,
+                true,
+                is_warm,
+            ));
+            state.sdb.add_account_to_access_list(&beneficiary);
+        }
+
+        // Account nonce and code hash reads of the beneficiary, which is
+        // created by the transfer of a non-zero balance when it's empty
+        // (EIP-161).  The code hash of an account which doesn't exist is 0.
+        let (_, account) = state.sdb.get_account(&beneficiary);
+        let (nonce, code_hash) = (account.nonce, account.codeHash);
+        state.push_op(AccountNonceOp::new(RW::READ, beneficiary, nonce, nonce));
+        state.push_op(AccountCodeHashOp::new(
+            RW::READ,
+            beneficiary,
+            code_hash,
+            code_hash,
+        ));
+
+        // Without enough gas, the balances of the beneficiary and of the
+        // account are only read
+        if step.error.is_some() {
+            for address in [beneficiary, call.address] {
+                let balance = state.sdb.get_account(&address).1.balance;
+                state.push_op(AccountBalanceOp::new(
+                    RW::READ,
+                    address,
+                    balance,
+                    balance,
+                ));
+            }
+            state.step.error = state.get_step_err(step, steps.get(1))?;
+            return Ok(());
+        }
+
+        // The beneficiary is credited before the balance of the account is
+        // cleared, so the balance is burnt when the account is its own
+        // beneficiary.
+        let value = state.sdb.get_account(&call.address).1.balance;
+        for (address, is_beneficiary) in
+            [(beneficiary, true), (call.address, false)]
+        {
+            let (_, account) = state.sdb.get_account_mut(&address);
+            let balance_prev = account.balance;
+            account.balance = if is_beneficiary {
+                balance_prev + value
+            } else {
+                Word::zero()
+            };
+            let balance = account.balance;
+            state.push_op(AccountBalanceOp::new(
+                RW::WRITE,
+                address,
+                balance,
+                balance_prev,
+            ));
+        }
+
+        if call.is_persistent {
+            state.sdb.destruct_account(&call.address);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod selfdestruct_tests {
+    use super::*;
+    use crate::{
+        bytecode,
+        bytecode::Bytecode,
+        circuit_input_builder::{CircuitInputBuilder, OogError},
+        eth_types::{Address, H256},
+        evm::OpcodeId,
+        exec_trace::OperationRef,
+        mock,
+        operation::{AccountCodeHashOp, AccountNonceOp, Target},
+    };
+    use pretty_assertions::assert_eq;
+
+    fn balance_ops(
+        builder: &CircuitInputBuilder,
+        op_refs: &[OperationRef],
+    ) -> Vec<AccountBalanceOp> {
+        op_refs
+            .iter()
+            .filter(|op_ref| op_ref.target() == Target::AccountBalance)
+            .map(|op_ref| {
+                builder.block.container.account_balance[op_ref.as_usize() - 1]
+                    .op()
+                    .clone()
+            })
+            .collect()
+    }
+
+    fn build(code: &Bytecode) -> CircuitInputBuilder {
+        let block = mock::BlockData::new_single_tx_trace_code(code).unwrap();
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        // The contract has the balance given by `mock::new_tracer_account`
+        builder.sdb.get_account_mut(&Address::zero()).1.balance =
+            Word::from(555u64);
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+        builder
+    }
+
+    #[test]
+    fn selfdestruct_opcode_impl() {
+        let code = bytecode! {
+            PUSH2(0x1234)
+            SELFDESTRUCT
+        };
+        let builder = build(&code);
+
+        let tx = &builder.block.txs()[0];
+        let step = tx.steps().last().unwrap();
+        assert_eq!(step.op, OpcodeId::SELFDESTRUCT);
+        assert_eq!(
+            step.bus_mapping_instance
+                .iter()
+                .map(|op_ref| op_ref.target())
+                .collect::<Vec<_>>(),
+            vec![
+                Target::CallContext,
+                Target::CallContext,
+                Target::CallContext,
+                Target::CallContext,
+                Target::CallContext,
+                Target::Stack,
+                Target::TxAccessListAccount,
+                Target::AccountNonce,
+                Target::AccountCodeHash,
+                Target::AccountBalance,
+                Target::AccountBalance,
+            ]
+        );
+        let beneficiary = Address::from_low_u64_be(0x1234);
+        assert_eq!(
+            balance_ops(&builder, &step.bus_mapping_instance),
+            vec![
+                AccountBalanceOp::new(
+                    RW::WRITE,
+                    beneficiary,
+                    Word::from(555u64),
+                    Word::zero()
+                ),
+                AccountBalanceOp::new(
+                    RW::WRITE,
+                    Address::zero(),
+                    Word::zero(),
+                    Word::from(555u64)
+                ),
+            ]
+        );
+
+        // The account is deleted at the end of the transaction
+        let end_tx_ops = &tx.end_tx_step().bus_mapping_instance[4..];
+        let container = &builder.block.container;
+        assert_eq!(
+            container.account_nonce[end_tx_ops[0].as_usize() - 1].op(),
+            &AccountNonceOp::new(
+                RW::WRITE,
+                Address::zero(),
+                Word::zero(),
+                Word::zero()
+            )
+        );
+        assert_eq!(
+            balance_ops(&builder, &end_tx_ops[1..2]),
+            vec![AccountBalanceOp::new(
+                RW::WRITE,
+                Address::zero(),
+                Word::zero(),
+                Word::zero()
+            )]
+        );
+        assert_eq!(
+            container.account_code_hash[end_tx_ops[2].as_usize() - 1].op(),
+            &AccountCodeHashOp::new(
+                RW::WRITE,
+                Address::zero(),
+                H256::zero(),
+                H256::zero()
+            )
+        );
+        assert!(!builder.sdb.get_account(&Address::zero()).0);
+        assert_eq!(
+            builder.sdb.get_account(&beneficiary).1.balance,
+            Word::from(555u64)
+        );
+    }
+
+    #[test]
+    fn selfdestruct_to_itself() {
+        let code = bytecode! {
+            ADDRESS
+            SELFDESTRUCT
+        };
+        let builder = build(&code);
+
+        // The balance is burnt
+        let step = builder.block.txs()[0].steps().last().unwrap();
+        assert_eq!(
+            balance_ops(&builder, &step.bus_mapping_instance),
+            vec![
+                AccountBalanceOp::new(
+                    RW::WRITE,
+                    Address::zero(),
+                    Word::from(1110u64),
+                    Word::from(555u64)
+                ),
+                AccountBalanceOp::new(
+                    RW::WRITE,
+                    Address::zero(),
+                    Word::zero(),
+                    Word::from(1110u64)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn selfdestruct_write_protection() {
+        let code_a = bytecode! {
+            PUSH1(0x00) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x00) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH2(0x123) // addr
+            PUSH2(0x1000) // gas
+            STATICCALL
+            STOP
+        };
+        let code_b = bytecode! {
+            PUSH1(0x00) // beneficiary
+            SELFDESTRUCT
+        };
+
+        // Get the execution steps from the external tracer
+        let block =
+            mock::BlockData::new_single_tx_trace_code_2(&code_a, &code_b)
+                .unwrap();
+
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let tx = &builder.block.txs()[0];
+        let step = tx
+            .steps()
+            .iter()
+            .find(|step| step.op == OpcodeId::SELFDESTRUCT)
+            .unwrap();
+        assert_eq!(step.error, Some(ExecError::WriteProtection));
+
+        // Only the static flag is read before the callee fails and returns
+        let op_ref = &step.bus_mapping_instance[0];
+        assert_eq!(op_ref.target(), Target::CallContext);
+        let op =
+            builder.block.container.call_context[op_ref.as_usize() - 1].op();
+        assert_eq!(
+            (op.field(), *op.value()),
+            (CallContextField::IsStatic, Word::one())
+        );
+        assert!(balance_ops(&builder, &step.bus_mapping_instance).is_empty());
+
+        // No balance is moved, and the account is not deleted
+        assert_eq!(tx.end_tx_step().bus_mapping_instance.len(), 4);
+    }
+
+    #[test]
+    fn selfdestruct_out_of_gas() {
+        // The callee doesn't have the gas to destroy its account
+        let code_a = bytecode! {
+            PUSH1(0x00) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x00) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH1(0x00) // value
+            PUSH2(0x123) // addr
+            PUSH2(0x800) // gas
+            CALL
+            STOP
+        };
+        let code_b = bytecode! {
+            PUSH2(0x1234) // beneficiary
+            SELFDESTRUCT
+        };
+        let block =
+            mock::BlockData::new_single_tx_trace_code_2(&code_a, &code_b)
+                .unwrap();
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let tx = &builder.block.txs()[0];
+        let step = tx
+            .steps()
+            .iter()
+            .find(|step| step.op == OpcodeId::SELFDESTRUCT)
+            .unwrap();
+        assert_eq!(
+            step.error,
+            Some(ExecError::OutOfGas(OogError::SelfDestruct))
+        );
+
+        // The warm status and the balances are read, and none is written
+        assert_eq!(
+            step.bus_mapping_instance[6..11]
+                .iter()
+                .map(|op_ref| op_ref.target())
+                .collect::<Vec<_>>(),
+            vec![
+                Target::TxAccessListAccount,
+                Target::AccountNonce,
+                Target::AccountCodeHash,
+                Target::AccountBalance,
+                Target::AccountBalance,
+            ]
+        );
+        let op_ref = &step.bus_mapping_instance[6];
+        let op = builder.block.container.tx_access_list_account
+            [op_ref.as_usize() - 1]
+            .op();
+        assert!(!op.rw().is_write());
+        assert!(balance_ops(&builder, &step.bus_mapping_instance[9..11])
+            .iter()
+            .all(|op| !op.rw().is_write()));
+        assert!(!builder
+            .sdb
+            .check_account_in_access_list(&Address::from_low_u64_be(0x1234)));
+    }
+}
@@ -0,0 +1,199 @@
+use super::Opcode;
+use crate::circuit_input_builder::{memory_bytes, CircuitInputStateRef};
+use crate::eth_types::GethExecStep;
+use crate::{
+    evm::MemoryAddress,
+    operation::{MemoryOp, StackOp, RW},
+    Error,
+};
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the [`OpcodeId::SHA3`](crate::evm::OpcodeId::SHA3)
+/// `OpcodeId`.  The hashed bytes are read from memory, and the step fails
+/// with [`OogError::Sha3`](crate::circuit_input_builder::OogError::Sha3) when
+/// there's not enough gas to expand the memory and hash them.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Sha3;
+
+impl Opcode for Sha3 {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        steps: &[GethExecStep],
+    ) -> Result<(), Error> {
+        let step = &steps[0];
+        let call_id = state.call().call_id;
+
+        // Stack reads of the memory offset and the length
+        for i in 0..2 {
+            state.push_op(StackOp::new(
+                RW::READ,
+                call_id,
+                step.stack.nth_last_filled(i),
+                step.stack.nth_last(i)?,
+            ));
+        }
+
+        if step.error.is_some() {
+            state.step.error = state.get_step_err(step, steps.get(1))?;
+            return Ok(());
+        }
+
+        // Memory reads of the hashed bytes
+        let offset = step.stack.nth_last(0)?.low_u64() as usize;
+        let length = step.stack.nth_last(1)?.low_u64() as usize;
+        for (i, byte) in
+            memory_bytes(step, offset, length).into_iter().enumerate()
+        {
+            state.push_op(MemoryOp::new(
+                RW::READ,
+                call_id,
+                MemoryAddress::from(offset + i),
+                byte,
+            ));
+        }
+
+        // Stack write of the hash
+        state.push_op(StackOp::new(
+            RW::WRITE,
+            call_id,
+            step.stack.nth_last_filled(1),
+            steps[1].stack.last()?,
+        ));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod sha3_tests {
+    use super::*;
+    use crate::{
+        bytecode,
+        circuit_input_builder::{CircuitInputBuilder, ExecError, OogError},
+        eth_types::Word,
+        evm::{OpcodeId, StackAddress},
+        mock,
+        operation::Target,
+    };
+    use ethers_core::utils::keccak256;
+    use pretty_assertions::assert_eq;
+
+    fn sha3_builder(
+        code: &bytecode::Bytecode,
+        callee_code: Option<&bytecode::Bytecode>,
+    ) -> CircuitInputBuilder {
+        // Get the execution steps from the external tracer
+        let block = match callee_code {
+            Some(callee_code) => {
+                mock::BlockData::new_single_tx_trace_code_2(code, callee_code)
+            }
+            None => mock::BlockData::new_single_tx_trace_code(code),
+        }
+        .unwrap();
+
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+        builder
+    }
+
+    #[test]
+    fn sha3_opcode_impl() {
+        // Hash 2 bytes in memory followed by 2 bytes past its end
+        let code = bytecode! {
+            PUSH2(0xaabb)
+            PUSH1(0x00)
+            MSTORE
+            PUSH1(0x04) // length
+            PUSH1(0x1e) // offset
+            SHA3
+            STOP
+        };
+        let builder = sha3_builder(&code, None);
+
+        let tx = &builder.block.txs()[0];
+        let call_id = tx.calls()[0].call_id;
+        let step = tx
+            .steps()
+            .iter()
+            .find(|step| step.op == OpcodeId::SHA3)
+            .unwrap();
+        let container = &builder.block.container;
+        let ops = &step.bus_mapping_instance;
+        assert_eq!(
+            ops.iter().map(|op_ref| op_ref.target()).collect::<Vec<_>>(),
+            [
+                vec![Target::Stack; 2],
+                vec![Target::Memory; 4],
+                vec![Target::Stack]
+            ]
+            .concat()
+        );
+
+        let bytes = [0xaa, 0xbb, 0x00, 0x00];
+        assert_eq!(
+            ops[2..6]
+                .iter()
+                .map(|op_ref| container.memory[op_ref.as_usize() - 1]
+                    .op()
+                    .clone())
+                .collect::<Vec<_>>(),
+            bytes
+                .iter()
+                .enumerate()
+                .map(|(i, byte)| MemoryOp::new(
+                    RW::READ,
+                    call_id,
+                    MemoryAddress::from(0x1e + i),
+                    *byte
+                ))
+                .collect::<Vec<_>>()
+        );
+
+        let hash = Word::from_big_endian(&keccak256(&bytes));
+        assert_eq!(
+            container.stack[ops[6].as_usize() - 1].op(),
+            &StackOp::new(RW::WRITE, call_id, StackAddress::from(1023), hash)
+        );
+    }
+
+    #[test]
+    fn sha3_out_of_gas() {
+        // The callee only has the gas to push the arguments of SHA3
+        let code = bytecode! {
+            PUSH1(0x00) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x00) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH1(0x00) // value
+            PUSH2(0x123) // addr
+            PUSH1(0x10) // gas
+            CALL
+            STOP
+        };
+        let callee_code = bytecode! {
+            PUSH1(0x20) // length
+            PUSH1(0x00) // offset
+            SHA3
+            STOP
+        };
+        let builder = sha3_builder(&code, Some(&callee_code));
+
+        let tx = &builder.block.txs()[0];
+        let step = tx
+            .steps()
+            .iter()
+            .find(|step| step.op == OpcodeId::SHA3)
+            .unwrap();
+        assert_eq!(step.error, Some(ExecError::OutOfGas(OogError::Sha3)));
+        // Only the arguments are read before the callee returns
+        assert!(step.bus_mapping_instance[..2]
+            .iter()
+            .all(|op_ref| op_ref.target() == Target::Stack));
+        assert!(step.bus_mapping_instance[2..]
+            .iter()
+            .all(|op_ref| op_ref.target() == Target::CallContext));
+    }
+}
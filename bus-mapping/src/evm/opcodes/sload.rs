@@ -1,14 +1,20 @@
 use super::Opcode;
 use crate::circuit_input_builder::CircuitInputStateRef;
-use crate::eth_types::GethExecStep;
+use crate::eth_types::{GethExecStep, ToWord, Word};
 use crate::{
-    operation::{StackOp, StorageOp, RW},
+    operation::{
+        CallContextField, CallContextOp, StackOp, StorageOp,
+        TxAccessListAccountStorageOp, RW,
+    },
     Error,
 };
 
 /// Placeholder structure used to implement [`Opcode`] trait over it
 /// corresponding to the [`OpcodeId::SLOAD`](crate::evm::OpcodeId::SLOAD)
-/// `OpcodeId`.
+/// `OpcodeId`.  The step fails with
+/// [`OogError::SloadSstore`](crate::circuit_input_builder::OogError::
+/// SloadSstore) when there's not enough gas to access the slot, whose warm
+/// status is read to know the cost.
 #[derive(Debug, Copy, Clone)]
 pub(crate) struct Sload;
 
@@ -18,27 +24,92 @@ impl Opcode for Sload {
         steps: &[GethExecStep],
     ) -> Result<(), Error> {
         let step = &steps[0];
+        let call_id = state.call().call_id;
+        let tx_id = state.tx_id();
+
+        // Call context reads of the address of the current account and of
+        // the transaction
+        let address = state.call().address;
+        state.push_op(CallContextOp::new(
+            RW::READ,
+            call_id,
+            CallContextField::CalleeAddress,
+            address.to_word(),
+        ));
+        state.push_op(CallContextOp::new(
+            RW::READ,
+            call_id,
+            CallContextField::TxId,
+            Word::from(tx_id),
+        ));
 
         // First stack read
         let stack_value_read = step.stack.last()?;
         let stack_position = step.stack.last_filled();
 
         // Manage first stack read at latest stack position
-        state.push_op(StackOp::new(RW::READ, stack_position, stack_value_read));
+        state.push_op(StackOp::new(
+            RW::READ,
+            call_id,
+            stack_position,
+            stack_value_read,
+        ));
+
+        // Without enough gas, only the warm status of the slot is read
+        if step.error.is_some() {
+            let is_warm = state.sdb.check_account_storage_in_access_list((
+                &address,
+                &stack_value_read,
+            ));
+            state.push_op(TxAccessListAccountStorageOp::new(
+                RW::READ,
+                tx_id,
+                address,
+                stack_value_read,
+                is_warm,
+                is_warm,
+            ));
+            state.step.error = state.get_step_err(step, steps.get(1))?;
+            return Ok(());
+        }
 
-        // Storage read
+        // Storage read, where the value at the beginning of the transaction
+        // is the one read when the slot hasn't been written in it
         let storage_value_read = step.storage.get_or_err(&stack_value_read)?;
+        let (found, committed_value) =
+            state.sdb.get_committed_storage(&address, &stack_value_read);
+        let committed_value = if found {
+            *committed_value
+        } else {
+            storage_value_read
+        };
         state.push_op(StorageOp::new(
             RW::READ,
-            state.call().address,
+            address,
             stack_value_read,
             storage_value_read,
             storage_value_read,
+            tx_id,
+            committed_value,
+        ));
+
+        // Mark the storage slot as warm in the access list
+        let is_warm = !state
+            .sdb
+            .add_account_storage_to_access_list((&address, &stack_value_read));
+        state.push_op(TxAccessListAccountStorageOp::new(
+            RW::WRITE,
+            tx_id,
+            address,
+            stack_value_read,
+            true,
+            is_warm,
         ));
 
         // First stack write
         state.push_op(StackOp::new(
             RW::WRITE,
+            call_id,
             stack_position,
             storage_value_read,
         ));
@@ -53,11 +124,13 @@ mod sload_tests {
     use crate::{
         bytecode,
         circuit_input_builder::{
-            CircuitInputBuilder, ExecStep, Transaction, TransactionContext,
+            CircuitInputBuilder, ExecError, ExecStep, OogError, Transaction,
+            TransactionContext,
         },
         eth_types::{Address, Word},
-        evm::StackAddress,
+        evm::{OpcodeId, StackAddress},
         mock,
+        operation::Target,
     };
     use pretty_assertions::assert_eq;
 
@@ -90,8 +163,9 @@ mod sload_tests {
             block.eth_block,
             block.block_ctants.clone(),
         );
-        let mut tx = Transaction::new(&block.eth_tx);
+        let mut tx = Transaction::new(0, &block.eth_tx);
         let mut tx_ctx = TransactionContext::new(&block.eth_tx);
+        test_builder.begin_tx(&mut tx).unwrap();
 
         // Generate step corresponding to SLOAD
         let mut step = ExecStep::new(
@@ -102,27 +176,58 @@ mod sload_tests {
         );
         let mut state_ref =
             test_builder.state_ref(&mut tx, &mut tx_ctx, &mut step);
+        // Add CallContextOps associated to the reads of the address and the
+        // transaction id.
+        state_ref.push_op(CallContextOp::new(
+            RW::READ,
+            0,
+            CallContextField::CalleeAddress,
+            Address::from([0u8; 20]).to_word(),
+        ));
+        state_ref.push_op(CallContextOp::new(
+            RW::READ,
+            0,
+            CallContextField::TxId,
+            Word::one(),
+        ));
         // Add StackOp associated to the stack pop.
         state_ref.push_op(StackOp::new(
             RW::READ,
+            0,
             StackAddress::from(1023),
             Word::from(0x0u32),
         ));
-        // Add StorageOp associated to the storage read.
+        // Add StorageOp associated to the storage read, whose slot hasn't
+        // been written in the transaction.
         state_ref.push_op(StorageOp::new(
             RW::READ,
             Address::from([0u8; 20]),
             Word::from(0x0u32),
             Word::from(0x6fu32),
             Word::from(0x6fu32),
+            1,
+            Word::from(0x6fu32),
+        ));
+        // Add TxAccessListAccountStorageOp associated to the access list
+        // update.  The SSTORE before `#[start]` is not handled by the builder,
+        // so the slot is still cold.
+        state_ref.push_op(TxAccessListAccountStorageOp::new(
+            RW::WRITE,
+            1,
+            Address::from([0u8; 20]),
+            Word::from(0x0u32),
+            true,
+            false,
         ));
         // Add StackOp associated to the stack push.
         state_ref.push_op(StackOp::new(
             RW::WRITE,
+            0,
             StackAddress::from(1023),
             Word::from(0x6fu32),
         ));
         tx.steps_mut().push(step);
+        test_builder.end_tx(&mut tx, &block.geth_trace);
         test_builder.block.txs_mut().push(tx);
 
         assert_eq!(
@@ -133,4 +238,53 @@ mod sload_tests {
 
         Ok(())
     }
+
+    #[test]
+    fn sload_out_of_gas() {
+        // The callee doesn't have the gas to access a cold slot
+        let code = bytecode! {
+            PUSH1(0x00) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x00) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH1(0x00) // value
+            PUSH2(0x123) // addr
+            PUSH2(0x800) // gas
+            CALL
+            STOP
+        };
+        let callee_code = bytecode! {
+            PUSH1(0x00)
+            SLOAD
+            STOP
+        };
+        let block =
+            mock::BlockData::new_single_tx_trace_code_2(&code, &callee_code)
+                .unwrap();
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let step = builder.block.txs()[0]
+            .steps()
+            .iter()
+            .find(|step| step.op == OpcodeId::SLOAD)
+            .unwrap();
+        assert_eq!(
+            step.error,
+            Some(ExecError::OutOfGas(OogError::SloadSstore))
+        );
+
+        // Only the warm status of the slot is read after the key
+        let op_ref = &step.bus_mapping_instance[3];
+        assert_eq!(op_ref.target(), Target::TxAccessListAccountStorage);
+        let op = builder.block.container.tx_access_list_account_storage
+            [op_ref.as_usize() - 1]
+            .op();
+        assert!(!op.rw().is_write());
+        assert!(!op.value());
+        assert!(builder.block.container.storage.is_empty());
+    }
 }
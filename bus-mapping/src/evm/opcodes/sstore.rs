@@ -0,0 +1,462 @@
+use super::Opcode;
+use crate::circuit_input_builder::{CircuitInputStateRef, ExecError};
+use crate::eth_types::{GethExecStep, ToWord, Word};
+use crate::evm::GasCost;
+use crate::{
+    operation::{
+        CallContextField, CallContextOp, StackOp, StorageOp,
+        TxAccessListAccountStorageOp, TxRefundOp, RW,
+    },
+    Error,
+};
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the [`OpcodeId::SSTORE`](crate::evm::OpcodeId::SSTORE)
+/// `OpcodeId`.  A static call fails with [`ExecError::WriteProtection`], and
+/// the step fails with
+/// [`OogError::SloadSstore`](crate::circuit_input_builder::OogError::
+/// SloadSstore) when the gas left is not more than `SSTORE_SENTRY` or doesn't
+/// cover the cost of the write, which is known from the reads of the slot and
+/// of its warm status.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Sstore;
+
+impl Opcode for Sstore {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        steps: &[GethExecStep],
+    ) -> Result<(), Error> {
+        let step = &steps[0];
+        let call = state.call().clone();
+        let tx_id = state.tx_id();
+
+        state.push_op(CallContextOp::new(
+            RW::READ,
+            call.call_id,
+            CallContextField::IsStatic,
+            (call.is_static() as u64).into(),
+        ));
+        if call.is_static() {
+            state.step.error = Some(ExecError::WriteProtection);
+            return Ok(());
+        }
+
+        // Call context reads of the address of the current account and of
+        // the transaction
+        let address = call.address;
+        state.push_op(CallContextOp::new(
+            RW::READ,
+            call.call_id,
+            CallContextField::CalleeAddress,
+            address.to_word(),
+        ));
+        state.push_op(CallContextOp::new(
+            RW::READ,
+            call.call_id,
+            CallContextField::TxId,
+            Word::from(tx_id),
+        ));
+
+        // Stack reads of the key and the value
+        let key = step.stack.nth_last(0)?;
+        let value = step.stack.nth_last(1)?;
+        state.push_op(StackOp::new(
+            RW::READ,
+            call.call_id,
+            step.stack.nth_last_filled(0),
+            key,
+        ));
+        state.push_op(StackOp::new(
+            RW::READ,
+            call.call_id,
+            step.stack.nth_last_filled(1),
+            value,
+        ));
+
+        let value_prev = *state.sdb.get_storage(&address, &key).1;
+        let committed_value =
+            *state.sdb.get_committed_storage(&address, &key).1;
+
+        // Without enough gas, the slot and its warm status are only read
+        if step.error.is_some() {
+            state.push_op(StorageOp::new(
+                RW::READ,
+                address,
+                key,
+                value_prev,
+                value_prev,
+                tx_id,
+                committed_value,
+            ));
+            let is_warm = state
+                .sdb
+                .check_account_storage_in_access_list((&address, &key));
+            state.push_op(TxAccessListAccountStorageOp::new(
+                RW::READ,
+                tx_id,
+                address,
+                key,
+                is_warm,
+                is_warm,
+            ));
+            state.step.error = state.get_step_err(step, steps.get(1))?;
+            return Ok(());
+        }
+
+        // Storage write
+        state.push_op(StorageOp::new(
+            RW::WRITE,
+            address,
+            key,
+            value,
+            value_prev,
+            tx_id,
+            committed_value,
+        ));
+        state.sdb.set_storage(&address, &key, &value);
+
+        // Mark the storage slot as warm in the access list
+        let is_warm = !state
+            .sdb
+            .add_account_storage_to_access_list((&address, &key));
+        state.push_op(TxAccessListAccountStorageOp::new(
+            RW::WRITE,
+            tx_id,
+            address,
+            key,
+            true,
+            is_warm,
+        ));
+
+        // Update the refund counter
+        let refund_prev = state.sdb.refund();
+        let refund =
+            sstore_refund(refund_prev, &value, &value_prev, &committed_value)
+                .ok_or_else(|| {
+                Error::InvalidGethExecStep(
+                    "refund counter below zero",
+                    Box::new(step.clone()),
+                )
+            })?;
+        state.push_op(TxRefundOp::new(RW::WRITE, tx_id, refund, refund_prev));
+        state.sdb.set_refund(refund);
+
+        Ok(())
+    }
+}
+
+/// Returns the refund counter after a SSTORE of `value` into a slot holding
+/// `value_prev`, which held `committed_value` at the beginning of the
+/// transaction, following EIP-2200 with the refunds of EIP-3529.  Returns
+/// `None` when the counter would go below zero, which can't happen in a
+/// valid execution.
+fn sstore_refund(
+    refund: u64,
+    value: &Word,
+    value_prev: &Word,
+    committed_value: &Word,
+) -> Option<u64> {
+    let clears_schedule = GasCost::SSTORE_CLEARS_SCHEDULE.as_u64();
+    let warm_read = GasCost::WARM_STORAGE_READ_COST.as_u64();
+
+    // No-op
+    if value == value_prev {
+        return Some(refund);
+    }
+
+    // Clean slot
+    if value_prev == committed_value {
+        if !committed_value.is_zero() && value.is_zero() {
+            return Some(refund + clears_schedule);
+        }
+        return Some(refund);
+    }
+
+    // Dirty slot
+    let mut refund = refund;
+    if !committed_value.is_zero() {
+        if value_prev.is_zero() {
+            // Recreate a slot cleared before in this transaction
+            refund = refund.checked_sub(clears_schedule)?;
+        } else if value.is_zero() {
+            refund += clears_schedule;
+        }
+    }
+    if value == committed_value {
+        // Reset to the original value
+        refund += if committed_value.is_zero() {
+            GasCost::SSTORE_SET.as_u64() - warm_read
+        } else {
+            GasCost::SSTORE_RESET.as_u64() - warm_read
+        };
+    }
+    Some(refund)
+}
+
+#[cfg(test)]
+mod sstore_tests {
+    use super::*;
+    use crate::{
+        bytecode,
+        bytecode::Bytecode,
+        circuit_input_builder::{CircuitInputBuilder, OogError},
+        eth_types::{Address, Word},
+        evm::{GlobalCounter, OpcodeId},
+        mock,
+        operation::{Operation, Target},
+    };
+    use pretty_assertions::assert_eq;
+
+    // Build the block of a tx executing `code`, which calls a callee with
+    // `callee_code`.
+    fn sstore_callee_builder(
+        code: &Bytecode,
+        callee_code: &Bytecode,
+    ) -> CircuitInputBuilder {
+        let block =
+            mock::BlockData::new_single_tx_trace_code_2(code, callee_code)
+                .unwrap();
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+        builder
+    }
+
+    #[test]
+    fn sstore_opcode_impl() -> Result<(), Error> {
+        let code = bytecode! {
+            // Write 0x6f to storage slot 0
+            PUSH1(0x6fu64)
+            PUSH1(0x00u64)
+            SSTORE
+            // Clear storage slot 0
+            PUSH1(0x00u64)
+            PUSH1(0x00u64)
+            SSTORE
+            STOP
+        };
+
+        // Get the execution steps from the external tracer
+        let block = mock::BlockData::new_single_tx_trace_code(&code).unwrap();
+
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let steps = builder.block.txs()[0].steps();
+        assert_eq!(steps[2].op, OpcodeId::SSTORE);
+        assert_eq!(steps[2].bus_mapping_instance.len(), 8);
+        assert_eq!(steps[5].op, OpcodeId::SSTORE);
+        assert_eq!(steps[5].bus_mapping_instance.len(), 8);
+
+        let address = Address::from([0u8; 20]);
+        let container = &builder.block.container;
+        assert_eq!(
+            container.sorted_storage(),
+            vec![
+                Operation::new(
+                    GlobalCounter::from(12),
+                    StorageOp::new(
+                        RW::WRITE,
+                        address,
+                        Word::from(0x00u64),
+                        Word::from(0x6fu64),
+                        Word::from(0x00u64),
+                        1,
+                        Word::from(0x00u64),
+                    )
+                ),
+                Operation::new(
+                    GlobalCounter::from(22),
+                    StorageOp::new(
+                        RW::WRITE,
+                        address,
+                        Word::from(0x00u64),
+                        Word::from(0x00u64),
+                        Word::from(0x6fu64),
+                        1,
+                        Word::from(0x00u64),
+                    )
+                ),
+            ]
+        );
+        assert_eq!(
+            container.sorted_tx_access_list_account_storage(),
+            vec![
+                Operation::new(
+                    GlobalCounter::from(13),
+                    TxAccessListAccountStorageOp::new(
+                        RW::WRITE,
+                        1,
+                        address,
+                        Word::from(0x00u64),
+                        true,
+                        false,
+                    )
+                ),
+                Operation::new(
+                    GlobalCounter::from(23),
+                    TxAccessListAccountStorageOp::new(
+                        RW::WRITE,
+                        1,
+                        address,
+                        Word::from(0x00u64),
+                        true,
+                        true,
+                    )
+                ),
+            ]
+        );
+        // Setting back the original zero value refunds `SSTORE_SET -
+        // WARM_STORAGE_READ_COST`
+        assert_eq!(
+            container.sorted_tx_refund(),
+            vec![
+                Operation::new(
+                    GlobalCounter::from(14),
+                    TxRefundOp::new(RW::WRITE, 1, 0, 0)
+                ),
+                Operation::new(
+                    GlobalCounter::from(24),
+                    TxRefundOp::new(RW::WRITE, 1, 19900, 0)
+                ),
+                // Read by the end of the transaction to refund the gas
+                Operation::new(
+                    GlobalCounter::from(26),
+                    TxRefundOp::new(RW::READ, 1, 19900, 19900)
+                ),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn sstore_refund_values() {
+        let zero = Word::zero();
+        let one = Word::from(1u64);
+        let two = Word::from(2u64);
+
+        // No-op
+        assert_eq!(sstore_refund(0, &one, &one, &one), Some(0));
+        // Clean slot: clear
+        assert_eq!(sstore_refund(0, &zero, &one, &one), Some(4800));
+        // Clean slot: update
+        assert_eq!(sstore_refund(0, &two, &one, &one), Some(0));
+        // Dirty slot: recreate a cleared slot
+        assert_eq!(sstore_refund(4800, &two, &zero, &one), Some(0));
+        // Dirty slot: clear
+        assert_eq!(sstore_refund(0, &zero, &two, &one), Some(4800));
+        // Dirty slot: reset to the original non-zero value
+        assert_eq!(sstore_refund(4800, &one, &zero, &one), Some(2800));
+        // Dirty slot: reset to the original zero value
+        assert_eq!(sstore_refund(0, &zero, &one, &zero), Some(19900));
+        // Recreating a slot without the refund of its clear underflows
+        assert_eq!(sstore_refund(0, &two, &zero, &one), None);
+    }
+
+    #[test]
+    fn sstore_write_protection() {
+        let code = bytecode! {
+            PUSH1(0x00) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x00) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH2(0x123) // addr
+            PUSH2(0x5000) // gas
+            STATICCALL
+            STOP
+        };
+        let callee_code = bytecode! {
+            PUSH1(0x6f)
+            PUSH1(0x00)
+            SSTORE
+            STOP
+        };
+        let builder = sstore_callee_builder(&code, &callee_code);
+
+        let step = builder.block.txs()[0]
+            .steps()
+            .iter()
+            .find(|step| step.op == OpcodeId::SSTORE)
+            .unwrap();
+        assert_eq!(step.error, Some(ExecError::WriteProtection));
+
+        // Only the static flag is read before the callee fails and returns
+        let op_ref = &step.bus_mapping_instance[0];
+        let op =
+            builder.block.container.call_context[op_ref.as_usize() - 1].op();
+        assert_eq!(
+            (op.field(), *op.value()),
+            (CallContextField::IsStatic, Word::one())
+        );
+        assert!(step.bus_mapping_instance[1..]
+            .iter()
+            .all(|op_ref| op_ref.target() == Target::CallContext));
+        assert!(builder.block.container.storage.is_empty());
+    }
+
+    #[test]
+    fn sstore_out_of_gas() {
+        // The callee has less gas left than `SSTORE_SENTRY` at the first
+        // SSTORE
+        let code = bytecode! {
+            PUSH1(0x00) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x00) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH1(0x00) // value
+            PUSH2(0x123) // addr
+            PUSH2(0x800) // gas
+            CALL
+            STOP
+        };
+        let callee_code = bytecode! {
+            PUSH1(0x6f)
+            PUSH1(0x00)
+            SSTORE
+            STOP
+        };
+        let builder = sstore_callee_builder(&code, &callee_code);
+
+        let step = builder.block.txs()[0]
+            .steps()
+            .iter()
+            .find(|step| step.op == OpcodeId::SSTORE)
+            .unwrap();
+        assert_eq!(
+            step.error,
+            Some(ExecError::OutOfGas(OogError::SloadSstore))
+        );
+
+        // The slot and its warm status are read, but not written
+        let targets = step
+            .bus_mapping_instance
+            .iter()
+            .map(|op_ref| op_ref.target())
+            .take(7)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            targets,
+            vec![
+                Target::CallContext,
+                Target::CallContext,
+                Target::CallContext,
+                Target::Stack,
+                Target::Stack,
+                Target::Storage,
+                Target::TxAccessListAccountStorage,
+            ]
+        );
+        let container = &builder.block.container;
+        assert!(container.storage.iter().all(|op| !op.op().rw().is_write()));
+        assert!(container
+            .tx_refund
+            .iter()
+            .all(|op| !op.op().rw().is_write()));
+    }
+}
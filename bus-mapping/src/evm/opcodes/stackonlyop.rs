@@ -25,6 +25,7 @@ impl<const N: usize> Opcode for StackOnlyOpcode<N> {
         for i in 0..N {
             state.push_op(StackOp::new(
                 RW::READ,
+                state.call().call_id,
                 step.stack.nth_last_filled(i),
                 step.stack.nth_last(i)?,
             ));
@@ -34,6 +35,7 @@ impl<const N: usize> Opcode for StackOnlyOpcode<N> {
         let result_value = steps[1].stack.last()?;
         state.push_op(StackOp::new(
             RW::WRITE,
+            state.call().call_id,
             step.stack.nth_last_filled(N - 1),
             result_value,
         ));
@@ -79,8 +81,9 @@ mod stackonlyop_tests {
             block.eth_block,
             block.block_ctants.clone(),
         );
-        let mut tx = Transaction::new(&block.eth_tx);
+        let mut tx = Transaction::new(0, &block.eth_tx);
         let mut tx_ctx = TransactionContext::new(&block.eth_tx);
+        test_builder.begin_tx(&mut tx).unwrap();
 
         // Generate step corresponding to NOT
         let mut step = ExecStep::new(
@@ -94,19 +97,20 @@ mod stackonlyop_tests {
 
         // Read a
         state_ref.push_op(StackOp::new(
-            RW::READ,
+            RW::READ, 0,
             StackAddress(1024 - 1),
             word!("0x000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f"),
         ));
 
         // Write ~a
         state_ref.push_op(StackOp::new(
-            RW::WRITE,
+            RW::WRITE, 0,
             StackAddress(1024 - 1),
             word!("0xfffefdfcfbfaf9f8f7f6f5f4f3f2f1f0efeeedecebeae9e8e7e6e5e4e3e2e1e0"),
         ));
 
         tx.steps_mut().push(step);
+        test_builder.end_tx(&mut tx, &block.geth_trace);
         test_builder.block.txs_mut().push(tx);
 
         // Compare first step bus mapping instance
@@ -145,8 +149,9 @@ mod stackonlyop_tests {
             block.eth_block,
             block.block_ctants.clone(),
         );
-        let mut tx = Transaction::new(&block.eth_tx);
+        let mut tx = Transaction::new(0, &block.eth_tx);
         let mut tx_ctx = TransactionContext::new(&block.eth_tx);
+        test_builder.begin_tx(&mut tx).unwrap();
 
         // Generate step corresponding to ADD
         let mut step = ExecStep::new(
@@ -167,6 +172,7 @@ mod stackonlyop_tests {
         // Manage first stack read at latest stack position
         state_ref.push_op(StackOp::new(
             RW::READ,
+            0,
             last_stack_pointer,
             stack_value_a,
         ));
@@ -174,6 +180,7 @@ mod stackonlyop_tests {
         // Manage second stack read at second latest stack position
         state_ref.push_op(StackOp::new(
             RW::READ,
+            0,
             second_last_stack_pointer,
             stack_value_b,
         ));
@@ -181,11 +188,13 @@ mod stackonlyop_tests {
         // Add StackOp associated to the 0x80 push at the latest Stack pos.
         state_ref.push_op(StackOp::new(
             RW::WRITE,
+            0,
             second_last_stack_pointer,
             sum,
         ));
 
         tx.steps_mut().push(step);
+        test_builder.end_tx(&mut tx, &block.geth_trace);
         test_builder.block.txs_mut().push(tx);
 
         // Compare first step bus mapping instance
@@ -225,8 +234,9 @@ mod stackonlyop_tests {
             block.eth_block,
             block.block_ctants.clone(),
         );
-        let mut tx = Transaction::new(&block.eth_tx);
+        let mut tx = Transaction::new(0, &block.eth_tx);
         let mut tx_ctx = TransactionContext::new(&block.eth_tx);
+        test_builder.begin_tx(&mut tx).unwrap();
 
         // Generate step corresponding to ADDMOD
         let mut step = ExecStep::new(
@@ -241,16 +251,19 @@ mod stackonlyop_tests {
         // Read a, b, n
         state_ref.push_op(StackOp::new(
             RW::READ,
+            0,
             StackAddress(1024 - 3),
             Word::from(0x12345),
         ));
         state_ref.push_op(StackOp::new(
             RW::READ,
+            0,
             StackAddress(1024 - 2),
             Word::from(0x6789a),
         ));
         state_ref.push_op(StackOp::new(
             RW::READ,
+            0,
             StackAddress(1024 - 1),
             Word::from(0xbcdef),
         ));
@@ -258,11 +271,13 @@ mod stackonlyop_tests {
         // Write a + b % n
         state_ref.push_op(StackOp::new(
             RW::WRITE,
+            0,
             StackAddress(1024 - 1),
             Word::from(0x79bdf),
         ));
 
         tx.steps_mut().push(step);
+        test_builder.end_tx(&mut tx, &block.geth_trace);
         test_builder.block.txs_mut().push(tx);
 
         // Compare first step bus mapping instance
@@ -0,0 +1,148 @@
+use super::Opcode;
+use crate::circuit_input_builder::CircuitInputStateRef;
+use crate::eth_types::GethExecStep;
+use crate::{
+    operation::{StackOp, RW},
+    Error,
+};
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to all the Stack only operations that take N words and don't
+/// return any.  The following cases exist in the EVM:
+/// - N = 1: POP and JUMP
+/// - N = 2: JUMPI
+///
+/// A jump to an invalid destination only reads the arguments.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct StackPopOnlyOpcode<const N: usize>;
+
+impl<const N: usize> Opcode for StackPopOnlyOpcode<N> {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        steps: &[GethExecStep],
+    ) -> Result<(), Error> {
+        let step = &steps[0];
+        // N stack reads
+        for i in 0..N {
+            state.push_op(StackOp::new(
+                RW::READ,
+                state.call().call_id,
+                step.stack.nth_last_filled(i),
+                step.stack.nth_last(i)?,
+            ));
+        }
+
+        // The tracer doesn't report the invalid jumps, which end the call
+        state.step.error = state.get_step_err(step, steps.get(1))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod stackpoponlyop_tests {
+    use super::*;
+    use crate::{
+        bytecode,
+        circuit_input_builder::{
+            CircuitInputBuilder, ExecError, ExecStep, Transaction,
+            TransactionContext,
+        },
+        eth_types::Word,
+        evm::{OpcodeId, StackAddress},
+        mock,
+    };
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn jumpi_opcode_impl() -> Result<(), Error> {
+        let code = bytecode! {
+            PUSH1(0x01) // condition
+            PUSH1(0x07) // destination
+            #[start]
+            JUMPI
+            STOP
+            JUMPDEST
+            STOP
+        };
+
+        // Get the execution steps from the external tracer
+        let block =
+            mock::BlockData::new_single_tx_trace_code_at_start(&code).unwrap();
+
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let mut test_builder = CircuitInputBuilder::new(
+            block.eth_block,
+            block.block_ctants.clone(),
+        );
+        let mut tx = Transaction::new(0, &block.eth_tx);
+        let mut tx_ctx = TransactionContext::new(&block.eth_tx);
+        test_builder.begin_tx(&mut tx).unwrap();
+
+        // Generate step corresponding to JUMPI
+        let mut step = ExecStep::new(
+            &block.geth_trace.struct_logs[0],
+            0,
+            test_builder.block_ctx.gc,
+            0,
+        );
+        let mut state_ref =
+            test_builder.state_ref(&mut tx, &mut tx_ctx, &mut step);
+
+        // Read the destination and the condition
+        for (i, value) in [0x07u64, 0x01].iter().enumerate() {
+            state_ref.push_op(StackOp::new(
+                RW::READ,
+                0,
+                StackAddress::from(1022 + i),
+                Word::from(*value),
+            ));
+        }
+
+        tx.steps_mut().push(step);
+        test_builder.end_tx(&mut tx, &block.geth_trace);
+        test_builder.block.txs_mut().push(tx);
+
+        // Compare first step bus mapping instance
+        assert_eq!(
+            builder.block.txs()[0].steps()[0].bus_mapping_instance,
+            test_builder.block.txs()[0].steps()[0].bus_mapping_instance,
+        );
+
+        // Compare containers
+        assert_eq!(builder.block.container, test_builder.block.container);
+
+        Ok(())
+    }
+
+    #[test]
+    fn jump_invalid_destination() {
+        let code = bytecode! {
+            PUSH1(0x42)
+            POP
+            PUSH1(0x05) // destination without a JUMPDEST
+            JUMP
+            STOP
+        };
+        let block = mock::BlockData::new_single_tx_trace_code(&code).unwrap();
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let steps = builder.block.txs()[0].steps();
+        // POP reads the popped word
+        assert_eq!(steps[1].op, OpcodeId::POP);
+        assert_eq!(steps[1].bus_mapping_instance.len(), 1);
+        // JUMP reads the destination before failing
+        assert_eq!(steps[3].op, OpcodeId::JUMP);
+        assert_eq!(steps[3].error, Some(ExecError::InvalidJump));
+        assert_eq!(steps[3].bus_mapping_instance.len(), 1);
+    }
+}
@@ -1,23 +1,41 @@
 use super::Opcode;
 use crate::circuit_input_builder::CircuitInputStateRef;
 use crate::eth_types::GethExecStep;
-use crate::Error;
+use crate::{
+    operation::{CallContextField, CallContextOp, RW},
+    Error,
+};
 
 /// Placeholder structure used to implement [`Opcode`] trait over it
 /// corresponding to the [`OpcodeId::STOP`](crate::evm::OpcodeId::STOP)
 /// `OpcodeId`. This is responsible of generating all of the associated
 /// operations and place them inside the trace's
 /// [`OperationContainer`](crate::operation::OperationContainer). In the case of
-/// STOP, it simply does not add anything.
+/// STOP, it reads the depth of the call, to know whether it ends the root
+/// call, and the caller id, which the return to the caller follows.
 #[derive(Debug, Copy, Clone)]
 pub(crate) struct Stop;
 
 impl Opcode for Stop {
     fn gen_associated_ops(
-        _state: &mut CircuitInputStateRef,
+        state: &mut CircuitInputStateRef,
         _steps: &[GethExecStep],
     ) -> Result<(), Error> {
-        // Stop does not generate any operations
+        // Call context reads of the depth, which is 1 when the root call ends
+        // the tx, and of the caller id
+        let call = state.call().clone();
+        for (field, value) in [
+            (CallContextField::Depth, call.depth),
+            (CallContextField::CallerId, call.caller_id),
+        ] {
+            state.push_op(CallContextOp::new(
+                RW::READ,
+                call.call_id,
+                field,
+                value.into(),
+            ));
+        }
+
         Ok(())
     }
 }
@@ -23,6 +23,7 @@ impl<const N: usize> Opcode for Swap<N> {
         let stack_b_position = step.stack.nth_last_filled(N);
         state.push_op(StackOp::new(
             RW::READ,
+            state.call().call_id,
             stack_b_position,
             stack_b_value_read,
         ));
@@ -30,6 +31,7 @@ impl<const N: usize> Opcode for Swap<N> {
         let stack_a_position = step.stack.last_filled();
         state.push_op(StackOp::new(
             RW::READ,
+            state.call().call_id,
             stack_a_position,
             stack_a_value_read,
         ));
@@ -37,11 +39,13 @@ impl<const N: usize> Opcode for Swap<N> {
         // Write a into b_position, write b into a_position
         state.push_op(StackOp::new(
             RW::WRITE,
+            state.call().call_id,
             stack_b_position,
             stack_a_value_read,
         ));
         state.push_op(StackOp::new(
             RW::WRITE,
+            state.call().call_id,
             stack_a_position,
             stack_b_value_read,
         ));
@@ -94,8 +98,9 @@ mod swap_tests {
             block.eth_block,
             block.block_ctants.clone(),
         );
-        let mut tx = Transaction::new(&block.eth_tx);
+        let mut tx = Transaction::new(0, &block.eth_tx);
         let mut tx_ctx = TransactionContext::new(&block.eth_tx);
+        test_builder.begin_tx(&mut tx).unwrap();
 
         // Generate steps corresponding to DUP1, DUP3, DUP5
         for (i, (a, b)) in [(6, 5), (5, 3), (3, 1)].iter().enumerate() {
@@ -113,14 +118,15 @@ mod swap_tests {
             let a_val = Word::from(*a);
             let b_val = Word::from(*b);
 
-            state_ref.push_op(StackOp::new(RW::READ, b_pos, b_val));
-            state_ref.push_op(StackOp::new(RW::READ, a_pos, a_val));
-            state_ref.push_op(StackOp::new(RW::WRITE, b_pos, a_val));
-            state_ref.push_op(StackOp::new(RW::WRITE, a_pos, b_val));
+            state_ref.push_op(StackOp::new(RW::READ, 0, b_pos, b_val));
+            state_ref.push_op(StackOp::new(RW::READ, 0, a_pos, a_val));
+            state_ref.push_op(StackOp::new(RW::WRITE, 0, b_pos, a_val));
+            state_ref.push_op(StackOp::new(RW::WRITE, 0, a_pos, b_val));
 
             tx.steps_mut().push(step);
         }
 
+        test_builder.end_tx(&mut tx, &block.geth_trace);
         test_builder.block.txs_mut().push(tx);
 
         // Compare first 3 steps bus mapping instance
@@ -19,14 +19,19 @@ pub struct BlockConstants {
     gas_limit: Word,
     chain_id: Word,
     base_fee: Word,
+    // Hashes of the previous blocks, up to 256, where the last one is the
+    // hash of the parent block.
+    history_hashes: Vec<Hash>,
 }
 
 impl BlockConstants {
-    /// Generate a BlockConstants from an ethereum block, useful for testing.
+    /// Generate a BlockConstants from an ethereum block and the hashes of its
+    /// previous blocks, useful for testing.
     pub fn from_eth_block<TX>(
         block: &Block<TX>,
         chain_id: &Word,
         &coinbase: &Address,
+        history_hashes: Vec<Hash>,
     ) -> Self {
         Self {
             hash: block.hash.unwrap(),
@@ -37,6 +42,7 @@ impl BlockConstants {
             gas_limit: block.gas_limit,
             chain_id: *chain_id,
             base_fee: block.base_fee_per_gas.unwrap(),
+            history_hashes,
         }
     }
 
@@ -54,6 +60,7 @@ impl BlockConstants {
             gas_limit: Word::from(15_000_000u64),
             chain_id: Word::one(),
             base_fee: Word::from(97u64),
+            history_hashes: Vec::new(),
         }
     }
 }
@@ -70,6 +77,7 @@ impl BlockConstants {
         gas_limit: Word,
         chain_id: Word,
         base_fee: Word,
+        history_hashes: Vec<Hash>,
     ) -> BlockConstants {
         BlockConstants {
             hash,
@@ -80,6 +88,7 @@ impl BlockConstants {
             gas_limit,
             chain_id,
             base_fee,
+            history_hashes,
         }
     }
     #[inline]
@@ -129,6 +138,28 @@ impl BlockConstants {
     pub fn base_fee(&self) -> &Word {
         &self.base_fee
     }
+
+    #[inline]
+    /// Return the hashes of the previous blocks, up to 256, ending with the
+    /// hash of the parent block.
+    pub fn history_hashes(&self) -> &[Hash] {
+        &self.history_hashes
+    }
+
+    /// Return the hash of the block `number` as seen by BLOCKHASH, which is
+    /// zero when the block is not one of the previous 256 blocks or its hash
+    /// is not known.
+    pub fn block_hash(&self, number: Word) -> Hash {
+        let current = Word::from(self.number.as_u64());
+        if number >= current || current - number > Word::from(256) {
+            return Hash::zero();
+        }
+        let distance = (current - number).as_usize();
+        self.history_hashes
+            .len()
+            .checked_sub(distance)
+            .map_or(Hash::zero(), |idx| self.history_hashes[idx])
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -142,6 +173,22 @@ impl From<(Target, usize)> for OperationRef {
             Target::Memory => Self(Target::Memory, op_ref_data.1),
             Target::Stack => Self(Target::Stack, op_ref_data.1),
             Target::Storage => Self(Target::Storage, op_ref_data.1),
+            Target::TxAccessListAccountStorage => {
+                Self(Target::TxAccessListAccountStorage, op_ref_data.1)
+            }
+            Target::TxRefund => Self(Target::TxRefund, op_ref_data.1),
+            Target::CallContext => Self(Target::CallContext, op_ref_data.1),
+            Target::AccountNonce => Self(Target::AccountNonce, op_ref_data.1),
+            Target::AccountBalance => {
+                Self(Target::AccountBalance, op_ref_data.1)
+            }
+            Target::AccountCodeHash => {
+                Self(Target::AccountCodeHash, op_ref_data.1)
+            }
+            Target::TxLog => Self(Target::TxLog, op_ref_data.1),
+            Target::TxAccessListAccount => {
+                Self(Target::TxAccessListAccount, op_ref_data.1)
+            }
         }
     }
 }
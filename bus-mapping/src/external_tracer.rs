@@ -1,5 +1,5 @@
 //! This module generates traces by connecting to an external tracer
-use crate::eth_types::{self, Address, GethExecStep, Word};
+use crate::eth_types::{self, Address, Bytes, GethExecStep, Word};
 use crate::BlockConstants;
 use crate::Error;
 use geth_utils;
@@ -12,8 +12,14 @@ pub struct Transaction {
     pub origin: Address,
     /// Gas Limit
     pub gas_limit: Word,
+    /// Gas Price
+    pub gas_price: Word,
     /// Target Address
     pub target: Address,
+    /// Value sent to the target
+    pub value: Word,
+    /// Call Data
+    pub call_data: Bytes,
 }
 
 impl Transaction {
@@ -22,7 +28,10 @@ impl Transaction {
         Self {
             origin: tx.from,
             gas_limit: tx.gas,
+            gas_price: tx.gas_price.unwrap_or_default(),
             target: tx.to.unwrap(),
+            value: tx.value,
+            call_data: tx.input.clone(),
         }
     }
 }
@@ -10,3 +10,5 @@ pub const GETH_ERR_OUT_OF_GAS: &str = "out of gas";
 pub const GETH_ERR_GAS_UINT_OVERFLOW: &str = "gas uint64 overflow";
 /// Geth error message for write protection
 pub const GETH_ERR_WRITE_PROTECTION: &str = "write protection";
+/// Geth error message for SSTORE with less gas than the reentrancy sentry
+pub const GETH_ERR_SSTORE_SENTRY: &str = "not enough gas for reentrancy sentry";
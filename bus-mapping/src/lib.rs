@@ -112,6 +112,7 @@
 //!     Word::zero(),
 //!     Word::zero(),
 //!     Word::zero(),
+//!     Vec::new(),
 //! );
 //!
 //! // We use some mock data as context for the trace
@@ -226,6 +227,6 @@ pub mod eth_types;
 pub(crate) mod geth_errors;
 pub mod mock;
 pub mod rpc;
-pub(crate) mod state_db;
+pub mod state_db;
 pub use error::Error;
 pub use exec_trace::BlockConstants;
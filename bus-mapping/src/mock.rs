@@ -35,6 +35,13 @@ pub fn new_block() -> eth_types::Block<()> {
     }
 }
 
+/// Generate the mock hashes of the 256 blocks previous to `block`, where the
+/// hash of the block `n` is `n`, useful for tests.
+pub fn new_history_hashes<TX>(block: &eth_types::Block<TX>) -> Vec<Hash> {
+    let number = block.number.unwrap().as_u64();
+    (number - 256..number).map(Hash::from_low_u64_be).collect()
+}
+
 /// Generate a new mock transaction with preloaded data, useful for tests.
 pub fn new_tx<TX>(block: &eth_types::Block<TX>) -> eth_types::Transaction {
     eth_types::Transaction {
@@ -77,20 +84,23 @@ pub struct BlockData {
 impl BlockData {
     /// Create a new block with a single tx that executes the code found in the
     /// account with address 0x0 (which can call code in the other accounts),
-    /// with the given gas limit.
+    /// with the given gas limit and call data.
     /// The trace will be generated automatically with the external_tracer
     /// from the accounts code.
     fn new_single_tx_trace_accounts_gas(
         accounts: &[external_tracer::Account],
         gas: Gas,
+        call_data: Bytes,
     ) -> Result<Self, Error> {
         let eth_block = new_block();
         let mut eth_tx = new_tx(&eth_block);
         eth_tx.gas = Word::from(gas.0);
+        eth_tx.input = call_data;
         let block_ctants = BlockConstants::from_eth_block(
             &eth_block,
             &eth_types::Word::one(),
             &address!("0x00000000000000000000000000000000c014ba5e"),
+            new_history_hashes(&eth_block),
         );
         let tracer_tx = external_tracer::Transaction::from_eth_tx(&eth_tx);
         let geth_trace = eth_types::GethExecTrace {
@@ -118,7 +128,11 @@ impl BlockData {
     fn new_single_tx_trace_accounts(
         accounts: &[external_tracer::Account],
     ) -> Result<Self, Error> {
-        Self::new_single_tx_trace_accounts_gas(accounts, Gas(1_000_000u64))
+        Self::new_single_tx_trace_accounts_gas(
+            accounts,
+            Gas(1_000_000u64),
+            Bytes::default(),
+        )
     }
 
     /// Create a new block with a single tx that executes the code passed by
@@ -137,7 +151,26 @@ impl BlockData {
         gas: Gas,
     ) -> Result<Self, Error> {
         let tracer_account = new_tracer_account(code);
-        Self::new_single_tx_trace_accounts_gas(&[tracer_account], gas)
+        Self::new_single_tx_trace_accounts_gas(
+            &[tracer_account],
+            gas,
+            Bytes::default(),
+        )
+    }
+
+    /// Create a new block with a single tx with the given call data that
+    /// executes the code passed by argument.  The trace will be generated
+    /// automatically with the external_tracer from the code.
+    pub fn new_single_tx_trace_code_calldata(
+        code: &Bytecode,
+        call_data: Bytes,
+    ) -> Result<Self, Error> {
+        let tracer_account = new_tracer_account(code);
+        Self::new_single_tx_trace_accounts_gas(
+            &[tracer_account],
+            Gas(1_000_000u64),
+            call_data,
+        )
     }
 
     /// Create a new block with a single tx that executes the code_a passed by
@@ -164,7 +197,19 @@ impl BlockData {
     pub fn new_single_tx_trace_code_at_start(
         code: &Bytecode,
     ) -> Result<Self, Error> {
-        let mut mock = Self::new_single_tx_trace_code(code)?;
+        Self::new_single_tx_trace_code_calldata_at_start(code, Bytes::default())
+    }
+
+    /// Create a new block with a single tx with the given call data that
+    /// executes the code passed by argument.  The trace will be generated
+    /// automatically with the external_tracer from the code.  The trace steps
+    /// will start at the "start" position as tagged in the code.
+    pub fn new_single_tx_trace_code_calldata_at_start(
+        code: &Bytecode,
+        call_data: Bytes,
+    ) -> Result<Self, Error> {
+        let mut mock =
+            Self::new_single_tx_trace_code_calldata(code, call_data)?;
         mock.geth_trace.struct_logs =
             mock.geth_trace.struct_logs[code.get_pos("start")..].to_vec();
         Ok(mock)
@@ -181,6 +226,7 @@ impl BlockData {
             &eth_block,
             &eth_types::Word::one(),
             &crate::address!("0x00000000000000000000000000000000c014ba5e"),
+            new_history_hashes(&eth_block),
         );
         let geth_trace = eth_types::GethExecTrace {
             gas: Gas(eth_tx.gas.as_u64()),
@@ -202,7 +248,10 @@ pub fn new_tracer_tx() -> external_tracer::Transaction {
     external_tracer::Transaction {
         origin: address!("0x00000000000000000000000000000000c014ba5e"),
         gas_limit: Word::from(1_000_000u64),
+        gas_price: Word::zero(),
         target: Address::zero(),
+        value: Word::zero(),
+        call_data: Bytes::default(),
     }
 }
 
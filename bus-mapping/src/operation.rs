@@ -1,5 +1,8 @@
 //! Collection of structs and functions used to:
-//! - Define the internals of a [`MemoryOp`], [`StackOp`] and [`StorageOp`].
+//! - Define the internals of a [`MemoryOp`], [`StackOp`], [`StorageOp`],
+//!   [`TxAccessListAccountStorageOp`], [`TxRefundOp`], [`CallContextOp`],
+//!   [`AccountNonceOp`], [`AccountBalanceOp`], [`AccountCodeHashOp`],
+//!   [`TxLogOp`] and [`TxAccessListAccountOp`].
 //! - Define the actual operation types and a wrapper over them (the
 //!   [`Operation`] enum).
 //! - Define structures that interact with operations such as
@@ -7,7 +10,7 @@
 pub(crate) mod container;
 
 pub use super::evm::{GlobalCounter, MemoryAddress, StackAddress};
-use crate::eth_types::{Address, Word};
+use crate::eth_types::{Address, Word, H256};
 pub use container::OperationContainer;
 use core::cmp::Ordering;
 use core::fmt::Debug;
@@ -36,7 +39,8 @@ impl RW {
     }
 }
 
-/// Enum used to differenciate between EVM Stack, Memory and Storage operations.
+/// Enum used to differenciate between EVM Stack, Memory, Storage and
+/// transaction-level operations.
 #[derive(Debug, Clone, PartialEq, Eq, Copy)]
 pub enum Target {
     /// Means the target of the operation is the Memory.
@@ -45,6 +49,25 @@ pub enum Target {
     Stack,
     /// Means the target of the operation is the Storage.
     Storage,
+    /// Means the target of the operation is the storage slots access list of
+    /// the transaction (EIP-2929).
+    TxAccessListAccountStorage,
+    /// Means the target of the operation is the gas refund counter of the
+    /// transaction.
+    TxRefund,
+    /// Means the target of the operation is the context of a call.
+    CallContext,
+    /// Means the target of the operation is the nonce of an account.
+    AccountNonce,
+    /// Means the target of the operation is the balance of an account.
+    AccountBalance,
+    /// Means the target of the operation is the code hash of an account.
+    AccountCodeHash,
+    /// Means the target of the operation is a log emitted by the transaction.
+    TxLog,
+    /// Means the target of the operation is the accounts access list of the
+    /// transaction (EIP-2929).
+    TxAccessListAccount,
 }
 
 /// Trait used for Operation Kinds.
@@ -60,14 +83,25 @@ pub trait Op: Eq + Ord {
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct MemoryOp {
     rw: RW,
+    call_id: usize,
     addr: MemoryAddress,
     value: u8,
 }
 
 impl MemoryOp {
     /// Create a new instance of a `MemoryOp` from it's components.
-    pub fn new(rw: RW, addr: MemoryAddress, value: u8) -> MemoryOp {
-        MemoryOp { rw, addr, value }
+    pub fn new(
+        rw: RW,
+        call_id: usize,
+        addr: MemoryAddress,
+        value: u8,
+    ) -> MemoryOp {
+        MemoryOp {
+            rw,
+            call_id,
+            addr,
+            value,
+        }
     }
 
     /// Returns the internal [`RW`] which says whether the operation corresponds
@@ -81,6 +115,11 @@ impl MemoryOp {
         Target::Memory
     }
 
+    /// Returns the id of the call whose memory is accessed.
+    pub const fn call_id(&self) -> usize {
+        self.call_id
+    }
+
     /// Returns the [`MemoryAddress`] associated to this Operation.
     pub const fn address(&self) -> &MemoryAddress {
         &self.addr
@@ -106,7 +145,10 @@ impl PartialOrd for MemoryOp {
 
 impl Ord for MemoryOp {
     fn cmp(&self, other: &MemoryOp) -> Ordering {
-        self.address().cmp(other.address())
+        match self.call_id().cmp(&other.call_id()) {
+            Ordering::Equal => self.address().cmp(other.address()),
+            ord => ord,
+        }
     }
 }
 
@@ -116,14 +158,25 @@ impl Ord for MemoryOp {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct StackOp {
     rw: RW,
+    call_id: usize,
     addr: StackAddress,
     value: Word,
 }
 
 impl StackOp {
     /// Create a new instance of a `MemoryOp` from it's components.
-    pub const fn new(rw: RW, addr: StackAddress, value: Word) -> StackOp {
-        StackOp { rw, addr, value }
+    pub const fn new(
+        rw: RW,
+        call_id: usize,
+        addr: StackAddress,
+        value: Word,
+    ) -> StackOp {
+        StackOp {
+            rw,
+            call_id,
+            addr,
+            value,
+        }
     }
 
     /// Returns the internal [`RW`] which says whether the operation corresponds
@@ -137,6 +190,11 @@ impl StackOp {
         Target::Stack
     }
 
+    /// Returns the id of the call whose stack is accessed.
+    pub const fn call_id(&self) -> usize {
+        self.call_id
+    }
+
     /// Returns the [`StackAddress`] associated to this Operation.
     pub const fn address(&self) -> &StackAddress {
         &self.addr
@@ -162,13 +220,19 @@ impl PartialOrd for StackOp {
 
 impl Ord for StackOp {
     fn cmp(&self, other: &StackOp) -> Ordering {
-        self.address().cmp(other.address())
+        match self.call_id().cmp(&other.call_id()) {
+            Ordering::Equal => self.address().cmp(other.address()),
+            ord => ord,
+        }
     }
 }
 
 /// Represents a [`READ`](RW::READ)/[`WRITE`](RW::WRITE) into the storage
 /// implied by an specific [`OpcodeId`](crate::evm::opcodes::ids::OpcodeId) of
-/// the [`ExecStep`](crate::circuit_input_builder::ExecStep).
+/// the [`ExecStep`](crate::circuit_input_builder::ExecStep).  The operation
+/// also carries the transaction doing it and the value of the slot at the
+/// beginning of that transaction, which sets the gas cost and the refund of
+/// SSTORE (EIP-2200).
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct StorageOp {
     rw: RW,
@@ -176,6 +240,8 @@ pub struct StorageOp {
     key: Word,
     value: Word,
     value_prev: Word,
+    tx_id: usize,
+    committed_value: Word,
 }
 
 impl StorageOp {
@@ -186,6 +252,8 @@ impl StorageOp {
         key: Word,
         value: Word,
         value_prev: Word,
+        tx_id: usize,
+        committed_value: Word,
     ) -> StorageOp {
         StorageOp {
             rw,
@@ -193,6 +261,8 @@ impl StorageOp {
             key,
             value,
             value_prev,
+            tx_id,
+            committed_value,
         }
     }
 
@@ -226,6 +296,16 @@ impl StorageOp {
     pub const fn value_prev(&self) -> &Word {
         &self.value_prev
     }
+
+    /// Returns the index of the transaction in the block, starting at 1.
+    pub const fn tx_id(&self) -> usize {
+        self.tx_id
+    }
+
+    /// Returns the [`Word`] at key found at the beginning of the transaction.
+    pub const fn committed_value(&self) -> &Word {
+        &self.committed_value
+    }
 }
 
 impl Op for StorageOp {
@@ -249,8 +329,729 @@ impl Ord for StorageOp {
     }
 }
 
+/// Represents a change in the warm status of a storage slot in the access list
+/// of a transaction (EIP-2929), implied by an specific
+/// [`OpcodeId`](crate::evm::opcodes::ids::OpcodeId) of the
+/// [`ExecStep`](crate::circuit_input_builder::ExecStep).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxAccessListAccountStorageOp {
+    rw: RW,
+    tx_id: usize,
+    address: Address,
+    key: Word,
+    value: bool,
+    value_prev: bool,
+}
+
+impl TxAccessListAccountStorageOp {
+    /// Create a new instance of a `TxAccessListAccountStorageOp` from it's
+    /// components.
+    pub const fn new(
+        rw: RW,
+        tx_id: usize,
+        address: Address,
+        key: Word,
+        value: bool,
+        value_prev: bool,
+    ) -> TxAccessListAccountStorageOp {
+        TxAccessListAccountStorageOp {
+            rw,
+            tx_id,
+            address,
+            key,
+            value,
+            value_prev,
+        }
+    }
+
+    /// Returns the internal [`RW`] which says whether the operation corresponds
+    /// to a Read or a Write into the access list.
+    pub const fn rw(&self) -> RW {
+        self.rw
+    }
+
+    /// Returns the [`Target`] (operation type) of this operation.
+    pub const fn target(&self) -> Target {
+        Target::TxAccessListAccountStorage
+    }
+
+    /// Returns the index of the transaction in the block, starting at 1.
+    pub const fn tx_id(&self) -> usize {
+        self.tx_id
+    }
+
+    /// Returns the [`Address`] of the account owning the storage slot.
+    pub const fn address(&self) -> &Address {
+        &self.address
+    }
+
+    /// Returns the [`Word`] used as key of the storage slot.
+    pub const fn key(&self) -> &Word {
+        &self.key
+    }
+
+    /// Returns true if the storage slot is warm after this operation.
+    pub const fn value(&self) -> bool {
+        self.value
+    }
+
+    /// Returns true if the storage slot was warm before this operation.
+    pub const fn value_prev(&self) -> bool {
+        self.value_prev
+    }
+}
+
+impl Op for TxAccessListAccountStorageOp {
+    fn into_enum(self) -> OpEnum {
+        OpEnum::TxAccessListAccountStorage(self)
+    }
+}
+
+impl PartialOrd for TxAccessListAccountStorageOp {
+    fn partial_cmp(
+        &self,
+        other: &TxAccessListAccountStorageOp,
+    ) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for TxAccessListAccountStorageOp {
+    fn cmp(&self, other: &TxAccessListAccountStorageOp) -> Ordering {
+        (self.tx_id, self.address(), self.key()).cmp(&(
+            other.tx_id,
+            other.address(),
+            other.key(),
+        ))
+    }
+}
+
+/// Represents a change in the warm status of an account in the access list of
+/// a transaction (EIP-2929), implied by an specific
+/// [`OpcodeId`](crate::evm::opcodes::ids::OpcodeId) of the
+/// [`ExecStep`](crate::circuit_input_builder::ExecStep).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxAccessListAccountOp {
+    rw: RW,
+    tx_id: usize,
+    address: Address,
+    value: bool,
+    value_prev: bool,
+}
+
+impl TxAccessListAccountOp {
+    /// Create a new instance of a `TxAccessListAccountOp` from it's
+    /// components.
+    pub const fn new(
+        rw: RW,
+        tx_id: usize,
+        address: Address,
+        value: bool,
+        value_prev: bool,
+    ) -> TxAccessListAccountOp {
+        TxAccessListAccountOp {
+            rw,
+            tx_id,
+            address,
+            value,
+            value_prev,
+        }
+    }
+
+    /// Returns the internal [`RW`] which says whether the operation corresponds
+    /// to a Read or a Write into the access list.
+    pub const fn rw(&self) -> RW {
+        self.rw
+    }
+
+    /// Returns the [`Target`] (operation type) of this operation.
+    pub const fn target(&self) -> Target {
+        Target::TxAccessListAccount
+    }
+
+    /// Returns the index of the transaction in the block, starting at 1.
+    pub const fn tx_id(&self) -> usize {
+        self.tx_id
+    }
+
+    /// Returns the [`Address`] of the account.
+    pub const fn address(&self) -> &Address {
+        &self.address
+    }
+
+    /// Returns true if the account is warm after this operation.
+    pub const fn value(&self) -> bool {
+        self.value
+    }
+
+    /// Returns true if the account was warm before this operation.
+    pub const fn value_prev(&self) -> bool {
+        self.value_prev
+    }
+}
+
+impl Op for TxAccessListAccountOp {
+    fn into_enum(self) -> OpEnum {
+        OpEnum::TxAccessListAccount(self)
+    }
+}
+
+impl PartialOrd for TxAccessListAccountOp {
+    fn partial_cmp(&self, other: &TxAccessListAccountOp) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for TxAccessListAccountOp {
+    fn cmp(&self, other: &TxAccessListAccountOp) -> Ordering {
+        (self.tx_id, self.address()).cmp(&(other.tx_id, other.address()))
+    }
+}
+
+/// Represents a [`READ`](RW::READ)/[`WRITE`](RW::WRITE) into the gas refund
+/// counter of a transaction, implied by an specific
+/// [`OpcodeId`](crate::evm::opcodes::ids::OpcodeId) of the
+/// [`ExecStep`](crate::circuit_input_builder::ExecStep).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxRefundOp {
+    rw: RW,
+    tx_id: usize,
+    value: u64,
+    value_prev: u64,
+}
+
+impl TxRefundOp {
+    /// Create a new instance of a `TxRefundOp` from it's components.
+    pub const fn new(
+        rw: RW,
+        tx_id: usize,
+        value: u64,
+        value_prev: u64,
+    ) -> TxRefundOp {
+        TxRefundOp {
+            rw,
+            tx_id,
+            value,
+            value_prev,
+        }
+    }
+
+    /// Returns the internal [`RW`] which says whether the operation corresponds
+    /// to a Read or a Write into the refund counter.
+    pub const fn rw(&self) -> RW {
+        self.rw
+    }
+
+    /// Returns the [`Target`] (operation type) of this operation.
+    pub const fn target(&self) -> Target {
+        Target::TxRefund
+    }
+
+    /// Returns the index of the transaction in the block, starting at 1.
+    pub const fn tx_id(&self) -> usize {
+        self.tx_id
+    }
+
+    /// Returns the refund counter after this operation.
+    pub const fn value(&self) -> u64 {
+        self.value
+    }
+
+    /// Returns the refund counter before this operation.
+    pub const fn value_prev(&self) -> u64 {
+        self.value_prev
+    }
+}
+
+impl Op for TxRefundOp {
+    fn into_enum(self) -> OpEnum {
+        OpEnum::TxRefund(self)
+    }
+}
+
+impl PartialOrd for TxRefundOp {
+    fn partial_cmp(&self, other: &TxRefundOp) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for TxRefundOp {
+    fn cmp(&self, other: &TxRefundOp) -> Ordering {
+        self.tx_id.cmp(&other.tx_id)
+    }
+}
+
+/// Field of the context of a call accessed by a [`CallContextOp`].  The fields
+/// up to [`CallContextField::GasLeft`] are set by the caller when the call is
+/// created.  The following ones hold the state of a caller while it's waiting
+/// for a callee to return, and the return data of its last callee.
+/// [`CallContextField::IsPersistent`] is written when the call reverts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CallContextField {
+    /// Id of the caller, or 0 for the root call of a transaction.
+    CallerId = 1,
+    /// Index of the transaction in the block, starting at 1.
+    TxId,
+    /// Depth of the call, starting at 1 for the root call.
+    Depth,
+    /// Address of the caller.
+    CallerAddress,
+    /// Address of the account whose storage and balance are used.
+    CalleeAddress,
+    /// Address of the account whose code is executed, which differs from
+    /// `CalleeAddress` in CALLCODE and DELEGATECALL.
+    CodeAddress,
+    /// Offset of the calldata in the memory of the caller.
+    CallDataOffset,
+    /// Length of the calldata.
+    CallDataLength,
+    /// Offset in the memory of the caller where the return data is copied.
+    ReturnDataOffset,
+    /// Length of the memory region where the return data is copied.
+    ReturnDataLength,
+    /// Value in wei sent with the call.
+    Value,
+    /// Whether the call and its sub-calls can't modify the state.
+    IsStatic,
+    /// Gas given to the call when it's created, which is refunded to its
+    /// caller when it returns, as the caller already paid for it.
+    Gas,
+    /// Gas left of a caller when it's waiting for a callee.
+    GasLeft,
+    /// Program counter of the caller after the call.
+    ProgramCounter,
+    /// Stack pointer of the caller after the call, pointing to the success
+    /// flag of the call.
+    StackPointer,
+    /// Memory size of the caller in bytes.
+    MemorySize,
+    /// State write counter of the caller.
+    StateWriteCounter,
+    /// Hash of the code executed by a caller, which resumes its execution when
+    /// its callee returns.
+    CodeHash,
+    /// Id of the last callee, whose return data is accessible by the caller.
+    LastCalleeId,
+    /// Offset of the return data of the last callee in its memory.
+    LastCalleeReturnDataOffset,
+    /// Length of the return data of the last callee.
+    LastCalleeReturnDataLength,
+    /// Whether the state changes of the call are kept, which stops being the
+    /// case when the call reverts.
+    IsPersistent,
+    /// Whether the call succeeds, which is the result pushed on the stack of
+    /// the caller.
+    IsSuccess,
+}
+
+/// Represents a [`READ`](RW::READ)/[`WRITE`](RW::WRITE) of a field of the
+/// context of a call, implied by an specific
+/// [`OpcodeId`](crate::evm::opcodes::ids::OpcodeId) of the
+/// [`ExecStep`](crate::circuit_input_builder::ExecStep).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallContextOp {
+    rw: RW,
+    call_id: usize,
+    field: CallContextField,
+    value: Word,
+}
+
+impl CallContextOp {
+    /// Create a new instance of a `CallContextOp` from it's components.
+    pub const fn new(
+        rw: RW,
+        call_id: usize,
+        field: CallContextField,
+        value: Word,
+    ) -> CallContextOp {
+        CallContextOp {
+            rw,
+            call_id,
+            field,
+            value,
+        }
+    }
+
+    /// Returns the internal [`RW`] which says whether the operation corresponds
+    /// to a Read or a Write into the call context.
+    pub const fn rw(&self) -> RW {
+        self.rw
+    }
+
+    /// Returns the [`Target`] (operation type) of this operation.
+    pub const fn target(&self) -> Target {
+        Target::CallContext
+    }
+
+    /// Returns the id of the call whose context is accessed.
+    pub const fn call_id(&self) -> usize {
+        self.call_id
+    }
+
+    /// Returns the [`CallContextField`] accessed by this operation.
+    pub const fn field(&self) -> CallContextField {
+        self.field
+    }
+
+    /// Returns the [`Word`] read or written by this operation.
+    pub const fn value(&self) -> &Word {
+        &self.value
+    }
+}
+
+impl Op for CallContextOp {
+    fn into_enum(self) -> OpEnum {
+        OpEnum::CallContext(self)
+    }
+}
+
+impl PartialOrd for CallContextOp {
+    fn partial_cmp(&self, other: &CallContextOp) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for CallContextOp {
+    fn cmp(&self, other: &CallContextOp) -> Ordering {
+        match self.call_id().cmp(&other.call_id()) {
+            Ordering::Equal => self.field().cmp(&other.field()),
+            ord => ord,
+        }
+    }
+}
+
+/// Represents a [`READ`](RW::READ)/[`WRITE`](RW::WRITE) into the nonce of an
+/// account, implied by an specific
+/// [`OpcodeId`](crate::evm::opcodes::ids::OpcodeId) of the
+/// [`ExecStep`](crate::circuit_input_builder::ExecStep).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountNonceOp {
+    rw: RW,
+    address: Address,
+    value: Word,
+    value_prev: Word,
+}
+
+impl AccountNonceOp {
+    /// Create a new instance of a `AccountNonceOp` from it's components.
+    pub const fn new(
+        rw: RW,
+        address: Address,
+        value: Word,
+        value_prev: Word,
+    ) -> AccountNonceOp {
+        AccountNonceOp {
+            rw,
+            address,
+            value,
+            value_prev,
+        }
+    }
+
+    /// Returns the internal [`RW`] which says whether the operation corresponds
+    /// to a Read or a Write into the nonce.
+    pub const fn rw(&self) -> RW {
+        self.rw
+    }
+
+    /// Returns the [`Target`] (operation type) of this operation.
+    pub const fn target(&self) -> Target {
+        Target::AccountNonce
+    }
+
+    /// Returns the [`Address`] of the account.
+    pub const fn address(&self) -> &Address {
+        &self.address
+    }
+
+    /// Returns the [`Word`] read or written by this operation.
+    pub const fn value(&self) -> &Word {
+        &self.value
+    }
+
+    /// Returns the [`Word`] found previous to this operation.
+    pub const fn value_prev(&self) -> &Word {
+        &self.value_prev
+    }
+}
+
+impl Op for AccountNonceOp {
+    fn into_enum(self) -> OpEnum {
+        OpEnum::AccountNonce(self)
+    }
+}
+
+impl PartialOrd for AccountNonceOp {
+    fn partial_cmp(&self, other: &AccountNonceOp) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for AccountNonceOp {
+    fn cmp(&self, other: &AccountNonceOp) -> Ordering {
+        self.address().cmp(other.address())
+    }
+}
+
+/// Represents a [`READ`](RW::READ)/[`WRITE`](RW::WRITE) into the balance of an
+/// account, implied by an specific
+/// [`OpcodeId`](crate::evm::opcodes::ids::OpcodeId) of the
+/// [`ExecStep`](crate::circuit_input_builder::ExecStep).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountBalanceOp {
+    rw: RW,
+    address: Address,
+    value: Word,
+    value_prev: Word,
+}
+
+impl AccountBalanceOp {
+    /// Create a new instance of a `AccountBalanceOp` from it's components.
+    pub const fn new(
+        rw: RW,
+        address: Address,
+        value: Word,
+        value_prev: Word,
+    ) -> AccountBalanceOp {
+        AccountBalanceOp {
+            rw,
+            address,
+            value,
+            value_prev,
+        }
+    }
+
+    /// Returns the internal [`RW`] which says whether the operation corresponds
+    /// to a Read or a Write into the balance.
+    pub const fn rw(&self) -> RW {
+        self.rw
+    }
+
+    /// Returns the [`Target`] (operation type) of this operation.
+    pub const fn target(&self) -> Target {
+        Target::AccountBalance
+    }
+
+    /// Returns the [`Address`] of the account.
+    pub const fn address(&self) -> &Address {
+        &self.address
+    }
+
+    /// Returns the [`Word`] read or written by this operation.
+    pub const fn value(&self) -> &Word {
+        &self.value
+    }
+
+    /// Returns the [`Word`] found previous to this operation.
+    pub const fn value_prev(&self) -> &Word {
+        &self.value_prev
+    }
+}
+
+impl Op for AccountBalanceOp {
+    fn into_enum(self) -> OpEnum {
+        OpEnum::AccountBalance(self)
+    }
+}
+
+impl PartialOrd for AccountBalanceOp {
+    fn partial_cmp(&self, other: &AccountBalanceOp) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for AccountBalanceOp {
+    fn cmp(&self, other: &AccountBalanceOp) -> Ordering {
+        self.address().cmp(other.address())
+    }
+}
+
+/// Represents a [`READ`](RW::READ)/[`WRITE`](RW::WRITE) into the code hash of
+/// an account, implied by an specific
+/// [`OpcodeId`](crate::evm::opcodes::ids::OpcodeId) of the
+/// [`ExecStep`](crate::circuit_input_builder::ExecStep).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountCodeHashOp {
+    rw: RW,
+    address: Address,
+    value: H256,
+    value_prev: H256,
+}
+
+impl AccountCodeHashOp {
+    /// Create a new instance of a `AccountCodeHashOp` from it's components.
+    pub const fn new(
+        rw: RW,
+        address: Address,
+        value: H256,
+        value_prev: H256,
+    ) -> AccountCodeHashOp {
+        AccountCodeHashOp {
+            rw,
+            address,
+            value,
+            value_prev,
+        }
+    }
+
+    /// Returns the internal [`RW`] which says whether the operation corresponds
+    /// to a Read or a Write into the code hash.
+    pub const fn rw(&self) -> RW {
+        self.rw
+    }
+
+    /// Returns the [`Target`] (operation type) of this operation.
+    pub const fn target(&self) -> Target {
+        Target::AccountCodeHash
+    }
+
+    /// Returns the [`Address`] of the account.
+    pub const fn address(&self) -> &Address {
+        &self.address
+    }
+
+    /// Returns the [`H256`] read or written by this operation.
+    pub const fn value(&self) -> &H256 {
+        &self.value
+    }
+
+    /// Returns the [`H256`] found previous to this operation.
+    pub const fn value_prev(&self) -> &H256 {
+        &self.value_prev
+    }
+}
+
+impl Op for AccountCodeHashOp {
+    fn into_enum(self) -> OpEnum {
+        OpEnum::AccountCodeHash(self)
+    }
+}
+
+impl PartialOrd for AccountCodeHashOp {
+    fn partial_cmp(&self, other: &AccountCodeHashOp) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for AccountCodeHashOp {
+    fn cmp(&self, other: &AccountCodeHashOp) -> Ordering {
+        self.address().cmp(other.address())
+    }
+}
+
+/// Field of a log emitted by a transaction, written by a [`TxLogOp`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TxLogField {
+    /// Address of the account emitting the log.
+    Address = 1,
+    /// Topic of the log at the index of the operation.
+    Topic,
+    /// Byte of the data of the log at the index of the operation.
+    Data,
+}
+
+/// Represents a [`WRITE`](RW::WRITE) of a field of a log emitted by a
+/// transaction, implied by an specific
+/// [`OpcodeId`](crate::evm::opcodes::ids::OpcodeId) of the
+/// [`ExecStep`](crate::circuit_input_builder::ExecStep).  The logs of a
+/// transaction are identified by the order in which they are emitted, starting
+/// at 1, and are what the receipt of the transaction is built from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxLogOp {
+    rw: RW,
+    tx_id: usize,
+    log_id: usize,
+    field: TxLogField,
+    index: usize,
+    value: Word,
+}
+
+impl TxLogOp {
+    /// Create a new instance of a `TxLogOp` from it's components.
+    pub const fn new(
+        rw: RW,
+        tx_id: usize,
+        log_id: usize,
+        field: TxLogField,
+        index: usize,
+        value: Word,
+    ) -> TxLogOp {
+        TxLogOp {
+            rw,
+            tx_id,
+            log_id,
+            field,
+            index,
+            value,
+        }
+    }
+
+    /// Returns the internal [`RW`] which says whether the operation corresponds
+    /// to a Read or a Write into the log.
+    pub const fn rw(&self) -> RW {
+        self.rw
+    }
+
+    /// Returns the [`Target`] (operation type) of this operation.
+    pub const fn target(&self) -> Target {
+        Target::TxLog
+    }
+
+    /// Returns the index of the transaction in the block, starting at 1.
+    pub const fn tx_id(&self) -> usize {
+        self.tx_id
+    }
+
+    /// Returns the index of the log in the transaction, starting at 1.
+    pub const fn log_id(&self) -> usize {
+        self.log_id
+    }
+
+    /// Returns the [`TxLogField`] written by this operation.
+    pub const fn field(&self) -> TxLogField {
+        self.field
+    }
+
+    /// Returns the index of the topic or of the data byte written by this
+    /// operation, which is 0 for the address.
+    pub const fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the [`Word`] written by this operation.
+    pub const fn value(&self) -> &Word {
+        &self.value
+    }
+}
+
+impl Op for TxLogOp {
+    fn into_enum(self) -> OpEnum {
+        OpEnum::TxLog(self)
+    }
+}
+
+impl PartialOrd for TxLogOp {
+    fn partial_cmp(&self, other: &TxLogOp) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for TxLogOp {
+    fn cmp(&self, other: &TxLogOp) -> Ordering {
+        (self.tx_id, self.log_id, self.field, self.index).cmp(&(
+            other.tx_id,
+            other.log_id,
+            other.field,
+            other.index,
+        ))
+    }
+}
+
 /// Generic enum that wraps over all the operation types possible.
-/// In particular [`StackOp`], [`MemoryOp`] and [`StorageOp`].
+/// In particular [`StackOp`], [`MemoryOp`], [`StorageOp`],
+/// [`TxAccessListAccountStorageOp`], [`TxRefundOp`], [`CallContextOp`],
+/// [`AccountNonceOp`], [`AccountBalanceOp`], [`AccountCodeHashOp`],
+/// [`TxLogOp`] and [`TxAccessListAccountOp`].
 #[derive(Debug, Clone)]
 pub enum OpEnum {
     /// Doc
@@ -259,6 +1060,94 @@ pub enum OpEnum {
     Memory(MemoryOp),
     /// Doc
     Storage(StorageOp),
+    /// Doc
+    TxAccessListAccountStorage(TxAccessListAccountStorageOp),
+    /// Doc
+    TxRefund(TxRefundOp),
+    /// Doc
+    CallContext(CallContextOp),
+    /// Doc
+    AccountNonce(AccountNonceOp),
+    /// Doc
+    AccountBalance(AccountBalanceOp),
+    /// Doc
+    AccountCodeHash(AccountCodeHashOp),
+    /// Doc
+    TxLog(TxLogOp),
+    /// Doc
+    TxAccessListAccount(TxAccessListAccountOp),
+}
+
+impl OpEnum {
+    /// Returns the write that undoes this operation when the call doing it
+    /// fails, which swaps its value and its previous value.  Returns `None`
+    /// for the operations that don't change the state: the reads, and the
+    /// operations on the stack, the memory, the context of a call and the
+    /// logs.
+    pub fn reverse(&self) -> Option<OpEnum> {
+        Some(match self {
+            OpEnum::Storage(op) if op.rw().is_write() => {
+                OpEnum::Storage(StorageOp::new(
+                    RW::WRITE,
+                    op.address,
+                    op.key,
+                    op.value_prev,
+                    op.value,
+                    op.tx_id,
+                    op.committed_value,
+                ))
+            }
+            OpEnum::TxAccessListAccountStorage(op) if op.rw().is_write() => {
+                OpEnum::TxAccessListAccountStorage(
+                    TxAccessListAccountStorageOp::new(
+                        RW::WRITE,
+                        op.tx_id,
+                        op.address,
+                        op.key,
+                        op.value_prev,
+                        op.value,
+                    ),
+                )
+            }
+            OpEnum::TxAccessListAccount(op) if op.rw().is_write() => {
+                OpEnum::TxAccessListAccount(TxAccessListAccountOp::new(
+                    RW::WRITE,
+                    op.tx_id,
+                    op.address,
+                    op.value_prev,
+                    op.value,
+                ))
+            }
+            OpEnum::TxRefund(op) if op.rw().is_write() => OpEnum::TxRefund(
+                TxRefundOp::new(RW::WRITE, op.tx_id, op.value_prev, op.value),
+            ),
+            OpEnum::AccountNonce(op) if op.rw().is_write() => {
+                OpEnum::AccountNonce(AccountNonceOp::new(
+                    RW::WRITE,
+                    op.address,
+                    op.value_prev,
+                    op.value,
+                ))
+            }
+            OpEnum::AccountBalance(op) if op.rw().is_write() => {
+                OpEnum::AccountBalance(AccountBalanceOp::new(
+                    RW::WRITE,
+                    op.address,
+                    op.value_prev,
+                    op.value,
+                ))
+            }
+            OpEnum::AccountCodeHash(op) if op.rw().is_write() => {
+                OpEnum::AccountCodeHash(AccountCodeHashOp::new(
+                    RW::WRITE,
+                    op.address,
+                    op.value_prev,
+                    op.value,
+                ))
+            }
+            _ => return None,
+        })
+    }
 }
 
 /// Operation is a Wrapper over a type that implements Op with a GlobalCounter.
@@ -303,11 +1192,26 @@ impl<T: Op> Operation<T> {
         }
     }
 
+    /// Create a new Operation from an `op` with a `gc`, which undoes a
+    /// previous write done by a call that fails.
+    pub fn new_revert(gc: GlobalCounter, op: T) -> Self {
+        Self {
+            gc,
+            revert: true,
+            op,
+        }
+    }
+
     /// Return this `Operation` `gc`
     pub fn gc(&self) -> GlobalCounter {
         self.gc
     }
 
+    /// Return true when this `Operation` undoes a previous write
+    pub fn revert(&self) -> bool {
+        self.revert
+    }
+
     /// Return this `Operation` `op`
     pub fn op(&self) -> &T {
         &self.op
@@ -374,13 +1278,17 @@ mod operation_tests {
 
     #[test]
     fn unchecked_op_transmutations_are_safe() {
-        let stack_op =
-            StackOp::new(RW::WRITE, StackAddress::from(1024), Word::from(0x40));
+        let stack_op = StackOp::new(
+            RW::WRITE,
+            1,
+            StackAddress::from(1024),
+            Word::from(0x40),
+        );
 
         let stack_op_as_operation =
             Operation::new(GlobalCounter(1), stack_op.clone());
 
-        let memory_op = MemoryOp::new(RW::WRITE, MemoryAddress(0x40), 0x40);
+        let memory_op = MemoryOp::new(RW::WRITE, 1, MemoryAddress(0x40), 0x40);
 
         let memory_op_as_operation =
             Operation::new(GlobalCounter(1), memory_op.clone());
@@ -1,4 +1,8 @@
-use super::{MemoryOp, Op, OpEnum, Operation, StackOp, StorageOp, Target};
+use super::{
+    AccountBalanceOp, AccountCodeHashOp, AccountNonceOp, CallContextOp,
+    GlobalCounter, MemoryOp, Op, OpEnum, Operation, StackOp, StorageOp, Target,
+    TxAccessListAccountOp, TxAccessListAccountStorageOp, TxLogOp, TxRefundOp,
+};
 use crate::exec_trace::OperationRef;
 use itertools::Itertools;
 
@@ -21,6 +25,15 @@ pub struct OperationContainer {
     pub(crate) memory: Vec<Operation<MemoryOp>>,
     pub(crate) stack: Vec<Operation<StackOp>>,
     pub(crate) storage: Vec<Operation<StorageOp>>,
+    pub(crate) tx_access_list_account_storage:
+        Vec<Operation<TxAccessListAccountStorageOp>>,
+    pub(crate) tx_refund: Vec<Operation<TxRefundOp>>,
+    pub(crate) call_context: Vec<Operation<CallContextOp>>,
+    pub(crate) account_nonce: Vec<Operation<AccountNonceOp>>,
+    pub(crate) account_balance: Vec<Operation<AccountBalanceOp>>,
+    pub(crate) account_code_hash: Vec<Operation<AccountCodeHashOp>>,
+    pub(crate) tx_log: Vec<Operation<TxLogOp>>,
+    pub(crate) tx_access_list_account: Vec<Operation<TxAccessListAccountOp>>,
 }
 
 impl Default for OperationContainer {
@@ -37,6 +50,14 @@ impl OperationContainer {
             memory: Vec::new(),
             stack: Vec::new(),
             storage: Vec::new(),
+            tx_access_list_account_storage: Vec::new(),
+            tx_refund: Vec::new(),
+            call_context: Vec::new(),
+            account_nonce: Vec::new(),
+            account_balance: Vec::new(),
+            account_code_hash: Vec::new(),
+            tx_log: Vec::new(),
+            tx_access_list_account: Vec::new(),
         }
     }
 
@@ -45,20 +66,86 @@ impl OperationContainer {
     /// location of the inserted operation inside the corresponding container
     /// vector.
     pub fn insert<T: Op>(&mut self, op: Operation<T>) -> OperationRef {
-        let gc = op.gc();
-        match op.op.into_enum() {
+        self.insert_op_enum(op.gc(), op.revert(), op.op.into_enum())
+    }
+
+    /// Inserts the operation `op` wrapped in an [`OpEnum`] with the global
+    /// counter `gc`, marked as a revert when `revert` is true, returning its
+    /// [`OperationRef`].
+    pub fn insert_op_enum(
+        &mut self,
+        gc: GlobalCounter,
+        revert: bool,
+        op: OpEnum,
+    ) -> OperationRef {
+        match op {
             OpEnum::Memory(op) => {
-                self.memory.push(Operation::new(gc, op));
+                self.memory.push(Operation { gc, revert, op });
                 OperationRef::from((Target::Memory, self.memory.len()))
             }
             OpEnum::Stack(op) => {
-                self.stack.push(Operation::new(gc, op));
+                self.stack.push(Operation { gc, revert, op });
                 OperationRef::from((Target::Stack, self.stack.len()))
             }
             OpEnum::Storage(op) => {
-                self.storage.push(Operation::new(gc, op));
+                self.storage.push(Operation { gc, revert, op });
                 OperationRef::from((Target::Storage, self.storage.len()))
             }
+            OpEnum::TxAccessListAccountStorage(op) => {
+                self.tx_access_list_account_storage.push(Operation {
+                    gc,
+                    revert,
+                    op,
+                });
+                OperationRef::from((
+                    Target::TxAccessListAccountStorage,
+                    self.tx_access_list_account_storage.len(),
+                ))
+            }
+            OpEnum::TxRefund(op) => {
+                self.tx_refund.push(Operation { gc, revert, op });
+                OperationRef::from((Target::TxRefund, self.tx_refund.len()))
+            }
+            OpEnum::CallContext(op) => {
+                self.call_context.push(Operation { gc, revert, op });
+                OperationRef::from((
+                    Target::CallContext,
+                    self.call_context.len(),
+                ))
+            }
+            OpEnum::AccountNonce(op) => {
+                self.account_nonce.push(Operation { gc, revert, op });
+                OperationRef::from((
+                    Target::AccountNonce,
+                    self.account_nonce.len(),
+                ))
+            }
+            OpEnum::AccountBalance(op) => {
+                self.account_balance.push(Operation { gc, revert, op });
+                OperationRef::from((
+                    Target::AccountBalance,
+                    self.account_balance.len(),
+                ))
+            }
+            OpEnum::AccountCodeHash(op) => {
+                self.account_code_hash.push(Operation { gc, revert, op });
+                OperationRef::from((
+                    Target::AccountCodeHash,
+                    self.account_code_hash.len(),
+                ))
+            }
+            OpEnum::TxLog(op) => {
+                self.tx_log.push(Operation { gc, revert, op });
+                OperationRef::from((Target::TxLog, self.tx_log.len()))
+            }
+            OpEnum::TxAccessListAccount(op) => {
+                self.tx_access_list_account
+                    .push(Operation { gc, revert, op });
+                OperationRef::from((
+                    Target::TxAccessListAccount,
+                    self.tx_access_list_account.len(),
+                ))
+            }
         }
     }
 
@@ -79,6 +166,68 @@ impl OperationContainer {
     pub fn sorted_storage(&self) -> Vec<Operation<StorageOp>> {
         self.storage.iter().sorted().cloned().collect()
     }
+
+    /// Returns a sorted vector of all of the [`TxAccessListAccountStorageOp`]s
+    /// contained inside of the container.
+    pub fn sorted_tx_access_list_account_storage(
+        &self,
+    ) -> Vec<Operation<TxAccessListAccountStorageOp>> {
+        self.tx_access_list_account_storage
+            .iter()
+            .sorted()
+            .cloned()
+            .collect()
+    }
+
+    /// Returns a sorted vector of all of the [`TxRefundOp`]s contained inside
+    /// of the container.
+    pub fn sorted_tx_refund(&self) -> Vec<Operation<TxRefundOp>> {
+        self.tx_refund.iter().sorted().cloned().collect()
+    }
+
+    /// Returns a sorted vector of all of the [`CallContextOp`]s contained
+    /// inside of the container.
+    pub fn sorted_call_context(&self) -> Vec<Operation<CallContextOp>> {
+        self.call_context.iter().sorted().cloned().collect()
+    }
+
+    /// Returns a sorted vector of all of the [`AccountNonceOp`]s contained
+    /// inside of the container.
+    pub fn sorted_account_nonce(&self) -> Vec<Operation<AccountNonceOp>> {
+        self.account_nonce.iter().sorted().cloned().collect()
+    }
+
+    /// Returns a sorted vector of all of the [`AccountBalanceOp`]s contained
+    /// inside of the container.
+    pub fn sorted_account_balance(&self) -> Vec<Operation<AccountBalanceOp>> {
+        self.account_balance.iter().sorted().cloned().collect()
+    }
+
+    /// Returns a sorted vector of all of the [`AccountCodeHashOp`]s contained
+    /// inside of the container.
+    pub fn sorted_account_code_hash(
+        &self,
+    ) -> Vec<Operation<AccountCodeHashOp>> {
+        self.account_code_hash.iter().sorted().cloned().collect()
+    }
+
+    /// Returns a sorted vector of all of the [`TxLogOp`]s contained inside of
+    /// the container.
+    pub fn sorted_tx_log(&self) -> Vec<Operation<TxLogOp>> {
+        self.tx_log.iter().sorted().cloned().collect()
+    }
+
+    /// Returns a sorted vector of all of the [`TxAccessListAccountOp`]s
+    /// contained inside of the container.
+    pub fn sorted_tx_access_list_account(
+        &self,
+    ) -> Vec<Operation<TxAccessListAccountOp>> {
+        self.tx_access_list_account
+            .iter()
+            .sorted()
+            .cloned()
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -97,11 +246,11 @@ mod container_test {
         let mut operation_container = OperationContainer::default();
         let stack_operation = Operation::new(
             global_counter.inc_pre(),
-            StackOp::new(RW::WRITE, StackAddress(1023), Word::from(0x100)),
+            StackOp::new(RW::WRITE, 1, StackAddress(1023), Word::from(0x100)),
         );
         let memory_operation = Operation::new(
             global_counter.inc_pre(),
-            MemoryOp::new(RW::WRITE, MemoryAddress::from(1), 1),
+            MemoryOp::new(RW::WRITE, 1, MemoryAddress::from(1), 1),
         );
         let storage_operation = Operation::new(
             global_counter.inc_pre(),
@@ -111,6 +260,8 @@ mod container_test {
                 Word::default(),
                 Word::from(0x1),
                 Word::default(),
+                1,
+                Word::default(),
             ),
         );
         let stack_ref = operation_container.insert(stack_operation.clone());
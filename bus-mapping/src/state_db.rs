@@ -1,13 +1,21 @@
+//! Implementation of an in-memory key-value database to represent the
+//! Ethereum State Trie.
+
 use crate::eth_types::{Address, Word, H256};
-use std::collections::HashMap;
+use lazy_static::lazy_static;
+use std::collections::{HashMap, HashSet};
 
 /// Account of the Ethereum State Trie, which contains an in-memory key-value
 /// database that represents the Account Storage Trie.
 #[derive(Debug, PartialEq)]
 pub struct Account {
+    /// Nonce
     pub nonce: Word,
+    /// Balance
     pub balance: Word,
+    /// Storage key-value map
     pub storage: HashMap<Word, Word>,
+    /// Code hash
     pub codeHash: H256,
 }
 
@@ -20,12 +28,42 @@ impl Account {
             codeHash: H256::zero(),
         }
     }
+
+    /// Returns true if the account has no nonce, no balance and no code
+    /// (EIP-161).
+    pub fn is_empty(&self) -> bool {
+        self.nonce.is_zero()
+            && self.balance.is_zero()
+            && (self.codeHash.is_zero() || self.codeHash == *EMPTY_CODE_HASH)
+    }
+}
+
+lazy_static! {
+    /// Hash of the empty code.
+    static ref EMPTY_CODE_HASH: H256 = H256::from_slice(
+        &hex::decode(
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        )
+        .unwrap()
+    );
 }
 
 /// In-memory key-value database that represents the Ethereum State Trie.
 #[derive(Debug)]
 pub struct StateDB {
     state: HashMap<Address, Account>,
+    // Storage values at the beginning of the current transaction of the slots
+    // written in it.
+    committed_storage: HashMap<(Address, Word), Word>,
+    // Accounts accessed in the current transaction (EIP-2929).
+    access_list_account: HashSet<Address>,
+    // Storage slots accessed in the current transaction (EIP-2929).
+    access_list_account_storage: HashSet<(Address, Word)>,
+    // Gas refund counter of the current transaction.
+    refund: u64,
+    // Accounts destroyed by SELFDESTRUCT in the current transaction, which
+    // are deleted at its end.
+    destructed_accounts: HashSet<Address>,
     acc_zero: Account,
     value_zero: Word,
 }
@@ -41,6 +79,11 @@ impl StateDB {
     pub fn new() -> Self {
         Self {
             state: HashMap::new(),
+            committed_storage: HashMap::new(),
+            access_list_account: HashSet::new(),
+            access_list_account_storage: HashSet::new(),
+            refund: 0,
+            destructed_accounts: HashSet::new(),
             acc_zero: Account::zero(),
             value_zero: Word::zero(),
         }
@@ -104,6 +147,121 @@ impl StateDB {
         };
         (found, acc.storage.get_mut(key).expect("key not inserted"))
     }
+
+    /// Set the storage value from [`Account`] at `addr`, at `key`, keeping
+    /// track of the value it had at the beginning of the current transaction.
+    pub fn set_storage(&mut self, addr: &Address, key: &Word, value: &Word) {
+        let (_, value_prev) = self.get_storage(addr, key);
+        let value_prev = *value_prev;
+        self.committed_storage
+            .entry((*addr, *key))
+            .or_insert(value_prev);
+        let (_, value_mut) = self.get_storage_mut(addr, key);
+        *value_mut = *value;
+    }
+
+    /// Get a reference to the storage value from [`Account`] at `addr`, at
+    /// `key`, as it was at the beginning of the current transaction (EIP-2200).
+    /// Returns false and a zero [`Word`] when the [`Account`] or `key` wasn't
+    /// found in the state.
+    pub fn get_committed_storage(
+        &self,
+        addr: &Address,
+        key: &Word,
+    ) -> (bool, &Word) {
+        match self.committed_storage.get(&(*addr, *key)) {
+            Some(value) => (true, value),
+            None => self.get_storage(addr, key),
+        }
+    }
+
+    /// Returns true if the account at `addr` is in the access list of the
+    /// current transaction.
+    pub fn check_account_in_access_list(&self, addr: &Address) -> bool {
+        self.access_list_account.contains(addr)
+    }
+
+    /// Add the account at `addr` to the access list of the current
+    /// transaction.  Returns true if the account was not in the access list
+    /// before.
+    pub fn add_account_to_access_list(&mut self, addr: &Address) -> bool {
+        self.access_list_account.insert(*addr)
+    }
+
+    /// Remove the account at `addr` from the access list of the current
+    /// transaction, which happens when the call that accessed it for the first
+    /// time fails.  Returns true if the account was in the access list before.
+    pub fn remove_account_from_access_list(&mut self, addr: &Address) -> bool {
+        self.access_list_account.remove(addr)
+    }
+
+    /// Returns true if the storage slot at (`addr`, `key`) is in the access
+    /// list of the current transaction.
+    pub fn check_account_storage_in_access_list(
+        &self,
+        (addr, key): (&Address, &Word),
+    ) -> bool {
+        self.access_list_account_storage.contains(&(*addr, *key))
+    }
+
+    /// Add the storage slot at (`addr`, `key`) to the access list of the
+    /// current transaction.  Returns true if the slot was not in the access
+    /// list before.
+    pub fn add_account_storage_to_access_list(
+        &mut self,
+        (addr, key): (&Address, &Word),
+    ) -> bool {
+        self.access_list_account_storage.insert((*addr, *key))
+    }
+
+    /// Remove the storage slot at (`addr`, `key`) from the access list of the
+    /// current transaction, which happens when the call that accessed it for
+    /// the first time fails.  Returns true if the slot was in the access list
+    /// before.
+    pub fn remove_account_storage_from_access_list(
+        &mut self,
+        (addr, key): (&Address, &Word),
+    ) -> bool {
+        self.access_list_account_storage.remove(&(*addr, *key))
+    }
+
+    /// Get the gas refund counter of the current transaction.
+    pub fn refund(&self) -> u64 {
+        self.refund
+    }
+
+    /// Set the gas refund counter of the current transaction.
+    pub fn set_refund(&mut self, value: u64) {
+        self.refund = value;
+    }
+
+    /// Mark the [`Account`] at `addr` as destroyed, to be deleted at the end
+    /// of the current transaction.
+    pub fn destruct_account(&mut self, addr: &Address) {
+        self.destructed_accounts.insert(*addr);
+    }
+
+    /// Get the addresses of the accounts destroyed in the current transaction,
+    /// sorted.
+    pub fn destructed_accounts(&self) -> Vec<Address> {
+        let mut addrs: Vec<_> =
+            self.destructed_accounts.iter().cloned().collect();
+        addrs.sort();
+        addrs
+    }
+
+    /// Delete the accounts destroyed in the current transaction and clear the
+    /// transaction-scoped state: committed storage values, access list and
+    /// refund counter.  Must be called at the end of every transaction.
+    pub fn commit_tx(&mut self) {
+        for addr in self.destructed_accounts.drain() {
+            self.state.remove(&addr);
+        }
+        self.committed_storage = HashMap::new();
+        self.access_list_account = HashSet::new();
+        self.access_list_account_storage = HashSet::new();
+        self.refund = 0;
+    }
 }
 
 #[cfg(test)]
@@ -165,4 +323,49 @@ mod statedb_tests {
         assert!(found);
         assert_eq!(value, &Word::from(102));
     }
+
+    #[test]
+    fn statedb_tx_scope() {
+        let addr_a = address!("0x0000000000000000000000000000000000000001");
+        let addr_b = address!("0x0000000000000000000000000000000000000002");
+        let key = Word::from(2);
+        let mut statedb = StateDB::new();
+
+        // Writes keep the value at the beginning of the transaction
+        statedb.set_storage(&addr_a, &key, &Word::from(101));
+        statedb.set_storage(&addr_a, &key, &Word::from(102));
+        let (_, value) = statedb.get_storage(&addr_a, &key);
+        assert_eq!(value, &Word::from(102));
+        let (found, value) = statedb.get_committed_storage(&addr_a, &key);
+        assert!(found);
+        assert_eq!(value, &Word::zero());
+
+        // Access list
+        assert!(statedb.add_account_to_access_list(&addr_a));
+        assert!(statedb.check_account_in_access_list(&addr_a));
+        assert!(statedb.remove_account_from_access_list(&addr_a));
+        assert!(!statedb.check_account_in_access_list(&addr_a));
+        assert!(!statedb.check_account_storage_in_access_list((&addr_a, &key)));
+        assert!(statedb.add_account_storage_to_access_list((&addr_a, &key)));
+        assert!(!statedb.add_account_storage_to_access_list((&addr_a, &key)));
+        assert!(statedb.check_account_storage_in_access_list((&addr_a, &key)));
+
+        // Refund
+        statedb.set_refund(4800);
+        assert_eq!(statedb.refund(), 4800);
+
+        // Destroyed account
+        statedb.get_account_mut(&addr_b).1.balance = Word::from(555);
+        statedb.destruct_account(&addr_b);
+        assert_eq!(statedb.destructed_accounts(), vec![addr_b]);
+
+        // After the transaction the written value becomes the committed one
+        statedb.commit_tx();
+        let (_, value) = statedb.get_committed_storage(&addr_a, &key);
+        assert_eq!(value, &Word::from(102));
+        assert!(!statedb.check_account_storage_in_access_list((&addr_a, &key)));
+        assert_eq!(statedb.refund(), 0);
+        assert!(statedb.destructed_accounts().is_empty());
+        assert!(!statedb.get_account(&addr_b).0);
+    }
 }
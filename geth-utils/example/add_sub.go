@@ -16,7 +16,7 @@ func main() {
 	asm := gethutil.NewAssembly().Add(0xdeadbeef, 0xcafeb0ba).Sub(0xfaceb00c, 0xb0bacafe)
 	contracts := []gethutil.Account{{Address: address, Bytecode: asm.Bytecode}}
 
-	logs, err := gethutil.TraceTx(&address, nil, &runtime.Config{GasLimit: 100}, contracts)
+	logs, err := gethutil.TraceTx(&address, nil, &runtime.Config{GasLimit: 100}, 0, contracts)
 	if err != nil {
 		fmt.Fprintf(os.Stderr, "failed to trace tx, err: %v\n", err)
 	}
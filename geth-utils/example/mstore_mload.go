@@ -16,7 +16,7 @@ func main() {
 	asm := gethutil.NewAssembly().MStore(0x40, 0x80).MLoad(0x40)
 	contracts := []gethutil.Account{{Address: address, Bytecode: asm.Bytecode}}
 
-	logs, err := gethutil.TraceTx(&address, nil, &runtime.Config{GasLimit: 100}, contracts)
+	logs, err := gethutil.TraceTx(&address, nil, &runtime.Config{GasLimit: 100}, 0, contracts)
 	if err != nil {
 		fmt.Fprintf(os.Stderr, "failed to trace tx, err: %v\n", err)
 	}
@@ -16,7 +16,7 @@ func main() {
 	asm := gethutil.NewAssembly().SStore(0, 0xcafeb0ba).SLoad(0).SStore(0, 0xdeabbeef).SLoad(0)
 	contracts := []gethutil.Account{{Address: address, Bytecode: asm.Bytecode}}
 
-	logs, err := gethutil.TraceTx(&address, nil, &runtime.Config{GasLimit: 25000}, contracts)
+	logs, err := gethutil.TraceTx(&address, nil, &runtime.Config{GasLimit: 25000}, 0, contracts)
 	if err != nil {
 		fmt.Fprintf(os.Stderr, "failed to trace tx, err: %v\n", err)
 	}
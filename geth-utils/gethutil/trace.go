@@ -2,13 +2,17 @@ package gethutil
 
 import (
 	"fmt"
+	"math"
 	"math/big"
 
 	"github.com/ethereum/go-ethereum/common"
+	"github.com/ethereum/go-ethereum/core"
 	"github.com/ethereum/go-ethereum/core/rawdb"
 	"github.com/ethereum/go-ethereum/core/state"
 	"github.com/ethereum/go-ethereum/core/vm"
 	"github.com/ethereum/go-ethereum/core/vm/runtime"
+	"github.com/ethereum/go-ethereum/crypto"
+	"github.com/ethereum/go-ethereum/params"
 )
 
 // Copied from github.com/ethereum/go-ethereum/internal/ethapi.StructLogRes
@@ -68,7 +72,64 @@ type Account struct {
 	Bytecode []byte
 }
 
-func TraceTx(toAddress *common.Address, calldata []byte, config *runtime.Config, accounts []Account) ([]StructLogRes, error) {
+// setDefaults sets the fields of config left unset to the defaults of
+// github.com/ethereum/go-ethereum/core/vm/runtime
+func setDefaults(config *runtime.Config) {
+	if config.ChainConfig == nil {
+		config.ChainConfig = &params.ChainConfig{
+			ChainID:             big.NewInt(1),
+			HomesteadBlock:      new(big.Int),
+			DAOForkBlock:        new(big.Int),
+			DAOForkSupport:      false,
+			EIP150Block:         new(big.Int),
+			EIP150Hash:          common.Hash{},
+			EIP155Block:         new(big.Int),
+			EIP158Block:         new(big.Int),
+			ByzantiumBlock:      new(big.Int),
+			ConstantinopleBlock: new(big.Int),
+			PetersburgBlock:     new(big.Int),
+			IstanbulBlock:       new(big.Int),
+			MuirGlacierBlock:    new(big.Int),
+			BerlinBlock:         new(big.Int),
+			LondonBlock:         new(big.Int),
+		}
+	}
+	if config.Difficulty == nil {
+		config.Difficulty = new(big.Int)
+	}
+	if config.Time == nil {
+		config.Time = new(big.Int)
+	}
+	if config.GasLimit == 0 {
+		config.GasLimit = math.MaxUint64
+	}
+	if config.GasPrice == nil {
+		config.GasPrice = new(big.Int)
+	}
+	if config.Value == nil {
+		config.Value = new(big.Int)
+	}
+	if config.BlockNumber == nil {
+		config.BlockNumber = new(big.Int)
+	}
+	if config.GetHashFn == nil {
+		config.GetHashFn = func(n uint64) common.Hash {
+			return common.BytesToHash(crypto.Keccak256([]byte(new(big.Int).SetUint64(n).String())))
+		}
+	}
+	if config.BaseFee == nil {
+		config.BaseFee = big.NewInt(params.InitialBaseFee)
+	}
+}
+
+// TraceTx traces the execution of a tx with the gas limit of config, in a
+// block with the given gas limit, or the one of the tx when it's 0.
+func TraceTx(toAddress *common.Address, calldata []byte, config *runtime.Config, blockGasLimit uint64, accounts []Account) ([]StructLogRes, error) {
+	setDefaults(config)
+	if blockGasLimit == 0 {
+		blockGasLimit = config.GasLimit
+	}
+
 	// Overwrite state
 	newState, err := state.New(common.Hash{}, state.NewDatabase(rawdb.NewMemoryDatabase()), nil)
 	if err != nil {
@@ -91,10 +152,42 @@ func TraceTx(toAddress *common.Address, calldata []byte, config *runtime.Config,
 	config.EVMConfig.Debug = true
 	config.EVMConfig.Tracer = tracer
 
+	// The environment is built like in runtime.NewEnv, but the gas limit of
+	// the block is not the one of the tx
+	blockContext := vm.BlockContext{
+		CanTransfer: core.CanTransfer,
+		Transfer:    core.Transfer,
+		GetHash:     config.GetHashFn,
+		Coinbase:    config.Coinbase,
+		BlockNumber: config.BlockNumber,
+		Time:        config.Time,
+		Difficulty:  config.Difficulty,
+		GasLimit:    blockGasLimit,
+		BaseFee:     config.BaseFee,
+	}
+	txContext := vm.TxContext{
+		Origin:   config.Origin,
+		GasPrice: config.GasPrice,
+	}
+	evm := vm.NewEVM(blockContext, txContext, config.State, config.ChainConfig, config.EVMConfig)
+
+	sender := vm.AccountRef(config.Origin)
+	rules := config.ChainConfig.Rules(config.BlockNumber)
+	config.State.PrepareAccessList(config.Origin, toAddress, vm.ActivePrecompiles(rules), nil)
+	// The root call is given the gas left after paying for the intrinsic gas,
+	// like in the execution of the tx in a block
+	intrinsicGas, err := core.IntrinsicGas(calldata, nil, toAddress == nil, true, true)
+	if err != nil {
+		return nil, err
+	}
+	if config.GasLimit < intrinsicGas {
+		return nil, fmt.Errorf("%w: have %d, want %d", core.ErrIntrinsicGas, config.GasLimit, intrinsicGas)
+	}
+	gas := config.GasLimit - intrinsicGas
 	if toAddress == nil {
-		_, _, _, err = runtime.Create(calldata, config)
+		_, _, _, err = evm.Create(sender, calldata, gas, config.Value)
 	} else {
-		_, _, err = runtime.Call(*toAddress, nil, config)
+		_, _, err = evm.Call(sender, *toAddress, calldata, gas, config.Value)
 	}
 
 	return FormatLogs(tracer.StructLogs()), err
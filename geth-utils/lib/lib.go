@@ -30,7 +30,7 @@ func CreateTrace(config *C.char) *C.char {
 		fmt.Fprintf(os.Stderr, "failed to load trace config, err: %v\n", err)
 	}
 
-	logs, err := gethutil.TraceTx(&gethConfig.target, nil, &gethConfig.config, gethConfig.contracts)
+	logs, err := gethutil.TraceTx(&gethConfig.target, gethConfig.calldata, &gethConfig.config, gethConfig.blockGasLimit, gethConfig.contracts)
 	if err != nil {
 		fmt.Fprintf(os.Stderr, "trace stopped unexpectedly, err: %v\n", err)
 	}
@@ -51,9 +51,11 @@ func FreeString(str *C.char) {
 // FIXME: GethConfig unmarshals from JsonConfig, which is extremely confusing.
 // https://github.com/appliedzkp/zkevm-circuits/issues/188
 type GethConfig struct {
-	config    runtime.Config
-	contracts []gethutil.Account
-	target    common.Address
+	config        runtime.Config
+	blockGasLimit uint64
+	contracts     []gethutil.Account
+	target        common.Address
+	calldata      []byte
 }
 
 type BlockConstants struct {
@@ -65,12 +67,18 @@ type BlockConstants struct {
 	GasLimit    *hexutil.Big   `json:"gas_limit"`
 	ChainID     *hexutil.Big   `json:"chain_id"`
 	BaseFee     *hexutil.Big   `json:"base_fee"`
+
+	// Hashes of the previous blocks, ending with the parent block
+	HistoryHashes []common.Hash `json:"history_hashes"`
 }
 
 type Transaction struct {
 	Origin   common.Address `json:"origin"`
 	GasLimit *hexutil.Big   `json:"gas_limit"`
+	GasPrice *hexutil.Big   `json:"gas_price"`
 	Target   common.Address `json:"target"`
+	Value    *hexutil.Big   `json:"value"`
+	CallData hexutil.Bytes  `json:"call_data"`
 }
 
 type AccountData struct {
@@ -92,13 +100,26 @@ func (this *GethConfig) UnmarshalJSON(b []byte) error {
 		return err
 	}
 
+	blockNumber := jConfig.Block.BlockNumber.ToInt().Uint64()
+	historyHashes := jConfig.Block.HistoryHashes
 	this.config = runtime.Config{
 		Origin:      jConfig.Transaction.Origin,
 		GasLimit:    jConfig.Transaction.GasLimit.ToInt().Uint64(),
+		GasPrice:    jConfig.Transaction.GasPrice.ToInt(),
+		Value:       jConfig.Transaction.Value.ToInt(),
 		Difficulty:  jConfig.Block.Difficulty.ToInt(),
 		Time:        jConfig.Block.Timestamp.ToInt(),
 		Coinbase:    jConfig.Block.Coinbase,
 		BlockNumber: jConfig.Block.BlockNumber.ToInt(),
+		BaseFee:     jConfig.Block.BaseFee.ToInt(),
+		GetHashFn: func(n uint64) common.Hash {
+			// BLOCKHASH only asks for one of the previous 256 blocks
+			distance := blockNumber - n
+			if distance > uint64(len(historyHashes)) {
+				return common.Hash{}
+			}
+			return historyHashes[uint64(len(historyHashes))-distance]
+		},
 		ChainConfig: &params.ChainConfig{
 			ChainID:             jConfig.Block.ChainID.ToInt(),
 			HomesteadBlock:      big.NewInt(0),
@@ -118,6 +139,7 @@ func (this *GethConfig) UnmarshalJSON(b []byte) error {
 		},
 		EVMConfig: vm.Config{},
 	}
+	this.blockGasLimit = jConfig.Block.GasLimit.ToInt().Uint64()
 
 	for _, contract := range jConfig.Accounts {
 		address := contract.Address
@@ -130,6 +152,7 @@ func (this *GethConfig) UnmarshalJSON(b []byte) error {
 	}
 
 	this.target = jConfig.Transaction.Target
+	this.calldata = jConfig.Transaction.CallData
 
 	return nil
 }
@@ -0,0 +1,29 @@
+//! The bytecode circuit implementation.
+
+pub(crate) mod bytecode;
+
+/*
+Example bytecode table, with the code `PUSH2 0x5b00 JUMPDEST` followed by the
+code `STOP`:
+
+| code_hash | index | is_code | byte | is_final | push_data_left | padding |
+--------------------------------------------------------------------------
+|  hash_0   |   0   |    1    | 0x61 |    0     |       2        |    0    |   // PUSH2
+|  hash_0   |   1   |    0    | 0x5b |    0     |       1        |    0    |   // not a JUMPDEST
+|  hash_0   |   2   |    0    | 0x00 |    0     |       0        |    0    |
+|  hash_0   |   3   |    1    | 0x5b |    1     |       0        |    0    |   // JUMPDEST
+|  hash_1   |   0   |    1    | 0x00 |    1     |       0        |    0    |   // STOP
+|     0     |   0   |    0    |  0   |    0     |       0        |    1    |   // padding
+*/
+
+// code_hash is the random linear combination of the keccak hash of the code,
+// index is the position of the byte in the code, and is_code tells whether
+// the byte is an opcode or PUSH data.  is_final marks the last byte of a
+// code, where the hash is checked against the keccak table, and
+// push_data_left counts the PUSH data bytes which follow the byte.  The rows
+// after the last code are padding.
+//
+// The keccak table isn't proven yet: until there is a keccak circuit, it is
+// assigned from the codes by the prover.  The code hashes of the bytecode
+// table, and the code sizes which the EVM circuit reads from the keccak
+// table, are therefore only bound to the codes once it's proven.
@@ -0,0 +1,622 @@
+use crate::gadget::{
+    evm_word::encode,
+    is_zero::{IsZeroChip, IsZeroConfig, IsZeroInstruction},
+};
+use bus_mapping::evm::OpcodeId;
+use digest::{FixedOutput, Input};
+use halo2::{
+    circuit::{Layouter, Region},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed},
+    poly::Rotation,
+};
+use pairing::arithmetic::FieldExt;
+use sha3::{Digest, Keccak256};
+
+/// A byte of a code, as laid out in the bytecode table.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct BytecodeRow {
+    pub(crate) index: usize,
+    pub(crate) is_code: bool,
+    pub(crate) byte: u8,
+    pub(crate) push_data_left: usize,
+}
+
+/// A code unrolled into its rows of the bytecode table.
+#[derive(Clone, Debug)]
+pub(crate) struct UnrolledBytecode<F> {
+    pub(crate) code_hash: F,
+    pub(crate) bytes: Vec<u8>,
+    pub(crate) rows: Vec<BytecodeRow>,
+}
+
+// Number of bytes pushed by `byte` when it's an opcode.
+fn push_size(byte: u8) -> usize {
+    if (OpcodeId::PUSH1.as_u8()..=OpcodeId::PUSH32.as_u8()).contains(&byte) {
+        (byte - OpcodeId::PUSH1.as_u8() + 1) as usize
+    } else {
+        0
+    }
+}
+
+/// Returns the random linear combination of the keccak hash of `code`, which
+/// identifies the code in the bytecode table.
+pub(crate) fn code_hash<F: FieldExt>(code: &[u8], r: F) -> F {
+    let mut hasher = Keccak256::new();
+    hasher.process(code);
+    encode(hasher.fixed_result().iter().cloned(), r)
+}
+
+/// Unrolls `code` into the rows of the bytecode table, telling its opcodes
+/// apart from the PUSH data.
+pub(crate) fn unroll<F: FieldExt>(code: Vec<u8>, r: F) -> UnrolledBytecode<F> {
+    let mut push_data_left = 0;
+    let rows = code
+        .iter()
+        .enumerate()
+        .map(|(index, &byte)| {
+            let is_code = push_data_left == 0;
+            push_data_left = if is_code {
+                push_size(byte)
+            } else {
+                push_data_left - 1
+            };
+            BytecodeRow {
+                index,
+                is_code,
+                byte,
+                push_data_left,
+            }
+        })
+        .collect();
+
+    UnrolledBytecode {
+        code_hash: code_hash(&code, r),
+        bytes: code,
+        rows,
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Config<F> {
+    r: F,
+    q_enable: Column<Fixed>,
+    q_first: Column<Fixed>,
+    q_last: Column<Fixed>,
+    bytecode_table: [Column<Advice>; 4],
+    is_final: Column<Advice>,
+    push_data_left: Column<Advice>,
+    push_data_left_is_zero: IsZeroConfig<F>,
+    hash_input_rlc: Column<Advice>,
+    padding: Column<Advice>,
+    push_table: [Column<Fixed>; 2],
+    keccak_table: [Column<Advice>; 3],
+}
+
+impl<F: FieldExt> Config<F> {
+    /// Configures the constraints proving the content of `bytecode_table`,
+    /// whose columns are the code hash, the index of the byte in the code,
+    /// whether the byte is an opcode, and the byte.
+    pub(crate) fn configure(
+        meta: &mut ConstraintSystem<F>,
+        r: F,
+        bytecode_table: [Column<Advice>; 4],
+    ) -> Self {
+        let q_enable = meta.fixed_column();
+        let q_first = meta.fixed_column();
+        let q_last = meta.fixed_column();
+        let is_final = meta.advice_column();
+        let push_data_left = meta.advice_column();
+        let push_data_left_inv = meta.advice_column();
+        let hash_input_rlc = meta.advice_column();
+        let padding = meta.advice_column();
+        let [code_hash, index, is_code, byte] = bytecode_table;
+
+        let push_table = [
+            meta.fixed_column(), // byte
+            meta.fixed_column(), // number of bytes pushed
+        ];
+
+        // TODO: keccak_table should be proven by the keccak circuit, without
+        // which a code hash isn't bound to the hashed code
+        let keccak_table = [
+            meta.advice_column(), // random linear combination of the input
+            meta.advice_column(), // length of the input
+            meta.advice_column(), // random linear combination of the hash
+        ];
+
+        let push_data_left_is_zero = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_fixed(q_enable, Rotation::cur()),
+            |meta| meta.query_advice(push_data_left, Rotation::cur()),
+            push_data_left_inv,
+        );
+        let push_data_left_is_zero_expression =
+            push_data_left_is_zero.is_zero_expression.clone();
+
+        meta.create_gate("bytecode rows", |meta| {
+            let one = Expression::Constant(F::one());
+            let r = Expression::Constant(r);
+
+            let q_enable = meta.query_fixed(q_enable, Rotation::cur());
+            let q_first = meta.query_fixed(q_first, Rotation::cur());
+            let q_last = meta.query_fixed(q_last, Rotation::cur());
+
+            let code_hash_cur = meta.query_advice(code_hash, Rotation::cur());
+            let code_hash_next = meta.query_advice(code_hash, Rotation::next());
+            let index_cur = meta.query_advice(index, Rotation::cur());
+            let index_next = meta.query_advice(index, Rotation::next());
+            let is_code_cur = meta.query_advice(is_code, Rotation::cur());
+            let is_code_next = meta.query_advice(is_code, Rotation::next());
+            let byte = meta.query_advice(byte, Rotation::cur());
+            let is_final_cur = meta.query_advice(is_final, Rotation::cur());
+            let is_final_prev = meta.query_advice(is_final, Rotation::prev());
+            let push_data_left_cur =
+                meta.query_advice(push_data_left, Rotation::cur());
+            let push_data_left_prev =
+                meta.query_advice(push_data_left, Rotation::prev());
+            let hash_input_rlc_cur =
+                meta.query_advice(hash_input_rlc, Rotation::cur());
+            let hash_input_rlc_prev =
+                meta.query_advice(hash_input_rlc, Rotation::prev());
+            let padding_cur = meta.query_advice(padding, Rotation::cur());
+            let padding_next = meta.query_advice(padding, Rotation::next());
+
+            // Rows holding a byte of a code, and the ones among them which
+            // are followed by another row
+            let q_row = q_enable.clone() * (one.clone() - padding_cur.clone());
+            let q_step = q_row.clone() * (one.clone() - q_last.clone());
+            let is_not_final = one.clone() - is_final_cur.clone();
+            // A code starts on the first row, or after the last byte of
+            // another code
+            let is_start =
+                q_first.clone() + (one.clone() - q_first) * is_final_prev;
+
+            let mut constraints = vec![];
+
+            for value in vec![
+                is_code_cur.clone(),
+                is_final_cur.clone(),
+                padding_cur.clone(),
+            ] {
+                constraints.push(
+                    q_enable.clone() * value.clone() * (one.clone() - value),
+                );
+            }
+
+            // Padding rows are empty, and are only followed by padding rows
+            for value in vec![
+                code_hash_cur.clone(),
+                index_cur.clone(),
+                is_code_cur.clone(),
+                byte.clone(),
+                is_final_cur.clone(),
+            ] {
+                constraints
+                    .push(q_enable.clone() * padding_cur.clone() * value);
+            }
+            constraints.push(
+                q_enable.clone()
+                    * (one.clone() - q_last.clone())
+                    * padding_cur
+                    * (one.clone() - padding_next.clone()),
+            );
+
+            // The last row can't be in the middle of a code
+            constraints.push(q_row.clone() * q_last * is_not_final.clone());
+
+            // A code starts with an opcode at index 0
+            constraints
+                .push(q_row.clone() * is_start.clone() * index_cur.clone());
+            constraints.push(
+                q_row.clone()
+                    * is_start.clone()
+                    * (one.clone() - is_code_cur.clone()),
+            );
+
+            // The next byte of the same code is an opcode when all the PUSH
+            // data of the previous opcode has been read
+            let q_next_byte = q_step * is_not_final;
+            constraints.push(q_next_byte.clone() * padding_next);
+            constraints
+                .push(q_next_byte.clone() * (code_hash_next - code_hash_cur));
+            constraints.push(
+                q_next_byte.clone() * (index_next - index_cur - one.clone()),
+            );
+            constraints.push(
+                q_next_byte
+                    * (is_code_next
+                        - push_data_left_is_zero_expression.clone()),
+            );
+
+            // Each byte of PUSH data decreases the number of bytes left, which
+            // is set by the push table for an opcode
+            constraints.push(
+                q_row.clone()
+                    * (one.clone() - is_code_cur)
+                    * (push_data_left_cur - push_data_left_prev + one.clone()),
+            );
+
+            // Accumulate the code into the input of its hash
+            constraints.push(
+                q_row
+                    * (hash_input_rlc_cur
+                        - (one - is_start) * hash_input_rlc_prev * r
+                        - byte),
+            );
+
+            constraints
+        });
+
+        // An opcode pushes the number of bytes given by the push table
+        meta.lookup_any(|meta| {
+            let q_code = meta.query_fixed(q_enable, Rotation::cur())
+                * (Expression::Constant(F::one())
+                    - meta.query_advice(padding, Rotation::cur()))
+                * meta.query_advice(is_code, Rotation::cur());
+
+            vec![
+                (
+                    q_code.clone() * meta.query_advice(byte, Rotation::cur()),
+                    meta.query_fixed(push_table[0], Rotation::cur()),
+                ),
+                (
+                    q_code * meta.query_advice(push_data_left, Rotation::cur()),
+                    meta.query_fixed(push_table[1], Rotation::cur()),
+                ),
+            ]
+        });
+
+        // The code hash is the keccak hash of the whole code
+        meta.lookup_any(|meta| {
+            let q_final = meta.query_fixed(q_enable, Rotation::cur())
+                * (Expression::Constant(F::one())
+                    - meta.query_advice(padding, Rotation::cur()))
+                * meta.query_advice(is_final, Rotation::cur());
+            let length = meta.query_advice(index, Rotation::cur())
+                + Expression::Constant(F::one());
+
+            vec![
+                meta.query_advice(hash_input_rlc, Rotation::cur()),
+                length,
+                meta.query_advice(code_hash, Rotation::cur()),
+            ]
+            .into_iter()
+            .zip(keccak_table.iter())
+            .map(|(expr, column)| {
+                (
+                    q_final.clone() * expr,
+                    meta.query_advice(*column, Rotation::cur()),
+                )
+            })
+            .collect::<Vec<_>>()
+        });
+
+        Self {
+            r,
+            q_enable,
+            q_first,
+            q_last,
+            bytecode_table,
+            is_final,
+            push_data_left,
+            push_data_left_is_zero,
+            hash_input_rlc,
+            padding,
+            push_table,
+            keccak_table,
+        }
+    }
+
+    /// Load the push table, which maps each byte to the number of bytes it
+    /// pushes as an opcode.
+    pub(crate) fn load(
+        &self,
+        layouter: &mut impl Layouter<F>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "push table",
+            |mut region| {
+                for byte in 0..=u8::MAX {
+                    for (column, value) in self
+                        .push_table
+                        .iter()
+                        .zip([byte as usize, push_size(byte)])
+                    {
+                        region.assign_fixed(
+                            || "push table",
+                            *column,
+                            byte as usize,
+                            || Ok(F::from(value as u64)),
+                        )?;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// Assign the rows of `bytecodes` to the bytecode table, followed by
+    /// padding rows up to `size` rows.
+    pub(crate) fn assign(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        size: usize,
+        bytecodes: &[UnrolledBytecode<F>],
+    ) -> Result<(), Error> {
+        let push_data_left_is_zero_chip =
+            IsZeroChip::construct(self.push_data_left_is_zero.clone());
+
+        layouter.assign_region(
+            || "bytecode table",
+            |mut region| {
+                let mut offset = 0;
+                for bytecode in bytecodes.iter() {
+                    let mut hash_input_rlc = F::zero();
+                    for (idx, row) in bytecode.rows.iter().enumerate() {
+                        hash_input_rlc =
+                            hash_input_rlc * self.r + F::from(row.byte as u64);
+                        self.assign_row(
+                            &mut region,
+                            offset,
+                            size,
+                            &push_data_left_is_zero_chip,
+                            [
+                                bytecode.code_hash,
+                                F::from(row.index as u64),
+                                F::from(row.is_code as u64),
+                                F::from(row.byte as u64),
+                            ],
+                            idx + 1 == bytecode.rows.len(),
+                            row.push_data_left,
+                            hash_input_rlc,
+                            false,
+                        )?;
+                        offset += 1;
+                    }
+                }
+                assert!(offset <= size, "bytecodes exceed the table size");
+
+                for offset in offset..size {
+                    self.assign_row(
+                        &mut region,
+                        offset,
+                        size,
+                        &push_data_left_is_zero_chip,
+                        [F::zero(); 4],
+                        false,
+                        0,
+                        F::zero(),
+                        true,
+                    )?;
+                }
+
+                Ok(())
+            },
+        )?;
+
+        // TODO: The keccak table is assigned from the codes until it's
+        // proven by the keccak circuit.
+        layouter.assign_region(
+            || "keccak table",
+            |mut region| {
+                for column in self.keccak_table.iter() {
+                    region.assign_advice(
+                        || "keccak noop",
+                        *column,
+                        0,
+                        || Ok(F::zero()),
+                    )?;
+                }
+
+                for (idx, bytecode) in bytecodes.iter().enumerate() {
+                    for (column, value) in self.keccak_table.iter().zip([
+                        encode(bytecode.bytes.iter().cloned(), self.r),
+                        F::from(bytecode.bytes.len() as u64),
+                        code_hash(&bytecode.bytes, self.r),
+                    ]) {
+                        region.assign_advice(
+                            || "keccak table",
+                            *column,
+                            idx + 1,
+                            || Ok(value),
+                        )?;
+                    }
+                }
+
+                Ok(())
+            },
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn assign_row(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        size: usize,
+        push_data_left_is_zero_chip: &IsZeroChip<F>,
+        table_row: [F; 4],
+        is_final: bool,
+        push_data_left: usize,
+        hash_input_rlc: F,
+        padding: bool,
+    ) -> Result<(), Error> {
+        for (name, column, value) in [
+            ("q_enable", self.q_enable, true),
+            ("q_first", self.q_first, offset == 0),
+            ("q_last", self.q_last, offset + 1 == size),
+        ] {
+            region.assign_fixed(
+                || name,
+                column,
+                offset,
+                || Ok(F::from(value as u64)),
+            )?;
+        }
+
+        for (column, value) in self.bytecode_table.iter().zip(table_row) {
+            region.assign_advice(
+                || "bytecode table",
+                *column,
+                offset,
+                || Ok(value),
+            )?;
+        }
+
+        let push_data_left = F::from(push_data_left as u64);
+        for (name, column, value) in [
+            ("is_final", self.is_final, F::from(is_final as u64)),
+            ("push_data_left", self.push_data_left, push_data_left),
+            ("hash_input_rlc", self.hash_input_rlc, hash_input_rlc),
+            ("padding", self.padding, F::from(padding as u64)),
+        ] {
+            region.assign_advice(|| name, column, offset, || Ok(value))?;
+        }
+        push_data_left_is_zero_chip.assign(
+            region,
+            offset,
+            Some(push_data_left),
+        )?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{unroll, BytecodeRow, Config, UnrolledBytecode};
+    use crate::gadget::evm_word::r;
+    use bus_mapping::{bytecode, evm::OpcodeId};
+    use halo2::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        dev::MockProver,
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+    use pairing::{arithmetic::FieldExt, bn256::Fr as Fp};
+
+    #[derive(Default)]
+    struct BytecodeCircuit<F> {
+        bytecodes: Vec<UnrolledBytecode<F>>,
+        size: usize,
+    }
+
+    impl<F: FieldExt> Circuit<F> for BytecodeCircuit<F> {
+        type Config = Config<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let bytecode_table = [
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+            ];
+            Config::configure(meta, r(), bytecode_table)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            config.load(&mut layouter)?;
+            config.assign(&mut layouter, self.size, &self.bytecodes)?;
+
+            Ok(())
+        }
+    }
+
+    fn verify(bytecodes: Vec<UnrolledBytecode<Fp>>, success: bool) {
+        let circuit = BytecodeCircuit {
+            bytecodes,
+            size: 64,
+        };
+        let prover = MockProver::<Fp>::run(9, &circuit, vec![]).unwrap();
+        assert_eq!(prover.verify().is_ok(), success);
+    }
+
+    fn codes() -> Vec<Vec<u8>> {
+        vec![
+            bytecode! {
+                PUSH2(0x5b00)
+                JUMPDEST
+                PUSH1(0x04)
+                JUMP
+            }
+            .to_bytes(),
+            bytecode! {
+                STOP
+            }
+            .to_bytes(),
+            // A truncated PUSH at the end of the code
+            vec![OpcodeId::PUSH32.as_u8(), 0xff, 0xff],
+        ]
+    }
+
+    #[test]
+    fn bytecode_unrolling() {
+        let bytecode = unroll(codes()[0].clone(), r::<Fp>());
+        assert_eq!(
+            bytecode.rows,
+            vec![
+                (0, true, OpcodeId::PUSH2.as_u8(), 2),
+                (1, false, 0x5b, 1),
+                (2, false, 0x00, 0),
+                (3, true, OpcodeId::JUMPDEST.as_u8(), 0),
+                (4, true, OpcodeId::PUSH1.as_u8(), 1),
+                (5, false, 0x04, 0),
+                (6, true, OpcodeId::JUMP.as_u8(), 0),
+            ]
+            .into_iter()
+            .map(|(index, is_code, byte, push_data_left)| BytecodeRow {
+                index,
+                is_code,
+                byte,
+                push_data_left,
+            })
+            .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn bytecode_circuit() {
+        let bytecodes =
+            codes().into_iter().map(|code| unroll(code, r())).collect();
+        verify(bytecodes, true);
+    }
+
+    #[test]
+    fn bytecode_circuit_invalid_is_code() {
+        // PUSH data can't be an opcode, and an opcode can't be PUSH data
+        for idx in [1, 3] {
+            let mut bytecode = unroll(codes()[0].clone(), r());
+            bytecode.rows[idx].is_code = !bytecode.rows[idx].is_code;
+            verify(vec![bytecode], false);
+        }
+    }
+
+    #[test]
+    fn bytecode_circuit_invalid_index() {
+        let mut bytecode = unroll(codes()[0].clone(), r());
+        bytecode.rows[2].index = 3;
+        verify(vec![bytecode], false);
+    }
+
+    #[test]
+    fn bytecode_circuit_invalid_byte() {
+        let mut bytecode = unroll(codes()[0].clone(), r());
+        bytecode.rows[3].byte = OpcodeId::STOP.as_u8();
+        verify(vec![bytecode], false);
+    }
+
+    #[test]
+    fn bytecode_circuit_invalid_hash() {
+        let mut bytecode = unroll(codes()[0].clone(), r());
+        bytecode.code_hash += Fp::one();
+        verify(vec![bytecode], false);
+    }
+}
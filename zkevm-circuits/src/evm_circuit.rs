@@ -1,12 +1,25 @@
 //! The EVM circuit implementation.
 
-use crate::util::Expr;
-use bus_mapping::{evm::OpcodeId, operation::Target};
+use crate::{
+    gadget::evm_word::r,
+    public_input_circuit::{
+        instance_len,
+        public_input::{words, Config as PublicInputConfig},
+    },
+    tx_circuit::tx::{unroll, Config as TxConfig, SignedTx},
+    util::Expr,
+};
+use bus_mapping::{
+    circuit_input_builder::Block,
+    evm::OpcodeId,
+    operation::{CallContextField, Target, TxLogField},
+};
 use halo2::{
     arithmetic::FieldExt,
-    circuit::{self, Layouter, Region},
+    circuit::{self, Layouter, Region, SimpleFloorPlanner},
     plonk::{
-        Advice, Column, ConstraintSystem, Error, Expression, Fixed, Selector,
+        Advice, Circuit, Column, ConstraintSystem, Error, Expression, Fixed,
+        Selector,
     },
     poly::Rotation,
 };
@@ -17,70 +30,23 @@ mod op_execution;
 use op_execution::{OpExecutionGadget, OpExecutionState};
 mod param;
 use param::{CIRCUIT_HEIGHT, CIRCUIT_WIDTH, NUM_CELL_OP_EXECUTION_STATE};
-
-#[derive(Clone, Debug)]
-pub(crate) enum CallField {
-    // Transaction id.
-    TxId,
-    // Global counter at the begin of call.
-    GlobalCounterBegin,
-    // Global counter at the end of call, used for locating reverting section.
-    GlobalCounterEnd,
-    // Caller’s unique identifier.
-    CallerID,
-    // Offset of calldata.
-    CalldataOffset,
-    // Size of calldata.
-    CalldataSize,
-    // Depth of call, should be expected in range 0..=1024.
-    Depth,
-    // Address of caller.
-    CallerAddress,
-    // Address of code which interpreter is executing, could differ from
-    // `ReceiverAddress` when delegate call.
-    CodeAddress,
-    // Address of receiver.
-    ReceiverAddress,
-    // Gas given of call.
-    GasAvailable,
-    // Value in wei of call.
-    Value,
-    // If call succeeds or not in the future.
-    IsPersistent,
-    // If call is within a static call.
-    IsStatic,
-    // If call is `CREATE*`. We lookup op from calldata when is create,
-    // otherwise we lookup code under address.
-    IsCreate,
-}
-
-#[derive(Clone, Debug)]
-pub(crate) enum CallStateField {
-    // Program counter.
-    ProgramCounter,
-    // Stack pointer.
-    StackPointer,
-    // Memory size.
-    MemorySize,
-    // Gas counter.
-    GasCounter,
-    // State trie write counter.
-    StateWriteCounter,
-    // Last callee's unique identifier.
-    CalleeID,
-    // Offset of returndata.
-    ReturndataOffset,
-    // Size of returndata.
-    ReturndataSize,
-}
+mod witness;
+pub use witness::WitnessError;
+use witness::{
+    block_witness, exp_table, keccak_table, BlockWitness, TxWitness,
+};
 
 #[derive(Clone, Debug)]
 pub(crate) enum BusMappingLookup<F> {
     // Read-Write
-    OpExecutionState {
+    CallContext {
         is_write: bool,
-        field: CallStateField,
+        // The context of the caller and of the callee are accessed when
+        // calling and returning, so the call id needs to be specified.
+        call_id: Expression<F>,
+        field: CallContextField,
         value: Expression<F>,
+        gc_offset: Expression<F>,
     },
     Stack {
         is_write: Expression<F>,
@@ -101,36 +67,133 @@ pub(crate) enum BusMappingLookup<F> {
         value: Expression<F>,
         gc_offset: Expression<F>,
     },
+    // The storage operations also carry the tx doing them and the value of
+    // the slot at the beginning of the tx, in the auxiliary columns.
     AccountStorage {
         is_write: bool,
         address: Expression<F>,
         location: Expression<F>,
         value: Expression<F>,
         value_prev: Expression<F>,
+        tx_id: Expression<F>,
+        committed_value: Expression<F>,
+        gc_offset: Expression<F>,
+    },
+    AccountNonce {
+        is_write: bool,
+        address: Expression<F>,
+        value: Expression<F>,
+        value_prev: Expression<F>,
+        gc_offset: Expression<F>,
+    },
+    AccountBalance {
+        is_write: bool,
+        address: Expression<F>,
+        value: Expression<F>,
+        value_prev: Expression<F>,
+        gc_offset: Expression<F>,
+    },
+    AccountCodeHash {
+        is_write: bool,
+        address: Expression<F>,
+        value: Expression<F>,
+        value_prev: Expression<F>,
+        gc_offset: Expression<F>,
+    },
+    TxAccessListAccount {
+        is_write: bool,
+        tx_id: Expression<F>,
+        address: Expression<F>,
+        value: Expression<F>,
+        value_prev: Expression<F>,
+        gc_offset: Expression<F>,
+    },
+    // TODO: Specify the tx id once the tx context is available in the
+    // circuit.
+    TxAccessListAccountStorage {
+        is_write: bool,
+        address: Expression<F>,
+        key: Expression<F>,
+        value: Expression<F>,
+        value_prev: Expression<F>,
+        gc_offset: Expression<F>,
+    },
+    TxRefund {
+        is_write: bool,
+        value: Expression<F>,
+        value_prev: Expression<F>,
+        gc_offset: Expression<F>,
+    },
+    // A write to any target of the state, given with the columns of the rw
+    // table following the target, like the writes undoing the state writes of
+    // a reverted call.
+    StateWrite {
+        target: Expression<F>,
+        keys: [Expression<F>; 2],
+        value: Expression<F>,
+        value_prev: Expression<F>,
+        aux: [Expression<F>; 2],
+        gc_offset: Expression<F>,
+    },
+    // TODO: Specify the tx id once the tx context is available in the
+    // circuit.
+    // Logs are only written, so there's no is_write.
+    TxLog {
+        log_id: Expression<F>,
+        field: TxLogField,
+        index: Expression<F>,
+        value: Expression<F>,
         gc_offset: Expression<F>,
     },
-    // TODO: AccountNonce,
-    // TODO: AccountBalance,
-    // TODO: AccountCodeHash,
-
     // Read-Only
-    Call {
-        field: CallField,
+    // TODO: Bytecode
+    TxContext {
+        tx_id: Expression<F>,
+        field: TxContextField,
+        value: Expression<F>,
+    },
+    TxCalldata {
+        tx_id: Expression<F>,
+        index: Expression<F>,
+        value: Expression<F>,
+    },
+    // The number is only used by the block hashes, which are indexed by the
+    // number of their block.
+    BlockContext {
+        field: Expression<F>,
+        number: Expression<F>,
         value: Expression<F>,
     },
-    /* TODO: Block,
-     * TODO: Bytecode,
-     * TODO: Tx,
-     * TODO: TxCalldata, */
 }
 
 impl<F: FieldExt> BusMappingLookup<F> {
-    fn rw_target(&self) -> Target {
+    fn rw_target(&self) -> Expression<F> {
         match self {
-            Self::Stack { .. } => Target::Stack,
-            Self::Memory { .. } => Target::Memory,
-            Self::AccountStorage { .. } => Target::Storage,
-            _ => unimplemented!(),
+            Self::CallContext { .. } => Target::CallContext.expr(),
+            Self::Stack { .. } => Target::Stack.expr(),
+            Self::Memory { .. } => Target::Memory.expr(),
+            Self::AccountStorage { .. } => Target::Storage.expr(),
+            Self::AccountNonce { .. } => Target::AccountNonce.expr(),
+            Self::AccountBalance { .. } => Target::AccountBalance.expr(),
+            Self::AccountCodeHash { .. } => Target::AccountCodeHash.expr(),
+            Self::TxAccessListAccount { .. } => {
+                Target::TxAccessListAccount.expr()
+            }
+            Self::TxAccessListAccountStorage { .. } => {
+                Target::TxAccessListAccountStorage.expr()
+            }
+            Self::TxRefund { .. } => Target::TxRefund.expr(),
+            Self::StateWrite { target, .. } => target.clone(),
+            Self::TxLog { .. } => Target::TxLog.expr(),
+            Self::TxContext { .. } => {
+                unreachable!("TxContext lookup is read-only")
+            }
+            Self::TxCalldata { .. } => {
+                unreachable!("TxCalldata lookup is read-only")
+            }
+            Self::BlockContext { .. } => {
+                unreachable!("BlockContext lookup is read-only")
+            }
         }
     }
 }
@@ -143,12 +206,14 @@ pub(crate) enum FixedLookup {
     Range16,
     Range17,
     Range32,
+    Range64,
     Range256,
     Range512,
     BitwiseAnd,
     BitwiseOr,
     BitwiseXor,
     SignByte,
+    Pow2,
 }
 
 impl<F: FieldExt> Expr<F> for FixedLookup {
@@ -157,12 +222,61 @@ impl<F: FieldExt> Expr<F> for FixedLookup {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum TxContextField {
+    Origin = 1,
+    GasPrice,
+    Nonce,
+    Gas,
+    CalleeAddress,
+    IsCreate,
+    Value,
+    CallDataLength,
+    TxSignHash,
+    // The gas paid for the calldata and for the access list, which are part
+    // of the intrinsic gas
+    CallDataGasCost,
+    AccessListGasCost,
+    // The keccak hash of the signed tx
+    TxHash,
+}
+
+impl<F: FieldExt> Expr<F> for TxContextField {
+    fn expr(&self) -> Expression<F> {
+        Expression::Constant(F::from(*self as u64))
+    }
+}
+
+// The fields pushed by COINBASE to BASEFEE are tagged by their opcode minus
+// BLOCKHASH, so that the tag follows from the opcode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum BlockContextField {
+    Coinbase = 1,
+    Timestamp,
+    Number,
+    Difficulty,
+    GasLimit,
+    ChainId,
+    BaseFee = 8,
+    BlockHash,
+}
+
+impl<F: FieldExt> Expr<F> for BlockContextField {
+    fn expr(&self) -> Expression<F> {
+        Expression::Constant(F::from(*self as u64))
+    }
+}
+
 #[allow(clippy::enum_variant_names)]
 #[derive(Clone, Debug)]
 pub(crate) enum Lookup<F> {
     FixedLookup(FixedLookup, [Expression<F>; 3]),
     BusMappingLookup(BusMappingLookup<F>),
     BytecodeLookup([Expression<F>; 4]),
+    ExpLookup([Expression<F>; 3]),
+    KeccakLookup([Expression<F>; 3]),
+    // Lookup that is only enabled when the condition is 1
+    Conditional(Expression<F>, Box<Lookup<F>>),
 }
 
 #[derive(Clone, Debug)]
@@ -266,6 +380,11 @@ pub(crate) struct CoreStateInstance {
     stack_pointer: usize,
     gas_counter: u64,
     memory_size: u64,
+    // Hash of the code being executed, as a little-endian word
+    code_source: [u8; 32],
+    log_id: usize,
+    gas_left: u64,
+    state_write_counter: usize,
 }
 
 impl CoreStateInstance {
@@ -278,6 +397,10 @@ impl CoreStateInstance {
             stack_pointer: 1024,
             gas_counter: 0,
             memory_size: 0,
+            code_source: [0; 32],
+            log_id: 0,
+            gas_left: 0,
+            state_write_counter: 0,
         }
     }
 }
@@ -321,27 +444,42 @@ pub(crate) struct ExecutionStep {
     values: Vec<BigUint>,
 }
 
+// Step done by a transaction outside of its execution, before its first step
+// or after its last one.
+pub(crate) struct TxExecutionStep {
+    values: Vec<BigUint>,
+}
+
 // TODO: use Operation from bus_mapping
 pub(crate) struct Operation<F> {
     gc: usize,
     target: Target,
     is_write: bool,
-    values: [F; 4],
+    values: [F; 6],
 }
 
 #[derive(Clone)]
 struct EvmCircuit<F> {
     q_step: Selector,
+    q_first_step: Selector,
     qs_byte_lookup: Column<Advice>,
     fixed_table: [Column<Fixed>; 4],
-    rw_table: [Column<Advice>; 7],
+    rw_table: [Column<Advice>; 9],
     bytecode_table: [Column<Advice>; 4],
+    exp_table: [Column<Advice>; 3],
+    tx_table: [Column<Advice>; 3],
+    tx_calldata_table: [Column<Advice>; 3],
+    keccak_table: [Column<Advice>; 3],
+    block_table: [Column<Advice>; 3],
+    public_input: PublicInputConfig<F>,
+    tx_circuit: TxConfig<F>,
     op_execution_gadget: OpExecutionGadget<F>,
 }
 
 impl<F: FieldExt> EvmCircuit<F> {
     fn configure(meta: &mut ConstraintSystem<F>, r: F) -> Self {
         let q_step = meta.complex_selector();
+        let q_first_step = meta.selector();
         let qs_byte_lookup = meta.advice_column();
         let advices = (0..CIRCUIT_WIDTH)
             .map(|_| meta.advice_column())
@@ -359,8 +497,11 @@ impl<F: FieldExt> EvmCircuit<F> {
             meta.advice_column(), // val2
             meta.advice_column(), // val3
             meta.advice_column(), // val4
+            meta.advice_column(), // aux1
+            meta.advice_column(), // aux2
         ];
 
+        // TODO: bytecode_table should be proven by the bytecode circuit
         let bytecode_table = [
             meta.advice_column(), // code_hash
             meta.advice_column(), // index
@@ -368,9 +509,69 @@ impl<F: FieldExt> EvmCircuit<F> {
             meta.advice_column(), // byte code
         ];
 
+        // TODO: exp_table should be shared with the exp circuit, which proves
+        // it. exp_table contains the results of EXP in compressed word form
+        let exp_table = [
+            meta.advice_column(), // base
+            meta.advice_column(), // exponent
+            meta.advice_column(), // base ** exponent (mod 2**256)
+        ];
+
+        // tx_table and tx_calldata_table are proven by the tx circuit, from
+        // the signed txs of the block
+        let tx_table = [
+            meta.advice_column(), // tx_id
+            meta.advice_column(), // field tag
+            meta.advice_column(), // value
+        ];
+        let tx_calldata_table = [
+            meta.advice_column(), // tx_id
+            meta.advice_column(), // index
+            meta.advice_column(), // byte
+        ];
+
+        // keccak_table is a stub: its rows are witnessed by the prover from
+        // the hashed inputs, and nothing proves that the hashes are the
+        // keccak of the inputs, since the keccak circuit isn't configured on
+        // it yet as it is for the MPT circuit.  Until then the hashes of
+        // SHA3, the code hashes with the code sizes read from them, and the
+        // addresses of CREATE are unsound.
+        // TODO: keccak_table should be proven by the keccak circuit
+        let keccak_table = [
+            meta.advice_column(), // random linear combination of the input
+            meta.advice_column(), // length of the input
+            meta.advice_column(), // random linear combination of the hash
+        ];
+
+        // block_table is proven by the public input circuit, from the words
+        // of the block which are committed to by the instance column, whose
+        // tx hashes are the ones of tx_table
+        let block_table = [
+            meta.advice_column(), // field tag
+            meta.advice_column(), // block number of a block hash
+            meta.advice_column(), // value
+        ];
+        let instance = meta.instance_column();
+        let public_input = PublicInputConfig::configure(
+            meta,
+            r,
+            block_table,
+            tx_table,
+            instance,
+        );
+        let tx_circuit = TxConfig::configure(
+            meta,
+            r,
+            tx_table,
+            tx_calldata_table,
+            keccak_table,
+            block_table,
+        );
+
         // fixed_table contains pre-built tables identified by tag including:
         // - different size range tables
         // - bitwise table
+        // - power of two table
         // - comparator table
         // - ...
         let fixed_table = [
@@ -388,6 +589,18 @@ impl<F: FieldExt> EvmCircuit<F> {
             op_execution_free_cells,
         ) = Self::configure_allocations(meta, q_step, qs_byte_lookup, advices);
 
+        // No transaction is finished before the first step, whose global
+        // counter is the one of the first operation
+        meta.create_gate("First step", |meta| {
+            let q_first_step = meta.query_selector(q_first_step);
+            vec![
+                q_first_step.clone() * op_execution_state_curr.call_id.expr(),
+                q_first_step
+                    * (op_execution_state_curr.global_counter.expr()
+                        - 1.expr()),
+            ]
+        });
+
         // independent_lookups collect lookups by independent selectors, which
         // means we can sum some of them together to save lookups.
         let mut independent_lookups =
@@ -413,16 +626,29 @@ impl<F: FieldExt> EvmCircuit<F> {
             fixed_table,
             rw_table,
             bytecode_table,
+            exp_table,
+            tx_table,
+            tx_calldata_table,
+            keccak_table,
+            block_table,
             op_execution_state_curr,
             independent_lookups,
         );
 
         EvmCircuit {
             q_step,
+            q_first_step,
             qs_byte_lookup,
             fixed_table,
             rw_table,
             bytecode_table,
+            exp_table,
+            tx_table,
+            tx_calldata_table,
+            keccak_table,
+            block_table,
+            public_input,
+            tx_circuit,
             op_execution_gadget,
         }
     }
@@ -518,23 +744,46 @@ impl<F: FieldExt> EvmCircuit<F> {
         qs_byte_lookup: Column<Advice>,
         advices: [Column<Advice>; CIRCUIT_WIDTH],
         fixed_table: [Column<Fixed>; 4],
-        rw_table: [Column<Advice>; 7],
+        rw_table: [Column<Advice>; 9],
         bytecode_table: [Column<Advice>; 4],
+        exp_table: [Column<Advice>; 3],
+        tx_table: [Column<Advice>; 3],
+        tx_calldata_table: [Column<Advice>; 3],
+        keccak_table: [Column<Advice>; 3],
+        block_table: [Column<Advice>; 3],
         op_execution_state_curr: OpExecutionState<F>,
         independent_lookups: Vec<(Expression<F>, Vec<Lookup<F>>)>,
     ) {
         // TODO: call_lookups
 
         let mut fixed_lookups = Vec::<[Expression<F>; 4]>::new();
-        let mut rw_lookups = Vec::<[Expression<F>; 7]>::new();
+        let mut rw_lookups = Vec::<[Expression<F>; 9]>::new();
         let mut bytecode_lookups = Vec::<[Expression<F>; 4]>::new();
+        let mut exp_lookups = Vec::<[Expression<F>; 3]>::new();
+        let mut tx_lookups = Vec::<[Expression<F>; 3]>::new();
+        let mut tx_calldata_lookups = Vec::<[Expression<F>; 3]>::new();
+        let mut keccak_lookups = Vec::<[Expression<F>; 3]>::new();
+        let mut block_lookups = Vec::<[Expression<F>; 3]>::new();
 
         for (qs_lookup, lookups) in independent_lookups {
             let mut fixed_lookup_count = 0;
             let mut rw_lookup_count = 0;
             let mut bytecode_lookup_count = 0;
+            let mut exp_lookup_count = 0;
+            let mut tx_lookup_count = 0;
+            let mut tx_calldata_lookup_count = 0;
+            let mut keccak_lookup_count = 0;
+            let mut block_lookup_count = 0;
+
+            for mut lookup in lookups {
+                // A conditional lookup is enabled by its condition on top of
+                // the selector of its case
+                let mut qs_lookup = qs_lookup.clone();
+                while let Lookup::Conditional(condition, inner) = lookup {
+                    qs_lookup = qs_lookup * condition;
+                    lookup = *inner;
+                }
 
-            for lookup in lookups {
                 match lookup {
                     Lookup::FixedLookup(tag, exprs) => {
                         let exprs = iter::once(tag.expr()).chain(exprs.clone());
@@ -558,11 +807,20 @@ impl<F: FieldExt> EvmCircuit<F> {
                         fixed_lookup_count += 1;
                     }
                     Lookup::BusMappingLookup(
-                        rw_lookup @ (BusMappingLookup::Stack { .. }
+                        rw_lookup @ (BusMappingLookup::CallContext { .. }
+                        | BusMappingLookup::Stack { .. }
                         | BusMappingLookup::Memory { .. }
-                        | BusMappingLookup::AccountStorage {
+                        | BusMappingLookup::AccountStorage { .. }
+                        | BusMappingLookup::AccountNonce { .. }
+                        | BusMappingLookup::AccountBalance { .. }
+                        | BusMappingLookup::AccountCodeHash { .. }
+                        | BusMappingLookup::TxAccessListAccount { .. }
+                        | BusMappingLookup::TxAccessListAccountStorage {
                             ..
-                        }),
+                        }
+                        | BusMappingLookup::TxRefund { .. }
+                        | BusMappingLookup::StateWrite { .. }
+                        | BusMappingLookup::TxLog { .. }),
                     ) => {
                         let OpExecutionState {
                             global_counter,
@@ -573,17 +831,32 @@ impl<F: FieldExt> EvmCircuit<F> {
 
                         if rw_lookups.len() == rw_lookup_count {
                             rw_lookups
-                                .push(vec![0.expr(); 7].try_into().unwrap());
+                                .push(vec![0.expr(); 9].try_into().unwrap());
                         }
 
-                        let rw_target = rw_lookup.rw_target().expr();
+                        let rw_target = rw_lookup.rw_target();
                         let exprs = vec![match rw_lookup {
+                            BusMappingLookup::CallContext {
+                                is_write,
+                                call_id,
+                                field,
+                                value,
+                                gc_offset,
+                            } => vec![
+                                global_counter.expr() + gc_offset,
+                                rw_target,
+                                is_write.expr(),
+                                call_id,
+                                field.expr(),
+                                value,
+                                0.expr(),
+                            ],
                             BusMappingLookup::Stack {
                                 is_write,
                                 index_offset,
                                 value,
                                 gc_offset,
-                            } => [
+                            } => vec![
                                 global_counter.expr() + gc_offset,
                                 rw_target,
                                 is_write,
@@ -598,7 +871,7 @@ impl<F: FieldExt> EvmCircuit<F> {
                                 index,
                                 value,
                                 gc_offset,
-                            } => [
+                            } => vec![
                                 global_counter.expr() + gc_offset,
                                 rw_target,
                                 is_write,
@@ -613,8 +886,10 @@ impl<F: FieldExt> EvmCircuit<F> {
                                 location,
                                 value,
                                 value_prev,
+                                tx_id,
+                                committed_value,
                                 gc_offset,
-                            } => [
+                            } => vec![
                                 global_counter.expr() + gc_offset,
                                 rw_target,
                                 is_write.expr(),
@@ -622,10 +897,122 @@ impl<F: FieldExt> EvmCircuit<F> {
                                 location,
                                 value,
                                 value_prev,
+                                tx_id,
+                                committed_value,
                             ],
-
-                            // TODO:
-                            _ => unimplemented!(),
+                            BusMappingLookup::AccountNonce {
+                                is_write,
+                                address,
+                                value,
+                                value_prev,
+                                gc_offset,
+                            }
+                            | BusMappingLookup::AccountBalance {
+                                is_write,
+                                address,
+                                value,
+                                value_prev,
+                                gc_offset,
+                            }
+                            | BusMappingLookup::AccountCodeHash {
+                                is_write,
+                                address,
+                                value,
+                                value_prev,
+                                gc_offset,
+                            } => vec![
+                                global_counter.expr() + gc_offset,
+                                rw_target,
+                                is_write.expr(),
+                                address,
+                                0.expr(),
+                                value,
+                                value_prev,
+                            ],
+                            BusMappingLookup::TxAccessListAccount {
+                                is_write,
+                                tx_id,
+                                address,
+                                value,
+                                value_prev,
+                                gc_offset,
+                            } => vec![
+                                global_counter.expr() + gc_offset,
+                                rw_target,
+                                is_write.expr(),
+                                tx_id,
+                                address,
+                                value,
+                                value_prev,
+                            ],
+                            BusMappingLookup::TxAccessListAccountStorage {
+                                is_write,
+                                address,
+                                key,
+                                value,
+                                value_prev,
+                                gc_offset,
+                            } => vec![
+                                global_counter.expr() + gc_offset,
+                                rw_target,
+                                is_write.expr(),
+                                address,
+                                key,
+                                value,
+                                value_prev,
+                            ],
+                            BusMappingLookup::TxRefund {
+                                is_write,
+                                value,
+                                value_prev,
+                                gc_offset,
+                            } => vec![
+                                global_counter.expr() + gc_offset,
+                                rw_target,
+                                is_write.expr(),
+                                0.expr(),
+                                0.expr(),
+                                value,
+                                value_prev,
+                            ],
+                            BusMappingLookup::StateWrite {
+                                target: _,
+                                keys,
+                                value,
+                                value_prev,
+                                aux,
+                                gc_offset,
+                            } => [
+                                vec![
+                                    global_counter.expr() + gc_offset,
+                                    rw_target,
+                                    true.expr(),
+                                ],
+                                keys.to_vec(),
+                                vec![value, value_prev],
+                                aux.to_vec(),
+                            ]
+                            .concat(),
+                            BusMappingLookup::TxLog {
+                                log_id,
+                                field,
+                                index,
+                                value,
+                                gc_offset,
+                            } => vec![
+                                global_counter.expr() + gc_offset,
+                                rw_target,
+                                true.expr(),
+                                log_id,
+                                field.expr(),
+                                index,
+                                value,
+                            ],
+                            BusMappingLookup::TxContext { .. }
+                            | BusMappingLookup::TxCalldata { .. }
+                            | BusMappingLookup::BlockContext { .. } => {
+                                unreachable!()
+                            }
                         }]
                         .concat();
 
@@ -658,6 +1045,118 @@ impl<F: FieldExt> EvmCircuit<F> {
                         }
                         bytecode_lookup_count += 1;
                     }
+                    Lookup::ExpLookup(exprs) => {
+                        if exp_lookups.len() == exp_lookup_count {
+                            exp_lookups.push(
+                                exprs
+                                    .iter()
+                                    .map(|expr| {
+                                        qs_lookup.clone() * expr.clone()
+                                    })
+                                    .collect::<Vec<_>>()
+                                    .try_into()
+                                    .unwrap(),
+                            );
+                        } else {
+                            for (acc, expr) in exp_lookups[exp_lookup_count]
+                                .iter_mut()
+                                .zip(exprs.iter())
+                            {
+                                *acc = acc.clone()
+                                    + qs_lookup.clone() * expr.clone();
+                            }
+                        }
+                        exp_lookup_count += 1;
+                    }
+                    Lookup::KeccakLookup(exprs) => {
+                        if keccak_lookups.len() == keccak_lookup_count {
+                            keccak_lookups.push(
+                                exprs
+                                    .iter()
+                                    .map(|expr| {
+                                        qs_lookup.clone() * expr.clone()
+                                    })
+                                    .collect::<Vec<_>>()
+                                    .try_into()
+                                    .unwrap(),
+                            );
+                        } else {
+                            for (acc, expr) in keccak_lookups
+                                [keccak_lookup_count]
+                                .iter_mut()
+                                .zip(exprs.iter())
+                            {
+                                *acc = acc.clone()
+                                    + qs_lookup.clone() * expr.clone();
+                            }
+                        }
+                        keccak_lookup_count += 1;
+                    }
+                    Lookup::BusMappingLookup(BusMappingLookup::TxContext {
+                        tx_id,
+                        field,
+                        value,
+                    }) => {
+                        let exprs = [tx_id, field.expr(), value];
+                        if tx_lookups.len() == tx_lookup_count {
+                            tx_lookups.push(
+                                exprs.map(|expr| qs_lookup.clone() * expr),
+                            );
+                        } else {
+                            for (acc, expr) in tx_lookups[tx_lookup_count]
+                                .iter_mut()
+                                .zip(exprs)
+                            {
+                                *acc = acc.clone() + qs_lookup.clone() * expr;
+                            }
+                        }
+                        tx_lookup_count += 1;
+                    }
+                    Lookup::BusMappingLookup(BusMappingLookup::TxCalldata {
+                        tx_id,
+                        index,
+                        value,
+                    }) => {
+                        let exprs = [tx_id, index, value];
+                        if tx_calldata_lookups.len() == tx_calldata_lookup_count
+                        {
+                            tx_calldata_lookups.push(
+                                exprs.map(|expr| qs_lookup.clone() * expr),
+                            );
+                        } else {
+                            for (acc, expr) in tx_calldata_lookups
+                                [tx_calldata_lookup_count]
+                                .iter_mut()
+                                .zip(exprs)
+                            {
+                                *acc = acc.clone() + qs_lookup.clone() * expr;
+                            }
+                        }
+                        tx_calldata_lookup_count += 1;
+                    }
+                    Lookup::BusMappingLookup(
+                        BusMappingLookup::BlockContext {
+                            field,
+                            number,
+                            value,
+                        },
+                    ) => {
+                        let exprs = [field, number, value];
+                        if block_lookups.len() == block_lookup_count {
+                            block_lookups.push(
+                                exprs.map(|expr| qs_lookup.clone() * expr),
+                            );
+                        } else {
+                            for (acc, expr) in block_lookups
+                                [block_lookup_count]
+                                .iter_mut()
+                                .zip(exprs)
+                            {
+                                *acc = acc.clone() + qs_lookup.clone() * expr;
+                            }
+                        }
+                        block_lookup_count += 1;
+                    }
                     _ => unimplemented!(),
                 }
             }
@@ -715,12 +1214,12 @@ impl<F: FieldExt> EvmCircuit<F> {
                     .collect::<Vec<_>>()
             });
         }
-        // Configure rw lookups
-        for rw_lookup in rw_lookups.iter() {
+        // Configure exp lookups
+        for exp_lookup in exp_lookups.iter() {
             meta.lookup_any(|meta| {
-                rw_lookup
+                exp_lookup
                     .iter()
-                    .zip(rw_table.iter())
+                    .zip(exp_table.iter())
                     .map(|(expr, column)| {
                         (
                             expr.clone(),
@@ -730,10 +1229,85 @@ impl<F: FieldExt> EvmCircuit<F> {
                     .collect::<Vec<_>>()
             });
         }
-    }
-
-    fn load_fixed_tables(
-        &self,
+        // Configure keccak lookups
+        for keccak_lookup in keccak_lookups.iter() {
+            meta.lookup_any(|meta| {
+                keccak_lookup
+                    .iter()
+                    .zip(keccak_table.iter())
+                    .map(|(expr, column)| {
+                        (
+                            expr.clone(),
+                            meta.query_advice(*column, Rotation::cur()),
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            });
+        }
+        // Configure tx lookups
+        for tx_lookup in tx_lookups.iter() {
+            meta.lookup_any(|meta| {
+                tx_lookup
+                    .iter()
+                    .zip(tx_table.iter())
+                    .map(|(expr, column)| {
+                        (
+                            expr.clone(),
+                            meta.query_advice(*column, Rotation::cur()),
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            });
+        }
+        // Configure tx calldata lookups
+        for tx_calldata_lookup in tx_calldata_lookups.iter() {
+            meta.lookup_any(|meta| {
+                tx_calldata_lookup
+                    .iter()
+                    .zip(tx_calldata_table.iter())
+                    .map(|(expr, column)| {
+                        (
+                            expr.clone(),
+                            meta.query_advice(*column, Rotation::cur()),
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            });
+        }
+        // Configure block lookups
+        for block_lookup in block_lookups.iter() {
+            meta.lookup_any(|meta| {
+                block_lookup
+                    .iter()
+                    .zip(block_table.iter())
+                    .map(|(expr, column)| {
+                        (
+                            expr.clone(),
+                            meta.query_advice(*column, Rotation::cur()),
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            });
+        }
+        // Configure rw lookups
+        for rw_lookup in rw_lookups.iter() {
+            meta.lookup_any(|meta| {
+                rw_lookup
+                    .iter()
+                    .zip(rw_table.iter())
+                    .map(|(expr, column)| {
+                        (
+                            expr.clone(),
+                            meta.query_advice(*column, Rotation::cur()),
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            });
+        }
+    }
+
+    fn load_fixed_tables(
+        &self,
         layouter: &mut impl Layouter<F>,
         including_large_tables: bool,
     ) -> Result<(), Error> {
@@ -807,6 +1381,33 @@ impl<F: FieldExt> EvmCircuit<F> {
                     offset += 1;
                 }
 
+                // Range64
+                for idx in 0..64 {
+                    region.assign_fixed(
+                        || "Range64: tag",
+                        self.fixed_table[0],
+                        offset,
+                        || Ok(F::from(FixedLookup::Range64 as u64)),
+                    )?;
+                    region.assign_fixed(
+                        || "Range64: value",
+                        self.fixed_table[1],
+                        offset,
+                        || Ok(F::from(idx as u64)),
+                    )?;
+                    for (idx, column) in
+                        self.fixed_table[2..].iter().enumerate()
+                    {
+                        region.assign_fixed(
+                            || format!("Range64: padding {}", idx),
+                            *column,
+                            offset,
+                            || Ok(F::zero()),
+                        )?;
+                    }
+                    offset += 1;
+                }
+
                 // Range17
                 for idx in 0..17 {
                     region.assign_fixed(
@@ -921,6 +1522,35 @@ impl<F: FieldExt> EvmCircuit<F> {
                     offset += 1;
                 }
 
+                // Pow2
+                for idx in 0..256 {
+                    region.assign_fixed(
+                        || "Pow2: tag",
+                        self.fixed_table[0],
+                        offset,
+                        || Ok(F::from(FixedLookup::Pow2 as u64)),
+                    )?;
+                    region.assign_fixed(
+                        || "Pow2: value",
+                        self.fixed_table[1],
+                        offset,
+                        || Ok(F::from(idx as u64)),
+                    )?;
+                    region.assign_fixed(
+                        || "Pow2: limb index",
+                        self.fixed_table[2],
+                        offset,
+                        || Ok(F::from(idx as u64 / 64)),
+                    )?;
+                    region.assign_fixed(
+                        || "Pow2: 2**(value % 64)",
+                        self.fixed_table[3],
+                        offset,
+                        || Ok(F::from(1u64 << (idx % 64))),
+                    )?;
+                    offset += 1;
+                }
+
                 if including_large_tables {
                     // BitwiseAnd
                     for a in 0..256 {
@@ -1077,10 +1707,9 @@ impl<F: FieldExt> EvmCircuit<F> {
                 for operation in operations.iter() {
                     // TODO: Ensure we got a sorted by gc operation from
                     // bus-mapping
-                    assert_eq!(
-                        operation.gc, offset,
-                        "global counter uniqueness assumption is broken"
-                    );
+                    if operation.gc != offset {
+                        return Err(Error::Synthesis);
+                    }
 
                     let values = [
                         vec![
@@ -1111,7 +1740,7 @@ impl<F: FieldExt> EvmCircuit<F> {
     fn load_bytecode_tables(
         &self,
         layouter: &mut impl Layouter<F>,
-        bytecode_table: Vec<[u32; 4]>,
+        bytecode_table: &[[F; 4]],
     ) -> Result<(), Error> {
         layouter.assign_region(
             || "bytecode table",
@@ -1136,7 +1765,241 @@ impl<F: FieldExt> EvmCircuit<F> {
                             || "bytecode table",
                             *column,
                             offset,
-                            || Ok(F::from(*value as u64)),
+                            || Ok(*value),
+                        )?;
+                    }
+                    offset += 1;
+                }
+
+                Ok(())
+            },
+        )
+    }
+
+    fn load_exp_tables(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        exp_table: Vec<[F; 3]>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "exp table",
+            |mut region| {
+                let mut offset = 0;
+
+                for column in self.exp_table.iter() {
+                    region.assign_advice(
+                        || "exp noop",
+                        *column,
+                        offset,
+                        || Ok(F::zero()),
+                    )?;
+                }
+                offset += 1;
+
+                for exp_entry in exp_table.iter() {
+                    for (column, value) in self.exp_table.iter().zip(exp_entry)
+                    {
+                        region.assign_advice(
+                            || "exp table",
+                            *column,
+                            offset,
+                            || Ok(*value),
+                        )?;
+                    }
+                    offset += 1;
+                }
+
+                Ok(())
+            },
+        )
+    }
+
+    // The rows are trusted as they are given, see keccak_table.
+    fn load_keccak_tables(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        keccak_table: Vec<[F; 3]>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "keccak table",
+            |mut region| {
+                let mut offset = 0;
+
+                for column in self.keccak_table.iter() {
+                    region.assign_advice(
+                        || "keccak noop",
+                        *column,
+                        offset,
+                        || Ok(F::zero()),
+                    )?;
+                }
+                offset += 1;
+
+                for keccak_entry in keccak_table.iter() {
+                    for (column, value) in
+                        self.keccak_table.iter().zip(keccak_entry)
+                    {
+                        region.assign_advice(
+                            || "keccak table",
+                            *column,
+                            offset,
+                            || Ok(*value),
+                        )?;
+                    }
+                    offset += 1;
+                }
+
+                Ok(())
+            },
+        )
+    }
+
+    // Assigns the block table without proving it, for the tests which don't
+    // commit to the words of a block
+    fn load_block_tables(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        block_table: &[[F; 3]],
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "block table",
+            |mut region| {
+                let mut offset = 0;
+
+                for column in self.block_table.iter() {
+                    region.assign_advice(
+                        || "block noop",
+                        *column,
+                        offset,
+                        || Ok(F::zero()),
+                    )?;
+                }
+                offset += 1;
+
+                for block_entry in block_table.iter() {
+                    for (column, value) in
+                        self.block_table.iter().zip(block_entry)
+                    {
+                        region.assign_advice(
+                            || "block table",
+                            *column,
+                            offset,
+                            || Ok(*value),
+                        )?;
+                    }
+                    offset += 1;
+                }
+
+                Ok(())
+            },
+        )
+    }
+
+    // Proves the block table from the `words` of a block, in the layout of the
+    // public inputs for blocks of at most `max_txs` txs
+    fn load_public_inputs(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        max_txs: usize,
+        words: &[[u8; 32]],
+    ) -> Result<(), Error> {
+        self.public_input.load(layouter)?;
+        // Each word takes a row per byte
+        let size = 16 * instance_len(max_txs);
+        self.public_input.assign(layouter, size, max_txs, words)
+    }
+
+    // Proves the tx tables from the signed `txs` of a block, in the layout
+    // for blocks of at most `max_txs` txs, and returns the rows of the keccak
+    // table which hash their payloads and the signed txs
+    fn load_signed_txs(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        max_txs: usize,
+        txs: &[SignedTx],
+    ) -> Result<Vec<[F; 3]>, Error> {
+        let r = self.tx_circuit.r();
+        let txs: Vec<_> = txs.iter().map(|tx| unroll(tx.clone(), r)).collect();
+        // The padding row after the payload rows is the noop row of the tx
+        // calldata table, and the unused tx slot the one of the tx table
+        let size = txs
+            .iter()
+            .map(|tx| tx.rows.len() + tx.signed_rows.len())
+            .sum::<usize>()
+            + 1;
+        self.tx_circuit.load(layouter)?;
+        self.tx_circuit.assign(layouter, size, max_txs + 1, &txs)?;
+        Ok(txs.iter().flat_map(|tx| tx.tx.keccak_rows(r)).collect())
+    }
+
+    // Assigns the tx table without proving it, for the tests which don't have
+    // the signed txs of a block
+    fn load_tx_tables(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        tx_table: &[[F; 3]],
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "tx table",
+            |mut region| {
+                let mut offset = 0;
+
+                for column in self.tx_table.iter() {
+                    region.assign_advice(
+                        || "tx noop",
+                        *column,
+                        offset,
+                        || Ok(F::zero()),
+                    )?;
+                }
+                offset += 1;
+
+                for tx_entry in tx_table.iter() {
+                    for (column, value) in self.tx_table.iter().zip(tx_entry) {
+                        region.assign_advice(
+                            || "tx table",
+                            *column,
+                            offset,
+                            || Ok(*value),
+                        )?;
+                    }
+                    offset += 1;
+                }
+
+                Ok(())
+            },
+        )
+    }
+
+    fn load_tx_calldata_tables(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        tx_calldata_table: &[[u64; 3]],
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "tx calldata table",
+            |mut region| {
+                let mut offset = 0;
+
+                for column in self.tx_calldata_table.iter() {
+                    region.assign_advice(
+                        || "tx calldata noop",
+                        *column,
+                        offset,
+                        || Ok(F::zero()),
+                    )?;
+                }
+                offset += 1;
+
+                for tx_calldata_entry in tx_calldata_table.iter() {
+                    for (column, value) in
+                        self.tx_calldata_table.iter().zip(tx_calldata_entry)
+                    {
+                        region.assign_advice(
+                            || "tx calldata table",
+                            *column,
+                            offset,
+                            || Ok(F::from(*value)),
                         )?;
                     }
                     offset += 1;
@@ -1150,7 +2013,9 @@ impl<F: FieldExt> EvmCircuit<F> {
     fn assign(
         &self,
         layouter: &mut impl Layouter<F>,
+        begin_tx_step: Option<&TxExecutionStep>,
         execution_steps: &[ExecutionStep],
+        end_tx_step: Option<&TxExecutionStep>,
     ) -> Result<(), Error> {
         layouter.assign_region(
             || "evm circuit",
@@ -1159,24 +2024,89 @@ impl<F: FieldExt> EvmCircuit<F> {
 
                 // TODO: call_initialization should maintain this
                 core_state.is_executing = true;
+                // Without the step beginning a transaction, the steps are
+                // given all the gas
+                if begin_tx_step.is_none() {
+                    core_state.gas_left = u64::MAX;
+                }
 
-                for (idx, execution_step) in execution_steps.iter().enumerate()
-                {
-                    let offset = idx * CIRCUIT_HEIGHT;
+                self.q_first_step.enable(&mut region, 0)?;
+                let offset = self.assign_tx(
+                    &mut region,
+                    0,
+                    &mut core_state,
+                    begin_tx_step,
+                    execution_steps,
+                    end_tx_step,
+                )?;
 
-                    self.q_step.enable(&mut region, offset)?;
+                self.op_execution_gadget.assign_execution_step(
+                    &mut region,
+                    offset,
+                    &mut core_state,
+                    None,
+                )?;
+
+                Ok(())
+            },
+        )
+    }
+
+    // Assigns the steps of the `txs` of a block, followed by steps which
+    // aren't executing up to `max_steps` steps, so that the selectors of the
+    // steps don't depend on the block, and by the state after them.
+    fn assign_block(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        txs: &[TxWitness],
+        max_steps: usize,
+    ) -> Result<(), Error> {
+        let num_steps = txs
+            .iter()
+            .map(|tx| {
+                1 + tx.execution_steps.len() + tx.end_tx_step.is_some() as usize
+            })
+            .sum::<usize>();
+        if num_steps > max_steps {
+            return Err(Error::Synthesis);
+        }
 
-                    self.op_execution_gadget.assign_execution_step(
+        layouter.assign_region(
+            || "evm circuit",
+            |mut region| {
+                let mut core_state = CoreStateInstance::new(0);
+
+                // TODO: call_initialization should maintain this
+                core_state.is_executing = true;
+
+                self.q_first_step.enable(&mut region, 0)?;
+                let mut offset = 0;
+                for tx in txs.iter() {
+                    offset = self.assign_tx(
                         &mut region,
                         offset,
                         &mut core_state,
-                        Some(execution_step),
+                        Some(&tx.begin_tx_step),
+                        &tx.execution_steps,
+                        tx.end_tx_step.as_ref(),
+                    )?;
+                }
+
+                core_state.is_executing = false;
+                for offset in
+                    (offset..max_steps * CIRCUIT_HEIGHT).step_by(CIRCUIT_HEIGHT)
+                {
+                    self.q_step.enable(&mut region, offset)?;
+                    self.op_execution_gadget.assign_padding_step(
+                        &mut region,
+                        offset,
+                        &core_state,
                     )?;
                 }
 
                 self.op_execution_gadget.assign_execution_step(
                     &mut region,
-                    execution_steps.len() * CIRCUIT_HEIGHT,
+                    max_steps * CIRCUIT_HEIGHT,
                     &mut core_state,
                     None,
                 )?;
@@ -1185,75 +2115,440 @@ impl<F: FieldExt> EvmCircuit<F> {
             },
         )
     }
+
+    // Assigns the steps of a tx from `offset`, and returns the offset after
+    // them
+    fn assign_tx(
+        &self,
+        region: &mut Region<'_, F>,
+        mut offset: usize,
+        core_state: &mut CoreStateInstance,
+        begin_tx_step: Option<&TxExecutionStep>,
+        execution_steps: &[ExecutionStep],
+        end_tx_step: Option<&TxExecutionStep>,
+    ) -> Result<usize, Error> {
+        if let Some(begin_tx_step) = begin_tx_step {
+            self.q_step.enable(region, offset)?;
+            self.op_execution_gadget.assign_begin_tx_step(
+                region,
+                offset,
+                core_state,
+                begin_tx_step,
+            )?;
+            offset += CIRCUIT_HEIGHT;
+        }
+
+        for execution_step in execution_steps.iter() {
+            self.q_step.enable(region, offset)?;
+            self.op_execution_gadget.assign_execution_step(
+                region,
+                offset,
+                core_state,
+                Some(execution_step),
+            )?;
+            offset += CIRCUIT_HEIGHT;
+        }
+
+        if let Some(end_tx_step) = end_tx_step {
+            self.q_step.enable(region, offset)?;
+            self.op_execution_gadget.assign_end_tx_step(
+                region,
+                offset,
+                core_state,
+                end_tx_step,
+            )?;
+            offset += CIRCUIT_HEIGHT;
+        }
+
+        Ok(offset)
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct BlockCircuitConfig<F> {
+    evm_circuit: EvmCircuit<F>,
+}
+
+// The EVM circuit proving the steps of all the txs of a block, with room for
+// at most `MAX_TXS` txs and `MAX_STEPS` steps, whose block table is proven
+// from the public inputs of the block.  Its fixed columns only depend on
+// these sizes, so its keys are valid for any block which fits in them.
+#[derive(Default)]
+pub(crate) struct BlockCircuit<F, const MAX_TXS: usize, const MAX_STEPS: usize>
+{
+    witness: BlockWitness<F>,
+    words: Vec<[u8; 32]>,
+}
+
+impl<F: FieldExt, const MAX_TXS: usize, const MAX_STEPS: usize>
+    BlockCircuit<F, MAX_TXS, MAX_STEPS>
+{
+    // Builds the circuit of `block`, whose txs must halt at their last step.
+    // The tx table is assigned from the txs of the block, which doesn't hold
+    // their signatures.
+    pub(crate) fn new(block: &Block) -> Result<Self, WitnessError> {
+        let witness = block_witness(block, r())?;
+        if let Some(idx) =
+            witness.txs.iter().position(|tx| tx.end_tx_step.is_none())
+        {
+            return Err(WitnessError::UnsupportedTx(idx));
+        }
+        let num_steps = witness
+            .txs
+            .iter()
+            .map(|tx| tx.execution_steps.len() + 2)
+            .sum::<usize>();
+        if num_steps > MAX_STEPS {
+            return Err(WitnessError::TooManySteps(MAX_STEPS));
+        }
+
+        Ok(Self {
+            witness,
+            words: words(block, MAX_TXS)?,
+        })
+    }
+}
+
+impl<F: FieldExt, const MAX_TXS: usize, const MAX_STEPS: usize> Circuit<F>
+    for BlockCircuit<F, MAX_TXS, MAX_STEPS>
+{
+    type Config = BlockCircuitConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        Self::Config {
+            evm_circuit: EvmCircuit::configure(meta, r()),
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let evm_circuit = config.evm_circuit;
+        let witness = &self.witness;
+
+        evm_circuit.load_fixed_tables(&mut layouter, true)?;
+        evm_circuit.load_rw_tables(&mut layouter, &witness.operations)?;
+        evm_circuit.load_tx_tables(&mut layouter, &witness.tx_table)?;
+        evm_circuit.load_tx_calldata_tables(
+            &mut layouter,
+            &witness.tx_calldata_table,
+        )?;
+        // The circuit without witnesses has zero words, in the layout of the
+        // public inputs
+        let words = if self.words.is_empty() {
+            vec![[0; 32]; instance_len(MAX_TXS) / 2]
+        } else {
+            self.words.clone()
+        };
+        evm_circuit.load_public_inputs(&mut layouter, MAX_TXS, &words)?;
+        evm_circuit
+            .load_bytecode_tables(&mut layouter, &witness.bytecode_table)?;
+
+        // The exp and keccak tables of the steps of the txs, followed by the
+        // keccak table of the codes
+        let mut exp_rows = Vec::new();
+        let mut keccak_rows = Vec::new();
+        for tx in witness.txs.iter() {
+            exp_rows.extend(exp_table(&tx.execution_steps, r())?);
+            keccak_rows.extend(keccak_table(&tx.execution_steps, r())?);
+        }
+        keccak_rows.extend(witness.keccak_table.iter().cloned());
+        evm_circuit.load_exp_tables(&mut layouter, exp_rows)?;
+        evm_circuit.load_keccak_tables(&mut layouter, keccak_rows)?;
+
+        evm_circuit.assign_block(&mut layouter, &witness.txs, MAX_STEPS)
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use super::{EvmCircuit, ExecutionStep, Operation};
+    use super::{
+        witness::{
+            block_witness, bytecode_row, exp_table, keccak_table, push_data,
+        },
+        Case, EvmCircuit, ExecutionStep, Operation, TxExecutionStep,
+    };
+    use crate::{
+        gadget::evm_word::encode,
+        public_input_circuit::{public_input::words, PublicInputError},
+        tx_circuit::tx::SignedTx,
+    };
     use halo2::{
         arithmetic::FieldExt,
         circuit::{Layouter, SimpleFloorPlanner},
+        dev::{MockProver, VerifyFailure},
         plonk::{Circuit, ConstraintSystem, Error},
     };
 
     extern crate num;
-    use bus_mapping::evm::OpcodeId;
+    use bus_mapping::{
+        bytecode::Bytecode,
+        circuit_input_builder::{Block, CircuitInputBuilder},
+        evm::OpcodeId,
+        mock,
+        operation::Target,
+    };
+    use num::{ToPrimitive, Zero};
 
     #[derive(Clone)]
     pub(crate) struct TestCircuitConfig<F> {
         evm_circuit: EvmCircuit<F>,
     }
 
-    // contruct bytecode table from ExecutionSteps of test
-    pub(crate) fn assgin_byte_table_step(
+    // contruct bytecode table from ExecutionSteps of test, following the
+    // program counter through the jumps of a single code whose hash is 0
+    pub(crate) fn assgin_byte_table_step<F: FieldExt>(
         execution_steps: &[ExecutionStep],
-    ) -> Vec<[u32; 4]> {
-        // TODO: add keccak hash (byte_codes)
-        let code_hash = 0_u32;
-        let mut i = 0;
-        let mut bytecode_table = Vec::<[u32; 4]>::new();
+    ) -> Vec<[F; 4]> {
+        let mut pc = 0;
+        let mut bytecode_table = Vec::new();
 
         for curr_step in execution_steps.iter() {
-            let byte = curr_step.opcode.as_u8();
-            if OpcodeId::PUSH1.as_u8() <= byte
-                && byte <= OpcodeId::PUSH32.as_u8()
-            {
-                bytecode_table.push([code_hash, i, 1, byte as u32]);
-                i += 1;
-                // loading data segement
-                for data in curr_step.values[0].to_bytes_le() {
-                    bytecode_table.push([code_hash, i, 0, data as u32]);
-
-                    i += 1; // next byte
+            let opcode = curr_step.opcode;
+            bytecode_table.push(bytecode_row(
+                F::zero(),
+                pc,
+                true,
+                opcode.as_u8(),
+            ));
+            pc = match opcode {
+                OpcodeId::JUMP if curr_step.case == Case::Success => {
+                    curr_step.values[0].to_usize().unwrap()
                 }
-            } else {
-                bytecode_table.push([code_hash, i, 1, byte as u32]);
-                i += 1
-            }
+                OpcodeId::JUMPI
+                    if curr_step.case == Case::Success
+                        && !curr_step.values[1].is_zero() =>
+                {
+                    curr_step.values[0].to_usize().unwrap()
+                }
+                _ if opcode.is_push() => {
+                    // loading data segement
+                    let data = push_data(opcode, &curr_step.values[0]);
+                    bytecode_table.extend(data.iter().enumerate().map(
+                        |(idx, byte)| {
+                            bytecode_row(F::zero(), pc + 1 + idx, false, *byte)
+                        },
+                    ));
+                    pc + 1 + data.len()
+                }
+                _ => pc + 1,
+            };
         }
 
         bytecode_table
     }
 
+    // contruct the keccak table row of the code whose hash is 0 from its rows
+    // in the bytecode table, which gives the size of the code, where the bytes
+    // never reached by the steps are zeros
+    fn code_keccak_row<F: FieldExt>(bytecode_table: &[[F; 4]]) -> [F; 3] {
+        // TODO: use a random r instead of 1
+        let r = F::one();
+        let mut code = Vec::new();
+        for row in bytecode_table.iter() {
+            let index = row[1].get_lower_128() as usize;
+            if code.len() <= index {
+                code.resize(index + 1, 0);
+            }
+            code[index] = row[3].get_lower_128() as u8;
+        }
+
+        [
+            encode(code.iter().cloned(), r),
+            F::from(code.len() as u64),
+            F::zero(),
+        ]
+    }
+
+    // contruct a TestCircuit with the ExecutionSteps and Operations of the
+    // first tx of a block built by bus-mapping, stopping at the first error.
+    // The steps of the tx before and after its execution are included, the
+    // latter only when the tx halts at its last step.
+    pub(crate) fn test_circuit_from_block<F: FieldExt>(
+        block: &Block,
+    ) -> TestCircuit<F> {
+        // TODO: use a random r instead of 1
+        let witness = block_witness(block, F::one()).unwrap();
+        let tx = witness.txs.into_iter().next().unwrap();
+
+        TestCircuit::new(tx.execution_steps, witness.operations, false)
+            .with_tx_steps(Some(tx.begin_tx_step), tx.end_tx_step)
+            .with_tx_table(witness.tx_table)
+            .with_tx_calldata_table(witness.tx_calldata_table)
+            .with_bytecode_table(witness.bytecode_table)
+            .with_keccak_table(witness.keccak_table)
+            .with_block_table(witness.block_table)
+    }
+
+    // contruct the TestCircuit of the single tx of a block executing the given
+    // code
+    fn test_circuit_from_code<F: FieldExt>(code: &Bytecode) -> TestCircuit<F> {
+        let block = mock::BlockData::new_single_tx_trace_code(code).unwrap();
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        test_circuit_from_block::<F>(&builder.block)
+    }
+
+    // verify with the MockProver the TestCircuit of the single tx of a block
+    // executing the given code
+    pub(crate) fn run_test_circuit_from_code<F: FieldExt>(
+        code: &Bytecode,
+    ) -> Result<(), Vec<VerifyFailure>> {
+        let circuit = test_circuit_from_code::<F>(code);
+        let prover = MockProver::<F>::run(11, &circuit, vec![vec![]]).unwrap();
+        prover.verify()
+    }
+
+    // verify with the MockProver the TestCircuit of the single tx of a block
+    // executing the given code, where the value of the last stack push, which
+    // is the result of the last step before STOP, is off by one
+    pub(crate) fn run_test_circuit_from_code_with_wrong_result<F: FieldExt>(
+        code: &Bytecode,
+    ) -> Result<(), Vec<VerifyFailure>> {
+        let mut circuit = test_circuit_from_code::<F>(code);
+        let push = circuit
+            .operations
+            .iter_mut()
+            .filter(|op| op.target == Target::Stack && op.is_write)
+            .max_by_key(|op| op.gc)
+            .unwrap();
+        push.values[2] += F::one();
+
+        let prover = MockProver::<F>::run(11, &circuit, vec![vec![]]).unwrap();
+        prover.verify()
+    }
+
     #[derive(Default)]
     pub(crate) struct TestCircuit<F> {
+        begin_tx_step: Option<TxExecutionStep>,
         execution_steps: Vec<ExecutionStep>,
+        end_tx_step: Option<TxExecutionStep>,
         operations: Vec<Operation<F>>,
+        tx_table: Vec<[F; 3]>,
+        tx_calldata_table: Vec<[u64; 3]>,
+        bytecode_table: Vec<[F; 4]>,
+        keccak_table: Vec<[F; 3]>,
+        block_table: Vec<[F; 3]>,
+        // The maximum number of txs and the words of the public inputs, which
+        // prove the block table instead of block_table when they are given
+        public_inputs: Option<(usize, Vec<[u8; 32]>)>,
+        // The maximum number of txs and the signed txs, which prove the tx
+        // tables instead of tx_table and tx_calldata_table when they are given
+        signed_txs: Option<(usize, Vec<SignedTx>)>,
         including_large_tables: bool,
     }
 
-    impl<F> TestCircuit<F> {
+    impl<F: FieldExt> TestCircuit<F> {
         pub fn new(
             execution_steps: Vec<ExecutionStep>,
             operations: Vec<Operation<F>>,
             including_large_tables: bool,
         ) -> Self {
+            // load bytecode source from test sequence
+            let bytecode_table = assgin_byte_table_step(&execution_steps);
+            let keccak_table = vec![code_keccak_row(&bytecode_table)];
+
             Self {
+                begin_tx_step: None,
                 execution_steps,
+                end_tx_step: None,
                 operations,
+                tx_table: Vec::new(),
+                tx_calldata_table: Vec::new(),
+                bytecode_table,
+                keccak_table,
+                block_table: Vec::new(),
+                public_inputs: None,
+                signed_txs: None,
                 including_large_tables,
             }
         }
+
+        pub fn with_tx_steps(
+            self,
+            begin_tx_step: Option<TxExecutionStep>,
+            end_tx_step: Option<TxExecutionStep>,
+        ) -> Self {
+            Self {
+                begin_tx_step,
+                end_tx_step,
+                ..self
+            }
+        }
+
+        pub fn with_tx_table(self, tx_table: Vec<[F; 3]>) -> Self {
+            Self { tx_table, ..self }
+        }
+
+        pub fn with_tx_calldata_table(
+            self,
+            tx_calldata_table: Vec<[u64; 3]>,
+        ) -> Self {
+            Self {
+                tx_calldata_table,
+                ..self
+            }
+        }
+
+        pub fn with_bytecode_table(self, bytecode_table: Vec<[F; 4]>) -> Self {
+            Self {
+                bytecode_table,
+                ..self
+            }
+        }
+
+        pub fn with_keccak_table(self, keccak_table: Vec<[F; 3]>) -> Self {
+            Self {
+                keccak_table,
+                ..self
+            }
+        }
+
+        pub fn with_block_table(self, block_table: Vec<[F; 3]>) -> Self {
+            Self {
+                block_table,
+                ..self
+            }
+        }
+
+        // Proves the block table from the public inputs of `block`, in the
+        // layout for blocks of at most `max_txs` txs, which are then the
+        // instance column of the circuit
+        pub fn with_public_inputs(
+            self,
+            block: &Block,
+            max_txs: usize,
+        ) -> Result<Self, PublicInputError> {
+            Ok(Self {
+                public_inputs: Some((max_txs, words(block, max_txs)?)),
+                ..self
+            })
+        }
+
+        // Proves the tx tables from the signed `txs`, in the layout for
+        // blocks of at most `max_txs` txs, whose payloads are then hashed by
+        // the keccak table
+        pub(crate) fn with_signed_txs(
+            self,
+            txs: Vec<SignedTx>,
+            max_txs: usize,
+        ) -> Self {
+            Self {
+                signed_txs: Some((max_txs, txs)),
+                ..self
+            }
+        }
     }
 
     impl<F: FieldExt> Circuit<F> for TestCircuit<F> {
@@ -1283,17 +2578,60 @@ mod test {
             config
                 .evm_circuit
                 .load_rw_tables(&mut layouter, &self.operations)?;
+            let tx_keccak_table =
+                match &self.signed_txs {
+                    Some((max_txs, txs)) => config
+                        .evm_circuit
+                        .load_signed_txs(&mut layouter, *max_txs, txs)?,
+                    None => {
+                        config
+                            .evm_circuit
+                            .load_tx_tables(&mut layouter, &self.tx_table)?;
+                        config.evm_circuit.load_tx_calldata_tables(
+                            &mut layouter,
+                            &self.tx_calldata_table,
+                        )?;
+                        vec![]
+                    }
+                };
+            match &self.public_inputs {
+                Some((max_txs, words)) => config
+                    .evm_circuit
+                    .load_public_inputs(&mut layouter, *max_txs, words)?,
+                None => config
+                    .evm_circuit
+                    .load_block_tables(&mut layouter, &self.block_table)?,
+            }
 
-            // load bytecode source from test sequence
-            let bytecode_table = assgin_byte_table_step(&self.execution_steps);
+            config
+                .evm_circuit
+                .load_bytecode_tables(&mut layouter, &self.bytecode_table)?;
+
+            // load exp results from test sequence
+            let exp_table = exp_table(&self.execution_steps, F::one())?;
 
             config
                 .evm_circuit
-                .load_bytecode_tables(&mut layouter, bytecode_table)?;
+                .load_exp_tables(&mut layouter, exp_table)?;
+
+            // load keccak hashes from test sequence, followed by the ones of
+            // the codes and of the payloads of the signed txs
+            let keccak_table = keccak_table(&self.execution_steps, F::one())?
+                .into_iter()
+                .chain(self.keccak_table.iter().cloned())
+                .chain(tx_keccak_table)
+                .collect();
 
             config
                 .evm_circuit
-                .assign(&mut layouter, &self.execution_steps)
+                .load_keccak_tables(&mut layouter, keccak_table)?;
+
+            config.evm_circuit.assign(
+                &mut layouter,
+                self.begin_tx_step.as_ref(),
+                &self.execution_steps,
+                self.end_tx_step.as_ref(),
+            )
         }
     }
 }
@@ -3,48 +3,114 @@ use super::{
         CIRCUIT_HEIGHT, CIRCUIT_WIDTH, NUM_CELL_OP_EXECUTION_STATE,
         NUM_CELL_OP_GADGET_SELECTOR, NUM_CELL_RESUMPTION,
     },
-    Case, Cell, Constraint, CoreStateInstance, ExecutionStep, Lookup, Word,
+    Case, Cell, Constraint, CoreStateInstance, ExecutionStep, Lookup,
+    TxExecutionStep, Word,
 };
-use crate::util::Expr;
+use crate::{gadget::evm_word::encode, util::Expr};
 use bus_mapping::evm::OpcodeId;
 use halo2::{
     arithmetic::FieldExt,
     circuit::Region,
     plonk::{ConstraintSystem, Error, Expression},
 };
-use std::{collections::HashMap, ops::Range};
+use std::{collections::HashMap, ops::Range, slice};
 
 mod arithmetic;
+mod balance;
+mod begin_tx;
+mod block_ctx;
 mod byte;
+mod call;
+mod call_ctx;
+mod calldatacopy;
+mod calldataload;
+mod calldatasize;
+mod codecopy;
+mod codesize;
 mod comparator;
+mod create;
 mod dup;
+mod end_tx;
+mod extcodehash;
+mod extcodesize;
+mod gas;
+mod iszero;
 mod jump;
 mod jumpdest;
 mod jumpi;
+mod log;
 mod memory;
+mod msize;
+mod not;
 mod pc;
 mod pop;
 mod push;
+mod r#return;
+mod returndatacopy;
+mod returndatasize;
+mod selfbalance;
+mod selfdestruct;
+mod sha3;
+mod shift;
 mod signextend;
+mod sload;
+mod sstore;
+mod stop;
 mod swap;
+mod tx_ctx;
 mod utils;
 
+use self::sha3::Sha3Gadget;
 use arithmetic::AddGadget;
+use arithmetic::AddMulModGadget;
 use arithmetic::AndGadget;
+use arithmetic::DivModGadget;
+use arithmetic::ExpGadget;
+use arithmetic::MulGadget;
 use arithmetic::OrGadget;
+use arithmetic::SignedDivModGadget;
 use arithmetic::XorGadget;
+use balance::BalanceGadget;
+use begin_tx::BeginTxGadget;
+use block_ctx::{BlockCtxGadget, BlockhashGadget};
 use byte::ByteGadget;
+use call::{CallGadget, DelegateCallGadget};
+use call_ctx::{AddressGadget, CallerGadget, CallvalueGadget};
+use calldatacopy::CalldatacopyGadget;
+use calldataload::CalldataloadGadget;
+use calldatasize::CalldatasizeGadget;
+use codecopy::{CodecopyGadget, ExtcodecopyGadget};
+use codesize::CodesizeGadget;
 use comparator::ComparatorGadget;
+use create::{Create2Gadget, CreateGadget};
 use dup::DupGadget;
+use end_tx::EndTxGadget;
+use extcodehash::ExtcodehashGadget;
+use extcodesize::ExtcodesizeGadget;
+use gas::GasGadget;
+use iszero::IszeroGadget;
 use jump::JumpGadget;
 use jumpdest::JumpdestGadget;
 use jumpi::JumpiGadget;
+use log::LogGadget;
 use memory::MemoryGadget;
+use msize::MsizeGadget;
+use not::NotGadget;
 use pc::PcGadget;
 use pop::PopGadget;
 use push::PushGadget;
+use r#return::{ReturnGadget, RevertGadget};
+use returndatacopy::ReturndatacopyGadget;
+use returndatasize::ReturndatasizeGadget;
+use selfbalance::SelfbalanceGadget;
+use selfdestruct::SelfdestructGadget;
+use shift::ShiftGadget;
 use signextend::SignextendGadget;
+use sload::SloadGadget;
+use sstore::SstoreGadget;
+use stop::StopGadget;
 use swap::SwapGadget;
+use tx_ctx::{GaspriceGadget, OriginGadget};
 
 fn bool_switches_constraints<F: FieldExt>(
     bool_switches: &[Cell<F>],
@@ -142,6 +208,9 @@ impl CaseConfig {
 #[derive(Debug)]
 pub(crate) struct CaseAllocation<F> {
     selector: Cell<F>,
+    // Randomness of the RLC of the words, for the gadgets building the RLC of
+    // other byte strings
+    r: F,
     words: Vec<Word<F>>,
     cells: Vec<Cell<F>>,
     resumption: Option<Resumption<F>>,
@@ -150,7 +219,6 @@ pub(crate) struct CaseAllocation<F> {
 #[derive(Clone, Debug)]
 struct Resumption<F> {
     caller_id: Cell<F>,
-    gas_available: Cell<F>,
 }
 
 impl<F: FieldExt> Resumption<F> {
@@ -159,7 +227,6 @@ impl<F: FieldExt> Resumption<F> {
 
         Self {
             caller_id: cells[0].clone(),
-            gas_available: cells[1].clone(),
         }
     }
 }
@@ -174,6 +241,15 @@ pub(crate) struct OpExecutionState<F> {
     gas_counter: Cell<F>,
     opcode: Cell<F>,
     memory_size: Cell<F>,
+    // Random linear combination of the hash of the code being executed
+    pub code_source: Cell<F>,
+    // Id of the last log of the tx, which is 0 before its first log
+    pub log_id: Cell<F>,
+    // Gas left to the call being executed
+    pub gas_left: Cell<F>,
+    // Number of state writes of the call and of its successful callees, which
+    // are undone when the call reverts
+    pub state_write_counter: Cell<F>,
 }
 
 impl<F: FieldExt> OpExecutionState<F> {
@@ -189,6 +265,10 @@ impl<F: FieldExt> OpExecutionState<F> {
             gas_counter: cells[5].clone(),
             opcode: cells[6].clone(),
             memory_size: cells[7].clone(),
+            code_source: cells[8].clone(),
+            log_id: cells[9].clone(),
+            gas_left: cells[10].clone(),
+            state_write_counter: cells[11].clone(),
         }
     }
 }
@@ -215,6 +295,29 @@ trait OpGadget<F: FieldExt> {
     ) -> Result<(), Error>;
 }
 
+// TxGadget is implemented by the gadgets of the steps done by a transaction
+// outside of its execution, which are not triggered by any opcode and have a
+// single case.
+trait TxGadget<F: FieldExt> {
+    const CASE_CONFIG: &'static CaseConfig;
+
+    fn construct(case_allocation: CaseAllocation<F>) -> Self;
+
+    fn constraints(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        state_next: &OpExecutionState<F>,
+    ) -> Vec<Constraint<F>>;
+
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        core_state: &mut CoreStateInstance,
+        tx_step: &TxExecutionStep,
+    ) -> Result<(), Error>;
+}
+
 // Preset stores default values for each case of op gadget
 #[derive(Clone, Default)]
 struct Preset<F> {
@@ -233,6 +336,10 @@ pub(crate) struct OpExecutionGadget<F> {
     resumption: Resumption<F>,
     qs_op_idx_map: HashMap<OpcodeId, usize>,
     preset_map: HashMap<(usize, Case), Preset<F>>,
+    begin_tx_qs_op_idx: usize,
+    end_tx_qs_op_idx: usize,
+    begin_tx_gadget: BeginTxGadget<F>,
+    end_tx_gadget: EndTxGadget<F>,
     add_gadget: AddGadget<F>,
     push_gadget: PushGadget<F>,
     comparator_gadget: ComparatorGadget<F>,
@@ -249,6 +356,47 @@ pub(crate) struct OpExecutionGadget<F> {
     or_gadget: OrGadget<F>,
     xor_gadget: XorGadget<F>,
     jumpi_gadget: JumpiGadget<F>,
+    mul_gadget: MulGadget<F>,
+    div_mod_gadget: DivModGadget<F>,
+    signed_div_mod_gadget: SignedDivModGadget<F>,
+    add_mul_mod_gadget: AddMulModGadget<F>,
+    exp_gadget: ExpGadget<F>,
+    shift_gadget: ShiftGadget<F>,
+    iszero_gadget: IszeroGadget<F>,
+    not_gadget: NotGadget<F>,
+    sload_gadget: SloadGadget<F>,
+    sstore_gadget: SstoreGadget<F>,
+    call_gadget: CallGadget<F>,
+    delegate_call_gadget: DelegateCallGadget<F>,
+    create_gadget: CreateGadget<F>,
+    create2_gadget: Create2Gadget<F>,
+    balance_gadget: BalanceGadget<F>,
+    selfbalance_gadget: SelfbalanceGadget<F>,
+    extcodehash_gadget: ExtcodehashGadget<F>,
+    calldataload_gadget: CalldataloadGadget<F>,
+    calldatasize_gadget: CalldatasizeGadget<F>,
+    calldatacopy_gadget: CalldatacopyGadget<F>,
+    codesize_gadget: CodesizeGadget<F>,
+    codecopy_gadget: CodecopyGadget<F>,
+    extcodesize_gadget: ExtcodesizeGadget<F>,
+    extcodecopy_gadget: ExtcodecopyGadget<F>,
+    return_gadget: ReturnGadget<F>,
+    revert_gadget: RevertGadget<F>,
+    returndatasize_gadget: ReturndatasizeGadget<F>,
+    returndatacopy_gadget: ReturndatacopyGadget<F>,
+    log_gadget: LogGadget<F>,
+    sha3_gadget: Sha3Gadget<F>,
+    block_ctx_gadget: BlockCtxGadget<F>,
+    blockhash_gadget: BlockhashGadget<F>,
+    address_gadget: AddressGadget<F>,
+    origin_gadget: OriginGadget<F>,
+    caller_gadget: CallerGadget<F>,
+    callvalue_gadget: CallvalueGadget<F>,
+    gasprice_gadget: GaspriceGadget<F>,
+    msize_gadget: MsizeGadget<F>,
+    gas_gadget: GasGadget<F>,
+    selfdestruct_gadget: SelfdestructGadget<F>,
+    stop_gadget: StopGadget<F>,
 }
 
 impl<F: FieldExt> OpExecutionGadget<F> {
@@ -318,6 +466,71 @@ impl<F: FieldExt> OpExecutionGadget<F> {
         construct_op_gadget!(xor_gadget);
         construct_op_gadget!(jump_gadget);
         construct_op_gadget!(jumpi_gadget);
+        construct_op_gadget!(mul_gadget);
+        construct_op_gadget!(div_mod_gadget);
+        construct_op_gadget!(signed_div_mod_gadget);
+        construct_op_gadget!(add_mul_mod_gadget);
+        construct_op_gadget!(exp_gadget);
+        construct_op_gadget!(shift_gadget);
+        construct_op_gadget!(iszero_gadget);
+        construct_op_gadget!(not_gadget);
+        construct_op_gadget!(sload_gadget);
+        construct_op_gadget!(sstore_gadget);
+        construct_op_gadget!(call_gadget);
+        construct_op_gadget!(delegate_call_gadget);
+        construct_op_gadget!(create_gadget);
+        construct_op_gadget!(create2_gadget);
+        construct_op_gadget!(balance_gadget);
+        construct_op_gadget!(selfbalance_gadget);
+        construct_op_gadget!(extcodehash_gadget);
+        construct_op_gadget!(calldataload_gadget);
+        construct_op_gadget!(calldatasize_gadget);
+        construct_op_gadget!(calldatacopy_gadget);
+        construct_op_gadget!(codesize_gadget);
+        construct_op_gadget!(codecopy_gadget);
+        construct_op_gadget!(extcodesize_gadget);
+        construct_op_gadget!(extcodecopy_gadget);
+        construct_op_gadget!(return_gadget);
+        construct_op_gadget!(revert_gadget);
+        construct_op_gadget!(returndatasize_gadget);
+        construct_op_gadget!(returndatacopy_gadget);
+        construct_op_gadget!(log_gadget);
+        construct_op_gadget!(sha3_gadget);
+        construct_op_gadget!(block_ctx_gadget);
+        construct_op_gadget!(blockhash_gadget);
+        construct_op_gadget!(address_gadget);
+        construct_op_gadget!(origin_gadget);
+        construct_op_gadget!(caller_gadget);
+        construct_op_gadget!(callvalue_gadget);
+        construct_op_gadget!(gasprice_gadget);
+        construct_op_gadget!(msize_gadget);
+        construct_op_gadget!(gas_gadget);
+        construct_op_gadget!(selfdestruct_gadget);
+        construct_op_gadget!(stop_gadget);
+
+        // This helps construct the gadgets that implement trait TxGadget,
+        // keeping the index of their op selector.
+        macro_rules! construct_tx_gadget {
+            ($name:ident, $qs_op_idx_name:ident) => {
+                let $name = Self::construct_tx_gadget(
+                    r,
+                    &state_curr,
+                    &state_next,
+                    &qs_byte_lookups[..],
+                    qs_ops,
+                    qs_op_idx,
+                    free_cells,
+                    &resumption,
+                    &mut preset_map,
+                    &mut constraints,
+                );
+                let $qs_op_idx_name = qs_op_idx;
+                qs_op_idx += 1;
+            };
+        }
+
+        construct_tx_gadget!(begin_tx_gadget, begin_tx_qs_op_idx);
+        construct_tx_gadget!(end_tx_gadget, end_tx_qs_op_idx);
         let _ = qs_op_idx;
 
         for constraint in constraints.into_iter() {
@@ -353,6 +566,10 @@ impl<F: FieldExt> OpExecutionGadget<F> {
             qs_op_idx_map,
             preset_map,
             resumption,
+            begin_tx_qs_op_idx,
+            end_tx_qs_op_idx,
+            begin_tx_gadget,
+            end_tx_gadget,
             add_gadget,
             push_gadget,
             comparator_gadget,
@@ -369,6 +586,47 @@ impl<F: FieldExt> OpExecutionGadget<F> {
             xor_gadget,
             jump_gadget,
             jumpi_gadget,
+            mul_gadget,
+            div_mod_gadget,
+            signed_div_mod_gadget,
+            add_mul_mod_gadget,
+            exp_gadget,
+            shift_gadget,
+            iszero_gadget,
+            not_gadget,
+            sload_gadget,
+            sstore_gadget,
+            call_gadget,
+            delegate_call_gadget,
+            create_gadget,
+            create2_gadget,
+            balance_gadget,
+            selfbalance_gadget,
+            extcodehash_gadget,
+            calldataload_gadget,
+            calldatasize_gadget,
+            calldatacopy_gadget,
+            codesize_gadget,
+            codecopy_gadget,
+            extcodesize_gadget,
+            extcodecopy_gadget,
+            return_gadget,
+            revert_gadget,
+            returndatasize_gadget,
+            returndatacopy_gadget,
+            log_gadget,
+            sha3_gadget,
+            block_ctx_gadget,
+            blockhash_gadget,
+            address_gadget,
+            origin_gadget,
+            caller_gadget,
+            callvalue_gadget,
+            gasprice_gadget,
+            msize_gadget,
+            gas_gadget,
+            selfdestruct_gadget,
+            stop_gadget,
         }
     }
 
@@ -386,10 +644,6 @@ impl<F: FieldExt> OpExecutionGadget<F> {
         preset_map: &mut HashMap<(usize, Case), Preset<F>>,
         constraints: &mut Vec<Constraint<F>>,
     ) -> O {
-        assert!(qs_op_idx < NUM_CELL_OP_GADGET_SELECTOR);
-
-        let qs_op = &qs_ops[qs_op_idx];
-
         // opcode should only be handled by one gadget
         for opcode in O::RESPONSIBLE_OPCODES {
             assert!(
@@ -398,7 +652,97 @@ impl<F: FieldExt> OpExecutionGadget<F> {
             );
         }
 
-        let case_configs = O::CASE_CONFIGS;
+        let case_allocations = Self::allocate_cases(
+            r,
+            qs_byte_lookups,
+            qs_ops,
+            qs_op_idx,
+            O::CASE_CONFIGS,
+            free_cells,
+            resumption,
+            preset_map,
+            constraints,
+        );
+
+        // The opcode is fetched from the code being executed.  The lookup is
+        // added to each case, whose selectors are independent, as it would
+        // otherwise be summed with the bytecode lookups of the active case.
+        let opcode_fetch = Lookup::BytecodeLookup([
+            state_curr.code_source.expr(),
+            state_curr.program_counter.expr(),
+            1.expr(),
+            state_curr.opcode.expr(),
+        ]);
+
+        let gadget = O::construct(case_allocations);
+        Self::push_gadget_constraints(
+            &qs_ops[qs_op_idx],
+            utils::batch_add_expressions(
+                gadget.constraints(state_curr, state_next),
+                vec![],
+                vec![opcode_fetch],
+            ),
+            constraints,
+        );
+
+        gadget
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn construct_tx_gadget<T: TxGadget<F>>(
+        r: F,
+        state_curr: &OpExecutionState<F>,
+        state_next: &OpExecutionState<F>,
+        qs_byte_lookups: &[Cell<F>],
+        qs_ops: &[Cell<F>],
+        qs_op_idx: usize,
+        free_cells: &[Cell<F>],
+        resumption: &Resumption<F>,
+        preset_map: &mut HashMap<(usize, Case), Preset<F>>,
+        constraints: &mut Vec<Constraint<F>>,
+    ) -> T {
+        let case_allocation = Self::allocate_cases(
+            r,
+            qs_byte_lookups,
+            qs_ops,
+            qs_op_idx,
+            slice::from_ref(T::CASE_CONFIG),
+            free_cells,
+            resumption,
+            preset_map,
+            constraints,
+        )
+        .pop()
+        .unwrap();
+
+        let gadget = T::construct(case_allocation);
+        Self::push_gadget_constraints(
+            &qs_ops[qs_op_idx],
+            gadget.constraints(state_curr, state_next),
+            constraints,
+        );
+
+        gadget
+    }
+
+    // Allocate the cells of each case of the gadget with op selector
+    // `qs_ops[qs_op_idx]`, and record the preset values of each case.
+    #[allow(clippy::too_many_arguments)]
+    fn allocate_cases(
+        r: F,
+        qs_byte_lookups: &[Cell<F>],
+        qs_ops: &[Cell<F>],
+        qs_op_idx: usize,
+        case_configs: &[CaseConfig],
+        free_cells: &[Cell<F>],
+        resumption: &Resumption<F>,
+        preset_map: &mut HashMap<(usize, Case), Preset<F>>,
+        constraints: &mut Vec<Constraint<F>>,
+    ) -> Vec<CaseAllocation<F>> {
+        assert!(qs_op_idx < NUM_CELL_OP_GADGET_SELECTOR);
+
+        let qs_op = &qs_ops[qs_op_idx];
+
         let num_case = case_configs.len();
         let qs_cases = &free_cells[..num_case];
 
@@ -409,7 +753,7 @@ impl<F: FieldExt> OpExecutionGadget<F> {
             lookups: vec![],
         });
 
-        let case_allocations = case_configs
+        case_configs
             .iter()
             .enumerate()
             .map(|(q_case_idx, case_config)| {
@@ -482,18 +826,22 @@ impl<F: FieldExt> OpExecutionGadget<F> {
 
                 CaseAllocation {
                     selector: qs_case.clone(),
+                    r,
                     words,
                     cells,
                     resumption,
                 }
             })
-            .collect::<Vec<_>>();
-
-        let gadget = O::construct(case_allocations);
+            .collect()
+    }
 
+    fn push_gadget_constraints(
+        qs_op: &Cell<F>,
+        gadget_constraints: Vec<Constraint<F>>,
+        constraints: &mut Vec<Constraint<F>>,
+    ) {
         constraints.append(
-            &mut gadget
-                .constraints(state_curr, state_next)
+            &mut gadget_constraints
                 .into_iter()
                 .map(|mut constraint| {
                     // assert!(
@@ -507,52 +855,70 @@ impl<F: FieldExt> OpExecutionGadget<F> {
                 })
                 .collect(),
         );
-
-        gadget
     }
 
-    pub(crate) fn assign_execution_step(
+    pub(crate) fn assign_begin_tx_step(
         &self,
         region: &mut Region<'_, F>,
         offset: usize,
         core_state: &mut CoreStateInstance,
-        execution_step: Option<&ExecutionStep>,
+        tx_step: &TxExecutionStep,
     ) -> Result<(), Error> {
-        assert!(core_state.is_executing);
-
-        self.state_curr
-            .is_executing
-            .assign(region, offset, Some(F::one()))?;
-        self.state_curr.global_counter.assign(
+        self.assign_tx_step(
             region,
             offset,
-            Some(F::from(core_state.global_counter as u64)),
-        )?;
-        self.state_curr.call_id.assign(
-            region,
-            offset,
-            Some(F::from(core_state.call_id as u64)),
-        )?;
-        self.state_curr.program_counter.assign(
-            region,
-            offset,
-            Some(F::from(core_state.program_counter as u64)),
-        )?;
-        self.state_curr.stack_pointer.assign(
-            region,
-            offset,
-            Some(F::from(core_state.stack_pointer as u64)),
-        )?;
-        self.state_curr.gas_counter.assign(
-            region,
-            offset,
-            Some(F::from(core_state.gas_counter as u64)),
-        )?;
-        self.state_curr.memory_size.assign(
+            core_state,
+            &self.begin_tx_gadget,
+            self.begin_tx_qs_op_idx,
+            tx_step,
+        )
+    }
+
+    pub(crate) fn assign_end_tx_step(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        core_state: &mut CoreStateInstance,
+        tx_step: &TxExecutionStep,
+    ) -> Result<(), Error> {
+        self.assign_tx_step(
             region,
             offset,
-            Some(F::from(core_state.memory_size as u64)),
-        )?;
+            core_state,
+            &self.end_tx_gadget,
+            self.end_tx_qs_op_idx,
+            tx_step,
+        )
+    }
+
+    fn assign_tx_step<T: TxGadget<F>>(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        core_state: &mut CoreStateInstance,
+        gadget: &T,
+        qs_op_idx: usize,
+        tx_step: &TxExecutionStep,
+    ) -> Result<(), Error> {
+        self.assign_state(region, offset, core_state)?;
+
+        // No opcode is executed in the steps of a transaction
+        self.state_curr
+            .opcode
+            .assign(region, offset, Some(F::zero()))?;
+        self.assign_preset(region, offset, qs_op_idx, Case::Success)?;
+
+        gadget.assign(region, offset, core_state, tx_step)
+    }
+
+    pub(crate) fn assign_execution_step(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        core_state: &mut CoreStateInstance,
+        execution_step: Option<&ExecutionStep>,
+    ) -> Result<(), Error> {
+        self.assign_state(region, offset, core_state)?;
 
         if let Some(execution_step) = execution_step {
             self.state_curr.opcode.assign(
@@ -564,30 +930,8 @@ impl<F: FieldExt> OpExecutionGadget<F> {
             let &qs_op_idx = self
                 .qs_op_idx_map
                 .get(&execution_step.opcode)
-                .expect("opcode to be handled");
-            for (idx, q_op) in self.qs_ops.iter().enumerate() {
-                q_op.assign(
-                    region,
-                    offset,
-                    Some(F::from((idx == qs_op_idx) as u64)),
-                )?;
-            }
-
-            let preset = self
-                .preset_map
-                .get(&(qs_op_idx, execution_step.case))
-                .expect("case to be handled");
-            for (cell, value) in self
-                .qs_byte_lookups
-                .iter()
-                .zip(preset.qs_byte_lookups.iter())
-            {
-                cell.assign(region, offset, Some(*value))?;
-            }
-
-            for (idx, value) in &preset.free_cells {
-                self.free_cells[*idx].assign(region, offset, Some(*value))?;
-            }
+                .ok_or(Error::Synthesis)?;
+            self.assign_preset(region, offset, qs_op_idx, execution_step.case)?;
 
             match (
                 execution_step.opcode.is_push(),
@@ -691,11 +1035,378 @@ impl<F: FieldExt> OpExecutionGadget<F> {
                     core_state,
                     execution_step,
                 )?,
+                (_, _, _, OpcodeId::MUL) => self.mul_gadget.assign(
+                    region,
+                    offset,
+                    core_state,
+                    execution_step,
+                )?,
+                (_, _, _, OpcodeId::DIV | OpcodeId::MOD) => self
+                    .div_mod_gadget
+                    .assign(region, offset, core_state, execution_step)?,
+                (_, _, _, OpcodeId::SDIV | OpcodeId::SMOD) => self
+                    .signed_div_mod_gadget
+                    .assign(region, offset, core_state, execution_step)?,
+                (_, _, _, OpcodeId::ADDMOD | OpcodeId::MULMOD) => self
+                    .add_mul_mod_gadget
+                    .assign(region, offset, core_state, execution_step)?,
+                (_, _, _, OpcodeId::EXP) => self.exp_gadget.assign(
+                    region,
+                    offset,
+                    core_state,
+                    execution_step,
+                )?,
+                (_, _, _, OpcodeId::SHL | OpcodeId::SHR | OpcodeId::SAR) => {
+                    self.shift_gadget.assign(
+                        region,
+                        offset,
+                        core_state,
+                        execution_step,
+                    )?
+                }
+                (_, _, _, OpcodeId::ISZERO) => self.iszero_gadget.assign(
+                    region,
+                    offset,
+                    core_state,
+                    execution_step,
+                )?,
+                (_, _, _, OpcodeId::NOT) => self.not_gadget.assign(
+                    region,
+                    offset,
+                    core_state,
+                    execution_step,
+                )?,
+                (_, _, _, OpcodeId::SLOAD) => self.sload_gadget.assign(
+                    region,
+                    offset,
+                    core_state,
+                    execution_step,
+                )?,
+                (_, _, _, OpcodeId::SSTORE) => self.sstore_gadget.assign(
+                    region,
+                    offset,
+                    core_state,
+                    execution_step,
+                )?,
+                (_, _, _, OpcodeId::CALL | OpcodeId::CALLCODE) => self
+                    .call_gadget
+                    .assign(region, offset, core_state, execution_step)?,
+                (_, _, _, OpcodeId::DELEGATECALL | OpcodeId::STATICCALL) => {
+                    self.delegate_call_gadget.assign(
+                        region,
+                        offset,
+                        core_state,
+                        execution_step,
+                    )?
+                }
+                (_, _, _, OpcodeId::CREATE) => self.create_gadget.assign(
+                    region,
+                    offset,
+                    core_state,
+                    execution_step,
+                )?,
+                (_, _, _, OpcodeId::CREATE2) => self.create2_gadget.assign(
+                    region,
+                    offset,
+                    core_state,
+                    execution_step,
+                )?,
+                (_, _, _, OpcodeId::BALANCE) => self.balance_gadget.assign(
+                    region,
+                    offset,
+                    core_state,
+                    execution_step,
+                )?,
+                (_, _, _, OpcodeId::SELFBALANCE) => self
+                    .selfbalance_gadget
+                    .assign(region, offset, core_state, execution_step)?,
+                (_, _, _, OpcodeId::EXTCODEHASH) => self
+                    .extcodehash_gadget
+                    .assign(region, offset, core_state, execution_step)?,
+                (_, _, _, OpcodeId::CALLDATALOAD) => {
+                    self.calldataload_gadget.assign(
+                        region,
+                        offset,
+                        core_state,
+                        execution_step,
+                    )?
+                }
+                (_, _, _, OpcodeId::CALLDATASIZE) => {
+                    self.calldatasize_gadget.assign(
+                        region,
+                        offset,
+                        core_state,
+                        execution_step,
+                    )?
+                }
+                (_, _, _, OpcodeId::CALLDATACOPY) => {
+                    self.calldatacopy_gadget.assign(
+                        region,
+                        offset,
+                        core_state,
+                        execution_step,
+                    )?
+                }
+                (_, _, _, OpcodeId::CODESIZE) => self.codesize_gadget.assign(
+                    region,
+                    offset,
+                    core_state,
+                    execution_step,
+                )?,
+                (_, _, _, OpcodeId::CODECOPY) => self.codecopy_gadget.assign(
+                    region,
+                    offset,
+                    core_state,
+                    execution_step,
+                )?,
+                (_, _, _, OpcodeId::EXTCODESIZE) => self
+                    .extcodesize_gadget
+                    .assign(region, offset, core_state, execution_step)?,
+                (_, _, _, OpcodeId::EXTCODECOPY) => self
+                    .extcodecopy_gadget
+                    .assign(region, offset, core_state, execution_step)?,
+                (_, _, _, OpcodeId::RETURN) => self.return_gadget.assign(
+                    region,
+                    offset,
+                    core_state,
+                    execution_step,
+                )?,
+                (_, _, _, OpcodeId::REVERT) => self.revert_gadget.assign(
+                    region,
+                    offset,
+                    core_state,
+                    execution_step,
+                )?,
+                (_, _, _, OpcodeId::RETURNDATASIZE) => self
+                    .returndatasize_gadget
+                    .assign(region, offset, core_state, execution_step)?,
+                (_, _, _, OpcodeId::RETURNDATACOPY) => self
+                    .returndatacopy_gadget
+                    .assign(region, offset, core_state, execution_step)?,
+                (
+                    _,
+                    _,
+                    _,
+                    OpcodeId::LOG0
+                    | OpcodeId::LOG1
+                    | OpcodeId::LOG2
+                    | OpcodeId::LOG3
+                    | OpcodeId::LOG4,
+                ) => self.log_gadget.assign(
+                    region,
+                    offset,
+                    core_state,
+                    execution_step,
+                )?,
+                (_, _, _, OpcodeId::SHA3) => self.sha3_gadget.assign(
+                    region,
+                    offset,
+                    core_state,
+                    execution_step,
+                )?,
+                (_, _, _, OpcodeId::ADDRESS) => self.address_gadget.assign(
+                    region,
+                    offset,
+                    core_state,
+                    execution_step,
+                )?,
+                (_, _, _, OpcodeId::ORIGIN) => self.origin_gadget.assign(
+                    region,
+                    offset,
+                    core_state,
+                    execution_step,
+                )?,
+                (_, _, _, OpcodeId::CALLER) => self.caller_gadget.assign(
+                    region,
+                    offset,
+                    core_state,
+                    execution_step,
+                )?,
+                (_, _, _, OpcodeId::CALLVALUE) => self
+                    .callvalue_gadget
+                    .assign(region, offset, core_state, execution_step)?,
+                (_, _, _, OpcodeId::GASPRICE) => self.gasprice_gadget.assign(
+                    region,
+                    offset,
+                    core_state,
+                    execution_step,
+                )?,
+                (_, _, _, OpcodeId::MSIZE) => self.msize_gadget.assign(
+                    region,
+                    offset,
+                    core_state,
+                    execution_step,
+                )?,
+                (_, _, _, OpcodeId::GAS) => self.gas_gadget.assign(
+                    region,
+                    offset,
+                    core_state,
+                    execution_step,
+                )?,
+                (_, _, _, OpcodeId::SELFDESTRUCT) => {
+                    self.selfdestruct_gadget.assign(
+                        region,
+                        offset,
+                        core_state,
+                        execution_step,
+                    )?
+                }
+                (_, _, _, OpcodeId::STOP) => self.stop_gadget.assign(
+                    region,
+                    offset,
+                    core_state,
+                    execution_step,
+                )?,
+                (_, _, _, OpcodeId::BLOCKHASH) => self
+                    .blockhash_gadget
+                    .assign(region, offset, core_state, execution_step)?,
+                (
+                    _,
+                    _,
+                    _,
+                    OpcodeId::COINBASE
+                    | OpcodeId::TIMESTAMP
+                    | OpcodeId::NUMBER
+                    | OpcodeId::DIFFICULTY
+                    | OpcodeId::GASLIMIT
+                    | OpcodeId::CHAINID
+                    | OpcodeId::BASEFEE,
+                ) => self.block_ctx_gadget.assign(
+                    region,
+                    offset,
+                    core_state,
+                    execution_step,
+                )?,
 
-                _ => unimplemented!(),
+                _ => return Err(Error::Synthesis),
             }
         }
 
         Ok(())
     }
+
+    // Assigns a step which isn't executing, with the state `core_state` and
+    // all its selectors and free cells disabled, which pads the steps of a
+    // block up to the steps of the circuit
+    pub(crate) fn assign_padding_step(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        core_state: &CoreStateInstance,
+    ) -> Result<(), Error> {
+        self.assign_state(region, offset, core_state)?;
+
+        self.state_curr
+            .opcode
+            .assign(region, offset, Some(F::zero()))?;
+        for cell in self
+            .qs_ops
+            .iter()
+            .chain(self.qs_byte_lookups.iter())
+            .chain(self.free_cells.iter())
+        {
+            cell.assign(region, offset, Some(F::zero()))?;
+        }
+
+        Ok(())
+    }
+
+    fn assign_state(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        core_state: &CoreStateInstance,
+    ) -> Result<(), Error> {
+        self.state_curr.is_executing.assign(
+            region,
+            offset,
+            Some(F::from(core_state.is_executing as u64)),
+        )?;
+        self.state_curr.global_counter.assign(
+            region,
+            offset,
+            Some(F::from(core_state.global_counter as u64)),
+        )?;
+        self.state_curr.call_id.assign(
+            region,
+            offset,
+            Some(F::from(core_state.call_id as u64)),
+        )?;
+        self.state_curr.program_counter.assign(
+            region,
+            offset,
+            Some(F::from(core_state.program_counter as u64)),
+        )?;
+        self.state_curr.stack_pointer.assign(
+            region,
+            offset,
+            Some(F::from(core_state.stack_pointer as u64)),
+        )?;
+        self.state_curr.gas_counter.assign(
+            region,
+            offset,
+            Some(F::from(core_state.gas_counter as u64)),
+        )?;
+        self.state_curr.memory_size.assign(
+            region,
+            offset,
+            Some(F::from(core_state.memory_size as u64)),
+        )?;
+        self.state_curr.code_source.assign(
+            region,
+            offset,
+            Some(encode(core_state.code_source.iter().rev().cloned(), self.r)),
+        )?;
+        self.state_curr.log_id.assign(
+            region,
+            offset,
+            Some(F::from(core_state.log_id as u64)),
+        )?;
+        self.state_curr.gas_left.assign(
+            region,
+            offset,
+            Some(F::from(core_state.gas_left)),
+        )?;
+        self.state_curr.state_write_counter.assign(
+            region,
+            offset,
+            Some(F::from(core_state.state_write_counter as u64)),
+        )?;
+
+        Ok(())
+    }
+
+    fn assign_preset(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        qs_op_idx: usize,
+        case: Case,
+    ) -> Result<(), Error> {
+        for (idx, q_op) in self.qs_ops.iter().enumerate() {
+            q_op.assign(
+                region,
+                offset,
+                Some(F::from((idx == qs_op_idx) as u64)),
+            )?;
+        }
+
+        let preset = self
+            .preset_map
+            .get(&(qs_op_idx, case))
+            .ok_or(Error::Synthesis)?;
+        for (cell, value) in self
+            .qs_byte_lookups
+            .iter()
+            .zip(preset.qs_byte_lookups.iter())
+        {
+            cell.assign(region, offset, Some(*value))?;
+        }
+
+        for (idx, value) in &preset.free_cells {
+            self.free_cells[*idx].assign(region, offset, Some(*value))?;
+        }
+
+        Ok(())
+    }
 }
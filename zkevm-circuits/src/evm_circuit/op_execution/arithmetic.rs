@@ -1,7 +1,17 @@
 mod add;
+mod add_mul_mod;
 mod bitwise;
+mod div_mod;
+mod exp;
+mod mul;
+mod sdiv_smod;
 
 pub use add::AddGadget;
+pub use add_mul_mod::AddMulModGadget;
 pub use bitwise::AndGadget;
 pub use bitwise::OrGadget;
 pub use bitwise::XorGadget;
+pub use div_mod::DivModGadget;
+pub use exp::ExpGadget;
+pub use mul::MulGadget;
+pub use sdiv_smod::SignedDivModGadget;
@@ -15,7 +15,6 @@ use array_init::array_init;
 use bus_mapping::evm::{GasCost, OpcodeId};
 use halo2::plonk::Error;
 use halo2::{arithmetic::FieldExt, circuit::Region};
-use std::convert::TryInto;
 
 static STATE_TRANSITION: StateTransition = StateTransition {
     gc_delta: Some(3), // 2 stack pops + 1 stack push
@@ -170,7 +169,8 @@ mod test {
         ($execution_steps:expr, $operations:expr, $result:expr) => {{
             let circuit =
                 TestCircuit::<Fp>::new($execution_steps, $operations, false);
-            let prover = MockProver::<Fp>::run(11, &circuit, vec![]).unwrap();
+            let prover =
+                MockProver::<Fp>::run(11, &circuit, vec![vec![]]).unwrap();
             assert_eq!(prover.verify(), $result);
         }};
     }
@@ -220,6 +220,8 @@ mod test {
                         Fp::from(1023),
                         Fp::from(1 + 2 + 3),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
                 Operation {
@@ -231,6 +233,8 @@ mod test {
                         Fp::from(1022),
                         Fp::from(4 + 5 + 6),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
                 Operation {
@@ -242,6 +246,8 @@ mod test {
                         Fp::from(1022),
                         Fp::from(4 + 5 + 6),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
                 Operation {
@@ -253,6 +259,8 @@ mod test {
                         Fp::from(1023),
                         Fp::from(1 + 2 + 3),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
                 Operation {
@@ -264,6 +272,8 @@ mod test {
                         Fp::from(1023),
                         Fp::from(5 + 7 + 9),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 }
             ],
@@ -309,6 +319,8 @@ mod test {
                         Fp::from(1023),
                         Fp::from(5 + 7 + 9),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
                 Operation {
@@ -320,6 +332,8 @@ mod test {
                         Fp::from(1022),
                         Fp::from(4 + 5 + 6),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
                 Operation {
@@ -331,6 +345,8 @@ mod test {
                         Fp::from(1022),
                         Fp::from(4 + 5 + 6),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
                 Operation {
@@ -342,6 +358,8 @@ mod test {
                         Fp::from(1023),
                         Fp::from(5 + 7 + 9),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
                 Operation {
@@ -353,6 +371,8 @@ mod test {
                         Fp::from(1023),
                         Fp::from(1 + 2 + 3),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 }
             ],
@@ -0,0 +1,311 @@
+use super::super::super::{Case, Cell, Constraint, ExecutionStep, Word};
+use super::super::utils::{
+    self,
+    common_cases::{OutOfGasCase, StackUnderflowCase},
+    constraint_builder::ConstraintBuilder,
+    math_gadgets::{
+        IsZeroGadget, LtWordGadget, MulAddWords512Gadget, MulAddWordsGadget,
+        PairSelectGadget,
+    },
+    select, sum, word_limbs, StateTransition,
+};
+use super::super::{
+    CaseAllocation, CaseConfig, CoreStateInstance, OpExecutionState, OpGadget,
+};
+use crate::impl_op_gadget;
+use crate::util::{Expr, ToWord};
+use bus_mapping::evm::{GasCost, OpcodeId};
+use halo2::plonk::Error;
+use halo2::{arithmetic::FieldExt, circuit::Region};
+use num::{BigUint, One, Zero};
+
+static STATE_TRANSITION: StateTransition = StateTransition {
+    gc_delta: Some(4), // 3 stack pops + 1 stack push
+    pc_delta: Some(1),
+    sp_delta: Some(2),
+    gas_delta: Some(GasCost::MID.as_u64()),
+    next_memory_size: None,
+};
+const NUM_POPPED: usize = 3;
+
+impl_op_gadget!(
+    #set[ADDMOD, MULMOD]
+    AddMulModGadget {
+        AddMulModSuccessCase(),
+        StackUnderflowCase(NUM_POPPED),
+        OutOfGasCase(STATE_TRANSITION.gas_delta.unwrap()),
+    }
+);
+
+#[derive(Clone, Debug)]
+struct AddMulModSuccessCase<F> {
+    case_selector: Cell<F>,
+    a: Word<F>,
+    b: Word<F>,
+    n: Word<F>,
+    r: Word<F>,
+    a_reduced: Word<F>,
+    a_quotient: Word<F>,
+    k: Word<F>,
+    is_addmod: PairSelectGadget<F>,
+    n_is_zero: IsZeroGadget<F>,
+    reduce_a: MulAddWordsGadget<F>,
+    mul_add_words_512: MulAddWords512Gadget<F>,
+    r_lt_n: LtWordGadget<F>,
+}
+
+// AddMulModGadget verifies ADDMOD and MULMOD at the same time. The full
+// intermediate result can take up to 257 bits for ADDMOD and up to 512 bits
+// for MULMOD, so the result is checked over the integers:
+// - `a` is first reduced with `a_quotient * n + a_reduced == a`, which keeps
+//   the quotient `k` below `2**256`
+// - ADDMOD: `a_reduced * 1 + b == k * n + r`
+// - MULMOD: `a_reduced * b + 0 == k * n + r`
+// and `r < n`. Note that `a_reduced` doesn't need to be less than `n` for the
+// result to be correct, it only needs to be congruent to `a`.
+// When `n == 0` the left side is set to `0`, which forces `r` to be `0` as
+// required by both opcodes.
+impl<F: FieldExt> AddMulModSuccessCase<F> {
+    pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::Success,
+        num_word: 7, // a + b + n + r + a_reduced + a_quotient + k
+        num_cell: PairSelectGadget::<F>::NUM_CELLS
+            + IsZeroGadget::<F>::NUM_CELLS
+            + MulAddWordsGadget::<F>::NUM_CELLS
+            + MulAddWords512Gadget::<F>::NUM_CELLS
+            + LtWordGadget::<F>::NUM_CELLS,
+        will_halt: false,
+    };
+
+    pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+            a: alloc.words.pop().unwrap(),
+            b: alloc.words.pop().unwrap(),
+            n: alloc.words.pop().unwrap(),
+            r: alloc.words.pop().unwrap(),
+            a_reduced: alloc.words.pop().unwrap(),
+            a_quotient: alloc.words.pop().unwrap(),
+            k: alloc.words.pop().unwrap(),
+            is_addmod: PairSelectGadget::construct(alloc),
+            n_is_zero: IsZeroGadget::construct(alloc),
+            reduce_a: MulAddWordsGadget::construct(alloc),
+            mul_add_words_512: MulAddWords512Gadget::construct(alloc),
+            r_lt_n: LtWordGadget::construct(alloc),
+        }
+    }
+
+    pub(crate) fn constraint(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        state_next: &OpExecutionState<F>,
+        name: &'static str,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::default();
+
+        let (is_addmod, _) = self.is_addmod.constraints(
+            &mut cb,
+            state_curr.opcode.expr(),
+            OpcodeId::ADDMOD.expr(),
+            OpcodeId::MULMOD.expr(),
+        );
+
+        let n_is_zero = self
+            .n_is_zero
+            .constraints(&mut cb, sum::expr(&self.n.cells));
+        let n_is_not_zero = 1.expr() - n_is_zero.clone();
+
+        // a_quotient * n + a_reduced == a, without overflow
+        let overflow = self.reduce_a.constraints(
+            &mut cb,
+            &word_limbs::expr(&self.a_quotient),
+            &word_limbs::expr(&self.n),
+            &word_limbs::expr(&self.a_reduced),
+            &word_limbs::expr(&self.a),
+        );
+        cb.require_zero(overflow);
+
+        // ADDMOD: a_reduced * 1 + b == k * n + r
+        // MULMOD: a_reduced * b + 0 == k * n + r
+        // with the left side set to 0 when n == 0
+        let b = word_limbs::expr(&self.b);
+        let lhs_a = word_limbs::expr(&self.a_reduced)
+            .map(|limb| limb * n_is_not_zero.clone());
+        let lhs_b = [
+            select::expr(is_addmod.clone(), 1.expr(), b[0].clone()),
+            (1.expr() - is_addmod.clone()) * b[1].clone(),
+            (1.expr() - is_addmod.clone()) * b[2].clone(),
+            (1.expr() - is_addmod.clone()) * b[3].clone(),
+        ];
+        let lhs_c =
+            b.map(|limb| is_addmod.clone() * n_is_not_zero.clone() * limb);
+        self.mul_add_words_512.constraints(
+            &mut cb,
+            [&lhs_a, &lhs_b, &lhs_c],
+            [
+                &word_limbs::expr(&self.k),
+                &word_limbs::expr(&self.n),
+                &word_limbs::expr(&self.r),
+            ],
+        );
+
+        // r < n when n != 0
+        let lt = self.r_lt_n.constraints(
+            &mut cb,
+            &word_limbs::expr(&self.r),
+            &word_limbs::expr(&self.n),
+        );
+        cb.require_zero(n_is_not_zero * (1.expr() - lt));
+
+        // Pop a, b and n from the stack, push r on the stack
+        cb.stack_pop(self.a.expr());
+        cb.stack_pop(self.b.expr());
+        cb.stack_pop(self.n.expr());
+        cb.stack_push(self.r.expr());
+
+        // State transitions
+        STATE_TRANSITION.constraints(&mut cb, state_curr, state_next);
+
+        // Generate the constraint
+        vec![cb.constraint(self.case_selector.expr(), name)]
+    }
+
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: &mut CoreStateInstance,
+        step: &ExecutionStep,
+    ) -> Result<(), Error> {
+        let (is_addmod, _) = self.is_addmod.assign(
+            region,
+            offset,
+            F::from(step.opcode.as_u64()),
+            F::from(OpcodeId::ADDMOD.as_u64()),
+            F::from(OpcodeId::MULMOD.as_u64()),
+        )?;
+        let is_addmod = is_addmod == F::one();
+
+        // Inputs
+        let a = step.values[0].clone();
+        let b = step.values[1].clone();
+        let n = step.values[2].clone();
+        self.a.assign(region, offset, Some(a.to_word()))?;
+        self.b.assign(region, offset, Some(b.to_word()))?;
+        self.n.assign(region, offset, Some(n.to_word()))?;
+        self.n_is_zero
+            .assign(region, offset, sum::value(&n.to_word()))?;
+
+        // Reduction of a
+        let (a_quotient, a_reduced) = if n.is_zero() {
+            (BigUint::zero(), a.clone())
+        } else {
+            (&a / &n, &a % &n)
+        };
+        self.a_quotient
+            .assign(region, offset, Some(a_quotient.to_word()))?;
+        self.a_reduced
+            .assign(region, offset, Some(a_reduced.to_word()))?;
+        self.reduce_a.assign(
+            region,
+            offset,
+            a_quotient.to_word(),
+            n.to_word(),
+            a_reduced.to_word(),
+        )?;
+
+        // Left side of the 512-bit equation
+        let (lhs_a, lhs_b, lhs_c) = match (n.is_zero(), is_addmod) {
+            (true, true) => (BigUint::zero(), BigUint::one(), BigUint::zero()),
+            (true, false) => (BigUint::zero(), b, BigUint::zero()),
+            (false, true) => (a_reduced, BigUint::one(), b),
+            (false, false) => (a_reduced, b, BigUint::zero()),
+        };
+        let lhs = &lhs_a * &lhs_b + &lhs_c;
+
+        // Quotient and result
+        let (k, r) = if n.is_zero() {
+            (BigUint::zero(), BigUint::zero())
+        } else {
+            (&lhs / &n, &lhs % &n)
+        };
+        self.k.assign(region, offset, Some(k.to_word()))?;
+        self.r.assign(region, offset, Some(r.to_word()))?;
+
+        self.mul_add_words_512.assign(
+            region,
+            offset,
+            [lhs_a.to_word(), lhs_b.to_word(), lhs_c.to_word()],
+            [k.to_word(), n.to_word(), r.to_word()],
+        )?;
+        self.r_lt_n
+            .assign(region, offset, r.to_word(), n.to_word())?;
+
+        // State transitions
+        STATE_TRANSITION.assign(state);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::super::test::{
+        run_test_circuit_from_code,
+        run_test_circuit_from_code_with_wrong_result,
+    };
+    use bus_mapping::{bytecode, eth_types::Word, evm::OpcodeId};
+    use pairing::bn256::Fr as Fp;
+
+    fn check(opcode: OpcodeId, a: Word, b: Word, n: Word) {
+        let mut code = bytecode! {
+            PUSH32(n)
+            PUSH32(b)
+            PUSH32(a)
+        };
+        code.write_op(opcode).write_op(OpcodeId::STOP);
+        assert_eq!(run_test_circuit_from_code::<Fp>(&code), Ok(()));
+        assert!(
+            run_test_circuit_from_code_with_wrong_result::<Fp>(&code).is_err()
+        );
+    }
+
+    #[test]
+    fn add_mul_mod_gadget() {
+        let max = Word::MAX;
+
+        // ADDMOD
+        // (0x0302 + 0x0605) % 0x1d == 0x14
+        check(
+            OpcodeId::ADDMOD,
+            0x03_02.into(),
+            0x06_05.into(),
+            0x1d.into(),
+        );
+        // (max + max) % max == 0
+        check(OpcodeId::ADDMOD, max, max, max);
+        // (max + 2) % 3 == 2, the sum doesn't fit in 256 bits
+        check(OpcodeId::ADDMOD, max, 2.into(), 3.into());
+        // (max + max) % 1 == 0
+        check(OpcodeId::ADDMOD, max, max, Word::one());
+        // (max + 1) % 0 == 0
+        check(OpcodeId::ADDMOD, max, Word::one(), Word::zero());
+
+        // MULMOD
+        // (0x0302 * 0x0605) % 0x1d == 0x06
+        check(
+            OpcodeId::MULMOD,
+            0x03_02.into(),
+            0x06_05.into(),
+            0x1d.into(),
+        );
+        // (max * max) % (max - 1) == 1
+        check(OpcodeId::MULMOD, max, max, max - 1);
+        // (max * max) % 12 == 9
+        check(OpcodeId::MULMOD, max, max, 12.into());
+        // (max * 2) % max == 0
+        check(OpcodeId::MULMOD, max, 2.into(), max);
+        // (max * max) % 0 == 0
+        check(OpcodeId::MULMOD, max, max, Word::zero());
+    }
+}
@@ -15,7 +15,6 @@ use crate::util::{Expr, ToWord};
 use bus_mapping::evm::{GasCost, OpcodeId};
 use halo2::plonk::Error;
 use halo2::{arithmetic::FieldExt, circuit::Region};
-use std::convert::TryInto;
 
 static STATE_TRANSITION: StateTransition = StateTransition {
     gc_delta: Some(3), // 2 stack pops + 1 stack push
@@ -152,7 +151,8 @@ mod test {
         ($execution_steps:expr, $operations:expr, $result:expr) => {{
             let circuit =
                 TestCircuit::<Fp>::new($execution_steps, $operations, true);
-            let prover = MockProver::<Fp>::run(18, &circuit, vec![]).unwrap();
+            let prover =
+                MockProver::<Fp>::run(18, &circuit, vec![vec![]]).unwrap();
             assert_eq!(prover.verify(), $result);
         }};
     }
@@ -192,6 +192,8 @@ mod test {
                         Fp::from(1023),
                         compress(b.clone()),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
                 Operation {
@@ -203,6 +205,8 @@ mod test {
                         Fp::from(1022),
                         compress(a.clone()),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
                 Operation {
@@ -214,6 +218,8 @@ mod test {
                         Fp::from(1022),
                         compress(a),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
                 Operation {
@@ -225,6 +231,8 @@ mod test {
                         Fp::from(1023),
                         compress(b),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
                 Operation {
@@ -236,6 +244,8 @@ mod test {
                         Fp::from(1023),
                         compress(c),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 }
             ],
@@ -0,0 +1,251 @@
+use super::super::super::{Case, Cell, Constraint, ExecutionStep, Word};
+use super::super::utils::{
+    self,
+    common_cases::{OutOfGasCase, StackUnderflowCase},
+    constraint_builder::ConstraintBuilder,
+    math_gadgets::{
+        IsZeroGadget, LtWordGadget, MulAddWordsGadget, PairSelectGadget,
+    },
+    select, sum, word_limbs, StateTransition,
+};
+use super::super::{
+    CaseAllocation, CaseConfig, CoreStateInstance, OpExecutionState, OpGadget,
+};
+use crate::impl_op_gadget;
+use crate::util::{Expr, ToWord};
+use bus_mapping::evm::{GasCost, OpcodeId};
+use halo2::plonk::Error;
+use halo2::{arithmetic::FieldExt, circuit::Region};
+use num::{BigUint, Zero};
+
+static STATE_TRANSITION: StateTransition = StateTransition {
+    gc_delta: Some(3), // 2 stack pops + 1 stack push
+    pc_delta: Some(1),
+    sp_delta: Some(1),
+    gas_delta: Some(GasCost::FAST.as_u64()),
+    next_memory_size: None,
+};
+const NUM_POPPED: usize = 2;
+
+impl_op_gadget!(
+    #set[DIV, MOD]
+    DivModGadget {
+        DivModSuccessCase(),
+        StackUnderflowCase(NUM_POPPED),
+        OutOfGasCase(STATE_TRANSITION.gas_delta.unwrap()),
+    }
+);
+
+#[derive(Clone, Debug)]
+struct DivModSuccessCase<F> {
+    case_selector: Cell<F>,
+    dividend: Word<F>,
+    divisor: Word<F>,
+    quotient: Word<F>,
+    remainder: Word<F>,
+    is_div: PairSelectGadget<F>,
+    divisor_is_zero: IsZeroGadget<F>,
+    mul_add_words: MulAddWordsGadget<F>,
+    remainder_lt_divisor: LtWordGadget<F>,
+}
+
+// DivModGadget verifies DIV and MOD at the same time. We witness the quotient
+// and the remainder and check that `quotient * divisor + remainder ==
+// dividend` without any overflow and that `remainder < divisor`.
+// Division by zero results in `0` for both opcodes. In that case the quotient
+// is required to be `0` and the remainder is set to the dividend so the
+// equation still holds, the result of MOD is then forced to `0`.
+impl<F: FieldExt> DivModSuccessCase<F> {
+    pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::Success,
+        num_word: 4, // dividend + divisor + quotient + remainder
+        num_cell: PairSelectGadget::<F>::NUM_CELLS
+            + IsZeroGadget::<F>::NUM_CELLS
+            + MulAddWordsGadget::<F>::NUM_CELLS
+            + LtWordGadget::<F>::NUM_CELLS,
+        will_halt: false,
+    };
+
+    pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+            dividend: alloc.words.pop().unwrap(),
+            divisor: alloc.words.pop().unwrap(),
+            quotient: alloc.words.pop().unwrap(),
+            remainder: alloc.words.pop().unwrap(),
+            is_div: PairSelectGadget::construct(alloc),
+            divisor_is_zero: IsZeroGadget::construct(alloc),
+            mul_add_words: MulAddWordsGadget::construct(alloc),
+            remainder_lt_divisor: LtWordGadget::construct(alloc),
+        }
+    }
+
+    pub(crate) fn constraint(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        state_next: &OpExecutionState<F>,
+        name: &'static str,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::default();
+
+        let (is_div, _) = self.is_div.constraints(
+            &mut cb,
+            state_curr.opcode.expr(),
+            OpcodeId::DIV.expr(),
+            OpcodeId::MOD.expr(),
+        );
+
+        let divisor_is_zero = self
+            .divisor_is_zero
+            .constraints(&mut cb, sum::expr(&self.divisor.cells));
+
+        // quotient * divisor + remainder == dividend, without overflow
+        let overflow = self.mul_add_words.constraints(
+            &mut cb,
+            &word_limbs::expr(&self.quotient),
+            &word_limbs::expr(&self.divisor),
+            &word_limbs::expr(&self.remainder),
+            &word_limbs::expr(&self.dividend),
+        );
+        cb.require_zero(overflow);
+
+        // remainder < divisor when divisor != 0
+        let lt = self.remainder_lt_divisor.constraints(
+            &mut cb,
+            &word_limbs::expr(&self.remainder),
+            &word_limbs::expr(&self.divisor),
+        );
+        cb.require_zero((1.expr() - divisor_is_zero.clone()) * (1.expr() - lt));
+
+        // quotient == 0 when divisor == 0
+        cb.require_zero(
+            divisor_is_zero.clone() * sum::expr(&self.quotient.cells),
+        );
+
+        // Pop dividend and divisor from the stack, push the result on the
+        // stack:
+        // - DIV: quotient
+        // - MOD: remainder, or 0 when divisor == 0
+        cb.stack_pop(self.dividend.expr());
+        cb.stack_pop(self.divisor.expr());
+        cb.stack_push(select::expr(
+            is_div,
+            self.quotient.expr(),
+            (1.expr() - divisor_is_zero) * self.remainder.expr(),
+        ));
+
+        // State transitions
+        STATE_TRANSITION.constraints(&mut cb, state_curr, state_next);
+
+        // Generate the constraint
+        vec![cb.constraint(self.case_selector.expr(), name)]
+    }
+
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: &mut CoreStateInstance,
+        step: &ExecutionStep,
+    ) -> Result<(), Error> {
+        self.is_div.assign(
+            region,
+            offset,
+            F::from(step.opcode.as_u64()),
+            F::from(OpcodeId::DIV.as_u64()),
+            F::from(OpcodeId::MOD.as_u64()),
+        )?;
+
+        // Inputs
+        let dividend = step.values[0].clone();
+        let divisor = step.values[1].clone();
+        self.dividend
+            .assign(region, offset, Some(dividend.to_word()))?;
+        self.divisor
+            .assign(region, offset, Some(divisor.to_word()))?;
+        self.divisor_is_zero.assign(
+            region,
+            offset,
+            sum::value(&divisor.to_word()),
+        )?;
+
+        // Quotient and remainder
+        let (quotient, remainder) = if divisor.is_zero() {
+            (BigUint::zero(), dividend)
+        } else {
+            (&dividend / &divisor, &dividend % &divisor)
+        };
+        self.quotient
+            .assign(region, offset, Some(quotient.to_word()))?;
+        self.remainder
+            .assign(region, offset, Some(remainder.to_word()))?;
+
+        self.mul_add_words.assign(
+            region,
+            offset,
+            quotient.to_word(),
+            divisor.to_word(),
+            remainder.to_word(),
+        )?;
+        self.remainder_lt_divisor.assign(
+            region,
+            offset,
+            remainder.to_word(),
+            divisor.to_word(),
+        )?;
+
+        // State transitions
+        STATE_TRANSITION.assign(state);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::super::test::{
+        run_test_circuit_from_code,
+        run_test_circuit_from_code_with_wrong_result,
+    };
+    use bus_mapping::{bytecode, eth_types::Word, evm::OpcodeId};
+    use pairing::bn256::Fr as Fp;
+
+    fn check(opcode: OpcodeId, a: Word, b: Word) {
+        let mut code = bytecode! {
+            PUSH32(b)
+            PUSH32(a)
+        };
+        code.write_op(opcode).write_op(OpcodeId::STOP);
+        assert_eq!(run_test_circuit_from_code::<Fp>(&code), Ok(()));
+        assert!(
+            run_test_circuit_from_code_with_wrong_result::<Fp>(&code).is_err()
+        );
+    }
+
+    #[test]
+    fn div_mod_gadget() {
+        let hi_lo = Word::MAX << 128;
+
+        // DIV
+        // 0x060504 / 0x0201 == 0x0301
+        check(OpcodeId::DIV, 0x06_05_04.into(), 0x02_01.into());
+        // max / 1 == max
+        check(OpcodeId::DIV, Word::MAX, Word::one());
+        // max / hi_lo == 1
+        check(OpcodeId::DIV, Word::MAX, hi_lo);
+        // hi_lo / max == 0
+        check(OpcodeId::DIV, hi_lo, Word::MAX);
+        // max / 0 == 0
+        check(OpcodeId::DIV, Word::MAX, Word::zero());
+
+        // MOD
+        // 0x060504 % 0x0201 == 0x03
+        check(OpcodeId::MOD, 0x06_05_04.into(), 0x02_01.into());
+        // max % hi_lo == 2**128 - 1
+        check(OpcodeId::MOD, Word::MAX, hi_lo);
+        // hi_lo % max == hi_lo
+        check(OpcodeId::MOD, hi_lo, Word::MAX);
+        // max % 0 == 0
+        check(OpcodeId::MOD, Word::MAX, Word::zero());
+    }
+}
@@ -0,0 +1,254 @@
+use super::super::super::{Case, Cell, Constraint, ExecutionStep, Word};
+use super::super::utils::{
+    self,
+    common_cases::StackUnderflowCase,
+    constraint_builder::ConstraintBuilder,
+    math_gadgets::{ByteSizeGadget, LtGadget},
+    StateTransition, StateTransitionExpressions,
+};
+use super::super::{
+    CaseAllocation, CaseConfig, CoreStateInstance, OpExecutionState, OpGadget,
+};
+use crate::evm_circuit::param::MAX_GAS_SIZE_IN_BYTES;
+use crate::impl_op_gadget;
+use crate::util::{Expr, ToWord};
+use bus_mapping::evm::{GasCost, OpcodeId};
+use halo2::plonk::Error;
+use halo2::{arithmetic::FieldExt, circuit::Region};
+use num::BigUint;
+
+static STATE_TRANSITION: StateTransition = StateTransition {
+    gc_delta: Some(3), // 2 stack pops + 1 stack push
+    pc_delta: Some(1),
+    sp_delta: Some(1),
+    gas_delta: None, // GAS + GAS_BYTE * byte_size(exponent)
+    next_memory_size: None,
+};
+const GAS: GasCost = GasCost::SLOW;
+const GAS_BYTE: GasCost = GasCost::EXP_BYTE_TIMES;
+const NUM_POPPED: usize = 2;
+
+impl_op_gadget!(
+    #set[EXP]
+    ExpGadget {
+        ExpSuccessCase(),
+        StackUnderflowCase(NUM_POPPED),
+        ExpOutOfGasCase(),
+    }
+);
+
+#[derive(Clone, Debug)]
+struct ExpSuccessCase<F> {
+    case_selector: Cell<F>,
+    base: Word<F>,
+    exponent: Word<F>,
+    result: Word<F>,
+    exponent_byte_size: ByteSizeGadget<F>,
+}
+
+// ExpGadget takes the result of `base ** exponent (mod 2**256)` from the exp
+// table, the step itself only verifies the stack operations and the gas cost,
+// which depends on the number of bytes of the exponent.
+impl<F: FieldExt> ExpSuccessCase<F> {
+    pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::Success,
+        num_word: 3, // base + exponent + result
+        num_cell: ByteSizeGadget::<F>::NUM_CELLS,
+        will_halt: false,
+    };
+
+    pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+            base: alloc.words.pop().unwrap(),
+            exponent: alloc.words.pop().unwrap(),
+            result: alloc.words.pop().unwrap(),
+            exponent_byte_size: ByteSizeGadget::construct(alloc),
+        }
+    }
+
+    pub(crate) fn constraint(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        state_next: &OpExecutionState<F>,
+        name: &'static str,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::default();
+
+        let byte_size =
+            self.exponent_byte_size.constraints(&mut cb, &self.exponent);
+
+        // base ** exponent == result (mod 2**256)
+        cb.add_exp_lookup([
+            self.base.expr(),
+            self.exponent.expr(),
+            self.result.expr(),
+        ]);
+
+        // Pop base and exponent from the stack, push result on the stack
+        cb.stack_pop(self.base.expr());
+        cb.stack_pop(self.exponent.expr());
+        cb.stack_push(self.result.expr());
+
+        // State transitions
+        // - `gas_delta` needs to be increased by `GAS + GAS_BYTE *
+        //   byte_size(exponent)`
+        let mut st = StateTransitionExpressions::new(STATE_TRANSITION.clone());
+        st.gas_delta = Some(GAS.expr() + GAS_BYTE.expr() * byte_size);
+        st.constraints(&mut cb, state_curr, state_next);
+
+        // Generate the constraint
+        vec![cb.constraint(self.case_selector.expr(), name)]
+    }
+
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: &mut CoreStateInstance,
+        step: &ExecutionStep,
+    ) -> Result<(), Error> {
+        // Inputs and output
+        let base = step.values[0].clone();
+        let exponent = step.values[1].clone();
+        let result = base.modpow(&exponent, &(BigUint::from(1u64) << 256));
+        self.base.assign(region, offset, Some(base.to_word()))?;
+        self.exponent
+            .assign(region, offset, Some(exponent.to_word()))?;
+        self.result.assign(region, offset, Some(result.to_word()))?;
+
+        let byte_size = self.exponent_byte_size.assign(
+            region,
+            offset,
+            exponent.to_word(),
+        )?;
+
+        // State transitions
+        let mut st = STATE_TRANSITION.clone();
+        st.gas_delta = Some(GAS.as_u64() + GAS_BYTE.as_u64() * byte_size);
+        st.assign(state);
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+struct ExpOutOfGasCase<F> {
+    case_selector: Cell<F>,
+    exponent: Word<F>,
+    exponent_byte_size: ByteSizeGadget<F>,
+    insufficient_gas: LtGadget<F, MAX_GAS_SIZE_IN_BYTES>,
+}
+
+impl<F: FieldExt> ExpOutOfGasCase<F> {
+    pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::OutOfGas,
+        num_word: 1, // exponent
+        num_cell: ByteSizeGadget::<F>::NUM_CELLS
+            + LtGadget::<F, MAX_GAS_SIZE_IN_BYTES>::NUM_CELLS,
+        will_halt: true,
+    };
+
+    pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+            exponent: alloc.words.pop().unwrap(),
+            exponent_byte_size: ByteSizeGadget::construct(alloc),
+            insufficient_gas: LtGadget::construct(alloc),
+        }
+    }
+
+    pub(crate) fn constraint(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        _state_next: &OpExecutionState<F>,
+        name: &'static str,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::default();
+
+        let byte_size =
+            self.exponent_byte_size.constraints(&mut cb, &self.exponent);
+
+        // Make sure the amount of gas available is less than the amount of
+        // gas required
+        let insufficient_gas = self.insufficient_gas.constraints(
+            &mut cb,
+            state_curr.gas_left.expr(),
+            GAS.expr() + GAS_BYTE.expr() * byte_size,
+        );
+        cb.require_equal(insufficient_gas, 1.expr());
+
+        // Read the exponent from the stack, which is the second item
+        cb.stack_lookup(1.expr(), self.exponent.expr(), false.expr());
+
+        // Generate the constraint
+        vec![cb.constraint(self.case_selector.expr(), name)]
+    }
+
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: &mut CoreStateInstance,
+        step: &ExecutionStep,
+    ) -> Result<(), Error> {
+        // Inputs
+        let exponent = step.values[1].clone();
+        self.exponent
+            .assign(region, offset, Some(exponent.to_word()))?;
+        let byte_size = self.exponent_byte_size.assign(
+            region,
+            offset,
+            exponent.to_word(),
+        )?;
+
+        // Gas insufficient check
+        self.insufficient_gas.assign(
+            region,
+            offset,
+            F::from(state.gas_left),
+            F::from(GAS.as_u64() + GAS_BYTE.as_u64() * byte_size),
+        )?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::super::test::{
+        run_test_circuit_from_code,
+        run_test_circuit_from_code_with_wrong_result,
+    };
+    use bus_mapping::{bytecode, eth_types::Word};
+    use pairing::bn256::Fr as Fp;
+
+    fn check(base: Word, exponent: Word) {
+        let code = bytecode! {
+            PUSH32(exponent)
+            PUSH32(base)
+            EXP
+            STOP
+        };
+        assert_eq!(run_test_circuit_from_code::<Fp>(&code), Ok(()));
+        assert!(
+            run_test_circuit_from_code_with_wrong_result::<Fp>(&code).is_err()
+        );
+    }
+
+    #[test]
+    fn exp_gadget() {
+        // 3 ** 5 == 243
+        check(3.into(), 5.into());
+        // max ** 0 == 1
+        check(Word::MAX, Word::zero());
+        // 0 ** 0 == 1
+        check(Word::zero(), Word::zero());
+        // 2 ** 255 == 2**255
+        check(2.into(), 255.into());
+        // 2 ** 256 == 0 (mod 2**256)
+        check(2.into(), 256.into());
+        // max ** max == max (mod 2**256), with a 32-byte exponent
+        check(Word::MAX, Word::MAX);
+    }
+}
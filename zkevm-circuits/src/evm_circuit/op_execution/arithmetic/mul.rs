@@ -0,0 +1,161 @@
+use super::super::super::{Case, Cell, Constraint, ExecutionStep, Word};
+use super::super::utils::{
+    self,
+    common_cases::{OutOfGasCase, StackUnderflowCase},
+    constraint_builder::ConstraintBuilder,
+    math_gadgets::MulAddWordsGadget,
+    word_limbs, StateTransition,
+};
+use super::super::{
+    CaseAllocation, CaseConfig, CoreStateInstance, OpExecutionState, OpGadget,
+};
+use crate::impl_op_gadget;
+use crate::util::{Expr, ToWord};
+use bus_mapping::evm::{GasCost, OpcodeId};
+use halo2::plonk::Error;
+use halo2::{arithmetic::FieldExt, circuit::Region};
+use num::BigUint;
+
+static STATE_TRANSITION: StateTransition = StateTransition {
+    gc_delta: Some(3), // 2 stack pops + 1 stack push
+    pc_delta: Some(1),
+    sp_delta: Some(1),
+    gas_delta: Some(GasCost::FAST.as_u64()),
+    next_memory_size: None,
+};
+const NUM_POPPED: usize = 2;
+
+impl_op_gadget!(
+    #set[MUL]
+    MulGadget {
+        MulSuccessCase(),
+        StackUnderflowCase(NUM_POPPED),
+        OutOfGasCase(STATE_TRANSITION.gas_delta.unwrap()),
+    }
+);
+
+#[derive(Clone, Debug)]
+struct MulSuccessCase<F> {
+    case_selector: Cell<F>,
+    a: Word<F>,
+    b: Word<F>,
+    c: Word<F>,
+    mul_add_words: MulAddWordsGadget<F>,
+}
+
+// MulGadget verifies `a * b == c (mod 2**256)` on 64-bit limbs, the overflow
+// of the product is simply ignored.
+impl<F: FieldExt> MulSuccessCase<F> {
+    pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::Success,
+        num_word: 3, // a + b + c
+        num_cell: MulAddWordsGadget::<F>::NUM_CELLS,
+        will_halt: false,
+    };
+
+    pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+            a: alloc.words.pop().unwrap(),
+            b: alloc.words.pop().unwrap(),
+            c: alloc.words.pop().unwrap(),
+            mul_add_words: MulAddWordsGadget::construct(alloc),
+        }
+    }
+
+    pub(crate) fn constraint(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        state_next: &OpExecutionState<F>,
+        name: &'static str,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::default();
+
+        // a * b + 0 == c (mod 2**256)
+        self.mul_add_words.constraints(
+            &mut cb,
+            &word_limbs::expr(&self.a),
+            &word_limbs::expr(&self.b),
+            &[0.expr(), 0.expr(), 0.expr(), 0.expr()],
+            &word_limbs::expr(&self.c),
+        );
+
+        // Pop a and b from the stack, push c on the stack
+        cb.stack_pop(self.a.expr());
+        cb.stack_pop(self.b.expr());
+        cb.stack_push(self.c.expr());
+
+        // State transitions
+        STATE_TRANSITION.constraints(&mut cb, state_curr, state_next);
+
+        // Generate the constraint
+        vec![cb.constraint(self.case_selector.expr(), name)]
+    }
+
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: &mut CoreStateInstance,
+        step: &ExecutionStep,
+    ) -> Result<(), Error> {
+        // Inputs and output
+        let a = step.values[0].clone();
+        let b = step.values[1].clone();
+        let c: BigUint = (&a * &b) % (BigUint::from(1u64) << 256);
+        self.a.assign(region, offset, Some(a.to_word()))?;
+        self.b.assign(region, offset, Some(b.to_word()))?;
+        self.c.assign(region, offset, Some(c.to_word()))?;
+
+        // Carries of the multiplication
+        self.mul_add_words.assign(
+            region,
+            offset,
+            a.to_word(),
+            b.to_word(),
+            [0u8; 32],
+        )?;
+
+        // State transitions
+        STATE_TRANSITION.assign(state);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::super::test::{
+        run_test_circuit_from_code,
+        run_test_circuit_from_code_with_wrong_result,
+    };
+    use bus_mapping::{bytecode, eth_types::Word};
+    use pairing::bn256::Fr as Fp;
+
+    fn check(a: Word, b: Word) {
+        let code = bytecode! {
+            PUSH32(b)
+            PUSH32(a)
+            MUL
+            STOP
+        };
+        assert_eq!(run_test_circuit_from_code::<Fp>(&code), Ok(()));
+        assert!(
+            run_test_circuit_from_code_with_wrong_result::<Fp>(&code).is_err()
+        );
+    }
+
+    #[test]
+    fn mul_gadget() {
+        // 0x030201 * 0x060504 == 0x12_1b_1c_0d_04
+        check(0x03_02_01.into(), 0x06_05_04.into());
+        // 0 * max == 0
+        check(Word::zero(), Word::MAX);
+        // max * max == 1 (mod 2**256)
+        check(Word::MAX, Word::MAX);
+        // (2**128) * (2**128) == 0 (mod 2**256)
+        check(Word::one() << 128, Word::one() << 128);
+        // max * 2 == max - 1 (mod 2**256)
+        check(Word::MAX, 2.into());
+    }
+}
@@ -0,0 +1,372 @@
+use super::super::super::{
+    Case, Cell, Constraint, ExecutionStep, FixedLookup, Word,
+};
+use super::super::utils::{
+    self,
+    common_cases::{OutOfGasCase, StackUnderflowCase},
+    constraint_builder::ConstraintBuilder,
+    math_gadgets::{
+        AbsWordGadget, IsZeroGadget, LtWordGadget, MulAddWordsGadget,
+        PairSelectGadget,
+    },
+    negate_word, select, sum, StateTransition,
+};
+use super::super::{
+    CaseAllocation, CaseConfig, CoreStateInstance, OpExecutionState, OpGadget,
+};
+use crate::impl_op_gadget;
+use crate::util::{Expr, ToWord};
+use bus_mapping::evm::{GasCost, OpcodeId};
+use halo2::plonk::Error;
+use halo2::{arithmetic::FieldExt, circuit::Region};
+use num::{BigUint, Zero};
+
+static STATE_TRANSITION: StateTransition = StateTransition {
+    gc_delta: Some(3), // 2 stack pops + 1 stack push
+    pc_delta: Some(1),
+    sp_delta: Some(1),
+    gas_delta: Some(GasCost::FAST.as_u64()),
+    next_memory_size: None,
+};
+const NUM_POPPED: usize = 2;
+
+impl_op_gadget!(
+    #set[SDIV, SMOD]
+    SignedDivModGadget {
+        SignedDivModSuccessCase(),
+        StackUnderflowCase(NUM_POPPED),
+        OutOfGasCase(STATE_TRANSITION.gas_delta.unwrap()),
+    }
+);
+
+#[derive(Clone, Debug)]
+struct SignedDivModSuccessCase<F> {
+    case_selector: Cell<F>,
+    dividend: Word<F>,
+    divisor: Word<F>,
+    quotient: Word<F>,
+    remainder: Word<F>,
+    is_sdiv: PairSelectGadget<F>,
+    dividend_sign_byte: Cell<F>,
+    divisor_sign_byte: Cell<F>,
+    dividend_abs: AbsWordGadget<F>,
+    divisor_abs: AbsWordGadget<F>,
+    quotient_abs: AbsWordGadget<F>,
+    remainder_abs: AbsWordGadget<F>,
+    divisor_is_zero: IsZeroGadget<F>,
+    mul_add_words: MulAddWordsGadget<F>,
+    remainder_lt_divisor: LtWordGadget<F>,
+}
+
+// SignedDivModGadget verifies SDIV and SMOD at the same time, by doing the
+// unsigned division on the absolute values:
+// `|quotient| * |divisor| + |remainder| == |dividend|` and
+// `|remainder| < |divisor|`.
+// The quotient is negative when exactly one of the inputs is negative, the
+// remainder always has the sign of the dividend. The absolute value of the
+// quotient is taken with this expected sign instead of the sign bit of the
+// quotient, so that `-2**255 / -1` correctly overflows to `-2**255`.
+// Division by zero is handled like in DivModGadget.
+impl<F: FieldExt> SignedDivModSuccessCase<F> {
+    pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::Success,
+        num_word: 4, // dividend + divisor + quotient + remainder
+        num_cell: PairSelectGadget::<F>::NUM_CELLS
+            + 2 // sign bytes
+            + 4 * AbsWordGadget::<F>::NUM_CELLS
+            + IsZeroGadget::<F>::NUM_CELLS
+            + MulAddWordsGadget::<F>::NUM_CELLS
+            + LtWordGadget::<F>::NUM_CELLS,
+        will_halt: false,
+    };
+
+    pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+            dividend: alloc.words.pop().unwrap(),
+            divisor: alloc.words.pop().unwrap(),
+            quotient: alloc.words.pop().unwrap(),
+            remainder: alloc.words.pop().unwrap(),
+            is_sdiv: PairSelectGadget::construct(alloc),
+            dividend_sign_byte: alloc.cells.pop().unwrap(),
+            divisor_sign_byte: alloc.cells.pop().unwrap(),
+            dividend_abs: AbsWordGadget::construct(alloc),
+            divisor_abs: AbsWordGadget::construct(alloc),
+            quotient_abs: AbsWordGadget::construct(alloc),
+            remainder_abs: AbsWordGadget::construct(alloc),
+            divisor_is_zero: IsZeroGadget::construct(alloc),
+            mul_add_words: MulAddWordsGadget::construct(alloc),
+            remainder_lt_divisor: LtWordGadget::construct(alloc),
+        }
+    }
+
+    pub(crate) fn constraint(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        state_next: &OpExecutionState<F>,
+        name: &'static str,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::default();
+
+        let (is_sdiv, _) = self.is_sdiv.constraints(
+            &mut cb,
+            state_curr.opcode.expr(),
+            OpcodeId::SDIV.expr(),
+            OpcodeId::SMOD.expr(),
+        );
+
+        // Lookup the sign bytes of the inputs, which are either 0x00 or 0xFF
+        // depending on the most significant bit.
+        cb.add_fixed_lookup(
+            FixedLookup::SignByte,
+            [
+                self.dividend.cells[31].expr(),
+                self.dividend_sign_byte.expr(),
+                0.expr(),
+            ],
+        );
+        cb.add_fixed_lookup(
+            FixedLookup::SignByte,
+            [
+                self.divisor.cells[31].expr(),
+                self.divisor_sign_byte.expr(),
+                0.expr(),
+            ],
+        );
+        let inv_255 = F::from(255).invert().unwrap();
+        let dividend_is_neg = self.dividend_sign_byte.expr() * inv_255;
+        let divisor_is_neg = self.divisor_sign_byte.expr() * inv_255;
+        let quotient_is_neg = dividend_is_neg.clone() + divisor_is_neg.clone()
+            - 2.expr() * dividend_is_neg.clone() * divisor_is_neg.clone();
+
+        let dividend_abs = self.dividend_abs.constraints(
+            &mut cb,
+            &self.dividend,
+            dividend_is_neg.clone(),
+        );
+        let divisor_abs = self.divisor_abs.constraints(
+            &mut cb,
+            &self.divisor,
+            divisor_is_neg,
+        );
+        let quotient_abs = self.quotient_abs.constraints(
+            &mut cb,
+            &self.quotient,
+            quotient_is_neg,
+        );
+        let remainder_abs = self.remainder_abs.constraints(
+            &mut cb,
+            &self.remainder,
+            dividend_is_neg,
+        );
+
+        let divisor_is_zero = self
+            .divisor_is_zero
+            .constraints(&mut cb, sum::expr(&self.divisor.cells));
+
+        // |quotient| * |divisor| + |remainder| == |dividend|, without overflow
+        let overflow = self.mul_add_words.constraints(
+            &mut cb,
+            &quotient_abs,
+            &divisor_abs,
+            &remainder_abs,
+            &dividend_abs,
+        );
+        cb.require_zero(overflow);
+
+        // |remainder| < |divisor| when divisor != 0
+        let lt = self.remainder_lt_divisor.constraints(
+            &mut cb,
+            &remainder_abs,
+            &divisor_abs,
+        );
+        cb.require_zero((1.expr() - divisor_is_zero.clone()) * (1.expr() - lt));
+
+        // quotient == 0 when divisor == 0
+        cb.require_zero(
+            divisor_is_zero.clone() * sum::expr(&self.quotient.cells),
+        );
+
+        // Pop dividend and divisor from the stack, push the result on the
+        // stack:
+        // - SDIV: quotient
+        // - SMOD: remainder, or 0 when divisor == 0
+        cb.stack_pop(self.dividend.expr());
+        cb.stack_pop(self.divisor.expr());
+        cb.stack_push(select::expr(
+            is_sdiv,
+            self.quotient.expr(),
+            (1.expr() - divisor_is_zero) * self.remainder.expr(),
+        ));
+
+        // State transitions
+        STATE_TRANSITION.constraints(&mut cb, state_curr, state_next);
+
+        // Generate the constraint
+        vec![cb.constraint(self.case_selector.expr(), name)]
+    }
+
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: &mut CoreStateInstance,
+        step: &ExecutionStep,
+    ) -> Result<(), Error> {
+        self.is_sdiv.assign(
+            region,
+            offset,
+            F::from(step.opcode.as_u64()),
+            F::from(OpcodeId::SDIV.as_u64()),
+            F::from(OpcodeId::SMOD.as_u64()),
+        )?;
+
+        // Inputs
+        let dividend = step.values[0].to_word();
+        let divisor = step.values[1].to_word();
+        self.dividend.assign(region, offset, Some(dividend))?;
+        self.divisor.assign(region, offset, Some(divisor))?;
+
+        // Signs
+        let dividend_is_neg = dividend[31] >= 128;
+        let divisor_is_neg = divisor[31] >= 128;
+        let quotient_is_neg = dividend_is_neg ^ divisor_is_neg;
+        for (cell, is_neg) in [
+            (&self.dividend_sign_byte, dividend_is_neg),
+            (&self.divisor_sign_byte, divisor_is_neg),
+        ] {
+            cell.assign(
+                region,
+                offset,
+                Some(F::from(if is_neg { 255 } else { 0 })),
+            )?;
+        }
+
+        // Absolute values of the inputs
+        let dividend_abs = self.dividend_abs.assign(
+            region,
+            offset,
+            dividend,
+            dividend_is_neg,
+        )?;
+        let divisor_abs =
+            self.divisor_abs
+                .assign(region, offset, divisor, divisor_is_neg)?;
+        self.divisor_is_zero
+            .assign(region, offset, sum::value(&divisor))?;
+
+        // Quotient and remainder of the absolute values
+        let dividend_abs = BigUint::from_bytes_le(&dividend_abs);
+        let divisor_abs = BigUint::from_bytes_le(&divisor_abs);
+        let (quotient_abs, remainder_abs) = if divisor_abs.is_zero() {
+            (BigUint::zero(), dividend_abs)
+        } else {
+            (&dividend_abs / &divisor_abs, &dividend_abs % &divisor_abs)
+        };
+        let (quotient_abs, remainder_abs) =
+            (quotient_abs.to_word(), remainder_abs.to_word());
+
+        // Apply the signs
+        let quotient = if quotient_is_neg {
+            negate_word(quotient_abs)
+        } else {
+            quotient_abs
+        };
+        let remainder = if dividend_is_neg {
+            negate_word(remainder_abs)
+        } else {
+            remainder_abs
+        };
+        self.quotient.assign(region, offset, Some(quotient))?;
+        self.remainder.assign(region, offset, Some(remainder))?;
+        self.quotient_abs
+            .assign(region, offset, quotient, quotient_is_neg)?;
+        self.remainder_abs.assign(
+            region,
+            offset,
+            remainder,
+            dividend_is_neg,
+        )?;
+
+        self.mul_add_words.assign(
+            region,
+            offset,
+            quotient_abs,
+            divisor_abs.to_word(),
+            remainder_abs,
+        )?;
+        self.remainder_lt_divisor.assign(
+            region,
+            offset,
+            remainder_abs,
+            divisor_abs.to_word(),
+        )?;
+
+        // State transitions
+        STATE_TRANSITION.assign(state);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::super::test::{
+        run_test_circuit_from_code,
+        run_test_circuit_from_code_with_wrong_result,
+    };
+    use bus_mapping::{bytecode, eth_types::Word, evm::OpcodeId};
+    use pairing::bn256::Fr as Fp;
+
+    // Two's complement of a small value
+    fn neg(value: u64) -> Word {
+        Word::zero().overflowing_sub(value.into()).0
+    }
+
+    fn check(opcode: OpcodeId, a: Word, b: Word) {
+        let mut code = bytecode! {
+            PUSH32(b)
+            PUSH32(a)
+        };
+        code.write_op(opcode).write_op(OpcodeId::STOP);
+        assert_eq!(run_test_circuit_from_code::<Fp>(&code), Ok(()));
+        assert!(
+            run_test_circuit_from_code_with_wrong_result::<Fp>(&code).is_err()
+        );
+    }
+
+    #[test]
+    fn signed_div_mod_gadget() {
+        let min = Word::one() << 255;
+
+        // SDIV
+        // 7 / 2 == 3
+        check(OpcodeId::SDIV, 7.into(), 2.into());
+        // -7 / 2 == -3
+        check(OpcodeId::SDIV, neg(7), 2.into());
+        // 7 / -2 == -3
+        check(OpcodeId::SDIV, 7.into(), neg(2));
+        // -7 / -2 == 3
+        check(OpcodeId::SDIV, neg(7), neg(2));
+        // -1 / 2 == 0
+        check(OpcodeId::SDIV, neg(1), 2.into());
+        // -2**255 / -1 == -2**255
+        check(OpcodeId::SDIV, min, neg(1));
+        // -7 / 0 == 0
+        check(OpcodeId::SDIV, neg(7), Word::zero());
+
+        // SMOD
+        // 7 % 2 == 1
+        check(OpcodeId::SMOD, 7.into(), 2.into());
+        // -7 % 2 == -1
+        check(OpcodeId::SMOD, neg(7), 2.into());
+        // 7 % -2 == 1
+        check(OpcodeId::SMOD, 7.into(), neg(2));
+        // -8 % -3 == -2
+        check(OpcodeId::SMOD, neg(8), neg(3));
+        // -2**255 % -1 == 0
+        check(OpcodeId::SMOD, min, neg(1));
+        // -7 % 0 == 0
+        check(OpcodeId::SMOD, neg(7), Word::zero());
+    }
+}
@@ -0,0 +1,365 @@
+use super::super::{
+    Case, Cell, Constraint, TxContextField, TxExecutionStep, Word,
+};
+use super::utils::{
+    constraint_builder::ConstraintBuilder,
+    from_bytes,
+    math_gadgets::{MulAddWordsGadget, RangeCheckGadget},
+    word_limbs,
+};
+use super::{
+    CaseAllocation, CaseConfig, CoreStateInstance, OpExecutionState, TxGadget,
+};
+use crate::util::{Expr, ToWord};
+use bus_mapping::evm::GasCost;
+use halo2::plonk::Error;
+use halo2::{arithmetic::FieldExt, circuit::Region};
+use num::{BigUint, ToPrimitive};
+
+// 1 sender nonce write + 3 balance writes (gas purchase, value transfer out
+// of the sender and into the receiver), followed by 1 code hash read of the
+// receiver of a call
+const GC_DELTA: usize = 4;
+const STACK_POINTER_INIT: usize = 1024;
+
+// BeginTxGadget verifies the state changes done by a transaction before the
+// execution of its first step:
+// - the nonce of the sender is increased by one
+// - the sender buys all the gas of the transaction upfront,
+//   `sender_balance_prev == gas_price * gas + sender_balance_after_gas`
+// - the value is transferred from the sender to the receiver (the new contract
+//   when the transaction is a creation)
+// None of the balance updates can overflow, which also proves the sender could
+// afford the transaction.
+// The execution then starts with the intrinsic gas of the transaction already
+// used, which is the constant cost of a transaction or of a creation plus the
+// costs of its calldata and of its access list, and with the code of the
+// receiver of a call.
+// The sender, the receiver of a call, the nonce, the gas, the gas price and the
+// value are the ones of the transaction in the tx table.  The transaction
+// follows the last finished one, whose id is kept in the call id of the steps
+// between two transactions.
+#[derive(Clone, Debug)]
+pub(crate) struct BeginTxGadget<F> {
+    case_selector: Cell<F>,
+    tx_id: Cell<F>,
+    is_create: Cell<F>,
+    sender: Cell<F>,
+    receiver: Cell<F>,
+    nonce_prev: Cell<F>,
+    gas: Cell<F>,
+    intrinsic_gas: Cell<F>,
+    call_data_gas_cost: Cell<F>,
+    access_list_gas_cost: Cell<F>,
+    gas_price: Word<F>,
+    value: Word<F>,
+    sender_balance_prev: Word<F>,
+    sender_balance_after_gas: Word<F>,
+    sender_balance: Word<F>,
+    receiver_balance_prev: Word<F>,
+    receiver_balance: Word<F>,
+    code_hash: Word<F>,
+    gas_range_check: RangeCheckGadget<F, 8>,
+    gas_fee: MulAddWordsGadget<F>,
+    sender_transfer: MulAddWordsGadget<F>,
+    receiver_transfer: MulAddWordsGadget<F>,
+}
+
+impl<F: FieldExt> TxGadget<F> for BeginTxGadget<F> {
+    const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::Success,
+        // gas_price + value + sender and receiver balances + code_hash
+        num_word: 8,
+        num_cell: 7 // tx_id + is_create + sender + receiver + nonce_prev
+            + 4 // gas + intrinsic_gas + calldata and access list gas costs
+            + RangeCheckGadget::<F, 8>::NUM_CELLS
+            + 3 * MulAddWordsGadget::<F>::NUM_CELLS,
+        will_halt: false,
+    };
+
+    fn construct(mut alloc: CaseAllocation<F>) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+            tx_id: alloc.cells.pop().unwrap(),
+            is_create: alloc.cells.pop().unwrap(),
+            sender: alloc.cells.pop().unwrap(),
+            receiver: alloc.cells.pop().unwrap(),
+            nonce_prev: alloc.cells.pop().unwrap(),
+            gas: alloc.cells.pop().unwrap(),
+            intrinsic_gas: alloc.cells.pop().unwrap(),
+            call_data_gas_cost: alloc.cells.pop().unwrap(),
+            access_list_gas_cost: alloc.cells.pop().unwrap(),
+            gas_price: alloc.words.pop().unwrap(),
+            value: alloc.words.pop().unwrap(),
+            sender_balance_prev: alloc.words.pop().unwrap(),
+            sender_balance_after_gas: alloc.words.pop().unwrap(),
+            sender_balance: alloc.words.pop().unwrap(),
+            receiver_balance_prev: alloc.words.pop().unwrap(),
+            receiver_balance: alloc.words.pop().unwrap(),
+            code_hash: alloc.words.pop().unwrap(),
+            gas_range_check: RangeCheckGadget::construct(&mut alloc),
+            gas_fee: MulAddWordsGadget::construct(&mut alloc),
+            sender_transfer: MulAddWordsGadget::construct(&mut alloc),
+            receiver_transfer: MulAddWordsGadget::construct(&mut alloc),
+        }
+    }
+
+    fn constraints(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        state_next: &OpExecutionState<F>,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::default();
+
+        // The tx id follows the one of the last finished transaction
+        cb.require_equal(
+            self.tx_id.expr(),
+            state_curr.call_id.expr() + 1.expr(),
+        );
+
+        // The receiver of a creation is the new contract, while the callee
+        // address of the transaction is empty
+        cb.require_boolean(self.is_create.expr());
+        for (field, value) in [
+            (TxContextField::Origin, self.sender.expr()),
+            (TxContextField::Nonce, self.nonce_prev.expr()),
+            (TxContextField::Gas, self.gas.expr()),
+            (TxContextField::GasPrice, self.gas_price.expr()),
+            (TxContextField::Value, self.value.expr()),
+            (TxContextField::IsCreate, self.is_create.expr()),
+            (
+                TxContextField::CalleeAddress,
+                (1.expr() - self.is_create.expr()) * self.receiver.expr(),
+            ),
+            (
+                TxContextField::CallDataGasCost,
+                self.call_data_gas_cost.expr(),
+            ),
+            (
+                TxContextField::AccessListGasCost,
+                self.access_list_gas_cost.expr(),
+            ),
+        ] {
+            cb.tx_context_lookup(self.tx_id.expr(), field, value);
+        }
+
+        // intrinsic_gas == TX + is_create * CREATION_TX + call_data_gas_cost +
+        // access_list_gas_cost
+        cb.require_equal(
+            self.intrinsic_gas.expr(),
+            GasCost::TX.expr()
+                + self.is_create.expr() * GasCost::CREATION_TX.expr()
+                + self.call_data_gas_cost.expr()
+                + self.access_list_gas_cost.expr(),
+        );
+
+        // The gas is a single limb of the gas fee multiplication
+        self.gas_range_check.constraints(&mut cb, self.gas.expr());
+        let gas = [self.gas.expr(), 0.expr(), 0.expr(), 0.expr()];
+        let one = [1.expr(), 0.expr(), 0.expr(), 0.expr()];
+
+        // Increase the nonce of the sender
+        cb.account_nonce_lookup(
+            true,
+            self.sender.expr(),
+            self.nonce_prev.expr() + 1.expr(),
+            self.nonce_prev.expr(),
+        );
+
+        // Buy the gas:
+        // gas_price * gas + sender_balance_after_gas == sender_balance_prev
+        let overflow = self.gas_fee.constraints(
+            &mut cb,
+            &word_limbs::expr(&self.gas_price),
+            &gas,
+            &word_limbs::expr(&self.sender_balance_after_gas),
+            &word_limbs::expr(&self.sender_balance_prev),
+        );
+        cb.require_zero(overflow);
+        cb.account_balance_lookup(
+            true,
+            self.sender.expr(),
+            self.sender_balance_after_gas.expr(),
+            self.sender_balance_prev.expr(),
+        );
+
+        // Transfer the value:
+        // value + sender_balance == sender_balance_after_gas
+        // value + receiver_balance_prev == receiver_balance
+        let overflow = self.sender_transfer.constraints(
+            &mut cb,
+            &word_limbs::expr(&self.value),
+            &one,
+            &word_limbs::expr(&self.sender_balance),
+            &word_limbs::expr(&self.sender_balance_after_gas),
+        );
+        cb.require_zero(overflow);
+        cb.account_balance_lookup(
+            true,
+            self.sender.expr(),
+            self.sender_balance.expr(),
+            self.sender_balance_after_gas.expr(),
+        );
+        let overflow = self.receiver_transfer.constraints(
+            &mut cb,
+            &word_limbs::expr(&self.value),
+            &one,
+            &word_limbs::expr(&self.receiver_balance_prev),
+            &word_limbs::expr(&self.receiver_balance),
+        );
+        cb.require_zero(overflow);
+        cb.account_balance_lookup(
+            true,
+            self.receiver.expr(),
+            self.receiver_balance.expr(),
+            self.receiver_balance_prev.expr(),
+        );
+
+        // The root call of a call executes the code of the receiver.
+        // TODO: The code of a creation is its calldata, whose hash should be
+        // given by the tx table.
+        let is_call = 1.expr() - self.is_create.expr();
+        cb.condition(is_call.clone(), |cb| {
+            cb.account_code_hash_lookup(
+                false,
+                self.receiver.expr(),
+                self.code_hash.expr(),
+                self.code_hash.expr(),
+            )
+        });
+
+        // The execution starts at the beginning of the code of the root call,
+        // which is identified by the global counter before the operations of
+        // the transaction, with an empty stack and memory, with the intrinsic
+        // gas used and the rest of the gas left, and no log
+        cb.require_equal(
+            state_next.global_counter.expr(),
+            state_curr.global_counter.expr() + GC_DELTA.expr() + is_call,
+        );
+        cb.require_equal(
+            state_next.call_id.expr(),
+            state_curr.global_counter.expr() - 1.expr(),
+        );
+        cb.require_zero(state_next.program_counter.expr());
+        cb.require_equal(
+            state_next.stack_pointer.expr(),
+            STACK_POINTER_INIT.expr(),
+        );
+        cb.require_equal(
+            state_next.gas_counter.expr(),
+            self.intrinsic_gas.expr(),
+        );
+        cb.require_equal(
+            state_next.gas_left.expr(),
+            self.gas.expr() - self.intrinsic_gas.expr(),
+        );
+        cb.require_zero(state_next.memory_size.expr());
+        cb.require_equal(state_next.code_source.expr(), self.code_hash.expr());
+        cb.require_zero(state_next.log_id.expr());
+        // The root call starts with the 2 balance writes of the value
+        // transfer, which are undone when it reverts, unlike the purchase of
+        // the gas and the nonce increase
+        cb.require_equal(state_next.state_write_counter.expr(), 2.expr());
+
+        vec![cb.constraint(self.case_selector.expr(), "BeginTx")]
+    }
+
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: &mut CoreStateInstance,
+        step: &TxExecutionStep,
+    ) -> Result<(), Error> {
+        for (cell, value) in [
+            (&self.sender, &step.values[0]),
+            (&self.receiver, &step.values[1]),
+        ] {
+            cell.assign(
+                region,
+                offset,
+                Some(from_bytes::value(value.to_word()[..20].to_vec())),
+            )?;
+        }
+        let gas = step.values[3].to_word();
+        for (cell, value) in [
+            (&self.tx_id, &step.values[13]),
+            (&self.is_create, &step.values[14]),
+            (&self.nonce_prev, &step.values[2]),
+            (&self.gas, &step.values[3]),
+            (&self.intrinsic_gas, &step.values[4]),
+            (&self.call_data_gas_cost, &step.values[15]),
+            (&self.access_list_gas_cost, &step.values[16]),
+        ] {
+            cell.assign(
+                region,
+                offset,
+                Some(from_bytes::value(value.to_word()[..8].to_vec())),
+            )?;
+        }
+        self.gas_range_check.assign(
+            region,
+            offset,
+            from_bytes::value(gas[..8].to_vec()),
+        )?;
+
+        let gas_price = step.values[5].to_word();
+        let value = step.values[6].to_word();
+        let sender_balance_prev = step.values[7].to_word();
+        let sender_balance_after_gas = step.values[8].to_word();
+        let sender_balance = step.values[9].to_word();
+        let receiver_balance_prev = step.values[10].to_word();
+        let receiver_balance = step.values[11].to_word();
+        let code_hash = step.values[12].to_word();
+        for (word, value) in [
+            (&self.gas_price, gas_price),
+            (&self.value, value),
+            (&self.sender_balance_prev, sender_balance_prev),
+            (&self.sender_balance_after_gas, sender_balance_after_gas),
+            (&self.sender_balance, sender_balance),
+            (&self.receiver_balance_prev, receiver_balance_prev),
+            (&self.receiver_balance, receiver_balance),
+            (&self.code_hash, code_hash),
+        ] {
+            word.assign(region, offset, Some(value))?;
+        }
+
+        let one = BigUint::from(1u64).to_word();
+        self.gas_fee.assign(
+            region,
+            offset,
+            gas_price,
+            gas,
+            sender_balance_after_gas,
+        )?;
+        self.sender_transfer.assign(
+            region,
+            offset,
+            value,
+            one,
+            sender_balance,
+        )?;
+        self.receiver_transfer.assign(
+            region,
+            offset,
+            value,
+            one,
+            receiver_balance_prev,
+        )?;
+
+        // State transitions
+        let is_create = step.values[14].to_u64().unwrap();
+        state.call_id = state.global_counter - 1;
+        state.global_counter += GC_DELTA + (1 - is_create) as usize;
+        state.program_counter = 0;
+        state.stack_pointer = STACK_POINTER_INIT;
+        state.gas_counter = step.values[4].to_u64().unwrap();
+        state.gas_left = step.values[3].to_u64().unwrap() - state.gas_counter;
+        state.memory_size = 0;
+        state.code_source = code_hash;
+        state.log_id = 0;
+        state.state_write_counter = 2;
+
+        Ok(())
+    }
+}
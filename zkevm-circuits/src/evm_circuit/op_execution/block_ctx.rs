@@ -0,0 +1,390 @@
+use super::super::{
+    BlockContextField, Case, Cell, Constraint, CoreStateInstance,
+    ExecutionStep, Word,
+};
+use super::utils::{
+    self, and,
+    common_cases::{OutOfGasCase, StackOverflowCase, StackUnderflowCase},
+    constraint_builder::ConstraintBuilder,
+    from_bytes,
+    math_gadgets::{IsZeroGadget, LtGadget},
+    sum, StateTransition,
+};
+use super::{CaseAllocation, CaseConfig, OpExecutionState, OpGadget};
+use crate::impl_op_gadget;
+use crate::util::{Expr, ToWord};
+use bus_mapping::evm::{GasCost, OpcodeId};
+use halo2::{arithmetic::FieldExt, circuit::Region, plonk::Error};
+
+// Block numbers are limited to 64 bits, one more byte is needed to compare
+// them with the end of the window of the previous blocks
+const NUM_BYTES_NUMBER: usize = 8;
+const NUM_BYTES_WINDOW: usize = NUM_BYTES_NUMBER + 1;
+// BLOCKHASH only returns the hashes of the previous 256 blocks
+const NUM_HISTORY_HASHES: u64 = 256;
+
+static BLOCK_CTX_STATE_TRANSITION: StateTransition = StateTransition {
+    gc_delta: Some(1), // 1 stack push
+    pc_delta: Some(1),
+    sp_delta: Some(-1),
+    gas_delta: Some(GasCost::QUICK.as_u64()),
+    next_memory_size: None,
+};
+const NUM_PUSHED: usize = 1;
+
+impl_op_gadget!(
+    #set[COINBASE, TIMESTAMP, NUMBER, DIFFICULTY, GASLIMIT, CHAINID, BASEFEE]
+    BlockCtxGadget {
+        BlockCtxSuccessCase(),
+        StackOverflowCase(NUM_PUSHED),
+        OutOfGasCase(BLOCK_CTX_STATE_TRANSITION.gas_delta.unwrap()),
+    }
+);
+
+#[derive(Clone, Debug)]
+struct BlockCtxSuccessCase<F> {
+    case_selector: Cell<F>,
+    value: Word<F>,
+}
+
+impl<F: FieldExt> BlockCtxSuccessCase<F> {
+    pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::Success,
+        num_word: 1, // value
+        num_cell: 0,
+        will_halt: false,
+    };
+
+    pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+            value: alloc.words.pop().unwrap(),
+        }
+    }
+
+    pub(crate) fn constraint(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        state_next: &OpExecutionState<F>,
+        name: &'static str,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::default();
+
+        // The field of the block is tagged by the opcode, see
+        // `BlockContextField`
+        cb.block_lookup(
+            state_curr.opcode.expr() - OpcodeId::BLOCKHASH.expr(),
+            0.expr(),
+            self.value.expr(),
+        );
+
+        // Push the result on the stack
+        cb.stack_push(self.value.expr());
+
+        // State transitions
+        BLOCK_CTX_STATE_TRANSITION.constraints(&mut cb, state_curr, state_next);
+
+        // Generate the constraint
+        vec![cb.constraint(self.case_selector.expr(), name)]
+    }
+
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: &mut CoreStateInstance,
+        step: &ExecutionStep,
+    ) -> Result<(), Error> {
+        // Output
+        self.value
+            .assign(region, offset, Some(step.values[0].to_word()))?;
+
+        // State transitions
+        BLOCK_CTX_STATE_TRANSITION.assign(state);
+
+        Ok(())
+    }
+}
+
+static BLOCKHASH_STATE_TRANSITION: StateTransition = StateTransition {
+    gc_delta: Some(2), // 1 stack pop + 1 stack push
+    pc_delta: Some(1),
+    sp_delta: Some(0),
+    gas_delta: Some(GasCost::EXT.as_u64()),
+    next_memory_size: None,
+};
+const NUM_POPPED: usize = 1;
+
+impl_op_gadget!(
+    #set[BLOCKHASH]
+    BlockhashGadget {
+        BlockhashSuccessCase(),
+        StackUnderflowCase(NUM_POPPED),
+        OutOfGasCase(BLOCKHASH_STATE_TRANSITION.gas_delta.unwrap()),
+    }
+);
+
+#[derive(Clone, Debug)]
+struct BlockhashSuccessCase<F> {
+    case_selector: Cell<F>,
+    number: Word<F>,
+    current_number: Word<F>,
+    hash: Word<F>,
+    number_is_small: IsZeroGadget<F>,
+    is_previous: LtGadget<F, NUM_BYTES_NUMBER>,
+    is_recent: LtGadget<F, NUM_BYTES_WINDOW>,
+}
+
+impl<F: FieldExt> BlockhashSuccessCase<F> {
+    pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::Success,
+        num_word: 3, // number + current_number + hash
+        num_cell: IsZeroGadget::<F>::NUM_CELLS
+            + LtGadget::<F, NUM_BYTES_NUMBER>::NUM_CELLS
+            + LtGadget::<F, NUM_BYTES_WINDOW>::NUM_CELLS,
+        will_halt: false,
+    };
+
+    pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+            number: alloc.words.pop().unwrap(),
+            current_number: alloc.words.pop().unwrap(),
+            hash: alloc.words.pop().unwrap(),
+            number_is_small: IsZeroGadget::construct(alloc),
+            is_previous: LtGadget::construct(alloc),
+            is_recent: LtGadget::construct(alloc),
+        }
+    }
+
+    pub(crate) fn constraint(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        state_next: &OpExecutionState<F>,
+        name: &'static str,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::default();
+
+        // Pop the block number from the stack
+        cb.stack_pop(self.number.expr());
+
+        // The number of the current block fits in 64 bits
+        cb.block_lookup(
+            BlockContextField::Number.expr(),
+            0.expr(),
+            self.current_number.expr(),
+        );
+        cb.require_zero(sum::expr(
+            &self.current_number.cells[NUM_BYTES_NUMBER..],
+        ));
+        let current_number = from_bytes::expr(
+            self.current_number.cells[..NUM_BYTES_NUMBER].to_vec(),
+        );
+
+        // The hash is only known when the number is the one of the previous
+        // 256 blocks:
+        // current_number - 256 <= number < current_number
+        let number_is_small = self.number_is_small.constraints(
+            &mut cb,
+            sum::expr(&self.number.cells[NUM_BYTES_NUMBER..]),
+        );
+        let number =
+            from_bytes::expr(self.number.cells[..NUM_BYTES_NUMBER].to_vec());
+        let is_previous = self.is_previous.constraints(
+            &mut cb,
+            number.clone(),
+            current_number.clone(),
+        );
+        let is_recent = self.is_recent.constraints(
+            &mut cb,
+            current_number,
+            number.clone() + (NUM_HISTORY_HASHES + 1).expr(),
+        );
+        let is_in_window =
+            and::expr(vec![number_is_small, is_previous, is_recent]);
+
+        // Look up the hash of the block in the window, the hash is 0 outside
+        // of it
+        cb.condition(is_in_window.clone(), |cb| {
+            cb.block_lookup(
+                BlockContextField::BlockHash.expr(),
+                number,
+                self.hash.expr(),
+            );
+        });
+        cb.require_zero((1.expr() - is_in_window) * self.hash.expr());
+
+        // Push the hash on the stack
+        cb.stack_push(self.hash.expr());
+
+        // State transitions
+        BLOCKHASH_STATE_TRANSITION.constraints(&mut cb, state_curr, state_next);
+
+        // Generate the constraint
+        vec![cb.constraint(self.case_selector.expr(), name)]
+    }
+
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: &mut CoreStateInstance,
+        step: &ExecutionStep,
+    ) -> Result<(), Error> {
+        // Input, output, followed by the number of the current block
+        let number = step.values[0].to_word();
+        let current_number = step.values[2].to_word();
+        self.number.assign(region, offset, Some(number))?;
+        self.hash
+            .assign(region, offset, Some(step.values[1].to_word()))?;
+        self.current_number
+            .assign(region, offset, Some(current_number))?;
+
+        let current_number =
+            from_bytes::value::<F>(current_number[..NUM_BYTES_NUMBER].to_vec());
+        self.number_is_small.assign(
+            region,
+            offset,
+            sum::value(&number[NUM_BYTES_NUMBER..]),
+        )?;
+        let number =
+            from_bytes::value::<F>(number[..NUM_BYTES_NUMBER].to_vec());
+        self.is_previous
+            .assign(region, offset, number, current_number)?;
+        self.is_recent.assign(
+            region,
+            offset,
+            current_number,
+            number + F::from(NUM_HISTORY_HASHES + 1),
+        )?;
+
+        // State transitions
+        BLOCKHASH_STATE_TRANSITION.assign(state);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::{
+        test::test_circuit_from_block, BlockCircuit, WitnessError,
+    };
+    use crate::public_input_circuit::public_inputs;
+    use bus_mapping::{
+        bytecode,
+        bytecode::Bytecode,
+        circuit_input_builder::{Block, CircuitInputBuilder},
+        eth_types::H256,
+        mock,
+    };
+    use halo2::dev::MockProver;
+    use pairing::bn256::Fr as Fp;
+
+    fn block(code: &Bytecode) -> Block {
+        let block = mock::BlockData::new_single_tx_trace_code(code).unwrap();
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+        builder.block.prev_state_root = Some(H256::zero());
+        builder.block
+    }
+
+    fn test_block_ctx_gadget(code: Bytecode) {
+        let circuit = test_circuit_from_block::<Fp>(&block(&code));
+        let prover = MockProver::<Fp>::run(11, &circuit, vec![vec![]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    fn block_ctx_gadget_from_trace() {
+        test_block_ctx_gadget(bytecode! {
+            COINBASE
+            TIMESTAMP
+            NUMBER
+            DIFFICULTY
+            GASLIMIT
+            CHAINID
+            BASEFEE
+            STOP
+        });
+    }
+
+    #[test]
+    fn blockhash_gadget_from_trace() {
+        // The parent block, the oldest block of the window, one block out of
+        // it, the current block and numbers at the end of the 64 bits range
+        let number = mock::new_block().number.unwrap().as_u64();
+        test_block_ctx_gadget(bytecode! {
+            PUSH32(number - 1)
+            BLOCKHASH
+            PUSH32(number - 256)
+            BLOCKHASH
+            PUSH32(number - 257)
+            BLOCKHASH
+            PUSH32(number)
+            BLOCKHASH
+            PUSH32(1u128 << 64)
+            BLOCKHASH
+            PUSH32(u64::MAX)
+            BLOCKHASH
+            STOP
+        });
+    }
+
+    #[test]
+    fn block_ctx_gadget_with_public_inputs() {
+        const MAX_TXS: usize = 1;
+        let number = mock::new_block().number.unwrap().as_u64();
+        let block = block(&bytecode! {
+            TIMESTAMP
+            PUSH32(number - 1)
+            BLOCKHASH
+            STOP
+        });
+        let circuit = test_circuit_from_block::<Fp>(&block)
+            .with_public_inputs(&block, MAX_TXS)
+            .unwrap();
+
+        // The block table is the one committed to by the public inputs
+        let mut instance = public_inputs(&block, MAX_TXS).unwrap();
+        let prover =
+            MockProver::<Fp>::run(14, &circuit, vec![instance.clone()])
+                .unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        // The low half of the timestamp
+        instance[11] += Fp::from(1);
+        let prover =
+            MockProver::<Fp>::run(14, &circuit, vec![instance]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn block_circuit_with_public_inputs() {
+        const MAX_TXS: usize = 2;
+        let number = mock::new_block().number.unwrap().as_u64();
+        let block = block(&bytecode! {
+            TIMESTAMP
+            PUSH32(number - 1)
+            BLOCKHASH
+            STOP
+        });
+
+        // The steps after the ones of the tx aren't executing
+        let circuit = BlockCircuit::<Fp, MAX_TXS, 16>::new(&block).unwrap();
+        let instance = public_inputs(&block, MAX_TXS).unwrap();
+        let prover =
+            MockProver::<Fp>::run(18, &circuit, vec![instance]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        // The 3 steps of the tx up to its STOP and its begin and end steps
+        // don't fit in 4 steps
+        assert_eq!(
+            BlockCircuit::<Fp, MAX_TXS, 4>::new(&block).err(),
+            Some(WitnessError::TooManySteps(4))
+        );
+    }
+}
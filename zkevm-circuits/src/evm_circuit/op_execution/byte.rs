@@ -15,7 +15,6 @@ use array_init::array_init;
 use bus_mapping::evm::{GasCost, OpcodeId};
 use halo2::plonk::Error;
 use halo2::{arithmetic::FieldExt, circuit::Region};
-use std::convert::TryInto;
 
 static STATE_TRANSITION: StateTransition = StateTransition {
     gc_delta: Some(3), // 2 stack pops + 1 stack push
@@ -169,7 +168,8 @@ mod test {
         ($execution_steps:expr, $operations:expr, $result:expr) => {{
             let circuit =
                 TestCircuit::<Fp>::new($execution_steps, $operations, false);
-            let prover = MockProver::<Fp>::run(11, &circuit, vec![]).unwrap();
+            let prover =
+                MockProver::<Fp>::run(11, &circuit, vec![vec![]]).unwrap();
             assert_eq!(prover.verify(), $result);
         }};
     }
@@ -211,6 +211,8 @@ mod test {
                         Fp::from(1023),
                         compress(value.clone()),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
                 Operation {
@@ -222,6 +224,8 @@ mod test {
                         Fp::from(1022),
                         compress(index.clone()),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
                 Operation {
@@ -233,6 +237,8 @@ mod test {
                         Fp::from(1022),
                         compress(index),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
                 Operation {
@@ -244,6 +250,8 @@ mod test {
                         Fp::from(1023),
                         compress(value),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
                 Operation {
@@ -255,6 +263,8 @@ mod test {
                         Fp::from(1023),
                         compress(result),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
             ],
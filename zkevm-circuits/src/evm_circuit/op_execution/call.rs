@@ -0,0 +1,676 @@
+use super::super::{Case, Cell, Constraint, ExecutionStep, Word};
+use super::utils::{
+    self,
+    common_cases::StackUnderflowCase,
+    constraint_builder::ConstraintBuilder,
+    from_bytes,
+    math_gadgets::{ConstantDivisionGadget, IsZeroGadget, LtGadget},
+    memory_gadgets::{self, address_low, MemoryExpansionGadget},
+    select, sum, StateTransitionExpressions,
+};
+use super::{
+    CaseAllocation, CaseConfig, CoreStateInstance, OpExecutionState, OpGadget,
+};
+use crate::evm_circuit::param::MAX_GAS_SIZE_IN_BYTES;
+use crate::impl_op_gadget;
+use crate::util::{Expr, ToWord};
+use bus_mapping::{
+    evm::{GasCost, OpcodeId},
+    operation::CallContextField,
+};
+use halo2::plonk::Error;
+use halo2::{arithmetic::FieldExt, circuit::Region, plonk::Expression};
+use std::convert::TryInto;
+
+// Number of call context reads of the caller, call context writes saving the
+// state of the caller, and call context writes setting up the callee.
+pub(super) const NUM_CALLER_READS: usize = 6;
+pub(super) const NUM_CALLER_WRITES: usize = 6;
+pub(super) const NUM_CALLEE_WRITES: usize = 14;
+
+// TODO: Add the cases where the callee doesn't execute any code: calls to
+// accounts without code or to precompiled contracts, and calls failing due to
+// the depth limit or an insufficient balance.  Add the out of gas case, the
+// write protection case of a CALL with value in a static call, and the balance
+// transfer of a CALL with value, which is rejected until then.
+impl_op_gadget!(
+    #set[CALL, CALLCODE]
+    CallGadget {
+        CallSuccessCase(true),
+        StackUnderflowCase(7),
+    }
+);
+
+impl_op_gadget!(
+    #set[DELEGATECALL, STATICCALL]
+    DelegateCallGadget {
+        CallSuccessCase(false),
+        StackUnderflowCase(6),
+    }
+);
+
+// Address past the end of a memory region, which is zero for an empty region
+// that doesn't expand the memory
+pub(super) fn memory_region_end<F: FieldExt>(
+    cb: &mut ConstraintBuilder<F>,
+    offset: &Word<F>,
+    length: &Word<F>,
+    length_is_zero: &IsZeroGadget<F>,
+) -> Expression<F> {
+    memory_gadgets::require_address_in_range(cb, length);
+    let length = address_low::expr(length);
+    let length_is_zero = length_is_zero.constraints(cb, length.clone());
+    cb.condition(1.expr() - length_is_zero.clone(), |cb| {
+        memory_gadgets::require_address_in_range(cb, offset);
+    });
+    (1.expr() - length_is_zero) * (address_low::expr(offset) + length)
+}
+
+#[derive(Clone, Debug)]
+struct CallSuccessCase<F> {
+    case_selector: Cell<F>,
+    // CALL and CALLCODE pop the value sent to the callee, while DELEGATECALL
+    // and STATICCALL don't.
+    has_value: bool,
+    gas: Word<F>,
+    to: Word<F>,
+    value: Word<F>,
+    args_offset: Word<F>,
+    args_length: Word<F>,
+    ret_offset: Word<F>,
+    ret_length: Word<F>,
+    is_success: Cell<F>,
+    // Context of the caller
+    tx_id: Cell<F>,
+    depth: Cell<F>,
+    caller_caller_address: Cell<F>,
+    caller_address: Cell<F>,
+    caller_value: Word<F>,
+    caller_is_static: Cell<F>,
+    // Hash of the code executed by the callee
+    callee_code_hash: Word<F>,
+    // Whether the code address was already accessed by the tx (EIP-2929)
+    is_warm: Cell<F>,
+    value_is_zero: IsZeroGadget<F>,
+    args_length_is_zero: IsZeroGadget<F>,
+    ret_length_is_zero: IsZeroGadget<F>,
+    args_memory_expansion: MemoryExpansionGadget<F, MAX_GAS_SIZE_IN_BYTES>,
+    ret_memory_expansion: MemoryExpansionGadget<F, MAX_GAS_SIZE_IN_BYTES>,
+    // Gas given to the callee: all but one 64th of the gas left after paying
+    // for the call (EIP-150), capped by the requested gas
+    one_64th_gas: ConstantDivisionGadget<F, MAX_GAS_SIZE_IN_BYTES>,
+    gas_is_u64: IsZeroGadget<F>,
+    gas_lt_all_but_one_64th: LtGadget<F, MAX_GAS_SIZE_IN_BYTES>,
+}
+
+impl<F: FieldExt> CallSuccessCase<F> {
+    pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::Success,
+        // gas + to + value + args_offset + args_length + ret_offset +
+        // ret_length + caller_value + callee_code_hash
+        num_word: 9
+            + 2 * MemoryExpansionGadget::<F, MAX_GAS_SIZE_IN_BYTES>::NUM_WORDS
+            + ConstantDivisionGadget::<F, MAX_GAS_SIZE_IN_BYTES>::NUM_WORDS,
+        // is_success + tx_id + depth + caller_caller_address +
+        // caller_address + caller_is_static + is_warm
+        num_cell: 7
+            + 4 * IsZeroGadget::<F>::NUM_CELLS
+            + 2 * MemoryExpansionGadget::<F, MAX_GAS_SIZE_IN_BYTES>::NUM_CELLS
+            + ConstantDivisionGadget::<F, MAX_GAS_SIZE_IN_BYTES>::NUM_CELLS
+            + LtGadget::<F, MAX_GAS_SIZE_IN_BYTES>::NUM_CELLS,
+        will_halt: false,
+    };
+
+    pub(crate) fn construct(
+        alloc: &mut CaseAllocation<F>,
+        has_value: bool,
+    ) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+            has_value,
+            gas: alloc.words.pop().unwrap(),
+            to: alloc.words.pop().unwrap(),
+            value: alloc.words.pop().unwrap(),
+            args_offset: alloc.words.pop().unwrap(),
+            args_length: alloc.words.pop().unwrap(),
+            ret_offset: alloc.words.pop().unwrap(),
+            ret_length: alloc.words.pop().unwrap(),
+            caller_value: alloc.words.pop().unwrap(),
+            callee_code_hash: alloc.words.pop().unwrap(),
+            is_success: alloc.cells.pop().unwrap(),
+            tx_id: alloc.cells.pop().unwrap(),
+            depth: alloc.cells.pop().unwrap(),
+            caller_caller_address: alloc.cells.pop().unwrap(),
+            caller_address: alloc.cells.pop().unwrap(),
+            caller_is_static: alloc.cells.pop().unwrap(),
+            is_warm: alloc.cells.pop().unwrap(),
+            value_is_zero: IsZeroGadget::construct(alloc),
+            args_length_is_zero: IsZeroGadget::construct(alloc),
+            ret_length_is_zero: IsZeroGadget::construct(alloc),
+            args_memory_expansion: MemoryExpansionGadget::construct(alloc),
+            ret_memory_expansion: MemoryExpansionGadget::construct(alloc),
+            one_64th_gas: ConstantDivisionGadget::construct(alloc, 64),
+            gas_is_u64: IsZeroGadget::construct(alloc),
+            gas_lt_all_but_one_64th: LtGadget::construct(alloc),
+        }
+    }
+
+    // Arguments popped from the stack
+    fn args(&self) -> Vec<&Word<F>> {
+        let mut args = vec![&self.gas, &self.to];
+        if self.has_value {
+            args.push(&self.value);
+        }
+        args.extend([
+            &self.args_offset,
+            &self.args_length,
+            &self.ret_offset,
+            &self.ret_length,
+        ]);
+        args
+    }
+
+    fn gc_delta(&self) -> usize {
+        self.args().len()
+            + 3
+            + NUM_CALLER_READS
+            + NUM_CALLER_WRITES
+            + NUM_CALLEE_WRITES
+    }
+
+    pub(crate) fn constraint(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        state_next: &OpExecutionState<F>,
+        name: &'static str,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::default();
+
+        // The success of the call is set up in the context of the callee,
+        // which reads it back when it returns.
+        // TODO: Read it back when the callee halts with STOP or an error.
+        cb.require_boolean(self.is_success.expr());
+        cb.require_boolean(self.caller_is_static.expr());
+        cb.require_boolean(self.is_warm.expr());
+
+        // Pop the arguments and push the success of the call
+        for arg in self.args() {
+            cb.stack_pop(arg.expr());
+        }
+        cb.stack_push(self.is_success.expr());
+
+        // The code address is warm after the call (EIP-2929)
+        let to = from_bytes::expr(self.to.cells[..20].to_vec());
+        cb.tx_access_list_account_write(
+            self.tx_id.expr(),
+            to.clone(),
+            1.expr(),
+            self.is_warm.expr(),
+        );
+
+        // The callee runs the code of the code address
+        cb.account_code_hash_lookup(
+            false,
+            to.clone(),
+            self.callee_code_hash.expr(),
+            self.callee_code_hash.expr(),
+        );
+
+        // Read the context of the caller
+        for (field, value) in [
+            (CallContextField::TxId, self.tx_id.expr()),
+            (CallContextField::Depth, self.depth.expr()),
+            (
+                CallContextField::CallerAddress,
+                self.caller_caller_address.expr(),
+            ),
+            (CallContextField::CalleeAddress, self.caller_address.expr()),
+            (CallContextField::Value, self.caller_value.expr()),
+            (CallContextField::IsStatic, self.caller_is_static.expr()),
+        ] {
+            cb.call_context_lookup(
+                false,
+                state_curr.call_id.expr(),
+                field,
+                value,
+            );
+        }
+
+        // The memory is expanded for the arguments and for the returned bytes,
+        // unless their regions are empty
+        let args_end = memory_region_end(
+            &mut cb,
+            &self.args_offset,
+            &self.args_length,
+            &self.args_length_is_zero,
+        );
+        let ret_end = memory_region_end(
+            &mut cb,
+            &self.ret_offset,
+            &self.ret_length,
+            &self.ret_length_is_zero,
+        );
+        let (args_memory_size, args_memory_cost) = self
+            .args_memory_expansion
+            .constraints(&mut cb, state_curr.memory_size.expr(), args_end);
+        let (next_memory_size, ret_memory_cost) = self
+            .ret_memory_expansion
+            .constraints(&mut cb, args_memory_size, ret_end);
+
+        // A non-zero value costs CALL_WITH_VALUE, and gives the callee the
+        // CALL_STIPEND for free.  CALLCODE sends the value to the caller
+        // itself, so only CALL transfers it.
+        let has_value = if self.has_value {
+            let value_is_zero = self
+                .value_is_zero
+                .constraints(&mut cb, sum::expr(&self.value.cells));
+            let is_call =
+                1.expr() - (state_curr.opcode.expr() - OpcodeId::CALL.expr());
+            cb.require_zero(is_call * (1.expr() - value_is_zero.clone()));
+            1.expr() - value_is_zero
+        } else {
+            0.expr()
+        };
+
+        // The caller pays for the memory expansion, the access to the code
+        // address and the value before giving gas to the callee, which
+        // requires the gas left to cover these costs.
+        let gas_left = state_curr.gas_left.expr();
+        let base_cost = args_memory_cost
+            + ret_memory_cost
+            + select::expr(
+                self.is_warm.expr(),
+                GasCost::WARM_STORAGE_READ_COST.expr(),
+                GasCost::COLD_ACCOUNT_ACCESS_COST.expr(),
+            )
+            + has_value.clone() * GasCost::CALL_WITH_VALUE.expr();
+
+        // The callee is given all but one 64th of the remaining gas
+        // (EIP-150), or the requested gas when it's less
+        let remaining_gas = gas_left.clone() - base_cost.clone();
+        let (one_64th_gas, _) = self
+            .one_64th_gas
+            .constraints(&mut cb, remaining_gas.clone());
+        let all_but_one_64th_gas = remaining_gas - one_64th_gas;
+        let gas_is_u64 = self
+            .gas_is_u64
+            .constraints(&mut cb, sum::expr(&self.gas.cells[8..32]));
+        let requested_gas = from_bytes::expr(self.gas.cells[..8].to_vec());
+        let gas_lt_all_but_one_64th = self.gas_lt_all_but_one_64th.constraints(
+            &mut cb,
+            requested_gas.clone(),
+            all_but_one_64th_gas.clone(),
+        );
+        let callee_gas = select::expr(
+            gas_is_u64 * gas_lt_all_but_one_64th,
+            requested_gas,
+            all_but_one_64th_gas,
+        );
+        let gas_cost = base_cost + callee_gas.clone();
+        let callee_gas = callee_gas + has_value * GasCost::CALL_STIPEND.expr();
+
+        // Save the state of the caller, which resumes after the CALL with
+        // the success pushed on the stack, the gas left after paying for the
+        // call, the expanded memory and its code. Its state writes include
+        // the access list write of the code address, which stays when the
+        // callee reverts.
+        for (field, value) in [
+            (
+                CallContextField::ProgramCounter,
+                state_curr.program_counter.expr() + 1.expr(),
+            ),
+            (
+                CallContextField::StackPointer,
+                state_curr.stack_pointer.expr() + cb.stack_offset.expr(),
+            ),
+            (CallContextField::GasLeft, gas_left - gas_cost.clone()),
+            (CallContextField::MemorySize, next_memory_size * 32.expr()),
+            (
+                CallContextField::StateWriteCounter,
+                state_curr.state_write_counter.expr() + 1.expr(),
+            ),
+            (CallContextField::CodeHash, state_curr.code_source.expr()),
+        ] {
+            cb.call_context_lookup(
+                true,
+                state_curr.call_id.expr(),
+                field,
+                value,
+            );
+        }
+
+        // The context of the callee depends on the kind of call:
+        // - CALL: executes the code at `to` in the account `to`
+        // - CALLCODE: executes the code at `to` in the account of the caller
+        // - DELEGATECALL: like CALLCODE, keeping the caller and value of the
+        //   caller
+        // - STATICCALL: like CALL without value, forbidding state changes
+        let (callee_caller_address, callee_address, callee_value, is_static) =
+            if self.has_value {
+                let is_callcode =
+                    state_curr.opcode.expr() - OpcodeId::CALL.expr();
+                (
+                    self.caller_address.expr(),
+                    select::expr(
+                        is_callcode,
+                        self.caller_address.expr(),
+                        to.clone(),
+                    ),
+                    self.value.expr(),
+                    self.caller_is_static.expr(),
+                )
+            } else {
+                let is_staticcall = (state_curr.opcode.expr()
+                    - OpcodeId::DELEGATECALL.expr())
+                    * F::from(
+                        (OpcodeId::STATICCALL.as_u8()
+                            - OpcodeId::DELEGATECALL.as_u8())
+                            as u64,
+                    )
+                    .invert()
+                    .unwrap();
+                (
+                    select::expr(
+                        is_staticcall.clone(),
+                        self.caller_address.expr(),
+                        self.caller_caller_address.expr(),
+                    ),
+                    select::expr(
+                        is_staticcall.clone(),
+                        to.clone(),
+                        self.caller_address.expr(),
+                    ),
+                    select::expr(
+                        is_staticcall.clone(),
+                        0.expr(),
+                        self.caller_value.expr(),
+                    ),
+                    select::expr(
+                        is_staticcall,
+                        1.expr(),
+                        self.caller_is_static.expr(),
+                    ),
+                )
+            };
+
+        // Set up the context of the callee, which is identified by the global
+        // counter of its first step.
+        let gc_delta = self.gc_delta();
+        let callee_id =
+            state_curr.global_counter.expr() + (gc_delta - 1).expr();
+        let from_u64_word =
+            |word: &Word<F>| from_bytes::expr(word.cells[..8].to_vec());
+        for (field, value) in [
+            (CallContextField::CallerId, state_curr.call_id.expr()),
+            (CallContextField::TxId, self.tx_id.expr()),
+            (CallContextField::Depth, self.depth.expr() + 1.expr()),
+            (CallContextField::CallerAddress, callee_caller_address),
+            (CallContextField::CalleeAddress, callee_address),
+            (CallContextField::CodeAddress, to),
+            (
+                CallContextField::CallDataOffset,
+                from_u64_word(&self.args_offset),
+            ),
+            (
+                CallContextField::CallDataLength,
+                from_u64_word(&self.args_length),
+            ),
+            (
+                CallContextField::ReturnDataOffset,
+                from_u64_word(&self.ret_offset),
+            ),
+            (
+                CallContextField::ReturnDataLength,
+                from_u64_word(&self.ret_length),
+            ),
+            (CallContextField::Value, callee_value),
+            (CallContextField::IsStatic, is_static),
+            (CallContextField::IsSuccess, self.is_success.expr()),
+            (CallContextField::Gas, callee_gas.clone()),
+        ] {
+            cb.call_context_lookup(true, callee_id.clone(), field, value);
+        }
+
+        // State transitions to the first step of the callee
+        cb.require_equal(state_next.call_id.expr(), callee_id);
+        let st = StateTransitionExpressions {
+            gc_delta: Some(gc_delta.expr()),
+            pc_delta: Some(0.expr() - state_curr.program_counter.expr()),
+            sp_delta: Some(1024.expr() - state_curr.stack_pointer.expr()),
+            gas_delta: Some(gas_cost),
+            next_memory_size: Some(0.expr()),
+            next_code_source: Some(self.callee_code_hash.expr()),
+            log_id_delta: None,
+            next_gas_left: Some(callee_gas),
+            // The callee counts its own state writes
+            next_state_write_counter: Some(0.expr()),
+        };
+        st.constraints(&mut cb, state_curr, state_next);
+
+        // Generate the constraint
+        vec![cb.constraint(self.case_selector.expr(), name)]
+    }
+
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: &mut CoreStateInstance,
+        step: &ExecutionStep,
+    ) -> Result<(), Error> {
+        // Inputs and output
+        let args = self.args();
+        for (word, value) in args.iter().zip(step.values.iter()) {
+            word.assign(region, offset, Some(value.to_word()))?;
+        }
+        if !self.has_value {
+            self.value.assign(region, offset, Some([0u8; 32]))?;
+        }
+        let is_success = step.values[args.len()] == 1u64.into();
+        self.is_success.assign(
+            region,
+            offset,
+            Some(F::from(is_success as u64)),
+        )?;
+
+        // Values of the call context operations, followed by the gas cost of
+        // the step, the code hash of the callee and whether the code address
+        // was warm
+        let values = &step.values[args.len() + 1..];
+        let value_u64 =
+            |idx: usize| -> u64 { values[idx].clone().try_into().unwrap() };
+        let value_address = |idx: usize| {
+            from_bytes::value(values[idx].to_word()[..20].to_vec())
+        };
+        self.tx_id
+            .assign(region, offset, Some(F::from(value_u64(0))))?;
+        self.depth
+            .assign(region, offset, Some(F::from(value_u64(1))))?;
+        self.caller_caller_address.assign(
+            region,
+            offset,
+            Some(value_address(2)),
+        )?;
+        self.caller_address
+            .assign(region, offset, Some(value_address(3)))?;
+        self.caller_value
+            .assign(region, offset, Some(values[4].to_word()))?;
+        self.caller_is_static.assign(
+            region,
+            offset,
+            Some(F::from(value_u64(5))),
+        )?;
+        let num_context_ops =
+            NUM_CALLER_READS + NUM_CALLER_WRITES + NUM_CALLEE_WRITES;
+        let gas_cost = value_u64(num_context_ops);
+        let callee_code_hash = values[num_context_ops + 1].to_word();
+        self.callee_code_hash
+            .assign(region, offset, Some(callee_code_hash))?;
+        let is_warm = value_u64(num_context_ops + 2);
+        self.is_warm
+            .assign(region, offset, Some(F::from(is_warm)))?;
+
+        // Memory expansion of the regions of the arguments and of the
+        // returned bytes
+        let regions = step.values[args.len() - 4..args.len()]
+            .iter()
+            .map(|value| value.to_word())
+            .collect::<Vec<_>>();
+        let mut memory_size = state.memory_size as u64;
+        let mut memory_cost = 0;
+        for (region_idx, (length_is_zero, memory_expansion)) in [
+            (&self.args_length_is_zero, &self.args_memory_expansion),
+            (&self.ret_length_is_zero, &self.ret_memory_expansion),
+        ]
+        .iter()
+        .enumerate()
+        {
+            let memory_offset =
+                address_low::value::<F>(regions[2 * region_idx]);
+            let length = address_low::value::<F>(regions[2 * region_idx + 1]);
+            let length_is_zero =
+                length_is_zero.assign(region, offset, F::from(length))?;
+            let address = if length_is_zero == F::one() {
+                0
+            } else {
+                memory_offset + length
+            };
+            let (next_memory_size, cost) = memory_expansion.assign(
+                region,
+                offset,
+                memory_size,
+                address,
+            )?;
+            memory_size = next_memory_size;
+            memory_cost += cost as u64;
+        }
+
+        // Gas given to the callee
+        let value = if self.has_value {
+            step.values[2].to_word()
+        } else {
+            [0u8; 32]
+        };
+        let value_is_zero = self.value_is_zero.assign(
+            region,
+            offset,
+            sum::value(&value.to_vec()),
+        )? == F::one();
+        let base_cost = memory_cost
+            + if is_warm == 1 {
+                GasCost::WARM_STORAGE_READ_COST.as_u64()
+            } else {
+                GasCost::COLD_ACCOUNT_ACCESS_COST.as_u64()
+            }
+            + if value_is_zero {
+                0
+            } else {
+                GasCost::CALL_WITH_VALUE.as_u64()
+            };
+        let remaining_gas = state.gas_left - base_cost;
+        let (one_64th_gas, _) =
+            self.one_64th_gas
+                .assign(region, offset, remaining_gas as u128)?;
+        let all_but_one_64th_gas = remaining_gas - one_64th_gas as u64;
+        let gas = step.values[0].to_word();
+        let gas_is_u64 = self.gas_is_u64.assign(
+            region,
+            offset,
+            sum::value(&gas[8..32].to_vec()),
+        )? == F::one();
+        let requested_gas = u64::from_le_bytes(gas[..8].try_into().unwrap());
+        let (gas_lt_all_but_one_64th, _) =
+            self.gas_lt_all_but_one_64th.assign(
+                region,
+                offset,
+                F::from(requested_gas),
+                F::from(all_but_one_64th_gas),
+            )?;
+        let callee_gas = if gas_is_u64 && gas_lt_all_but_one_64th == F::one() {
+            requested_gas
+        } else {
+            all_but_one_64th_gas
+        };
+
+        // State transitions to the first step of the callee
+        state.global_counter += self.gc_delta();
+        state.call_id = state.global_counter - 1;
+        state.program_counter = 0;
+        state.stack_pointer = 1024;
+        state.gas_counter += gas_cost;
+        state.gas_left = callee_gas
+            + if value_is_zero {
+                0
+            } else {
+                GasCost::CALL_STIPEND.as_u64()
+            };
+        state.memory_size = 0;
+        state.code_source = callee_code_hash;
+        state.state_write_counter = 0;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::test::test_circuit_from_block;
+    use bus_mapping::{
+        bytecode, bytecode::Bytecode,
+        circuit_input_builder::CircuitInputBuilder, evm::OpcodeId, mock,
+    };
+    use halo2::dev::MockProver;
+    use pairing::bn256::Fr as Fp;
+
+    fn test_call_gadget(code: Bytecode) {
+        // The callee halts at once with STOP, which resumes the caller
+        let callee_code = bytecode! {
+            STOP
+        };
+        let block =
+            mock::BlockData::new_single_tx_trace_code_2(&code, &callee_code)
+                .unwrap();
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let circuit = test_circuit_from_block::<Fp>(&builder.block);
+        let prover = MockProver::<Fp>::run(11, &circuit, vec![vec![]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    fn call_gadget_from_trace() {
+        for opcode in [OpcodeId::CALL, OpcodeId::CALLCODE] {
+            let mut code = bytecode! {
+                PUSH1(0x20) // retLength
+                PUSH1(0x00) // retOffset
+                PUSH1(0x10) // argsLength
+                PUSH1(0x00) // argsOffset
+                PUSH1(0x00) // value
+                PUSH2(0x123) // addr
+                PUSH2(0x1000) // gas
+            };
+            code.write_op(opcode);
+            test_call_gadget(code);
+        }
+    }
+
+    #[test]
+    fn delegatecall_gadget_from_trace() {
+        for opcode in [OpcodeId::DELEGATECALL, OpcodeId::STATICCALL] {
+            let mut code = bytecode! {
+                PUSH1(0x20) // retLength
+                PUSH1(0x00) // retOffset
+                PUSH1(0x10) // argsLength
+                PUSH1(0x00) // argsOffset
+                PUSH2(0x123) // addr
+                PUSH2(0x1000) // gas
+            };
+            code.write_op(opcode);
+            test_call_gadget(code);
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use super::super::{Case, Cell, Constraint, ExecutionStep, Word};
+use super::utils::{
+    self,
+    common_cases::{OutOfGasCase, StackOverflowCase},
+    constraint_builder::ConstraintBuilder,
+    from_bytes, sum, StateTransition,
+};
+use super::{
+    CaseAllocation, CaseConfig, CoreStateInstance, OpExecutionState, OpGadget,
+};
+use crate::impl_op_gadget;
+use crate::util::{Expr, ToWord};
+use bus_mapping::{
+    evm::{GasCost, OpcodeId},
+    operation::CallContextField,
+};
+use halo2::plonk::Error;
+use halo2::{arithmetic::FieldExt, circuit::Region};
+
+static STATE_TRANSITION: StateTransition = StateTransition {
+    gc_delta: Some(2), // 1 call context read + 1 stack push
+    pc_delta: Some(1),
+    sp_delta: Some(-1),
+    gas_delta: Some(GasCost::QUICK.as_u64()),
+    next_memory_size: None,
+};
+const NUM_PUSHED: usize = 1;
+
+impl_op_gadget!(
+    #set[CALLER]
+    CallerGadget {
+        CallCtxSuccessCase(CallContextField::CallerAddress),
+        StackOverflowCase(NUM_PUSHED),
+        OutOfGasCase(STATE_TRANSITION.gas_delta.unwrap()),
+    }
+);
+
+impl_op_gadget!(
+    #set[CALLVALUE]
+    CallvalueGadget {
+        CallCtxSuccessCase(CallContextField::Value),
+        StackOverflowCase(NUM_PUSHED),
+        OutOfGasCase(STATE_TRANSITION.gas_delta.unwrap()),
+    }
+);
+
+impl_op_gadget!(
+    #set[ADDRESS]
+    AddressGadget {
+        CallCtxSuccessCase(CallContextField::CalleeAddress),
+        StackOverflowCase(NUM_PUSHED),
+        OutOfGasCase(STATE_TRANSITION.gas_delta.unwrap()),
+    }
+);
+
+#[derive(Clone, Debug)]
+struct CallCtxSuccessCase<F> {
+    case_selector: Cell<F>,
+    // Field of the call context pushed on the stack
+    field: CallContextField,
+    value: Word<F>,
+}
+
+impl<F: FieldExt> CallCtxSuccessCase<F> {
+    pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::Success,
+        num_word: 1, // value
+        num_cell: 0,
+        will_halt: false,
+    };
+
+    pub(crate) fn construct(
+        alloc: &mut CaseAllocation<F>,
+        field: CallContextField,
+    ) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+            field,
+            value: alloc.words.pop().unwrap(),
+        }
+    }
+
+    pub(crate) fn constraint(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        state_next: &OpExecutionState<F>,
+        name: &'static str,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::default();
+
+        // The call context keeps the value as a word, and the addresses as
+        // their 20 bytes
+        let value = match self.field {
+            CallContextField::Value => self.value.expr(),
+            _ => {
+                cb.require_zero(sum::expr(&self.value.cells[20..]));
+                from_bytes::expr(self.value.cells[..20].to_vec())
+            }
+        };
+
+        // Read the field from the call context and push it on the stack
+        cb.call_context_lookup(
+            false,
+            state_curr.call_id.expr(),
+            self.field,
+            value,
+        );
+        cb.stack_push(self.value.expr());
+
+        // State transitions
+        STATE_TRANSITION.constraints(&mut cb, state_curr, state_next);
+
+        // Generate the constraint
+        vec![cb.constraint(self.case_selector.expr(), name)]
+    }
+
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: &mut CoreStateInstance,
+        step: &ExecutionStep,
+    ) -> Result<(), Error> {
+        // Output
+        self.value
+            .assign(region, offset, Some(step.values[0].to_word()))?;
+
+        // State transitions
+        STATE_TRANSITION.assign(state);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::test::test_circuit_from_block;
+    use bus_mapping::{
+        bytecode, circuit_input_builder::CircuitInputBuilder, mock,
+    };
+    use halo2::dev::MockProver;
+    use pairing::bn256::Fr as Fp;
+
+    #[test]
+    fn call_ctx_gadget_from_trace() {
+        let code = bytecode! {
+            CALLER
+            CALLVALUE
+            ADDRESS
+            STOP
+        };
+        let block = mock::BlockData::new_single_tx_trace_code(&code).unwrap();
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let circuit = test_circuit_from_block::<Fp>(&builder.block);
+        let prover = MockProver::<Fp>::run(11, &circuit, vec![vec![]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+}
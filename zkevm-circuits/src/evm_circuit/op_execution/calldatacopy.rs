@@ -0,0 +1,473 @@
+use super::super::{Case, Cell, Constraint, ExecutionStep, Word};
+use super::utils::{
+    self,
+    common_cases::{MemoryCopyOutOfGasCase, StackUnderflowCase},
+    constraint_builder::ConstraintBuilder,
+    math_gadgets::{IsEqualGadget, IsZeroGadget},
+    memory_gadgets::{
+        self, address_high, address_low, BufferReaderGadget,
+        MemoryExpansionGadget, MemorySizeGadget,
+    },
+    sum, StateTransition, StateTransitionExpressions,
+};
+use super::{
+    CaseAllocation, CaseConfig, CoreStateInstance, OpExecutionState, OpGadget,
+};
+use crate::evm_circuit::param::{MAX_COPY_BYTES, MAX_GAS_SIZE_IN_BYTES};
+use crate::impl_op_gadget;
+use crate::util::{Expr, ToWord};
+use array_init::array_init;
+use bus_mapping::{
+    evm::{GasCost, OpcodeId},
+    operation::CallContextField,
+};
+use halo2::plonk::Error;
+use halo2::{arithmetic::FieldExt, circuit::Region};
+use std::convert::TryInto;
+
+static STATE_TRANSITION: StateTransition = StateTransition {
+    gc_delta: None, // GC_DELTA + the memory reads and writes of the copy
+    pc_delta: Some(1),
+    sp_delta: Some(3),
+    gas_delta: None, // GAS + GasCost::COPY * words + memory_cost
+    next_memory_size: None, // next_memory_size
+};
+const GAS: GasCost = GasCost::FASTEST;
+// 3 stack pops + 5 call context reads
+const GC_DELTA: usize = 8;
+const NUM_POPPED: usize = 3;
+
+impl_op_gadget!(
+    #set[CALLDATACOPY]
+    CalldatacopyGadget {
+        CalldatacopySuccessCase(),
+        StackUnderflowCase(NUM_POPPED),
+        MemoryCopyOutOfGasCase(),
+    }
+);
+
+#[derive(Clone, Debug)]
+struct CalldatacopySuccessCase<F> {
+    case_selector: Cell<F>,
+    memory_offset: Word<F>,
+    data_offset: Word<F>,
+    length: Word<F>,
+    tx_id: Cell<F>,
+    depth: Cell<F>,
+    caller_id: Cell<F>,
+    call_data_offset: Cell<F>,
+    call_data_length: Cell<F>,
+    copy_flags: [Cell<F>; MAX_COPY_BYTES],
+    bytes: [Cell<F>; MAX_COPY_BYTES],
+    is_root: IsEqualGadget<F>,
+    length_is_zero: IsZeroGadget<F>,
+    buffer_reader: BufferReaderGadget<F, MAX_COPY_BYTES>,
+    copy_words: MemorySizeGadget<F>,
+    memory_expansion: MemoryExpansionGadget<F, MAX_GAS_SIZE_IN_BYTES>,
+}
+
+impl<F: FieldExt> CalldatacopySuccessCase<F> {
+    pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::Success,
+        num_word: 3, // memory_offset + data_offset + length
+        num_cell: 5 // tx_id + depth + caller_id + call data offset and length
+            + 2 * MAX_COPY_BYTES // copy_flags + bytes
+            + IsEqualGadget::<F>::NUM_CELLS
+            + IsZeroGadget::<F>::NUM_CELLS
+            + BufferReaderGadget::<F, MAX_COPY_BYTES>::NUM_CELLS
+            + MemorySizeGadget::<F>::NUM_CELLS
+            + MemoryExpansionGadget::<F, MAX_GAS_SIZE_IN_BYTES>::NUM_CELLS,
+        will_halt: false,
+    };
+
+    pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+            memory_offset: alloc.words.pop().unwrap(),
+            data_offset: alloc.words.pop().unwrap(),
+            length: alloc.words.pop().unwrap(),
+            tx_id: alloc.cells.pop().unwrap(),
+            depth: alloc.cells.pop().unwrap(),
+            caller_id: alloc.cells.pop().unwrap(),
+            call_data_offset: alloc.cells.pop().unwrap(),
+            call_data_length: alloc.cells.pop().unwrap(),
+            copy_flags: array_init(|_| alloc.cells.pop().unwrap()),
+            bytes: array_init(|_| alloc.cells.pop().unwrap()),
+            is_root: IsEqualGadget::construct(alloc),
+            length_is_zero: IsZeroGadget::construct(alloc),
+            buffer_reader: BufferReaderGadget::construct(alloc),
+            copy_words: MemorySizeGadget::construct(alloc),
+            memory_expansion: MemoryExpansionGadget::construct(alloc),
+        }
+    }
+
+    pub(crate) fn constraint(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        state_next: &OpExecutionState<F>,
+        name: &'static str,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::with_call_id(state_curr.call_id.expr());
+
+        // Pop the memory offset, the call data offset and the length from the
+        // stack
+        cb.stack_pop(self.memory_offset.expr());
+        cb.stack_pop(self.data_offset.expr());
+        cb.stack_pop(self.length.expr());
+
+        // Read where the call data is from the call context
+        for (field, value) in [
+            (CallContextField::TxId, self.tx_id.expr()),
+            (CallContextField::Depth, self.depth.expr()),
+            (CallContextField::CallerId, self.caller_id.expr()),
+            (
+                CallContextField::CallDataOffset,
+                self.call_data_offset.expr(),
+            ),
+            (
+                CallContextField::CallDataLength,
+                self.call_data_length.expr(),
+            ),
+        ] {
+            cb.call_context_lookup(
+                false,
+                state_curr.call_id.expr(),
+                field,
+                value,
+            );
+        }
+        let is_root =
+            self.is_root
+                .constraints(&mut cb, self.depth.expr(), 1.expr());
+        let is_internal = 1.expr() - is_root.clone();
+
+        // The copy flags are set for the first `length` bytes, which limits
+        // the length to MAX_COPY_BYTES
+        cb.require_zero(address_high::expr(&self.length));
+        let length = address_low::expr(&self.length);
+        for (idx, copy_flag) in self.copy_flags.iter().enumerate() {
+            cb.require_boolean(copy_flag.expr());
+            if idx > 0 {
+                cb.require_zero(
+                    copy_flag.expr()
+                        * (1.expr() - self.copy_flags[idx - 1].expr()),
+                );
+            }
+        }
+        cb.require_equal(sum::expr(&self.copy_flags), length.clone());
+        let length_is_zero =
+            self.length_is_zero.constraints(&mut cb, length.clone());
+
+        // The memory offset is only used when bytes are copied
+        cb.condition(1.expr() - length_is_zero.clone(), |cb| {
+            memory_gadgets::require_address_in_range(cb, &self.memory_offset);
+        });
+        let memory_offset = address_low::expr(&self.memory_offset);
+
+        // The copied bytes past the end of the call data are zeros
+        let bytes = self
+            .bytes
+            .iter()
+            .map(|byte| byte.expr())
+            .collect::<Vec<_>>();
+        let read_flags = self.buffer_reader.constraints(
+            &mut cb,
+            &self.data_offset,
+            self.call_data_length.expr(),
+            length.clone(),
+            &bytes,
+        );
+
+        // Copy the bytes: look up the bytes within the call data, in the tx
+        // calldata for a root call, or in the memory of the caller for an
+        // internal call, and write all of them into the memory
+        let data_offset = address_low::expr(&self.data_offset);
+        let mut gc_offset = cb.gc_offset.expr();
+        for (idx, ((read_flag, copy_flag), byte)) in read_flags
+            .iter()
+            .zip(self.copy_flags.iter())
+            .zip(bytes.iter())
+            .enumerate()
+        {
+            cb.condition(is_root.clone() * read_flag.clone(), |cb| {
+                cb.tx_calldata_lookup(
+                    self.tx_id.expr(),
+                    data_offset.clone() + idx.expr(),
+                    byte.clone(),
+                );
+            });
+            let is_memory_read = is_internal.clone() * read_flag.clone();
+            cb.condition(is_memory_read.clone(), |cb| {
+                cb.memory_lookup_with_call_id_at(
+                    self.caller_id.expr(),
+                    self.call_data_offset.expr()
+                        + data_offset.clone()
+                        + idx.expr(),
+                    byte.clone(),
+                    0.expr(),
+                    gc_offset.clone(),
+                );
+            });
+            gc_offset = gc_offset + is_memory_read;
+            cb.condition(copy_flag.expr(), |cb| {
+                cb.memory_lookup_at(
+                    memory_offset.clone() + idx.expr(),
+                    byte.clone(),
+                    1.expr(),
+                    gc_offset.clone(),
+                );
+            });
+            gc_offset = gc_offset + copy_flag.expr();
+        }
+
+        // Calculate the next memory size and the gas cost for this memory
+        // access, where the memory is not expanded when no bytes are copied
+        let (next_memory_size, memory_cost) =
+            self.memory_expansion.constraints(
+                &mut cb,
+                state_curr.memory_size.expr(),
+                (1.expr() - length_is_zero) * (memory_offset + length.clone()),
+            );
+        let copy_words = self.copy_words.constraints(&mut cb, length);
+
+        // State transitions
+        let mut st = StateTransitionExpressions::new(STATE_TRANSITION.clone());
+        st.gc_delta = Some(gc_offset);
+        st.gas_delta =
+            Some(GAS.expr() + GasCost::COPY.expr() * copy_words + memory_cost);
+        st.next_memory_size = Some(next_memory_size);
+        st.constraints(&mut cb, state_curr, state_next);
+
+        // Generate the constraint
+        vec![cb.constraint(self.case_selector.expr(), name)]
+    }
+
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: &mut CoreStateInstance,
+        step: &ExecutionStep,
+    ) -> Result<(), Error> {
+        // Inputs, followed by the call context values and the copied bytes
+        let memory_offset = step.values[0].to_word();
+        let data_offset = step.values[1].to_word();
+        let length = step.values[2].to_word();
+        for (word, value) in [
+            (&self.memory_offset, memory_offset),
+            (&self.data_offset, data_offset),
+            (&self.length, length),
+        ] {
+            word.assign(region, offset, Some(value))?;
+        }
+        let value_u64 = |idx: usize| -> u64 {
+            step.values[idx].clone().try_into().unwrap()
+        };
+        for (cell, value) in [
+            (&self.tx_id, value_u64(3)),
+            (&self.depth, value_u64(4)),
+            (&self.caller_id, value_u64(5)),
+            (&self.call_data_offset, value_u64(6)),
+            (&self.call_data_length, value_u64(7)),
+        ] {
+            cell.assign(region, offset, Some(F::from(value)))?;
+        }
+        let is_root = self.is_root.assign(
+            region,
+            offset,
+            F::from(value_u64(4)),
+            F::one(),
+        )?;
+
+        let length = address_low::value::<F>(length);
+        for (idx, copy_flag) in self.copy_flags.iter().enumerate() {
+            copy_flag.assign(
+                region,
+                offset,
+                Some(F::from(((idx as u64) < length) as u64)),
+            )?;
+        }
+        let bytes = step.values[8].to_word();
+        for (cell, byte) in self.bytes.iter().zip(bytes.iter()) {
+            cell.assign(region, offset, Some(F::from(*byte as u64)))?;
+        }
+        let length_is_zero =
+            self.length_is_zero
+                .assign(region, offset, F::from(length))?;
+
+        let num_bytes_read = self.buffer_reader.assign(
+            region,
+            offset,
+            data_offset,
+            value_u64(7),
+            length,
+        )?;
+
+        // Memory expansion
+        let address = if length_is_zero == F::one() {
+            0
+        } else {
+            address_low::value::<F>(memory_offset) + length
+        };
+        let (next_memory_size, memory_cost) = self.memory_expansion.assign(
+            region,
+            offset,
+            state.memory_size as u64,
+            address,
+        )?;
+        let copy_words = self.copy_words.assign(region, offset, length)?;
+
+        // State transitions
+        let num_memory_reads = if is_root == F::one() {
+            0
+        } else {
+            num_bytes_read
+        };
+        let mut st = STATE_TRANSITION.clone();
+        st.gc_delta =
+            Some(GC_DELTA + (num_memory_reads as usize) + (length as usize));
+        st.gas_delta = Some(
+            GAS.as_u64()
+                + GasCost::COPY.as_u64() * copy_words
+                + (memory_cost as u64),
+        );
+        st.next_memory_size = Some(next_memory_size);
+        st.assign(state);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::{
+        test::test_circuit_from_block, BlockCircuit, WitnessError,
+    };
+    use bus_mapping::{
+        bytecode,
+        bytecode::Bytecode,
+        circuit_input_builder::{Block, CircuitInputBuilder},
+        eth_types::Bytes,
+        mock,
+    };
+    use halo2::dev::MockProver;
+    use pairing::bn256::Fr as Fp;
+
+    fn calldatacopy_block(
+        code: Bytecode,
+        callee_code: Option<Bytecode>,
+    ) -> Block {
+        let block = match callee_code {
+            Some(callee_code) => {
+                mock::BlockData::new_single_tx_trace_code_2(&code, &callee_code)
+            }
+            None => mock::BlockData::new_single_tx_trace_code_calldata(
+                &code,
+                Bytes::from((1..=0x20u8).collect::<Vec<_>>()),
+            ),
+        }
+        .unwrap();
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+        builder.block
+    }
+
+    fn test_calldatacopy_gadget(code: Bytecode, callee_code: Option<Bytecode>) {
+        let block = calldatacopy_block(code, callee_code);
+        let circuit = test_circuit_from_block::<Fp>(&block);
+        let prover = MockProver::<Fp>::run(11, &circuit, vec![vec![]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    fn calldatacopy_gadget_from_trace() {
+        // Copies within, across and past the end of the call data, and an
+        // empty copy which doesn't expand the memory
+        for (data_offset, length) in
+            [(0x00u64, 0x20u64), (0x18, 0x10), (0x40, 0x04), (0x00, 0x00)]
+        {
+            let code = bytecode! {
+                PUSH1(length)
+                PUSH1(data_offset)
+                PUSH1(0x40) // memory offset
+                CALLDATACOPY
+                STOP
+            };
+            test_calldatacopy_gadget(code, None);
+        }
+    }
+
+    #[test]
+    fn calldatacopy_gadget_internal_call() {
+        // The caller passes 8 bytes of its memory as call data, of which the
+        // callee copies the last 2 followed by zeros
+        let code = bytecode! {
+            PUSH32(0x0102030405060708u64)
+            PUSH1(0x00)
+            MSTORE
+            PUSH1(0x00) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x08) // argsLength
+            PUSH1(0x18) // argsOffset
+            PUSH1(0x00) // value
+            PUSH2(0x123) // addr
+            PUSH2(0x1000) // gas
+            CALL
+        };
+        let callee_code = bytecode! {
+            PUSH1(0x04) // length
+            PUSH1(0x06) // data offset
+            PUSH1(0x00) // memory offset
+            CALLDATACOPY
+            STOP
+        };
+        test_calldatacopy_gadget(code, Some(callee_code));
+    }
+
+    #[test]
+    fn calldatacopy_gadget_out_of_gas() {
+        // The callee doesn't have the gas to expand the memory up to the
+        // copied bytes, or to copy bytes out of the addressable memory
+        for memory_offset in [0x10000u64, 1 << 40] {
+            let code = bytecode! {
+                PUSH1(0x00) // retLength
+                PUSH1(0x00) // retOffset
+                PUSH1(0x00) // argsLength
+                PUSH1(0x00) // argsOffset
+                PUSH1(0x00) // value
+                PUSH2(0x123) // addr
+                PUSH2(0x800) // gas
+                CALL
+                STOP
+            };
+            let callee_code = bytecode! {
+                PUSH1(0x04) // length
+                PUSH1(0x00) // data offset
+                PUSH32(memory_offset)
+                CALLDATACOPY
+                STOP
+            };
+            test_calldatacopy_gadget(code, Some(callee_code));
+        }
+    }
+
+    #[test]
+    fn calldatacopy_gadget_too_many_bytes() {
+        // A step can't copy more than 32 bytes
+        let code = bytecode! {
+            PUSH1(0x21) // length
+            PUSH1(0x00) // data offset
+            PUSH1(0x00) // memory offset
+            CALLDATACOPY
+            STOP
+        };
+        let block = calldatacopy_block(code, None);
+        assert_eq!(
+            BlockCircuit::<Fp, 1, 16>::new(&block).err(),
+            Some(WitnessError::TooManyCopiedBytes(0))
+        );
+    }
+}
@@ -0,0 +1,302 @@
+use super::super::{Case, Cell, Constraint, ExecutionStep, Word};
+use super::utils::{
+    self,
+    common_cases::{OutOfGasCase, StackUnderflowCase},
+    constraint_builder::ConstraintBuilder,
+    math_gadgets::IsEqualGadget,
+    memory_gadgets::{address_low, BufferReaderGadget},
+    StateTransition, StateTransitionExpressions,
+};
+use super::{
+    CaseAllocation, CaseConfig, CoreStateInstance, OpExecutionState, OpGadget,
+};
+use crate::impl_op_gadget;
+use crate::util::{Expr, ToWord};
+use bus_mapping::{
+    evm::{GasCost, OpcodeId},
+    operation::CallContextField,
+};
+use halo2::plonk::Error;
+use halo2::{arithmetic::FieldExt, circuit::Region};
+use std::convert::TryInto;
+
+static STATE_TRANSITION: StateTransition = StateTransition {
+    gc_delta: None, // GC_DELTA + the memory reads of an internal call
+    pc_delta: Some(1),
+    sp_delta: Some(0),
+    gas_delta: Some(GasCost::FASTEST.as_u64()),
+    next_memory_size: None,
+};
+// 1 stack pop + 5 call context reads + 1 stack push
+const GC_DELTA: usize = 7;
+const NUM_POPPED: usize = 1;
+
+impl_op_gadget!(
+    #set[CALLDATALOAD]
+    CalldataloadGadget {
+        CalldataloadSuccessCase(),
+        StackUnderflowCase(NUM_POPPED),
+        OutOfGasCase(STATE_TRANSITION.gas_delta.unwrap()),
+    }
+);
+
+#[derive(Clone, Debug)]
+struct CalldataloadSuccessCase<F> {
+    case_selector: Cell<F>,
+    offset: Word<F>,
+    value: Word<F>,
+    tx_id: Cell<F>,
+    depth: Cell<F>,
+    caller_id: Cell<F>,
+    call_data_offset: Cell<F>,
+    call_data_length: Cell<F>,
+    is_root: IsEqualGadget<F>,
+    buffer_reader: BufferReaderGadget<F, 32>,
+}
+
+impl<F: FieldExt> CalldataloadSuccessCase<F> {
+    pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::Success,
+        num_word: 2, // offset + value
+        num_cell: 5 // tx_id + depth + caller_id + call data offset and length
+            + IsEqualGadget::<F>::NUM_CELLS
+            + BufferReaderGadget::<F, 32>::NUM_CELLS,
+        will_halt: false,
+    };
+
+    pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+            offset: alloc.words.pop().unwrap(),
+            value: alloc.words.pop().unwrap(),
+            tx_id: alloc.cells.pop().unwrap(),
+            depth: alloc.cells.pop().unwrap(),
+            caller_id: alloc.cells.pop().unwrap(),
+            call_data_offset: alloc.cells.pop().unwrap(),
+            call_data_length: alloc.cells.pop().unwrap(),
+            is_root: IsEqualGadget::construct(alloc),
+            buffer_reader: BufferReaderGadget::construct(alloc),
+        }
+    }
+
+    pub(crate) fn constraint(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        state_next: &OpExecutionState<F>,
+        name: &'static str,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::default();
+
+        // Pop the offset from the stack
+        cb.stack_pop(self.offset.expr());
+
+        // Read where the call data is from the call context
+        for (field, value) in [
+            (CallContextField::TxId, self.tx_id.expr()),
+            (CallContextField::Depth, self.depth.expr()),
+            (CallContextField::CallerId, self.caller_id.expr()),
+            (
+                CallContextField::CallDataOffset,
+                self.call_data_offset.expr(),
+            ),
+            (
+                CallContextField::CallDataLength,
+                self.call_data_length.expr(),
+            ),
+        ] {
+            cb.call_context_lookup(
+                false,
+                state_curr.call_id.expr(),
+                field,
+                value,
+            );
+        }
+        let is_root =
+            self.is_root
+                .constraints(&mut cb, self.depth.expr(), 1.expr());
+        let is_internal = 1.expr() - is_root.clone();
+
+        // The loaded word is big-endian, and its bytes past the end of the
+        // call data are zeros
+        let bytes = (0..32)
+            .map(|idx| self.value.cells[31 - idx].expr())
+            .collect::<Vec<_>>();
+        let read_flags = self.buffer_reader.constraints(
+            &mut cb,
+            &self.offset,
+            self.call_data_length.expr(),
+            32.expr(),
+            &bytes,
+        );
+
+        // Look up the bytes within the call data, in the tx calldata for a
+        // root call, or in the memory of the caller for an internal call
+        let offset = address_low::expr(&self.offset);
+        let gc_offset = cb.gc_offset.expr();
+        for (idx, (read_flag, byte)) in
+            read_flags.iter().zip(bytes.iter()).enumerate()
+        {
+            cb.condition(is_root.clone() * read_flag.clone(), |cb| {
+                cb.tx_calldata_lookup(
+                    self.tx_id.expr(),
+                    offset.clone() + idx.expr(),
+                    byte.clone(),
+                );
+            });
+            cb.condition(is_internal.clone() * read_flag.clone(), |cb| {
+                cb.memory_lookup_with_call_id_at(
+                    self.caller_id.expr(),
+                    self.call_data_offset.expr() + offset.clone() + idx.expr(),
+                    byte.clone(),
+                    0.expr(),
+                    gc_offset.clone() + idx.expr(),
+                );
+            });
+        }
+
+        // Push the value on the stack, after the memory reads
+        let num_memory_reads =
+            is_internal * self.buffer_reader.num_bytes_read();
+        cb.stack_lookup_at(
+            0.expr(),
+            self.value.expr(),
+            1.expr(),
+            gc_offset + num_memory_reads.clone(),
+        );
+
+        // State transitions
+        let mut st = StateTransitionExpressions::new(STATE_TRANSITION.clone());
+        st.gc_delta = Some(GC_DELTA.expr() + num_memory_reads);
+        st.constraints(&mut cb, state_curr, state_next);
+
+        // Generate the constraint
+        vec![cb.constraint(self.case_selector.expr(), name)]
+    }
+
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: &mut CoreStateInstance,
+        step: &ExecutionStep,
+    ) -> Result<(), Error> {
+        // Inputs/Outputs, followed by the call context values
+        let offset_word = step.values[0].to_word();
+        self.offset.assign(region, offset, Some(offset_word))?;
+        self.value
+            .assign(region, offset, Some(step.values[1].to_word()))?;
+        let value_u64 = |idx: usize| -> u64 {
+            step.values[idx].clone().try_into().unwrap()
+        };
+        for (cell, value) in [
+            (&self.tx_id, value_u64(2)),
+            (&self.depth, value_u64(3)),
+            (&self.caller_id, value_u64(4)),
+            (&self.call_data_offset, value_u64(5)),
+            (&self.call_data_length, value_u64(6)),
+        ] {
+            cell.assign(region, offset, Some(F::from(value)))?;
+        }
+        let is_root = self.is_root.assign(
+            region,
+            offset,
+            F::from(value_u64(3)),
+            F::one(),
+        )?;
+
+        let num_bytes_read = self.buffer_reader.assign(
+            region,
+            offset,
+            offset_word,
+            value_u64(6),
+            32,
+        )?;
+
+        // State transitions
+        let mut st = STATE_TRANSITION.clone();
+        st.gc_delta = Some(if is_root == F::one() {
+            GC_DELTA
+        } else {
+            GC_DELTA + num_bytes_read as usize
+        });
+        st.assign(state);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::test::test_circuit_from_block;
+    use bus_mapping::{
+        bytecode,
+        circuit_input_builder::CircuitInputBuilder,
+        eth_types::{Bytes, Word},
+        mock,
+    };
+    use halo2::dev::MockProver;
+    use pairing::bn256::Fr as Fp;
+
+    #[test]
+    fn calldataload_gadget_from_trace() {
+        // Offsets within, across and past the end of the call data
+        for offset in [0x00u64, 0x10, 0x30, 0x100] {
+            let code = bytecode! {
+                PUSH32(offset)
+                CALLDATALOAD
+                STOP
+            };
+            let block = mock::BlockData::new_single_tx_trace_code_calldata(
+                &code,
+                Bytes::from((1..=0x40u8).collect::<Vec<u8>>()),
+            )
+            .unwrap();
+            let mut builder = CircuitInputBuilder::new(
+                block.eth_block.clone(),
+                block.block_ctants.clone(),
+            );
+            builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+            let circuit = test_circuit_from_block::<Fp>(&builder.block);
+            let prover =
+                MockProver::<Fp>::run(11, &circuit, vec![vec![]]).unwrap();
+            assert_eq!(prover.verify(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn calldataload_gadget_internal_call() {
+        // The caller passes 8 bytes of its memory as call data, of which the
+        // callee loads the last 6 followed by zeros
+        let code = bytecode! {
+            PUSH32(Word::MAX)
+            PUSH1(0x00)
+            MSTORE
+            PUSH1(0x00) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x08) // argsLength
+            PUSH1(0x04) // argsOffset
+            PUSH1(0x00) // value
+            PUSH2(0x123) // addr
+            PUSH2(0x1000) // gas
+            CALL
+        };
+        let callee_code = bytecode! {
+            PUSH1(0x02)
+            CALLDATALOAD
+            STOP
+        };
+        let block =
+            mock::BlockData::new_single_tx_trace_code_2(&code, &callee_code)
+                .unwrap();
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let circuit = test_circuit_from_block::<Fp>(&builder.block);
+        let prover = MockProver::<Fp>::run(11, &circuit, vec![vec![]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+}
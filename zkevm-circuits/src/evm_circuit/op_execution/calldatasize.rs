@@ -0,0 +1,145 @@
+use super::super::{Case, Cell, Constraint, ExecutionStep, Word};
+use super::utils::{
+    self,
+    common_cases::{OutOfGasCase, StackOverflowCase},
+    constraint_builder::ConstraintBuilder,
+    memory_gadgets::{self, address_low},
+    StateTransition,
+};
+use super::{
+    CaseAllocation, CaseConfig, CoreStateInstance, OpExecutionState, OpGadget,
+};
+use crate::impl_op_gadget;
+use crate::util::{Expr, ToWord};
+use bus_mapping::{
+    evm::{GasCost, OpcodeId},
+    operation::CallContextField,
+};
+use halo2::plonk::Error;
+use halo2::{arithmetic::FieldExt, circuit::Region};
+
+static STATE_TRANSITION: StateTransition = StateTransition {
+    gc_delta: Some(2), // 1 call context read + 1 stack push
+    pc_delta: Some(1),
+    sp_delta: Some(-1),
+    gas_delta: Some(GasCost::QUICK.as_u64()),
+    next_memory_size: None,
+};
+const NUM_PUSHED: usize = 1;
+
+impl_op_gadget!(
+    #set[CALLDATASIZE]
+    CalldatasizeGadget {
+        CalldatasizeSuccessCase(),
+        StackOverflowCase(NUM_PUSHED),
+        OutOfGasCase(STATE_TRANSITION.gas_delta.unwrap()),
+    }
+);
+
+#[derive(Clone, Debug)]
+struct CalldatasizeSuccessCase<F> {
+    case_selector: Cell<F>,
+    call_data_length: Word<F>,
+}
+
+impl<F: FieldExt> CalldatasizeSuccessCase<F> {
+    pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::Success,
+        num_word: 1, // call_data_length
+        num_cell: 0,
+        will_halt: false,
+    };
+
+    pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+            call_data_length: alloc.words.pop().unwrap(),
+        }
+    }
+
+    pub(crate) fn constraint(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        state_next: &OpExecutionState<F>,
+        name: &'static str,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::default();
+
+        // Read the length of the call data from the call context, which
+        // always fits in the low bytes of the pushed word, and push it on
+        // the stack
+        memory_gadgets::require_address_in_range(
+            &mut cb,
+            &self.call_data_length,
+        );
+        cb.call_context_lookup(
+            false,
+            state_curr.call_id.expr(),
+            CallContextField::CallDataLength,
+            address_low::expr(&self.call_data_length),
+        );
+        cb.stack_push(self.call_data_length.expr());
+
+        // State transitions
+        STATE_TRANSITION.constraints(&mut cb, state_curr, state_next);
+
+        // Generate the constraint
+        vec![cb.constraint(self.case_selector.expr(), name)]
+    }
+
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: &mut CoreStateInstance,
+        step: &ExecutionStep,
+    ) -> Result<(), Error> {
+        // Output
+        self.call_data_length.assign(
+            region,
+            offset,
+            Some(step.values[0].to_word()),
+        )?;
+
+        // State transitions
+        STATE_TRANSITION.assign(state);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::test::test_circuit_from_block;
+    use bus_mapping::{
+        bytecode, circuit_input_builder::CircuitInputBuilder, eth_types::Bytes,
+        mock,
+    };
+    use halo2::dev::MockProver;
+    use pairing::bn256::Fr as Fp;
+
+    #[test]
+    fn calldatasize_gadget_from_trace() {
+        let code = bytecode! {
+            CALLDATASIZE
+            STOP
+        };
+        for call_data in [vec![], vec![0xff; 0x23]] {
+            let block = mock::BlockData::new_single_tx_trace_code_calldata(
+                &code,
+                Bytes::from(call_data),
+            )
+            .unwrap();
+            let mut builder = CircuitInputBuilder::new(
+                block.eth_block.clone(),
+                block.block_ctants.clone(),
+            );
+            builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+            let circuit = test_circuit_from_block::<Fp>(&builder.block);
+            let prover =
+                MockProver::<Fp>::run(11, &circuit, vec![vec![]]).unwrap();
+            assert_eq!(prover.verify(), Ok(()));
+        }
+    }
+}
@@ -0,0 +1,767 @@
+use super::super::{Case, Cell, Constraint, ExecutionStep, Word};
+use super::utils::{
+    self,
+    common_cases::StackUnderflowCase,
+    constraint_builder::ConstraintBuilder,
+    from_bytes,
+    math_gadgets::{IsZeroGadget, LtGadget},
+    memory_gadgets::{
+        self, address_high, address_low, BufferReaderGadget,
+        MemoryExpansionGadget, MemoryRangeGadget, MemorySizeGadget,
+    },
+    select, sum, StateTransition, StateTransitionExpressions,
+};
+use super::{
+    CaseAllocation, CaseConfig, CoreStateInstance, OpExecutionState, OpGadget,
+};
+use crate::evm_circuit::param::{MAX_COPY_BYTES, MAX_GAS_SIZE_IN_BYTES};
+use crate::impl_op_gadget;
+use crate::util::{Expr, ToWord};
+use array_init::array_init;
+use bus_mapping::{
+    evm::{GasCost, OpcodeId},
+    operation::CallContextField,
+};
+use halo2::plonk::Error;
+use halo2::{arithmetic::FieldExt, circuit::Region, plonk::Expression};
+use std::convert::{TryFrom, TryInto};
+
+static STATE_TRANSITION: StateTransition = StateTransition {
+    // the call context read, stack pops, access list write, code hash read
+    // and memory writes
+    gc_delta: None,
+    pc_delta: Some(1),
+    sp_delta: None,         // 3 for CODECOPY and 4 for EXTCODECOPY
+    gas_delta: None,        // access cost + GasCost::COPY * words + memory_cost
+    next_memory_size: None, // next_memory_size
+};
+
+impl_op_gadget!(
+    #set[CODECOPY]
+    CodecopyGadget {
+        CodeCopySuccessCase(false),
+        StackUnderflowCase(3),
+        CodeCopyOutOfGasCase(false),
+    }
+);
+
+impl_op_gadget!(
+    #set[EXTCODECOPY]
+    ExtcodecopyGadget {
+        CodeCopySuccessCase(true),
+        StackUnderflowCase(4),
+        CodeCopyOutOfGasCase(true),
+    }
+);
+
+// The gas cost of accessing the copied code, which for EXTCODECOPY depends on
+// the warm status of the account (EIP-2929)
+fn access_cost<F: FieldExt>(is_ext: bool, is_warm: &Cell<F>) -> Expression<F> {
+    if is_ext {
+        select::expr(
+            is_warm.expr(),
+            GasCost::WARM_STORAGE_READ_COST.expr(),
+            GasCost::COLD_ACCOUNT_ACCESS_COST.expr(),
+        )
+    } else {
+        GasCost::FASTEST.expr()
+    }
+}
+
+fn access_cost_value(is_ext: bool, is_warm: bool) -> u64 {
+    if !is_ext {
+        GasCost::FASTEST.as_u64()
+    } else if is_warm {
+        GasCost::WARM_STORAGE_READ_COST.as_u64()
+    } else {
+        GasCost::COLD_ACCOUNT_ACCESS_COST.as_u64()
+    }
+}
+
+// Pop the arguments from the stack, EXTCODECOPY starting with the address of
+// the account whose code is copied.  EXTCODECOPY first reads the transaction
+// id, whose access list holds the account.
+fn pop_arguments<F: FieldExt>(
+    cb: &mut ConstraintBuilder<F>,
+    state_curr: &OpExecutionState<F>,
+    is_ext: bool,
+    tx_id: &Cell<F>,
+    arguments: [&Word<F>; 4],
+) {
+    let [address, memory_offset, code_offset, length] = arguments;
+    if is_ext {
+        cb.call_context_lookup(
+            false,
+            state_curr.call_id.expr(),
+            CallContextField::TxId,
+            tx_id.expr(),
+        );
+        cb.stack_pop(address.expr());
+    }
+    cb.stack_pop(memory_offset.expr());
+    cb.stack_pop(code_offset.expr());
+    cb.stack_pop(length.expr());
+}
+
+#[derive(Clone, Debug)]
+struct CodeCopySuccessCase<F> {
+    case_selector: Cell<F>,
+    // EXTCODECOPY pops the address of the account whose code is copied,
+    // while CODECOPY copies the code being executed.
+    is_ext: bool,
+    address: Word<F>,
+    memory_offset: Word<F>,
+    code_offset: Word<F>,
+    length: Word<F>,
+    code_hash: Word<F>,
+    tx_id: Cell<F>,
+    is_warm: Cell<F>,
+    code_rlc: Cell<F>,
+    code_size: Cell<F>,
+    copy_flags: [Cell<F>; MAX_COPY_BYTES],
+    bytes: [Cell<F>; MAX_COPY_BYTES],
+    is_code: [Cell<F>; MAX_COPY_BYTES],
+    is_code_hash_zero: IsZeroGadget<F>,
+    length_is_zero: IsZeroGadget<F>,
+    buffer_reader: BufferReaderGadget<F, MAX_COPY_BYTES>,
+    copy_words: MemorySizeGadget<F>,
+    memory_expansion: MemoryExpansionGadget<F, MAX_GAS_SIZE_IN_BYTES>,
+}
+
+impl<F: FieldExt> CodeCopySuccessCase<F> {
+    pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::Success,
+        // address + memory_offset + code_offset + length + code_hash
+        num_word: 5,
+        num_cell: 4 // tx_id + is_warm + code_rlc + code_size
+            + 3 * MAX_COPY_BYTES // copy_flags + bytes + is_code
+            + IsZeroGadget::<F>::NUM_CELLS * 2
+            + BufferReaderGadget::<F, MAX_COPY_BYTES>::NUM_CELLS
+            + MemorySizeGadget::<F>::NUM_CELLS
+            + MemoryExpansionGadget::<F, MAX_GAS_SIZE_IN_BYTES>::NUM_CELLS,
+        will_halt: false,
+    };
+
+    pub(crate) fn construct(
+        alloc: &mut CaseAllocation<F>,
+        is_ext: bool,
+    ) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+            is_ext,
+            address: alloc.words.pop().unwrap(),
+            memory_offset: alloc.words.pop().unwrap(),
+            code_offset: alloc.words.pop().unwrap(),
+            length: alloc.words.pop().unwrap(),
+            code_hash: alloc.words.pop().unwrap(),
+            tx_id: alloc.cells.pop().unwrap(),
+            is_warm: alloc.cells.pop().unwrap(),
+            code_rlc: alloc.cells.pop().unwrap(),
+            code_size: alloc.cells.pop().unwrap(),
+            copy_flags: array_init(|_| alloc.cells.pop().unwrap()),
+            bytes: array_init(|_| alloc.cells.pop().unwrap()),
+            is_code: array_init(|_| alloc.cells.pop().unwrap()),
+            is_code_hash_zero: IsZeroGadget::construct(alloc),
+            length_is_zero: IsZeroGadget::construct(alloc),
+            buffer_reader: BufferReaderGadget::construct(alloc),
+            copy_words: MemorySizeGadget::construct(alloc),
+            memory_expansion: MemoryExpansionGadget::construct(alloc),
+        }
+    }
+
+    // Number of stack pops
+    fn num_popped(&self) -> usize {
+        if self.is_ext {
+            4
+        } else {
+            3
+        }
+    }
+
+    pub(crate) fn constraint(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        state_next: &OpExecutionState<F>,
+        name: &'static str,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::with_call_id(state_curr.call_id.expr());
+
+        // Pop the arguments from the stack.  EXTCODECOPY marks the account,
+        // which is given by the lower 20 bytes of the popped address, as warm
+        // and reads its code hash, while CODECOPY copies the code being
+        // executed.
+        pop_arguments(
+            &mut cb,
+            state_curr,
+            self.is_ext,
+            &self.tx_id,
+            [
+                &self.address,
+                &self.memory_offset,
+                &self.code_offset,
+                &self.length,
+            ],
+        );
+        let code_source = if self.is_ext {
+            let address = from_bytes::expr(self.address.cells[..20].to_vec());
+            cb.require_boolean(self.is_warm.expr());
+            cb.tx_access_list_account_write(
+                self.tx_id.expr(),
+                address.clone(),
+                1.expr(),
+                self.is_warm.expr(),
+            );
+            cb.account_code_hash_lookup(
+                false,
+                address,
+                self.code_hash.expr(),
+                self.code_hash.expr(),
+            );
+            self.code_hash.expr()
+        } else {
+            state_curr.code_source.expr()
+        };
+
+        // The size of a code is the length of the keccak input hashed into
+        // its code hash.  An empty account has no code, and its code hash is
+        // 0.
+        let is_code_hash_zero = if self.is_ext {
+            self.is_code_hash_zero
+                .constraints(&mut cb, sum::expr(&self.code_hash.cells))
+        } else {
+            0.expr()
+        };
+        cb.condition(is_code_hash_zero.clone(), |cb| {
+            cb.require_zero(self.code_size.expr());
+        });
+        cb.condition(1.expr() - is_code_hash_zero, |cb| {
+            cb.add_keccak_lookup([
+                self.code_rlc.expr(),
+                self.code_size.expr(),
+                code_source.clone(),
+            ]);
+        });
+
+        // The copy flags are set for the first `length` bytes, which limits
+        // the length to MAX_COPY_BYTES
+        cb.require_zero(address_high::expr(&self.length));
+        let length = address_low::expr(&self.length);
+        for (idx, copy_flag) in self.copy_flags.iter().enumerate() {
+            cb.require_boolean(copy_flag.expr());
+            if idx > 0 {
+                cb.require_zero(
+                    copy_flag.expr()
+                        * (1.expr() - self.copy_flags[idx - 1].expr()),
+                );
+            }
+        }
+        cb.require_equal(sum::expr(&self.copy_flags), length.clone());
+        let length_is_zero =
+            self.length_is_zero.constraints(&mut cb, length.clone());
+
+        // The memory offset is only used when bytes are copied
+        cb.condition(1.expr() - length_is_zero.clone(), |cb| {
+            memory_gadgets::require_address_in_range(cb, &self.memory_offset);
+        });
+        let memory_offset = address_low::expr(&self.memory_offset);
+
+        // The copied bytes past the end of the code are zeros
+        let bytes = self
+            .bytes
+            .iter()
+            .map(|byte| byte.expr())
+            .collect::<Vec<_>>();
+        let read_flags = self.buffer_reader.constraints(
+            &mut cb,
+            &self.code_offset,
+            self.code_size.expr(),
+            length.clone(),
+            &bytes,
+        );
+
+        // Copy the bytes: look up the bytes within the code in the bytecode
+        // table, either opcodes or PUSH data, and write all of them into the
+        // memory
+        let code_offset = address_low::expr(&self.code_offset);
+        let mut gc_offset = cb.gc_offset.expr();
+        for (idx, (((read_flag, copy_flag), byte), is_code)) in read_flags
+            .iter()
+            .zip(self.copy_flags.iter())
+            .zip(bytes.iter())
+            .zip(self.is_code.iter())
+            .enumerate()
+        {
+            cb.condition(read_flag.clone(), |cb| {
+                cb.add_bytecode_lookup([
+                    code_source.clone(),
+                    code_offset.clone() + idx.expr(),
+                    is_code.expr(),
+                    byte.clone(),
+                ]);
+            });
+            cb.condition(copy_flag.expr(), |cb| {
+                cb.memory_lookup_at(
+                    memory_offset.clone() + idx.expr(),
+                    byte.clone(),
+                    1.expr(),
+                    gc_offset.clone(),
+                );
+            });
+            gc_offset = gc_offset + copy_flag.expr();
+        }
+
+        // Calculate the next memory size and the gas cost for this memory
+        // access, where the memory is not expanded when no bytes are copied
+        let (next_memory_size, memory_cost) =
+            self.memory_expansion.constraints(
+                &mut cb,
+                state_curr.memory_size.expr(),
+                (1.expr() - length_is_zero) * (memory_offset + length.clone()),
+            );
+        let copy_words = self.copy_words.constraints(&mut cb, length);
+
+        // State transitions
+        let mut st = StateTransitionExpressions::new(STATE_TRANSITION.clone());
+        st.gc_delta = Some(gc_offset);
+        st.sp_delta = Some(self.num_popped().expr());
+        st.gas_delta = Some(
+            access_cost(self.is_ext, &self.is_warm)
+                + GasCost::COPY.expr() * copy_words
+                + memory_cost,
+        );
+        st.next_memory_size = Some(next_memory_size);
+        st.constraints(&mut cb, state_curr, state_next);
+
+        // Generate the constraint
+        vec![cb.constraint(self.case_selector.expr(), name)]
+    }
+
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: &mut CoreStateInstance,
+        step: &ExecutionStep,
+    ) -> Result<(), Error> {
+        // Inputs, EXTCODECOPY starting with the address and followed by the
+        // transaction id, the warm status of the account and its code hash,
+        // and then the random linear combination and the size of the code,
+        // the copied bytes and their is_code flags
+        let (inputs, is_warm) = if self.is_ext {
+            self.address.assign(
+                region,
+                offset,
+                Some(step.values[0].to_word()),
+            )?;
+            let tx_id = u64::try_from(&step.values[4]).unwrap();
+            self.tx_id.assign(region, offset, Some(F::from(tx_id)))?;
+            let is_warm = step.values[5] == 1u64.into();
+            self.is_warm.assign(
+                region,
+                offset,
+                Some(F::from(is_warm as u64)),
+            )?;
+            let code_hash = step.values[6].to_word();
+            self.code_hash.assign(region, offset, Some(code_hash))?;
+            self.is_code_hash_zero.assign(
+                region,
+                offset,
+                sum::value(&code_hash),
+            )?;
+            (&step.values[1..4], is_warm)
+        } else {
+            (&step.values[..3], false)
+        };
+        let memory_offset = inputs[0].to_word();
+        let code_offset = inputs[1].to_word();
+        let length = inputs[2].to_word();
+        for (word, value) in [
+            (&self.memory_offset, memory_offset),
+            (&self.code_offset, code_offset),
+            (&self.length, length),
+        ] {
+            word.assign(region, offset, Some(value))?;
+        }
+        let num_values = step.values.len();
+        self.code_rlc.assign(
+            region,
+            offset,
+            Some(
+                F::from_bytes(&step.values[num_values - 4].to_word()).unwrap(),
+            ),
+        )?;
+        let code_size: u64 =
+            step.values[num_values - 3].clone().try_into().unwrap();
+        self.code_size
+            .assign(region, offset, Some(F::from(code_size)))?;
+
+        let length = address_low::value::<F>(length);
+        for (idx, copy_flag) in self.copy_flags.iter().enumerate() {
+            copy_flag.assign(
+                region,
+                offset,
+                Some(F::from(((idx as u64) < length) as u64)),
+            )?;
+        }
+        let bytes = step.values[num_values - 2].to_word();
+        let is_code = step.values[num_values - 1].to_word();
+        for ((byte_cell, is_code_cell), (byte, is_code)) in self
+            .bytes
+            .iter()
+            .zip(self.is_code.iter())
+            .zip(bytes.iter().zip(is_code.iter()))
+        {
+            byte_cell.assign(region, offset, Some(F::from(*byte as u64)))?;
+            is_code_cell.assign(
+                region,
+                offset,
+                Some(F::from(*is_code as u64)),
+            )?;
+        }
+        let length_is_zero =
+            self.length_is_zero
+                .assign(region, offset, F::from(length))?;
+
+        self.buffer_reader.assign(
+            region,
+            offset,
+            code_offset,
+            code_size,
+            length,
+        )?;
+
+        // Memory expansion
+        let address = if length_is_zero == F::one() {
+            0
+        } else {
+            address_low::value::<F>(memory_offset) + length
+        };
+        let (next_memory_size, memory_cost) = self.memory_expansion.assign(
+            region,
+            offset,
+            state.memory_size as u64,
+            address,
+        )?;
+        let copy_words = self.copy_words.assign(region, offset, length)?;
+        let copy_cost =
+            GasCost::COPY.as_u64() * copy_words + (memory_cost as u64);
+
+        // State transitions
+        let mut st = STATE_TRANSITION.clone();
+        st.gc_delta = Some(
+            self.num_popped() + 3 * (self.is_ext as usize) + (length as usize),
+        );
+        st.sp_delta = Some(self.num_popped() as i32);
+        st.gas_delta =
+            Some(access_cost_value(self.is_ext, is_warm) + copy_cost);
+        st.next_memory_size = Some(next_memory_size);
+        st.assign(state);
+        // The access list write of EXTCODECOPY is a state write
+        state.state_write_counter += self.is_ext as usize;
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+struct CodeCopyOutOfGasCase<F> {
+    case_selector: Cell<F>,
+    is_ext: bool,
+    address: Word<F>,
+    memory_offset: Word<F>,
+    code_offset: Word<F>,
+    length: Word<F>,
+    tx_id: Cell<F>,
+    is_warm: Cell<F>,
+    memory_range: MemoryRangeGadget<F>,
+    insufficient_gas: LtGadget<F, MAX_GAS_SIZE_IN_BYTES>,
+}
+
+impl<F: FieldExt> CodeCopyOutOfGasCase<F> {
+    pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::OutOfGas,
+        num_word: 4, // address + memory_offset + code_offset + length
+        num_cell: 2 // tx_id + is_warm
+            + MemoryRangeGadget::<F>::NUM_CELLS
+            + LtGadget::<F, MAX_GAS_SIZE_IN_BYTES>::NUM_CELLS,
+        will_halt: true,
+    };
+
+    pub(crate) fn construct(
+        alloc: &mut CaseAllocation<F>,
+        is_ext: bool,
+    ) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+            is_ext,
+            address: alloc.words.pop().unwrap(),
+            memory_offset: alloc.words.pop().unwrap(),
+            code_offset: alloc.words.pop().unwrap(),
+            length: alloc.words.pop().unwrap(),
+            tx_id: alloc.cells.pop().unwrap(),
+            is_warm: alloc.cells.pop().unwrap(),
+            memory_range: MemoryRangeGadget::construct(alloc),
+            insufficient_gas: LtGadget::construct(alloc),
+        }
+    }
+
+    pub(crate) fn constraint(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        _state_next: &OpExecutionState<F>,
+        name: &'static str,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::with_call_id(state_curr.call_id.expr());
+
+        // Pop the arguments from the stack, and read the warm status of the
+        // account whose code EXTCODECOPY copies
+        pop_arguments(
+            &mut cb,
+            state_curr,
+            self.is_ext,
+            &self.tx_id,
+            [
+                &self.address,
+                &self.memory_offset,
+                &self.code_offset,
+                &self.length,
+            ],
+        );
+        if self.is_ext {
+            cb.require_boolean(self.is_warm.expr());
+            cb.tx_access_list_account_read(
+                self.tx_id.expr(),
+                from_bytes::expr(self.address.cells[..20].to_vec()),
+                self.is_warm.expr(),
+            );
+        }
+
+        // Make sure the amount of gas available is less than the amount of
+        // gas required to access the code, expand the memory and copy the
+        // bytes, unless the bytes are copied out of the addressable memory,
+        // which never has enough gas
+        let (is_in_range, copy_words, memory_cost) =
+            self.memory_range.constraints(
+                &mut cb,
+                state_curr.memory_size.expr(),
+                &self.memory_offset,
+                &self.length,
+            );
+        let insufficient_gas = self.insufficient_gas.constraints(
+            &mut cb,
+            state_curr.gas_left.expr(),
+            access_cost(self.is_ext, &self.is_warm)
+                + GasCost::COPY.expr() * copy_words
+                + memory_cost,
+        );
+        cb.condition(is_in_range, |cb| {
+            cb.require_equal(insufficient_gas, 1.expr());
+        });
+
+        // Generate the constraint
+        vec![cb.constraint(self.case_selector.expr(), name)]
+    }
+
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: &mut CoreStateInstance,
+        step: &ExecutionStep,
+    ) -> Result<(), Error> {
+        // Inputs, EXTCODECOPY starting with the address and followed by the
+        // transaction id and the warm status of the account
+        let (inputs, is_warm) = if self.is_ext {
+            self.address.assign(
+                region,
+                offset,
+                Some(step.values[0].to_word()),
+            )?;
+            let tx_id = u64::try_from(&step.values[4]).unwrap();
+            self.tx_id.assign(region, offset, Some(F::from(tx_id)))?;
+            let is_warm = step.values[5] == 1u64.into();
+            self.is_warm.assign(
+                region,
+                offset,
+                Some(F::from(is_warm as u64)),
+            )?;
+            (&step.values[1..4], is_warm)
+        } else {
+            (&step.values[..3], false)
+        };
+        let memory_offset = inputs[0].to_word();
+        let length = inputs[2].to_word();
+        for (word, value) in [
+            (&self.memory_offset, memory_offset),
+            (&self.code_offset, inputs[1].to_word()),
+            (&self.length, length),
+        ] {
+            word.assign(region, offset, Some(value))?;
+        }
+
+        let (_, copy_words, memory_cost) = self.memory_range.assign(
+            region,
+            offset,
+            state.memory_size as u64,
+            memory_offset,
+            length,
+        )?;
+
+        // Gas insufficient check
+        self.insufficient_gas.assign(
+            region,
+            offset,
+            F::from(state.gas_left),
+            F::from(
+                access_cost_value(self.is_ext, is_warm)
+                    + GasCost::COPY.as_u64() * copy_words
+                    + (memory_cost as u64),
+            ),
+        )?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::test::test_circuit_from_block;
+    use bus_mapping::{
+        bytecode,
+        bytecode::Bytecode,
+        circuit_input_builder::CircuitInputBuilder,
+        eth_types::{Address, Word},
+        mock,
+    };
+    use halo2::dev::MockProver;
+    use pairing::bn256::Fr as Fp;
+
+    fn test_codecopy_gadget(code: Bytecode, callee_code: Bytecode) {
+        let block =
+            mock::BlockData::new_single_tx_trace_code_2(&code, &callee_code)
+                .unwrap();
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.set_code(&Address::zero(), code.to_bytes());
+        builder
+            .set_code(&Address::from_low_u64_be(0x123), callee_code.to_bytes());
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let circuit = test_circuit_from_block::<Fp>(&builder.block);
+        let prover = MockProver::<Fp>::run(11, &circuit, vec![vec![]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    fn codecopy_gadget_from_trace() {
+        // Copies within, across and past the end of the code, and an empty
+        // copy which doesn't expand the memory
+        for (code_offset, length) in
+            [(0x00u64, 0x08u64), (0x06, 0x08), (0x40, 0x04), (0x00, 0x00)]
+        {
+            let code = bytecode! {
+                PUSH1(length)
+                PUSH1(code_offset)
+                PUSH1(0x20) // memory offset
+                CODECOPY
+                STOP
+            };
+            test_codecopy_gadget(code, bytecode! { STOP });
+        }
+    }
+
+    #[test]
+    fn extcodecopy_gadget_from_trace() {
+        // The first EXTCODECOPY copies from a cold contract, the second one
+        // from the same contract, which is then warm, past the end of its
+        // code, and the last one from an empty account
+        let code = bytecode! {
+            PUSH1(0x03) // length
+            PUSH1(0x01) // code offset
+            PUSH1(0x00) // memory offset
+            PUSH2(0x123) // address
+            EXTCODECOPY
+            PUSH1(0x04) // length
+            PUSH1(0x02) // code offset
+            PUSH1(0x20) // memory offset
+            PUSH2(0x123) // address
+            EXTCODECOPY
+            PUSH1(0x02) // length
+            PUSH1(0x00) // code offset
+            PUSH1(0x40) // memory offset
+            PUSH2(0x456) // address
+            EXTCODECOPY
+            STOP
+        };
+        let callee_code = bytecode! {
+            PUSH2(0xaabb)
+            STOP
+        };
+        test_codecopy_gadget(code, callee_code);
+    }
+
+    // The code calling the contract at 0x123 with less gas than needed by
+    // its copy
+    fn out_of_gas_caller_code() -> Bytecode {
+        bytecode! {
+            PUSH1(0x00) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x00) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH1(0x00) // value
+            PUSH2(0x123) // addr
+            PUSH2(0x800) // gas
+            CALL
+            STOP
+        }
+    }
+
+    #[test]
+    fn codecopy_gadget_out_of_gas() {
+        // The callee doesn't have the gas to expand the memory up to the
+        // copied bytes
+        let callee_code = bytecode! {
+            PUSH1(0x04) // length
+            PUSH1(0x00) // code offset
+            PUSH3(0x10000) // memory offset
+            CODECOPY
+            STOP
+        };
+        test_codecopy_gadget(out_of_gas_caller_code(), callee_code);
+    }
+
+    #[test]
+    fn codecopy_gadget_out_of_addressable_memory() {
+        // Copies into a memory offset and of a length out of the addressable
+        // memory
+        for (memory_offset, length) in [
+            (Word::from(1) << 40, Word::from(4)),
+            (Word::zero(), Word::MAX),
+        ] {
+            let callee_code = bytecode! {
+                PUSH32(length)
+                PUSH1(0x00) // code offset
+                PUSH32(memory_offset)
+                CODECOPY
+                STOP
+            };
+            test_codecopy_gadget(out_of_gas_caller_code(), callee_code);
+        }
+    }
+
+    #[test]
+    fn extcodecopy_gadget_out_of_gas() {
+        // The callee doesn't have the gas to access a cold account
+        let callee_code = bytecode! {
+            PUSH1(0x00) // length
+            PUSH1(0x00) // code offset
+            PUSH1(0x00) // memory offset
+            PUSH2(0x456) // address
+            EXTCODECOPY
+            STOP
+        };
+        test_codecopy_gadget(out_of_gas_caller_code(), callee_code);
+    }
+}
@@ -0,0 +1,138 @@
+use super::super::{Case, Cell, Constraint, ExecutionStep, Word};
+use super::utils::{
+    self,
+    common_cases::{OutOfGasCase, StackOverflowCase},
+    constraint_builder::ConstraintBuilder,
+    memory_gadgets, StateTransition,
+};
+use super::{
+    CaseAllocation, CaseConfig, CoreStateInstance, OpExecutionState, OpGadget,
+};
+use crate::impl_op_gadget;
+use crate::util::{Expr, ToWord};
+use bus_mapping::evm::{GasCost, OpcodeId};
+use halo2::plonk::Error;
+use halo2::{arithmetic::FieldExt, circuit::Region};
+
+static STATE_TRANSITION: StateTransition = StateTransition {
+    gc_delta: Some(1), // 1 stack push
+    pc_delta: Some(1),
+    sp_delta: Some(-1),
+    gas_delta: Some(GasCost::QUICK.as_u64()),
+    next_memory_size: None,
+};
+const NUM_PUSHED: usize = 1;
+
+impl_op_gadget!(
+    #set[CODESIZE]
+    CodesizeGadget {
+        CodesizeSuccessCase(),
+        StackOverflowCase(NUM_PUSHED),
+        OutOfGasCase(STATE_TRANSITION.gas_delta.unwrap()),
+    }
+);
+
+#[derive(Clone, Debug)]
+struct CodesizeSuccessCase<F> {
+    case_selector: Cell<F>,
+    code_size: Word<F>,
+    // The random linear combination of the code being executed
+    code_rlc: Cell<F>,
+}
+
+impl<F: FieldExt> CodesizeSuccessCase<F> {
+    pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::Success,
+        num_word: 1, // code_size
+        num_cell: 1, // code_rlc
+        will_halt: false,
+    };
+
+    pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+            code_size: alloc.words.pop().unwrap(),
+            code_rlc: alloc.cells.pop().unwrap(),
+        }
+    }
+
+    pub(crate) fn constraint(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        state_next: &OpExecutionState<F>,
+        name: &'static str,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::default();
+
+        // Push the size of the code being executed, which always fits in the
+        // low bytes of the pushed word.  The size is the length of the keccak
+        // input hashed into the code hash.
+        memory_gadgets::require_address_in_range(&mut cb, &self.code_size);
+        cb.add_keccak_lookup([
+            self.code_rlc.expr(),
+            memory_gadgets::address_low::expr(&self.code_size),
+            state_curr.code_source.expr(),
+        ]);
+        cb.stack_push(self.code_size.expr());
+
+        // State transitions
+        STATE_TRANSITION.constraints(&mut cb, state_curr, state_next);
+
+        // Generate the constraint
+        vec![cb.constraint(self.case_selector.expr(), name)]
+    }
+
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: &mut CoreStateInstance,
+        step: &ExecutionStep,
+    ) -> Result<(), Error> {
+        // Output
+        self.code_size.assign(
+            region,
+            offset,
+            Some(step.values[0].to_word()),
+        )?;
+        self.code_rlc.assign(
+            region,
+            offset,
+            Some(F::from_bytes(&step.values[1].to_word()).unwrap()),
+        )?;
+
+        // State transitions
+        STATE_TRANSITION.assign(state);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::test::test_circuit_from_block;
+    use bus_mapping::{
+        bytecode, circuit_input_builder::CircuitInputBuilder, mock,
+    };
+    use halo2::dev::MockProver;
+    use pairing::bn256::Fr as Fp;
+
+    #[test]
+    fn codesize_gadget_from_trace() {
+        let code = bytecode! {
+            PUSH32(0)
+            CODESIZE
+            STOP
+        };
+        let block = mock::BlockData::new_single_tx_trace_code(&code).unwrap();
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let circuit = test_circuit_from_block::<Fp>(&builder.block);
+        let prover = MockProver::<Fp>::run(11, &circuit, vec![vec![]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+}
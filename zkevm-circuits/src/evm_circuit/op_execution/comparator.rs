@@ -14,7 +14,6 @@ use crate::impl_op_gadget;
 use crate::util::{Expr, ToWord};
 use bus_mapping::evm::{GasCost, OpcodeId};
 use halo2::{arithmetic::FieldExt, circuit::Region, plonk::Error};
-use std::convert::TryInto;
 
 static STATE_TRANSITION: StateTransition = StateTransition {
     gc_delta: Some(3), // 2 stack pops + 1 stack push
@@ -205,7 +204,8 @@ mod test {
         ($execution_step:expr, $operations:expr, $result:expr) => {{
             let circuit =
                 TestCircuit::<Fp>::new($execution_step, $operations, false);
-            let prover = MockProver::<Fp>::run(11, &circuit, vec![]).unwrap();
+            let prover =
+                MockProver::<Fp>::run(11, &circuit, vec![vec![]]).unwrap();
             assert_eq!(prover.verify(), $result);
         }};
     }
@@ -245,6 +245,8 @@ mod test {
                         Fp::from(1023),
                         compress(b.clone()),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
                 Operation {
@@ -256,6 +258,8 @@ mod test {
                         Fp::from(1022),
                         compress(a.clone()),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
                 Operation {
@@ -267,6 +271,8 @@ mod test {
                         Fp::from(1022),
                         compress(a),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
                 Operation {
@@ -278,6 +284,8 @@ mod test {
                         Fp::from(1023),
                         compress(b),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
                 Operation {
@@ -289,6 +297,8 @@ mod test {
                         Fp::from(1023),
                         compress(result),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
             ],
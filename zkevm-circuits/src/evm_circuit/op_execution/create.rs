@@ -0,0 +1,620 @@
+use super::super::{Case, Cell, Constraint, ExecutionStep, Word};
+use super::call::{
+    memory_region_end, NUM_CALLEE_WRITES, NUM_CALLER_READS, NUM_CALLER_WRITES,
+};
+use super::utils::{
+    self,
+    common_cases::StackUnderflowCase,
+    constraint_builder::ConstraintBuilder,
+    from_bytes,
+    math_gadgets::{
+        ByteSizeGadget, ConstantDivisionGadget, IsZeroGadget, LtGadget,
+    },
+    memory_gadgets::{address_low, MemoryExpansionGadget, MemorySizeGadget},
+    rlc, select, sum, StateTransitionExpressions,
+};
+use super::{
+    CaseAllocation, CaseConfig, CoreStateInstance, OpExecutionState, OpGadget,
+};
+use crate::bytecode_circuit::bytecode::code_hash;
+use crate::evm_circuit::param::MAX_GAS_SIZE_IN_BYTES;
+use crate::impl_op_gadget;
+use crate::util::{Expr, ToWord};
+use bus_mapping::{
+    evm::{GasCost, OpcodeId},
+    operation::CallContextField,
+};
+use halo2::plonk::Error;
+use halo2::{arithmetic::FieldExt, circuit::Region, plonk::Expression};
+use std::convert::TryInto;
+
+// Number of account operations: the nonce write of the caller, the code hash
+// read of the contract, the nonce write of the contract and the access list
+// write marking the contract as warm.
+const NUM_ACCOUNT_OPS: usize = 4;
+
+// TODO: Add the cases where the init code is not executed: creations failing
+// due to the depth limit or an insufficient balance.  Add the out of gas case,
+// the write protection case in a static call, and the balance transfer of a
+// creation with value, which is rejected until then.
+impl_op_gadget!(
+    #set[CREATE]
+    CreateGadget {
+        CreateSuccessCase(false),
+        StackUnderflowCase(3),
+    }
+);
+
+impl_op_gadget!(
+    #set[CREATE2]
+    Create2Gadget {
+        CreateSuccessCase(true),
+        StackUnderflowCase(4),
+    }
+);
+
+#[derive(Clone, Debug)]
+struct CreateSuccessCase<F> {
+    case_selector: Cell<F>,
+    // CREATE2 pops the salt of the address of the contract, while CREATE
+    // doesn't.
+    is_create2: bool,
+    // Randomness of the RLC of the input of keccak
+    r: F,
+    value: Word<F>,
+    offset: Word<F>,
+    length: Word<F>,
+    salt: Word<F>,
+    // Keccak hash whose 20 lowest bytes are the address of the created
+    // contract, which is pushed on the stack when the init code succeeds.
+    address_hash: Word<F>,
+    is_success: Cell<F>,
+    // Context of the caller
+    tx_id: Cell<F>,
+    depth: Cell<F>,
+    caller_caller_address: Cell<F>,
+    caller_address: Word<F>,
+    caller_value: Word<F>,
+    caller_is_static: Cell<F>,
+    // Nonce of the caller before the creation
+    caller_nonce: Word<F>,
+    // Hash of the init code
+    init_code_hash: Word<F>,
+    // Code hash of the contract account before the creation, which is zero
+    // when the account doesn't exist
+    code_hash_prev: Cell<F>,
+    // Whether the contract account was warm before the creation
+    is_warm: Cell<F>,
+    length_is_zero: IsZeroGadget<F>,
+    memory_expansion: MemoryExpansionGadget<F, MAX_GAS_SIZE_IN_BYTES>,
+    init_code_words: MemorySizeGadget<F>,
+    // Gas given to the init code: all but one 64th of the gas left after
+    // paying for the creation (EIP-150)
+    one_64th_gas: ConstantDivisionGadget<F, MAX_GAS_SIZE_IN_BYTES>,
+    // RLP encoding of the nonce of the caller
+    nonce_byte_size: ByteSizeGadget<F>,
+    nonce_is_zero: IsZeroGadget<F>,
+    nonce_lt_128: LtGadget<F, 8>,
+}
+
+impl<F: FieldExt> CreateSuccessCase<F> {
+    pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::Success,
+        // value + offset + length + salt + address_hash + caller_address +
+        // caller_value + caller_nonce + init_code_hash
+        num_word: 9
+            + MemoryExpansionGadget::<F, MAX_GAS_SIZE_IN_BYTES>::NUM_WORDS
+            + MemorySizeGadget::<F>::NUM_WORDS
+            + ConstantDivisionGadget::<F, MAX_GAS_SIZE_IN_BYTES>::NUM_WORDS
+            + ByteSizeGadget::<F>::NUM_WORDS
+            + LtGadget::<F, 8>::NUM_WORDS,
+        // is_success + tx_id + depth + caller_caller_address +
+        // caller_is_static + code_hash_prev + is_warm
+        num_cell: 7
+            + 2 * IsZeroGadget::<F>::NUM_CELLS
+            + MemoryExpansionGadget::<F, MAX_GAS_SIZE_IN_BYTES>::NUM_CELLS
+            + MemorySizeGadget::<F>::NUM_CELLS
+            + ConstantDivisionGadget::<F, MAX_GAS_SIZE_IN_BYTES>::NUM_CELLS
+            + ByteSizeGadget::<F>::NUM_CELLS
+            + LtGadget::<F, 8>::NUM_CELLS,
+        will_halt: false,
+    };
+
+    pub(crate) fn construct(
+        alloc: &mut CaseAllocation<F>,
+        is_create2: bool,
+    ) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+            is_create2,
+            r: alloc.r,
+            value: alloc.words.pop().unwrap(),
+            offset: alloc.words.pop().unwrap(),
+            length: alloc.words.pop().unwrap(),
+            salt: alloc.words.pop().unwrap(),
+            address_hash: alloc.words.pop().unwrap(),
+            caller_address: alloc.words.pop().unwrap(),
+            caller_value: alloc.words.pop().unwrap(),
+            caller_nonce: alloc.words.pop().unwrap(),
+            init_code_hash: alloc.words.pop().unwrap(),
+            is_success: alloc.cells.pop().unwrap(),
+            tx_id: alloc.cells.pop().unwrap(),
+            depth: alloc.cells.pop().unwrap(),
+            caller_caller_address: alloc.cells.pop().unwrap(),
+            caller_is_static: alloc.cells.pop().unwrap(),
+            code_hash_prev: alloc.cells.pop().unwrap(),
+            is_warm: alloc.cells.pop().unwrap(),
+            length_is_zero: IsZeroGadget::construct(alloc),
+            memory_expansion: MemoryExpansionGadget::construct(alloc),
+            init_code_words: MemorySizeGadget::construct(alloc),
+            one_64th_gas: ConstantDivisionGadget::construct(alloc, 64),
+            nonce_byte_size: ByteSizeGadget::construct(alloc),
+            nonce_is_zero: IsZeroGadget::construct(alloc),
+            nonce_lt_128: LtGadget::construct(alloc),
+        }
+    }
+
+    // Arguments popped from the stack
+    fn args(&self) -> Vec<&Word<F>> {
+        let mut args = vec![&self.value, &self.offset, &self.length];
+        if self.is_create2 {
+            args.push(&self.salt);
+        }
+        args
+    }
+
+    fn gc_delta(&self) -> usize {
+        self.args().len()
+            + 1
+            + NUM_ACCOUNT_OPS
+            + NUM_CALLER_READS
+            + NUM_CALLER_WRITES
+            + NUM_CALLEE_WRITES
+    }
+
+    // RLC and length of the input of keccak hashed into the address of the
+    // contract:
+    // - CREATE: `rlp([caller_address, caller_nonce])`
+    // - CREATE2: `0xff ++ caller_address ++ salt ++ init_code_hash`
+    fn address_hash_input(
+        &self,
+        cb: &mut ConstraintBuilder<F>,
+    ) -> (Expression<F>, Expression<F>) {
+        let r_pow = |exponent: u64| self.r.pow(&[exponent, 0, 0, 0]);
+        let caller_address =
+            rlc::expr(&self.caller_address.cells[..20], self.r);
+
+        if self.is_create2 {
+            let input = 0xff.expr() * r_pow(84)
+                + caller_address * r_pow(64)
+                + self.salt.expr() * r_pow(32)
+                + self.init_code_hash.expr();
+            return (input, 85.expr());
+        }
+
+        // A nonce below 128 is encoded as a single byte, which is 0x80 for
+        // zero, and a larger nonce as its big-endian bytes prefixed by 0x80
+        // plus their number.
+        let nonce = from_bytes::expr(self.caller_nonce.cells[..8].to_vec());
+        let nonce_byte_size =
+            self.nonce_byte_size.constraints(cb, &self.caller_nonce);
+        let nonce_is_zero = self.nonce_is_zero.constraints(cb, nonce.clone());
+        let nonce_lt_128 =
+            self.nonce_lt_128.constraints(cb, nonce.clone(), 128.expr());
+        let nonce_rlc = select::expr(
+            nonce_lt_128.clone(),
+            select::expr(nonce_is_zero, 0x80.expr(), nonce),
+            (0x80.expr() + nonce_byte_size.clone())
+                * self.nonce_byte_size.pow_expr(self.r)
+                + self.caller_nonce.expr(),
+        );
+        let nonce_length = select::expr(
+            nonce_lt_128.clone(),
+            1.expr(),
+            1.expr() + nonce_byte_size,
+        );
+        let r_pow_nonce_length = select::expr(
+            nonce_lt_128,
+            Expression::Constant(self.r),
+            self.nonce_byte_size.pow_expr(self.r) * self.r,
+        );
+
+        // The list is prefixed by 0xc0 plus the length of its payload, which
+        // is the address prefixed by 0x94 and the nonce.
+        let input = ((0xc0.expr() + 21.expr() + nonce_length.clone())
+            * r_pow(21)
+            + 0x94.expr() * r_pow(20)
+            + caller_address)
+            * r_pow_nonce_length
+            + nonce_rlc;
+        (input, 22.expr() + nonce_length)
+    }
+
+    pub(crate) fn constraint(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        state_next: &OpExecutionState<F>,
+        name: &'static str,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::default();
+
+        // TODO: Constrain the success of the init code, which also depends on
+        // the result of the callee.
+        cb.require_boolean(self.is_success.expr());
+        // A contract can't be created in a static call
+        cb.require_zero(self.caller_is_static.expr());
+        // TODO: Transfer the value to the contract
+        cb.require_zero(sum::expr(&self.value.cells));
+        cb.require_zero(sum::expr(&self.caller_address.cells[20..]));
+        cb.require_zero(sum::expr(&self.caller_nonce.cells[8..]));
+
+        // Pop the arguments and push the address of the contract on success
+        for arg in self.args() {
+            cb.stack_pop(arg.expr());
+        }
+        cb.stack_push(
+            self.is_success.expr()
+                * rlc::expr(&self.address_hash.cells[..20], self.r),
+        );
+
+        // The address of the contract is the keccak hash of the caller and its
+        // nonce, or of the caller, the salt and the hash of the init code.
+        let (input, input_length) = self.address_hash_input(&mut cb);
+        cb.add_keccak_lookup([input, input_length, self.address_hash.expr()]);
+        let address = from_bytes::expr(self.address_hash.cells[..20].to_vec());
+        let caller_address =
+            from_bytes::expr(self.caller_address.cells[..20].to_vec());
+
+        // Increment the nonce of the caller and create the account of the
+        // contract with nonce 1, marking it as warm (EIP-2929).  The contract
+        // account needs not to exist yet, which is when it has neither a nonce
+        // nor a code (EIP-684).
+        let caller_nonce =
+            from_bytes::expr(self.caller_nonce.cells[..8].to_vec());
+        cb.account_nonce_lookup(
+            true,
+            caller_address.clone(),
+            caller_nonce.clone() + 1.expr(),
+            caller_nonce,
+        );
+        cb.require_zero(
+            self.code_hash_prev.expr()
+                * (self.code_hash_prev.expr()
+                    - Expression::Constant(code_hash(&[], self.r))),
+        );
+        cb.account_code_hash_lookup(
+            false,
+            address.clone(),
+            self.code_hash_prev.expr(),
+            self.code_hash_prev.expr(),
+        );
+        cb.account_nonce_lookup(true, address.clone(), 1.expr(), 0.expr());
+        cb.require_boolean(self.is_warm.expr());
+        cb.tx_access_list_account_write(
+            self.tx_id.expr(),
+            address.clone(),
+            1.expr(),
+            self.is_warm.expr(),
+        );
+
+        // Read the context of the caller
+        for (field, value) in [
+            (CallContextField::TxId, self.tx_id.expr()),
+            (CallContextField::Depth, self.depth.expr()),
+            (
+                CallContextField::CallerAddress,
+                self.caller_caller_address.expr(),
+            ),
+            (CallContextField::CalleeAddress, caller_address.clone()),
+            (CallContextField::Value, self.caller_value.expr()),
+            (CallContextField::IsStatic, self.caller_is_static.expr()),
+        ] {
+            cb.call_context_lookup(
+                false,
+                state_curr.call_id.expr(),
+                field,
+                value,
+            );
+        }
+
+        // The memory is expanded for the init code, unless it's empty
+        let init_code_end = memory_region_end(
+            &mut cb,
+            &self.offset,
+            &self.length,
+            &self.length_is_zero,
+        );
+        let (next_memory_size, memory_cost) = self
+            .memory_expansion
+            .constraints(&mut cb, state_curr.memory_size.expr(), init_code_end);
+
+        // The caller pays for the creation, the memory expansion and the
+        // hashing of the init code by CREATE2, and gives all but one 64th of
+        // the remaining gas to the init code (EIP-150).
+        let gas_left = state_curr.gas_left.expr();
+        let base_cost = if self.is_create2 {
+            let init_code_words = self
+                .init_code_words
+                .constraints(&mut cb, address_low::expr(&self.length));
+            GasCost::CREATE.expr()
+                + memory_cost
+                + GasCost::SHA3_WORD.expr() * init_code_words
+        } else {
+            GasCost::CREATE.expr() + memory_cost
+        };
+        let remaining_gas = gas_left.clone() - base_cost.clone();
+        let (one_64th_gas, _) = self
+            .one_64th_gas
+            .constraints(&mut cb, remaining_gas.clone());
+        let callee_gas = remaining_gas - one_64th_gas;
+        let gas_cost = base_cost + callee_gas.clone();
+
+        // Save the state of the caller, which resumes after the CREATE with
+        // the address pushed on the stack, the gas left after paying for the
+        // creation, the expanded memory and its code. Its state writes include
+        // the nonce increase, which stays when the init code reverts, while
+        // the writes to the contract account are counted by the init code.
+        for (field, value) in [
+            (
+                CallContextField::ProgramCounter,
+                state_curr.program_counter.expr() + 1.expr(),
+            ),
+            (
+                CallContextField::StackPointer,
+                state_curr.stack_pointer.expr() + cb.stack_offset.expr(),
+            ),
+            (CallContextField::GasLeft, gas_left - gas_cost.clone()),
+            (CallContextField::MemorySize, next_memory_size * 32.expr()),
+            (
+                CallContextField::StateWriteCounter,
+                state_curr.state_write_counter.expr() + 1.expr(),
+            ),
+            (CallContextField::CodeHash, state_curr.code_source.expr()),
+        ] {
+            cb.call_context_lookup(
+                true,
+                state_curr.call_id.expr(),
+                field,
+                value,
+            );
+        }
+
+        // Set up the context of the callee, which runs the init code in the
+        // account of the contract without call data.
+        let gc_delta = self.gc_delta();
+        let callee_id =
+            state_curr.global_counter.expr() + (gc_delta - 1).expr();
+        for (field, value) in [
+            (CallContextField::CallerId, state_curr.call_id.expr()),
+            (CallContextField::TxId, self.tx_id.expr()),
+            (CallContextField::Depth, self.depth.expr() + 1.expr()),
+            (CallContextField::CallerAddress, caller_address),
+            (CallContextField::CalleeAddress, address.clone()),
+            (CallContextField::CodeAddress, address),
+            (CallContextField::CallDataOffset, 0.expr()),
+            (CallContextField::CallDataLength, 0.expr()),
+            (CallContextField::ReturnDataOffset, 0.expr()),
+            (CallContextField::ReturnDataLength, 0.expr()),
+            (CallContextField::Value, self.value.expr()),
+            (CallContextField::IsStatic, 0.expr()),
+            (CallContextField::IsSuccess, self.is_success.expr()),
+            (CallContextField::Gas, callee_gas.clone()),
+        ] {
+            cb.call_context_lookup(true, callee_id.clone(), field, value);
+        }
+
+        // State transitions to the first step of the init code
+        // TODO: Constrain the hash of the init code read from the memory.
+        cb.require_equal(state_next.call_id.expr(), callee_id);
+        let st = StateTransitionExpressions {
+            gc_delta: Some(gc_delta.expr()),
+            pc_delta: Some(0.expr() - state_curr.program_counter.expr()),
+            sp_delta: Some(1024.expr() - state_curr.stack_pointer.expr()),
+            gas_delta: Some(gas_cost),
+            next_memory_size: Some(0.expr()),
+            next_code_source: Some(self.init_code_hash.expr()),
+            log_id_delta: None,
+            next_gas_left: Some(callee_gas),
+            // The nonce and the access list writes of the contract are done on
+            // behalf of the init code, so they're undone when it fails
+            next_state_write_counter: Some(2.expr()),
+        };
+        st.constraints(&mut cb, state_curr, state_next);
+
+        // Generate the constraint
+        vec![cb.constraint(self.case_selector.expr(), name)]
+    }
+
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: &mut CoreStateInstance,
+        step: &ExecutionStep,
+    ) -> Result<(), Error> {
+        // Inputs and output
+        let args = self.args();
+        for (word, value) in args.iter().zip(step.values.iter()) {
+            word.assign(region, offset, Some(value.to_word()))?;
+        }
+        if !self.is_create2 {
+            self.salt.assign(region, offset, Some([0u8; 32]))?;
+        }
+        let is_success = step.values[args.len()] != 0u64.into();
+        self.is_success.assign(
+            region,
+            offset,
+            Some(F::from(is_success as u64)),
+        )?;
+
+        // Values of the call context operations, followed by the gas cost of
+        // the step, the nonce of the caller, the hash of the init code, the
+        // hash of the address of the contract, the code hash of the contract
+        // before the creation, whether the contract was warm and the input of
+        // keccak hashed into the address
+        let values = &step.values[args.len() + 1..];
+        let value_u64 =
+            |idx: usize| -> u64 { values[idx].clone().try_into().unwrap() };
+        self.tx_id
+            .assign(region, offset, Some(F::from(value_u64(0))))?;
+        self.depth
+            .assign(region, offset, Some(F::from(value_u64(1))))?;
+        self.caller_caller_address.assign(
+            region,
+            offset,
+            Some(from_bytes::value(values[2].to_word()[..20].to_vec())),
+        )?;
+        self.caller_address.assign(
+            region,
+            offset,
+            Some(values[3].to_word()),
+        )?;
+        self.caller_value
+            .assign(region, offset, Some(values[4].to_word()))?;
+        self.caller_is_static.assign(
+            region,
+            offset,
+            Some(F::from(value_u64(5))),
+        )?;
+        let num_context_ops =
+            NUM_CALLER_READS + NUM_CALLER_WRITES + NUM_CALLEE_WRITES;
+        let gas_cost = value_u64(num_context_ops);
+        let caller_nonce = values[num_context_ops + 1].to_word();
+        self.caller_nonce
+            .assign(region, offset, Some(caller_nonce))?;
+        let init_code_hash = values[num_context_ops + 2].to_word();
+        self.init_code_hash
+            .assign(region, offset, Some(init_code_hash))?;
+        self.address_hash.assign(
+            region,
+            offset,
+            Some(values[num_context_ops + 3].to_word()),
+        )?;
+        self.code_hash_prev.assign(
+            region,
+            offset,
+            Some(rlc::value(&values[num_context_ops + 4].to_word(), self.r)),
+        )?;
+        self.is_warm.assign(
+            region,
+            offset,
+            Some(F::from(value_u64(num_context_ops + 5))),
+        )?;
+
+        // RLP encoding of the nonce of the caller
+        let nonce = value_u64(num_context_ops + 1);
+        self.nonce_byte_size.assign(region, offset, caller_nonce)?;
+        self.nonce_is_zero.assign(region, offset, F::from(nonce))?;
+        self.nonce_lt_128.assign(
+            region,
+            offset,
+            F::from(nonce),
+            F::from(128),
+        )?;
+
+        // Memory expansion of the init code, and gas given to the init code
+        let memory_offset = address_low::value::<F>(step.values[1].to_word());
+        let length = address_low::value::<F>(step.values[2].to_word());
+        let length_is_zero =
+            self.length_is_zero
+                .assign(region, offset, F::from(length))?;
+        let address = if length_is_zero == F::one() {
+            0
+        } else {
+            memory_offset + length
+        };
+        let (_, memory_cost) = self.memory_expansion.assign(
+            region,
+            offset,
+            state.memory_size,
+            address,
+        )?;
+        let init_code_words =
+            self.init_code_words.assign(region, offset, length)?;
+        let base_cost = GasCost::CREATE.as_u64()
+            + memory_cost as u64
+            + if self.is_create2 {
+                GasCost::SHA3_WORD.as_u64() * init_code_words
+            } else {
+                0
+            };
+        let remaining_gas = state.gas_left - base_cost;
+        let (one_64th_gas, _) =
+            self.one_64th_gas
+                .assign(region, offset, remaining_gas as u128)?;
+
+        // State transitions to the first step of the init code
+        state.global_counter += self.gc_delta();
+        state.call_id = state.global_counter - 1;
+        state.program_counter = 0;
+        state.stack_pointer = 1024;
+        state.gas_counter += gas_cost;
+        state.gas_left = remaining_gas - one_64th_gas as u64;
+        state.memory_size = 0;
+        state.code_source = init_code_hash;
+        state.state_write_counter = 2;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::test::test_circuit_from_block;
+    use bus_mapping::{
+        bytecode,
+        bytecode::Bytecode,
+        circuit_input_builder::CircuitInputBuilder,
+        eth_types::{Address, Word, H256},
+        mock,
+        state_db::Account,
+    };
+    use halo2::dev::MockProver;
+    use pairing::bn256::Fr as Fp;
+    use std::collections::HashMap;
+
+    fn test_create_gadget(code: Bytecode) {
+        // The init code is the single zero byte of memory, which is STOP, so
+        // the init code halts at once and resumes the caller.
+        let block = mock::BlockData::new_single_tx_trace_code(&code).unwrap();
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.sdb.set_account(
+            &Address::zero(),
+            Account {
+                nonce: Word::zero(),
+                balance: Word::from(555u64),
+                storage: HashMap::new(),
+                codeHash: H256::zero(),
+            },
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let circuit = test_circuit_from_block::<Fp>(&builder.block);
+        let prover = MockProver::<Fp>::run(11, &circuit, vec![vec![]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    fn create_gadget_from_trace() {
+        test_create_gadget(bytecode! {
+            PUSH1(0x01) // length
+            PUSH1(0x00) // offset
+            PUSH1(0x00) // value
+            CREATE
+        });
+    }
+
+    #[test]
+    fn create2_gadget_from_trace() {
+        test_create_gadget(bytecode! {
+            PUSH1(0x42) // salt
+            PUSH1(0x01) // length
+            PUSH1(0x00) // offset
+            PUSH1(0x00) // value
+            CREATE2
+        });
+    }
+}
@@ -13,7 +13,6 @@ use crate::util::{Expr, ToWord};
 use bus_mapping::evm::{GasCost, OpcodeId};
 use halo2::plonk::Error;
 use halo2::{arithmetic::FieldExt, circuit::Region};
-use std::convert::TryInto;
 
 static STATE_TRANSITION: StateTransition = StateTransition {
     gc_delta: Some(2), // 1 stack read + 1 stack push
@@ -114,7 +113,8 @@ mod test {
         ($execution_steps:expr, $operations:expr, $result:expr) => {{
             let circuit =
                 TestCircuit::<Fp>::new($execution_steps, $operations, false);
-            let prover = MockProver::<Fp>::run(11, &circuit, vec![]).unwrap();
+            let prover =
+                MockProver::<Fp>::run(11, &circuit, vec![vec![]]).unwrap();
             assert_eq!(prover.verify(), $result);
         }};
     }
@@ -155,6 +155,8 @@ mod test {
                         Fp::from(1023),
                         Fp::from(4 + 5 + 6),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
                 Operation {
@@ -166,6 +168,8 @@ mod test {
                         Fp::from(1022),
                         Fp::from(3),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
                 Operation {
@@ -177,6 +181,8 @@ mod test {
                         Fp::from(1023),
                         Fp::from(4 + 5 + 6),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
                 Operation {
@@ -188,6 +194,8 @@ mod test {
                         Fp::from(1021),
                         Fp::from(4 + 5 + 6),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 }
             ],
@@ -231,6 +239,8 @@ mod test {
                         Fp::from(1023),
                         Fp::from(4 + 5 + 6),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
                 Operation {
@@ -242,6 +252,8 @@ mod test {
                         Fp::from(1022),
                         Fp::from(3),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
                 Operation {
@@ -253,6 +265,8 @@ mod test {
                         Fp::from(1022),
                         Fp::from(3),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
                 Operation {
@@ -264,6 +278,8 @@ mod test {
                         Fp::from(1021),
                         Fp::from(3),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 }
             ],
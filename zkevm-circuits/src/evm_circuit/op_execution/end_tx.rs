@@ -0,0 +1,364 @@
+use super::super::{
+    Case, Cell, Constraint, TxContextField, TxExecutionStep, Word,
+};
+use super::utils::{
+    constraint_builder::ConstraintBuilder,
+    from_bytes,
+    math_gadgets::{LtGadget, MulAddWordsGadget, RangeCheckGadget},
+    select, word_limbs,
+};
+use super::{
+    CaseAllocation, CaseConfig, CoreStateInstance, OpExecutionState, TxGadget,
+};
+use crate::util::{Expr, ToWord};
+use bus_mapping::{
+    evm::MAX_REFUND_QUOTIENT_OF_GAS_USED, operation::CallContextField,
+};
+use halo2::plonk::Error;
+use halo2::{arithmetic::FieldExt, circuit::Region};
+use num::{BigUint, ToPrimitive};
+
+// 1 call context read of the tx id + 1 refund read + 2 balance writes
+// (sender refund and coinbase reward)
+const GC_DELTA: usize = 4;
+// 1 nonce write + 1 balance write + 1 code hash write resetting an account
+// destroyed by SELFDESTRUCT
+const GC_DELTA_DESTRUCTED: usize = 3;
+
+// EndTxGadget verifies the state changes done by a transaction after the
+// execution of its last step, with the gas used taken from the gas counter:
+// - the refund counter is capped to a fifth of the gas used (EIP-3529)
+// - the gas left and the capped refund are paid back to the sender at the gas
+//   price
+// - the rest of the gas used is paid to the coinbase at the effective tip
+//   (EIP-1559)
+// - the nonce, the balance and the code hash of the account destroyed by
+//   SELFDESTRUCT, if any, are reset
+// The sender, the gas and the gas price are the ones of the transaction of the
+// root call in the tx table, and the id of the transaction is kept in the call
+// id until the next one begins.
+#[derive(Clone, Debug)]
+pub(crate) struct EndTxGadget<F> {
+    case_selector: Cell<F>,
+    tx_id: Cell<F>,
+    sender: Cell<F>,
+    coinbase: Cell<F>,
+    gas: Cell<F>,
+    refund: Cell<F>,
+    max_refund: Cell<F>,
+    max_refund_remainder: Cell<F>,
+    gas_price: Word<F>,
+    effective_tip: Word<F>,
+    sender_balance_prev: Word<F>,
+    sender_balance: Word<F>,
+    coinbase_balance_prev: Word<F>,
+    coinbase_balance: Word<F>,
+    is_destructed: Cell<F>,
+    destructed_address: Cell<F>,
+    destructed_nonce_prev: Cell<F>,
+    destructed_balance_prev: Word<F>,
+    destructed_code_hash_prev: Word<F>,
+    max_refund_range_check: RangeCheckGadget<F, 8>,
+    gas_left_range_check: RangeCheckGadget<F, 8>,
+    refund_lt_max_refund: LtGadget<F, 8>,
+    sender_refund: MulAddWordsGadget<F>,
+    coinbase_reward: MulAddWordsGadget<F>,
+}
+
+impl<F: FieldExt> TxGadget<F> for EndTxGadget<F> {
+    const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::Success,
+        // gas_price + effective_tip + sender and coinbase balances +
+        // destructed_balance_prev + destructed_code_hash_prev
+        num_word: 8,
+        num_cell: 7 // tx_id + sender + coinbase + gas + refund + max_refund
+            + 1 // remainder
+            + 3 // is_destructed + destructed_address + destructed_nonce_prev
+            + 2 * RangeCheckGadget::<F, 8>::NUM_CELLS
+            + LtGadget::<F, 8>::NUM_CELLS
+            + 2 * MulAddWordsGadget::<F>::NUM_CELLS,
+        will_halt: false,
+    };
+
+    fn construct(mut alloc: CaseAllocation<F>) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+            tx_id: alloc.cells.pop().unwrap(),
+            sender: alloc.cells.pop().unwrap(),
+            coinbase: alloc.cells.pop().unwrap(),
+            gas: alloc.cells.pop().unwrap(),
+            refund: alloc.cells.pop().unwrap(),
+            max_refund: alloc.cells.pop().unwrap(),
+            max_refund_remainder: alloc.cells.pop().unwrap(),
+            gas_price: alloc.words.pop().unwrap(),
+            effective_tip: alloc.words.pop().unwrap(),
+            sender_balance_prev: alloc.words.pop().unwrap(),
+            sender_balance: alloc.words.pop().unwrap(),
+            coinbase_balance_prev: alloc.words.pop().unwrap(),
+            coinbase_balance: alloc.words.pop().unwrap(),
+            is_destructed: alloc.cells.pop().unwrap(),
+            destructed_address: alloc.cells.pop().unwrap(),
+            destructed_nonce_prev: alloc.cells.pop().unwrap(),
+            destructed_balance_prev: alloc.words.pop().unwrap(),
+            destructed_code_hash_prev: alloc.words.pop().unwrap(),
+            max_refund_range_check: RangeCheckGadget::construct(&mut alloc),
+            gas_left_range_check: RangeCheckGadget::construct(&mut alloc),
+            refund_lt_max_refund: LtGadget::construct(&mut alloc),
+            sender_refund: MulAddWordsGadget::construct(&mut alloc),
+            coinbase_reward: MulAddWordsGadget::construct(&mut alloc),
+        }
+    }
+
+    fn constraints(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        state_next: &OpExecutionState<F>,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::default();
+
+        // TODO: Lookup the coinbase and the base fee in the block table, to
+        // also constrain the effective tip.
+
+        // The transaction is the one of the root call, which is the current
+        // call after its last step
+        cb.call_context_lookup(
+            false,
+            state_curr.call_id.expr(),
+            CallContextField::TxId,
+            self.tx_id.expr(),
+        );
+        for (field, value) in [
+            (TxContextField::Origin, self.sender.expr()),
+            (TxContextField::Gas, self.gas.expr()),
+            (TxContextField::GasPrice, self.gas_price.expr()),
+        ] {
+            cb.tx_context_lookup(self.tx_id.expr(), field, value);
+        }
+
+        // The gas counter holds the gas used by the whole transaction, which
+        // can't be more than its gas
+        let gas_used = state_curr.gas_counter.expr();
+        let gas_left = self.gas.expr() - gas_used.clone();
+        self.gas_left_range_check
+            .constraints(&mut cb, gas_left.clone());
+
+        // max_refund == gas_used / MAX_REFUND_QUOTIENT_OF_GAS_USED
+        self.max_refund_range_check
+            .constraints(&mut cb, self.max_refund.expr());
+        cb.require_in_set(
+            self.max_refund_remainder.expr(),
+            (0..MAX_REFUND_QUOTIENT_OF_GAS_USED)
+                .map(|remainder| remainder.expr())
+                .collect(),
+        );
+        cb.require_equal(
+            gas_used.clone(),
+            self.max_refund.expr() * MAX_REFUND_QUOTIENT_OF_GAS_USED.expr()
+                + self.max_refund_remainder.expr(),
+        );
+
+        // effective_refund == min(refund, max_refund)
+        cb.tx_refund_read(self.refund.expr());
+        let refund_lt_max_refund = self.refund_lt_max_refund.constraints(
+            &mut cb,
+            self.refund.expr(),
+            self.max_refund.expr(),
+        );
+        let effective_refund = select::expr(
+            refund_lt_max_refund,
+            self.refund.expr(),
+            self.max_refund.expr(),
+        );
+
+        // Refund the sender:
+        // gas_price * (gas_left + effective_refund) + sender_balance_prev ==
+        // sender_balance
+        let overflow = self.sender_refund.constraints(
+            &mut cb,
+            &word_limbs::expr(&self.gas_price),
+            &[
+                gas_left + effective_refund.clone(),
+                0.expr(),
+                0.expr(),
+                0.expr(),
+            ],
+            &word_limbs::expr(&self.sender_balance_prev),
+            &word_limbs::expr(&self.sender_balance),
+        );
+        cb.require_zero(overflow);
+        cb.account_balance_lookup(
+            true,
+            self.sender.expr(),
+            self.sender_balance.expr(),
+            self.sender_balance_prev.expr(),
+        );
+
+        // Reward the coinbase:
+        // effective_tip * (gas_used - effective_refund) + coinbase_balance_prev
+        // == coinbase_balance
+        let overflow = self.coinbase_reward.constraints(
+            &mut cb,
+            &word_limbs::expr(&self.effective_tip),
+            &[gas_used - effective_refund, 0.expr(), 0.expr(), 0.expr()],
+            &word_limbs::expr(&self.coinbase_balance_prev),
+            &word_limbs::expr(&self.coinbase_balance),
+        );
+        cb.require_zero(overflow);
+        cb.account_balance_lookup(
+            true,
+            self.coinbase.expr(),
+            self.coinbase_balance.expr(),
+            self.coinbase_balance_prev.expr(),
+        );
+
+        // Reset the account destroyed by SELFDESTRUCT, whose storage is
+        // dropped with it.
+        // TODO: The destroyed account should be bound to the SELFDESTRUCT
+        // step halting the root call.
+        cb.require_boolean(self.is_destructed.expr());
+        cb.condition(self.is_destructed.expr(), |cb| {
+            cb.account_nonce_lookup(
+                true,
+                self.destructed_address.expr(),
+                0.expr(),
+                self.destructed_nonce_prev.expr(),
+            );
+            cb.account_balance_lookup(
+                true,
+                self.destructed_address.expr(),
+                0.expr(),
+                self.destructed_balance_prev.expr(),
+            );
+            cb.account_code_hash_lookup(
+                true,
+                self.destructed_address.expr(),
+                0.expr(),
+                self.destructed_code_hash_prev.expr(),
+            );
+        });
+
+        // The id of the transaction is kept in the call id until the next
+        // one begins
+        cb.require_equal(
+            state_next.global_counter.expr(),
+            state_curr.global_counter.expr()
+                + GC_DELTA.expr()
+                + self.is_destructed.expr() * GC_DELTA_DESTRUCTED.expr(),
+        );
+        cb.require_equal(state_next.call_id.expr(), self.tx_id.expr());
+
+        vec![cb.constraint(self.case_selector.expr(), "EndTx")]
+    }
+
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: &mut CoreStateInstance,
+        step: &TxExecutionStep,
+    ) -> Result<(), Error> {
+        for (cell, value) in [
+            (&self.sender, &step.values[0]),
+            (&self.coinbase, &step.values[1]),
+        ] {
+            cell.assign(
+                region,
+                offset,
+                Some(from_bytes::value(value.to_word()[..20].to_vec())),
+            )?;
+        }
+
+        let tx_id = step.values[10].to_u64().unwrap();
+        let is_destructed = step.values[11].to_u64().unwrap();
+        for (cell, value) in [
+            (&self.tx_id, tx_id),
+            (&self.is_destructed, is_destructed),
+            (
+                &self.destructed_nonce_prev,
+                step.values[13].to_u64().unwrap(),
+            ),
+        ] {
+            cell.assign(region, offset, Some(F::from(value)))?;
+        }
+        self.destructed_address.assign(
+            region,
+            offset,
+            Some(from_bytes::value(step.values[12].to_word()[..20].to_vec())),
+        )?;
+        for (word, value) in [
+            (&self.destructed_balance_prev, &step.values[14]),
+            (&self.destructed_code_hash_prev, &step.values[15]),
+        ] {
+            word.assign(region, offset, Some(value.to_word()))?;
+        }
+
+        let gas = step.values[2].to_u64().unwrap();
+        let refund = step.values[5].to_u64().unwrap();
+        let gas_used = state.gas_counter;
+        let gas_left = gas - gas_used;
+        let max_refund = gas_used / MAX_REFUND_QUOTIENT_OF_GAS_USED;
+        let effective_refund = refund.min(max_refund);
+        for (cell, value) in [
+            (&self.gas, gas),
+            (&self.refund, refund),
+            (&self.max_refund, max_refund),
+            (
+                &self.max_refund_remainder,
+                gas_used % MAX_REFUND_QUOTIENT_OF_GAS_USED,
+            ),
+        ] {
+            cell.assign(region, offset, Some(F::from(value)))?;
+        }
+        self.max_refund_range_check.assign(
+            region,
+            offset,
+            F::from(max_refund),
+        )?;
+        self.gas_left_range_check
+            .assign(region, offset, F::from(gas_left))?;
+        self.refund_lt_max_refund.assign(
+            region,
+            offset,
+            F::from(refund),
+            F::from(max_refund),
+        )?;
+
+        let gas_price = step.values[3].to_word();
+        let effective_tip = step.values[4].to_word();
+        let sender_balance_prev = step.values[6].to_word();
+        let sender_balance = step.values[7].to_word();
+        let coinbase_balance_prev = step.values[8].to_word();
+        let coinbase_balance = step.values[9].to_word();
+        for (word, value) in [
+            (&self.gas_price, gas_price),
+            (&self.effective_tip, effective_tip),
+            (&self.sender_balance_prev, sender_balance_prev),
+            (&self.sender_balance, sender_balance),
+            (&self.coinbase_balance_prev, coinbase_balance_prev),
+            (&self.coinbase_balance, coinbase_balance),
+        ] {
+            word.assign(region, offset, Some(value))?;
+        }
+
+        self.sender_refund.assign(
+            region,
+            offset,
+            gas_price,
+            BigUint::from(gas_left + effective_refund).to_word(),
+            sender_balance_prev,
+        )?;
+        self.coinbase_reward.assign(
+            region,
+            offset,
+            effective_tip,
+            BigUint::from(gas_used - effective_refund).to_word(),
+            coinbase_balance_prev,
+        )?;
+
+        state.global_counter +=
+            GC_DELTA + is_destructed as usize * GC_DELTA_DESTRUCTED;
+        state.call_id = tx_id as usize;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,264 @@
+use super::super::{Case, Cell, Constraint, ExecutionStep, Word};
+use super::utils::{
+    self,
+    common_cases::{
+        account_access_cost, AccountAccessOutOfGasCase, StackUnderflowCase,
+    },
+    constraint_builder::ConstraintBuilder,
+    from_bytes,
+    math_gadgets::IsZeroGadget,
+    memory_gadgets::{self, address_low},
+    sum, StateTransition, StateTransitionExpressions,
+};
+use super::{
+    CaseAllocation, CaseConfig, CoreStateInstance, OpExecutionState, OpGadget,
+};
+use crate::impl_op_gadget;
+use crate::util::{Expr, ToWord};
+use bus_mapping::{
+    evm::{GasCost, OpcodeId},
+    operation::CallContextField,
+};
+use halo2::plonk::Error;
+use halo2::{arithmetic::FieldExt, circuit::Region};
+use std::convert::TryFrom;
+
+static STATE_TRANSITION: StateTransition = StateTransition {
+    // 1 call context read + 2 stack ops + 1 access list write + 1 account
+    // code hash read
+    gc_delta: Some(5),
+    pc_delta: Some(1),
+    sp_delta: Some(0),
+    gas_delta: None, // WARM_STORAGE_READ_COST or COLD_ACCOUNT_ACCESS_COST
+    next_memory_size: None,
+};
+const NUM_POPPED: usize = 1;
+
+impl_op_gadget!(
+    #set[EXTCODESIZE]
+    ExtcodesizeGadget {
+        ExtcodesizeSuccessCase(),
+        StackUnderflowCase(NUM_POPPED),
+        AccountAccessOutOfGasCase(),
+    }
+);
+
+#[derive(Clone, Debug)]
+struct ExtcodesizeSuccessCase<F> {
+    case_selector: Cell<F>,
+    tx_id: Cell<F>,
+    is_warm: Cell<F>,
+    code_rlc: Cell<F>,
+    address: Word<F>,
+    code_hash: Word<F>,
+    code_size: Word<F>,
+    is_code_hash_zero: IsZeroGadget<F>,
+}
+
+impl<F: FieldExt> ExtcodesizeSuccessCase<F> {
+    pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::Success,
+        num_word: 3, // address + code_hash + code_size
+        num_cell: 3 // tx_id + is_warm + code_rlc
+            + IsZeroGadget::<F>::NUM_CELLS,
+        will_halt: false,
+    };
+
+    pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+            tx_id: alloc.cells.pop().unwrap(),
+            is_warm: alloc.cells.pop().unwrap(),
+            code_rlc: alloc.cells.pop().unwrap(),
+            address: alloc.words.pop().unwrap(),
+            code_hash: alloc.words.pop().unwrap(),
+            code_size: alloc.words.pop().unwrap(),
+            is_code_hash_zero: IsZeroGadget::construct(alloc),
+        }
+    }
+
+    pub(crate) fn constraint(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        state_next: &OpExecutionState<F>,
+        name: &'static str,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::default();
+
+        cb.call_context_lookup(
+            false,
+            state_curr.call_id.expr(),
+            CallContextField::TxId,
+            self.tx_id.expr(),
+        );
+        cb.require_boolean(self.is_warm.expr());
+
+        // Pop the address from the stack and mark the account, which is given
+        // by the lower 20 bytes of the popped word, as warm
+        cb.stack_pop(self.address.expr());
+        let address = from_bytes::expr(self.address.cells[..20].to_vec());
+        cb.tx_access_list_account_write(
+            self.tx_id.expr(),
+            address.clone(),
+            1.expr(),
+            self.is_warm.expr(),
+        );
+
+        // Read the code hash of the account and push the size of its code on
+        // the stack.  The size of a code is the length of the keccak input
+        // hashed into its code hash, and the code of an empty account, whose
+        // code hash is 0, has size 0.
+        cb.account_code_hash_lookup(
+            false,
+            address,
+            self.code_hash.expr(),
+            self.code_hash.expr(),
+        );
+        memory_gadgets::require_address_in_range(&mut cb, &self.code_size);
+        let code_size = address_low::expr(&self.code_size);
+        let is_code_hash_zero = self
+            .is_code_hash_zero
+            .constraints(&mut cb, sum::expr(&self.code_hash.cells));
+        cb.condition(is_code_hash_zero.clone(), |cb| {
+            cb.require_zero(code_size.clone());
+        });
+        cb.condition(1.expr() - is_code_hash_zero, |cb| {
+            cb.add_keccak_lookup([
+                self.code_rlc.expr(),
+                code_size,
+                self.code_hash.expr(),
+            ]);
+        });
+        cb.stack_push(self.code_size.expr());
+
+        // State transitions
+        // - `gas_delta` needs to be increased by `WARM_STORAGE_READ_COST` when
+        //   the account is warm, and by `COLD_ACCOUNT_ACCESS_COST` otherwise
+        //   (EIP-2929)
+        let mut st = StateTransitionExpressions::new(STATE_TRANSITION.clone());
+        st.gas_delta = Some(account_access_cost(&self.is_warm));
+        st.constraints(&mut cb, state_curr, state_next);
+
+        // Generate the constraint
+        vec![cb.constraint(self.case_selector.expr(), name)]
+    }
+
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: &mut CoreStateInstance,
+        step: &ExecutionStep,
+    ) -> Result<(), Error> {
+        // Input and output, followed by the transaction id, the warm status
+        // of the account, its code hash and the random linear combination of
+        // its code
+        let code_hash = step.values[4].to_word();
+        for (word, value) in [
+            (&self.address, step.values[0].to_word()),
+            (&self.code_size, step.values[1].to_word()),
+            (&self.code_hash, code_hash),
+        ] {
+            word.assign(region, offset, Some(value))?;
+        }
+        let tx_id = u64::try_from(&step.values[2]).unwrap();
+        self.tx_id.assign(region, offset, Some(F::from(tx_id)))?;
+        let is_warm = step.values[3] == 1u64.into();
+        self.is_warm
+            .assign(region, offset, Some(F::from(is_warm as u64)))?;
+        self.code_rlc.assign(
+            region,
+            offset,
+            Some(F::from_bytes(&step.values[5].to_word()).unwrap()),
+        )?;
+        self.is_code_hash_zero.assign(
+            region,
+            offset,
+            sum::value(&code_hash),
+        )?;
+
+        // State transitions
+        let mut st = STATE_TRANSITION.clone();
+        st.gas_delta = Some(if is_warm {
+            GasCost::WARM_STORAGE_READ_COST.as_u64()
+        } else {
+            GasCost::COLD_ACCOUNT_ACCESS_COST.as_u64()
+        });
+        st.assign(state);
+        // The access list write of the account is a state write
+        state.state_write_counter += 1;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::test::test_circuit_from_block;
+    use bus_mapping::{
+        bytecode, bytecode::Bytecode,
+        circuit_input_builder::CircuitInputBuilder, eth_types::Address, mock,
+    };
+    use halo2::dev::MockProver;
+    use pairing::bn256::Fr as Fp;
+
+    fn test_extcodesize_gadget(code: Bytecode, callee_code: Bytecode) {
+        let block =
+            mock::BlockData::new_single_tx_trace_code_2(&code, &callee_code)
+                .unwrap();
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.set_code(&Address::zero(), code.to_bytes());
+        builder
+            .set_code(&Address::from_low_u64_be(0x123), callee_code.to_bytes());
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let circuit = test_circuit_from_block::<Fp>(&builder.block);
+        let prover = MockProver::<Fp>::run(11, &circuit, vec![vec![]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    fn extcodesize_gadget_from_trace() {
+        // The first EXTCODESIZE accesses the contract itself, which is warm,
+        // the second one a cold contract, and the last one an empty account
+        let code = bytecode! {
+            PUSH1(0x00)
+            EXTCODESIZE
+            PUSH2(0x123)
+            EXTCODESIZE
+            PUSH2(0x456)
+            EXTCODESIZE
+            STOP
+        };
+        let callee_code = bytecode! {
+            PUSH2(0xaabb)
+            STOP
+        };
+        test_extcodesize_gadget(code, callee_code);
+    }
+
+    #[test]
+    fn extcodesize_gadget_out_of_gas() {
+        // The callee doesn't have the gas to access a cold account
+        let code = bytecode! {
+            PUSH1(0x00) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x00) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH1(0x00) // value
+            PUSH2(0x123) // addr
+            PUSH2(0x800) // gas
+            CALL
+            STOP
+        };
+        let callee_code = bytecode! {
+            PUSH2(0x456)
+            EXTCODESIZE
+            STOP
+        };
+        test_extcodesize_gadget(code, callee_code);
+    }
+}
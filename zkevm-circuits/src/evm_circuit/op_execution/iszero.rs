@@ -0,0 +1,147 @@
+use super::super::{Case, Cell, Constraint, ExecutionStep, Word};
+use super::utils::{
+    self,
+    common_cases::{OutOfGasCase, StackUnderflowCase},
+    constraint_builder::ConstraintBuilder,
+    math_gadgets::IsZeroGadget,
+    sum, StateTransition,
+};
+use super::{
+    CaseAllocation, CaseConfig, CoreStateInstance, OpExecutionState, OpGadget,
+};
+use crate::impl_op_gadget;
+use crate::util::{Expr, ToWord};
+use bus_mapping::evm::{GasCost, OpcodeId};
+use halo2::plonk::Error;
+use halo2::{arithmetic::FieldExt, circuit::Region};
+
+static STATE_TRANSITION: StateTransition = StateTransition {
+    gc_delta: Some(2), // 1 stack pop + 1 stack push
+    pc_delta: Some(1),
+    sp_delta: Some(0),
+    gas_delta: Some(GasCost::FASTEST.as_u64()),
+    next_memory_size: None,
+};
+const NUM_POPPED: usize = 1;
+
+impl_op_gadget!(
+    #set[ISZERO]
+    IszeroGadget {
+        IszeroSuccessCase(),
+        StackUnderflowCase(NUM_POPPED),
+        OutOfGasCase(STATE_TRANSITION.gas_delta.unwrap()),
+    }
+);
+
+#[derive(Clone, Debug)]
+struct IszeroSuccessCase<F> {
+    case_selector: Cell<F>,
+    value: Word<F>,
+    is_zero: IsZeroGadget<F>,
+}
+
+impl<F: FieldExt> IszeroSuccessCase<F> {
+    pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::Success,
+        num_word: 1, // value
+        num_cell: IsZeroGadget::<F>::NUM_CELLS,
+        will_halt: false,
+    };
+
+    pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+            value: alloc.words.pop().unwrap(),
+            is_zero: IsZeroGadget::construct(alloc),
+        }
+    }
+
+    pub(crate) fn constraint(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        state_next: &OpExecutionState<F>,
+        name: &'static str,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::default();
+
+        // The sum of the bytes is zero only when all bytes are zero, and it
+        // can't wrap around the field because it's at most 32 * 255.
+        let is_zero = self
+            .is_zero
+            .constraints(&mut cb, sum::expr(&self.value.cells));
+
+        // Pop the value from the stack, push the result on the stack.
+        // We can push the result here directly because it only uses the LSB
+        // of a word.
+        cb.stack_pop(self.value.expr());
+        cb.stack_push(is_zero);
+
+        // State transitions
+        STATE_TRANSITION.constraints(&mut cb, state_curr, state_next);
+
+        // Generate the constraint
+        vec![cb.constraint(self.case_selector.expr(), name)]
+    }
+
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: &mut CoreStateInstance,
+        step: &ExecutionStep,
+    ) -> Result<(), Error> {
+        // Input
+        let value = step.values[0].to_word();
+        self.value.assign(region, offset, Some(value))?;
+
+        self.is_zero
+            .assign(region, offset, sum::value::<F>(&value))?;
+
+        // State transitions
+        STATE_TRANSITION.assign(state);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::test::run_test_circuit_from_code;
+    use bus_mapping::{bytecode, eth_types::Word, word};
+    use pairing::bn256::Fr as Fp;
+
+    fn check(value: Word) {
+        let code = bytecode! {
+            PUSH32(value)
+            ISZERO
+            STOP
+        };
+        assert_eq!(run_test_circuit_from_code::<Fp>(&code), Ok(()));
+    }
+
+    #[test]
+    fn iszero_gadget() {
+        // iszero(0) == 1
+        check(Word::zero());
+        // iszero(1) == 0
+        check(Word::one());
+        // iszero(2**255) == 0
+        check(Word::one() << 255);
+        // iszero(max) == 0
+        check(Word::MAX);
+    }
+
+    #[test]
+    fn iszero_gadget_from_trace() {
+        let code = bytecode! {
+            PUSH32(0)
+            ISZERO
+            PUSH32(word!("ff00000000000000000000000000000000000000000000000000000000000001"))
+            ISZERO
+            EQ
+            ISZERO
+            STOP
+        };
+        assert_eq!(run_test_circuit_from_code::<Fp>(&code), Ok(()));
+    }
+}
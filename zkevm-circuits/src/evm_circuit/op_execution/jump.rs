@@ -5,7 +5,9 @@ use super::utils::{
     self,
     common_cases::{OutOfGasCase, StackUnderflowCase},
     constraint_builder::ConstraintBuilder,
-    from_bytes, StateTransition, StateTransitionExpressions,
+    from_bytes,
+    math_gadgets::{IsEqualGadget, IsZeroGadget, LtGadget},
+    sum, StateTransition, StateTransitionExpressions,
 };
 use super::{CaseAllocation, CaseConfig, OpExecutionState, OpGadget};
 use crate::impl_op_gadget;
@@ -13,8 +15,7 @@ use crate::util::{Expr, ToWord};
 use bus_mapping::evm::{GasCost, OpcodeId};
 use halo2::plonk::Error;
 use halo2::{arithmetic::FieldExt, circuit::Region};
-use num::ToPrimitive;
-use std::convert::TryInto;
+use num::{ToPrimitive, Zero};
 
 static STATE_TRANSITION: StateTransition = StateTransition {
     gc_delta: Some(1),
@@ -31,21 +32,20 @@ impl_op_gadget!(
         JumpSuccessCase(),
         OutOfGasCase(STATE_TRANSITION.gas_delta.unwrap()),
         StackUnderflowCase(NUM_POPPED),
-        //TODO: ErrJumpcase
+        InvalidJumpCase(false),
     }
 );
 
 #[derive(Clone, Debug)]
 struct JumpSuccessCase<F> {
     case_selector: Cell<F>,
-    code_hash: Word<F>,
     dest: Word<F>,
 }
 
 impl<F: FieldExt> JumpSuccessCase<F> {
     pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
         case: Case::Success,
-        num_word: 2, // top stack value as pc, hash of contract
+        num_word: 1, // top stack value as pc
         num_cell: 0,
         will_halt: false,
     };
@@ -53,7 +53,6 @@ impl<F: FieldExt> JumpSuccessCase<F> {
     pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
         Self {
             case_selector: alloc.selector.clone(),
-            code_hash: alloc.words.pop().unwrap(),
             dest: alloc.words.pop().unwrap(),
         }
     }
@@ -78,9 +77,10 @@ impl<F: FieldExt> JumpSuccessCase<F> {
         // Pop the value from the stack
         cb.stack_pop(self.dest.expr());
         // lookup byte code table to ensure 'dest' is valid( jumpdest & is_code)
+        cb.require_zero(sum::expr(&self.dest.cells[3..]));
         cb.add_bytecode_lookup([
-            self.code_hash.expr(),
-            self.dest.expr(),
+            state_curr.code_source.expr(),
+            from_bytes::expr(self.dest.cells[..3].to_vec()),
             1.expr(),
             OpcodeId::JUMPDEST.as_u8().expr(),
         ]);
@@ -97,21 +97,176 @@ impl<F: FieldExt> JumpSuccessCase<F> {
         step: &ExecutionStep,
     ) -> Result<(), Error> {
         // Inputs
-
-        self.code_hash.assign(
-            region,
-            offset,
-            Some(step.values[0].to_word()),
-        )?;
-
         self.dest
-            .assign(region, offset, Some(step.values[1].to_word()))?;
+            .assign(region, offset, Some(step.values[0].to_word()))?;
 
         // State transitions
         let st = STATE_TRANSITION.clone();
         st.assign(state);
         // other than normal op code, jump change pc specially, adjust here
-        state.program_counter = step.values[1].to_usize().unwrap();
+        state.program_counter = step.values[0].to_usize().unwrap();
+        Ok(())
+    }
+}
+
+/// Case of JUMP and JUMPI when the destination is not a JUMPDEST opcode of
+/// the code being executed, which is shared by both opcodes.  For JUMPI the
+/// condition is popped as well and needs to be non-zero.
+#[derive(Clone, Debug)]
+pub(super) struct InvalidJumpCase<F> {
+    case_selector: Cell<F>,
+    is_jumpi: bool,
+    dest: Word<F>,
+    cond: Word<F>,
+    is_code: Cell<F>,
+    byte: Cell<F>,
+    // The size of the code being executed, given by the keccak table with
+    // the random linear combination of the code
+    code_rlc: Cell<F>,
+    code_size: Cell<F>,
+    is_dest_high_zero: IsZeroGadget<F>,
+    is_dest_lt_code_size: LtGadget<F, 3>,
+    is_cond_zero: IsZeroGadget<F>,
+    is_jumpdest: IsEqualGadget<F>,
+}
+
+impl<F: FieldExt> InvalidJumpCase<F> {
+    pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::InvalidJump,
+        num_word: 2, // dest and cond (only used by JUMPI)
+        num_cell: 4 // is_code + byte + code_rlc + code_size
+            + IsZeroGadget::<F>::NUM_CELLS * 2
+            + LtGadget::<F, 3>::NUM_CELLS
+            + IsEqualGadget::<F>::NUM_CELLS,
+        will_halt: true,
+    };
+
+    pub(crate) fn construct(
+        alloc: &mut CaseAllocation<F>,
+        is_jumpi: bool,
+    ) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+            is_jumpi,
+            dest: alloc.words.pop().unwrap(),
+            cond: alloc.words.pop().unwrap(),
+            is_code: alloc.cells.pop().unwrap(),
+            byte: alloc.cells.pop().unwrap(),
+            code_rlc: alloc.cells.pop().unwrap(),
+            code_size: alloc.cells.pop().unwrap(),
+            is_dest_high_zero: IsZeroGadget::construct(alloc),
+            is_dest_lt_code_size: LtGadget::construct(alloc),
+            is_cond_zero: IsZeroGadget::construct(alloc),
+            is_jumpdest: IsEqualGadget::construct(alloc),
+        }
+    }
+
+    pub(crate) fn constraint(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        _state_next: &OpExecutionState<F>,
+        name: &'static str,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::default();
+
+        cb.stack_pop(self.dest.expr());
+        if self.is_jumpi {
+            cb.stack_pop(self.cond.expr());
+            let is_cond_zero = self
+                .is_cond_zero
+                .constraints(&mut cb, sum::expr(&self.cond.cells));
+            cb.require_zero(is_cond_zero);
+        }
+
+        // A destination which doesn't fit in 3 bytes, or which isn't less
+        // than the size of the code, is past the end of the code and always
+        // invalid.  Otherwise the byte at the destination can't be a JUMPDEST
+        // opcode.  The code size is the length of the keccak input hashed
+        // into the code hash, and fits in 3 bytes.
+        cb.add_keccak_lookup([
+            self.code_rlc.expr(),
+            self.code_size.expr(),
+            state_curr.code_source.expr(),
+        ]);
+        let dest = from_bytes::expr(self.dest.cells[..3].to_vec());
+        let is_dest_high_zero = self
+            .is_dest_high_zero
+            .constraints(&mut cb, sum::expr(&self.dest.cells[3..]));
+        let is_dest_lt_code_size = self.is_dest_lt_code_size.constraints(
+            &mut cb,
+            dest.clone(),
+            self.code_size.expr(),
+        );
+        cb.condition(is_dest_high_zero * is_dest_lt_code_size, |cb| {
+            cb.add_bytecode_lookup([
+                state_curr.code_source.expr(),
+                dest,
+                self.is_code.expr(),
+                self.byte.expr(),
+            ]);
+            let is_jumpdest = self.is_jumpdest.constraints(
+                cb,
+                self.byte.expr(),
+                OpcodeId::JUMPDEST.as_u8().expr(),
+            );
+            cb.require_zero(self.is_code.expr() * is_jumpdest);
+        });
+
+        vec![cb.constraint(self.case_selector.expr(), name)]
+    }
+
+    pub(crate) fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        _state: &mut CoreStateInstance,
+        step: &ExecutionStep,
+    ) -> Result<(), Error> {
+        let dest = step.values[0].to_word();
+        self.dest.assign(region, offset, Some(dest))?;
+        self.is_dest_high_zero.assign(
+            region,
+            offset,
+            sum::value(&dest[3..]),
+        )?;
+
+        let values = if self.is_jumpi {
+            let cond = step.values[1].to_word();
+            self.cond.assign(region, offset, Some(cond))?;
+            self.is_cond_zero
+                .assign(region, offset, sum::value(&cond))?;
+            &step.values[2..]
+        } else {
+            &step.values[1..]
+        };
+
+        let (is_code, byte) =
+            (!values[0].is_zero(), values[1].to_u8().unwrap());
+        let code_size = values[3].to_u64().unwrap();
+        self.code_rlc.assign(
+            region,
+            offset,
+            Some(F::from_bytes(&values[2].to_word()).unwrap()),
+        )?;
+        self.code_size
+            .assign(region, offset, Some(F::from(code_size)))?;
+        self.is_dest_lt_code_size.assign(
+            region,
+            offset,
+            from_bytes::value(dest[..3].to_vec()),
+            F::from(code_size),
+        )?;
+        self.is_code
+            .assign(region, offset, Some(F::from(is_code as u64)))?;
+        self.byte
+            .assign(region, offset, Some(F::from(byte as u64)))?;
+        self.is_jumpdest.assign(
+            region,
+            offset,
+            F::from(byte as u64),
+            F::from(OpcodeId::JUMPDEST.as_u8() as u64),
+        )?;
+
         Ok(())
     }
 }
@@ -130,7 +285,8 @@ mod test {
         ($execution_steps:expr, $operations:expr, $result:expr) => {{
             let circuit =
                 TestCircuit::<Fp>::new($execution_steps, $operations, false);
-            let prover = MockProver::<Fp>::run(11, &circuit, vec![]).unwrap();
+            let prover =
+                MockProver::<Fp>::run(11, &circuit, vec![vec![]]).unwrap();
             assert_eq!(prover.verify(), $result);
         }};
     }
@@ -152,7 +308,6 @@ mod test {
                     opcode: OpcodeId::JUMP,
                     case: Case::Success,
                     values: vec![
-                        BigUint::from(0x00u64), // code hash
                         BigUint::from(0x03u64), // dest value
                     ],
                 },
@@ -173,6 +328,8 @@ mod test {
                         Fp::from(1023),
                         Fp::from(3u64),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
                 Operation {
@@ -184,10 +341,103 @@ mod test {
                         Fp::from(1023),
                         Fp::from(3u64),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 }
             ],
             Ok(())
         );
     }
+
+    // The code `PUSH1 0x5b PUSH1 dest JUMP`, whose size is 5 and whose
+    // random linear combination with r = 1 is the sum of its bytes
+    fn invalid_jump_steps(
+        dest: u64,
+        is_code: u64,
+        byte: u64,
+    ) -> Vec<ExecutionStep> {
+        vec![
+            ExecutionStep {
+                opcode: OpcodeId::PUSH1,
+                case: Case::Success,
+                values: vec![BigUint::from(0x5bu64), BigUint::from(0x01u64)],
+            },
+            ExecutionStep {
+                opcode: OpcodeId::PUSH1,
+                case: Case::Success,
+                values: vec![BigUint::from(dest), BigUint::from(0x01u64)],
+            },
+            ExecutionStep {
+                opcode: OpcodeId::JUMP,
+                case: Case::InvalidJump,
+                values: vec![
+                    BigUint::from(dest),    // dest value
+                    BigUint::from(is_code), // is_code
+                    BigUint::from(byte),    // byte
+                    BigUint::from(0x60u64 + 0x5b + 0x60 + dest + 0x56), // rlc
+                    BigUint::from(5u64),    // code size
+                ],
+            },
+        ]
+    }
+
+    fn invalid_jump_operations(dest: u64) -> Vec<Operation<Fp>> {
+        [
+            (1, true, 1023, 0x5b),
+            (2, true, 1022, dest),
+            (3, false, 1022, dest),
+        ]
+        .iter()
+        .map(|(gc, is_write, address, value)| Operation {
+            gc: *gc,
+            target: Target::Stack,
+            is_write: *is_write,
+            values: [
+                Fp::zero(),
+                Fp::from(*address),
+                Fp::from(*value),
+                Fp::zero(),
+                Fp::zero(),
+                Fp::zero(),
+            ],
+        })
+        .collect()
+    }
+
+    #[test]
+    fn jump_gadget_invalid_jump() {
+        // Jump into the data of the first PUSH1
+        try_test_circuit!(
+            invalid_jump_steps(0x01, 0, 0x5b),
+            invalid_jump_operations(0x01),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn jump_gadget_invalid_jump_past_code_end() {
+        // Jump right after the last byte of the code, and far beyond it,
+        // where there is no byte in the bytecode table
+        for dest in [0x05, 0xff] {
+            try_test_circuit!(
+                invalid_jump_steps(dest, 0, 0),
+                invalid_jump_operations(dest),
+                Ok(())
+            );
+        }
+    }
+
+    #[test]
+    fn jump_gadget_invalid_jump_into_jumpdest() {
+        // The destination byte is a JUMPDEST opcode when it is claimed to be
+        // code, which doesn't match the bytecode table
+        let circuit = TestCircuit::<Fp>::new(
+            invalid_jump_steps(0x01, 1, 0x5b),
+            invalid_jump_operations(0x01),
+            false,
+        );
+        let prover = MockProver::<Fp>::run(11, &circuit, vec![vec![]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
 }
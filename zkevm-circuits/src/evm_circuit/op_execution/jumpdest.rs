@@ -11,7 +11,6 @@ use crate::util::Expr;
 use bus_mapping::evm::{GasCost, OpcodeId};
 use halo2::plonk::Error;
 use halo2::{arithmetic::FieldExt, circuit::Region};
-use std::convert::TryInto;
 
 static STATE_TRANSITION: StateTransition = StateTransition {
     gc_delta: Some(0),
@@ -91,7 +90,8 @@ mod test {
         ($execution_steps:expr, $operations:expr, $result:expr) => {{
             let circuit =
                 TestCircuit::<Fp>::new($execution_steps, $operations, false);
-            let prover = MockProver::<Fp>::run(11, &circuit, vec![]).unwrap();
+            let prover =
+                MockProver::<Fp>::run(11, &circuit, vec![vec![]]).unwrap();
             assert_eq!(prover.verify(), $result);
         }};
     }
@@ -124,6 +124,8 @@ mod test {
                     Fp::from(1023),
                     Fp::from(2 + 3),
                     Fp::zero(),
+                    Fp::zero(),
+                    Fp::zero(),
                 ]
             }],
             Ok(())
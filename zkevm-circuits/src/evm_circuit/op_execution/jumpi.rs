@@ -10,6 +10,7 @@ use super::utils::{
     select, sum, StateTransition, StateTransitionExpressions,
 };
 
+use super::jump::InvalidJumpCase;
 use super::{CaseAllocation, CaseConfig, OpExecutionState, OpGadget};
 use crate::impl_op_gadget;
 use crate::util::{Expr, ToWord};
@@ -18,7 +19,6 @@ use halo2::plonk::Error;
 use halo2::{arithmetic::FieldExt, circuit::Region};
 use num::BigUint;
 use num::ToPrimitive;
-use std::convert::TryInto;
 
 static STATE_TRANSITION: StateTransition = StateTransition {
     gc_delta: Some(2),
@@ -35,14 +35,13 @@ impl_op_gadget!(
         JumpiSuccessCase(),
         OutOfGasCase(STATE_TRANSITION.gas_delta.unwrap()),
         StackUnderflowCase(NUM_POPPED),
-        //TODO: ErrJumpcase
+        InvalidJumpCase(true),
     }
 );
 
 #[derive(Clone, Debug)]
 struct JumpiSuccessCase<F> {
     case_selector: Cell<F>,
-    code_hash: Word<F>,
     dest: Word<F>,
     cond: Word<F>,
     is_cond_zero: IsZeroGadget<F>,
@@ -51,7 +50,7 @@ struct JumpiSuccessCase<F> {
 impl<F: FieldExt> JumpiSuccessCase<F> {
     pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
         case: Case::Success,
-        num_word: 3, // two stack values + code_hash of contract
+        num_word: 2, // two stack values
         num_cell: IsZeroGadget::<F>::NUM_CELLS,
         will_halt: false,
     };
@@ -59,7 +58,6 @@ impl<F: FieldExt> JumpiSuccessCase<F> {
     pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
         Self {
             case_selector: alloc.selector.clone(),
-            code_hash: alloc.words.pop().unwrap(),
             dest: alloc.words.pop().unwrap(),
             cond: alloc.words.pop().unwrap(),
             is_cond_zero: IsZeroGadget::construct(alloc),
@@ -105,9 +103,10 @@ impl<F: FieldExt> JumpiSuccessCase<F> {
         ));
         // 2. `cond` is non-zero constraint (is_cond_met = 1 )
         // lookup byte code table to ensure 'dest' is valid( jumpdest & is_code)
+        cb.require_zero(sum::expr(&self.dest.cells[3..]));
         cb.add_bytecode_lookup([
-            self.code_hash.expr(),
-            self.dest.expr(),
+            state_curr.code_source.expr(),
+            from_bytes::expr(self.dest.cells[..3].to_vec()),
             1.expr(),
             OpcodeId::JUMPDEST.as_u8().expr(),
         ]);
@@ -124,31 +123,24 @@ impl<F: FieldExt> JumpiSuccessCase<F> {
         step: &ExecutionStep,
     ) -> Result<(), Error> {
         // Inputs
-
-        self.code_hash.assign(
-            region,
-            offset,
-            Some(step.values[0].to_word()),
-        )?;
-
         self.dest
-            .assign(region, offset, Some(step.values[1].to_word()))?;
+            .assign(region, offset, Some(step.values[0].to_word()))?;
 
         self.cond
-            .assign(region, offset, Some(step.values[2].to_word()))?;
+            .assign(region, offset, Some(step.values[1].to_word()))?;
 
         self.is_cond_zero.assign(
             region,
             offset,
-            sum::value(&step.values[2].to_word()),
+            sum::value(&step.values[1].to_word()),
         )?;
 
         // State transitions
         let st = STATE_TRANSITION.clone();
         st.assign(state);
         // other than normal op code, jumpi change pc specially, adjust here
-        if step.values[2] != BigUint::from(0x00u64) {
-            state.program_counter = step.values[1].to_usize().unwrap();
+        if step.values[1] != BigUint::from(0x00u64) {
+            state.program_counter = step.values[0].to_usize().unwrap();
         }
 
         Ok(())
@@ -169,7 +161,8 @@ mod test {
         ($execution_steps:expr, $operations:expr, $result:expr) => {{
             let circuit =
                 TestCircuit::<Fp>::new($execution_steps, $operations, false);
-            let prover = MockProver::<Fp>::run(11, &circuit, vec![]).unwrap();
+            let prover =
+                MockProver::<Fp>::run(11, &circuit, vec![vec![]]).unwrap();
             assert_eq!(prover.verify(), $result);
         }};
     }
@@ -199,7 +192,6 @@ mod test {
                     opcode: OpcodeId::JUMPI,
                     case: Case::Success,
                     values: vec![
-                        BigUint::from(0x00u64), // code hash
                         BigUint::from(0x05u64), // dest
                         BigUint::from(0x01u64), // cond
                     ],
@@ -221,6 +213,8 @@ mod test {
                         Fp::from(1023),
                         Fp::from(1u64),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
                 Operation {
@@ -232,6 +226,8 @@ mod test {
                         Fp::from(1022),
                         Fp::from(5u64),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
                 Operation {
@@ -243,6 +239,8 @@ mod test {
                         Fp::from(1022),
                         Fp::from(5u64),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
                 Operation {
@@ -254,6 +252,8 @@ mod test {
                         Fp::from(1023),
                         Fp::from(1u64),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 }
             ],
@@ -285,7 +285,6 @@ mod test {
                     opcode: OpcodeId::JUMPI,
                     case: Case::Success,
                     values: vec![
-                        BigUint::from(0x00u64), // code hash
                         BigUint::from(0x05u64), // dest
                         BigUint::from(0x00u64), // cond
                     ],
@@ -307,6 +306,8 @@ mod test {
                         Fp::from(1023),
                         Fp::from(0),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
                 Operation {
@@ -318,6 +319,8 @@ mod test {
                         Fp::from(1022),
                         Fp::from(5u64),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
                 Operation {
@@ -329,6 +332,8 @@ mod test {
                         Fp::from(1022),
                         Fp::from(5u64),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
                 Operation {
@@ -340,6 +345,8 @@ mod test {
                         Fp::from(1023),
                         Fp::from(0u64),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 }
             ],
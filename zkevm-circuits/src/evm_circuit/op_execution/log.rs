@@ -0,0 +1,646 @@
+use super::super::{Case, Cell, Constraint, ExecutionStep, Word};
+use super::utils::{
+    self,
+    common_cases::RangeStackUnderflowCase,
+    constraint_builder::ConstraintBuilder,
+    from_bytes,
+    math_gadgets::{IsZeroGadget, LtGadget},
+    memory_gadgets::{
+        self, address_high, address_low, MemoryExpansionGadget,
+        MemoryRangeGadget,
+    },
+    sum, StateTransition, StateTransitionExpressions,
+};
+use super::{
+    CaseAllocation, CaseConfig, CoreStateInstance, OpExecutionState, OpGadget,
+};
+use crate::evm_circuit::param::{MAX_COPY_BYTES, MAX_GAS_SIZE_IN_BYTES};
+use crate::impl_op_gadget;
+use crate::util::{Expr, ToWord};
+use array_init::array_init;
+use bus_mapping::{
+    evm::{GasCost, OpcodeId},
+    operation::{CallContextField, TxLogField},
+};
+use halo2::plonk::Error;
+use halo2::{arithmetic::FieldExt, circuit::Region};
+use std::convert::TryInto;
+
+static STATE_TRANSITION: StateTransition = StateTransition {
+    gc_delta: None, // GC_DELTA + 2 * num_topics + 2 * length
+    pc_delta: Some(1),
+    sp_delta: None,         // 2 + num_topics
+    gas_delta: None,        // LOG + LOG_TOPIC * n + 8 * length + memory_cost
+    next_memory_size: None, // next_memory_size
+};
+// 3 call context reads + 2 stack pops
+const GC_DELTA: usize = 5;
+const MAX_NUM_TOPICS: usize = 4;
+
+impl_op_gadget!(
+    #set[LOG0, LOG1, LOG2, LOG3, LOG4]
+    LogGadget {
+        LogSuccessCase(),
+        RangeStackUnderflowCase(OpcodeId::LOG0, 5, 1),
+        LogWriteProtectionCase(),
+        LogOutOfGasCase(),
+    }
+);
+
+#[derive(Clone, Debug)]
+struct LogSuccessCase<F> {
+    case_selector: Cell<F>,
+    memory_offset: Word<F>,
+    length: Word<F>,
+    topics: [Word<F>; MAX_NUM_TOPICS],
+    address: Cell<F>,
+    // The log is only written when the call is persistent
+    is_persistent: Cell<F>,
+    topic_flags: [Cell<F>; MAX_NUM_TOPICS],
+    copy_flags: [Cell<F>; MAX_COPY_BYTES],
+    bytes: [Cell<F>; MAX_COPY_BYTES],
+    length_is_zero: IsZeroGadget<F>,
+    memory_expansion: MemoryExpansionGadget<F, MAX_GAS_SIZE_IN_BYTES>,
+}
+
+impl<F: FieldExt> LogSuccessCase<F> {
+    pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::Success,
+        num_word: 2 + MAX_NUM_TOPICS, // memory_offset + length + topics
+        num_cell: 2 // address + is_persistent
+            + MAX_NUM_TOPICS // topic_flags
+            + 2 * MAX_COPY_BYTES // copy_flags + bytes
+            + IsZeroGadget::<F>::NUM_CELLS
+            + MemoryExpansionGadget::<F, MAX_GAS_SIZE_IN_BYTES>::NUM_CELLS,
+        will_halt: false,
+    };
+
+    pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+            memory_offset: alloc.words.pop().unwrap(),
+            length: alloc.words.pop().unwrap(),
+            topics: array_init(|_| alloc.words.pop().unwrap()),
+            address: alloc.cells.pop().unwrap(),
+            is_persistent: alloc.cells.pop().unwrap(),
+            topic_flags: array_init(|_| alloc.cells.pop().unwrap()),
+            copy_flags: array_init(|_| alloc.cells.pop().unwrap()),
+            bytes: array_init(|_| alloc.cells.pop().unwrap()),
+            length_is_zero: IsZeroGadget::construct(alloc),
+            memory_expansion: MemoryExpansionGadget::construct(alloc),
+        }
+    }
+
+    pub(crate) fn constraint(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        state_next: &OpExecutionState<F>,
+        name: &'static str,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::with_call_id(state_curr.call_id.expr());
+
+        // The call is not static, and the log is emitted by its account unless
+        // the call or one of its callers reverts
+        cb.call_context_lookup(
+            false,
+            state_curr.call_id.expr(),
+            CallContextField::IsStatic,
+            0.expr(),
+        );
+        cb.call_context_lookup(
+            false,
+            state_curr.call_id.expr(),
+            CallContextField::CalleeAddress,
+            self.address.expr(),
+        );
+        cb.call_context_lookup(
+            false,
+            state_curr.call_id.expr(),
+            CallContextField::IsPersistent,
+            self.is_persistent.expr(),
+        );
+        cb.require_boolean(self.is_persistent.expr());
+
+        // Pop the memory offset and the length from the stack
+        cb.stack_pop(self.memory_offset.expr());
+        cb.stack_pop(self.length.expr());
+
+        // The topic flags are set for the first `opcode - LOG0` topics
+        for (idx, topic_flag) in self.topic_flags.iter().enumerate() {
+            cb.require_boolean(topic_flag.expr());
+            if idx > 0 {
+                cb.require_zero(
+                    topic_flag.expr()
+                        * (1.expr() - self.topic_flags[idx - 1].expr()),
+                );
+            }
+        }
+        let num_topics = sum::expr(&self.topic_flags);
+        cb.require_equal(
+            num_topics.clone(),
+            state_curr.opcode.expr() - OpcodeId::LOG0.expr(),
+        );
+
+        // Pop the topics from the stack
+        for (idx, (topic_flag, topic)) in
+            self.topic_flags.iter().zip(self.topics.iter()).enumerate()
+        {
+            cb.condition(topic_flag.expr(), |cb| {
+                cb.stack_lookup_at(
+                    (2 + idx).expr(),
+                    topic.expr(),
+                    false.expr(),
+                    (5 + idx).expr(),
+                );
+            });
+        }
+
+        // Write the address and the topics into the log, which follows the
+        // previous log of the tx
+        let log_id = state_curr.log_id.expr() + 1.expr();
+        let gc_offset = 5.expr() + num_topics.clone();
+        cb.condition(self.is_persistent.expr(), |cb| {
+            cb.tx_log_write_at(
+                log_id.clone(),
+                TxLogField::Address,
+                0.expr(),
+                self.address.expr(),
+                gc_offset.clone(),
+            );
+            for (idx, (topic_flag, topic)) in
+                self.topic_flags.iter().zip(self.topics.iter()).enumerate()
+            {
+                cb.condition(topic_flag.expr(), |cb| {
+                    cb.tx_log_write_at(
+                        log_id.clone(),
+                        TxLogField::Topic,
+                        idx.expr(),
+                        topic.expr(),
+                        gc_offset.clone() + (1 + idx).expr(),
+                    );
+                });
+            }
+        });
+
+        // The copy flags are set for the first `length` bytes, which limits
+        // the length to MAX_COPY_BYTES
+        cb.require_zero(address_high::expr(&self.length));
+        let length = address_low::expr(&self.length);
+        for (idx, copy_flag) in self.copy_flags.iter().enumerate() {
+            cb.require_boolean(copy_flag.expr());
+            if idx > 0 {
+                cb.require_zero(
+                    copy_flag.expr()
+                        * (1.expr() - self.copy_flags[idx - 1].expr()),
+                );
+            }
+        }
+        cb.require_equal(sum::expr(&self.copy_flags), length.clone());
+        let length_is_zero =
+            self.length_is_zero.constraints(&mut cb, length.clone());
+
+        // The memory offset is only used when bytes are copied
+        cb.condition(1.expr() - length_is_zero.clone(), |cb| {
+            memory_gadgets::require_address_in_range(cb, &self.memory_offset);
+        });
+        let memory_offset = address_low::expr(&self.memory_offset);
+
+        // Copy the data: read each byte from the memory and write it into the
+        // log when the call is persistent
+        let mut gc_offset = gc_offset
+            + self.is_persistent.expr() * (1.expr() + num_topics.clone());
+        for (idx, (copy_flag, byte)) in
+            self.copy_flags.iter().zip(self.bytes.iter()).enumerate()
+        {
+            cb.condition(copy_flag.expr(), |cb| {
+                cb.memory_lookup_at(
+                    memory_offset.clone() + idx.expr(),
+                    byte.expr(),
+                    0.expr(),
+                    gc_offset.clone(),
+                );
+                cb.condition(self.is_persistent.expr(), |cb| {
+                    cb.tx_log_write_at(
+                        log_id.clone(),
+                        TxLogField::Data,
+                        idx.expr(),
+                        byte.expr(),
+                        gc_offset.clone() + 1.expr(),
+                    );
+                });
+            });
+            gc_offset = gc_offset
+                + copy_flag.expr() * (1.expr() + self.is_persistent.expr());
+        }
+
+        // Calculate the next memory size and the gas cost for this memory
+        // access, where the memory is not expanded when there's no data
+        let (next_memory_size, memory_cost) =
+            self.memory_expansion.constraints(
+                &mut cb,
+                state_curr.memory_size.expr(),
+                (1.expr() - length_is_zero) * (memory_offset + length.clone()),
+            );
+
+        // State transitions
+        let mut st = StateTransitionExpressions::new(STATE_TRANSITION.clone());
+        st.gc_delta = Some(gc_offset);
+        st.sp_delta = Some(2.expr() + num_topics.clone());
+        st.gas_delta = Some(
+            GasCost::LOG.expr()
+                + GasCost::LOG_TOPIC.expr() * num_topics
+                + GasCost::LOG_DATA_BYTE.expr() * length
+                + memory_cost,
+        );
+        st.next_memory_size = Some(next_memory_size);
+        st.log_id_delta = Some(self.is_persistent.expr());
+        st.constraints(&mut cb, state_curr, state_next);
+
+        // Generate the constraint
+        vec![cb.constraint(self.case_selector.expr(), name)]
+    }
+
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: &mut CoreStateInstance,
+        step: &ExecutionStep,
+    ) -> Result<(), Error> {
+        // Inputs, followed by the call context values and the data bytes
+        let num_topics =
+            (step.opcode.as_u8() - OpcodeId::LOG0.as_u8()) as usize;
+        let memory_offset = step.values[0].to_word();
+        let length = step.values[1].to_word();
+        self.memory_offset
+            .assign(region, offset, Some(memory_offset))?;
+        self.length.assign(region, offset, Some(length))?;
+        for (idx, (topic_flag, topic)) in
+            self.topic_flags.iter().zip(self.topics.iter()).enumerate()
+        {
+            let is_topic = idx < num_topics;
+            topic_flag.assign(
+                region,
+                offset,
+                Some(F::from(is_topic as u64)),
+            )?;
+            topic.assign(
+                region,
+                offset,
+                Some(if is_topic {
+                    step.values[2 + idx].to_word()
+                } else {
+                    [0u8; 32]
+                }),
+            )?;
+        }
+        let values = &step.values[2 + num_topics..];
+        self.address.assign(
+            region,
+            offset,
+            Some(from_bytes::value(values[1].to_word()[..20].to_vec())),
+        )?;
+        let is_persistent: u64 = values[2].clone().try_into().unwrap();
+        self.is_persistent.assign(
+            region,
+            offset,
+            Some(F::from(is_persistent)),
+        )?;
+
+        let length = address_low::value::<F>(length);
+        for (idx, copy_flag) in self.copy_flags.iter().enumerate() {
+            copy_flag.assign(
+                region,
+                offset,
+                Some(F::from(((idx as u64) < length) as u64)),
+            )?;
+        }
+        let bytes = values[3].to_word();
+        for (cell, byte) in self.bytes.iter().zip(bytes.iter()) {
+            cell.assign(region, offset, Some(F::from(*byte as u64)))?;
+        }
+        let length_is_zero =
+            self.length_is_zero
+                .assign(region, offset, F::from(length))?;
+
+        // Memory expansion
+        let address = if length_is_zero == F::one() {
+            0
+        } else {
+            address_low::value::<F>(memory_offset) + length
+        };
+        let (next_memory_size, memory_cost) = self.memory_expansion.assign(
+            region,
+            offset,
+            state.memory_size as u64,
+            address,
+        )?;
+
+        // State transitions
+        let mut st = STATE_TRANSITION.clone();
+        st.gc_delta = Some(
+            GC_DELTA
+                + num_topics
+                + is_persistent as usize * (1 + num_topics)
+                + (1 + is_persistent as usize) * length as usize,
+        );
+        st.sp_delta = Some(2 + num_topics as i32);
+        st.gas_delta = Some(
+            GasCost::LOG.as_u64()
+                + GasCost::LOG_TOPIC.as_u64() * num_topics as u64
+                + GasCost::LOG_DATA_BYTE.as_u64() * length
+                + (memory_cost as u64),
+        );
+        st.next_memory_size = Some(next_memory_size);
+        st.assign(state);
+        state.log_id += is_persistent as usize;
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+struct LogWriteProtectionCase<F> {
+    case_selector: Cell<F>,
+}
+
+impl<F: FieldExt> LogWriteProtectionCase<F> {
+    pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::WriteProtection,
+        num_word: 0,
+        num_cell: 0,
+        will_halt: true,
+    };
+
+    pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+        }
+    }
+
+    pub(crate) fn constraint(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        _state_next: &OpExecutionState<F>,
+        name: &'static str,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::default();
+
+        // Logs can't be emitted by a static call
+        cb.call_context_lookup(
+            false,
+            state_curr.call_id.expr(),
+            CallContextField::IsStatic,
+            1.expr(),
+        );
+
+        vec![cb.constraint(self.case_selector.expr(), name)]
+    }
+
+    pub(crate) fn assign(
+        &self,
+        _region: &mut Region<'_, F>,
+        _offset: usize,
+        _state: &mut CoreStateInstance,
+        _step: &ExecutionStep,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+struct LogOutOfGasCase<F> {
+    case_selector: Cell<F>,
+    memory_offset: Word<F>,
+    length: Word<F>,
+    memory_range: MemoryRangeGadget<F>,
+    insufficient_gas: LtGadget<F, MAX_GAS_SIZE_IN_BYTES>,
+}
+
+impl<F: FieldExt> LogOutOfGasCase<F> {
+    pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::OutOfGas,
+        num_word: 2, // memory_offset + length
+        num_cell: MemoryRangeGadget::<F>::NUM_CELLS
+            + LtGadget::<F, MAX_GAS_SIZE_IN_BYTES>::NUM_CELLS,
+        will_halt: true,
+    };
+
+    pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+            memory_offset: alloc.words.pop().unwrap(),
+            length: alloc.words.pop().unwrap(),
+            memory_range: MemoryRangeGadget::construct(alloc),
+            insufficient_gas: LtGadget::construct(alloc),
+        }
+    }
+
+    pub(crate) fn constraint(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        _state_next: &OpExecutionState<F>,
+        name: &'static str,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::default();
+
+        // The call is not static, and only the memory offset and the length
+        // are popped from the stack
+        cb.call_context_lookup(
+            false,
+            state_curr.call_id.expr(),
+            CallContextField::IsStatic,
+            0.expr(),
+        );
+        cb.stack_pop(self.memory_offset.expr());
+        cb.stack_pop(self.length.expr());
+
+        // Make sure the amount of gas available is less than the amount of
+        // gas required to expand the memory and log the topics and the data,
+        // unless the data is out of the addressable memory, which never has
+        // enough gas
+        let (is_in_range, _, memory_cost) = self.memory_range.constraints(
+            &mut cb,
+            state_curr.memory_size.expr(),
+            &self.memory_offset,
+            &self.length,
+        );
+        let insufficient_gas = self.insufficient_gas.constraints(
+            &mut cb,
+            state_curr.gas_left.expr(),
+            GasCost::LOG.expr()
+                + GasCost::LOG_TOPIC.expr()
+                    * (state_curr.opcode.expr() - OpcodeId::LOG0.expr())
+                + GasCost::LOG_DATA_BYTE.expr()
+                    * address_low::expr(&self.length)
+                + memory_cost,
+        );
+        cb.condition(is_in_range, |cb| {
+            cb.require_equal(insufficient_gas, 1.expr());
+        });
+
+        // Generate the constraint
+        vec![cb.constraint(self.case_selector.expr(), name)]
+    }
+
+    pub(crate) fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: &mut CoreStateInstance,
+        step: &ExecutionStep,
+    ) -> Result<(), Error> {
+        // Inputs
+        let num_topics = (step.opcode.as_u8() - OpcodeId::LOG0.as_u8()) as u64;
+        let memory_offset = step.values[0].to_word();
+        let length = step.values[1].to_word();
+        self.memory_offset
+            .assign(region, offset, Some(memory_offset))?;
+        self.length.assign(region, offset, Some(length))?;
+
+        let (_, _, memory_cost) = self.memory_range.assign(
+            region,
+            offset,
+            state.memory_size as u64,
+            memory_offset,
+            length,
+        )?;
+
+        // Gas insufficient check
+        self.insufficient_gas.assign(
+            region,
+            offset,
+            F::from(state.gas_left),
+            F::from(
+                GasCost::LOG.as_u64()
+                    + GasCost::LOG_TOPIC.as_u64() * num_topics
+                    + GasCost::LOG_DATA_BYTE.as_u64()
+                        * address_low::value::<F>(length)
+                    + (memory_cost as u64),
+            ),
+        )?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::test::test_circuit_from_block;
+    use bus_mapping::{
+        bytecode, bytecode::Bytecode,
+        circuit_input_builder::CircuitInputBuilder, mock,
+    };
+    use halo2::dev::MockProver;
+    use pairing::bn256::Fr as Fp;
+
+    fn test_log_gadget(code: Bytecode, callee_code: Option<Bytecode>) {
+        let block = match callee_code {
+            Some(callee_code) => {
+                mock::BlockData::new_single_tx_trace_code_2(&code, &callee_code)
+            }
+            None => mock::BlockData::new_single_tx_trace_code(&code),
+        }
+        .unwrap();
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let circuit = test_circuit_from_block::<Fp>(&builder.block);
+        let prover = MockProver::<Fp>::run(11, &circuit, vec![vec![]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    fn log_gadget_from_trace() {
+        // A log with topics and data which expands the memory, followed by
+        // an empty one
+        let code = bytecode! {
+            PUSH32(0x0102030405060708u64)
+            PUSH1(0x00)
+            MSTORE
+            PUSH1(0xbb) // topic 1
+            PUSH1(0xaa) // topic 0
+            PUSH1(0x10) // length
+            PUSH1(0x18) // offset
+            LOG2
+            PUSH1(0x00) // length
+            PUSH1(0x00) // offset
+            LOG0
+            STOP
+        };
+        test_log_gadget(code, None);
+    }
+
+    #[test]
+    fn log_gadget_write_protection() {
+        let code = bytecode! {
+            PUSH1(0x00) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x00) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH2(0x123) // addr
+            PUSH2(0x1000) // gas
+            STATICCALL
+        };
+        let callee_code = bytecode! {
+            PUSH1(0x00) // length
+            PUSH1(0x00) // offset
+            LOG0
+            STOP
+        };
+        test_log_gadget(code, Some(callee_code));
+    }
+
+    #[test]
+    fn log_gadget_in_reverted_call() {
+        // The log of the reverted callee isn't written, so the log of the
+        // caller is the first one of the tx
+        let code = bytecode! {
+            PUSH1(0x00) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x00) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH1(0x00) // value
+            PUSH2(0x123) // addr
+            PUSH2(0x1000) // gas
+            CALL
+            PUSH1(0xaa) // topic 0
+            PUSH1(0x00) // length
+            PUSH1(0x00) // offset
+            LOG1
+            STOP
+        };
+        let callee_code = bytecode! {
+            PUSH32(0x0102u64)
+            PUSH1(0x00)
+            MSTORE
+            PUSH1(0xbb) // topic 0
+            PUSH1(0x02) // length
+            PUSH1(0x1e) // offset
+            LOG1
+            PUSH1(0x00) // length
+            PUSH1(0x00) // offset
+            REVERT
+        };
+        test_log_gadget(code, Some(callee_code));
+    }
+
+    #[test]
+    fn log_gadget_out_of_gas() {
+        // The memory can't be expanded up to the data, which may be out of
+        // the addressable memory
+        for memory_offset in [0x100000u64, 1 << 40] {
+            let code = bytecode! {
+                PUSH1(0xaa) // topic 0
+                PUSH1(0x01) // length
+                PUSH32(memory_offset) // offset
+                LOG1
+                STOP
+            };
+            test_log_gadget(code, None);
+        }
+    }
+}
@@ -17,7 +17,6 @@ use crate::util::{Expr, ToWord};
 use bus_mapping::evm::{GasCost, OpcodeId};
 use halo2::plonk::Error;
 use halo2::{arithmetic::FieldExt, circuit::Region};
-use std::convert::TryInto;
 
 static STATE_TRANSITION: StateTransition = StateTransition {
     gc_delta: None, // GC_DELTA_MLOAD_MSTORE/GC_DELTA_MSTORE8
@@ -235,7 +234,6 @@ impl<F: FieldExt> MemorySuccessCase<F> {
 #[derive(Clone, Debug)]
 struct MemoryOutOfGasCase<F> {
     case_selector: Cell<F>,
-    gas_available: Cell<F>,
     address: Word<F>,
     address_in_range: IsZeroGadget<F>,
     memory_expansion:
@@ -258,7 +256,6 @@ impl<F: FieldExt> MemoryOutOfGasCase<F> {
     pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
         Self {
             case_selector: alloc.selector.clone(),
-            gas_available: alloc.resumption.clone().unwrap().gas_available,
             address: alloc.words.pop().unwrap(),
             address_in_range: IsZeroGadget::construct(alloc),
             memory_expansion: MemoryExpansionGadget::construct(alloc),
@@ -300,8 +297,8 @@ impl<F: FieldExt> MemoryOutOfGasCase<F> {
         // required
         let insufficient_gas = self.insufficient_gas.constraints(
             &mut cb,
-            self.gas_available.expr(),
-            state_curr.gas_counter.expr() + GAS.expr() + memory_cost,
+            state_curr.gas_left.expr(),
+            GAS.expr() + memory_cost,
         );
 
         // Make sure we are out of gas
@@ -354,12 +351,11 @@ impl<F: FieldExt> MemoryOutOfGasCase<F> {
         )?;
 
         // Gas insufficient check
-        // Get `gas_available` variable here once it's available
         self.insufficient_gas.assign(
             region,
             offset,
-            F::from(state.gas_counter + GAS.as_u64() + (memory_cost as u64)),
-            F::from_bytes(&step.values[1].to_word()).unwrap(),
+            F::from(state.gas_left),
+            F::from(GAS.as_u64() + (memory_cost as u64)),
         )?;
 
         Ok(())
@@ -446,7 +442,8 @@ mod test {
         ($execution_steps:expr, $operations:expr, $result:expr) => {{
             let circuit =
                 TestCircuit::<Fp>::new($execution_steps, $operations, false);
-            let prover = MockProver::<Fp>::run(11, &circuit, vec![]).unwrap();
+            let prover =
+                MockProver::<Fp>::run(11, &circuit, vec![vec![]]).unwrap();
             assert_eq!(prover.verify(), $result);
         }};
     }
@@ -485,6 +482,8 @@ mod test {
                 Fp::from(stack_index),
                 compress(value.clone()),
                 Fp::zero(),
+                Fp::zero(),
+                Fp::zero(),
             ],
         });
         operations.push(Operation {
@@ -496,6 +495,8 @@ mod test {
                 Fp::from(stack_index - 1),
                 compress(address.clone()),
                 Fp::zero(),
+                Fp::zero(),
+                Fp::zero(),
             ],
         });
         operations.push(Operation {
@@ -507,6 +508,8 @@ mod test {
                 Fp::from(stack_index - 1),
                 compress(address.clone()),
                 Fp::zero(),
+                Fp::zero(),
+                Fp::zero(),
             ],
         });
         operations.push(Operation {
@@ -518,6 +521,8 @@ mod test {
                 Fp::from(stack_index),
                 compress(value.clone()),
                 Fp::zero(),
+                Fp::zero(),
+                Fp::zero(),
             ],
         });
         for idx in 0..count {
@@ -536,6 +541,8 @@ mod test {
                         value.to_bytes_le()[count - 1 - idx as usize] as u64,
                     ),
                     Fp::zero(),
+                    Fp::zero(),
+                    Fp::zero(),
                 ],
             });
         }
@@ -557,6 +564,8 @@ mod test {
                 Fp::from(stack_index),
                 compress(address.clone()),
                 Fp::zero(),
+                Fp::zero(),
+                Fp::zero(),
             ],
         });
         operations.push(Operation {
@@ -568,6 +577,8 @@ mod test {
                 Fp::from(stack_index),
                 compress(address.clone()),
                 Fp::zero(),
+                Fp::zero(),
+                Fp::zero(),
             ],
         });
         operations.push(Operation {
@@ -579,6 +590,8 @@ mod test {
                 Fp::from(stack_index),
                 compress(value.clone()),
                 Fp::zero(),
+                Fp::zero(),
+                Fp::zero(),
             ],
         });
         for idx in 0..32 {
@@ -595,6 +608,8 @@ mod test {
                     .unwrap(),
                     Fp::from(value.to_bytes_le()[31 - idx as usize] as u64),
                     Fp::zero(),
+                    Fp::zero(),
+                    Fp::zero(),
                 ],
             });
         }
@@ -0,0 +1,132 @@
+use super::super::{
+    Case, Cell, Constraint, CoreStateInstance, ExecutionStep, Word,
+};
+use super::utils::{
+    self,
+    common_cases::{OutOfGasCase, StackOverflowCase},
+    constraint_builder::ConstraintBuilder,
+    from_bytes, sum, StateTransition,
+};
+use super::{CaseAllocation, CaseConfig, OpExecutionState, OpGadget};
+use crate::impl_op_gadget;
+use crate::util::{Expr, ToWord};
+use bus_mapping::evm::{GasCost, OpcodeId};
+use halo2::{arithmetic::FieldExt, circuit::Region, plonk::Error};
+
+static STATE_TRANSITION: StateTransition = StateTransition {
+    gc_delta: Some(1), // 1 stack push
+    pc_delta: Some(1),
+    sp_delta: Some(-1),
+    gas_delta: Some(GasCost::QUICK.as_u64()),
+    next_memory_size: None,
+};
+const NUM_PUSHED: usize = 1;
+
+impl_op_gadget!(
+    #set[MSIZE]
+    MsizeGadget {
+        MsizeSuccessCase(),
+        StackOverflowCase(NUM_PUSHED),
+        OutOfGasCase(STATE_TRANSITION.gas_delta.unwrap()),
+    }
+);
+
+#[derive(Clone, Debug)]
+struct MsizeSuccessCase<F> {
+    case_selector: Cell<F>,
+    msize: Word<F>,
+}
+
+impl<F: FieldExt> MsizeSuccessCase<F> {
+    pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::Success,
+        num_word: 1, // msize
+        num_cell: 0,
+        will_halt: false,
+    };
+
+    pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+            msize: alloc.words.pop().unwrap(),
+        }
+    }
+
+    pub(crate) fn constraint(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        state_next: &OpExecutionState<F>,
+        name: &'static str,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::default();
+
+        // The memory size is kept in words in the state, and pushed in bytes:
+        // - msize[7..0] = state.memory_size * 32
+        // - msize[32] + .. + msize[8] = 0
+        cb.require_equal(
+            from_bytes::expr(self.msize.cells[0..8].to_vec()),
+            state_curr.memory_size.expr() * 32.expr(),
+        );
+        cb.require_zero(sum::expr(&self.msize.cells[8..32]));
+
+        // Push the result on the stack
+        cb.stack_push(self.msize.expr());
+
+        // State transitions
+        STATE_TRANSITION.constraints(&mut cb, state_curr, state_next);
+
+        // Generate the constraint
+        vec![cb.constraint(self.case_selector.expr(), name)]
+    }
+
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: &mut CoreStateInstance,
+        step: &ExecutionStep,
+    ) -> Result<(), Error> {
+        // Output
+        self.msize
+            .assign(region, offset, Some(step.values[0].to_word()))?;
+
+        // State transitions
+        STATE_TRANSITION.assign(state);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::test::test_circuit_from_block;
+    use bus_mapping::{
+        bytecode, circuit_input_builder::CircuitInputBuilder, mock,
+    };
+    use halo2::dev::MockProver;
+    use pairing::bn256::Fr as Fp;
+
+    #[test]
+    fn msize_gadget_from_trace() {
+        // The size of the empty memory, followed by the size after an
+        // expansion to 3 words
+        let code = bytecode! {
+            MSIZE
+            PUSH1(0x01)
+            PUSH1(0x21)
+            MSTORE
+            MSIZE
+            STOP
+        };
+        let block = mock::BlockData::new_single_tx_trace_code(&code).unwrap();
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let circuit = test_circuit_from_block::<Fp>(&builder.block);
+        let prover = MockProver::<Fp>::run(11, &circuit, vec![vec![]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+}
@@ -0,0 +1,142 @@
+use super::super::{Case, Cell, Constraint, ExecutionStep, Word};
+use super::utils::{
+    self,
+    common_cases::{OutOfGasCase, StackUnderflowCase},
+    constraint_builder::ConstraintBuilder,
+    StateTransition,
+};
+use super::{
+    CaseAllocation, CaseConfig, CoreStateInstance, OpExecutionState, OpGadget,
+};
+use crate::impl_op_gadget;
+use crate::util::{Expr, ToWord};
+use bus_mapping::evm::{GasCost, OpcodeId};
+use halo2::plonk::Error;
+use halo2::{arithmetic::FieldExt, circuit::Region};
+
+static STATE_TRANSITION: StateTransition = StateTransition {
+    gc_delta: Some(2), // 1 stack pop + 1 stack push
+    pc_delta: Some(1),
+    sp_delta: Some(0),
+    gas_delta: Some(GasCost::FASTEST.as_u64()),
+    next_memory_size: None,
+};
+const NUM_POPPED: usize = 1;
+
+impl_op_gadget!(
+    #set[NOT]
+    NotGadget {
+        NotSuccessCase(),
+        StackUnderflowCase(NUM_POPPED),
+        OutOfGasCase(STATE_TRANSITION.gas_delta.unwrap()),
+    }
+);
+
+#[derive(Clone, Debug)]
+struct NotSuccessCase<F> {
+    case_selector: Cell<F>,
+    a: Word<F>,
+    b: Word<F>,
+}
+
+impl<F: FieldExt> NotSuccessCase<F> {
+    pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::Success,
+        num_word: 2, // a + b
+        num_cell: 0,
+        will_halt: false,
+    };
+
+    pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+            a: alloc.words.pop().unwrap(),
+            b: alloc.words.pop().unwrap(),
+        }
+    }
+
+    pub(crate) fn constraint(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        state_next: &OpExecutionState<F>,
+        name: &'static str,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::default();
+
+        // `b = !a` byte by byte, bytes are range checked by the word
+        // allocation so `a[i] + b[i] == 255` is enough.
+        for (a, b) in self.a.cells.iter().zip(self.b.cells.iter()) {
+            cb.require_equal(a.expr() + b.expr(), 255.expr());
+        }
+
+        // Pop a from the stack, push b on the stack
+        cb.stack_pop(self.a.expr());
+        cb.stack_push(self.b.expr());
+
+        // State transitions
+        STATE_TRANSITION.constraints(&mut cb, state_curr, state_next);
+
+        // Generate the constraint
+        vec![cb.constraint(self.case_selector.expr(), name)]
+    }
+
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: &mut CoreStateInstance,
+        step: &ExecutionStep,
+    ) -> Result<(), Error> {
+        // Input and output
+        let a = step.values[0].to_word();
+        let b = a.map(|byte| !byte);
+        self.a.assign(region, offset, Some(a))?;
+        self.b.assign(region, offset, Some(b))?;
+
+        // State transitions
+        STATE_TRANSITION.assign(state);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::test::run_test_circuit_from_code;
+    use bus_mapping::{bytecode, eth_types::Word, word};
+    use pairing::bn256::Fr as Fp;
+
+    fn check(value: Word) {
+        let code = bytecode! {
+            PUSH32(value)
+            NOT
+            STOP
+        };
+        assert_eq!(run_test_circuit_from_code::<Fp>(&code), Ok(()));
+    }
+
+    #[test]
+    fn not_gadget() {
+        // !0 == max
+        check(Word::zero());
+        // !max == 0
+        check(Word::MAX);
+        // !1 == max - 1
+        check(Word::one());
+        // !hi_lo == lo_hi
+        check(Word::MAX << 128);
+    }
+
+    #[test]
+    fn not_gadget_from_trace() {
+        let code = bytecode! {
+            PUSH32(0)
+            NOT
+            NOT
+            PUSH32(word!("ff00000000000000000000000000000000000000000000000000000000000001"))
+            NOT
+            STOP
+        };
+        assert_eq!(run_test_circuit_from_code::<Fp>(&code), Ok(()));
+    }
+}
@@ -0,0 +1,970 @@
+use super::super::{Case, Cell, Constraint, ExecutionStep, Word};
+use super::call::NUM_CALLER_WRITES;
+use super::utils::{
+    self,
+    common_cases::StackUnderflowCase,
+    constraint_builder::ConstraintBuilder,
+    from_bytes,
+    math_gadgets::{IsEqualGadget, IsZeroGadget, LtGadget},
+    memory_gadgets::{self, address_low, MemoryExpansionGadget},
+    select, sum, StateTransitionExpressions,
+};
+use super::{
+    CaseAllocation, CaseConfig, CoreStateInstance, OpExecutionState, OpGadget,
+};
+use crate::evm_circuit::param::{
+    MAX_COPY_BYTES, MAX_GAS_SIZE_IN_BYTES, MAX_REVERTED_WRITES,
+};
+use crate::impl_op_gadget;
+use crate::util::{Expr, ToWord};
+use array_init::array_init;
+use bus_mapping::{
+    evm::OpcodeId,
+    operation::{CallContextField, Target},
+};
+use halo2::plonk::Error;
+use halo2::{arithmetic::FieldExt, circuit::Region};
+use std::convert::TryInto;
+
+// 2 stack pops + call context reads of the depth, which is 1 when the root
+// call ends the tx, and of the caller id
+const NUM_READS_BEFORE_COPY: usize = 4;
+// Call context reads of the memory region of the caller receiving the
+// returned bytes
+const NUM_REGION_READS: usize = 2;
+// Call context reads of the success and of the gas of the call, reads of the
+// saved state of the caller and writes of the return data of its last callee
+pub(super) const NUM_CONTEXT_OPS_AFTER_COPY: usize = 2 + NUM_CALLER_WRITES + 3;
+
+// TODO: Add the RETURN of a contract creation, which deploys the returned
+// code, and the out of gas case.
+impl_op_gadget!(
+    #set[RETURN]
+    ReturnGadget {
+        ReturnSuccessCase(false),
+        StackUnderflowCase(2),
+    }
+);
+
+impl_op_gadget!(
+    #set[REVERT]
+    RevertGadget {
+        ReturnSuccessCase(true),
+        StackUnderflowCase(2),
+    }
+);
+
+// A state write undone by REVERT, given with the columns of the rw table
+// following the target, and the global counter of the write it undoes.
+#[derive(Clone, Debug)]
+struct RevertedWrite<F> {
+    flag: Cell<F>,
+    target: Cell<F>,
+    keys: [Cell<F>; 2],
+    // Value written back, and value of the undone write
+    value: Cell<F>,
+    value_prev: Cell<F>,
+    aux: [Cell<F>; 2],
+    reverted_gc: Cell<F>,
+    // The undone writes are after the start of the call, in the reverse
+    // order of the reversions
+    gc_lt: LtGadget<F, 4>,
+}
+
+impl<F: FieldExt> RevertedWrite<F> {
+    const NUM_CELLS: usize = 9 + LtGadget::<F, 4>::NUM_CELLS;
+
+    fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            flag: alloc.cells.pop().unwrap(),
+            target: alloc.cells.pop().unwrap(),
+            keys: array_init(|_| alloc.cells.pop().unwrap()),
+            value: alloc.cells.pop().unwrap(),
+            value_prev: alloc.cells.pop().unwrap(),
+            aux: array_init(|_| alloc.cells.pop().unwrap()),
+            reverted_gc: alloc.cells.pop().unwrap(),
+            gc_lt: LtGadget::construct(alloc),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct ReturnSuccessCase<F> {
+    case_selector: Cell<F>,
+    // REVERT makes the call non-persistent and undoes its state writes, while
+    // RETURN doesn't.
+    is_revert: bool,
+    offset: Word<F>,
+    length: Word<F>,
+    // Memory region of the caller receiving the returned bytes
+    return_data_offset: Cell<F>,
+    return_data_length: Cell<F>,
+    copy_flags: [Cell<F>; MAX_COPY_BYTES],
+    bytes: [Cell<F>; MAX_COPY_BYTES],
+    // The root call has no caller, and ends the tx
+    depth: Cell<F>,
+    caller_id: Cell<F>,
+    is_root: IsEqualGadget<F>,
+    // State of the caller saved by the call, in words for the memory size
+    caller_program_counter: Cell<F>,
+    caller_stack_pointer: Cell<F>,
+    caller_gas_left: Cell<F>,
+    caller_memory_size: Cell<F>,
+    caller_state_write_counter: Cell<F>,
+    caller_code_hash: Word<F>,
+    // Gas given to the callee by the call
+    callee_gas: Cell<F>,
+    // State writes undone by REVERT, from the last one to the first one
+    reverted_writes: [RevertedWrite<F>; MAX_REVERTED_WRITES],
+    first_reverted_gc_lt_gc: LtGadget<F, 4>,
+    length_lt_return_data_length: LtGadget<F, 8>,
+    length_is_zero: IsZeroGadget<F>,
+    memory_expansion: MemoryExpansionGadget<F, MAX_GAS_SIZE_IN_BYTES>,
+}
+
+impl<F: FieldExt> ReturnSuccessCase<F> {
+    pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::Success,
+        num_word: 3, // offset + length + caller_code_hash
+        // return_data_offset + return_data_length + depth + caller_id + the
+        // 5 saved fields of the caller in cells + callee_gas
+        num_cell: 10
+            + 2 * MAX_COPY_BYTES // copy_flags + bytes
+            + IsEqualGadget::<F>::NUM_CELLS
+            + MAX_REVERTED_WRITES * RevertedWrite::<F>::NUM_CELLS
+            + LtGadget::<F, 4>::NUM_CELLS
+            + LtGadget::<F, 8>::NUM_CELLS
+            + IsZeroGadget::<F>::NUM_CELLS
+            + MemoryExpansionGadget::<F, MAX_GAS_SIZE_IN_BYTES>::NUM_CELLS,
+        will_halt: false,
+    };
+
+    pub(crate) fn construct(
+        alloc: &mut CaseAllocation<F>,
+        is_revert: bool,
+    ) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+            is_revert,
+            offset: alloc.words.pop().unwrap(),
+            length: alloc.words.pop().unwrap(),
+            caller_code_hash: alloc.words.pop().unwrap(),
+            return_data_offset: alloc.cells.pop().unwrap(),
+            return_data_length: alloc.cells.pop().unwrap(),
+            copy_flags: array_init(|_| alloc.cells.pop().unwrap()),
+            bytes: array_init(|_| alloc.cells.pop().unwrap()),
+            depth: alloc.cells.pop().unwrap(),
+            caller_id: alloc.cells.pop().unwrap(),
+            is_root: IsEqualGadget::construct(alloc),
+            caller_program_counter: alloc.cells.pop().unwrap(),
+            caller_stack_pointer: alloc.cells.pop().unwrap(),
+            caller_gas_left: alloc.cells.pop().unwrap(),
+            caller_memory_size: alloc.cells.pop().unwrap(),
+            caller_state_write_counter: alloc.cells.pop().unwrap(),
+            callee_gas: alloc.cells.pop().unwrap(),
+            reverted_writes: array_init(|_| RevertedWrite::construct(alloc)),
+            first_reverted_gc_lt_gc: LtGadget::construct(alloc),
+            length_lt_return_data_length: LtGadget::construct(alloc),
+            length_is_zero: IsZeroGadget::construct(alloc),
+            memory_expansion: MemoryExpansionGadget::construct(alloc),
+        }
+    }
+
+    pub(crate) fn constraint(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        state_next: &OpExecutionState<F>,
+        name: &'static str,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::with_call_id(state_curr.call_id.expr());
+
+        // Pop the offset and the length of the returned memory, and read the
+        // depth to know whether the root call ends, and the caller id
+        cb.stack_pop(self.offset.expr());
+        cb.stack_pop(self.length.expr());
+        for (field, value) in [
+            (CallContextField::Depth, self.depth.expr()),
+            (CallContextField::CallerId, self.caller_id.expr()),
+        ] {
+            cb.call_context_lookup(
+                false,
+                state_curr.call_id.expr(),
+                field,
+                value,
+            );
+        }
+        let is_root =
+            self.is_root
+                .constraints(&mut cb, self.depth.expr(), 1.expr());
+        let not_root = 1.expr() - is_root.clone();
+
+        // Read where the caller expects the returned bytes
+        let mut gc_offset = cb.gc_offset.expr();
+        cb.condition(not_root.clone(), |cb| {
+            for (idx, (field, value)) in [
+                (
+                    CallContextField::ReturnDataOffset,
+                    self.return_data_offset.expr(),
+                ),
+                (
+                    CallContextField::ReturnDataLength,
+                    self.return_data_length.expr(),
+                ),
+            ]
+            .iter()
+            .enumerate()
+            {
+                cb.call_context_lookup_at(
+                    false,
+                    state_curr.call_id.expr(),
+                    *field,
+                    value.clone(),
+                    gc_offset.clone() + idx.expr(),
+                );
+            }
+        });
+        gc_offset = gc_offset + NUM_REGION_READS.expr() * not_root.clone();
+
+        // The length always fits in the low bytes, while the offset is only
+        // used when bytes are returned
+        memory_gadgets::require_address_in_range(&mut cb, &self.length);
+        let length = address_low::expr(&self.length);
+        let length_is_zero =
+            self.length_is_zero.constraints(&mut cb, length.clone());
+        cb.condition(1.expr() - length_is_zero.clone(), |cb| {
+            memory_gadgets::require_address_in_range(cb, &self.offset);
+        });
+        let offset = address_low::expr(&self.offset);
+
+        // The returned bytes are copied into the memory of the caller up to
+        // the length of its region, and the copy flags are set for the first
+        // `min(length, return_data_length)` bytes, or none when the root call
+        // ends
+        let length_lt_return_data_length =
+            self.length_lt_return_data_length.constraints(
+                &mut cb,
+                length.clone(),
+                self.return_data_length.expr(),
+            );
+        for (idx, copy_flag) in self.copy_flags.iter().enumerate() {
+            cb.require_boolean(copy_flag.expr());
+            if idx > 0 {
+                cb.require_zero(
+                    copy_flag.expr()
+                        * (1.expr() - self.copy_flags[idx - 1].expr()),
+                );
+            }
+        }
+        cb.require_equal(
+            sum::expr(&self.copy_flags),
+            not_root.clone()
+                * select::expr(
+                    length_lt_return_data_length,
+                    length.clone(),
+                    self.return_data_length.expr(),
+                ),
+        );
+
+        // Copy the bytes: read each of them from the memory and write it into
+        // the memory of the caller
+        for (idx, (copy_flag, byte)) in
+            self.copy_flags.iter().zip(self.bytes.iter()).enumerate()
+        {
+            cb.condition(copy_flag.expr(), |cb| {
+                cb.memory_lookup_at(
+                    offset.clone() + idx.expr(),
+                    byte.expr(),
+                    0.expr(),
+                    gc_offset.clone(),
+                );
+                cb.memory_lookup_with_call_id_at(
+                    self.caller_id.expr(),
+                    self.return_data_offset.expr() + idx.expr(),
+                    byte.expr(),
+                    1.expr(),
+                    gc_offset.clone() + 1.expr(),
+                );
+            });
+            gc_offset = gc_offset + 2.expr() * copy_flag.expr();
+        }
+
+        if self.is_revert {
+            // REVERT makes the call non-persistent
+            cb.call_context_lookup_at(
+                true,
+                state_curr.call_id.expr(),
+                CallContextField::IsPersistent,
+                0.expr(),
+                gc_offset.clone(),
+            );
+            gc_offset = gc_offset + 1.expr();
+
+            // and undoes the state writes of the call and of its successful
+            // callees, which are counted by its state write counter, from the
+            // last one to the first one.  Each reversion writes back the value
+            // before a write of the same key done after the start of the
+            // call, which is its id, and before the write undone by the
+            // previous reversion.
+            cb.require_equal(
+                self.reverted_writes
+                    .iter()
+                    .fold(0.expr(), |acc, write| acc + write.flag.expr()),
+                state_curr.state_write_counter.expr(),
+            );
+            let first_reverted_gc_lt_gc =
+                self.first_reverted_gc_lt_gc.constraints(
+                    &mut cb,
+                    self.reverted_writes[0].reverted_gc.expr(),
+                    state_curr.global_counter.expr(),
+                );
+            cb.require_zero(
+                self.reverted_writes[0].flag.expr()
+                    * (1.expr() - first_reverted_gc_lt_gc),
+            );
+            for (idx, write) in self.reverted_writes.iter().enumerate() {
+                cb.require_boolean(write.flag.expr());
+                let lower_gc = match self.reverted_writes.get(idx + 1) {
+                    Some(next) => {
+                        cb.require_zero(
+                            next.flag.expr() * (1.expr() - write.flag.expr()),
+                        );
+                        select::expr(
+                            next.flag.expr(),
+                            next.reverted_gc.expr(),
+                            state_curr.call_id.expr(),
+                        )
+                    }
+                    None => state_curr.call_id.expr(),
+                };
+                let lower_gc_lt_reverted_gc = write.gc_lt.constraints(
+                    &mut cb,
+                    lower_gc,
+                    write.reverted_gc.expr(),
+                );
+                cb.condition(write.flag.expr(), |cb| {
+                    cb.require_equal(lower_gc_lt_reverted_gc, 1.expr());
+                    cb.require_in_set(
+                        write.target.expr(),
+                        vec![
+                            Target::Storage.expr(),
+                            Target::TxAccessListAccountStorage.expr(),
+                            Target::TxRefund.expr(),
+                            Target::AccountNonce.expr(),
+                            Target::AccountBalance.expr(),
+                            Target::AccountCodeHash.expr(),
+                            Target::TxAccessListAccount.expr(),
+                        ],
+                    );
+                    let keys = [write.keys[0].expr(), write.keys[1].expr()];
+                    let aux = [write.aux[0].expr(), write.aux[1].expr()];
+                    cb.state_write_lookup_at(
+                        write.target.expr(),
+                        keys.clone(),
+                        write.value_prev.expr(),
+                        write.value.expr(),
+                        aux.clone(),
+                        write.reverted_gc.expr()
+                            - state_curr.global_counter.expr(),
+                    );
+                    cb.state_write_lookup_at(
+                        write.target.expr(),
+                        keys,
+                        write.value.expr(),
+                        write.value_prev.expr(),
+                        aux,
+                        gc_offset.clone(),
+                    );
+                });
+                gc_offset = gc_offset + write.flag.expr();
+            }
+        }
+
+        // Read the success of the call pushed on the stack of the caller and
+        // the gas given to the call, which are set up by the call, and the
+        // saved state of the caller, and record the returned bytes as the
+        // return data of its last callee.
+        cb.condition(not_root.clone(), |cb| {
+            for (idx, (is_write, call_id, field, value)) in [
+                (
+                    false,
+                    state_curr.call_id.expr(),
+                    CallContextField::IsSuccess,
+                    (!self.is_revert).expr(),
+                ),
+                (
+                    false,
+                    state_curr.call_id.expr(),
+                    CallContextField::Gas,
+                    self.callee_gas.expr(),
+                ),
+                (
+                    false,
+                    self.caller_id.expr(),
+                    CallContextField::ProgramCounter,
+                    self.caller_program_counter.expr(),
+                ),
+                (
+                    false,
+                    self.caller_id.expr(),
+                    CallContextField::StackPointer,
+                    self.caller_stack_pointer.expr(),
+                ),
+                (
+                    false,
+                    self.caller_id.expr(),
+                    CallContextField::GasLeft,
+                    self.caller_gas_left.expr(),
+                ),
+                (
+                    false,
+                    self.caller_id.expr(),
+                    CallContextField::MemorySize,
+                    self.caller_memory_size.expr() * 32.expr(),
+                ),
+                (
+                    false,
+                    self.caller_id.expr(),
+                    CallContextField::StateWriteCounter,
+                    self.caller_state_write_counter.expr(),
+                ),
+                (
+                    false,
+                    self.caller_id.expr(),
+                    CallContextField::CodeHash,
+                    self.caller_code_hash.expr(),
+                ),
+                (
+                    true,
+                    self.caller_id.expr(),
+                    CallContextField::LastCalleeId,
+                    state_curr.call_id.expr(),
+                ),
+                (
+                    true,
+                    self.caller_id.expr(),
+                    CallContextField::LastCalleeReturnDataOffset,
+                    from_bytes::expr(self.offset.cells[..8].to_vec()),
+                ),
+                (
+                    true,
+                    self.caller_id.expr(),
+                    CallContextField::LastCalleeReturnDataLength,
+                    length.clone(),
+                ),
+            ]
+            .iter()
+            .enumerate()
+            {
+                cb.call_context_lookup_at(
+                    *is_write,
+                    call_id.clone(),
+                    *field,
+                    value.clone(),
+                    gc_offset.clone() + idx.expr(),
+                );
+            }
+        });
+        gc_offset =
+            gc_offset + NUM_CONTEXT_OPS_AFTER_COPY.expr() * not_root.clone();
+
+        // The memory is expanded for the returned bytes, unless none are
+        // returned
+        let (next_memory_size, memory_cost) =
+            self.memory_expansion.constraints(
+                &mut cb,
+                state_curr.memory_size.expr(),
+                (1.expr() - length_is_zero) * (offset + length),
+            );
+
+        // State transitions to the step of the caller following the call, or
+        // to the end of the tx, which stays in the root call.  The gas given
+        // to the callee was paid by the call and the gas used by the callee
+        // is accounted again by its steps, so the callee gas is refunded.
+        cb.require_equal(
+            state_next.call_id.expr(),
+            select::expr(
+                is_root.clone(),
+                state_curr.call_id.expr(),
+                self.caller_id.expr(),
+            ),
+        );
+        // The state writes of the call are added to the ones of the caller,
+        // unless REVERT undid them
+        let state_write_counter = if self.is_revert {
+            0.expr()
+        } else {
+            state_curr.state_write_counter.expr()
+        };
+        let next_state_write_counter = state_write_counter
+            + not_root.clone() * self.caller_state_write_counter.expr();
+        let st = StateTransitionExpressions {
+            gc_delta: Some(gc_offset),
+            pc_delta: Some(
+                not_root.clone()
+                    * (self.caller_program_counter.expr()
+                        - state_curr.program_counter.expr()),
+            ),
+            sp_delta: Some(
+                not_root.clone()
+                    * (self.caller_stack_pointer.expr()
+                        - state_curr.stack_pointer.expr()),
+            ),
+            gas_delta: Some(
+                memory_cost.clone() - not_root.clone() * self.callee_gas.expr(),
+            ),
+            next_gas_left: Some(
+                state_curr.gas_left.expr() - memory_cost
+                    + not_root * self.caller_gas_left.expr(),
+            ),
+            next_memory_size: Some(select::expr(
+                is_root.clone(),
+                next_memory_size,
+                self.caller_memory_size.expr(),
+            )),
+            next_code_source: Some(select::expr(
+                is_root,
+                state_curr.code_source.expr(),
+                self.caller_code_hash.expr(),
+            )),
+            log_id_delta: None,
+            next_state_write_counter: Some(next_state_write_counter),
+        };
+        st.constraints(&mut cb, state_curr, state_next);
+
+        // Generate the constraint
+        vec![cb.constraint(self.case_selector.expr(), name)]
+    }
+
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: &mut CoreStateInstance,
+        step: &ExecutionStep,
+    ) -> Result<(), Error> {
+        // Inputs, depth and caller id, followed by the values of the other
+        // call context operations, the copied bytes and, for REVERT, the number
+        // of reversions and the values of each of them
+        let memory_offset = step.values[0].to_word();
+        let length = step.values[1].to_word();
+        self.offset.assign(region, offset, Some(memory_offset))?;
+        self.length.assign(region, offset, Some(length))?;
+        let value_u64 = |idx: usize| -> u64 {
+            step.values[idx].clone().try_into().unwrap()
+        };
+        let value_f = |idx: usize| -> F {
+            F::from_bytes(&step.values[idx].to_word()).unwrap()
+        };
+        let (depth, caller_id) = (
+            value_u64(NUM_READS_BEFORE_COPY - 2),
+            value_u64(NUM_READS_BEFORE_COPY - 1),
+        );
+        self.depth.assign(region, offset, Some(F::from(depth)))?;
+        self.caller_id
+            .assign(region, offset, Some(F::from(caller_id)))?;
+        let is_root =
+            self.is_root
+                .assign(region, offset, F::from(depth), F::one())?
+                == F::one();
+
+        let mut values_idx = NUM_READS_BEFORE_COPY;
+        let (return_data_offset, return_data_length) = if is_root {
+            (0, 0)
+        } else {
+            values_idx += NUM_REGION_READS;
+            (value_u64(values_idx - 2), value_u64(values_idx - 1))
+        };
+        self.return_data_offset.assign(
+            region,
+            offset,
+            Some(F::from(return_data_offset)),
+        )?;
+        self.return_data_length.assign(
+            region,
+            offset,
+            Some(F::from(return_data_length)),
+        )?;
+
+        // The success and the gas of the call follow the non-persistent write
+        // of REVERT, and are followed by the saved state of the caller
+        values_idx += self.is_revert as usize;
+        let caller_values = if is_root {
+            vec![0; NUM_CALLER_WRITES - 1]
+        } else {
+            (1..NUM_CALLER_WRITES)
+                .map(|idx| value_u64(values_idx + idx))
+                .collect::<Vec<_>>()
+        };
+        for (cell, value) in [
+            &self.callee_gas,
+            &self.caller_program_counter,
+            &self.caller_stack_pointer,
+            &self.caller_gas_left,
+        ]
+        .iter()
+        .zip(caller_values.iter())
+        {
+            cell.assign(region, offset, Some(F::from(*value)))?;
+        }
+        let (callee_gas, caller_memory_size) =
+            (caller_values[0], caller_values[4] / 32);
+        self.caller_memory_size.assign(
+            region,
+            offset,
+            Some(F::from(caller_memory_size)),
+        )?;
+        let caller_state_write_counter = if is_root {
+            0
+        } else {
+            value_u64(values_idx + NUM_CALLER_WRITES)
+        };
+        self.caller_state_write_counter.assign(
+            region,
+            offset,
+            Some(F::from(caller_state_write_counter)),
+        )?;
+        let caller_code_hash = if is_root {
+            [0u8; 32]
+        } else {
+            step.values[values_idx + NUM_CALLER_WRITES + 1].to_word()
+        };
+        self.caller_code_hash
+            .assign(region, offset, Some(caller_code_hash))?;
+        if !is_root {
+            values_idx += NUM_CONTEXT_OPS_AFTER_COPY;
+        }
+
+        let length = address_low::value::<F>(length);
+        let num_bytes = length.min(return_data_length);
+        self.length_lt_return_data_length.assign(
+            region,
+            offset,
+            F::from(length),
+            F::from(return_data_length),
+        )?;
+        for (idx, copy_flag) in self.copy_flags.iter().enumerate() {
+            copy_flag.assign(
+                region,
+                offset,
+                Some(F::from(((idx as u64) < num_bytes) as u64)),
+            )?;
+        }
+        let bytes = step.values[values_idx].to_word();
+        for (cell, byte) in self.bytes.iter().zip(bytes.iter()) {
+            cell.assign(region, offset, Some(F::from(*byte as u64)))?;
+        }
+        values_idx += 1;
+
+        // The reversions, each with its target, keys, value, value_prev,
+        // auxiliary values and the global counter of the undone write
+        let num_reverted_writes = if self.is_revert {
+            value_u64(values_idx) as usize
+        } else {
+            0
+        };
+        if num_reverted_writes > MAX_REVERTED_WRITES {
+            return Err(Error::Synthesis);
+        }
+        let reverted_gcs = (0..MAX_REVERTED_WRITES)
+            .map(|idx| {
+                if idx < num_reverted_writes {
+                    value_f(values_idx + 8 * idx + 8)
+                } else {
+                    F::zero()
+                }
+            })
+            .collect::<Vec<_>>();
+        if self.is_revert {
+            self.first_reverted_gc_lt_gc.assign(
+                region,
+                offset,
+                reverted_gcs[0],
+                F::from(state.global_counter as u64),
+            )?;
+            for (idx, write) in self.reverted_writes.iter().enumerate() {
+                let is_reverted = idx < num_reverted_writes;
+                write.flag.assign(
+                    region,
+                    offset,
+                    Some(F::from(is_reverted as u64)),
+                )?;
+                for (jdx, cell) in [
+                    &write.target,
+                    &write.keys[0],
+                    &write.keys[1],
+                    &write.value,
+                    &write.value_prev,
+                    &write.aux[0],
+                    &write.aux[1],
+                    &write.reverted_gc,
+                ]
+                .iter()
+                .enumerate()
+                {
+                    let value = if is_reverted {
+                        value_f(values_idx + 8 * idx + 1 + jdx)
+                    } else {
+                        F::zero()
+                    };
+                    cell.assign(region, offset, Some(value))?;
+                }
+                let lower_gc = if idx + 1 < num_reverted_writes {
+                    reverted_gcs[idx + 1]
+                } else {
+                    F::from(state.call_id as u64)
+                };
+                write.gc_lt.assign(
+                    region,
+                    offset,
+                    lower_gc,
+                    reverted_gcs[idx],
+                )?;
+            }
+        }
+
+        // Memory expansion
+        let length_is_zero =
+            self.length_is_zero
+                .assign(region, offset, F::from(length))?;
+        let address = if length_is_zero == F::one() {
+            0
+        } else {
+            address_low::value::<F>(memory_offset) + length
+        };
+        let (next_memory_size, memory_cost) = self.memory_expansion.assign(
+            region,
+            offset,
+            state.memory_size as u64,
+            address,
+        )?;
+
+        // State transitions to the step of the caller following the call, or
+        // to the end of the tx
+        state.global_counter += NUM_READS_BEFORE_COPY
+            + self.is_revert as usize
+            + num_reverted_writes;
+        state.gas_counter = state.gas_counter + memory_cost as u64 - callee_gas;
+        state.gas_left = state.gas_left + caller_values[3] - memory_cost as u64;
+        if self.is_revert {
+            state.state_write_counter = 0;
+        }
+        state.state_write_counter += caller_state_write_counter as usize;
+        if is_root {
+            state.memory_size = next_memory_size;
+        } else {
+            state.global_counter += NUM_REGION_READS
+                + 2 * num_bytes as usize
+                + NUM_CONTEXT_OPS_AFTER_COPY;
+            state.call_id = caller_id as usize;
+            state.program_counter = caller_values[1] as usize;
+            state.stack_pointer = caller_values[2] as usize;
+            state.memory_size = caller_memory_size;
+            state.code_source = caller_code_hash;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::{
+        test::test_circuit_from_block, BlockCircuit, WitnessError,
+    };
+    use bus_mapping::{
+        bytecode, bytecode::Bytecode,
+        circuit_input_builder::CircuitInputBuilder, evm::OpcodeId, mock,
+    };
+    use halo2::dev::MockProver;
+    use pairing::bn256::Fr as Fp;
+
+    fn test_return_gadget(op: OpcodeId, length: u64) {
+        // The caller reserves 2 bytes at 0x20 for the bytes returned by the
+        // callee, which stores them at the end of its first word of memory
+        let code = bytecode! {
+            PUSH1(0x02) // retLength
+            PUSH1(0x20) // retOffset
+            PUSH1(0x00) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH1(0x00) // value
+            PUSH2(0x123) // addr
+            PUSH2(0x1000) // gas
+            CALL
+            STOP
+        };
+        let callee_code = bytecode! {
+            PUSH32(0xaabbccddu64)
+            PUSH1(0x00)
+            MSTORE
+            PUSH1(length) // length
+            PUSH1(0x20 - length) // offset
+            .write_op(op)
+        };
+        let block =
+            mock::BlockData::new_single_tx_trace_code_2(&code, &callee_code)
+                .unwrap();
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let circuit = test_circuit_from_block::<Fp>(&builder.block);
+        let prover = MockProver::<Fp>::run(11, &circuit, vec![vec![]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    fn return_gadget_from_trace() {
+        // More, less and no bytes than the region of the caller
+        for length in [4, 1, 0] {
+            test_return_gadget(OpcodeId::RETURN, length);
+        }
+    }
+
+    #[test]
+    fn revert_gadget_from_trace() {
+        test_return_gadget(OpcodeId::REVERT, 4);
+    }
+
+    #[test]
+    fn return_gadget_too_many_bytes() {
+        // A step can't copy more than 32 bytes into the memory of the caller
+        let code = bytecode! {
+            PUSH1(0x21) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x00) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH1(0x00) // value
+            PUSH2(0x123) // addr
+            PUSH2(0x1000) // gas
+            CALL
+            STOP
+        };
+        let callee_code = bytecode! {
+            PUSH1(0x21) // length
+            PUSH1(0x00) // offset
+            RETURN
+        };
+        let block =
+            mock::BlockData::new_single_tx_trace_code_2(&code, &callee_code)
+                .unwrap();
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        assert_eq!(
+            BlockCircuit::<Fp, 1, 16>::new(&builder.block).err(),
+            Some(WitnessError::TooManyCopiedBytes(0))
+        );
+    }
+
+    fn test_root_return_gadget(code: Bytecode) {
+        let block = mock::BlockData::new_single_tx_trace_code(&code).unwrap();
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let circuit = test_circuit_from_block::<Fp>(&builder.block);
+        let prover = MockProver::<Fp>::run(11, &circuit, vec![vec![]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    fn root_return_gadget_from_trace() {
+        // RETURN and REVERT of the root call end the tx, which reverts its
+        // storage write in the case of REVERT
+        for op in [OpcodeId::RETURN, OpcodeId::REVERT] {
+            test_root_return_gadget(bytecode! {
+                PUSH1(0x01) // value
+                PUSH1(0x00) // key
+                SSTORE
+                PUSH1(0x02) // length
+                PUSH1(0x1e) // offset
+                .write_op(op)
+            });
+        }
+    }
+
+    #[test]
+    fn revert_gadget_undoes_state_writes() {
+        // The callee writes a storage slot, which is warmed up and written
+        // back by the reversions of REVERT
+        let code = bytecode! {
+            PUSH1(0x00) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x00) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH1(0x00) // value
+            PUSH2(0x123) // addr
+            PUSH2(0xffff) // gas
+            CALL
+            STOP
+        };
+        let callee_code = bytecode! {
+            PUSH1(0x01) // value
+            PUSH1(0x00) // key
+            SSTORE
+            PUSH1(0x00) // length
+            PUSH1(0x00) // offset
+            REVERT
+        };
+        let block =
+            mock::BlockData::new_single_tx_trace_code_2(&code, &callee_code)
+                .unwrap();
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let circuit = test_circuit_from_block::<Fp>(&builder.block);
+        let prover = MockProver::<Fp>::run(11, &circuit, vec![vec![]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    fn revert_gadget_undoes_state_writes_of_callees() {
+        // The storage write of the callee is added to the state writes of the
+        // root call when the callee returns, so the REVERT of the root call
+        // undoes it with the value transfer of the tx and the warm status of
+        // the callee
+        let code = bytecode! {
+            PUSH1(0x00) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x00) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH1(0x00) // value
+            PUSH2(0x123) // addr
+            PUSH2(0xffff) // gas
+            CALL
+            PUSH1(0x00) // length
+            PUSH1(0x00) // offset
+            REVERT
+        };
+        let callee_code = bytecode! {
+            PUSH1(0x01) // value
+            PUSH1(0x00) // key
+            SSTORE
+            PUSH1(0x00) // length
+            PUSH1(0x00) // offset
+            RETURN
+        };
+        let block =
+            mock::BlockData::new_single_tx_trace_code_2(&code, &callee_code)
+                .unwrap();
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let circuit = test_circuit_from_block::<Fp>(&builder.block);
+        let prover = MockProver::<Fp>::run(11, &circuit, vec![vec![]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+}
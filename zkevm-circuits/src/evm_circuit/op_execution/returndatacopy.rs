@@ -0,0 +1,513 @@
+use super::super::{Case, Cell, Constraint, ExecutionStep, Word};
+use super::utils::{
+    self,
+    common_cases::{MemoryCopyOutOfGasCase, StackUnderflowCase},
+    constraint_builder::ConstraintBuilder,
+    math_gadgets::{IsZeroGadget, LtGadget},
+    memory_gadgets::{
+        self, address_high, address_low, MemoryExpansionGadget,
+        MemorySizeGadget,
+    },
+    sum, StateTransition, StateTransitionExpressions,
+};
+use super::{
+    CaseAllocation, CaseConfig, CoreStateInstance, OpExecutionState, OpGadget,
+};
+use crate::evm_circuit::param::{MAX_COPY_BYTES, MAX_GAS_SIZE_IN_BYTES};
+use crate::impl_op_gadget;
+use crate::util::{Expr, ToWord};
+use array_init::array_init;
+use bus_mapping::{
+    evm::{GasCost, OpcodeId},
+    operation::CallContextField,
+};
+use halo2::plonk::Error;
+use halo2::{arithmetic::FieldExt, circuit::Region, plonk::Expression};
+use std::convert::TryInto;
+
+static STATE_TRANSITION: StateTransition = StateTransition {
+    gc_delta: None, // GC_DELTA + the memory reads and writes of the copy
+    pc_delta: Some(1),
+    sp_delta: Some(3),
+    gas_delta: None, // GAS + GasCost::COPY * words + memory_cost
+    next_memory_size: None, // next_memory_size
+};
+const GAS: GasCost = GasCost::FASTEST;
+// 3 stack pops + 3 call context reads
+const GC_DELTA: usize = 6;
+const NUM_POPPED: usize = 3;
+
+impl_op_gadget!(
+    #set[RETURNDATACOPY]
+    ReturndatacopyGadget {
+        ReturndatacopySuccessCase(),
+        StackUnderflowCase(NUM_POPPED),
+        ReturnDataOutOfBoundsCase(),
+        MemoryCopyOutOfGasCase(),
+    }
+);
+
+// Pops the arguments of RETURNDATACOPY, and reads the id of the last callee
+// and the offset and length of its return data in its memory
+fn read_return_data<F: FieldExt>(
+    cb: &mut ConstraintBuilder<F>,
+    state_curr: &OpExecutionState<F>,
+    args: [&Word<F>; 3],
+    context: [&Cell<F>; 3],
+) {
+    for arg in args {
+        cb.stack_pop(arg.expr());
+    }
+    for (field, cell) in [
+        (CallContextField::LastCalleeId, context[0]),
+        (CallContextField::LastCalleeReturnDataOffset, context[1]),
+        (CallContextField::LastCalleeReturnDataLength, context[2]),
+    ] {
+        cb.call_context_lookup(
+            false,
+            state_curr.call_id.expr(),
+            field,
+            cell.expr(),
+        );
+    }
+}
+
+// Returns the end of the copied return data, which is only meaningful when
+// the high bytes of both the data offset and the length are zero
+fn copy_end<F: FieldExt>(
+    data_offset: &Word<F>,
+    length: &Word<F>,
+) -> (Expression<F>, Expression<F>) {
+    (
+        address_high::expr(data_offset) + address_high::expr(length),
+        address_low::expr(data_offset) + address_low::expr(length),
+    )
+}
+
+#[derive(Clone, Debug)]
+struct ReturndatacopySuccessCase<F> {
+    case_selector: Cell<F>,
+    memory_offset: Word<F>,
+    data_offset: Word<F>,
+    length: Word<F>,
+    callee_id: Cell<F>,
+    return_data_offset: Cell<F>,
+    return_data_length: Cell<F>,
+    copy_flags: [Cell<F>; MAX_COPY_BYTES],
+    bytes: [Cell<F>; MAX_COPY_BYTES],
+    is_out_of_bounds: LtGadget<F, 8>,
+    length_is_zero: IsZeroGadget<F>,
+    copy_words: MemorySizeGadget<F>,
+    memory_expansion: MemoryExpansionGadget<F, MAX_GAS_SIZE_IN_BYTES>,
+}
+
+impl<F: FieldExt> ReturndatacopySuccessCase<F> {
+    pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::Success,
+        num_word: 3, // memory_offset + data_offset + length
+        num_cell: 3 // callee_id + return data offset and length
+            + 2 * MAX_COPY_BYTES // copy_flags + bytes
+            + LtGadget::<F, 8>::NUM_CELLS
+            + IsZeroGadget::<F>::NUM_CELLS
+            + MemorySizeGadget::<F>::NUM_CELLS
+            + MemoryExpansionGadget::<F, MAX_GAS_SIZE_IN_BYTES>::NUM_CELLS,
+        will_halt: false,
+    };
+
+    pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+            memory_offset: alloc.words.pop().unwrap(),
+            data_offset: alloc.words.pop().unwrap(),
+            length: alloc.words.pop().unwrap(),
+            callee_id: alloc.cells.pop().unwrap(),
+            return_data_offset: alloc.cells.pop().unwrap(),
+            return_data_length: alloc.cells.pop().unwrap(),
+            copy_flags: array_init(|_| alloc.cells.pop().unwrap()),
+            bytes: array_init(|_| alloc.cells.pop().unwrap()),
+            is_out_of_bounds: LtGadget::construct(alloc),
+            length_is_zero: IsZeroGadget::construct(alloc),
+            copy_words: MemorySizeGadget::construct(alloc),
+            memory_expansion: MemoryExpansionGadget::construct(alloc),
+        }
+    }
+
+    pub(crate) fn constraint(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        state_next: &OpExecutionState<F>,
+        name: &'static str,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::with_call_id(state_curr.call_id.expr());
+
+        // Pop the memory offset, the return data offset and the length from
+        // the stack, and read where the return data is from the call context
+        read_return_data(
+            &mut cb,
+            state_curr,
+            [&self.memory_offset, &self.data_offset, &self.length],
+            [
+                &self.callee_id,
+                &self.return_data_offset,
+                &self.return_data_length,
+            ],
+        );
+
+        // The copied bytes are within the return data
+        let (end_high, end) = copy_end(&self.data_offset, &self.length);
+        cb.require_zero(end_high);
+        let is_out_of_bounds = self.is_out_of_bounds.constraints(
+            &mut cb,
+            self.return_data_length.expr(),
+            end,
+        );
+        cb.require_zero(is_out_of_bounds);
+
+        // The copy flags are set for the first `length` bytes, which limits
+        // the length to MAX_COPY_BYTES
+        let length = address_low::expr(&self.length);
+        for (idx, copy_flag) in self.copy_flags.iter().enumerate() {
+            cb.require_boolean(copy_flag.expr());
+            if idx > 0 {
+                cb.require_zero(
+                    copy_flag.expr()
+                        * (1.expr() - self.copy_flags[idx - 1].expr()),
+                );
+            }
+        }
+        cb.require_equal(sum::expr(&self.copy_flags), length.clone());
+        let length_is_zero =
+            self.length_is_zero.constraints(&mut cb, length.clone());
+
+        // The memory offset is only used when bytes are copied
+        cb.condition(1.expr() - length_is_zero.clone(), |cb| {
+            memory_gadgets::require_address_in_range(cb, &self.memory_offset);
+        });
+        let memory_offset = address_low::expr(&self.memory_offset);
+
+        // Copy the bytes: read each of them from the memory of the last
+        // callee and write it into the memory
+        let data_offset = address_low::expr(&self.data_offset);
+        let mut gc_offset = cb.gc_offset.expr();
+        for (idx, (copy_flag, byte)) in
+            self.copy_flags.iter().zip(self.bytes.iter()).enumerate()
+        {
+            cb.condition(copy_flag.expr(), |cb| {
+                cb.memory_lookup_with_call_id_at(
+                    self.callee_id.expr(),
+                    self.return_data_offset.expr()
+                        + data_offset.clone()
+                        + idx.expr(),
+                    byte.expr(),
+                    0.expr(),
+                    gc_offset.clone(),
+                );
+                cb.memory_lookup_at(
+                    memory_offset.clone() + idx.expr(),
+                    byte.expr(),
+                    1.expr(),
+                    gc_offset.clone() + 1.expr(),
+                );
+            });
+            gc_offset = gc_offset + 2.expr() * copy_flag.expr();
+        }
+
+        // Calculate the next memory size and the gas cost for this memory
+        // access, where the memory is not expanded when no bytes are copied
+        let (next_memory_size, memory_cost) =
+            self.memory_expansion.constraints(
+                &mut cb,
+                state_curr.memory_size.expr(),
+                (1.expr() - length_is_zero) * (memory_offset + length.clone()),
+            );
+        let copy_words = self.copy_words.constraints(&mut cb, length);
+
+        // State transitions
+        let mut st = StateTransitionExpressions::new(STATE_TRANSITION.clone());
+        st.gc_delta = Some(gc_offset);
+        st.gas_delta =
+            Some(GAS.expr() + GasCost::COPY.expr() * copy_words + memory_cost);
+        st.next_memory_size = Some(next_memory_size);
+        st.constraints(&mut cb, state_curr, state_next);
+
+        // Generate the constraint
+        vec![cb.constraint(self.case_selector.expr(), name)]
+    }
+
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: &mut CoreStateInstance,
+        step: &ExecutionStep,
+    ) -> Result<(), Error> {
+        // Inputs, followed by the call context values and the copied bytes
+        let memory_offset = step.values[0].to_word();
+        let data_offset = step.values[1].to_word();
+        let length = step.values[2].to_word();
+        for (word, value) in [
+            (&self.memory_offset, memory_offset),
+            (&self.data_offset, data_offset),
+            (&self.length, length),
+        ] {
+            word.assign(region, offset, Some(value))?;
+        }
+        let value_u64 = |idx: usize| -> u64 {
+            step.values[idx].clone().try_into().unwrap()
+        };
+        for (cell, value) in [
+            (&self.callee_id, value_u64(3)),
+            (&self.return_data_offset, value_u64(4)),
+            (&self.return_data_length, value_u64(5)),
+        ] {
+            cell.assign(region, offset, Some(F::from(value)))?;
+        }
+
+        let length = address_low::value::<F>(length);
+        self.is_out_of_bounds.assign(
+            region,
+            offset,
+            F::from(value_u64(5)),
+            F::from(address_low::value::<F>(data_offset) + length),
+        )?;
+        for (idx, copy_flag) in self.copy_flags.iter().enumerate() {
+            copy_flag.assign(
+                region,
+                offset,
+                Some(F::from(((idx as u64) < length) as u64)),
+            )?;
+        }
+        let bytes = step.values[6].to_word();
+        for (cell, byte) in self.bytes.iter().zip(bytes.iter()) {
+            cell.assign(region, offset, Some(F::from(*byte as u64)))?;
+        }
+        let length_is_zero =
+            self.length_is_zero
+                .assign(region, offset, F::from(length))?;
+
+        // Memory expansion
+        let address = if length_is_zero == F::one() {
+            0
+        } else {
+            address_low::value::<F>(memory_offset) + length
+        };
+        let (next_memory_size, memory_cost) = self.memory_expansion.assign(
+            region,
+            offset,
+            state.memory_size as u64,
+            address,
+        )?;
+        let copy_words = self.copy_words.assign(region, offset, length)?;
+
+        // State transitions
+        let mut st = STATE_TRANSITION.clone();
+        st.gc_delta = Some(GC_DELTA + 2 * length as usize);
+        st.gas_delta = Some(
+            GAS.as_u64()
+                + GasCost::COPY.as_u64() * copy_words
+                + (memory_cost as u64),
+        );
+        st.next_memory_size = Some(next_memory_size);
+        st.assign(state);
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+struct ReturnDataOutOfBoundsCase<F> {
+    case_selector: Cell<F>,
+    memory_offset: Word<F>,
+    data_offset: Word<F>,
+    length: Word<F>,
+    callee_id: Cell<F>,
+    return_data_offset: Cell<F>,
+    return_data_length: Cell<F>,
+    end_high_is_zero: IsZeroGadget<F>,
+    is_out_of_bounds: LtGadget<F, 8>,
+}
+
+impl<F: FieldExt> ReturnDataOutOfBoundsCase<F> {
+    pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::ReturnDataOutOfBounds,
+        num_word: 3, // memory_offset + data_offset + length
+        num_cell: 3 // callee_id + return data offset and length
+            + IsZeroGadget::<F>::NUM_CELLS
+            + LtGadget::<F, 8>::NUM_CELLS,
+        will_halt: true,
+    };
+
+    pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+            memory_offset: alloc.words.pop().unwrap(),
+            data_offset: alloc.words.pop().unwrap(),
+            length: alloc.words.pop().unwrap(),
+            callee_id: alloc.cells.pop().unwrap(),
+            return_data_offset: alloc.cells.pop().unwrap(),
+            return_data_length: alloc.cells.pop().unwrap(),
+            end_high_is_zero: IsZeroGadget::construct(alloc),
+            is_out_of_bounds: LtGadget::construct(alloc),
+        }
+    }
+
+    pub(crate) fn constraint(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        _state_next: &OpExecutionState<F>,
+        name: &'static str,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::default();
+
+        read_return_data(
+            &mut cb,
+            state_curr,
+            [&self.memory_offset, &self.data_offset, &self.length],
+            [
+                &self.callee_id,
+                &self.return_data_offset,
+                &self.return_data_length,
+            ],
+        );
+
+        // The end of the copied bytes is past the end of the return data,
+        // which is always the case when it doesn't fit in the low bytes
+        let (end_high, end) = copy_end(&self.data_offset, &self.length);
+        let end_high_is_zero =
+            self.end_high_is_zero.constraints(&mut cb, end_high);
+        let is_out_of_bounds = self.is_out_of_bounds.constraints(
+            &mut cb,
+            self.return_data_length.expr(),
+            end,
+        );
+        cb.require_zero(end_high_is_zero * (1.expr() - is_out_of_bounds));
+
+        vec![cb.constraint(self.case_selector.expr(), name)]
+    }
+
+    pub(crate) fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        _state: &mut CoreStateInstance,
+        step: &ExecutionStep,
+    ) -> Result<(), Error> {
+        let memory_offset = step.values[0].to_word();
+        let data_offset = step.values[1].to_word();
+        let length = step.values[2].to_word();
+        for (word, value) in [
+            (&self.memory_offset, memory_offset),
+            (&self.data_offset, data_offset),
+            (&self.length, length),
+        ] {
+            word.assign(region, offset, Some(value))?;
+        }
+        let value_u64 = |idx: usize| -> u64 {
+            step.values[idx].clone().try_into().unwrap()
+        };
+        for (cell, value) in [
+            (&self.callee_id, value_u64(3)),
+            (&self.return_data_offset, value_u64(4)),
+            (&self.return_data_length, value_u64(5)),
+        ] {
+            cell.assign(region, offset, Some(F::from(value)))?;
+        }
+
+        self.end_high_is_zero.assign(
+            region,
+            offset,
+            address_high::value::<F>(data_offset)
+                + address_high::value::<F>(length),
+        )?;
+        self.is_out_of_bounds.assign(
+            region,
+            offset,
+            F::from(value_u64(5)),
+            F::from(
+                address_low::value::<F>(data_offset)
+                    + address_low::value::<F>(length),
+            ),
+        )?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::test::test_circuit_from_block;
+    use bus_mapping::{
+        bytecode, circuit_input_builder::CircuitInputBuilder, mock,
+    };
+    use halo2::dev::MockProver;
+    use pairing::bn256::Fr as Fp;
+
+    fn test_returndatacopy_gadget(
+        memory_offset: u64,
+        data_offset: u64,
+        length: u64,
+    ) {
+        // The callee returns the bytes 0xaa, 0xbb and 0xcc stored at the end
+        // of its first word of memory
+        let code = bytecode! {
+            PUSH1(0x00) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x00) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH1(0x00) // value
+            PUSH2(0x123) // addr
+            PUSH2(0x1000) // gas
+            CALL
+            PUSH1(length)
+            PUSH1(data_offset)
+            PUSH32(memory_offset)
+            RETURNDATACOPY
+            STOP
+        };
+        let callee_code = bytecode! {
+            PUSH3(0xaabbcc)
+            PUSH1(0x00)
+            MSTORE
+            PUSH1(0x03) // length
+            PUSH1(0x1d) // offset
+            RETURN
+        };
+        let block =
+            mock::BlockData::new_single_tx_trace_code_2(&code, &callee_code)
+                .unwrap();
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let circuit = test_circuit_from_block::<Fp>(&builder.block);
+        let prover = MockProver::<Fp>::run(11, &circuit, vec![vec![]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    fn returndatacopy_gadget_from_trace() {
+        // Copies of the whole, the end and none of the return data
+        for (data_offset, length) in [(0x00, 0x03), (0x01, 0x02), (0x03, 0x00)]
+        {
+            test_returndatacopy_gadget(0x40, data_offset, length);
+        }
+    }
+
+    #[test]
+    fn returndatacopy_gadget_out_of_bounds() {
+        // The copy ends past the end of the return data, which halts the
+        // execution of the tx
+        test_returndatacopy_gadget(0x40, 0x01, 0x03);
+    }
+
+    #[test]
+    fn returndatacopy_gadget_out_of_gas() {
+        // The memory can't be expanded up to the copied bytes, which may be
+        // out of the addressable memory
+        for memory_offset in [0x100000, 1 << 40] {
+            test_returndatacopy_gadget(memory_offset, 0x00, 0x03);
+        }
+    }
+}
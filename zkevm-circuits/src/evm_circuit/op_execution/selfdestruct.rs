@@ -0,0 +1,978 @@
+use super::super::{Case, Cell, Constraint, ExecutionStep, Word};
+use super::call::NUM_CALLER_WRITES;
+use super::r#return::NUM_CONTEXT_OPS_AFTER_COPY;
+use super::utils::{
+    self,
+    common_cases::StackUnderflowCase,
+    constraint_builder::ConstraintBuilder,
+    from_bytes,
+    math_gadgets::{IsEqualGadget, IsZeroGadget, LtGadget, MulAddWordsGadget},
+    select, sum, word_limbs, StateTransitionExpressions,
+};
+use super::{
+    CaseAllocation, CaseConfig, CoreStateInstance, OpExecutionState, OpGadget,
+};
+use crate::evm_circuit::param::MAX_GAS_SIZE_IN_BYTES;
+use crate::impl_op_gadget;
+use crate::util::{Expr, ToWord};
+use array_init::array_init;
+use bus_mapping::{
+    evm::{GasCost, OpcodeId},
+    operation::CallContextField,
+};
+use ethers_core::utils::keccak256;
+use halo2::plonk::{Error, Expression};
+use halo2::{arithmetic::FieldExt, circuit::Region};
+use num::BigUint;
+use std::convert::TryInto;
+
+// Call context reads of IsStatic, CalleeAddress, TxId, Depth and CallerId + 1
+// stack pop + access list write or read of the beneficiary + account nonce and
+// code hash reads of the beneficiary + 2 account balance writes or reads
+const NUM_OPS: usize = 11;
+const NUM_POPPED: usize = 1;
+
+impl_op_gadget!(
+    #set[SELFDESTRUCT]
+    SelfdestructGadget {
+        SelfdestructSuccessCase(),
+        SelfdestructWriteProtectionCase(),
+        SelfdestructOutOfGasCase(),
+        StackUnderflowCase(NUM_POPPED),
+    }
+);
+
+// The code hash of an account without code
+fn empty_code_hash() -> [u8; 32] {
+    let mut code_hash = keccak256(&[]);
+    code_hash.reverse();
+    code_hash
+}
+
+// The beneficiary popped by SELFDESTRUCT, with the reads telling the gas cost
+// of the step, shared by the success and the out of gas cases
+#[derive(Clone, Debug)]
+struct BeneficiaryGadget<F> {
+    address: Cell<F>,
+    tx_id: Cell<F>,
+    depth: Cell<F>,
+    caller_id: Cell<F>,
+    beneficiary: Word<F>,
+    is_warm: Cell<F>,
+    nonce: Cell<F>,
+    code_hash: Word<F>,
+    // Whether the beneficiary is empty and receives a non-zero balance
+    is_new_account: Cell<F>,
+    nonce_is_zero: IsZeroGadget<F>,
+    code_hash_is_zero: IsZeroGadget<F>,
+    // The low and high 16 bytes of the code hash against the ones of the
+    // empty code hash
+    code_hash_is_empty: [IsEqualGadget<F>; 2],
+    balance_prev_is_zero: IsZeroGadget<F>,
+    value_is_zero: IsZeroGadget<F>,
+}
+
+impl<F: FieldExt> BeneficiaryGadget<F> {
+    // beneficiary + code_hash
+    const NUM_WORDS: usize = 2;
+    // address + tx_id + depth + caller_id + is_warm + nonce +
+    // is_new_account
+    const NUM_CELLS: usize = 7
+        + 4 * IsZeroGadget::<F>::NUM_CELLS
+        + 2 * IsEqualGadget::<F>::NUM_CELLS;
+
+    fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            address: alloc.cells.pop().unwrap(),
+            tx_id: alloc.cells.pop().unwrap(),
+            depth: alloc.cells.pop().unwrap(),
+            caller_id: alloc.cells.pop().unwrap(),
+            beneficiary: alloc.words.pop().unwrap(),
+            is_warm: alloc.cells.pop().unwrap(),
+            nonce: alloc.cells.pop().unwrap(),
+            code_hash: alloc.words.pop().unwrap(),
+            is_new_account: alloc.cells.pop().unwrap(),
+            nonce_is_zero: IsZeroGadget::construct(alloc),
+            code_hash_is_zero: IsZeroGadget::construct(alloc),
+            code_hash_is_empty: array_init(|_| IsEqualGadget::construct(alloc)),
+            balance_prev_is_zero: IsZeroGadget::construct(alloc),
+            value_is_zero: IsZeroGadget::construct(alloc),
+        }
+    }
+
+    // Reads the account, the tx, the depth and the caller id, pops the
+    // beneficiary, marks it as warm or only reads its warm status without
+    // enough gas, reads its nonce and code hash, and returns it with the
+    // gas cost of the step, given its balance before the transfer and the
+    // transferred balance.
+    fn constraints(
+        &self,
+        cb: &mut ConstraintBuilder<F>,
+        state_curr: &OpExecutionState<F>,
+        is_success: bool,
+        balance_prev: &Word<F>,
+        value: &Word<F>,
+    ) -> (Expression<F>, Expression<F>) {
+        for (field, value) in [
+            (CallContextField::CalleeAddress, self.address.expr()),
+            (CallContextField::TxId, self.tx_id.expr()),
+            (CallContextField::Depth, self.depth.expr()),
+            (CallContextField::CallerId, self.caller_id.expr()),
+        ] {
+            cb.call_context_lookup(
+                false,
+                state_curr.call_id.expr(),
+                field,
+                value,
+            );
+        }
+
+        // Pop the beneficiary, which is given by the lower 20 bytes of the
+        // popped word, and mark it as warm (EIP-2929)
+        cb.stack_pop(self.beneficiary.expr());
+        let beneficiary =
+            from_bytes::expr(self.beneficiary.cells[..20].to_vec());
+        cb.require_boolean(self.is_warm.expr());
+        if is_success {
+            cb.tx_access_list_account_write(
+                self.tx_id.expr(),
+                beneficiary.clone(),
+                1.expr(),
+                self.is_warm.expr(),
+            );
+        } else {
+            cb.tx_access_list_account_read(
+                self.tx_id.expr(),
+                beneficiary.clone(),
+                self.is_warm.expr(),
+            );
+        }
+
+        // The beneficiary is created when it's empty, with no nonce, no
+        // balance and no code, and receives a non-zero balance (EIP-161).
+        // The code hash of an account which doesn't exist is 0.
+        cb.account_nonce_lookup(
+            false,
+            beneficiary.clone(),
+            self.nonce.expr(),
+            self.nonce.expr(),
+        );
+        cb.account_code_hash_lookup(
+            false,
+            beneficiary.clone(),
+            self.code_hash.expr(),
+            self.code_hash.expr(),
+        );
+        let nonce_is_zero =
+            self.nonce_is_zero.constraints(cb, self.nonce.expr());
+        let code_hash_is_zero = self
+            .code_hash_is_zero
+            .constraints(cb, sum::expr(&self.code_hash.cells));
+        let empty_code_hash = empty_code_hash();
+        let code_hash_is_empty = self.code_hash_is_empty[0].constraints(
+            cb,
+            from_bytes::expr(self.code_hash.cells[..16].to_vec()),
+            Expression::Constant(from_bytes::value(
+                empty_code_hash[..16].to_vec(),
+            )),
+        ) * self.code_hash_is_empty[1].constraints(
+            cb,
+            from_bytes::expr(self.code_hash.cells[16..].to_vec()),
+            Expression::Constant(from_bytes::value(
+                empty_code_hash[16..].to_vec(),
+            )),
+        );
+        let balance_prev_is_zero = self
+            .balance_prev_is_zero
+            .constraints(cb, sum::expr(&balance_prev.cells));
+        let value_is_zero =
+            self.value_is_zero.constraints(cb, sum::expr(&value.cells));
+        cb.require_equal(
+            self.is_new_account.expr(),
+            nonce_is_zero
+                * (code_hash_is_zero + code_hash_is_empty)
+                * balance_prev_is_zero
+                * (1.expr() - value_is_zero),
+        );
+
+        // SELFDESTRUCT, increased by COLD_ACCOUNT_ACCESS_COST when the
+        // beneficiary is cold, and by NEW_ACCOUNT when it's created
+        let gas_cost = GasCost::SELFDESTRUCT.expr()
+            + (1.expr() - self.is_warm.expr())
+                * GasCost::COLD_ACCOUNT_ACCESS_COST.expr()
+            + self.is_new_account.expr() * GasCost::NEW_ACCOUNT.expr();
+
+        (beneficiary, gas_cost)
+    }
+
+    // Assigns the beneficiary given the values of the step, and returns the
+    // gas cost of the step
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        step: &ExecutionStep,
+        values_idx: usize,
+        balance_prev: [u8; 32],
+        value: [u8; 32],
+    ) -> Result<u64, Error> {
+        // Input and call context reads, followed from `values_idx` by the
+        // warm status, the nonce and the code hash of the beneficiary
+        let value_u64 = |idx: usize| -> u64 {
+            step.values[idx].clone().try_into().unwrap()
+        };
+        self.beneficiary.assign(
+            region,
+            offset,
+            Some(step.values[0].to_word()),
+        )?;
+        self.address.assign(
+            region,
+            offset,
+            Some(from_bytes::value(step.values[2].to_word()[..20].to_vec())),
+        )?;
+        for (cell, value) in [
+            (&self.tx_id, value_u64(3)),
+            (&self.depth, value_u64(4)),
+            (&self.caller_id, value_u64(5)),
+            (&self.is_warm, value_u64(values_idx)),
+            (&self.nonce, value_u64(values_idx + 1)),
+        ] {
+            cell.assign(region, offset, Some(F::from(value)))?;
+        }
+        let is_warm = value_u64(values_idx) == 1;
+        let nonce = value_u64(values_idx + 1);
+        let code_hash = step.values[values_idx + 2].to_word();
+        self.code_hash.assign(region, offset, Some(code_hash))?;
+
+        self.nonce_is_zero.assign(region, offset, F::from(nonce))?;
+        self.code_hash_is_zero.assign(
+            region,
+            offset,
+            sum::value(&code_hash),
+        )?;
+        let empty_code_hash = empty_code_hash();
+        for (gadget, range) in
+            self.code_hash_is_empty.iter().zip([0..16, 16..32])
+        {
+            gadget.assign(
+                region,
+                offset,
+                from_bytes::value(code_hash[range.clone()].to_vec()),
+                from_bytes::value(empty_code_hash[range].to_vec()),
+            )?;
+        }
+        self.balance_prev_is_zero.assign(
+            region,
+            offset,
+            sum::value(&balance_prev),
+        )?;
+        self.value_is_zero
+            .assign(region, offset, sum::value(&value))?;
+        let is_new_account = nonce == 0
+            && (code_hash == [0; 32] || code_hash == empty_code_hash)
+            && balance_prev == [0; 32]
+            && value != [0; 32];
+        self.is_new_account.assign(
+            region,
+            offset,
+            Some(F::from(is_new_account as u64)),
+        )?;
+
+        Ok(GasCost::SELFDESTRUCT.as_u64()
+            + (!is_warm) as u64 * GasCost::COLD_ACCOUNT_ACCESS_COST.as_u64()
+            + is_new_account as u64 * GasCost::NEW_ACCOUNT.as_u64())
+    }
+}
+
+#[derive(Clone, Debug)]
+struct SelfdestructSuccessCase<F> {
+    case_selector: Cell<F>,
+    beneficiary: BeneficiaryGadget<F>,
+    // Balance moved to the beneficiary
+    balance: Word<F>,
+    beneficiary_balance_prev: Word<F>,
+    beneficiary_balance: Word<F>,
+    // Balance of the account before it's cleared, which already includes the
+    // transfer when the account is its own beneficiary
+    address_balance_prev: Word<F>,
+    is_own_beneficiary: IsEqualGadget<F>,
+    transfer: MulAddWordsGadget<F>,
+    // The root call has no caller, and ends the tx
+    is_root: IsEqualGadget<F>,
+    // State of the caller saved by the call, in words for the memory size
+    caller_program_counter: Cell<F>,
+    caller_stack_pointer: Cell<F>,
+    caller_gas_left: Cell<F>,
+    caller_memory_size: Cell<F>,
+    caller_state_write_counter: Cell<F>,
+    caller_code_hash: Word<F>,
+    // Gas given to the callee by the call
+    callee_gas: Cell<F>,
+}
+
+impl<F: FieldExt> SelfdestructSuccessCase<F> {
+    pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::Success,
+        // balance + beneficiary_balance_prev + beneficiary_balance +
+        // address_balance_prev + caller_code_hash
+        num_word: 5 + BeneficiaryGadget::<F>::NUM_WORDS,
+        // the 5 saved fields of the caller in cells + callee_gas
+        num_cell: 6
+            + BeneficiaryGadget::<F>::NUM_CELLS
+            + IsEqualGadget::<F>::NUM_CELLS
+            + MulAddWordsGadget::<F>::NUM_CELLS
+            + IsEqualGadget::<F>::NUM_CELLS,
+        will_halt: false,
+    };
+
+    pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+            beneficiary: BeneficiaryGadget::construct(alloc),
+            balance: alloc.words.pop().unwrap(),
+            beneficiary_balance_prev: alloc.words.pop().unwrap(),
+            beneficiary_balance: alloc.words.pop().unwrap(),
+            address_balance_prev: alloc.words.pop().unwrap(),
+            is_own_beneficiary: IsEqualGadget::construct(alloc),
+            transfer: MulAddWordsGadget::construct(alloc),
+            is_root: IsEqualGadget::construct(alloc),
+            caller_program_counter: alloc.cells.pop().unwrap(),
+            caller_stack_pointer: alloc.cells.pop().unwrap(),
+            caller_gas_left: alloc.cells.pop().unwrap(),
+            caller_memory_size: alloc.cells.pop().unwrap(),
+            caller_state_write_counter: alloc.cells.pop().unwrap(),
+            caller_code_hash: alloc.words.pop().unwrap(),
+            callee_gas: alloc.cells.pop().unwrap(),
+        }
+    }
+
+    pub(crate) fn constraint(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        state_next: &OpExecutionState<F>,
+        name: &'static str,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::default();
+
+        // The current call can't be static, and it destroys the account it's
+        // executing
+        cb.call_context_lookup(
+            false,
+            state_curr.call_id.expr(),
+            CallContextField::IsStatic,
+            0.expr(),
+        );
+        let (beneficiary, gas_cost) = self.beneficiary.constraints(
+            &mut cb,
+            state_curr,
+            true,
+            &self.beneficiary_balance_prev,
+            &self.balance,
+        );
+        let address = self.beneficiary.address.expr();
+        let is_own_beneficiary = self.is_own_beneficiary.constraints(
+            &mut cb,
+            beneficiary.clone(),
+            address.clone(),
+        );
+
+        // Credit the beneficiary:
+        // balance + beneficiary_balance_prev == beneficiary_balance
+        let overflow = self.transfer.constraints(
+            &mut cb,
+            &word_limbs::expr(&self.balance),
+            &[1.expr(), 0.expr(), 0.expr(), 0.expr()],
+            &word_limbs::expr(&self.beneficiary_balance_prev),
+            &word_limbs::expr(&self.beneficiary_balance),
+        );
+        cb.require_zero(overflow);
+        cb.account_balance_lookup(
+            true,
+            beneficiary,
+            self.beneficiary_balance.expr(),
+            self.beneficiary_balance_prev.expr(),
+        );
+
+        // Clear the balance of the account. The balance moved is the one of
+        // the account before the beneficiary is credited, so it's burnt when
+        // the account is its own beneficiary.
+        cb.require_zero(
+            is_own_beneficiary.clone()
+                * (self.balance.expr() - self.beneficiary_balance_prev.expr()),
+        );
+        cb.require_equal(
+            self.address_balance_prev.expr(),
+            select::expr(
+                is_own_beneficiary,
+                self.beneficiary_balance.expr(),
+                self.balance.expr(),
+            ),
+        );
+        cb.account_balance_lookup(
+            true,
+            address,
+            0.expr(),
+            self.address_balance_prev.expr(),
+        );
+
+        // Return to the caller like STOP when the current call isn't the root
+        // one: read the success of the call and the gas given to it, which
+        // are set up by the call, and the saved state of the caller, and
+        // record the call as the last callee of the caller, with no return
+        // data.
+        let caller_id = self.beneficiary.caller_id.expr();
+        let is_root = self.is_root.constraints(
+            &mut cb,
+            self.beneficiary.depth.expr(),
+            1.expr(),
+        );
+        let not_root = 1.expr() - is_root.clone();
+        cb.condition(not_root.clone(), |cb| {
+            for (idx, (is_write, call_id, field, value)) in [
+                (
+                    false,
+                    state_curr.call_id.expr(),
+                    CallContextField::IsSuccess,
+                    1.expr(),
+                ),
+                (
+                    false,
+                    state_curr.call_id.expr(),
+                    CallContextField::Gas,
+                    self.callee_gas.expr(),
+                ),
+                (
+                    false,
+                    caller_id.clone(),
+                    CallContextField::ProgramCounter,
+                    self.caller_program_counter.expr(),
+                ),
+                (
+                    false,
+                    caller_id.clone(),
+                    CallContextField::StackPointer,
+                    self.caller_stack_pointer.expr(),
+                ),
+                (
+                    false,
+                    caller_id.clone(),
+                    CallContextField::GasLeft,
+                    self.caller_gas_left.expr(),
+                ),
+                (
+                    false,
+                    caller_id.clone(),
+                    CallContextField::MemorySize,
+                    self.caller_memory_size.expr() * 32.expr(),
+                ),
+                (
+                    false,
+                    caller_id.clone(),
+                    CallContextField::StateWriteCounter,
+                    self.caller_state_write_counter.expr(),
+                ),
+                (
+                    false,
+                    caller_id.clone(),
+                    CallContextField::CodeHash,
+                    self.caller_code_hash.expr(),
+                ),
+                (
+                    true,
+                    caller_id.clone(),
+                    CallContextField::LastCalleeId,
+                    state_curr.call_id.expr(),
+                ),
+                (
+                    true,
+                    caller_id.clone(),
+                    CallContextField::LastCalleeReturnDataOffset,
+                    0.expr(),
+                ),
+                (
+                    true,
+                    caller_id.clone(),
+                    CallContextField::LastCalleeReturnDataLength,
+                    0.expr(),
+                ),
+            ]
+            .iter()
+            .enumerate()
+            {
+                cb.call_context_lookup_at(
+                    *is_write,
+                    call_id.clone(),
+                    *field,
+                    value.clone(),
+                    NUM_OPS.expr() + idx.expr(),
+                );
+            }
+        });
+
+        // State transitions to the step of the caller following the call, or
+        // to the end of the tx, which stays in the root call.  The gas given
+        // to the callee was paid by the call and the gas used by the callee
+        // is accounted again by its steps, so the callee gas is refunded.
+        cb.require_equal(
+            state_next.call_id.expr(),
+            select::expr(is_root.clone(), state_curr.call_id.expr(), caller_id),
+        );
+        // The state writes of the call, including the ones of the step, are
+        // added to the ones of the caller
+        let next_state_write_counter = state_curr.state_write_counter.expr()
+            + cb.state_write_counter_offset.clone()
+            + not_root.clone() * self.caller_state_write_counter.expr();
+        let st = StateTransitionExpressions {
+            gc_delta: Some(
+                NUM_OPS.expr()
+                    + NUM_CONTEXT_OPS_AFTER_COPY.expr() * not_root.clone(),
+            ),
+            pc_delta: Some(select::expr(
+                is_root.clone(),
+                1.expr(),
+                self.caller_program_counter.expr()
+                    - state_curr.program_counter.expr(),
+            )),
+            sp_delta: Some(select::expr(
+                is_root.clone(),
+                NUM_POPPED.expr(),
+                self.caller_stack_pointer.expr()
+                    - state_curr.stack_pointer.expr(),
+            )),
+            gas_delta: Some(
+                gas_cost.clone() - not_root.clone() * self.callee_gas.expr(),
+            ),
+            next_gas_left: Some(
+                state_curr.gas_left.expr() - gas_cost
+                    + not_root * self.caller_gas_left.expr(),
+            ),
+            next_memory_size: Some(select::expr(
+                is_root.clone(),
+                state_curr.memory_size.expr(),
+                self.caller_memory_size.expr(),
+            )),
+            next_code_source: Some(select::expr(
+                is_root,
+                state_curr.code_source.expr(),
+                self.caller_code_hash.expr(),
+            )),
+            log_id_delta: None,
+            next_state_write_counter: Some(next_state_write_counter),
+        };
+        st.constraints(&mut cb, state_curr, state_next);
+
+        // Generate the constraint
+        vec![cb.constraint(self.case_selector.expr(), name)]
+    }
+
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: &mut CoreStateInstance,
+        step: &ExecutionStep,
+    ) -> Result<(), Error> {
+        // Input, followed by the values of the call context reads, including
+        // the return to the caller unless the call is the root one, the warm
+        // status, the nonce and the code hash of the beneficiary, its balance
+        // before and after the transfer and the balance of the account before
+        // it's cleared
+        let value_u64 = |idx: usize| -> u64 {
+            step.values[idx].clone().try_into().unwrap()
+        };
+        let (depth, caller_id) = (value_u64(4), value_u64(5));
+        let is_root =
+            self.is_root
+                .assign(region, offset, F::from(depth), F::one())?
+                == F::one();
+        let values_idx = 6 + (!is_root) as usize * NUM_CONTEXT_OPS_AFTER_COPY;
+
+        let beneficiary_balance_prev = step.values[values_idx + 3].to_word();
+        let balance: BigUint =
+            &step.values[values_idx + 4] - &step.values[values_idx + 3];
+        let balance = balance.to_word();
+        let gas_cost = self.beneficiary.assign(
+            region,
+            offset,
+            step,
+            values_idx,
+            beneficiary_balance_prev,
+            balance,
+        )?;
+        self.is_own_beneficiary.assign(
+            region,
+            offset,
+            from_bytes::value(step.values[0].to_word()[..20].to_vec()),
+            from_bytes::value(step.values[2].to_word()[..20].to_vec()),
+        )?;
+        for (word, value) in [
+            (&self.balance, balance),
+            (&self.beneficiary_balance_prev, beneficiary_balance_prev),
+            (
+                &self.beneficiary_balance,
+                step.values[values_idx + 4].to_word(),
+            ),
+            (
+                &self.address_balance_prev,
+                step.values[values_idx + 5].to_word(),
+            ),
+        ] {
+            word.assign(region, offset, Some(value))?;
+        }
+        self.transfer.assign(
+            region,
+            offset,
+            balance,
+            BigUint::from(1u64).to_word(),
+            beneficiary_balance_prev,
+        )?;
+
+        // The success and the gas of the call, followed by the saved state
+        // of the caller
+        let caller_values = if is_root {
+            vec![0; NUM_CALLER_WRITES]
+        } else {
+            (1..=NUM_CALLER_WRITES)
+                .map(|idx| value_u64(6 + idx))
+                .collect::<Vec<_>>()
+        };
+        for (cell, value) in [
+            &self.callee_gas,
+            &self.caller_program_counter,
+            &self.caller_stack_pointer,
+            &self.caller_gas_left,
+        ]
+        .iter()
+        .zip(caller_values.iter())
+        {
+            cell.assign(region, offset, Some(F::from(*value)))?;
+        }
+        let (callee_gas, caller_memory_size) =
+            (caller_values[0], caller_values[4] / 32);
+        self.caller_memory_size.assign(
+            region,
+            offset,
+            Some(F::from(caller_memory_size)),
+        )?;
+        self.caller_state_write_counter.assign(
+            region,
+            offset,
+            Some(F::from(caller_values[5])),
+        )?;
+        let caller_code_hash = if is_root {
+            [0u8; 32]
+        } else {
+            step.values[7 + NUM_CALLER_WRITES].to_word()
+        };
+        self.caller_code_hash
+            .assign(region, offset, Some(caller_code_hash))?;
+
+        // State transitions to the step of the caller following the call, or
+        // to the end of the tx
+        state.global_counter += NUM_OPS;
+        state.gas_counter = state.gas_counter + gas_cost - callee_gas;
+        state.gas_left = state.gas_left + caller_values[3] - gas_cost;
+        // The access list write of the beneficiary and the 2 balance writes
+        // are state writes
+        state.state_write_counter += 3 + caller_values[5] as usize;
+        if is_root {
+            state.program_counter += 1;
+            state.stack_pointer += NUM_POPPED;
+        } else {
+            state.global_counter += NUM_CONTEXT_OPS_AFTER_COPY;
+            state.call_id = caller_id as usize;
+            state.program_counter = caller_values[1] as usize;
+            state.stack_pointer = caller_values[2] as usize;
+            state.memory_size = caller_memory_size;
+            state.code_source = caller_code_hash;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+struct SelfdestructWriteProtectionCase<F> {
+    case_selector: Cell<F>,
+}
+
+impl<F: FieldExt> SelfdestructWriteProtectionCase<F> {
+    pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::WriteProtection,
+        num_word: 0,
+        num_cell: 0,
+        will_halt: true,
+    };
+
+    pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+        }
+    }
+
+    pub(crate) fn constraint(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        _state_next: &OpExecutionState<F>,
+        name: &'static str,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::default();
+
+        // Accounts can't be destroyed by a static call
+        cb.call_context_lookup(
+            false,
+            state_curr.call_id.expr(),
+            CallContextField::IsStatic,
+            1.expr(),
+        );
+
+        vec![cb.constraint(self.case_selector.expr(), name)]
+    }
+
+    pub(crate) fn assign(
+        &self,
+        _region: &mut Region<'_, F>,
+        _offset: usize,
+        _state: &mut CoreStateInstance,
+        _step: &ExecutionStep,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+struct SelfdestructOutOfGasCase<F> {
+    case_selector: Cell<F>,
+    beneficiary: BeneficiaryGadget<F>,
+    beneficiary_balance: Word<F>,
+    // Balance which would be moved to the beneficiary
+    balance: Word<F>,
+    insufficient_gas: LtGadget<F, MAX_GAS_SIZE_IN_BYTES>,
+}
+
+impl<F: FieldExt> SelfdestructOutOfGasCase<F> {
+    pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::OutOfGas,
+        // beneficiary_balance + balance
+        num_word: 2 + BeneficiaryGadget::<F>::NUM_WORDS,
+        num_cell: BeneficiaryGadget::<F>::NUM_CELLS
+            + LtGadget::<F, MAX_GAS_SIZE_IN_BYTES>::NUM_CELLS,
+        will_halt: true,
+    };
+
+    pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+            beneficiary: BeneficiaryGadget::construct(alloc),
+            beneficiary_balance: alloc.words.pop().unwrap(),
+            balance: alloc.words.pop().unwrap(),
+            insufficient_gas: LtGadget::construct(alloc),
+        }
+    }
+
+    pub(crate) fn constraint(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        _state_next: &OpExecutionState<F>,
+        name: &'static str,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::default();
+
+        // Read the beneficiary and its warm status, and the balances of the
+        // beneficiary and of the account, which tell the gas cost of the step
+        cb.call_context_lookup(
+            false,
+            state_curr.call_id.expr(),
+            CallContextField::IsStatic,
+            0.expr(),
+        );
+        let (beneficiary, gas_cost) = self.beneficiary.constraints(
+            &mut cb,
+            state_curr,
+            false,
+            &self.beneficiary_balance,
+            &self.balance,
+        );
+        for (address, balance) in [
+            (beneficiary, &self.beneficiary_balance),
+            (self.beneficiary.address.expr(), &self.balance),
+        ] {
+            cb.account_balance_lookup(
+                false,
+                address,
+                balance.expr(),
+                balance.expr(),
+            );
+        }
+
+        // Make sure the amount of gas available is less than the amount of
+        // gas required to destroy the account
+        let insufficient_gas = self.insufficient_gas.constraints(
+            &mut cb,
+            state_curr.gas_left.expr(),
+            gas_cost,
+        );
+        cb.require_equal(insufficient_gas, 1.expr());
+
+        // Generate the constraint
+        vec![cb.constraint(self.case_selector.expr(), name)]
+    }
+
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: &mut CoreStateInstance,
+        step: &ExecutionStep,
+    ) -> Result<(), Error> {
+        // Same values as the success case, with the balances of the
+        // beneficiary and of the account only read
+        let depth: u64 = step.values[4].clone().try_into().unwrap();
+        let values_idx = 6 + (depth != 1) as usize * NUM_CONTEXT_OPS_AFTER_COPY;
+        let beneficiary_balance = step.values[values_idx + 3].to_word();
+        let balance = step.values[values_idx + 5].to_word();
+        let gas_cost = self.beneficiary.assign(
+            region,
+            offset,
+            step,
+            values_idx,
+            beneficiary_balance,
+            balance,
+        )?;
+        self.beneficiary_balance.assign(
+            region,
+            offset,
+            Some(beneficiary_balance),
+        )?;
+        self.balance.assign(region, offset, Some(balance))?;
+
+        // Gas insufficient check
+        self.insufficient_gas.assign(
+            region,
+            offset,
+            F::from(state.gas_left),
+            F::from(gas_cost),
+        )?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::test::test_circuit_from_block;
+    use bus_mapping::{
+        bytecode,
+        bytecode::Bytecode,
+        circuit_input_builder::CircuitInputBuilder,
+        eth_types::{Address, Word},
+        mock,
+    };
+    use halo2::dev::MockProver;
+    use pairing::bn256::Fr as Fp;
+
+    fn test_selfdestruct_gadget(code: Bytecode, callee_code: Option<Bytecode>) {
+        let block = match callee_code {
+            Some(callee_code) => {
+                mock::BlockData::new_single_tx_trace_code_2(&code, &callee_code)
+            }
+            None => mock::BlockData::new_single_tx_trace_code(&code),
+        }
+        .unwrap();
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        // The contract has the balance given by `mock::new_tracer_account`
+        builder.sdb.get_account_mut(&Address::zero()).1.balance =
+            Word::from(555u64);
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let circuit = test_circuit_from_block::<Fp>(&builder.block);
+        let prover = MockProver::<Fp>::run(11, &circuit, vec![vec![]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    fn selfdestruct_gadget_from_trace() {
+        // The balance is sent to a cold account which is created by it
+        let code = bytecode! {
+            PUSH2(0x1234)
+            SELFDESTRUCT
+        };
+        test_selfdestruct_gadget(code, None);
+    }
+
+    #[test]
+    fn selfdestruct_gadget_to_itself() {
+        // The balance is burnt
+        let code = bytecode! {
+            ADDRESS
+            SELFDESTRUCT
+        };
+        test_selfdestruct_gadget(code, None);
+    }
+
+    #[test]
+    fn selfdestruct_gadget_write_protection() {
+        let code = bytecode! {
+            PUSH1(0x00) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x00) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH2(0x123) // addr
+            PUSH2(0x1000) // gas
+            STATICCALL
+        };
+        let callee_code = bytecode! {
+            PUSH1(0x00) // beneficiary
+            SELFDESTRUCT
+        };
+        test_selfdestruct_gadget(code, Some(callee_code));
+    }
+
+    #[test]
+    fn selfdestruct_gadget_return_to_caller() {
+        // The callee destroys its account and returns to the caller, which
+        // pops the success of the call
+        let code = bytecode! {
+            PUSH1(0x00) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x00) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH1(0x00) // value
+            PUSH2(0x123) // addr
+            PUSH3(0x10000) // gas
+            CALL
+            POP
+            STOP
+        };
+        let callee_code = bytecode! {
+            PUSH2(0x1234) // beneficiary
+            SELFDESTRUCT
+        };
+        test_selfdestruct_gadget(code, Some(callee_code));
+    }
+
+    #[test]
+    fn selfdestruct_gadget_out_of_gas() {
+        // The callee doesn't have the gas to destroy its account
+        let code = bytecode! {
+            PUSH1(0x00) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x00) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH1(0x00) // value
+            PUSH2(0x123) // addr
+            PUSH2(0x800) // gas
+            CALL
+            STOP
+        };
+        let callee_code = bytecode! {
+            PUSH2(0x1234) // beneficiary
+            SELFDESTRUCT
+        };
+        test_selfdestruct_gadget(code, Some(callee_code));
+    }
+}
@@ -0,0 +1,439 @@
+use super::super::{Case, Cell, Constraint, ExecutionStep, Word};
+use super::utils::{
+    self,
+    common_cases::StackUnderflowCase,
+    constraint_builder::ConstraintBuilder,
+    math_gadgets::{IsZeroGadget, LtGadget},
+    memory_gadgets::{
+        self, address_high, address_low, MemoryExpansionGadget,
+        MemoryRangeGadget, MemorySizeGadget,
+    },
+    sum, StateTransition, StateTransitionExpressions,
+};
+use super::{
+    CaseAllocation, CaseConfig, CoreStateInstance, OpExecutionState, OpGadget,
+};
+use crate::evm_circuit::param::{MAX_COPY_BYTES, MAX_GAS_SIZE_IN_BYTES};
+use crate::impl_op_gadget;
+use crate::util::{Expr, ToWord};
+use array_init::array_init;
+use bus_mapping::evm::{GasCost, OpcodeId};
+use halo2::plonk::Error;
+use halo2::{arithmetic::FieldExt, circuit::Region};
+
+static STATE_TRANSITION: StateTransition = StateTransition {
+    gc_delta: None, // GC_DELTA + length
+    pc_delta: Some(1),
+    sp_delta: Some(1),
+    gas_delta: None, // SHA3 + SHA3_WORD * words + memory_cost
+    next_memory_size: None, // next_memory_size
+};
+// 2 stack pops + 1 stack push
+const GC_DELTA: usize = 3;
+const NUM_POPPED: usize = 2;
+
+impl_op_gadget!(
+    #set[SHA3]
+    Sha3Gadget {
+        Sha3SuccessCase(),
+        StackUnderflowCase(NUM_POPPED),
+        Sha3OutOfGasCase(),
+    }
+);
+
+#[derive(Clone, Debug)]
+struct Sha3SuccessCase<F> {
+    case_selector: Cell<F>,
+    memory_offset: Word<F>,
+    length: Word<F>,
+    hash: Word<F>,
+    // The hashed bytes in reverse order, so that the word is the random
+    // linear combination of the input of keccak
+    input: Word<F>,
+    copy_flags: [Cell<F>; MAX_COPY_BYTES],
+    length_is_zero: IsZeroGadget<F>,
+    hash_words: MemorySizeGadget<F>,
+    memory_expansion: MemoryExpansionGadget<F, MAX_GAS_SIZE_IN_BYTES>,
+}
+
+impl<F: FieldExt> Sha3SuccessCase<F> {
+    pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::Success,
+        num_word: 4, // memory_offset + length + hash + input
+        num_cell: MAX_COPY_BYTES // copy_flags
+            + IsZeroGadget::<F>::NUM_CELLS
+            + MemorySizeGadget::<F>::NUM_CELLS
+            + MemoryExpansionGadget::<F, MAX_GAS_SIZE_IN_BYTES>::NUM_CELLS,
+        will_halt: false,
+    };
+
+    pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+            memory_offset: alloc.words.pop().unwrap(),
+            length: alloc.words.pop().unwrap(),
+            hash: alloc.words.pop().unwrap(),
+            input: alloc.words.pop().unwrap(),
+            copy_flags: array_init(|_| alloc.cells.pop().unwrap()),
+            length_is_zero: IsZeroGadget::construct(alloc),
+            hash_words: MemorySizeGadget::construct(alloc),
+            memory_expansion: MemoryExpansionGadget::construct(alloc),
+        }
+    }
+
+    pub(crate) fn constraint(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        state_next: &OpExecutionState<F>,
+        name: &'static str,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::with_call_id(state_curr.call_id.expr());
+
+        // Pop the memory offset and the length from the stack
+        cb.stack_pop(self.memory_offset.expr());
+        cb.stack_pop(self.length.expr());
+
+        // The copy flags are set for the first `length` bytes, which limits
+        // the length to MAX_COPY_BYTES
+        cb.require_zero(address_high::expr(&self.length));
+        let length = address_low::expr(&self.length);
+        for (idx, copy_flag) in self.copy_flags.iter().enumerate() {
+            cb.require_boolean(copy_flag.expr());
+            if idx > 0 {
+                cb.require_zero(
+                    copy_flag.expr()
+                        * (1.expr() - self.copy_flags[idx - 1].expr()),
+                );
+            }
+        }
+        cb.require_equal(sum::expr(&self.copy_flags), length.clone());
+        let length_is_zero =
+            self.length_is_zero.constraints(&mut cb, length.clone());
+
+        // The memory offset is only used when bytes are hashed
+        cb.condition(1.expr() - length_is_zero.clone(), |cb| {
+            memory_gadgets::require_address_in_range(cb, &self.memory_offset);
+        });
+        let memory_offset = address_low::expr(&self.memory_offset);
+
+        // Read the input from the memory, where the byte `idx` of the input
+        // word is the byte `length - 1 - idx` of the memory range, and the
+        // bytes past the length are zeros
+        for (idx, (copy_flag, byte)) in self
+            .copy_flags
+            .iter()
+            .zip(self.input.cells.iter())
+            .enumerate()
+        {
+            let position = length.clone() - (1 + idx).expr();
+            cb.condition(copy_flag.expr(), |cb| {
+                cb.memory_lookup_at(
+                    memory_offset.clone() + position.clone(),
+                    byte.expr(),
+                    0.expr(),
+                    2.expr() + position,
+                );
+            });
+            cb.require_zero((1.expr() - copy_flag.expr()) * byte.expr());
+        }
+
+        // Push the hash of the input, which is checked against the keccak
+        // table.  The table is an unproven stub for now, so the hash is only
+        // as sound as the rows the prover witnesses in it.
+        cb.add_keccak_lookup([
+            self.input.expr(),
+            length.clone(),
+            self.hash.expr(),
+        ]);
+        cb.stack_lookup_at(
+            1.expr(),
+            self.hash.expr(),
+            true.expr(),
+            2.expr() + length.clone(),
+        );
+
+        // Calculate the next memory size and the gas cost for this memory
+        // access, where the memory is not expanded when there's no input
+        let (next_memory_size, memory_cost) =
+            self.memory_expansion.constraints(
+                &mut cb,
+                state_curr.memory_size.expr(),
+                (1.expr() - length_is_zero) * (memory_offset + length.clone()),
+            );
+        let hash_words = self.hash_words.constraints(&mut cb, length.clone());
+
+        // State transitions
+        let mut st = StateTransitionExpressions::new(STATE_TRANSITION.clone());
+        st.gc_delta = Some(GC_DELTA.expr() + length);
+        st.gas_delta = Some(
+            GasCost::SHA3.expr()
+                + GasCost::SHA3_WORD.expr() * hash_words
+                + memory_cost,
+        );
+        st.next_memory_size = Some(next_memory_size);
+        st.constraints(&mut cb, state_curr, state_next);
+
+        // Generate the constraint
+        vec![cb.constraint(self.case_selector.expr(), name)]
+    }
+
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: &mut CoreStateInstance,
+        step: &ExecutionStep,
+    ) -> Result<(), Error> {
+        // Inputs and output, followed by the hashed bytes
+        let memory_offset = step.values[0].to_word();
+        let length = step.values[1].to_word();
+        self.memory_offset
+            .assign(region, offset, Some(memory_offset))?;
+        self.length.assign(region, offset, Some(length))?;
+        self.hash
+            .assign(region, offset, Some(step.values[2].to_word()))?;
+
+        let length = address_low::value::<F>(length);
+        for (idx, copy_flag) in self.copy_flags.iter().enumerate() {
+            copy_flag.assign(
+                region,
+                offset,
+                Some(F::from(((idx as u64) < length) as u64)),
+            )?;
+        }
+        let bytes = step.values[3].to_word();
+        let mut input = [0u8; 32];
+        for (idx, byte) in bytes[..length as usize].iter().rev().enumerate() {
+            input[idx] = *byte;
+        }
+        self.input.assign(region, offset, Some(input))?;
+        let length_is_zero =
+            self.length_is_zero
+                .assign(region, offset, F::from(length))?;
+
+        // Memory expansion
+        let address = if length_is_zero == F::one() {
+            0
+        } else {
+            address_low::value::<F>(memory_offset) + length
+        };
+        let (next_memory_size, memory_cost) = self.memory_expansion.assign(
+            region,
+            offset,
+            state.memory_size as u64,
+            address,
+        )?;
+        let hash_words = self.hash_words.assign(region, offset, length)?;
+
+        // State transitions
+        let mut st = STATE_TRANSITION.clone();
+        st.gc_delta = Some(GC_DELTA + length as usize);
+        st.gas_delta = Some(
+            GasCost::SHA3.as_u64()
+                + GasCost::SHA3_WORD.as_u64() * hash_words
+                + (memory_cost as u64),
+        );
+        st.next_memory_size = Some(next_memory_size);
+        st.assign(state);
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Sha3OutOfGasCase<F> {
+    case_selector: Cell<F>,
+    memory_offset: Word<F>,
+    length: Word<F>,
+    memory_range: MemoryRangeGadget<F>,
+    insufficient_gas: LtGadget<F, MAX_GAS_SIZE_IN_BYTES>,
+}
+
+impl<F: FieldExt> Sha3OutOfGasCase<F> {
+    pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::OutOfGas,
+        num_word: 2, // memory_offset + length
+        num_cell: MemoryRangeGadget::<F>::NUM_CELLS
+            + LtGadget::<F, MAX_GAS_SIZE_IN_BYTES>::NUM_CELLS,
+        will_halt: true,
+    };
+
+    pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+            memory_offset: alloc.words.pop().unwrap(),
+            length: alloc.words.pop().unwrap(),
+            memory_range: MemoryRangeGadget::construct(alloc),
+            insufficient_gas: LtGadget::construct(alloc),
+        }
+    }
+
+    pub(crate) fn constraint(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        _state_next: &OpExecutionState<F>,
+        name: &'static str,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::default();
+
+        // Pop the memory offset and the length from the stack
+        cb.stack_pop(self.memory_offset.expr());
+        cb.stack_pop(self.length.expr());
+
+        // Make sure the amount of gas available is less than the amount of
+        // gas required to expand the memory and hash the input, unless the
+        // input is out of the addressable memory, which never has enough gas
+        let (is_in_range, hash_words, memory_cost) =
+            self.memory_range.constraints(
+                &mut cb,
+                state_curr.memory_size.expr(),
+                &self.memory_offset,
+                &self.length,
+            );
+        let insufficient_gas = self.insufficient_gas.constraints(
+            &mut cb,
+            state_curr.gas_left.expr(),
+            GasCost::SHA3.expr()
+                + GasCost::SHA3_WORD.expr() * hash_words
+                + memory_cost,
+        );
+        cb.condition(is_in_range, |cb| {
+            cb.require_equal(insufficient_gas, 1.expr());
+        });
+
+        // Generate the constraint
+        vec![cb.constraint(self.case_selector.expr(), name)]
+    }
+
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: &mut CoreStateInstance,
+        step: &ExecutionStep,
+    ) -> Result<(), Error> {
+        // Inputs
+        let memory_offset = step.values[0].to_word();
+        let length = step.values[1].to_word();
+        self.memory_offset
+            .assign(region, offset, Some(memory_offset))?;
+        self.length.assign(region, offset, Some(length))?;
+
+        let (_, hash_words, memory_cost) = self.memory_range.assign(
+            region,
+            offset,
+            state.memory_size as u64,
+            memory_offset,
+            length,
+        )?;
+
+        // Gas insufficient check
+        self.insufficient_gas.assign(
+            region,
+            offset,
+            F::from(state.gas_left),
+            F::from(
+                GasCost::SHA3.as_u64()
+                    + GasCost::SHA3_WORD.as_u64() * hash_words
+                    + (memory_cost as u64),
+            ),
+        )?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::test::test_circuit_from_block;
+    use bus_mapping::{
+        bytecode, bytecode::Bytecode,
+        circuit_input_builder::CircuitInputBuilder, mock,
+    };
+    use halo2::dev::MockProver;
+    use pairing::bn256::Fr as Fp;
+
+    fn test_sha3_gadget(code: Bytecode, callee_code: Option<Bytecode>) {
+        let block = match callee_code {
+            Some(callee_code) => {
+                mock::BlockData::new_single_tx_trace_code_2(&code, &callee_code)
+            }
+            None => mock::BlockData::new_single_tx_trace_code(&code),
+        }
+        .unwrap();
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let circuit = test_circuit_from_block::<Fp>(&builder.block);
+        let prover = MockProver::<Fp>::run(11, &circuit, vec![vec![]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    fn sha3_gadget_from_trace() {
+        // A hash of bytes which expands the memory, followed by the hash of
+        // an empty input
+        let code = bytecode! {
+            PUSH32(0x0102030405060708u64)
+            PUSH1(0x00)
+            MSTORE
+            PUSH1(0x0a) // length
+            PUSH1(0x1c) // offset
+            SHA3
+            PUSH1(0x00) // length
+            PUSH1(0x40) // offset
+            SHA3
+            STOP
+        };
+        test_sha3_gadget(code, None);
+    }
+
+    #[test]
+    fn sha3_gadget_out_of_gas() {
+        let code = bytecode! {
+            PUSH1(0x00) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x00) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH1(0x00) // value
+            PUSH2(0x123) // addr
+            PUSH1(0x10) // gas
+            CALL
+            STOP
+        };
+        let callee_code = bytecode! {
+            PUSH1(0x20) // length
+            PUSH1(0x00) // offset
+            SHA3
+            STOP
+        };
+        test_sha3_gadget(code, Some(callee_code));
+    }
+
+    #[test]
+    fn sha3_gadget_out_of_addressable_memory() {
+        // The callee hashes bytes out of the addressable memory, which never
+        // has enough gas
+        let code = bytecode! {
+            PUSH1(0x00) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x00) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH1(0x00) // value
+            PUSH2(0x123) // addr
+            PUSH2(0x800) // gas
+            CALL
+            STOP
+        };
+        let callee_code = bytecode! {
+            PUSH1(0x20) // length
+            PUSH6(0x010000000000u64) // offset
+            SHA3
+            STOP
+        };
+        test_sha3_gadget(code, Some(callee_code));
+    }
+}
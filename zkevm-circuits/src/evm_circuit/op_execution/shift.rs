@@ -0,0 +1,386 @@
+use super::super::{Case, Cell, Constraint, ExecutionStep, FixedLookup, Word};
+use super::utils::{
+    self,
+    common_cases::{OutOfGasCase, StackUnderflowCase},
+    constraint_builder::ConstraintBuilder,
+    get_range,
+    math_gadgets::{
+        IsEqualGadget, IsZeroGadget, LtWordGadget, MulAddWordsGadget,
+    },
+    select, sum, word_limbs, StateTransition,
+};
+use super::{
+    CaseAllocation, CaseConfig, CoreStateInstance, OpExecutionState, OpGadget,
+};
+use crate::impl_op_gadget;
+use crate::util::{Expr, ToWord};
+use array_init::array_init;
+use bus_mapping::evm::{GasCost, OpcodeId};
+use halo2::plonk::{Error, Expression};
+use halo2::{arithmetic::FieldExt, circuit::Region};
+use num::{BigUint, Zero};
+
+static STATE_TRANSITION: StateTransition = StateTransition {
+    gc_delta: Some(3), // 2 stack pops + 1 stack push
+    pc_delta: Some(1),
+    sp_delta: Some(1),
+    gas_delta: Some(GasCost::FASTEST.as_u64()),
+    next_memory_size: None,
+};
+const NUM_POPPED: usize = 2;
+
+impl_op_gadget!(
+    #set[SHL, SHR, SAR]
+    ShiftGadget {
+        ShiftSuccessCase(),
+        StackUnderflowCase(NUM_POPPED),
+        OutOfGasCase(STATE_TRANSITION.gas_delta.unwrap()),
+    }
+);
+
+#[derive(Clone, Debug)]
+struct ShiftSuccessCase<F> {
+    case_selector: Cell<F>,
+    shift: Word<F>,
+    a: Word<F>,
+    result: Word<F>,
+    remainder: Word<F>,
+    is_shl: IsEqualGadget<F>,
+    is_sar: IsEqualGadget<F>,
+    a_sign_byte: Cell<F>,
+    shift_in_range: IsZeroGadget<F>,
+    pow2_limb_selector: [Cell<F>; 4],
+    pow2_limb: Cell<F>,
+    mul_add_words: MulAddWordsGadget<F>,
+    remainder_lt_pow2: LtWordGadget<F>,
+}
+
+// ShiftGadget verifies SHL, SHR and SAR at the same time using `2**shift`,
+// which has a single non-zero limb `2**(shift % 64)` at limb `shift / 64`,
+// both taken from the Pow2 table. When `shift >= 256` it is set to `0`.
+// - SHL: `a * 2**shift == result (mod 2**256)`
+// - SHR: `quotient * 2**shift + remainder == a`, with `remainder < 2**shift`
+//   and `result == quotient`
+// - SAR: same as SHR, but for a negative `a` the division is done on `!a`, and
+//   `result == !quotient`, which rounds towards negative infinity
+// When `shift >= 256` the quotient is required to be `0`, so the result is
+// `0` for SHL and SHR, and `0` or `-1` depending on the sign of `a` for SAR.
+impl<F: FieldExt> ShiftSuccessCase<F> {
+    pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::Success,
+        num_word: 4, // shift + a + result + remainder
+        num_cell: IsEqualGadget::<F>::NUM_CELLS * 2
+            + 1 // a_sign_byte
+            + IsZeroGadget::<F>::NUM_CELLS
+            + 4 // pow2_limb_selector
+            + 1 // pow2_limb
+            + MulAddWordsGadget::<F>::NUM_CELLS
+            + LtWordGadget::<F>::NUM_CELLS,
+        will_halt: false,
+    };
+
+    pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+            shift: alloc.words.pop().unwrap(),
+            a: alloc.words.pop().unwrap(),
+            result: alloc.words.pop().unwrap(),
+            remainder: alloc.words.pop().unwrap(),
+            is_shl: IsEqualGadget::construct(alloc),
+            is_sar: IsEqualGadget::construct(alloc),
+            a_sign_byte: alloc.cells.pop().unwrap(),
+            shift_in_range: IsZeroGadget::construct(alloc),
+            pow2_limb_selector: array_init(|_| alloc.cells.pop().unwrap()),
+            pow2_limb: alloc.cells.pop().unwrap(),
+            mul_add_words: MulAddWordsGadget::construct(alloc),
+            remainder_lt_pow2: LtWordGadget::construct(alloc),
+        }
+    }
+
+    pub(crate) fn constraint(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        state_next: &OpExecutionState<F>,
+        name: &'static str,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::default();
+
+        let is_shl = self.is_shl.constraints(
+            &mut cb,
+            state_curr.opcode.expr(),
+            OpcodeId::SHL.expr(),
+        );
+        let is_sar = self.is_sar.constraints(
+            &mut cb,
+            state_curr.opcode.expr(),
+            OpcodeId::SAR.expr(),
+        );
+        let is_shr_or_sar = 1.expr() - is_shl.clone();
+
+        // Lookup the sign byte of a, only used by SAR
+        cb.add_fixed_lookup(
+            FixedLookup::SignByte,
+            [self.a.cells[31].expr(), self.a_sign_byte.expr(), 0.expr()],
+        );
+        let inv_255 = F::from(255).invert().unwrap();
+        let is_neg = is_sar * self.a_sign_byte.expr() * inv_255;
+
+        // shift < 256 when all bytes except the least significant one are 0
+        let shift_in_range = self
+            .shift_in_range
+            .constraints(&mut cb, sum::expr(&self.shift.cells[1..]));
+
+        // Lookup `2**(shift % 64)` and the limb it belongs to
+        let mut limb_index = 0.expr();
+        for (idx, selector) in self.pow2_limb_selector.iter().enumerate() {
+            cb.require_boolean(selector.expr());
+            limb_index = limb_index + selector.expr() * idx.expr();
+        }
+        cb.require_equal(sum::expr(&self.pow2_limb_selector), 1.expr());
+        cb.add_fixed_lookup(
+            FixedLookup::Pow2,
+            [
+                self.shift.cells[0].expr(),
+                limb_index,
+                self.pow2_limb.expr(),
+            ],
+        );
+        let pow2: [Expression<F>; 4] = array_init(|idx| {
+            shift_in_range.clone()
+                * self.pow2_limb_selector[idx].expr()
+                * self.pow2_limb.expr()
+        });
+
+        // Bitwise not on limbs, used to handle negative values for SAR
+        let not = |limbs: [Expression<F>; 4]| {
+            limbs.map(|limb| {
+                select::expr(
+                    is_neg.clone(),
+                    Expression::Constant(get_range::<F>(64) - F::one())
+                        - limb.clone(),
+                    limb,
+                )
+            })
+        };
+        let a = word_limbs::expr(&self.a);
+        let result = word_limbs::expr(&self.result);
+        let dividend = not(a.clone());
+        let quotient = not(result.clone());
+        let remainder = word_limbs::expr(&self.remainder);
+
+        // SHL: a * 2**shift + 0 == result (mod 2**256)
+        // SHR/SAR: quotient * 2**shift + remainder == dividend
+        let overflow = self.mul_add_words.constraints(
+            &mut cb,
+            &array_init(|idx| {
+                select::expr(
+                    is_shl.clone(),
+                    a[idx].clone(),
+                    quotient[idx].clone(),
+                )
+            }),
+            &pow2,
+            &remainder.clone().map(|limb| is_shr_or_sar.clone() * limb),
+            &array_init(|idx| {
+                select::expr(
+                    is_shl.clone(),
+                    result[idx].clone(),
+                    dividend[idx].clone(),
+                )
+            }),
+        );
+        // The overflow is only allowed for SHL
+        cb.require_zero(is_shr_or_sar.clone() * overflow);
+
+        // remainder < 2**shift when shift < 256 for SHR/SAR
+        let lt = self
+            .remainder_lt_pow2
+            .constraints(&mut cb, &remainder, &pow2);
+        cb.require_zero(
+            is_shr_or_sar.clone() * shift_in_range.clone() * (1.expr() - lt),
+        );
+
+        // quotient == 0 when shift >= 256 for SHR/SAR
+        cb.require_zero(
+            is_shr_or_sar
+                * (1.expr() - shift_in_range)
+                * quotient
+                    .iter()
+                    .fold(0.expr(), |acc, limb| acc + limb.clone()),
+        );
+
+        // Pop shift and a from the stack, push result on the stack
+        cb.stack_pop(self.shift.expr());
+        cb.stack_pop(self.a.expr());
+        cb.stack_push(self.result.expr());
+
+        // State transitions
+        STATE_TRANSITION.constraints(&mut cb, state_curr, state_next);
+
+        // Generate the constraint
+        vec![cb.constraint(self.case_selector.expr(), name)]
+    }
+
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: &mut CoreStateInstance,
+        step: &ExecutionStep,
+    ) -> Result<(), Error> {
+        let opcode = F::from(step.opcode.as_u64());
+        let is_shl = self.is_shl.assign(
+            region,
+            offset,
+            opcode,
+            F::from(OpcodeId::SHL.as_u64()),
+        )? == F::one();
+        let is_sar = self.is_sar.assign(
+            region,
+            offset,
+            opcode,
+            F::from(OpcodeId::SAR.as_u64()),
+        )? == F::one();
+
+        // Inputs
+        let shift = step.values[0].clone();
+        let a = step.values[1].clone();
+        self.shift.assign(region, offset, Some(shift.to_word()))?;
+        self.a.assign(region, offset, Some(a.to_word()))?;
+
+        let a_is_neg = a.to_word()[31] >= 128;
+        self.a_sign_byte.assign(
+            region,
+            offset,
+            Some(F::from(a_is_neg as u64 * 0xFF)),
+        )?;
+        let is_neg = is_sar && a_is_neg;
+
+        // 2**shift, or 0 when shift >= 256
+        let shift_word = shift.to_word();
+        self.shift_in_range.assign(
+            region,
+            offset,
+            sum::value(&shift_word[1..]),
+        )?;
+        let shift_in_range = shift < BigUint::from(256u64);
+        let shift_byte = shift_word[0] as usize;
+        for (idx, selector) in self.pow2_limb_selector.iter().enumerate() {
+            selector.assign(
+                region,
+                offset,
+                Some(F::from((idx == shift_byte / 64) as u64)),
+            )?;
+        }
+        self.pow2_limb.assign(
+            region,
+            offset,
+            Some(F::from(1u64 << (shift_byte % 64))),
+        )?;
+        let pow2 = if shift_in_range {
+            BigUint::from(1u64) << shift_byte
+        } else {
+            BigUint::zero()
+        };
+
+        // Result
+        let max: BigUint = (BigUint::from(1u64) << 256) - 1u64;
+        let not = |value: BigUint| {
+            if is_neg {
+                &max - value
+            } else {
+                value
+            }
+        };
+        let (result, remainder) = if is_shl {
+            (&a * &pow2 % (BigUint::from(1u64) << 256), BigUint::zero())
+        } else {
+            let dividend = not(a.clone());
+            let (quotient, remainder) = if shift_in_range {
+                (&dividend >> shift_byte, &dividend % &pow2)
+            } else {
+                (BigUint::zero(), dividend)
+            };
+            (not(quotient), remainder)
+        };
+        self.result.assign(region, offset, Some(result.to_word()))?;
+        self.remainder
+            .assign(region, offset, Some(remainder.to_word()))?;
+
+        let multiplicand = if is_shl { a } else { not(result) };
+        self.mul_add_words.assign(
+            region,
+            offset,
+            multiplicand.to_word(),
+            pow2.to_word(),
+            remainder.to_word(),
+        )?;
+        self.remainder_lt_pow2.assign(
+            region,
+            offset,
+            remainder.to_word(),
+            pow2.to_word(),
+        )?;
+
+        // State transitions
+        STATE_TRANSITION.assign(state);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::test::run_test_circuit_from_code;
+    use bus_mapping::{bytecode, eth_types::Word, evm::OpcodeId};
+    use pairing::bn256::Fr as Fp;
+
+    fn check(opcode: OpcodeId, shift: Word, a: Word) {
+        let mut code = bytecode! {
+            PUSH32(a)
+            PUSH32(shift)
+        };
+        code.write_op(opcode).write_op(OpcodeId::STOP);
+        assert_eq!(run_test_circuit_from_code::<Fp>(&code), Ok(()));
+    }
+
+    #[test]
+    fn shift_gadget() {
+        let max = Word::MAX;
+        let min_neg = Word::one() << 255;
+
+        // SHL
+        // 0x0201 << 4 == 0x2010
+        check(OpcodeId::SHL, 4.into(), 0x02_01.into());
+        // max << 255 == 2**255
+        check(OpcodeId::SHL, 255.into(), max);
+        // max << 100 == max - (2**100 - 1)
+        check(OpcodeId::SHL, 100.into(), max);
+        // max << 256 == 0
+        check(OpcodeId::SHL, 256.into(), max);
+
+        // SHR
+        // 0x0201 >> 4 == 0x20
+        check(OpcodeId::SHR, 4.into(), 0x02_01.into());
+        // max >> 255 == 1
+        check(OpcodeId::SHR, 255.into(), max);
+        // max >> 0 == max
+        check(OpcodeId::SHR, Word::zero(), max);
+        // max >> max == 0
+        check(OpcodeId::SHR, max, max);
+
+        // SAR
+        // 0x0201 >> 4 == 0x20
+        check(OpcodeId::SAR, 4.into(), 0x02_01.into());
+        // -2**255 >> 254 == -2
+        check(OpcodeId::SAR, 254.into(), min_neg);
+        // -1 >> 100 == -1
+        check(OpcodeId::SAR, 100.into(), max);
+        // -0x1001 >> 4 == -0x101, which rounds towards negative infinity
+        check(OpcodeId::SAR, 4.into(), max - 0x10_00);
+        // -2**255 >> 256 == -1
+        check(OpcodeId::SAR, 256.into(), min_neg);
+        // 2**255 - 1 >> 256 == 0
+        check(OpcodeId::SAR, 256.into(), min_neg - 1);
+    }
+}
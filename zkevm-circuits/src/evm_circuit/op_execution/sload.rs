@@ -0,0 +1,358 @@
+use super::super::{Case, Cell, Constraint, ExecutionStep, Word};
+use super::utils::{
+    self, common_cases::StackUnderflowCase,
+    constraint_builder::ConstraintBuilder, from_bytes, math_gadgets::LtGadget,
+    select, StateTransition, StateTransitionExpressions,
+};
+use super::{
+    CaseAllocation, CaseConfig, CoreStateInstance, OpExecutionState, OpGadget,
+};
+use crate::evm_circuit::param::MAX_GAS_SIZE_IN_BYTES;
+use crate::impl_op_gadget;
+use crate::util::{Expr, ToWord};
+use bus_mapping::{
+    evm::{GasCost, OpcodeId},
+    operation::CallContextField,
+};
+use halo2::plonk::Error;
+use halo2::{arithmetic::FieldExt, circuit::Region};
+use std::convert::TryFrom;
+
+static STATE_TRANSITION: StateTransition = StateTransition {
+    // 2 call context reads + 2 stack ops + 1 storage read + 1 access list
+    // write
+    gc_delta: Some(6),
+    pc_delta: Some(1),
+    sp_delta: Some(0),
+    gas_delta: None, // WARM_STORAGE_READ_COST or COLD_SLOAD_COST
+    next_memory_size: None,
+};
+const NUM_POPPED: usize = 1;
+
+impl_op_gadget!(
+    #set[SLOAD]
+    SloadGadget {
+        SloadSuccessCase(),
+        StackUnderflowCase(NUM_POPPED),
+        SloadOutOfGasCase(),
+    }
+);
+
+#[derive(Clone, Debug)]
+struct SloadSuccessCase<F> {
+    case_selector: Cell<F>,
+    address: Cell<F>,
+    tx_id: Cell<F>,
+    is_warm: Cell<F>,
+    key: Word<F>,
+    value: Word<F>,
+    committed_value: Word<F>,
+}
+
+impl<F: FieldExt> SloadSuccessCase<F> {
+    pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::Success,
+        num_word: 3, // key + value + committed_value
+        num_cell: 3, // address + tx_id + is_warm
+        will_halt: false,
+    };
+
+    pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+            address: alloc.cells.pop().unwrap(),
+            tx_id: alloc.cells.pop().unwrap(),
+            is_warm: alloc.cells.pop().unwrap(),
+            key: alloc.words.pop().unwrap(),
+            value: alloc.words.pop().unwrap(),
+            committed_value: alloc.words.pop().unwrap(),
+        }
+    }
+
+    pub(crate) fn constraint(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        state_next: &OpExecutionState<F>,
+        name: &'static str,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::default();
+
+        // The storage is the one of the current account, accessed in the
+        // current tx
+        cb.call_context_lookup(
+            false,
+            state_curr.call_id.expr(),
+            CallContextField::CalleeAddress,
+            self.address.expr(),
+        );
+        cb.call_context_lookup(
+            false,
+            state_curr.call_id.expr(),
+            CallContextField::TxId,
+            self.tx_id.expr(),
+        );
+        cb.require_boolean(self.is_warm.expr());
+
+        // Pop the key from the stack, read the value from the storage, mark
+        // the slot as warm and push the value on the stack
+        cb.stack_pop(self.key.expr());
+        cb.account_storage_lookup(
+            false,
+            self.address.expr(),
+            self.key.expr(),
+            self.value.expr(),
+            self.value.expr(),
+            self.tx_id.expr(),
+            self.committed_value.expr(),
+        );
+        cb.tx_access_list_account_storage_write(
+            self.address.expr(),
+            self.key.expr(),
+            1.expr(),
+            self.is_warm.expr(),
+        );
+        cb.stack_push(self.value.expr());
+
+        // State transitions
+        // - `gas_delta` needs to be increased by `WARM_STORAGE_READ_COST` when
+        //   the slot is warm, and by `COLD_SLOAD_COST` otherwise (EIP-2929)
+        let mut st = StateTransitionExpressions::new(STATE_TRANSITION.clone());
+        st.gas_delta = Some(select::expr(
+            self.is_warm.expr(),
+            GasCost::WARM_STORAGE_READ_COST.expr(),
+            GasCost::COLD_SLOAD_COST.expr(),
+        ));
+        st.constraints(&mut cb, state_curr, state_next);
+
+        // Generate the constraint
+        vec![cb.constraint(self.case_selector.expr(), name)]
+    }
+
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: &mut CoreStateInstance,
+        step: &ExecutionStep,
+    ) -> Result<(), Error> {
+        // Inputs and output, followed by the committed value, the warm status
+        // and the call context values
+        self.key
+            .assign(region, offset, Some(step.values[0].to_word()))?;
+        self.value
+            .assign(region, offset, Some(step.values[1].to_word()))?;
+        self.committed_value.assign(
+            region,
+            offset,
+            Some(step.values[2].to_word()),
+        )?;
+        let is_warm = step.values[3] == 1u64.into();
+        self.is_warm
+            .assign(region, offset, Some(F::from(is_warm as u64)))?;
+        self.address.assign(
+            region,
+            offset,
+            Some(from_bytes::value(step.values[4].to_word()[..20].to_vec())),
+        )?;
+        let tx_id = u64::try_from(&step.values[5]).unwrap();
+        self.tx_id.assign(region, offset, Some(F::from(tx_id)))?;
+
+        // State transitions
+        let mut st = STATE_TRANSITION.clone();
+        st.gas_delta = Some(if is_warm {
+            GasCost::WARM_STORAGE_READ_COST.as_u64()
+        } else {
+            GasCost::COLD_SLOAD_COST.as_u64()
+        });
+        st.assign(state);
+        // The access list write of the slot is a state write
+        state.state_write_counter += 1;
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+struct SloadOutOfGasCase<F> {
+    case_selector: Cell<F>,
+    address: Cell<F>,
+    tx_id: Cell<F>,
+    is_warm: Cell<F>,
+    key: Word<F>,
+    insufficient_gas: LtGadget<F, MAX_GAS_SIZE_IN_BYTES>,
+}
+
+impl<F: FieldExt> SloadOutOfGasCase<F> {
+    pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::OutOfGas,
+        num_word: 1, // key
+        num_cell: 3 // address + tx_id + is_warm
+            + LtGadget::<F, MAX_GAS_SIZE_IN_BYTES>::NUM_CELLS,
+        will_halt: true,
+    };
+
+    pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+            address: alloc.cells.pop().unwrap(),
+            tx_id: alloc.cells.pop().unwrap(),
+            is_warm: alloc.cells.pop().unwrap(),
+            key: alloc.words.pop().unwrap(),
+            insufficient_gas: LtGadget::construct(alloc),
+        }
+    }
+
+    pub(crate) fn constraint(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        _state_next: &OpExecutionState<F>,
+        name: &'static str,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::default();
+
+        cb.call_context_lookup(
+            false,
+            state_curr.call_id.expr(),
+            CallContextField::CalleeAddress,
+            self.address.expr(),
+        );
+        cb.call_context_lookup(
+            false,
+            state_curr.call_id.expr(),
+            CallContextField::TxId,
+            self.tx_id.expr(),
+        );
+        cb.require_boolean(self.is_warm.expr());
+
+        // Pop the key from the stack and read the warm status of the slot
+        cb.stack_pop(self.key.expr());
+        cb.tx_access_list_account_storage_read(
+            self.address.expr(),
+            self.key.expr(),
+            self.is_warm.expr(),
+        );
+
+        // Make sure the amount of gas available is less than the amount of
+        // gas required to access the slot
+        let insufficient_gas = self.insufficient_gas.constraints(
+            &mut cb,
+            state_curr.gas_left.expr(),
+            select::expr(
+                self.is_warm.expr(),
+                GasCost::WARM_STORAGE_READ_COST.expr(),
+                GasCost::COLD_SLOAD_COST.expr(),
+            ),
+        );
+        cb.require_equal(insufficient_gas, 1.expr());
+
+        // Generate the constraint
+        vec![cb.constraint(self.case_selector.expr(), name)]
+    }
+
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: &mut CoreStateInstance,
+        step: &ExecutionStep,
+    ) -> Result<(), Error> {
+        // Input, followed by the warm status and the call context values
+        self.key
+            .assign(region, offset, Some(step.values[0].to_word()))?;
+        let is_warm = step.values[1] == 1u64.into();
+        self.is_warm
+            .assign(region, offset, Some(F::from(is_warm as u64)))?;
+        self.address.assign(
+            region,
+            offset,
+            Some(from_bytes::value(step.values[2].to_word()[..20].to_vec())),
+        )?;
+        let tx_id = u64::try_from(&step.values[3]).unwrap();
+        self.tx_id.assign(region, offset, Some(F::from(tx_id)))?;
+
+        // Gas insufficient check
+        let cost = if is_warm {
+            GasCost::WARM_STORAGE_READ_COST.as_u64()
+        } else {
+            GasCost::COLD_SLOAD_COST.as_u64()
+        };
+        self.insufficient_gas.assign(
+            region,
+            offset,
+            F::from(state.gas_left),
+            F::from(cost),
+        )?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::test::test_circuit_from_block;
+    use bus_mapping::{
+        bytecode, bytecode::Bytecode,
+        circuit_input_builder::CircuitInputBuilder, mock,
+    };
+    use halo2::dev::MockProver;
+    use pairing::bn256::Fr as Fp;
+
+    fn test_sload_gadget(code: Bytecode, callee_code: Option<Bytecode>) {
+        let block = match callee_code {
+            Some(callee_code) => {
+                mock::BlockData::new_single_tx_trace_code_2(&code, &callee_code)
+            }
+            None => mock::BlockData::new_single_tx_trace_code(&code),
+        }
+        .unwrap();
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let circuit = test_circuit_from_block::<Fp>(&builder.block);
+        let prover = MockProver::<Fp>::run(11, &circuit, vec![vec![]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    fn sload_gadget_from_trace() {
+        // The first SLOAD accesses a cold slot, the second one a warm slot
+        let code = bytecode! {
+            PUSH1(0x6f)
+            PUSH1(0x01)
+            SSTORE
+            PUSH1(0x00)
+            SLOAD
+            PUSH1(0x00)
+            SLOAD
+            PUSH1(0x01)
+            SLOAD
+            STOP
+        };
+        test_sload_gadget(code, None);
+    }
+
+    #[test]
+    fn sload_gadget_out_of_gas() {
+        // The callee doesn't have the gas to access a cold slot
+        let code = bytecode! {
+            PUSH1(0x00) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x00) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH1(0x00) // value
+            PUSH2(0x123) // addr
+            PUSH2(0x800) // gas
+            CALL
+            STOP
+        };
+        let callee_code = bytecode! {
+            PUSH1(0x00)
+            SLOAD
+            STOP
+        };
+        test_sload_gadget(code, Some(callee_code));
+    }
+}
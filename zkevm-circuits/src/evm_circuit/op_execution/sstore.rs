@@ -0,0 +1,743 @@
+use super::super::{Case, Cell, Constraint, ExecutionStep, Word};
+use super::utils::{
+    self,
+    common_cases::StackUnderflowCase,
+    constraint_builder::ConstraintBuilder,
+    from_bytes,
+    math_gadgets::{IsEqualWordGadget, IsZeroGadget, LtGadget},
+    select, sum, StateTransition, StateTransitionExpressions,
+};
+use super::{
+    CaseAllocation, CaseConfig, CoreStateInstance, OpExecutionState, OpGadget,
+};
+use crate::evm_circuit::param::MAX_GAS_SIZE_IN_BYTES;
+use crate::impl_op_gadget;
+use crate::util::{Expr, ToWord};
+use bus_mapping::{
+    evm::{GasCost, OpcodeId},
+    operation::CallContextField,
+};
+use halo2::plonk::{Error, Expression};
+use halo2::{arithmetic::FieldExt, circuit::Region};
+use std::convert::TryFrom;
+
+static STATE_TRANSITION: StateTransition = StateTransition {
+    // 3 call context reads + 2 stack pops + 1 storage write + 2 tx writes
+    gc_delta: Some(8),
+    pc_delta: Some(1),
+    sp_delta: Some(2),
+    gas_delta: None, // EIP-2929 access cost + EIP-2200 storage cost
+    next_memory_size: None,
+};
+const NUM_POPPED: usize = 2;
+
+impl_op_gadget!(
+    #set[SSTORE]
+    SstoreGadget {
+        SstoreSuccessCase(),
+        StackUnderflowCase(NUM_POPPED),
+        SstoreOutOfGasCase(),
+        SstoreWriteProtectionCase(),
+    }
+);
+
+/// Returns the gas cost of SSTORE, which is increased by `COLD_SLOAD_COST`
+/// when the slot is cold (EIP-2929), plus:
+/// - `WARM_STORAGE_READ_COST` when the value doesn't change or the slot is
+///   dirty
+/// - `SSTORE_SET` when setting a clean zero slot
+/// - `SSTORE_RESET` when updating a clean non-zero slot
+fn sstore_gas_cost_expr<F: FieldExt>(
+    is_warm: Expression<F>,
+    value_eq_prev: Expression<F>,
+    prev_eq_committed: Expression<F>,
+    committed_is_zero: Expression<F>,
+) -> Expression<F> {
+    (1.expr() - is_warm) * GasCost::COLD_SLOAD_COST.expr()
+        + select::expr(
+            (1.expr() - value_eq_prev) * prev_eq_committed,
+            select::expr(
+                committed_is_zero,
+                GasCost::SSTORE_SET.expr(),
+                GasCost::SSTORE_RESET.expr(),
+            ),
+            GasCost::WARM_STORAGE_READ_COST.expr(),
+        )
+}
+
+fn sstore_gas_cost(
+    is_warm: bool,
+    value_eq_prev: bool,
+    prev_eq_committed: bool,
+    committed_is_zero: bool,
+) -> u64 {
+    let storage_cost = if !value_eq_prev && prev_eq_committed {
+        if committed_is_zero {
+            GasCost::SSTORE_SET.as_u64()
+        } else {
+            GasCost::SSTORE_RESET.as_u64()
+        }
+    } else {
+        GasCost::WARM_STORAGE_READ_COST.as_u64()
+    };
+    let access_cost = if is_warm {
+        0
+    } else {
+        GasCost::COLD_SLOAD_COST.as_u64()
+    };
+    access_cost + storage_cost
+}
+
+#[derive(Clone, Debug)]
+struct SstoreSuccessCase<F> {
+    case_selector: Cell<F>,
+    address: Cell<F>,
+    tx_id: Cell<F>,
+    is_warm: Cell<F>,
+    refund: Cell<F>,
+    refund_prev: Cell<F>,
+    key: Word<F>,
+    value: Word<F>,
+    value_prev: Word<F>,
+    committed_value: Word<F>,
+    value_eq_prev: IsEqualWordGadget<F>,
+    value_eq_committed: IsEqualWordGadget<F>,
+    prev_eq_committed: IsEqualWordGadget<F>,
+    value_is_zero: IsZeroGadget<F>,
+    prev_is_zero: IsZeroGadget<F>,
+    committed_is_zero: IsZeroGadget<F>,
+    above_sentry: LtGadget<F, MAX_GAS_SIZE_IN_BYTES>,
+}
+
+impl<F: FieldExt> SstoreSuccessCase<F> {
+    pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::Success,
+        num_word: 4, // key + value + value_prev + committed_value
+        // address + tx_id + is_warm + refund + refund_prev
+        num_cell: 5
+            + IsEqualWordGadget::<F>::NUM_CELLS * 3
+            + IsZeroGadget::<F>::NUM_CELLS * 3
+            + LtGadget::<F, MAX_GAS_SIZE_IN_BYTES>::NUM_CELLS,
+        will_halt: false,
+    };
+
+    pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+            address: alloc.cells.pop().unwrap(),
+            tx_id: alloc.cells.pop().unwrap(),
+            is_warm: alloc.cells.pop().unwrap(),
+            refund: alloc.cells.pop().unwrap(),
+            refund_prev: alloc.cells.pop().unwrap(),
+            key: alloc.words.pop().unwrap(),
+            value: alloc.words.pop().unwrap(),
+            value_prev: alloc.words.pop().unwrap(),
+            committed_value: alloc.words.pop().unwrap(),
+            value_eq_prev: IsEqualWordGadget::construct(alloc),
+            value_eq_committed: IsEqualWordGadget::construct(alloc),
+            prev_eq_committed: IsEqualWordGadget::construct(alloc),
+            value_is_zero: IsZeroGadget::construct(alloc),
+            prev_is_zero: IsZeroGadget::construct(alloc),
+            committed_is_zero: IsZeroGadget::construct(alloc),
+            above_sentry: LtGadget::construct(alloc),
+        }
+    }
+
+    pub(crate) fn constraint(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        state_next: &OpExecutionState<F>,
+        name: &'static str,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::default();
+
+        // The storage is the one of the current account, accessed in the
+        // current tx by a call that isn't static
+        cb.call_context_lookup(
+            false,
+            state_curr.call_id.expr(),
+            CallContextField::IsStatic,
+            0.expr(),
+        );
+        cb.call_context_lookup(
+            false,
+            state_curr.call_id.expr(),
+            CallContextField::CalleeAddress,
+            self.address.expr(),
+        );
+        cb.call_context_lookup(
+            false,
+            state_curr.call_id.expr(),
+            CallContextField::TxId,
+            self.tx_id.expr(),
+        );
+        cb.require_boolean(self.is_warm.expr());
+
+        // The gas left has to be more than `SSTORE_SENTRY` (EIP-2200)
+        let above_sentry = self.above_sentry.constraints(
+            &mut cb,
+            GasCost::SSTORE_SENTRY.expr(),
+            state_curr.gas_left.expr(),
+        );
+        cb.require_equal(above_sentry, 1.expr());
+
+        let value_eq_prev = self.value_eq_prev.constraints(
+            &mut cb,
+            &self.value,
+            &self.value_prev,
+        );
+        let value_eq_committed = self.value_eq_committed.constraints(
+            &mut cb,
+            &self.value,
+            &self.committed_value,
+        );
+        let prev_eq_committed = self.prev_eq_committed.constraints(
+            &mut cb,
+            &self.value_prev,
+            &self.committed_value,
+        );
+        let value_is_zero = self
+            .value_is_zero
+            .constraints(&mut cb, sum::expr(&self.value.cells));
+        let prev_is_zero = self
+            .prev_is_zero
+            .constraints(&mut cb, sum::expr(&self.value_prev.cells));
+        let committed_is_zero = self
+            .committed_is_zero
+            .constraints(&mut cb, sum::expr(&self.committed_value.cells));
+
+        // Refund when the value changes (EIP-2200 with EIP-3529 values), both
+        // parts are zero when it doesn't:
+        // - Clean slot: `SSTORE_CLEARS_SCHEDULE` when clearing it
+        // - Dirty slot with a non-zero committed value:
+        //   `-SSTORE_CLEARS_SCHEDULE` when recreating it,
+        //   `SSTORE_CLEARS_SCHEDULE` when clearing it
+        // - Dirty slot reset to the committed value: `SSTORE_SET -
+        //   WARM_STORAGE_READ_COST` when the committed value is zero,
+        //   `SSTORE_RESET - WARM_STORAGE_READ_COST` otherwise
+        let clears_schedule = GasCost::SSTORE_CLEARS_SCHEDULE.expr();
+        let clean_refund = (1.expr() - committed_is_zero.clone())
+            * value_is_zero.clone()
+            * clears_schedule.clone();
+        let dirty_refund = (1.expr() - committed_is_zero.clone())
+            * (value_is_zero - prev_is_zero)
+            * clears_schedule
+            + value_eq_committed
+                * select::expr(
+                    committed_is_zero.clone(),
+                    GasCost::SSTORE_SET.expr()
+                        - GasCost::WARM_STORAGE_READ_COST.expr(),
+                    GasCost::SSTORE_RESET.expr()
+                        - GasCost::WARM_STORAGE_READ_COST.expr(),
+                );
+        cb.require_equal(
+            self.refund.expr(),
+            self.refund_prev.expr()
+                + select::expr(
+                    prev_eq_committed.clone(),
+                    clean_refund,
+                    dirty_refund,
+                ),
+        );
+
+        // Pop the key and the value from the stack, write the value into the
+        // storage, mark the slot as warm and update the refund counter. The
+        // storage operation carries the value of the slot at the start of the
+        // tx, which is verified by the state circuit.
+        cb.stack_pop(self.key.expr());
+        cb.stack_pop(self.value.expr());
+        cb.account_storage_lookup(
+            true,
+            self.address.expr(),
+            self.key.expr(),
+            self.value.expr(),
+            self.value_prev.expr(),
+            self.tx_id.expr(),
+            self.committed_value.expr(),
+        );
+        cb.tx_access_list_account_storage_write(
+            self.address.expr(),
+            self.key.expr(),
+            1.expr(),
+            self.is_warm.expr(),
+        );
+        cb.tx_refund_write(self.refund.expr(), self.refund_prev.expr());
+
+        // State transitions
+        let mut st = StateTransitionExpressions::new(STATE_TRANSITION.clone());
+        st.gas_delta = Some(sstore_gas_cost_expr(
+            self.is_warm.expr(),
+            value_eq_prev,
+            prev_eq_committed,
+            committed_is_zero,
+        ));
+        st.constraints(&mut cb, state_curr, state_next);
+
+        // Generate the constraint
+        vec![cb.constraint(self.case_selector.expr(), name)]
+    }
+
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: &mut CoreStateInstance,
+        step: &ExecutionStep,
+    ) -> Result<(), Error> {
+        // Inputs, followed by the previous value, the committed value, the
+        // warm status, the refund counter, the call context values and the
+        // gas left
+        let key = step.values[0].to_word();
+        let value = step.values[1].to_word();
+        let value_prev = step.values[2].to_word();
+        let committed_value = step.values[3].to_word();
+        let is_warm = step.values[4] == 1u64.into();
+        let refund_prev = u64::try_from(&step.values[5]).unwrap();
+        self.key.assign(region, offset, Some(key))?;
+        self.value.assign(region, offset, Some(value))?;
+        self.value_prev.assign(region, offset, Some(value_prev))?;
+        self.committed_value
+            .assign(region, offset, Some(committed_value))?;
+        self.is_warm
+            .assign(region, offset, Some(F::from(is_warm as u64)))?;
+        self.address.assign(
+            region,
+            offset,
+            Some(from_bytes::value(step.values[7].to_word()[..20].to_vec())),
+        )?;
+        let tx_id = u64::try_from(&step.values[8]).unwrap();
+        self.tx_id.assign(region, offset, Some(F::from(tx_id)))?;
+
+        // Gas sentry check
+        self.above_sentry.assign(
+            region,
+            offset,
+            F::from(GasCost::SSTORE_SENTRY.as_u64()),
+            F::from(state.gas_left),
+        )?;
+
+        let value_eq_prev = self
+            .value_eq_prev
+            .assign(region, offset, value, value_prev)?
+            == F::one();
+        let value_eq_committed = self.value_eq_committed.assign(
+            region,
+            offset,
+            value,
+            committed_value,
+        )? == F::one();
+        let prev_eq_committed = self.prev_eq_committed.assign(
+            region,
+            offset,
+            value_prev,
+            committed_value,
+        )? == F::one();
+        let value_is_zero =
+            self.value_is_zero
+                .assign(region, offset, sum::value(&value))?
+                == F::one();
+        let prev_is_zero = self.prev_is_zero.assign(
+            region,
+            offset,
+            sum::value(&value_prev),
+        )? == F::one();
+        let committed_is_zero = self.committed_is_zero.assign(
+            region,
+            offset,
+            sum::value(&committed_value),
+        )? == F::one();
+
+        // Refund, which can't go below zero
+        let clears_schedule = GasCost::SSTORE_CLEARS_SCHEDULE.as_u64();
+        let warm_read = GasCost::WARM_STORAGE_READ_COST.as_u64();
+        let mut refund = refund_prev;
+        if !value_eq_prev {
+            if prev_eq_committed {
+                if !committed_is_zero && value_is_zero {
+                    refund += clears_schedule;
+                }
+            } else {
+                if !committed_is_zero {
+                    if prev_is_zero {
+                        refund = refund
+                            .checked_sub(clears_schedule)
+                            .ok_or(Error::Synthesis)?;
+                    } else if value_is_zero {
+                        refund += clears_schedule;
+                    }
+                }
+                if value_eq_committed {
+                    refund += if committed_is_zero {
+                        GasCost::SSTORE_SET.as_u64() - warm_read
+                    } else {
+                        GasCost::SSTORE_RESET.as_u64() - warm_read
+                    };
+                }
+            }
+        }
+        self.refund_prev
+            .assign(region, offset, Some(F::from(refund_prev)))?;
+        self.refund.assign(region, offset, Some(F::from(refund)))?;
+
+        // State transitions
+        let mut st = STATE_TRANSITION.clone();
+        st.gas_delta = Some(sstore_gas_cost(
+            is_warm,
+            value_eq_prev,
+            prev_eq_committed,
+            committed_is_zero,
+        ));
+        st.assign(state);
+        // The writes of the slot, of its warm status and of the refund are
+        // state writes
+        state.state_write_counter += 3;
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+struct SstoreOutOfGasCase<F> {
+    case_selector: Cell<F>,
+    address: Cell<F>,
+    tx_id: Cell<F>,
+    is_warm: Cell<F>,
+    key: Word<F>,
+    value: Word<F>,
+    value_prev: Word<F>,
+    committed_value: Word<F>,
+    value_eq_prev: IsEqualWordGadget<F>,
+    prev_eq_committed: IsEqualWordGadget<F>,
+    committed_is_zero: IsZeroGadget<F>,
+    below_sentry: LtGadget<F, MAX_GAS_SIZE_IN_BYTES>,
+    insufficient_gas: LtGadget<F, MAX_GAS_SIZE_IN_BYTES>,
+}
+
+impl<F: FieldExt> SstoreOutOfGasCase<F> {
+    pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::OutOfGas,
+        num_word: 4, // key + value + value_prev + committed_value
+        num_cell: 3 // address + tx_id + is_warm
+            + IsEqualWordGadget::<F>::NUM_CELLS * 2
+            + IsZeroGadget::<F>::NUM_CELLS
+            + LtGadget::<F, MAX_GAS_SIZE_IN_BYTES>::NUM_CELLS * 2,
+        will_halt: true,
+    };
+
+    pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+            address: alloc.cells.pop().unwrap(),
+            tx_id: alloc.cells.pop().unwrap(),
+            is_warm: alloc.cells.pop().unwrap(),
+            key: alloc.words.pop().unwrap(),
+            value: alloc.words.pop().unwrap(),
+            value_prev: alloc.words.pop().unwrap(),
+            committed_value: alloc.words.pop().unwrap(),
+            value_eq_prev: IsEqualWordGadget::construct(alloc),
+            prev_eq_committed: IsEqualWordGadget::construct(alloc),
+            committed_is_zero: IsZeroGadget::construct(alloc),
+            below_sentry: LtGadget::construct(alloc),
+            insufficient_gas: LtGadget::construct(alloc),
+        }
+    }
+
+    pub(crate) fn constraint(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        _state_next: &OpExecutionState<F>,
+        name: &'static str,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::default();
+
+        cb.call_context_lookup(
+            false,
+            state_curr.call_id.expr(),
+            CallContextField::IsStatic,
+            0.expr(),
+        );
+        cb.call_context_lookup(
+            false,
+            state_curr.call_id.expr(),
+            CallContextField::CalleeAddress,
+            self.address.expr(),
+        );
+        cb.call_context_lookup(
+            false,
+            state_curr.call_id.expr(),
+            CallContextField::TxId,
+            self.tx_id.expr(),
+        );
+        cb.require_boolean(self.is_warm.expr());
+
+        // Pop the key and the value from the stack and read the slot and its
+        // warm status, which determine the gas cost
+        cb.stack_pop(self.key.expr());
+        cb.stack_pop(self.value.expr());
+        cb.account_storage_lookup(
+            false,
+            self.address.expr(),
+            self.key.expr(),
+            self.value_prev.expr(),
+            self.value_prev.expr(),
+            self.tx_id.expr(),
+            self.committed_value.expr(),
+        );
+        cb.tx_access_list_account_storage_read(
+            self.address.expr(),
+            self.key.expr(),
+            self.is_warm.expr(),
+        );
+
+        let value_eq_prev = self.value_eq_prev.constraints(
+            &mut cb,
+            &self.value,
+            &self.value_prev,
+        );
+        let prev_eq_committed = self.prev_eq_committed.constraints(
+            &mut cb,
+            &self.value_prev,
+            &self.committed_value,
+        );
+        let committed_is_zero = self
+            .committed_is_zero
+            .constraints(&mut cb, sum::expr(&self.committed_value.cells));
+
+        // Make sure the gas left is not more than `SSTORE_SENTRY` (EIP-2200)
+        // or less than the amount of gas required to write the slot
+        let below_sentry = self.below_sentry.constraints(
+            &mut cb,
+            state_curr.gas_left.expr(),
+            GasCost::SSTORE_SENTRY.expr() + 1.expr(),
+        );
+        let insufficient_gas = self.insufficient_gas.constraints(
+            &mut cb,
+            state_curr.gas_left.expr(),
+            sstore_gas_cost_expr(
+                self.is_warm.expr(),
+                value_eq_prev,
+                prev_eq_committed,
+                committed_is_zero,
+            ),
+        );
+        cb.require_zero(
+            (1.expr() - below_sentry) * (1.expr() - insufficient_gas),
+        );
+
+        // Generate the constraint
+        vec![cb.constraint(self.case_selector.expr(), name)]
+    }
+
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: &mut CoreStateInstance,
+        step: &ExecutionStep,
+    ) -> Result<(), Error> {
+        // Inputs, followed by the current value, the committed value, the
+        // warm status and the call context values
+        let key = step.values[0].to_word();
+        let value = step.values[1].to_word();
+        let value_prev = step.values[2].to_word();
+        let committed_value = step.values[3].to_word();
+        let is_warm = step.values[4] == 1u64.into();
+        self.key.assign(region, offset, Some(key))?;
+        self.value.assign(region, offset, Some(value))?;
+        self.value_prev.assign(region, offset, Some(value_prev))?;
+        self.committed_value
+            .assign(region, offset, Some(committed_value))?;
+        self.is_warm
+            .assign(region, offset, Some(F::from(is_warm as u64)))?;
+        self.address.assign(
+            region,
+            offset,
+            Some(from_bytes::value(step.values[6].to_word()[..20].to_vec())),
+        )?;
+        let tx_id = u64::try_from(&step.values[7]).unwrap();
+        self.tx_id.assign(region, offset, Some(F::from(tx_id)))?;
+
+        let value_eq_prev = self
+            .value_eq_prev
+            .assign(region, offset, value, value_prev)?
+            == F::one();
+        let prev_eq_committed = self.prev_eq_committed.assign(
+            region,
+            offset,
+            value_prev,
+            committed_value,
+        )? == F::one();
+        let committed_is_zero = self.committed_is_zero.assign(
+            region,
+            offset,
+            sum::value(&committed_value),
+        )? == F::one();
+
+        // Gas insufficient check
+        self.below_sentry.assign(
+            region,
+            offset,
+            F::from(state.gas_left),
+            F::from(GasCost::SSTORE_SENTRY.as_u64() + 1),
+        )?;
+        self.insufficient_gas.assign(
+            region,
+            offset,
+            F::from(state.gas_left),
+            F::from(sstore_gas_cost(
+                is_warm,
+                value_eq_prev,
+                prev_eq_committed,
+                committed_is_zero,
+            )),
+        )?;
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+struct SstoreWriteProtectionCase<F> {
+    case_selector: Cell<F>,
+}
+
+impl<F: FieldExt> SstoreWriteProtectionCase<F> {
+    pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::WriteProtection,
+        num_word: 0,
+        num_cell: 0,
+        will_halt: true,
+    };
+
+    pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+        }
+    }
+
+    pub(crate) fn constraint(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        _state_next: &OpExecutionState<F>,
+        name: &'static str,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::default();
+
+        // The storage can't be written by a static call
+        cb.call_context_lookup(
+            false,
+            state_curr.call_id.expr(),
+            CallContextField::IsStatic,
+            1.expr(),
+        );
+
+        vec![cb.constraint(self.case_selector.expr(), name)]
+    }
+
+    pub(crate) fn assign(
+        &self,
+        _region: &mut Region<'_, F>,
+        _offset: usize,
+        _state: &mut CoreStateInstance,
+        _step: &ExecutionStep,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::test::test_circuit_from_block;
+    use bus_mapping::{
+        bytecode, bytecode::Bytecode,
+        circuit_input_builder::CircuitInputBuilder, mock,
+    };
+    use halo2::dev::MockProver;
+    use pairing::bn256::Fr as Fp;
+
+    fn test_sstore_gadget(code: Bytecode, callee_code: Option<Bytecode>) {
+        let block = match callee_code {
+            Some(callee_code) => {
+                mock::BlockData::new_single_tx_trace_code_2(&code, &callee_code)
+            }
+            None => mock::BlockData::new_single_tx_trace_code(&code),
+        }
+        .unwrap();
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let circuit = test_circuit_from_block::<Fp>(&builder.block);
+        let prover = MockProver::<Fp>::run(11, &circuit, vec![vec![]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    fn sstore_gadget_from_trace() {
+        // Set a clean slot, overwrite it while dirty, restore it to its
+        // original value and then write the same value again
+        let code = bytecode! {
+            PUSH1(0x6f)
+            PUSH1(0x00)
+            SSTORE
+            PUSH1(0x70)
+            PUSH1(0x00)
+            SSTORE
+            PUSH1(0x00)
+            PUSH1(0x00)
+            SSTORE
+            PUSH1(0x00)
+            PUSH1(0x00)
+            SSTORE
+            STOP
+        };
+        test_sstore_gadget(code, None);
+    }
+
+    #[test]
+    fn sstore_gadget_out_of_gas() {
+        // The callee has less gas left than `SSTORE_SENTRY`
+        let callee_code = bytecode! {
+            PUSH1(0x6f)
+            PUSH1(0x00)
+            SSTORE
+            STOP
+        };
+        let code = bytecode! {
+            PUSH1(0x00) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x00) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH1(0x00) // value
+            PUSH2(0x123) // addr
+            PUSH2(0x800) // gas
+            CALL
+            STOP
+        };
+        test_sstore_gadget(code, Some(callee_code));
+    }
+
+    #[test]
+    fn sstore_gadget_write_protection() {
+        // The storage can't be written by a static call
+        let callee_code = bytecode! {
+            PUSH1(0x6f)
+            PUSH1(0x00)
+            SSTORE
+            STOP
+        };
+        let code = bytecode! {
+            PUSH1(0x00) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x00) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH2(0x123) // addr
+            PUSH2(0x5000) // gas
+            STATICCALL
+            STOP
+        };
+        test_sstore_gadget(code, Some(callee_code));
+    }
+}
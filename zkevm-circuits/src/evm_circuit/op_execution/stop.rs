@@ -0,0 +1,371 @@
+use super::super::{Case, Cell, Constraint, ExecutionStep, Word};
+use super::call::NUM_CALLER_WRITES;
+use super::r#return::NUM_CONTEXT_OPS_AFTER_COPY;
+use super::utils::{
+    self, constraint_builder::ConstraintBuilder, math_gadgets::IsEqualGadget,
+    select, StateTransitionExpressions,
+};
+use super::{
+    CaseAllocation, CaseConfig, CoreStateInstance, OpExecutionState, OpGadget,
+};
+use crate::impl_op_gadget;
+use crate::util::{Expr, ToWord};
+use bus_mapping::{evm::OpcodeId, operation::CallContextField};
+use halo2::plonk::Error;
+use halo2::{arithmetic::FieldExt, circuit::Region};
+use std::convert::TryInto;
+
+// Call context reads of the depth, which is 1 when the root call ends the tx,
+// and of the caller id
+const NUM_READS: usize = 2;
+
+impl_op_gadget!(
+    #set[STOP]
+    StopGadget {
+        StopSuccessCase(),
+    }
+);
+
+#[derive(Clone, Debug)]
+struct StopSuccessCase<F> {
+    case_selector: Cell<F>,
+    // The root call has no caller, and ends the tx
+    depth: Cell<F>,
+    caller_id: Cell<F>,
+    is_root: IsEqualGadget<F>,
+    // State of the caller saved by the call, in words for the memory size
+    caller_program_counter: Cell<F>,
+    caller_stack_pointer: Cell<F>,
+    caller_gas_left: Cell<F>,
+    caller_memory_size: Cell<F>,
+    caller_state_write_counter: Cell<F>,
+    caller_code_hash: Word<F>,
+    // Gas given to the callee by the call
+    callee_gas: Cell<F>,
+}
+
+impl<F: FieldExt> StopSuccessCase<F> {
+    pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::Success,
+        num_word: 1, // caller_code_hash
+        // depth + caller_id + the 5 saved fields of the caller in cells +
+        // callee_gas
+        num_cell: 8 + IsEqualGadget::<F>::NUM_CELLS,
+        will_halt: false,
+    };
+
+    pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+            depth: alloc.cells.pop().unwrap(),
+            caller_id: alloc.cells.pop().unwrap(),
+            is_root: IsEqualGadget::construct(alloc),
+            caller_program_counter: alloc.cells.pop().unwrap(),
+            caller_stack_pointer: alloc.cells.pop().unwrap(),
+            caller_gas_left: alloc.cells.pop().unwrap(),
+            caller_memory_size: alloc.cells.pop().unwrap(),
+            caller_state_write_counter: alloc.cells.pop().unwrap(),
+            caller_code_hash: alloc.words.pop().unwrap(),
+            callee_gas: alloc.cells.pop().unwrap(),
+        }
+    }
+
+    pub(crate) fn constraint(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        state_next: &OpExecutionState<F>,
+        name: &'static str,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::default();
+
+        // Read the depth to know whether the root call ends, and the caller
+        // id
+        for (field, value) in [
+            (CallContextField::Depth, self.depth.expr()),
+            (CallContextField::CallerId, self.caller_id.expr()),
+        ] {
+            cb.call_context_lookup(
+                false,
+                state_curr.call_id.expr(),
+                field,
+                value,
+            );
+        }
+        let is_root =
+            self.is_root
+                .constraints(&mut cb, self.depth.expr(), 1.expr());
+        let not_root = 1.expr() - is_root.clone();
+
+        // Return to the caller when the current call isn't the root one: read
+        // the success of the call and the gas given to it, which are set up
+        // by the call, and the saved state of the caller, and record the call
+        // as the last callee of the caller, with no return data.
+        cb.condition(not_root.clone(), |cb| {
+            for (idx, (is_write, call_id, field, value)) in [
+                (
+                    false,
+                    state_curr.call_id.expr(),
+                    CallContextField::IsSuccess,
+                    1.expr(),
+                ),
+                (
+                    false,
+                    state_curr.call_id.expr(),
+                    CallContextField::Gas,
+                    self.callee_gas.expr(),
+                ),
+                (
+                    false,
+                    self.caller_id.expr(),
+                    CallContextField::ProgramCounter,
+                    self.caller_program_counter.expr(),
+                ),
+                (
+                    false,
+                    self.caller_id.expr(),
+                    CallContextField::StackPointer,
+                    self.caller_stack_pointer.expr(),
+                ),
+                (
+                    false,
+                    self.caller_id.expr(),
+                    CallContextField::GasLeft,
+                    self.caller_gas_left.expr(),
+                ),
+                (
+                    false,
+                    self.caller_id.expr(),
+                    CallContextField::MemorySize,
+                    self.caller_memory_size.expr() * 32.expr(),
+                ),
+                (
+                    false,
+                    self.caller_id.expr(),
+                    CallContextField::StateWriteCounter,
+                    self.caller_state_write_counter.expr(),
+                ),
+                (
+                    false,
+                    self.caller_id.expr(),
+                    CallContextField::CodeHash,
+                    self.caller_code_hash.expr(),
+                ),
+                (
+                    true,
+                    self.caller_id.expr(),
+                    CallContextField::LastCalleeId,
+                    state_curr.call_id.expr(),
+                ),
+                (
+                    true,
+                    self.caller_id.expr(),
+                    CallContextField::LastCalleeReturnDataOffset,
+                    0.expr(),
+                ),
+                (
+                    true,
+                    self.caller_id.expr(),
+                    CallContextField::LastCalleeReturnDataLength,
+                    0.expr(),
+                ),
+            ]
+            .iter()
+            .enumerate()
+            {
+                cb.call_context_lookup_at(
+                    *is_write,
+                    call_id.clone(),
+                    *field,
+                    value.clone(),
+                    NUM_READS.expr() + idx.expr(),
+                );
+            }
+        });
+
+        // State transitions to the step of the caller following the call, or
+        // to the end of the tx, which stays in the root call.  STOP costs no
+        // gas, and the callee gas is refunded like for RETURN.
+        cb.require_equal(
+            state_next.call_id.expr(),
+            select::expr(
+                is_root.clone(),
+                state_curr.call_id.expr(),
+                self.caller_id.expr(),
+            ),
+        );
+        let st = StateTransitionExpressions {
+            gc_delta: Some(
+                NUM_READS.expr()
+                    + NUM_CONTEXT_OPS_AFTER_COPY.expr() * not_root.clone(),
+            ),
+            pc_delta: Some(
+                not_root.clone()
+                    * (self.caller_program_counter.expr()
+                        - state_curr.program_counter.expr()),
+            ),
+            sp_delta: Some(
+                not_root.clone()
+                    * (self.caller_stack_pointer.expr()
+                        - state_curr.stack_pointer.expr()),
+            ),
+            gas_delta: Some(
+                0.expr() - not_root.clone() * self.callee_gas.expr(),
+            ),
+            next_gas_left: Some(
+                state_curr.gas_left.expr()
+                    + not_root.clone() * self.caller_gas_left.expr(),
+            ),
+            next_memory_size: Some(select::expr(
+                is_root.clone(),
+                state_curr.memory_size.expr(),
+                self.caller_memory_size.expr(),
+            )),
+            next_code_source: Some(select::expr(
+                is_root,
+                state_curr.code_source.expr(),
+                self.caller_code_hash.expr(),
+            )),
+            log_id_delta: None,
+            // The state writes of the call are added to the ones of the caller
+            next_state_write_counter: Some(
+                state_curr.state_write_counter.expr()
+                    + not_root * self.caller_state_write_counter.expr(),
+            ),
+        };
+        st.constraints(&mut cb, state_curr, state_next);
+
+        // Generate the constraint
+        vec![cb.constraint(self.case_selector.expr(), name)]
+    }
+
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: &mut CoreStateInstance,
+        step: &ExecutionStep,
+    ) -> Result<(), Error> {
+        // Depth and caller id, followed by the success and the gas of the
+        // call and the saved state of the caller unless the call is the root
+        // one
+        let value_u64 = |idx: usize| -> u64 {
+            step.values[idx].clone().try_into().unwrap()
+        };
+        let (depth, caller_id) = (value_u64(0), value_u64(1));
+        self.depth.assign(region, offset, Some(F::from(depth)))?;
+        self.caller_id
+            .assign(region, offset, Some(F::from(caller_id)))?;
+        let is_root =
+            self.is_root
+                .assign(region, offset, F::from(depth), F::one())?
+                == F::one();
+
+        let caller_values = if is_root {
+            vec![0; NUM_CALLER_WRITES]
+        } else {
+            (1..=NUM_CALLER_WRITES)
+                .map(|idx| value_u64(NUM_READS + idx))
+                .collect::<Vec<_>>()
+        };
+        for (cell, value) in [
+            &self.callee_gas,
+            &self.caller_program_counter,
+            &self.caller_stack_pointer,
+            &self.caller_gas_left,
+        ]
+        .iter()
+        .zip(caller_values.iter())
+        {
+            cell.assign(region, offset, Some(F::from(*value)))?;
+        }
+        let (callee_gas, caller_memory_size) =
+            (caller_values[0], caller_values[4] / 32);
+        self.caller_memory_size.assign(
+            region,
+            offset,
+            Some(F::from(caller_memory_size)),
+        )?;
+        self.caller_state_write_counter.assign(
+            region,
+            offset,
+            Some(F::from(caller_values[5])),
+        )?;
+        let caller_code_hash = if is_root {
+            [0u8; 32]
+        } else {
+            step.values[NUM_READS + 1 + NUM_CALLER_WRITES].to_word()
+        };
+        self.caller_code_hash
+            .assign(region, offset, Some(caller_code_hash))?;
+
+        // State transitions to the step of the caller following the call, or
+        // to the end of the tx
+        state.global_counter += NUM_READS;
+        if !is_root {
+            state.global_counter += NUM_CONTEXT_OPS_AFTER_COPY;
+            state.call_id = caller_id as usize;
+            state.program_counter = caller_values[1] as usize;
+            state.stack_pointer = caller_values[2] as usize;
+            state.gas_counter -= callee_gas;
+            state.gas_left += caller_values[3];
+            state.memory_size = caller_memory_size;
+            state.code_source = caller_code_hash;
+            state.state_write_counter += caller_values[5] as usize;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::test::test_circuit_from_block;
+    use bus_mapping::{
+        bytecode, bytecode::Bytecode,
+        circuit_input_builder::CircuitInputBuilder, mock,
+    };
+    use halo2::dev::MockProver;
+    use pairing::bn256::Fr as Fp;
+
+    fn test_stop_gadget(code: Bytecode, callee_code: Bytecode) {
+        let block =
+            mock::BlockData::new_single_tx_trace_code_2(&code, &callee_code)
+                .unwrap();
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let circuit = test_circuit_from_block::<Fp>(&builder.block);
+        let prover = MockProver::<Fp>::run(11, &circuit, vec![vec![]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    fn stop_gadget_from_trace() {
+        // The callee writes a storage slot and halts with STOP, which resumes
+        // the caller with the write added to its own ones, and the caller
+        // ends the tx with STOP
+        let code = bytecode! {
+            PUSH1(0x00) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x00) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH1(0x00) // value
+            PUSH2(0x123) // addr
+            PUSH2(0xffff) // gas
+            CALL
+            RETURNDATASIZE
+            POP
+            STOP
+        };
+        let callee_code = bytecode! {
+            PUSH1(0x01) // value
+            PUSH1(0x00) // key
+            SSTORE
+            STOP
+        };
+        test_stop_gadget(code, callee_code);
+    }
+}
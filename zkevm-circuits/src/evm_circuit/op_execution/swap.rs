@@ -14,7 +14,6 @@ use array_init::array_init;
 use bus_mapping::evm::{GasCost, OpcodeId};
 use halo2::plonk::Error;
 use halo2::{arithmetic::FieldExt, circuit::Region};
-use std::convert::TryInto;
 
 static STATE_TRANSITION: StateTransition = StateTransition {
     gc_delta: Some(4), // 2 stack reads + 2 stack writes
@@ -124,7 +123,8 @@ mod test {
         ($execution_steps:expr, $operations:expr, $result:expr) => {{
             let circuit =
                 TestCircuit::<Fp>::new($execution_steps, $operations, false);
-            let prover = MockProver::<Fp>::run(11, &circuit, vec![]).unwrap();
+            let prover =
+                MockProver::<Fp>::run(11, &circuit, vec![vec![]]).unwrap();
             assert_eq!(prover.verify(), $result);
         }};
     }
@@ -177,6 +177,8 @@ mod test {
                         Fp::from(1023),
                         Fp::from(1 + 2 + 3),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ],
                 },
                 Operation {
@@ -188,6 +190,8 @@ mod test {
                         Fp::from(1022),
                         Fp::from(4 + 5),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
                 Operation {
@@ -199,6 +203,8 @@ mod test {
                         Fp::from(1021),
                         Fp::from(6),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
                 // swap1 1021 <=> 1023
@@ -211,6 +217,8 @@ mod test {
                         Fp::from(1023),
                         Fp::from(1 + 2 + 3),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
                 Operation {
@@ -222,6 +230,8 @@ mod test {
                         Fp::from(1021),
                         Fp::from(6),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
                 Operation {
@@ -233,6 +243,8 @@ mod test {
                         Fp::from(1023),
                         Fp::from(6),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
                 Operation {
@@ -244,6 +256,8 @@ mod test {
                         Fp::from(1021),
                         Fp::from(1 + 2 + 3),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 }
             ],
@@ -291,6 +305,8 @@ mod test {
                         Fp::from(1023),
                         Fp::from(1 + 2 + 3),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ],
                 },
                 Operation {
@@ -302,6 +318,8 @@ mod test {
                         Fp::from(1022),
                         Fp::from(4 + 5),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
                 // swap1 1023 <=> 1022
@@ -314,6 +332,8 @@ mod test {
                         Fp::from(1023),
                         Fp::from(1 + 2 + 3),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
                 Operation {
@@ -325,6 +345,8 @@ mod test {
                         Fp::from(1022),
                         Fp::from(4 + 5),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
                 Operation {
@@ -336,6 +358,8 @@ mod test {
                         Fp::from(1023),
                         Fp::from(4 + 5),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 },
                 Operation {
@@ -347,6 +371,8 @@ mod test {
                         Fp::from(1022),
                         Fp::from(1 + 2 + 3),
                         Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
                     ]
                 }
             ],
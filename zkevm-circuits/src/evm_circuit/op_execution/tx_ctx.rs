@@ -0,0 +1,260 @@
+use super::super::{
+    Case, Cell, Constraint, ExecutionStep, TxContextField, Word,
+};
+use super::utils::{
+    self,
+    common_cases::{OutOfGasCase, StackOverflowCase},
+    constraint_builder::ConstraintBuilder,
+    from_bytes, sum, StateTransition,
+};
+use super::{
+    CaseAllocation, CaseConfig, CoreStateInstance, OpExecutionState, OpGadget,
+};
+use crate::impl_op_gadget;
+use crate::util::{Expr, ToWord};
+use bus_mapping::{
+    evm::{GasCost, OpcodeId},
+    operation::CallContextField,
+};
+use halo2::plonk::Error;
+use halo2::{arithmetic::FieldExt, circuit::Region};
+use num::ToPrimitive;
+
+static STATE_TRANSITION: StateTransition = StateTransition {
+    gc_delta: Some(2), // 1 call context read + 1 stack push
+    pc_delta: Some(1),
+    sp_delta: Some(-1),
+    gas_delta: Some(GasCost::QUICK.as_u64()),
+    next_memory_size: None,
+};
+const NUM_PUSHED: usize = 1;
+
+impl_op_gadget!(
+    #set[ORIGIN]
+    OriginGadget {
+        TxCtxSuccessCase(TxContextField::Origin),
+        StackOverflowCase(NUM_PUSHED),
+        OutOfGasCase(STATE_TRANSITION.gas_delta.unwrap()),
+    }
+);
+
+impl_op_gadget!(
+    #set[GASPRICE]
+    GaspriceGadget {
+        TxCtxSuccessCase(TxContextField::GasPrice),
+        StackOverflowCase(NUM_PUSHED),
+        OutOfGasCase(STATE_TRANSITION.gas_delta.unwrap()),
+    }
+);
+
+#[derive(Clone, Debug)]
+struct TxCtxSuccessCase<F> {
+    case_selector: Cell<F>,
+    // Field of the transaction pushed on the stack
+    field: TxContextField,
+    tx_id: Cell<F>,
+    value: Word<F>,
+}
+
+impl<F: FieldExt> TxCtxSuccessCase<F> {
+    pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::Success,
+        num_word: 1, // value
+        num_cell: 1, // tx_id
+        will_halt: false,
+    };
+
+    pub(crate) fn construct(
+        alloc: &mut CaseAllocation<F>,
+        field: TxContextField,
+    ) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+            field,
+            tx_id: alloc.cells.pop().unwrap(),
+            value: alloc.words.pop().unwrap(),
+        }
+    }
+
+    pub(crate) fn constraint(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        state_next: &OpExecutionState<F>,
+        name: &'static str,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::default();
+
+        // The tx table keeps the gas price, the value and the hashes as
+        // words, the addresses as their 20 bytes, and the other fields as
+        // numbers of at most 8 bytes
+        let value = match self.field {
+            TxContextField::Origin | TxContextField::CalleeAddress => {
+                cb.require_zero(sum::expr(&self.value.cells[20..]));
+                from_bytes::expr(self.value.cells[..20].to_vec())
+            }
+            TxContextField::GasPrice
+            | TxContextField::Value
+            | TxContextField::TxSignHash
+            | TxContextField::TxHash => self.value.expr(),
+            TxContextField::Nonce
+            | TxContextField::Gas
+            | TxContextField::IsCreate
+            | TxContextField::CallDataLength
+            | TxContextField::CallDataGasCost
+            | TxContextField::AccessListGasCost => {
+                cb.require_zero(sum::expr(&self.value.cells[8..]));
+                from_bytes::expr(self.value.cells[..8].to_vec())
+            }
+        };
+
+        // Read the id of the tx from the call context, look up the field of
+        // the tx and push it on the stack
+        cb.call_context_lookup(
+            false,
+            state_curr.call_id.expr(),
+            CallContextField::TxId,
+            self.tx_id.expr(),
+        );
+        cb.tx_context_lookup(self.tx_id.expr(), self.field, value);
+        cb.stack_push(self.value.expr());
+
+        // State transitions
+        STATE_TRANSITION.constraints(&mut cb, state_curr, state_next);
+
+        // Generate the constraint
+        vec![cb.constraint(self.case_selector.expr(), name)]
+    }
+
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: &mut CoreStateInstance,
+        step: &ExecutionStep,
+    ) -> Result<(), Error> {
+        // Output, followed by the tx id read from the call context
+        self.value
+            .assign(region, offset, Some(step.values[0].to_word()))?;
+        let tx_id = step.values[1].to_u64().ok_or(Error::Synthesis)?;
+        self.tx_id.assign(region, offset, Some(F::from(tx_id)))?;
+
+        // State transitions
+        STATE_TRANSITION.assign(state);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::{
+        test::{test_circuit_from_block, TestCircuit},
+        BlockContextField, Case, ExecutionStep, Operation,
+    };
+    use crate::{
+        gadget::evm_word::encode,
+        public_input_circuit::public_input::BlockTag,
+        tx_circuit::tx::{
+            tests::{from_hex, EIP155_TX},
+            SignedTx,
+        },
+    };
+    use bus_mapping::{
+        bytecode,
+        circuit_input_builder::CircuitInputBuilder,
+        evm::OpcodeId,
+        mock,
+        operation::{CallContextField, Target},
+    };
+    use halo2::dev::MockProver;
+    use num::BigUint;
+    use pairing::bn256::Fr as Fp;
+
+    #[test]
+    fn tx_ctx_gadget_from_trace() {
+        let code = bytecode! {
+            ORIGIN
+            GASPRICE
+            STOP
+        };
+        let block = mock::BlockData::new_single_tx_trace_code(&code).unwrap();
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+
+        let circuit = test_circuit_from_block::<Fp>(&builder.block);
+        let prover = MockProver::<Fp>::run(11, &circuit, vec![vec![]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    fn tx_ctx_gadget_with_signed_tx() {
+        // The origin of the EIP-155 example tx, which is the tx 1 of the tx
+        // table proven from the signed txs, and whose chain id is 1.  The
+        // block table has its hash, in the rlc of the circuit with r = 1.
+        let tx = SignedTx::decode(&from_hex(EIP155_TX)).unwrap();
+        let origin = tx.sender.0;
+        let tx_hash = encode(tx.hash().0.iter().cloned(), Fp::one());
+        for (chain_id, success) in [(1, true), (2, false)] {
+            let execution_steps = vec![ExecutionStep {
+                opcode: OpcodeId::ORIGIN,
+                case: Case::Success,
+                values: vec![
+                    BigUint::from_bytes_be(&origin),
+                    BigUint::from(1u64),
+                ],
+            }];
+            let operations = vec![
+                Operation {
+                    gc: 1,
+                    target: Target::CallContext,
+                    is_write: false,
+                    values: [
+                        Fp::zero(),
+                        Fp::from(CallContextField::TxId as u64),
+                        Fp::one(),
+                        Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
+                    ],
+                },
+                Operation {
+                    gc: 2,
+                    target: Target::Stack,
+                    is_write: true,
+                    values: [
+                        Fp::zero(),
+                        Fp::from(1023),
+                        Fp::from(
+                            origin.iter().map(|byte| *byte as u64).sum::<u64>(),
+                        ),
+                        Fp::zero(),
+                        Fp::zero(),
+                        Fp::zero(),
+                    ],
+                },
+            ];
+
+            let circuit =
+                TestCircuit::<Fp>::new(execution_steps, operations, false)
+                    .with_signed_txs(vec![tx.clone()], 1)
+                    .with_block_table(vec![
+                        [
+                            Fp::from(BlockContextField::ChainId as u64),
+                            Fp::zero(),
+                            Fp::from(chain_id),
+                        ],
+                        [
+                            Fp::from(BlockTag::TxHash.value()),
+                            Fp::one(),
+                            tx_hash,
+                        ],
+                    ]);
+            let prover =
+                MockProver::<Fp>::run(11, &circuit, vec![vec![]]).unwrap();
+            assert_eq!(prover.verify().is_ok(), success);
+        }
+    }
+}
@@ -30,6 +30,14 @@ pub(crate) struct StateTransitionExpressions<F> {
     pub pc_delta: Option<Expression<F>>,
     pub gas_delta: Option<Expression<F>>,
     pub next_memory_size: Option<Expression<F>>,
+    pub next_code_source: Option<Expression<F>>,
+    pub log_id_delta: Option<Expression<F>>,
+    // The gas left decreases by the gas used, unless the step switches to
+    // another call
+    pub next_gas_left: Option<Expression<F>>,
+    // The state write counter increases by the state writes of the step,
+    // unless the step switches to another call
+    pub next_state_write_counter: Option<Expression<F>>,
 }
 
 impl<F: FieldExt> StateTransitionExpressions<F> {
@@ -42,6 +50,10 @@ impl<F: FieldExt> StateTransitionExpressions<F> {
             next_memory_size: state_transition
                 .next_memory_size
                 .map(|v| v.expr()),
+            next_code_source: None,
+            log_id_delta: None,
+            next_gas_left: None,
+            next_state_write_counter: None,
         }
     }
 
@@ -82,6 +94,35 @@ impl<F: FieldExt> StateTransitionExpressions<F> {
                 .clone()
                 .unwrap_or_else(|| state_curr.memory_size.expr()),
         );
+        // Code source
+        cb.require_equal(
+            state_next.code_source.expr(),
+            self.next_code_source
+                .clone()
+                .unwrap_or_else(|| state_curr.code_source.expr()),
+        );
+        // Log id
+        cb.require_equal(
+            state_next.log_id.expr(),
+            state_curr.log_id.expr()
+                + self.log_id_delta.clone().unwrap_or_else(|| 0.expr()),
+        );
+        // Gas left
+        cb.require_equal(
+            state_next.gas_left.expr(),
+            self.next_gas_left.clone().unwrap_or_else(|| {
+                state_curr.gas_left.expr()
+                    - self.gas_delta.clone().unwrap_or_else(|| 0.expr())
+            }),
+        );
+        // State write counter
+        cb.require_equal(
+            state_next.state_write_counter.expr(),
+            self.next_state_write_counter.clone().unwrap_or_else(|| {
+                state_curr.state_write_counter.expr()
+                    + cb.state_write_counter_offset.clone()
+            }),
+        );
     }
 }
 
@@ -107,8 +148,9 @@ impl StateTransition {
         } else {
             state.stack_pointer += sp_delta as usize;
         }
-        // Gas Counter
+        // Gas Counter and gas left
         state.gas_counter += self.gas_delta.unwrap_or(0);
+        state.gas_left -= self.gas_delta.unwrap_or(0);
         // Memory size
         state.memory_size = self.next_memory_size.unwrap_or(state.memory_size);
     }
@@ -244,6 +286,64 @@ pub(crate) mod from_bytes {
     }
 }
 
+/// Splits a word into its four 64-bit limbs, least significant limb first
+/// Random linear combination of little-endian bytes, which is the RLC of the
+/// `Word` of these bytes, zero-extended.
+pub(crate) mod rlc {
+    use crate::{evm_circuit::Cell, util::Expr};
+    use halo2::{arithmetic::FieldExt, plonk::Expression};
+
+    pub(crate) fn expr<F: FieldExt>(bytes: &[Cell<F>], r: F) -> Expression<F> {
+        let mut value = 0.expr();
+        let mut multiplier = F::one();
+        for byte in bytes.iter() {
+            value = value + byte.expr() * multiplier;
+            multiplier *= r;
+        }
+        value
+    }
+
+    pub(crate) fn value<F: FieldExt>(bytes: &[u8], r: F) -> F {
+        bytes
+            .iter()
+            .rev()
+            .fold(F::zero(), |acc, byte| acc * r + F::from(*byte as u64))
+    }
+}
+
+pub(crate) mod word_limbs {
+    use super::from_bytes;
+    use crate::evm_circuit::Word;
+    use array_init::array_init;
+    use halo2::{arithmetic::FieldExt, plonk::Expression};
+    use std::convert::TryInto;
+
+    pub(crate) fn expr<F: FieldExt>(word: &Word<F>) -> [Expression<F>; 4] {
+        array_init(|idx| {
+            from_bytes::expr(word.cells[idx * 8..(idx + 1) * 8].to_vec())
+        })
+    }
+
+    pub(crate) fn value(word: [u8; 32]) -> [u64; 4] {
+        array_init(|idx| {
+            u64::from_le_bytes(word[idx * 8..(idx + 1) * 8].try_into().unwrap())
+        })
+    }
+}
+
+/// Returns the two's complement of a word, which is `2**256 - word` for a
+/// non-zero word and `0` otherwise.
+pub(crate) fn negate_word(word: [u8; 32]) -> [u8; 32] {
+    let mut negated = [0u8; 32];
+    let mut carry = true;
+    for (negated, byte) in negated.iter_mut().zip(word.iter()) {
+        let (value, overflow) = (!byte).overflowing_add(carry as u8);
+        *negated = value;
+        carry = overflow;
+    }
+    negated
+}
+
 /// Returns 2**num_bits
 pub(crate) fn get_range<F: FieldExt>(num_bits: usize) -> F {
     F::from(2).pow(&[num_bits as u64, 0, 0, 0])
@@ -313,7 +413,8 @@ macro_rules! impl_op_gadget {
                             mut [<$case:snake>],
                         )*
                     ]: [CaseAllocation<F>; crate::count!($($case)*)] =
-                        case_allocations.try_into().unwrap();
+                        std::convert::TryInto::try_into(case_allocations)
+                            .unwrap();
                     Self {
                         $(
                             [<$case:snake>]: $case::construct(
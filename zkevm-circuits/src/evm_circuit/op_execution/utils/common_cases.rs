@@ -1,18 +1,23 @@
 use super::super::{
     Case, CaseAllocation, CaseConfig, Cell, Constraint, CoreStateInstance,
-    ExecutionStep, OpExecutionState,
+    ExecutionStep, OpExecutionState, Word,
+};
+use super::math_gadgets::LtGadget;
+use super::memory_gadgets::MemoryRangeGadget;
+use super::{constraint_builder::ConstraintBuilder, from_bytes, select};
+use crate::evm_circuit::param::{MAX_GAS_SIZE_IN_BYTES, STACK_START_IDX};
+use crate::util::{Expr, ToWord};
+use bus_mapping::{
+    evm::{GasCost, OpcodeId},
+    operation::CallContextField,
 };
-use super::constraint_builder::ConstraintBuilder;
-use crate::evm_circuit::param::STACK_START_IDX;
-use crate::util::Expr;
-use bus_mapping::evm::OpcodeId;
 use halo2::plonk::Error;
-use halo2::{arithmetic::FieldExt, circuit::Region};
+use halo2::{arithmetic::FieldExt, circuit::Region, plonk::Expression};
+use std::convert::TryFrom;
 
 #[derive(Clone, Debug)]
 pub(crate) struct OutOfGasCase<F> {
     case_selector: Cell<F>,
-    gas_available: Cell<F>,
     gas_used: u64,
 }
 
@@ -30,12 +35,6 @@ impl<F: FieldExt> OutOfGasCase<F> {
     ) -> Self {
         Self {
             case_selector: alloc.selector.clone(),
-            gas_available: alloc
-                .resumption
-                .as_ref()
-                .unwrap()
-                .gas_available
-                .clone(),
             gas_used,
         }
     }
@@ -46,27 +45,32 @@ impl<F: FieldExt> OutOfGasCase<F> {
         _state_next: &OpExecutionState<F>,
         name: &'static str,
     ) -> Vec<Constraint<F>> {
-        let gas_overdemand = state_curr.gas_counter.expr()
-            + self.gas_used.expr()
-            - self.gas_available.expr();
-        let set = (1..=self.gas_used).map(|i| i.expr()).collect();
+        let gas_overdemand = self.gas_used.expr() - state_curr.gas_left.expr();
         let mut cb = ConstraintBuilder::default();
-        cb.require_in_set(gas_overdemand, set);
+        if self.gas_used <= 16 {
+            let set = (1..=self.gas_used).map(|i| i.expr()).collect();
+            cb.require_in_set(gas_overdemand, set);
+        } else {
+            // The set would exceed the maximum degree, so the overdemand minus
+            // one is checked to be below 32, and below `gas_used` once shifted
+            // by `32 - gas_used`.
+            assert!(self.gas_used <= 32);
+            cb.require_in_range(gas_overdemand.clone() - 1.expr(), 32);
+            cb.require_in_range(
+                gas_overdemand + (31 - self.gas_used).expr(),
+                32,
+            );
+        }
         vec![cb.constraint(self.case_selector.expr(), name)]
     }
 
     pub(crate) fn assign(
         &self,
-        region: &mut Region<'_, F>,
-        offset: usize,
-        state: &mut CoreStateInstance,
+        _region: &mut Region<'_, F>,
+        _offset: usize,
+        _state: &mut CoreStateInstance,
         _step: &ExecutionStep,
     ) -> Result<(), Error> {
-        self.gas_available.assign(
-            region,
-            offset,
-            Some(F::from(state.gas_counter as u64)),
-        )?;
         Ok(())
     }
 }
@@ -164,11 +168,17 @@ impl<F: FieldExt> RangeStackUnderflowCase<F> {
         //  `STACK_START_IDX <=
         //      state_curr.stack_pointer.expr() + stack_offset
         //          < STACK_START_IDX + range`
-        cb.require_in_range(
-            state_curr.stack_pointer.expr() + stack_offset
-                - STACK_START_IDX.expr(),
-            self.range,
-        );
+        let stack_index = state_curr.stack_pointer.expr() + stack_offset
+            - STACK_START_IDX.expr();
+        if self.range < 16 {
+            // Smaller ranges, such as the one of LOG0 to LOG4, have no table,
+            // so the index is checked to be below 16, and below `range` once
+            // shifted by `16 - range`.
+            cb.require_in_range(stack_index.clone(), 16);
+            cb.require_in_range(stack_index + (16 - self.range).expr(), 16);
+        } else {
+            cb.require_in_range(stack_index, self.range);
+        }
 
         // Generate the constraint
         vec![cb.constraint(self.case_selector.expr(), name)]
@@ -231,3 +241,231 @@ impl<F: FieldExt> StackOverflowCase<F> {
         Ok(())
     }
 }
+
+// The gas cost of accessing an account (EIP-2929)
+pub(crate) fn account_access_cost<F: FieldExt>(
+    is_warm: &Cell<F>,
+) -> Expression<F> {
+    select::expr(
+        is_warm.expr(),
+        GasCost::WARM_STORAGE_READ_COST.expr(),
+        GasCost::COLD_ACCOUNT_ACCESS_COST.expr(),
+    )
+}
+
+// The out of gas case of the opcodes popping an address and accessing its
+// account, such as BALANCE, EXTCODESIZE and EXTCODEHASH, which only read the
+// warm status of the account
+#[derive(Clone, Debug)]
+pub(crate) struct AccountAccessOutOfGasCase<F> {
+    case_selector: Cell<F>,
+    tx_id: Cell<F>,
+    is_warm: Cell<F>,
+    address: Word<F>,
+    insufficient_gas: LtGadget<F, MAX_GAS_SIZE_IN_BYTES>,
+}
+
+impl<F: FieldExt> AccountAccessOutOfGasCase<F> {
+    pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::OutOfGas,
+        num_word: 1, // address
+        num_cell: 2 // tx_id + is_warm
+            + LtGadget::<F, MAX_GAS_SIZE_IN_BYTES>::NUM_CELLS,
+        will_halt: true,
+    };
+
+    pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+            tx_id: alloc.cells.pop().unwrap(),
+            is_warm: alloc.cells.pop().unwrap(),
+            address: alloc.words.pop().unwrap(),
+            insufficient_gas: LtGadget::construct(alloc),
+        }
+    }
+
+    pub(crate) fn constraint(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        _state_next: &OpExecutionState<F>,
+        name: &'static str,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::default();
+
+        cb.call_context_lookup(
+            false,
+            state_curr.call_id.expr(),
+            CallContextField::TxId,
+            self.tx_id.expr(),
+        );
+        cb.require_boolean(self.is_warm.expr());
+
+        // Pop the address from the stack and read the warm status of the
+        // account
+        cb.stack_pop(self.address.expr());
+        cb.tx_access_list_account_read(
+            self.tx_id.expr(),
+            from_bytes::expr(self.address.cells[..20].to_vec()),
+            self.is_warm.expr(),
+        );
+
+        // Make sure the amount of gas available is less than the amount of
+        // gas required to access the account
+        let insufficient_gas = self.insufficient_gas.constraints(
+            &mut cb,
+            state_curr.gas_left.expr(),
+            account_access_cost(&self.is_warm),
+        );
+        cb.require_equal(insufficient_gas, 1.expr());
+
+        // Generate the constraint
+        vec![cb.constraint(self.case_selector.expr(), name)]
+    }
+
+    pub(crate) fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: &mut CoreStateInstance,
+        step: &ExecutionStep,
+    ) -> Result<(), Error> {
+        // Input, followed by the transaction id and the warm status of the
+        // account
+        self.address
+            .assign(region, offset, Some(step.values[0].to_word()))?;
+        let tx_id = u64::try_from(&step.values[1]).unwrap();
+        self.tx_id.assign(region, offset, Some(F::from(tx_id)))?;
+        let is_warm = step.values[2] == 1u64.into();
+        self.is_warm
+            .assign(region, offset, Some(F::from(is_warm as u64)))?;
+
+        // Gas insufficient check
+        let cost = if is_warm {
+            GasCost::WARM_STORAGE_READ_COST.as_u64()
+        } else {
+            GasCost::COLD_ACCOUNT_ACCESS_COST.as_u64()
+        };
+        self.insufficient_gas.assign(
+            region,
+            offset,
+            F::from(state.gas_left),
+            F::from(cost),
+        )?;
+
+        Ok(())
+    }
+}
+
+/// Out of gas case of the opcodes which pop a memory offset, a data offset and
+/// a length, and copy `length` bytes into the memory from `memory_offset` on,
+/// like CALLDATACOPY and RETURNDATACOPY.
+#[derive(Clone, Debug)]
+pub(crate) struct MemoryCopyOutOfGasCase<F> {
+    case_selector: Cell<F>,
+    memory_offset: Word<F>,
+    data_offset: Word<F>,
+    length: Word<F>,
+    memory_range: MemoryRangeGadget<F>,
+    insufficient_gas: LtGadget<F, MAX_GAS_SIZE_IN_BYTES>,
+}
+
+impl<F: FieldExt> MemoryCopyOutOfGasCase<F> {
+    pub(crate) const CASE_CONFIG: &'static CaseConfig = &CaseConfig {
+        case: Case::OutOfGas,
+        num_word: 3, // memory_offset + data_offset + length
+        num_cell: MemoryRangeGadget::<F>::NUM_CELLS
+            + LtGadget::<F, MAX_GAS_SIZE_IN_BYTES>::NUM_CELLS,
+        will_halt: true,
+    };
+
+    pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            case_selector: alloc.selector.clone(),
+            memory_offset: alloc.words.pop().unwrap(),
+            data_offset: alloc.words.pop().unwrap(),
+            length: alloc.words.pop().unwrap(),
+            memory_range: MemoryRangeGadget::construct(alloc),
+            insufficient_gas: LtGadget::construct(alloc),
+        }
+    }
+
+    pub(crate) fn constraint(
+        &self,
+        state_curr: &OpExecutionState<F>,
+        _state_next: &OpExecutionState<F>,
+        name: &'static str,
+    ) -> Vec<Constraint<F>> {
+        let mut cb = ConstraintBuilder::default();
+
+        // Pop the arguments from the stack
+        cb.stack_pop(self.memory_offset.expr());
+        cb.stack_pop(self.data_offset.expr());
+        cb.stack_pop(self.length.expr());
+
+        // Make sure the amount of gas available is less than the amount of
+        // gas required to expand the memory and copy the bytes, unless the
+        // bytes are copied out of the addressable memory, which never has
+        // enough gas
+        let (is_in_range, copy_words, memory_cost) =
+            self.memory_range.constraints(
+                &mut cb,
+                state_curr.memory_size.expr(),
+                &self.memory_offset,
+                &self.length,
+            );
+        let insufficient_gas = self.insufficient_gas.constraints(
+            &mut cb,
+            state_curr.gas_left.expr(),
+            GasCost::FASTEST.expr()
+                + GasCost::COPY.expr() * copy_words
+                + memory_cost,
+        );
+        cb.condition(is_in_range, |cb| {
+            cb.require_equal(insufficient_gas, 1.expr());
+        });
+
+        // Generate the constraint
+        vec![cb.constraint(self.case_selector.expr(), name)]
+    }
+
+    pub(crate) fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: &mut CoreStateInstance,
+        step: &ExecutionStep,
+    ) -> Result<(), Error> {
+        // Inputs
+        let memory_offset = step.values[0].to_word();
+        let length = step.values[2].to_word();
+        for (word, value) in [
+            (&self.memory_offset, memory_offset),
+            (&self.data_offset, step.values[1].to_word()),
+            (&self.length, length),
+        ] {
+            word.assign(region, offset, Some(value))?;
+        }
+
+        let (_, copy_words, memory_cost) = self.memory_range.assign(
+            region,
+            offset,
+            state.memory_size as u64,
+            memory_offset,
+            length,
+        )?;
+
+        // Gas insufficient check
+        self.insufficient_gas.assign(
+            region,
+            offset,
+            F::from(state.gas_left),
+            F::from(
+                GasCost::FASTEST.as_u64()
+                    + GasCost::COPY.as_u64() * copy_words
+                    + (memory_cost as u64),
+            ),
+        )?;
+
+        Ok(())
+    }
+}
@@ -1,5 +1,8 @@
-use super::super::super::{BusMappingLookup, Constraint, FixedLookup, Lookup};
+use super::super::super::{
+    BusMappingLookup, Constraint, FixedLookup, Lookup, TxContextField,
+};
 use crate::util::Expr;
+use bus_mapping::operation::{CallContextField, TxLogField};
 use halo2::{arithmetic::FieldExt, plonk::Expression};
 
 // Default max degree allowed in all expressions passing through the
@@ -14,6 +17,10 @@ pub struct ConstraintBuilder<F> {
     pub(crate) lookups: Vec<Lookup<F>>,
     pub stack_offset: i32,
     pub gc_offset: usize,
+    // Number of the state writes added, which can be undone by a reversion,
+    // where the ones added under a condition only count when it's 1
+    pub state_write_counter_offset: Expression<F>,
+    num_state_writes: usize,
     pub call_id: Option<Expression<F>>,
     pub max_degree: usize,
 }
@@ -37,6 +44,8 @@ impl<F: FieldExt> ConstraintBuilder<F> {
             lookups: vec![],
             stack_offset: 0,
             gc_offset: 0,
+            state_write_counter_offset: 0.expr(),
+            num_state_writes: 0,
             call_id,
             max_degree,
         }
@@ -69,6 +78,7 @@ impl<F: FieldExt> ConstraintBuilder<F> {
             16 => FixedLookup::Range16,
             17 => FixedLookup::Range17,
             32 => FixedLookup::Range32,
+            64 => FixedLookup::Range64,
             256 => FixedLookup::Range256,
             512 => FixedLookup::Range512,
             _ => unimplemented!(),
@@ -88,6 +98,47 @@ impl<F: FieldExt> ConstraintBuilder<F> {
         self.add_expression(expression);
     }
 
+    // Call context
+
+    pub(crate) fn call_context_lookup(
+        &mut self,
+        is_write: bool,
+        call_id: Expression<F>,
+        field: CallContextField,
+        value: Expression<F>,
+    ) {
+        self.call_context_lookup_at(
+            is_write,
+            call_id,
+            field,
+            value,
+            self.gc_offset.expr(),
+        );
+        self.gc_offset += 1;
+    }
+
+    pub(crate) fn call_context_lookup_at(
+        &mut self,
+        is_write: bool,
+        call_id: Expression<F>,
+        field: CallContextField,
+        value: Expression<F>,
+        gc_offset: Expression<F>,
+    ) {
+        self.validate_lookup_expression(&call_id);
+        self.validate_lookup_expression(&value);
+        self.validate_lookup_expression(&gc_offset);
+        self.add_lookup(Lookup::BusMappingLookup(
+            BusMappingLookup::CallContext {
+                is_write,
+                call_id,
+                field,
+                value,
+                gc_offset,
+            },
+        ));
+    }
+
     // Stack
 
     pub(crate) fn stack_pop(&mut self, value: Expression<F>) {
@@ -153,13 +204,32 @@ impl<F: FieldExt> ConstraintBuilder<F> {
         is_write: Expression<F>,
         gc_offset: Expression<F>,
     ) {
-        self.validate_lookup_expression(&self.call_id.clone().unwrap());
+        self.memory_lookup_with_call_id_at(
+            self.call_id.clone().unwrap(),
+            address,
+            byte,
+            is_write,
+            gc_offset,
+        );
+    }
+
+    // The memory of another call, like the caller's one where the calldata of
+    // an internal call is, is accessed with its call id.
+    pub(crate) fn memory_lookup_with_call_id_at(
+        &mut self,
+        call_id: Expression<F>,
+        address: Expression<F>,
+        byte: Expression<F>,
+        is_write: Expression<F>,
+        gc_offset: Expression<F>,
+    ) {
+        self.validate_lookup_expression(&call_id);
         self.validate_lookup_expression(&address);
         self.validate_lookup_expression(&byte);
         self.validate_lookup_expression(&is_write);
         self.validate_lookup_expression(&gc_offset);
         self.add_lookup(Lookup::BusMappingLookup(BusMappingLookup::Memory {
-            call_id: self.call_id.clone().unwrap(),
+            call_id,
             index: address,
             value: byte,
             is_write,
@@ -167,6 +237,409 @@ impl<F: FieldExt> ConstraintBuilder<F> {
         }));
     }
 
+    // Account storage
+
+    // The value of the slot at the beginning of the tx is the committed value
+    // of EIP-2200.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn account_storage_lookup(
+        &mut self,
+        is_write: bool,
+        address: Expression<F>,
+        key: Expression<F>,
+        value: Expression<F>,
+        value_prev: Expression<F>,
+        tx_id: Expression<F>,
+        committed_value: Expression<F>,
+    ) {
+        self.validate_lookup_expression(&address);
+        self.validate_lookup_expression(&key);
+        self.validate_lookup_expression(&value);
+        self.validate_lookup_expression(&value_prev);
+        self.validate_lookup_expression(&tx_id);
+        self.validate_lookup_expression(&committed_value);
+        self.add_lookup(Lookup::BusMappingLookup(
+            BusMappingLookup::AccountStorage {
+                is_write,
+                address,
+                location: key,
+                value,
+                value_prev,
+                tx_id,
+                committed_value,
+                gc_offset: self.gc_offset.expr(),
+            },
+        ));
+        self.gc_offset += 1;
+        if is_write {
+            self.add_state_write();
+        }
+    }
+
+    // Account
+
+    pub(crate) fn account_nonce_lookup(
+        &mut self,
+        is_write: bool,
+        address: Expression<F>,
+        value: Expression<F>,
+        value_prev: Expression<F>,
+    ) {
+        self.validate_lookup_expression(&address);
+        self.validate_lookup_expression(&value);
+        self.validate_lookup_expression(&value_prev);
+        self.add_lookup(Lookup::BusMappingLookup(
+            BusMappingLookup::AccountNonce {
+                is_write,
+                address,
+                value,
+                value_prev,
+                gc_offset: self.gc_offset.expr(),
+            },
+        ));
+        self.gc_offset += 1;
+        if is_write {
+            self.add_state_write();
+        }
+    }
+
+    pub(crate) fn account_balance_lookup(
+        &mut self,
+        is_write: bool,
+        address: Expression<F>,
+        value: Expression<F>,
+        value_prev: Expression<F>,
+    ) {
+        self.validate_lookup_expression(&address);
+        self.validate_lookup_expression(&value);
+        self.validate_lookup_expression(&value_prev);
+        self.add_lookup(Lookup::BusMappingLookup(
+            BusMappingLookup::AccountBalance {
+                is_write,
+                address,
+                value,
+                value_prev,
+                gc_offset: self.gc_offset.expr(),
+            },
+        ));
+        self.gc_offset += 1;
+        if is_write {
+            self.add_state_write();
+        }
+    }
+
+    pub(crate) fn account_code_hash_lookup(
+        &mut self,
+        is_write: bool,
+        address: Expression<F>,
+        value: Expression<F>,
+        value_prev: Expression<F>,
+    ) {
+        self.validate_lookup_expression(&address);
+        self.validate_lookup_expression(&value);
+        self.validate_lookup_expression(&value_prev);
+        self.add_lookup(Lookup::BusMappingLookup(
+            BusMappingLookup::AccountCodeHash {
+                is_write,
+                address,
+                value,
+                value_prev,
+                gc_offset: self.gc_offset.expr(),
+            },
+        ));
+        self.gc_offset += 1;
+        if is_write {
+            self.add_state_write();
+        }
+    }
+
+    // State
+
+    // Counts a state write in the state write counter of the call
+    fn add_state_write(&mut self) {
+        self.state_write_counter_offset =
+            self.state_write_counter_offset.clone() + 1.expr();
+        self.num_state_writes += 1;
+    }
+
+    // A write of any target of the state, whose keys and auxiliary values are
+    // in the columns of the rw table following the target.  It isn't counted
+    // as a state write, like the reversions it's used for.
+    pub(crate) fn state_write_lookup_at(
+        &mut self,
+        target: Expression<F>,
+        keys: [Expression<F>; 2],
+        value: Expression<F>,
+        value_prev: Expression<F>,
+        aux: [Expression<F>; 2],
+        gc_offset: Expression<F>,
+    ) {
+        for expr in [
+            &target,
+            &keys[0],
+            &keys[1],
+            &value,
+            &value_prev,
+            &aux[0],
+            &aux[1],
+            &gc_offset,
+        ] {
+            self.validate_lookup_expression(expr);
+        }
+        self.add_lookup(Lookup::BusMappingLookup(
+            BusMappingLookup::StateWrite {
+                target,
+                keys,
+                value,
+                value_prev,
+                aux,
+                gc_offset,
+            },
+        ));
+    }
+
+    // Tx
+
+    pub(crate) fn tx_access_list_account_write(
+        &mut self,
+        tx_id: Expression<F>,
+        address: Expression<F>,
+        value: Expression<F>,
+        value_prev: Expression<F>,
+    ) {
+        self.validate_lookup_expression(&tx_id);
+        self.validate_lookup_expression(&address);
+        self.validate_lookup_expression(&value);
+        self.validate_lookup_expression(&value_prev);
+        self.add_lookup(Lookup::BusMappingLookup(
+            BusMappingLookup::TxAccessListAccount {
+                is_write: true,
+                tx_id,
+                address,
+                value,
+                value_prev,
+                gc_offset: self.gc_offset.expr(),
+            },
+        ));
+        self.gc_offset += 1;
+        self.add_state_write();
+    }
+
+    pub(crate) fn tx_access_list_account_read(
+        &mut self,
+        tx_id: Expression<F>,
+        address: Expression<F>,
+        value: Expression<F>,
+    ) {
+        self.validate_lookup_expression(&tx_id);
+        self.validate_lookup_expression(&address);
+        self.validate_lookup_expression(&value);
+        self.add_lookup(Lookup::BusMappingLookup(
+            BusMappingLookup::TxAccessListAccount {
+                is_write: false,
+                tx_id,
+                address,
+                value: value.clone(),
+                value_prev: value,
+                gc_offset: self.gc_offset.expr(),
+            },
+        ));
+        self.gc_offset += 1;
+    }
+
+    pub(crate) fn tx_access_list_account_storage_write(
+        &mut self,
+        address: Expression<F>,
+        key: Expression<F>,
+        value: Expression<F>,
+        value_prev: Expression<F>,
+    ) {
+        self.validate_lookup_expression(&address);
+        self.validate_lookup_expression(&key);
+        self.validate_lookup_expression(&value);
+        self.validate_lookup_expression(&value_prev);
+        self.add_lookup(Lookup::BusMappingLookup(
+            BusMappingLookup::TxAccessListAccountStorage {
+                is_write: true,
+                address,
+                key,
+                value,
+                value_prev,
+                gc_offset: self.gc_offset.expr(),
+            },
+        ));
+        self.gc_offset += 1;
+        self.add_state_write();
+    }
+
+    pub(crate) fn tx_access_list_account_storage_read(
+        &mut self,
+        address: Expression<F>,
+        key: Expression<F>,
+        value: Expression<F>,
+    ) {
+        self.validate_lookup_expression(&address);
+        self.validate_lookup_expression(&key);
+        self.validate_lookup_expression(&value);
+        self.add_lookup(Lookup::BusMappingLookup(
+            BusMappingLookup::TxAccessListAccountStorage {
+                is_write: false,
+                address,
+                key,
+                value: value.clone(),
+                value_prev: value,
+                gc_offset: self.gc_offset.expr(),
+            },
+        ));
+        self.gc_offset += 1;
+    }
+
+    pub(crate) fn tx_refund_write(
+        &mut self,
+        value: Expression<F>,
+        value_prev: Expression<F>,
+    ) {
+        self.validate_lookup_expression(&value);
+        self.validate_lookup_expression(&value_prev);
+        self.add_lookup(Lookup::BusMappingLookup(BusMappingLookup::TxRefund {
+            is_write: true,
+            value,
+            value_prev,
+            gc_offset: self.gc_offset.expr(),
+        }));
+        self.gc_offset += 1;
+        self.add_state_write();
+    }
+
+    pub(crate) fn tx_refund_read(&mut self, value: Expression<F>) {
+        self.validate_lookup_expression(&value);
+        self.add_lookup(Lookup::BusMappingLookup(BusMappingLookup::TxRefund {
+            is_write: false,
+            value: value.clone(),
+            value_prev: value,
+            gc_offset: self.gc_offset.expr(),
+        }));
+        self.gc_offset += 1;
+    }
+
+    pub(crate) fn tx_log_write_at(
+        &mut self,
+        log_id: Expression<F>,
+        field: TxLogField,
+        index: Expression<F>,
+        value: Expression<F>,
+        gc_offset: Expression<F>,
+    ) {
+        self.validate_lookup_expression(&log_id);
+        self.validate_lookup_expression(&index);
+        self.validate_lookup_expression(&value);
+        self.validate_lookup_expression(&gc_offset);
+        self.add_lookup(Lookup::BusMappingLookup(BusMappingLookup::TxLog {
+            log_id,
+            field,
+            index,
+            value,
+            gc_offset,
+        }));
+    }
+
+    pub(crate) fn tx_calldata_lookup(
+        &mut self,
+        tx_id: Expression<F>,
+        index: Expression<F>,
+        value: Expression<F>,
+    ) {
+        self.validate_lookup_expression(&tx_id);
+        self.validate_lookup_expression(&index);
+        self.validate_lookup_expression(&value);
+        self.add_lookup(Lookup::BusMappingLookup(
+            BusMappingLookup::TxCalldata {
+                tx_id,
+                index,
+                value,
+            },
+        ));
+    }
+
+    pub(crate) fn tx_context_lookup(
+        &mut self,
+        tx_id: Expression<F>,
+        field: TxContextField,
+        value: Expression<F>,
+    ) {
+        self.validate_lookup_expression(&tx_id);
+        self.validate_lookup_expression(&value);
+        self.add_lookup(Lookup::BusMappingLookup(
+            BusMappingLookup::TxContext {
+                tx_id,
+                field,
+                value,
+            },
+        ));
+    }
+
+    pub(crate) fn block_lookup(
+        &mut self,
+        field: Expression<F>,
+        number: Expression<F>,
+        value: Expression<F>,
+    ) {
+        self.validate_lookup_expression(&field);
+        self.validate_lookup_expression(&number);
+        self.validate_lookup_expression(&value);
+        self.add_lookup(Lookup::BusMappingLookup(
+            BusMappingLookup::BlockContext {
+                field,
+                number,
+                value,
+            },
+        ));
+    }
+
+    // Condition
+
+    /// Applies `condition` to the expressions and lookups added in `build`,
+    /// so that they only need to hold when `condition == 1`, and so that the
+    /// state writes among them only count when `condition == 1`.
+    /// `condition` needs to be boolean.
+    pub(crate) fn condition<R>(
+        &mut self,
+        condition: Expression<F>,
+        build: impl FnOnce(&mut Self) -> R,
+    ) -> R {
+        let num_expressions = self.expressions.len();
+        let num_lookups = self.lookups.len();
+        let num_state_writes = self.num_state_writes;
+        let state_write_counter_offset =
+            self.state_write_counter_offset.clone();
+
+        // The degree of the condition is added to everything built
+        let degree = condition.degree();
+        self.validate_degree(degree);
+        self.max_degree -= degree;
+        let ret = build(self);
+        self.max_degree += degree;
+
+        for expression in self.expressions[num_expressions..].iter_mut() {
+            *expression = condition.clone() * expression.clone();
+        }
+        for lookup in self.lookups[num_lookups..].iter_mut() {
+            *lookup = Lookup::Conditional(
+                condition.clone(),
+                Box::new(lookup.clone()),
+            );
+        }
+        if self.num_state_writes > num_state_writes {
+            self.state_write_counter_offset = state_write_counter_offset
+                .clone()
+                + condition
+                    * (self.state_write_counter_offset.clone()
+                        - state_write_counter_offset);
+        }
+
+        ret
+    }
+
     // Validation
 
     pub(crate) fn validate_expression(&self, expression: &Expression<F>) {
@@ -223,6 +696,23 @@ impl<F: FieldExt> ConstraintBuilder<F> {
         self.add_lookup(Lookup::BytecodeLookup(expressions));
     }
 
+    pub(crate) fn add_exp_lookup(&mut self, expressions: [Expression<F>; 3]) {
+        for expression in expressions.iter() {
+            self.validate_lookup_expression(expression);
+        }
+        self.add_lookup(Lookup::ExpLookup(expressions));
+    }
+
+    pub(crate) fn add_keccak_lookup(
+        &mut self,
+        expressions: [Expression<F>; 3],
+    ) {
+        for expression in expressions.iter() {
+            self.validate_lookup_expression(expression);
+        }
+        self.add_lookup(Lookup::KeccakLookup(expressions));
+    }
+
     fn add_lookup(&mut self, lookup: Lookup<F>) {
         self.lookups.push(lookup);
     }
@@ -3,12 +3,13 @@ use super::super::Cell;
 use super::constraint_builder::ConstraintBuilder;
 use super::select;
 use super::sum;
-use super::{from_bytes, get_range};
-use crate::evm_circuit::param::MAX_BYTES_FIELD;
+use super::{from_bytes, get_range, negate_word, word_limbs};
+use crate::evm_circuit::{param::MAX_BYTES_FIELD, Word};
 use crate::util::Expr;
 use array_init::array_init;
 use halo2::plonk::Error;
 use halo2::{arithmetic::FieldExt, circuit::Region, plonk::Expression};
+use num::{BigInt, BigUint, One, Zero};
 
 /// Returns `1` when `value == 0`, and returns `0` otherwise.
 #[derive(Clone, Debug)]
@@ -94,6 +95,68 @@ impl<F: FieldExt> IsEqualGadget<F> {
     }
 }
 
+/// Returns `1` when the words `lhs == rhs`, and returns `0` otherwise.
+/// Both 128-bit halves are compared separately so that the result doesn't
+/// depend on the randomness used to compress the words.
+#[derive(Clone, Debug)]
+pub struct IsEqualWordGadget<F> {
+    is_eq_lo: IsEqualGadget<F>,
+    is_eq_hi: IsEqualGadget<F>,
+}
+
+impl<F: FieldExt> IsEqualWordGadget<F> {
+    pub const NUM_CELLS: usize = IsEqualGadget::<F>::NUM_CELLS * 2;
+    pub const NUM_WORDS: usize = 0;
+
+    pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            is_eq_lo: IsEqualGadget::<F>::construct(alloc),
+            is_eq_hi: IsEqualGadget::<F>::construct(alloc),
+        }
+    }
+
+    pub(crate) fn constraints(
+        &self,
+        cb: &mut ConstraintBuilder<F>,
+        lhs: &Word<F>,
+        rhs: &Word<F>,
+    ) -> Expression<F> {
+        let is_eq_lo = self.is_eq_lo.constraints(
+            cb,
+            from_bytes::expr(lhs.cells[0..16].to_vec()),
+            from_bytes::expr(rhs.cells[0..16].to_vec()),
+        );
+        let is_eq_hi = self.is_eq_hi.constraints(
+            cb,
+            from_bytes::expr(lhs.cells[16..32].to_vec()),
+            from_bytes::expr(rhs.cells[16..32].to_vec()),
+        );
+        is_eq_lo * is_eq_hi
+    }
+
+    pub(crate) fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        lhs: [u8; 32],
+        rhs: [u8; 32],
+    ) -> Result<F, Error> {
+        let is_eq_lo = self.is_eq_lo.assign(
+            region,
+            offset,
+            from_bytes::value(lhs[0..16].to_vec()),
+            from_bytes::value(rhs[0..16].to_vec()),
+        )?;
+        let is_eq_hi = self.is_eq_hi.assign(
+            region,
+            offset,
+            from_bytes::value(lhs[16..32].to_vec()),
+            from_bytes::value(rhs[16..32].to_vec()),
+        )?;
+        Ok(is_eq_lo * is_eq_hi)
+    }
+}
+
 /// Requires that the passed in value is within the specified range.
 /// `NUM_BYTES` is required to be `<= 31`.
 #[derive(Clone, Debug)]
@@ -451,3 +514,511 @@ impl<F: FieldExt, const NUM_BYTES: usize> MaxGadget<F, NUM_BYTES> {
         Ok(select::value(lt, rhs, lhs))
     }
 }
+
+/// Returns `1` when `lhs < rhs`, and returns `0` otherwise.
+/// lhs and rhs are 256-bit values passed in as four 64-bit limbs, least
+/// significant limb first. The comparison is done on the 128-bit halves:
+/// `lhs < rhs` when the high half is smaller, or when the high halves are
+/// equal and the low half is smaller.
+#[derive(Clone, Debug)]
+pub struct LtWordGadget<F> {
+    comparison_hi: ComparisonGadget<F, 16>,
+    lt_lo: LtGadget<F, 16>,
+}
+
+impl<F: FieldExt> LtWordGadget<F> {
+    pub const NUM_CELLS: usize =
+        ComparisonGadget::<F, 16>::NUM_CELLS + LtGadget::<F, 16>::NUM_CELLS;
+    pub const NUM_WORDS: usize = 0;
+
+    pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            comparison_hi: ComparisonGadget::construct(alloc),
+            lt_lo: LtGadget::construct(alloc),
+        }
+    }
+
+    pub(crate) fn constraints(
+        &self,
+        cb: &mut ConstraintBuilder<F>,
+        lhs: &[Expression<F>; 4],
+        rhs: &[Expression<F>; 4],
+    ) -> Expression<F> {
+        let half = |limbs: &[Expression<F>; 4], idx: usize| {
+            limbs[2 * idx].clone()
+                + limbs[2 * idx + 1].clone() * get_range::<F>(64)
+        };
+        let (lt_hi, eq_hi) =
+            self.comparison_hi
+                .constraints(cb, half(lhs, 1), half(rhs, 1));
+        let lt_lo = self.lt_lo.constraints(cb, half(lhs, 0), half(rhs, 0));
+        select::expr(lt_hi, 1.expr(), eq_hi * lt_lo)
+    }
+
+    pub(crate) fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        lhs: [u8; 32],
+        rhs: [u8; 32],
+    ) -> Result<F, Error> {
+        let (lt_hi, eq_hi) = self.comparison_hi.assign(
+            region,
+            offset,
+            from_bytes::value(lhs[16..32].to_vec()),
+            from_bytes::value(rhs[16..32].to_vec()),
+        )?;
+        let (lt_lo, _) = self.lt_lo.assign(
+            region,
+            offset,
+            from_bytes::value(lhs[0..16].to_vec()),
+            from_bytes::value(rhs[0..16].to_vec()),
+        )?;
+        Ok(select::value(lt_hi, F::one(), eq_hi * lt_lo))
+    }
+}
+
+/// Returns the four 64-bit limbs of `|x|`, with `x` a word that is
+/// interpreted as the two's complement of `|x|` when `is_neg == 1`, and as
+/// `|x|` itself otherwise. `is_neg` needs to be boolean.
+/// The negation is done per limb as `!x_i + carry_i`, with the carry into a
+/// limb only set when all the limbs below it are zero.
+#[derive(Clone, Debug)]
+pub struct AbsWordGadget<F> {
+    limb_is_zero: [IsZeroGadget<F>; 4],
+    abs: [Cell<F>; 4],
+}
+
+impl<F: FieldExt> AbsWordGadget<F> {
+    pub const NUM_CELLS: usize = 4 * IsZeroGadget::<F>::NUM_CELLS + 4;
+    pub const NUM_WORDS: usize = 0;
+
+    pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            limb_is_zero: array_init(|_| IsZeroGadget::construct(alloc)),
+            abs: array_init(|_| alloc.cells.pop().unwrap()),
+        }
+    }
+
+    pub(crate) fn constraints(
+        &self,
+        cb: &mut ConstraintBuilder<F>,
+        x: &Word<F>,
+        is_neg: Expression<F>,
+    ) -> [Expression<F>; 4] {
+        let range = get_range::<F>(64);
+        let mut carry = 1.expr();
+        for (idx, limb) in word_limbs::expr(x).iter().enumerate() {
+            let is_zero = self.limb_is_zero[idx].constraints(cb, limb.clone());
+            let carry_out = carry.clone() * is_zero;
+            // `!x_i + carry_in - carry_out * 2**64`
+            let negated = Expression::Constant(range - F::one()) - limb.clone()
+                + carry
+                - carry_out.clone() * range;
+            cb.require_equal(
+                self.abs[idx].expr(),
+                select::expr(is_neg.clone(), negated, limb.clone()),
+            );
+            carry = carry_out;
+        }
+        array_init(|idx| self.abs[idx].expr())
+    }
+
+    pub(crate) fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        x: [u8; 32],
+        is_neg: bool,
+    ) -> Result<[u8; 32], Error> {
+        for (is_zero, limb) in
+            self.limb_is_zero.iter().zip(word_limbs::value(x).iter())
+        {
+            is_zero.assign(region, offset, F::from(*limb))?;
+        }
+
+        let abs = if is_neg { negate_word(x) } else { x };
+        for (cell, limb) in self.abs.iter().zip(word_limbs::value(abs).iter()) {
+            cell.assign(region, offset, Some(F::from(*limb)))?;
+        }
+
+        Ok(abs)
+    }
+}
+
+/// Requires that `a * b + c == d (mod 2**256)` and returns `overflow`, which
+/// is `0` only when `a * b + c` also fits in 256 bits.
+/// All inputs are passed in as four 64-bit limbs, least significant limb
+/// first. With `t_k` the sum of all `a_i * b_j` where `i + j == k`:
+/// - `t_0 + t_1 * 2**64 + c_lo == d_lo + carry_lo * 2**128`
+/// - `t_2 + t_3 * 2**64 + c_hi + carry_lo == d_hi + carry_hi * 2**128`
+/// Because `t_k < 4 * 2**128` both carries fit in 9 bytes.
+/// The overflow is `carry_hi` plus all the `a_i * b_j` where `i + j > 3`.
+#[derive(Clone, Debug)]
+pub struct MulAddWordsGadget<F> {
+    carry_lo: [Cell<F>; 9],
+    carry_hi: [Cell<F>; 9],
+}
+
+impl<F: FieldExt> MulAddWordsGadget<F> {
+    pub const NUM_CELLS: usize = 9 + 9;
+    pub const NUM_WORDS: usize = 0;
+
+    pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            carry_lo: array_init(|_| alloc.cells.pop().unwrap()),
+            carry_hi: array_init(|_| alloc.cells.pop().unwrap()),
+        }
+    }
+
+    pub(crate) fn constraints(
+        &self,
+        cb: &mut ConstraintBuilder<F>,
+        a: &[Expression<F>; 4],
+        b: &[Expression<F>; 4],
+        c: &[Expression<F>; 4],
+        d: &[Expression<F>; 4],
+    ) -> Expression<F> {
+        let range_64 = get_range::<F>(64);
+        let range_128 = get_range::<F>(128);
+
+        let products = (0..4)
+            .map(|k| {
+                (0..=k).fold(0.expr(), |acc, i| {
+                    acc + a[i].clone() * b[k - i].clone()
+                })
+            })
+            .collect::<Vec<_>>();
+        let overflow_products = (1..4)
+            .flat_map(|i| ((4 - i)..4).map(move |j| (i, j)))
+            .fold(0.expr(), |acc, (i, j)| acc + a[i].clone() * b[j].clone());
+
+        // All parts of the carries need to be bytes
+        for byte in self.carry_lo.iter().chain(self.carry_hi.iter()) {
+            cb.require_in_range(byte.expr(), 256);
+        }
+        let carry_lo = from_bytes::expr(self.carry_lo.to_vec());
+        let carry_hi = from_bytes::expr(self.carry_hi.to_vec());
+
+        cb.require_equal(
+            products[0].clone()
+                + products[1].clone() * range_64
+                + c[0].clone()
+                + c[1].clone() * range_64,
+            d[0].clone()
+                + d[1].clone() * range_64
+                + carry_lo.clone() * range_128,
+        );
+        cb.require_equal(
+            products[2].clone()
+                + products[3].clone() * range_64
+                + c[2].clone()
+                + c[3].clone() * range_64
+                + carry_lo,
+            d[2].clone()
+                + d[3].clone() * range_64
+                + carry_hi.clone() * range_128,
+        );
+
+        carry_hi + overflow_products
+    }
+
+    pub(crate) fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        a: [u8; 32],
+        b: [u8; 32],
+        c: [u8; 32],
+    ) -> Result<(), Error> {
+        let [a, b, c] = [a, b, c].map(word_limbs::value);
+
+        let products = (0..4)
+            .map(|k| {
+                (0..=k)
+                    .map(|i| BigUint::from(a[i]) * b[k - i])
+                    .sum::<BigUint>()
+            })
+            .collect::<Vec<_>>();
+        let carry_lo: BigUint = (&products[0]
+            + (&products[1] << 64)
+            + BigUint::from(c[0])
+            + (BigUint::from(c[1]) << 64))
+            >> 128;
+        let carry_hi: BigUint = (&products[2]
+            + (&products[3] << 64)
+            + BigUint::from(c[2])
+            + (BigUint::from(c[3]) << 64)
+            + &carry_lo)
+            >> 128;
+
+        for (cells, carry) in
+            [(&self.carry_lo, carry_lo), (&self.carry_hi, carry_hi)]
+        {
+            let bytes = carry.to_bytes_le();
+            for (idx, cell) in cells.iter().enumerate() {
+                cell.assign(
+                    region,
+                    offset,
+                    Some(F::from(*bytes.get(idx).unwrap_or(&0) as u64)),
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Requires that `a * b + c == d * e + f` holds over the integers, so no
+/// overflow of either side is thrown away.
+/// All inputs are passed in as four 64-bit limbs, least significant limb
+/// first, and are grouped as `lhs = [a, b, c]` and `rhs = [d, e, f]`.
+/// Both sides are split in four 128-bit chunks which are compared one by one
+/// from the least significant chunk on:
+/// - `lhs_m - rhs_m + carry_(m-1) == carry_m * 2**128` for `m` in `0..3`
+/// - `lhs_3 - rhs_3 + carry_2 == 0`
+/// Each chunk is less than `2**195` so the carries, which can be negative,
+/// are stored in 9 bytes with an offset of `2**71`.
+#[derive(Clone, Debug)]
+pub struct MulAddWords512Gadget<F> {
+    carries: [[Cell<F>; 9]; 3],
+}
+
+impl<F: FieldExt> MulAddWords512Gadget<F> {
+    pub const NUM_CELLS: usize = 3 * 9;
+    pub const NUM_WORDS: usize = 0;
+
+    pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            carries: array_init(|_| array_init(|_| alloc.cells.pop().unwrap())),
+        }
+    }
+
+    pub(crate) fn constraints(
+        &self,
+        cb: &mut ConstraintBuilder<F>,
+        lhs: [&[Expression<F>; 4]; 3],
+        rhs: [&[Expression<F>; 4]; 3],
+    ) {
+        let range_64 = get_range::<F>(64);
+        let range_128 = get_range::<F>(128);
+        let offset = get_range::<F>(71);
+
+        // 128-bit chunks of `x * y + z`
+        let chunks = |[x, y, z]: [&[Expression<F>; 4]; 3]| {
+            let products = (0..8)
+                .map(|k| {
+                    (0..4)
+                        .filter(|i| k >= *i && k - i < 4)
+                        .fold(0.expr(), |acc, i| {
+                            acc + x[i].clone() * y[k - i].clone()
+                        })
+                })
+                .collect::<Vec<_>>();
+            (0..4)
+                .map(|m| {
+                    let chunk = products[2 * m].clone()
+                        + products[2 * m + 1].clone() * range_64;
+                    if m < 2 {
+                        chunk
+                            + z[2 * m].clone()
+                            + z[2 * m + 1].clone() * range_64
+                    } else {
+                        chunk
+                    }
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // All parts of the carries need to be bytes
+        for byte in self.carries.iter().flatten() {
+            cb.require_in_range(byte.expr(), 256);
+        }
+        let carries = self
+            .carries
+            .iter()
+            .map(|bytes| {
+                from_bytes::expr(bytes.to_vec()) - Expression::Constant(offset)
+            })
+            .collect::<Vec<_>>();
+
+        let mut carry = 0.expr();
+        for (m, (lhs, rhs)) in
+            chunks(lhs).into_iter().zip(chunks(rhs)).enumerate()
+        {
+            let carry_out = carries.get(m).cloned().unwrap_or_else(|| 0.expr());
+            cb.require_equal(lhs + carry, rhs + carry_out.clone() * range_128);
+            carry = carry_out;
+        }
+    }
+
+    pub(crate) fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        lhs: [[u8; 32]; 3],
+        rhs: [[u8; 32]; 3],
+    ) -> Result<(), Error> {
+        // 128-bit chunks of `x * y + z`
+        let chunks = |[x, y, z]: [[u8; 32]; 3]| {
+            let [x, y, z] = [x, y, z].map(word_limbs::value);
+            let products = (0..8)
+                .map(|k| {
+                    (0..4)
+                        .filter(|i| k >= *i && k - i < 4)
+                        .map(|i| BigUint::from(x[i]) * y[k - i])
+                        .sum::<BigUint>()
+                })
+                .collect::<Vec<_>>();
+            (0..4)
+                .map(|m| {
+                    let chunk = &products[2 * m] + (&products[2 * m + 1] << 64);
+                    if m < 2 {
+                        chunk
+                            + BigUint::from(z[2 * m])
+                            + (BigUint::from(z[2 * m + 1]) << 64)
+                    } else {
+                        chunk
+                    }
+                })
+                .map(BigInt::from)
+                .collect::<Vec<_>>()
+        };
+
+        let mut carry = BigInt::zero();
+        for (cells, (lhs, rhs)) in self
+            .carries
+            .iter()
+            .zip(chunks(lhs).into_iter().zip(chunks(rhs)))
+        {
+            carry = (lhs - rhs + carry) >> 128;
+            let bias: BigInt = BigInt::one() << 71;
+            let bytes = (&carry + bias)
+                .to_biguint()
+                .unwrap()
+                .to_bytes_le();
+            for (idx, cell) in cells.iter().enumerate() {
+                cell.assign(
+                    region,
+                    offset,
+                    Some(F::from(*bytes.get(idx).unwrap_or(&0) as u64)),
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the number of bytes needed to represent `word`, which is the
+/// position of its most significant non-zero byte plus one, and `0` when
+/// `word == 0`.
+/// The most significant byte is selected by a flag, all bytes above it need
+/// to be zero and the byte itself needs to be non-zero.
+#[derive(Clone, Debug)]
+pub struct ByteSizeGadget<F> {
+    most_significant_byte: [Cell<F>; 32],
+    most_significant_byte_inverse: Cell<F>,
+}
+
+impl<F: FieldExt> ByteSizeGadget<F> {
+    pub const NUM_CELLS: usize = 32 + 1;
+    pub const NUM_WORDS: usize = 0;
+
+    pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            most_significant_byte: array_init(|_| alloc.cells.pop().unwrap()),
+            most_significant_byte_inverse: alloc.cells.pop().unwrap(),
+        }
+    }
+
+    pub(crate) fn constraints(
+        &self,
+        cb: &mut ConstraintBuilder<F>,
+        word: &Word<F>,
+    ) -> Expression<F> {
+        // At most a single byte is flagged
+        for flag in self.most_significant_byte.iter() {
+            cb.require_boolean(flag.expr());
+        }
+        let is_non_zero = sum::expr(&self.most_significant_byte);
+        cb.require_boolean(is_non_zero.clone());
+
+        // A byte can only be non-zero when it's flagged or below the
+        // flagged byte
+        let mut at_or_above = 0.expr();
+        for (flag, byte) in self
+            .most_significant_byte
+            .iter()
+            .zip(word.cells.iter())
+            .rev()
+        {
+            at_or_above = at_or_above + flag.expr();
+            cb.require_zero(byte.expr() * (1.expr() - at_or_above.clone()));
+        }
+
+        // The flagged byte needs to be non-zero
+        let most_significant_byte = self
+            .most_significant_byte
+            .iter()
+            .zip(word.cells.iter())
+            .fold(0.expr(), |acc, (flag, byte)| {
+                acc + flag.expr() * byte.expr()
+            });
+        cb.require_equal(
+            most_significant_byte * self.most_significant_byte_inverse.expr(),
+            is_non_zero,
+        );
+
+        self.most_significant_byte
+            .iter()
+            .enumerate()
+            .fold(0.expr(), |acc, (idx, flag)| {
+                acc + flag.expr() * (idx + 1).expr()
+            })
+    }
+
+    /// Returns `base` to the power of the byte size, which is `1` when
+    /// `word == 0`.
+    pub(crate) fn pow_expr(&self, base: F) -> Expression<F> {
+        let mut power = F::one();
+        self.most_significant_byte.iter().fold(
+            1.expr() - sum::expr(&self.most_significant_byte),
+            |acc, flag| {
+                power *= base;
+                acc + flag.expr() * power
+            },
+        )
+    }
+
+    pub(crate) fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        word: [u8; 32],
+    ) -> Result<u64, Error> {
+        let byte_size = word
+            .iter()
+            .rposition(|byte| *byte != 0)
+            .map_or(0, |idx| idx + 1);
+
+        for (idx, flag) in self.most_significant_byte.iter().enumerate() {
+            flag.assign(
+                region,
+                offset,
+                Some(F::from((idx + 1 == byte_size) as u64)),
+            )?;
+        }
+        self.most_significant_byte_inverse.assign(
+            region,
+            offset,
+            Some(if byte_size == 0 {
+                F::zero()
+            } else {
+                F::from(word[byte_size - 1] as u64).invert().unwrap()
+            }),
+        )?;
+
+        Ok(byte_size as u64)
+    }
+}
@@ -1,10 +1,15 @@
-use super::super::{CaseAllocation, Word};
+use super::super::{CaseAllocation, Cell, Word};
 use super::constraint_builder::ConstraintBuilder;
-use super::math_gadgets::{ConstantDivisionGadget, MaxGadget};
-use super::{Address, MemorySize};
-use crate::evm_circuit::param::MAX_MEMORY_SIZE_IN_BYTES;
+use super::math_gadgets::{
+    ConstantDivisionGadget, IsZeroGadget, LtGadget, MaxGadget,
+};
+use super::{select, sum, Address, MemorySize};
+use crate::evm_circuit::param::{
+    MAX_GAS_SIZE_IN_BYTES, MAX_MEMORY_SIZE_IN_BYTES, NUM_ADDRESS_BYTES_USED,
+};
 use crate::util::Expr;
-use bus_mapping::evm::GasCost;
+use array_init::array_init;
+use bus_mapping::evm::{memory_expansion, GasCost};
 use halo2::plonk::Error;
 use halo2::{arithmetic::FieldExt, circuit::Region, plonk::Expression};
 
@@ -200,35 +205,276 @@ impl<F: FieldExt, const MAX_QUAD_COST_IN_BYTES: usize>
         let address_memory_size =
             self.address_memory_size.assign(region, offset, address)?;
 
-        // Calculate the next memory size
-        let next_memory_size = self
-            .next_memory_size
-            .assign(
-                region,
-                offset,
-                F::from(address_memory_size),
-                F::from(curr_memory_size),
-            )?
-            .get_lower_128() as MemorySize;
+        // The next memory size and the gas cost for the expansion are
+        // computed as in bus-mapping
+        let (next_memory_size, memory_cost) =
+            memory_expansion(curr_memory_size, address);
+
+        // Assign the next memory size
+        self.next_memory_size.assign(
+            region,
+            offset,
+            F::from(address_memory_size),
+            F::from(curr_memory_size),
+        )?;
 
-        // Calculate the quad gas cost for the memory size
-        let (curr_quad_memory_cost, _) = self.curr_quad_memory_cost.assign(
+        // Assign the quad gas cost for the memory size
+        self.curr_quad_memory_cost.assign(
             region,
             offset,
             (curr_memory_size as u128) * (curr_memory_size as u128),
         )?;
-        let (next_quad_memory_cost, _) = self.next_quad_memory_cost.assign(
+        self.next_quad_memory_cost.assign(
             region,
             offset,
             (next_memory_size as u128) * (next_memory_size as u128),
         )?;
 
-        // Calculate the gas cost for the expansian
-        let memory_cost = (next_memory_size - curr_memory_size) as u128
-            * (Self::GAS_MEM.as_u64() as u128)
-            + (next_quad_memory_cost - curr_quad_memory_cost);
-
         // Return the new memory size and the memory expansion gas cost
         Ok((next_memory_size, memory_cost))
     }
 }
+
+/// Returns (is in range, number of words, memory gas cost) for an access to
+/// the memory range given by an offset and a length.
+/// A range is in range when it's empty or when the offset and the length only
+/// use the lower `NUM_ADDRESS_BYTES_USED` bytes.  Accessing any other range
+/// always results in an out-of-gas error, so its number of words and memory gas
+/// cost are returned as 0.
+#[derive(Clone, Debug)]
+pub(crate) struct MemoryRangeGadget<F> {
+    length_is_zero: IsZeroGadget<F>,
+    address_high_is_zero: IsZeroGadget<F>,
+    num_words: MemorySizeGadget<F>,
+    memory_expansion: MemoryExpansionGadget<F, MAX_GAS_SIZE_IN_BYTES>,
+}
+
+impl<F: FieldExt> MemoryRangeGadget<F> {
+    pub const NUM_CELLS: usize = IsZeroGadget::<F>::NUM_CELLS * 2
+        + MemorySizeGadget::<F>::NUM_CELLS
+        + MemoryExpansionGadget::<F, MAX_GAS_SIZE_IN_BYTES>::NUM_CELLS;
+    pub const NUM_WORDS: usize = IsZeroGadget::<F>::NUM_WORDS * 2
+        + MemorySizeGadget::<F>::NUM_WORDS
+        + MemoryExpansionGadget::<F, MAX_GAS_SIZE_IN_BYTES>::NUM_WORDS;
+
+    pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            length_is_zero: IsZeroGadget::construct(alloc),
+            address_high_is_zero: IsZeroGadget::construct(alloc),
+            num_words: MemorySizeGadget::construct(alloc),
+            memory_expansion: MemoryExpansionGadget::construct(alloc),
+        }
+    }
+
+    pub(crate) fn constraints(
+        &self,
+        cb: &mut ConstraintBuilder<F>,
+        curr_memory_size: Expression<F>,
+        memory_offset: &Word<F>,
+        length: &Word<F>,
+    ) -> (Expression<F>, Expression<F>, Expression<F>) {
+        let length_is_zero = self
+            .length_is_zero
+            .constraints(cb, sum::expr(&length.cells));
+        let address_high_is_zero = self.address_high_is_zero.constraints(
+            cb,
+            address_high::expr(memory_offset) + address_high::expr(length),
+        );
+        let is_in_range = length_is_zero.clone() + address_high_is_zero.clone()
+            - length_is_zero.clone() * address_high_is_zero.clone();
+
+        // The length and the address of the end of the range, which are 0 for
+        // the ranges out of the addressable memory
+        let length = address_high_is_zero.clone() * address_low::expr(length);
+        let address = (1.expr() - length_is_zero)
+            * (address_high_is_zero * address_low::expr(memory_offset)
+                + length.clone());
+
+        let num_words = self.num_words.constraints(cb, length);
+        let (_, memory_cost) =
+            self.memory_expansion
+                .constraints(cb, curr_memory_size, address);
+
+        (is_in_range, num_words, memory_cost)
+    }
+
+    pub(crate) fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        curr_memory_size: MemorySize,
+        memory_offset: [u8; 32],
+        length: [u8; 32],
+    ) -> Result<(bool, MemorySize, u128), Error> {
+        let length_is_zero =
+            self.length_is_zero
+                .assign(region, offset, sum::value(&length))?;
+        let address_high_is_zero = self.address_high_is_zero.assign(
+            region,
+            offset,
+            address_high::value::<F>(memory_offset)
+                + address_high::value::<F>(length),
+        )?;
+        let is_in_range =
+            length_is_zero == F::one() || address_high_is_zero == F::one();
+
+        let length = if address_high_is_zero == F::one() {
+            address_low::value::<F>(length)
+        } else {
+            0
+        };
+        let address = if length == 0 {
+            0
+        } else {
+            address_low::value::<F>(memory_offset) + length
+        };
+
+        let num_words = self.num_words.assign(region, offset, length)?;
+        let (_, memory_cost) = self.memory_expansion.assign(
+            region,
+            offset,
+            curr_memory_size,
+            address,
+        )?;
+
+        Ok((is_in_range, num_words, memory_cost))
+    }
+}
+
+/// Reads `num_bytes` bytes from `buffer_offset` of a buffer of
+/// `buffer_length` bytes, like CALLDATALOAD and CALLDATACOPY read the calldata,
+/// where the bytes past the end of the buffer are read as zeros.
+/// Returns a flag for each of the `MAX_BYTES` bytes, which is `1` for the
+/// bytes within the buffer that need to be looked up, and requires all the
+/// other bytes to be zero.
+/// Input requirements:
+/// - `num_bytes <= MAX_BYTES`
+/// - `buffer_length < 256**NUM_ADDRESS_BYTES_USED`
+#[derive(Clone, Debug)]
+pub(crate) struct BufferReaderGadget<F, const MAX_BYTES: usize> {
+    offset_in_range: IsZeroGadget<F>,
+    offset_lt_length: LtGadget<F, NUM_ADDRESS_BYTES_USED>,
+    num_bytes_left_lt_num_bytes: LtGadget<F, NUM_ADDRESS_BYTES_USED>,
+    read_flags: [Cell<F>; MAX_BYTES],
+}
+
+impl<F: FieldExt, const MAX_BYTES: usize> BufferReaderGadget<F, MAX_BYTES> {
+    pub const NUM_CELLS: usize = IsZeroGadget::<F>::NUM_CELLS
+        + LtGadget::<F, NUM_ADDRESS_BYTES_USED>::NUM_CELLS * 2
+        + MAX_BYTES;
+    pub const NUM_WORDS: usize = IsZeroGadget::<F>::NUM_WORDS
+        + LtGadget::<F, NUM_ADDRESS_BYTES_USED>::NUM_WORDS * 2;
+
+    pub(crate) fn construct(alloc: &mut CaseAllocation<F>) -> Self {
+        Self {
+            offset_in_range: IsZeroGadget::construct(alloc),
+            offset_lt_length: LtGadget::construct(alloc),
+            num_bytes_left_lt_num_bytes: LtGadget::construct(alloc),
+            read_flags: array_init(|_| alloc.cells.pop().unwrap()),
+        }
+    }
+
+    pub(crate) fn constraints(
+        &self,
+        cb: &mut ConstraintBuilder<F>,
+        buffer_offset: &Word<F>,
+        buffer_length: Expression<F>,
+        num_bytes: Expression<F>,
+        bytes: &[Expression<F>],
+    ) -> Vec<Expression<F>> {
+        assert_eq!(bytes.len(), MAX_BYTES);
+
+        // The number of bytes left in the buffer from the offset, which is
+        // zero when the offset is past its end
+        let offset_in_range = self
+            .offset_in_range
+            .constraints(cb, address_high::expr(buffer_offset));
+        let offset = address_low::expr(buffer_offset);
+        let offset_lt_length = self.offset_lt_length.constraints(
+            cb,
+            offset.clone(),
+            buffer_length.clone(),
+        );
+        let num_bytes_left =
+            offset_in_range * offset_lt_length * (buffer_length - offset);
+
+        // num_bytes_read == min(num_bytes_left, num_bytes)
+        let num_bytes_left_lt_num_bytes = self
+            .num_bytes_left_lt_num_bytes
+            .constraints(cb, num_bytes_left.clone(), num_bytes.clone());
+        let num_bytes_read = select::expr(
+            num_bytes_left_lt_num_bytes,
+            num_bytes_left,
+            num_bytes,
+        );
+
+        // The flags are set for the first `num_bytes_read` bytes, and the
+        // bytes that are not read are zeros
+        for (idx, (read_flag, byte)) in
+            self.read_flags.iter().zip(bytes.iter()).enumerate()
+        {
+            cb.require_boolean(read_flag.expr());
+            if idx > 0 {
+                cb.require_zero(
+                    read_flag.expr()
+                        * (1.expr() - self.read_flags[idx - 1].expr()),
+                );
+            }
+            cb.require_zero((1.expr() - read_flag.expr()) * byte.clone());
+        }
+        cb.require_equal(self.num_bytes_read(), num_bytes_read);
+
+        self.read_flags.iter().map(|flag| flag.expr()).collect()
+    }
+
+    pub(crate) fn num_bytes_read(&self) -> Expression<F> {
+        sum::expr(&self.read_flags)
+    }
+
+    /// Returns the number of bytes read from the buffer.
+    pub(crate) fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        buffer_offset: [u8; 32],
+        buffer_length: u64,
+        num_bytes: u64,
+    ) -> Result<u64, Error> {
+        let offset_in_range = self.offset_in_range.assign(
+            region,
+            offset,
+            address_high::value::<F>(buffer_offset),
+        )?;
+        let buffer_offset = address_low::value::<F>(buffer_offset);
+        let (offset_lt_length, _) = self.offset_lt_length.assign(
+            region,
+            offset,
+            F::from(buffer_offset),
+            F::from(buffer_length),
+        )?;
+        let num_bytes_left =
+            if offset_in_range == F::one() && offset_lt_length == F::one() {
+                buffer_length - buffer_offset
+            } else {
+                0
+            };
+
+        self.num_bytes_left_lt_num_bytes.assign(
+            region,
+            offset,
+            F::from(num_bytes_left),
+            F::from(num_bytes),
+        )?;
+        let num_bytes_read = num_bytes_left.min(num_bytes);
+
+        for (idx, read_flag) in self.read_flags.iter().enumerate() {
+            read_flag.assign(
+                region,
+                offset,
+                Some(F::from(((idx as u64) < num_bytes_read) as u64)),
+            )?;
+        }
+
+        Ok(num_bytes_read)
+    }
+}
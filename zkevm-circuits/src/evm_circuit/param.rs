@@ -2,14 +2,20 @@
 // Circuit dimension
 // NOTE: CIRCUIT_WIDTH should be divide 32 evenly for whole row byte lookup
 pub const CIRCUIT_WIDTH: usize = 32;
-pub const CIRCUIT_HEIGHT: usize = 10;
+// NOTE: A step needs to fit its largest case on top of the 93 cells of the
+// execution state, the gadget selectors and the resumption, with each word on
+// a row of its own. A height of 10 only leaves 7 rows for words, while EXP
+// fits in it, ADDMOD and MULMOD need 7 words and 82 cells, and CALL needs 9
+// words and 96 cells. A height of 16 fits them, at the cost of 6 more rows
+// per step.
+pub const CIRCUIT_HEIGHT: usize = 16;
 
 // Number of cells used for each purpose
 // TODO: pub const NUM_CELL_CALL_INITIALIZATION_STATE: usize = ;
-pub const NUM_CELL_OP_EXECUTION_STATE: usize = 8;
+pub const NUM_CELL_OP_EXECUTION_STATE: usize = 12;
 // FIXME: naive estimation, should be optmize to fit in the future
 pub const NUM_CELL_OP_GADGET_SELECTOR: usize = 80;
-pub const NUM_CELL_RESUMPTION: usize = 2;
+pub const NUM_CELL_RESUMPTION: usize = 1;
 
 /// The maximum number of bytes that a field element
 /// can be broken down into without causing the value it
@@ -23,3 +29,9 @@ pub const MAX_GAS_SIZE_IN_BYTES: usize = 8;
 // always result in an out-of-gas error.
 pub const NUM_ADDRESS_BYTES_USED: usize = 5;
 pub const MAX_MEMORY_SIZE_IN_BYTES: usize = 5;
+// Maximum number of bytes copied into memory in a single step.
+// TODO: Split larger copies over multiple steps.
+pub const MAX_COPY_BYTES: usize = 32;
+// Maximum number of state writes undone by a REVERT in a single step.
+// TODO: Split larger reversions over multiple steps.
+pub const MAX_REVERTED_WRITES: usize = 8;
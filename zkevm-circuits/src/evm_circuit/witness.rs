@@ -0,0 +1,1161 @@
+use super::{
+    param::MAX_COPY_BYTES, BlockContextField, Case, ExecutionStep, Operation,
+    TxContextField, TxExecutionStep,
+};
+use crate::{
+    bytecode_circuit::bytecode, gadget::evm_word::encode,
+    public_input_circuit::PublicInputError, util::ToWord,
+};
+use bus_mapping::{
+    circuit_input_builder::{Block, ExecError},
+    eth_types::{Address, ToAddress, Word},
+    evm::{GasCost, GlobalCounter, OpcodeId},
+    operation::{CallContextField, Target, TxLogField},
+};
+use ethers_core::utils::{keccak256, rlp::RlpStream};
+use halo2::{arithmetic::FieldExt, plonk::Error};
+use num::{BigUint, ToPrimitive};
+use std::{collections::HashSet, iter};
+
+/// Error returned when the witness of the EVM circuit can't be built from a
+/// block.
+#[derive(Debug, PartialEq, Eq)]
+pub enum WitnessError {
+    /// The operations or the calls of the tx of the given index don't match
+    /// its steps.
+    InconsistentTrace(usize),
+    /// The tx of the given index doesn't halt at its last step, or halts a
+    /// call with SELFDESTRUCT or an error before it, which the circuit can't
+    /// prove yet.
+    UnsupportedTx(usize),
+    /// A step of the tx of the given index copies more than `MAX_COPY_BYTES`
+    /// bytes from or into the memory, which the circuit can't prove yet.
+    TooManyCopiedBytes(usize),
+    /// The block has more steps than the circuit, which has room for the
+    /// given number of steps.
+    TooManySteps(usize),
+    /// The public inputs of the block couldn't be computed.
+    PublicInput(PublicInputError),
+}
+
+impl From<PublicInputError> for WitnessError {
+    fn from(err: PublicInputError) -> Self {
+        Self::PublicInput(err)
+    }
+}
+
+// The steps of a tx, where the step after its execution is only given when
+// the tx halts at its last step, and the execution steps otherwise stop at
+// the first error.
+pub(crate) struct TxWitness {
+    pub(crate) begin_tx_step: TxExecutionStep,
+    pub(crate) execution_steps: Vec<ExecutionStep>,
+    pub(crate) end_tx_step: Option<TxExecutionStep>,
+}
+
+// The steps of the txs of a block, with the rows of the tables they look up.
+#[derive(Default)]
+pub(crate) struct BlockWitness<F> {
+    pub(crate) txs: Vec<TxWitness>,
+    pub(crate) operations: Vec<Operation<F>>,
+    pub(crate) tx_table: Vec<[F; 3]>,
+    pub(crate) tx_calldata_table: Vec<[u64; 3]>,
+    pub(crate) bytecode_table: Vec<[F; 4]>,
+    pub(crate) keccak_table: Vec<[F; 3]>,
+    pub(crate) block_table: Vec<[F; 3]>,
+}
+
+// Returns the witness of the EVM circuit for the txs of `block` built by
+// bus-mapping, whose words are encoded with the randomness `r`.  The tx of
+// index `idx` has the id `idx + 1`.
+pub(crate) fn block_witness<F: FieldExt>(
+    block: &Block,
+    r: F,
+) -> Result<BlockWitness<F>, WitnessError> {
+    let to_biguint = |value: &Word| {
+        let mut bytes = [0u8; 32];
+        value.to_big_endian(&mut bytes);
+        BigUint::from_bytes_be(&bytes)
+    };
+    let compress = |value: &Word| {
+        let mut bytes = [0u8; 32];
+        value.to_big_endian(&mut bytes);
+        encode(bytes.iter().cloned(), r)
+    };
+    let address_value =
+        |address: &Address| encode(address.0.iter().cloned(), F::from(256));
+
+    let container = &block.container;
+    let mut stack_ops = container.sorted_stack();
+    stack_ops.sort_by_key(|op| op.gc());
+    let mut memory_ops = container.sorted_memory();
+    memory_ops.sort_by_key(|op| op.gc());
+    let mut storage_ops = container.sorted_storage();
+    storage_ops.sort_by_key(|op| op.gc());
+    let mut access_list_account_ops = container.sorted_tx_access_list_account();
+    access_list_account_ops.sort_by_key(|op| op.gc());
+    let mut access_list_ops = container.sorted_tx_access_list_account_storage();
+    access_list_ops.sort_by_key(|op| op.gc());
+    let mut refund_ops = container.sorted_tx_refund();
+    refund_ops.sort_by_key(|op| op.gc());
+    let mut call_context_ops = container.sorted_call_context();
+    call_context_ops.sort_by_key(|op| op.gc());
+    let mut nonce_ops = container.sorted_account_nonce();
+    nonce_ops.sort_by_key(|op| op.gc());
+    let mut balance_ops = container.sorted_account_balance();
+    balance_ops.sort_by_key(|op| op.gc());
+    let mut code_hash_ops = container.sorted_account_code_hash();
+    code_hash_ops.sort_by_key(|op| op.gc());
+    let mut tx_log_ops = container.sorted_tx_log();
+    tx_log_ops.sort_by_key(|op| op.gc());
+
+    let address_to_biguint =
+        |address: &Address| BigUint::from_bytes_be(address.as_bytes());
+
+    // The global counter of the circuit starts at 1, and the rw table
+    // expects the operations sorted by it
+    let mut operations: Vec<_> = stack_ops
+        .iter()
+        .map(|op| Operation {
+            gc: usize::from(op.gc()) + 1,
+            target: Target::Stack,
+            is_write: op.op().rw().is_write(),
+            values: [
+                F::from(op.op().call_id() as u64),
+                F::from(usize::from(*op.op().address()) as u64),
+                compress(op.op().value()),
+                F::zero(),
+                F::zero(),
+                F::zero(),
+            ],
+        })
+        .chain(memory_ops.iter().map(|op| Operation {
+            gc: usize::from(op.gc()) + 1,
+            target: Target::Memory,
+            is_write: op.op().rw().is_write(),
+            values: [
+                F::from(op.op().call_id() as u64),
+                F::from(usize::from(*op.op().address()) as u64),
+                F::from(op.op().value() as u64),
+                F::zero(),
+                F::zero(),
+                F::zero(),
+            ],
+        }))
+        .chain(storage_ops.iter().map(|op| Operation {
+            gc: usize::from(op.gc()) + 1,
+            target: Target::Storage,
+            is_write: op.op().rw().is_write(),
+            values: [
+                address_value(op.op().address()),
+                compress(op.op().key()),
+                compress(op.op().value()),
+                compress(op.op().value_prev()),
+                F::from(op.op().tx_id() as u64),
+                compress(op.op().committed_value()),
+            ],
+        }))
+        .chain(access_list_account_ops.iter().map(|op| Operation {
+            gc: usize::from(op.gc()) + 1,
+            target: Target::TxAccessListAccount,
+            is_write: op.op().rw().is_write(),
+            values: [
+                F::from(op.op().tx_id() as u64),
+                address_value(op.op().address()),
+                F::from(op.op().value() as u64),
+                F::from(op.op().value_prev() as u64),
+                F::zero(),
+                F::zero(),
+            ],
+        }))
+        .chain(access_list_ops.iter().map(|op| Operation {
+            gc: usize::from(op.gc()) + 1,
+            target: Target::TxAccessListAccountStorage,
+            is_write: op.op().rw().is_write(),
+            values: [
+                address_value(op.op().address()),
+                compress(op.op().key()),
+                F::from(op.op().value() as u64),
+                F::from(op.op().value_prev() as u64),
+                F::zero(),
+                F::zero(),
+            ],
+        }))
+        .chain(refund_ops.iter().map(|op| Operation {
+            gc: usize::from(op.gc()) + 1,
+            target: Target::TxRefund,
+            is_write: op.op().rw().is_write(),
+            values: [
+                F::zero(),
+                F::zero(),
+                F::from(op.op().value()),
+                F::from(op.op().value_prev()),
+                F::zero(),
+                F::zero(),
+            ],
+        }))
+        .chain(call_context_ops.iter().map(|op| {
+            let value = op.op().value();
+            Operation {
+                gc: usize::from(op.gc()) + 1,
+                target: Target::CallContext,
+                is_write: op.op().rw().is_write(),
+                values: [
+                    F::from(op.op().call_id() as u64),
+                    F::from(op.op().field() as u64),
+                    match op.op().field() {
+                        CallContextField::CallerAddress
+                        | CallContextField::CalleeAddress
+                        | CallContextField::CodeAddress => {
+                            address_value(&value.to_address())
+                        }
+                        CallContextField::Value
+                        | CallContextField::CodeHash => compress(value),
+                        _ => F::from(value.low_u64()),
+                    },
+                    F::zero(),
+                    F::zero(),
+                    F::zero(),
+                ],
+            }
+        }))
+        .chain(nonce_ops.iter().map(|op| Operation {
+            gc: usize::from(op.gc()) + 1,
+            target: Target::AccountNonce,
+            is_write: op.op().rw().is_write(),
+            values: [
+                address_value(op.op().address()),
+                F::zero(),
+                F::from(op.op().value().low_u64()),
+                F::from(op.op().value_prev().low_u64()),
+                F::zero(),
+                F::zero(),
+            ],
+        }))
+        .chain(balance_ops.iter().map(|op| Operation {
+            gc: usize::from(op.gc()) + 1,
+            target: Target::AccountBalance,
+            is_write: op.op().rw().is_write(),
+            values: [
+                address_value(op.op().address()),
+                F::zero(),
+                compress(op.op().value()),
+                compress(op.op().value_prev()),
+                F::zero(),
+                F::zero(),
+            ],
+        }))
+        .chain(code_hash_ops.iter().map(|op| Operation {
+            gc: usize::from(op.gc()) + 1,
+            target: Target::AccountCodeHash,
+            is_write: op.op().rw().is_write(),
+            values: [
+                address_value(op.op().address()),
+                F::zero(),
+                compress(&Word::from_big_endian(op.op().value().as_bytes())),
+                compress(&Word::from_big_endian(
+                    op.op().value_prev().as_bytes(),
+                )),
+                F::zero(),
+                F::zero(),
+            ],
+        }))
+        .chain(tx_log_ops.iter().map(|op| {
+            let value = op.op().value();
+            Operation {
+                gc: usize::from(op.gc()) + 1,
+                target: Target::TxLog,
+                is_write: op.op().rw().is_write(),
+                values: [
+                    F::from(op.op().log_id() as u64),
+                    F::from(op.op().field() as u64),
+                    F::from(op.op().index() as u64),
+                    match op.op().field() {
+                        TxLogField::Address => {
+                            address_value(&value.to_address())
+                        }
+                        TxLogField::Topic => compress(value),
+                        TxLogField::Data => F::from(value.low_u64()),
+                    },
+                    F::zero(),
+                    F::zero(),
+                ],
+            }
+        }))
+        .collect();
+    operations.sort_by_key(|op| op.gc);
+
+    // The codes known to the block, which may be copied without being
+    // executed, each of them once
+    let mut code_hashes = HashSet::new();
+    let codes: Vec<_> = block
+        .txs()
+        .iter()
+        .flat_map(|tx| tx.calls().iter().map(|call| call.code_hash()))
+        .chain(code_hash_ops.iter().map(|op| *op.op().value()))
+        .filter(|code_hash| code_hashes.insert(*code_hash))
+        .filter_map(|code_hash| {
+            // The empty code isn't deployed, but may be the code of an
+            // account given by the trace
+            let empty_code = (code_hash.0 == keccak256(&[])).then(|| &[][..]);
+            block.code(&code_hash).or(empty_code).map(|code| {
+                (
+                    compress(&Word::from_big_endian(code_hash.as_bytes())),
+                    code.to_vec(),
+                )
+            })
+        })
+        .collect();
+
+    // The codes are hashed into their code hashes, which gives their sizes
+    let keccak_table = codes
+        .iter()
+        .map(|(code_hash, code)| {
+            [
+                encode(code.iter().cloned(), r),
+                F::from(code.len() as u64),
+                *code_hash,
+            ]
+        })
+        .collect();
+
+    // The fields of the block, followed by the hashes of its previous
+    // blocks
+    let constants = &block.constants;
+    let number = constants.number().as_u64();
+    let history_hashes = constants.history_hashes();
+    let block_table = [
+        (
+            BlockContextField::Coinbase,
+            Word::from_big_endian(constants.coinbase().as_bytes()),
+        ),
+        (BlockContextField::Timestamp, *constants.timestamp()),
+        (BlockContextField::Number, Word::from(number)),
+        (BlockContextField::Difficulty, *constants.difficulty()),
+        (BlockContextField::GasLimit, *constants.gas_limit()),
+        (BlockContextField::ChainId, *constants.chain_id()),
+        (BlockContextField::BaseFee, *constants.base_fee()),
+    ]
+    .iter()
+    .map(|(field, value)| [F::from(*field as u64), F::zero(), compress(value)])
+    .chain(history_hashes.iter().enumerate().map(|(idx, hash)| {
+        let hash_number = number - (history_hashes.len() - idx) as u64;
+        [
+            F::from(BlockContextField::BlockHash as u64),
+            F::from(hash_number),
+            compress(&Word::from_big_endian(hash.as_bytes())),
+        ]
+    }))
+    .collect();
+
+    let mut txs = Vec::new();
+    let mut tx_table = Vec::new();
+    let mut tx_calldata_table = Vec::new();
+    let mut bytecode_table = Vec::new();
+    for (idx, tx) in block.txs().iter().enumerate() {
+        let tx_id = idx as u64 + 1;
+        let inconsistent = || WitnessError::InconsistentTrace(idx);
+        let call = |call_index: usize| {
+            tx.calls().get(call_index).ok_or_else(inconsistent)
+        };
+
+        // The intrinsic gas is split into the constant cost of the tx and the
+        // costs of its calldata and of its access list
+        let call_data_gas_cost = tx
+            .input
+            .iter()
+            .map(|byte| match byte {
+                0 => GasCost::TX_DATA_ZERO.as_u64(),
+                _ => GasCost::TX_DATA_NON_ZERO.as_u64(),
+            })
+            .sum::<u64>();
+        let access_list_gas_cost = tx
+            .intrinsic_gas
+            .checked_sub(
+                GasCost::TX.as_u64()
+                    + tx.is_create() as u64 * GasCost::CREATION_TX.as_u64()
+                    + call_data_gas_cost,
+            )
+            .ok_or_else(inconsistent)?;
+
+        let begin_tx_step = {
+            let gc = tx.begin_tx_step().gc;
+            let nonce_op = nonce_ops
+                .iter()
+                .find(|op| op.gc() == gc)
+                .ok_or_else(inconsistent)?;
+            let balance_ops = balance_ops
+                .iter()
+                .filter(|op| {
+                    (usize::from(gc)..usize::from(gc) + 4)
+                        .contains(&usize::from(op.gc()))
+                })
+                .map(|op| op.op())
+                .collect::<Vec<_>>();
+            let balance_op =
+                |idx: usize| balance_ops.get(idx).ok_or_else(inconsistent);
+            TxExecutionStep {
+                values: vec![
+                    address_to_biguint(&tx.from),
+                    address_to_biguint(balance_op(2)?.address()),
+                    to_biguint(nonce_op.op().value_prev()),
+                    BigUint::from(tx.gas),
+                    BigUint::from(tx.intrinsic_gas),
+                    to_biguint(&tx.gas_price),
+                    to_biguint(&tx.value),
+                    to_biguint(balance_op(0)?.value_prev()),
+                    to_biguint(balance_op(0)?.value()),
+                    to_biguint(balance_op(1)?.value()),
+                    to_biguint(balance_op(2)?.value_prev()),
+                    to_biguint(balance_op(2)?.value()),
+                    to_biguint(&Word::from_big_endian(
+                        tx.calls()
+                            .first()
+                            .ok_or_else(inconsistent)?
+                            .code_hash()
+                            .as_bytes(),
+                    )),
+                    BigUint::from(tx_id),
+                    BigUint::from(tx.is_create() as u64),
+                    BigUint::from(call_data_gas_cost),
+                    BigUint::from(access_list_gas_cost),
+                ],
+            }
+        };
+
+        // The root call halts, returns or reverts at the last step of the tx,
+        // which follows the steps of the circuit when none of them is a
+        // SELFDESTRUCT and no error halts a call, whose caller can't be
+        // resumed yet
+        let halts_at_last_step =
+            tx.steps().last().map_or(false, |last_step| {
+                matches!(
+                    last_step.op,
+                    OpcodeId::STOP
+                        | OpcodeId::SELFDESTRUCT
+                        | OpcodeId::RETURN
+                        | OpcodeId::REVERT
+                )
+            }) && tx.steps().iter().enumerate().all(|(idx, step)| {
+                step.error.is_none()
+                    && (idx == tx.steps().len() - 1
+                        || step.op != OpcodeId::SELFDESTRUCT)
+            });
+        let end_tx_step = if halts_at_last_step {
+            let gc = tx.end_tx_step().gc;
+            let gc_range = usize::from(gc)
+                ..usize::from(gc) + tx.end_tx_step().bus_mapping_instance.len();
+            let in_step =
+                |gc: GlobalCounter| gc_range.contains(&usize::from(gc));
+            let refund_op = refund_ops
+                .iter()
+                .find(|op| in_step(op.gc()))
+                .ok_or_else(inconsistent)?;
+            let balance_ops = balance_ops
+                .iter()
+                .filter(|op| in_step(op.gc()))
+                .map(|op| op.op())
+                .collect::<Vec<_>>();
+            let balance_op =
+                |idx: usize| balance_ops.get(idx).ok_or_else(inconsistent);
+            let effective_tip =
+                tx.gas_price.saturating_sub(*block.constants.base_fee());
+
+            // At most one account is destroyed, by the SELFDESTRUCT halting
+            // the root call
+            let destructed_nonce_ops = nonce_ops
+                .iter()
+                .filter(|op| in_step(op.gc()))
+                .map(|op| op.op())
+                .collect::<Vec<_>>();
+            if destructed_nonce_ops.len() > 1 {
+                return Err(inconsistent());
+            }
+            let destructed = destructed_nonce_ops
+                .first()
+                .map(|nonce_op| -> Result<_, WitnessError> {
+                    let code_hash_op = code_hash_ops
+                        .iter()
+                        .find(|op| in_step(op.gc()))
+                        .ok_or_else(inconsistent)?
+                        .op();
+                    Ok([
+                        address_to_biguint(nonce_op.address()),
+                        to_biguint(nonce_op.value_prev()),
+                        to_biguint(balance_op(2)?.value_prev()),
+                        to_biguint(&Word::from_big_endian(
+                            code_hash_op.value_prev().as_bytes(),
+                        )),
+                    ])
+                })
+                .transpose()?;
+            Some(TxExecutionStep {
+                values: vec![
+                    address_to_biguint(balance_op(0)?.address()),
+                    address_to_biguint(balance_op(1)?.address()),
+                    BigUint::from(tx.gas),
+                    to_biguint(&tx.gas_price),
+                    to_biguint(&effective_tip),
+                    BigUint::from(refund_op.op().value()),
+                    to_biguint(balance_op(0)?.value_prev()),
+                    to_biguint(balance_op(0)?.value()),
+                    to_biguint(balance_op(1)?.value_prev()),
+                    to_biguint(balance_op(1)?.value()),
+                    BigUint::from(tx_id),
+                    BigUint::from(destructed.is_some() as u64),
+                ]
+                .into_iter()
+                .chain(destructed.unwrap_or_default())
+                .collect(),
+            })
+        } else {
+            None
+        };
+
+        // The steps of the circuit end at the first error, which halts its
+        // call
+        let num_steps = tx
+            .steps()
+            .iter()
+            .position(|step| step.error.is_some())
+            .map_or(tx.steps().len(), |idx| idx + 1);
+        let execution_steps = tx.steps()[..num_steps]
+            .iter()
+            .map(|step| -> Result<_, WitnessError> {
+                let gc_range = usize::from(step.gc)
+                    ..usize::from(step.gc) + step.bus_mapping_instance.len();
+                let in_step =
+                    |gc: GlobalCounter| gc_range.contains(&usize::from(gc));
+                // A step reads and writes at most `MAX_COPY_BYTES` bytes of
+                // memory
+                let num_memory_ops = |is_write: bool| {
+                    memory_ops
+                        .iter()
+                        .filter(|op| {
+                            in_step(op.gc())
+                                && op.op().rw().is_write() == is_write
+                        })
+                        .count()
+                };
+                if num_memory_ops(false).max(num_memory_ops(true))
+                    > MAX_COPY_BYTES
+                {
+                    return Err(WitnessError::TooManyCopiedBytes(idx));
+                }
+                let mut values = stack_ops
+                    .iter()
+                    .filter(|op| in_step(op.gc()))
+                    .map(|op| to_biguint(op.op().value()))
+                    .collect::<Vec<_>>();
+                if step.op.is_push() {
+                    let num_bytes = (step.op.as_u8() - OpcodeId::PUSH1.as_u8()
+                        + 1) as usize;
+                    values.push(BigUint::from_bytes_le(&vec![1u8; num_bytes]));
+                }
+                if matches!(step.op, OpcodeId::SLOAD | OpcodeId::SSTORE)
+                    && step.error != Some(ExecError::WriteProtection)
+                {
+                    // The slot is only read by SSTORE when there's not enough
+                    // gas, and not by SLOAD, so that the operations of the
+                    // step are followed by the reverts of the failed call.
+                    let storage_op = storage_ops
+                        .iter()
+                        .find(|op| in_step(op.gc()))
+                        .filter(|_| {
+                            step.op == OpcodeId::SSTORE || step.error.is_none()
+                        });
+                    let access_list_op = access_list_ops
+                        .iter()
+                        .find(|op| in_step(op.gc()))
+                        .ok_or_else(inconsistent)?
+                        .op();
+                    if let Some(storage_op) = storage_op {
+                        if step.op == OpcodeId::SSTORE {
+                            values
+                                .push(to_biguint(storage_op.op().value_prev()));
+                        }
+                        values.push(to_biguint(
+                            storage_op.op().committed_value(),
+                        ));
+                    }
+                    values.push(BigUint::from(
+                        access_list_op.value_prev() as u64
+                    ));
+                    if step.op == OpcodeId::SSTORE && step.error.is_none() {
+                        let refund_op = refund_ops
+                            .iter()
+                            .find(|op| in_step(op.gc()))
+                            .ok_or_else(inconsistent)?
+                            .op();
+                        values.push(BigUint::from(refund_op.value_prev()));
+                    }
+                }
+                if matches!(
+                    step.op,
+                    OpcodeId::STOP
+                        | OpcodeId::CALL
+                        | OpcodeId::CALLCODE
+                        | OpcodeId::DELEGATECALL
+                        | OpcodeId::STATICCALL
+                        | OpcodeId::CREATE
+                        | OpcodeId::CREATE2
+                        | OpcodeId::ADDRESS
+                        | OpcodeId::ORIGIN
+                        | OpcodeId::CALLER
+                        | OpcodeId::CALLVALUE
+                        | OpcodeId::GASPRICE
+                        | OpcodeId::SELFBALANCE
+                        | OpcodeId::SLOAD
+                        | OpcodeId::SSTORE
+                        | OpcodeId::CALLDATALOAD
+                        | OpcodeId::CALLDATASIZE
+                        | OpcodeId::CALLDATACOPY
+                        | OpcodeId::BALANCE
+                        | OpcodeId::EXTCODESIZE
+                        | OpcodeId::EXTCODECOPY
+                        | OpcodeId::EXTCODEHASH
+                        | OpcodeId::RETURN
+                        | OpcodeId::REVERT
+                        | OpcodeId::RETURNDATASIZE
+                        | OpcodeId::RETURNDATACOPY
+                        | OpcodeId::LOG0
+                        | OpcodeId::LOG1
+                        | OpcodeId::LOG2
+                        | OpcodeId::LOG3
+                        | OpcodeId::LOG4
+                        | OpcodeId::SELFDESTRUCT
+                ) {
+                    values.extend(
+                        call_context_ops
+                            .iter()
+                            .filter(|op| in_step(op.gc()))
+                            .map(|op| to_biguint(op.op().value())),
+                    );
+                }
+                if matches!(
+                    step.op,
+                    OpcodeId::CALL
+                        | OpcodeId::CALLCODE
+                        | OpcodeId::DELEGATECALL
+                        | OpcodeId::STATICCALL
+                        | OpcodeId::CREATE
+                        | OpcodeId::CREATE2
+                ) {
+                    values.push(BigUint::from(step.gas_cost.as_u64()));
+                }
+                if matches!(step.op, OpcodeId::CREATE | OpcodeId::CREATE2) {
+                    // The first nonce write of the step is the one of the
+                    // caller
+                    let nonce_op = nonce_ops
+                        .iter()
+                        .find(|op| in_step(op.gc()))
+                        .ok_or_else(inconsistent)?
+                        .op();
+                    values.push(to_biguint(nonce_op.value_prev()));
+                }
+                if matches!(
+                    step.op,
+                    OpcodeId::CALL
+                        | OpcodeId::CALLCODE
+                        | OpcodeId::DELEGATECALL
+                        | OpcodeId::STATICCALL
+                        | OpcodeId::CREATE
+                        | OpcodeId::CREATE2
+                ) {
+                    // The code hash of the callee, whose call id is the
+                    // global counter following its context writes
+                    let caller_id = call(step.call_index)?.call_id;
+                    let callee = tx
+                        .calls()
+                        .iter()
+                        .find(|call| {
+                            call.caller_id == caller_id
+                                && gc_range.contains(&(call.call_id - 1))
+                        })
+                        .ok_or_else(inconsistent)?;
+                    values.push(to_biguint(&Word::from_big_endian(
+                        callee.code_hash().as_bytes(),
+                    )));
+                }
+                if matches!(
+                    step.op,
+                    OpcodeId::CALL
+                        | OpcodeId::CALLCODE
+                        | OpcodeId::DELEGATECALL
+                        | OpcodeId::STATICCALL
+                ) {
+                    // Whether the code address was warm
+                    let access_list_account_op = access_list_account_ops
+                        .iter()
+                        .find(|op| in_step(op.gc()))
+                        .ok_or_else(inconsistent)?
+                        .op();
+                    values.push(BigUint::from(
+                        access_list_account_op.value_prev() as u64,
+                    ));
+                }
+                if matches!(
+                    step.op,
+                    OpcodeId::BALANCE
+                        | OpcodeId::EXTCODESIZE
+                        | OpcodeId::EXTCODECOPY
+                        | OpcodeId::EXTCODEHASH
+                ) {
+                    // Whether the account was warm
+                    let access_list_account_op = access_list_account_ops
+                        .iter()
+                        .find(|op| in_step(op.gc()))
+                        .ok_or_else(inconsistent)?
+                        .op();
+                    values.push(BigUint::from(
+                        access_list_account_op.value_prev() as u64,
+                    ));
+                }
+                if matches!(step.op, OpcodeId::CREATE | OpcodeId::CREATE2) {
+                    // The hash of the address of the contract, the code hash
+                    // of the contract before the creation and whether the
+                    // contract was warm, followed by the input of keccak
+                    // hashed into the address, which starts with a non-zero
+                    // byte and is the last value of the step.
+                    let caller = call(step.call_index)?;
+                    let input = if step.op == OpcodeId::CREATE {
+                        let nonce_op = nonce_ops
+                            .iter()
+                            .find(|op| in_step(op.gc()))
+                            .ok_or_else(inconsistent)?
+                            .op();
+                        let mut stream = RlpStream::new_list(2);
+                        stream.append(&caller.address);
+                        stream.append(nonce_op.value_prev());
+                        stream.out().to_vec()
+                    } else {
+                        let mut salt = [0u8; 32];
+                        stack_ops
+                            .iter()
+                            .filter(|op| in_step(op.gc()))
+                            .nth(3)
+                            .ok_or_else(inconsistent)?
+                            .op()
+                            .value()
+                            .to_big_endian(&mut salt);
+                        let init_code_hash = values
+                            .last()
+                            .ok_or_else(inconsistent)?
+                            .to_bytes_be();
+                        iter::once(0xff)
+                            .chain(caller.address.0.iter().cloned())
+                            .chain(salt.iter().cloned())
+                            .chain(
+                                iter::repeat(0)
+                                    .take(32 - init_code_hash.len())
+                                    .chain(init_code_hash),
+                            )
+                            .collect()
+                    };
+                    let code_hash_op = code_hash_ops
+                        .iter()
+                        .find(|op| in_step(op.gc()))
+                        .ok_or_else(inconsistent)?
+                        .op();
+                    values.push(BigUint::from_bytes_be(&keccak256(&input)));
+                    values.push(to_biguint(&Word::from_big_endian(
+                        code_hash_op.value_prev().as_bytes(),
+                    )));
+                    let access_list_account_op = access_list_account_ops
+                        .iter()
+                        .find(|op| in_step(op.gc()))
+                        .ok_or_else(inconsistent)?
+                        .op();
+                    values.push(BigUint::from(
+                        access_list_account_op.value_prev() as u64,
+                    ));
+                    values.push(BigUint::from_bytes_be(&input));
+                }
+                // The code read by the step, followed by its random linear
+                // combination
+                let code_hash = match step.op {
+                    OpcodeId::EXTCODESIZE | OpcodeId::EXTCODECOPY
+                        if step.error.is_none() =>
+                    {
+                        let code_hash = code_hash_ops
+                            .iter()
+                            .find(|op| in_step(op.gc()))
+                            .ok_or_else(inconsistent)?
+                            .op()
+                            .value();
+                        values.push(to_biguint(&Word::from_big_endian(
+                            code_hash.as_bytes(),
+                        )));
+                        *code_hash
+                    }
+                    _ => call(step.call_index)?.code_hash(),
+                };
+                let code = block.code(&code_hash).unwrap_or(&[]);
+                if matches!(
+                    step.op,
+                    OpcodeId::CODESIZE
+                        | OpcodeId::CODECOPY
+                        | OpcodeId::EXTCODESIZE
+                        | OpcodeId::EXTCODECOPY
+                ) && step.error.is_none()
+                {
+                    values.push(BigUint::from_bytes_le(
+                        &encode(code.iter().cloned(), r).to_bytes(),
+                    ));
+                }
+                if matches!(step.op, OpcodeId::CODECOPY | OpcodeId::EXTCODECOPY)
+                    && step.error.is_none()
+                {
+                    // The size of the code, followed by the bytes written into
+                    // the memory and whether they are opcodes
+                    let rows = bytecode::unroll(code.to_vec(), r).rows;
+                    let args = values
+                        .get((step.op == OpcodeId::EXTCODECOPY) as usize..)
+                        .unwrap_or_default();
+                    let (code_offset, length) = match args {
+                        [_, code_offset, length, ..] => (
+                            code_offset.to_usize(),
+                            length.to_usize().ok_or_else(inconsistent)?,
+                        ),
+                        _ => return Err(inconsistent()),
+                    };
+                    let is_code = (0..length)
+                        .map(|idx| {
+                            code_offset
+                                .and_then(|offset| rows.get(offset + idx))
+                                .map_or(0, |row| row.is_code as u8)
+                        })
+                        .collect::<Vec<_>>();
+                    values.push(BigUint::from(code.len()));
+                    values.push(BigUint::from_bytes_le(
+                        &memory_ops
+                            .iter()
+                            .filter(|op| {
+                                in_step(op.gc()) && op.op().rw().is_write()
+                            })
+                            .map(|op| op.op().value())
+                            .collect::<Vec<_>>(),
+                    ));
+                    values.push(BigUint::from_bytes_le(&is_code));
+                }
+                if matches!(
+                    step.op,
+                    OpcodeId::CALLDATACOPY
+                        | OpcodeId::RETURN
+                        | OpcodeId::REVERT
+                        | OpcodeId::RETURNDATACOPY
+                ) {
+                    // The bytes written into the memory
+                    values.push(BigUint::from_bytes_le(
+                        &memory_ops
+                            .iter()
+                            .filter(|op| {
+                                in_step(op.gc()) && op.op().rw().is_write()
+                            })
+                            .map(|op| op.op().value())
+                            .collect::<Vec<_>>(),
+                    ));
+                }
+                if step.op == OpcodeId::REVERT {
+                    // The number of reversions, followed by the target, the
+                    // keys, the values and the auxiliary values of each of
+                    // them, and the global counter of the write it undoes,
+                    // which is the last write before the step with the same
+                    // keys and the values swapped not undone yet
+                    let reversions = operations.iter().filter(|op| {
+                        gc_range.contains(&(op.gc - 1))
+                            && matches!(
+                                op.target,
+                                Target::Storage
+                                    | Target::TxAccessListAccountStorage
+                                    | Target::TxRefund
+                                    | Target::AccountNonce
+                                    | Target::AccountBalance
+                                    | Target::AccountCodeHash
+                                    | Target::TxAccessListAccount
+                            )
+                    });
+                    let mut undone_gcs = vec![];
+                    let mut reversion_values = vec![];
+                    for reversion in reversions {
+                        let undone = operations
+                            .iter()
+                            .rev()
+                            .find(|op| {
+                                op.gc <= usize::from(step.gc)
+                                    && op.is_write
+                                    && op.target == reversion.target
+                                    && op.values[..2] == reversion.values[..2]
+                                    && op.values[2] == reversion.values[3]
+                                    && op.values[3] == reversion.values[2]
+                                    && op.values[4..] == reversion.values[4..]
+                                    && !undone_gcs.contains(&op.gc)
+                            })
+                            .ok_or_else(inconsistent)?;
+                        undone_gcs.push(undone.gc);
+                        reversion_values.extend(
+                            iter::once(F::from(reversion.target as u64))
+                                .chain(reversion.values.iter().cloned())
+                                .chain(iter::once(F::from(undone.gc as u64))),
+                        );
+                    }
+                    values.push(BigUint::from(undone_gcs.len()));
+                    values.extend(reversion_values.iter().map(|value| {
+                        BigUint::from_bytes_le(&value.to_bytes())
+                    }));
+                }
+                if step.op == OpcodeId::BLOCKHASH && step.error.is_none() {
+                    // The number of the current block
+                    values
+                        .push(BigUint::from(block.constants.number().as_u64()));
+                }
+                if step.op == OpcodeId::SELFDESTRUCT
+                    && matches!(step.error, None | Some(ExecError::OutOfGas(_)))
+                {
+                    // Whether the beneficiary was warm, its nonce and code
+                    // hash, and its balance before and after the transfer,
+                    // followed by the balance of the account before it's
+                    // cleared, which are only read without enough gas
+                    let access_list_account_op = access_list_account_ops
+                        .iter()
+                        .find(|op| in_step(op.gc()))
+                        .ok_or_else(inconsistent)?
+                        .op();
+                    let nonce_op = nonce_ops
+                        .iter()
+                        .find(|op| in_step(op.gc()))
+                        .ok_or_else(inconsistent)?
+                        .op();
+                    let code_hash_op = code_hash_ops
+                        .iter()
+                        .find(|op| in_step(op.gc()))
+                        .ok_or_else(inconsistent)?
+                        .op();
+                    let balance_ops = balance_ops
+                        .iter()
+                        .filter(|op| in_step(op.gc()))
+                        .map(|op| op.op())
+                        .collect::<Vec<_>>();
+                    values.push(BigUint::from(
+                        access_list_account_op.value_prev() as u64,
+                    ));
+                    values.push(to_biguint(nonce_op.value_prev()));
+                    values.push(to_biguint(&Word::from_big_endian(
+                        code_hash_op.value_prev().as_bytes(),
+                    )));
+                    let balance_op = |idx: usize| {
+                        balance_ops.get(idx).ok_or_else(inconsistent)
+                    };
+                    values.push(to_biguint(balance_op(0)?.value_prev()));
+                    values.push(to_biguint(balance_op(0)?.value()));
+                    values.push(to_biguint(balance_op(1)?.value_prev()));
+                }
+                if step.op == OpcodeId::SHA3 && step.error.is_none() {
+                    // The hashed bytes read from the memory
+                    values.push(BigUint::from_bytes_le(
+                        &memory_ops
+                            .iter()
+                            .filter(|op| in_step(op.gc()))
+                            .map(|op| op.op().value())
+                            .collect::<Vec<_>>(),
+                    ));
+                }
+                if step.op.is_log() && step.error.is_none() {
+                    // The data bytes read from the memory
+                    values.push(BigUint::from_bytes_le(
+                        &memory_ops
+                            .iter()
+                            .filter(|op| in_step(op.gc()))
+                            .map(|op| op.op().value())
+                            .collect::<Vec<_>>(),
+                    ));
+                }
+                let case = match step.error {
+                    Some(ExecError::ReturnDataOutOfBounds) => {
+                        Case::ReturnDataOutOfBounds
+                    }
+                    Some(ExecError::WriteProtection) => Case::WriteProtection,
+                    Some(ExecError::OutOfGas(_)) => Case::OutOfGas,
+                    _ => Case::Success,
+                };
+                Ok(ExecutionStep {
+                    opcode: step.op,
+                    case,
+                    values,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // The opcodes executed in the tx, with the PUSH data following them
+        for step in tx.steps() {
+            let (code_hash, pc) = (
+                compress(&Word::from_big_endian(
+                    call(step.call_index)?.code_hash().as_bytes(),
+                )),
+                usize::from(step.pc),
+            );
+            let data = if step.op.is_push() {
+                let push_op = stack_ops
+                    .iter()
+                    .find(|op| op.gc() == step.gc)
+                    .ok_or_else(inconsistent)?;
+                push_data(step.op, &to_biguint(push_op.op().value()))
+            } else {
+                vec![]
+            };
+            bytecode_table.extend(
+                iter::once(bytecode_row(code_hash, pc, true, step.op.as_u8()))
+                    .chain(data.into_iter().enumerate().map(|(idx, byte)| {
+                        bytecode_row(code_hash, pc + 1 + idx, false, byte)
+                    })),
+            );
+        }
+
+        tx_table.extend(
+            [
+                (TxContextField::Origin, address_value(&tx.from)),
+                (TxContextField::GasPrice, compress(&tx.gas_price)),
+                (TxContextField::Nonce, F::from(tx.nonce)),
+                (TxContextField::Gas, F::from(tx.gas)),
+                (TxContextField::CalleeAddress, address_value(&tx.to)),
+                (TxContextField::IsCreate, F::from(tx.is_create() as u64)),
+                (TxContextField::Value, compress(&tx.value)),
+                (
+                    TxContextField::CallDataLength,
+                    F::from(tx.input.len() as u64),
+                ),
+                (TxContextField::CallDataGasCost, F::from(call_data_gas_cost)),
+                (
+                    TxContextField::AccessListGasCost,
+                    F::from(access_list_gas_cost),
+                ),
+                (
+                    TxContextField::TxHash,
+                    compress(&Word::from_big_endian(tx.hash.as_bytes())),
+                ),
+            ]
+            .iter()
+            .map(|(field, value)| {
+                [F::from(tx_id), F::from(*field as u64), *value]
+            }),
+        );
+        tx_calldata_table.extend(
+            tx.input
+                .iter()
+                .enumerate()
+                .map(|(index, byte)| [tx_id, index as u64, *byte as u64]),
+        );
+
+        txs.push(TxWitness {
+            begin_tx_step,
+            execution_steps,
+            end_tx_step,
+        });
+    }
+
+    // All the bytes of the codes known to the block
+    bytecode_table.extend(codes.iter().flat_map(|(code_hash, code)| {
+        bytecode::unroll(code.clone(), r)
+            .rows
+            .into_iter()
+            .map(move |row| {
+                bytecode_row(*code_hash, row.index, row.is_code, row.byte)
+            })
+    }));
+
+    Ok(BlockWitness {
+        txs,
+        operations,
+        tx_table,
+        tx_calldata_table,
+        bytecode_table,
+        keccak_table,
+        block_table,
+    })
+}
+
+pub(crate) fn bytecode_row<F: FieldExt>(
+    code_hash: F,
+    index: usize,
+    is_code: bool,
+    byte: u8,
+) -> [F; 4] {
+    [
+        code_hash,
+        F::from(index as u64),
+        F::from(is_code as u64),
+        F::from(byte as u64),
+    ]
+}
+
+// The big-endian bytes of a PUSH opcode, which are the pushed value
+pub(crate) fn push_data(opcode: OpcodeId, value: &BigUint) -> Vec<u8> {
+    let num_bytes = (opcode.as_u8() - OpcodeId::PUSH1.as_u8() + 1) as usize;
+    value.to_word()[..num_bytes].iter().rev().cloned().collect()
+}
+
+// Returns the rows of the exp table of the EXP `execution_steps`, whose words
+// are encoded with the randomness `r`.
+pub(crate) fn exp_table<F: FieldExt>(
+    execution_steps: &[ExecutionStep],
+    r: F,
+) -> Result<Vec<[F; 3]>, Error> {
+    let modulus = BigUint::from(1u64) << 256;
+
+    execution_steps
+        .iter()
+        .filter(|step| {
+            step.opcode == OpcodeId::EXP && step.case == Case::Success
+        })
+        .map(|step| match &step.values[..] {
+            [base, exponent, ..] => {
+                let result = base.modpow(exponent, &modulus);
+                Ok([base, exponent, &result].map(|value| {
+                    encode(value.to_word().to_vec().into_iter().rev(), r)
+                }))
+            }
+            _ => Err(Error::Synthesis),
+        })
+        .collect()
+}
+
+// Returns the rows of the keccak table of the SHA3 `execution_steps`, whose
+// inputs are the hashed bytes in memory order, and of the CREATE and CREATE2
+// steps, whose last value is the input hashed into the address of the
+// contract, in the random linear combinations of the randomness `r`.
+pub(crate) fn keccak_table<F: FieldExt>(
+    execution_steps: &[ExecutionStep],
+    r: F,
+) -> Result<Vec<[F; 3]>, Error> {
+    execution_steps
+        .iter()
+        .filter(|step| {
+            matches!(
+                step.opcode,
+                OpcodeId::SHA3 | OpcodeId::CREATE | OpcodeId::CREATE2
+            ) && step.case == Case::Success
+        })
+        .map(|step| {
+            let input = if step.opcode == OpcodeId::SHA3 {
+                match &step.values[..] {
+                    [_, length, _, bytes, ..] => {
+                        let word = bytes.to_word();
+                        length
+                            .to_usize()
+                            .and_then(|length| word.get(..length))
+                            .ok_or(Error::Synthesis)?
+                            .to_vec()
+                    }
+                    _ => return Err(Error::Synthesis),
+                }
+            } else {
+                step.values.last().ok_or(Error::Synthesis)?.to_bytes_be()
+            };
+            Ok([
+                encode(input.iter().cloned(), r),
+                F::from(input.len() as u64),
+                bytecode::code_hash(&input, r),
+            ])
+        })
+        .collect()
+}
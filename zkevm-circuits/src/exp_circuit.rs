@@ -0,0 +1,34 @@
+//! The exponentiation circuit implementation.
+
+pub(crate) mod exp;
+
+/*
+Example rows of `3 ** 5`, whose exponent has the bits 0...0101:
+
+| row | acc      | base_limbs | bit | byte_acc | exponent_rlc | exp_table      |
+-----------------------------------------------------------------------------
+|  0  | 3        | 3, 0, 0, 0 |  0  |    0     |      0       | 0, 0, 0        |   // base
+|  1  | 1        | 3, 0, 0, 0 |  0  |    0     |      0       | 0, 0, 0        |   // init
+|  2  | 1        | 3, 0, 0, 0 |  0  |    0     |      0       | 0, 0, 0        |   // bit 255
+|  3  | 1        | 3, 0, 0, 0 |  0  |    0     |      0       | 0, 0, 0        |   // square
+|  .. |    ..    |     ..     |  .. |    ..    |      ..      |       ..       |
+| 508 | 3        | 3, 0, 0, 0 |  1  |    1     |      0       | 0, 0, 0        |   // bit 2
+| 509 | 9        | 3, 0, 0, 0 |  1  |    1     |      0       | 0, 0, 0        |   // square
+| 510 | 9        | 3, 0, 0, 0 |  0  |    2     |      0       | 0, 0, 0        |   // bit 1
+| 511 | 81       | 3, 0, 0, 0 |  0  |    2     |      0       | 0, 0, 0        |   // square
+| 512 | 243      | 3, 0, 0, 0 |  1  |    5     |      0       | 3, 5, 243      |   // bit 0
+
+Each exponentiation takes ROWS_PER_EXP rows, and computes the power with the
+square-and-multiply method from the most significant bit of the exponent.  The
+first row holds the base in `acc`, whose 64-bit limbs are kept in `base_limbs`
+on all the rows, and the second one starts the accumulator at 1.  Then a row
+multiplies the accumulator by the base when the bit of the row is set, and by 1
+otherwise, and is followed by a row squaring the accumulator, until the last
+bit.  The products are taken modulo 2**256, with the carries of the 64-bit
+limbs witnessed in bytes.
+
+The bits are accumulated into the bytes of the exponent in `byte_acc`, and the
+completed bytes into their random linear combination in `exponent_rlc`.  The
+last row adds the random linear combinations of the base, the exponent and the
+result to the exp table, which is empty on the other rows.
+*/
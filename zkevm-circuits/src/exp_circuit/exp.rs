@@ -0,0 +1,758 @@
+use crate::{gadget::evm_word::encode, util::Expr};
+use halo2::{
+    circuit::{Layouter, Region},
+    plonk::{
+        Advice, Column, ConstraintSystem, Error, Expression, Fixed,
+        VirtualCells,
+    },
+    poly::Rotation,
+};
+use num::BigUint;
+use pairing::arithmetic::FieldExt;
+
+/// Number of rows taken by an exponentiation: the base, the initial
+/// accumulator, a multiplication for each of the 256 bits of the exponent and
+/// a squaring between each of them.
+pub(crate) const ROWS_PER_EXP: usize = 2 + 256 + 255;
+
+// Returns the four 64-bit limbs of a word given in little endian bytes.
+fn limbs(word: &[u8; 32]) -> [u64; 4] {
+    let mut limbs = [0u64; 4];
+    for (idx, limb) in limbs.iter_mut().enumerate() {
+        for byte in word[idx * 8..(idx + 1) * 8].iter().rev() {
+            *limb = (*limb << 8) + *byte as u64;
+        }
+    }
+    limbs
+}
+
+// Returns `a * b (mod 2**256)` and the carries of its limbs, which fit in 9
+// bytes each.
+fn mul_mod(a: &[u8; 32], b: &[u8; 32]) -> ([u8; 32], [u8; 9], [u8; 9]) {
+    let [a, b] = [a, b].map(|word| limbs(word).map(BigUint::from));
+    let products = (0..4)
+        .map(|k| (0..=k).map(|i| &a[i] * &b[k - i]).sum::<BigUint>())
+        .collect::<Vec<_>>();
+    let lo = &products[0] + (&products[1] << 64);
+    let carry_lo = &lo >> 128;
+    let hi = &products[2] + (&products[3] << 64) + &carry_lo;
+    let carry_hi = &hi >> 128;
+
+    let mask = (BigUint::from(1u64) << 128) - 1u64;
+    let product = (hi & &mask) << 128 | (lo & mask);
+    (to_bytes(&product), to_bytes(&carry_lo), to_bytes(&carry_hi))
+}
+
+fn to_bytes<const N: usize>(value: &BigUint) -> [u8; N] {
+    let mut bytes = [0u8; N];
+    for (idx, byte) in value.to_bytes_le().into_iter().enumerate() {
+        bytes[idx] = byte;
+    }
+    bytes
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Config<F> {
+    r: F,
+    q_enable: Column<Fixed>,
+    q_base: Column<Fixed>,
+    q_init: Column<Fixed>,
+    q_multiply: Column<Fixed>,
+    q_square: Column<Fixed>,
+    q_byte_start: Column<Fixed>,
+    q_last: Column<Fixed>,
+    acc: [Column<Advice>; 32],
+    base_limbs: [Column<Advice>; 4],
+    base_rlc: Column<Advice>,
+    bit: Column<Advice>,
+    byte_acc: Column<Advice>,
+    exponent_rlc: Column<Advice>,
+    carry_lo: [Column<Advice>; 9],
+    carry_hi: [Column<Advice>; 9],
+    byte_table: Column<Fixed>,
+    exp_table: [Column<Advice>; 3],
+}
+
+impl<F: FieldExt> Config<F> {
+    /// Configures the constraints proving the content of `exp_table`, whose
+    /// columns are the random linear combinations of the base, the exponent
+    /// and `base ** exponent (mod 2**256)`.
+    pub(crate) fn configure(
+        meta: &mut ConstraintSystem<F>,
+        r: F,
+        exp_table: [Column<Advice>; 3],
+    ) -> Self {
+        let q_enable = meta.fixed_column();
+        let q_base = meta.fixed_column();
+        let q_init = meta.fixed_column();
+        let q_multiply = meta.fixed_column();
+        let q_square = meta.fixed_column();
+        let q_byte_start = meta.fixed_column();
+        let q_last = meta.fixed_column();
+        let acc = [(); 32].map(|_| meta.advice_column());
+        let base_limbs = [(); 4].map(|_| meta.advice_column());
+        let base_rlc = meta.advice_column();
+        let bit = meta.advice_column();
+        let byte_acc = meta.advice_column();
+        let exponent_rlc = meta.advice_column();
+        let carry_lo = [(); 9].map(|_| meta.advice_column());
+        let carry_hi = [(); 9].map(|_| meta.advice_column());
+        let byte_table = meta.fixed_column();
+
+        meta.create_gate("exp rows", |meta| {
+            let one = Expression::Constant(F::one());
+            let r = Expression::Constant(r);
+            let range_64 = Expression::Constant(F::from(2).pow(&[64, 0, 0, 0]));
+            let range_128 =
+                Expression::Constant(F::from(2).pow(&[128, 0, 0, 0]));
+
+            let q_enable = meta.query_fixed(q_enable, Rotation::cur());
+            let q_base = meta.query_fixed(q_base, Rotation::cur());
+            let q_init = meta.query_fixed(q_init, Rotation::cur());
+            let q_multiply = meta.query_fixed(q_multiply, Rotation::cur());
+            let q_square = meta.query_fixed(q_square, Rotation::cur());
+            let q_byte_start = meta.query_fixed(q_byte_start, Rotation::cur());
+            let q_last = meta.query_fixed(q_last, Rotation::cur());
+
+            let acc_limbs = |meta: &mut VirtualCells<F>, rotation| {
+                let bytes = acc
+                    .iter()
+                    .map(|column| meta.query_advice(*column, rotation))
+                    .collect::<Vec<_>>();
+                let limbs = [0, 1, 2, 3].map(|idx| {
+                    bytes[idx * 8..(idx + 1) * 8].iter().rev().fold(
+                        Expression::Constant(F::zero()),
+                        |acc, byte| {
+                            acc * Expression::Constant(F::from(256))
+                                + byte.clone()
+                        },
+                    )
+                });
+                (bytes, limbs)
+            };
+            let (acc_cur, acc_limbs_cur) = acc_limbs(meta, Rotation::cur());
+            let (_, acc_limbs_prev) = acc_limbs(meta, Rotation::prev());
+            let acc_rlc = acc_cur
+                .iter()
+                .rev()
+                .fold(Expression::Constant(F::zero()), |acc, byte| {
+                    acc * r.clone() + byte.clone()
+                });
+            let base_limbs_cur = base_limbs
+                .map(|column| meta.query_advice(column, Rotation::cur()));
+            let base_limbs_prev = base_limbs
+                .map(|column| meta.query_advice(column, Rotation::prev()));
+            let base_rlc_cur = meta.query_advice(base_rlc, Rotation::cur());
+            let base_rlc_prev = meta.query_advice(base_rlc, Rotation::prev());
+            let bit_cur = meta.query_advice(bit, Rotation::cur());
+            let bit_prev = meta.query_advice(bit, Rotation::prev());
+            let byte_acc_cur = meta.query_advice(byte_acc, Rotation::cur());
+            let byte_acc_prev = meta.query_advice(byte_acc, Rotation::prev());
+            let exponent_rlc_cur =
+                meta.query_advice(exponent_rlc, Rotation::cur());
+            let exponent_rlc_prev =
+                meta.query_advice(exponent_rlc, Rotation::prev());
+            let carry = |meta: &mut VirtualCells<F>, columns: [_; 9]| {
+                columns.iter().rev().fold(
+                    Expression::Constant(F::zero()),
+                    |acc, column| {
+                        acc * Expression::Constant(F::from(256))
+                            + meta.query_advice(*column, Rotation::cur())
+                    },
+                )
+            };
+            let carry_lo = carry(meta, carry_lo);
+            let carry_hi = carry(meta, carry_hi);
+
+            let mut constraints = vec![];
+
+            // The base row holds the base, whose limbs and random linear
+            // combination are kept by all the rows
+            for (base_limb, acc_limb) in
+                base_limbs_cur.iter().zip(acc_limbs_cur.iter())
+            {
+                constraints.push(
+                    q_base.clone() * (base_limb.clone() - acc_limb.clone()),
+                );
+            }
+            constraints.push(
+                q_base.clone() * (base_rlc_cur.clone() - acc_rlc.clone()),
+            );
+            let q_step = q_enable.clone() - q_base;
+            for (cur, prev) in base_limbs_cur
+                .iter()
+                .chain(std::iter::once(&base_rlc_cur))
+                .zip(
+                    base_limbs_prev
+                        .iter()
+                        .chain(std::iter::once(&base_rlc_prev)),
+                )
+            {
+                constraints.push(q_step.clone() * (cur.clone() - prev.clone()));
+            }
+
+            // The accumulator starts at 1, before any bit of the exponent
+            for (idx, limb) in acc_limbs_cur.iter().enumerate() {
+                let expected = (idx == 0).expr();
+                constraints.push(q_init.clone() * (limb.clone() - expected));
+            }
+            for value in [byte_acc_cur.clone(), exponent_rlc_cur.clone()] {
+                constraints.push(q_init.clone() * value);
+            }
+
+            // `a * b == d (mod 2**256)`, on 64-bit limbs
+            let mul_mod = |a: &[Expression<F>; 4],
+                           b: &[Expression<F>; 4],
+                           d: &[Expression<F>; 4]| {
+                let products = (0..4)
+                    .map(|k| {
+                        (0..=k)
+                            .fold(Expression::Constant(F::zero()), |acc, i| {
+                                acc + a[i].clone() * b[k - i].clone()
+                            })
+                    })
+                    .collect::<Vec<_>>();
+                [
+                    products[0].clone()
+                        + products[1].clone() * range_64.clone()
+                        - d[0].clone()
+                        - d[1].clone() * range_64.clone()
+                        - carry_lo.clone() * range_128.clone(),
+                    products[2].clone()
+                        + products[3].clone() * range_64.clone()
+                        + carry_lo.clone()
+                        - d[2].clone()
+                        - d[3].clone() * range_64.clone()
+                        - carry_hi.clone() * range_128.clone(),
+                ]
+            };
+
+            // A multiplication row multiplies the accumulator by the base when
+            // its bit is set, and by 1 otherwise
+            constraints.push(
+                q_multiply.clone()
+                    * bit_cur.clone()
+                    * (one.clone() - bit_cur.clone()),
+            );
+            let multiplier = [0, 1, 2, 3].map(|idx| {
+                bit_cur.clone() * base_limbs_cur[idx].clone()
+                    + (one.clone() - bit_cur.clone()) * (idx == 0).expr()
+            });
+            for constraint in
+                mul_mod(&acc_limbs_prev, &multiplier, &acc_limbs_cur)
+            {
+                constraints.push(q_multiply.clone() * constraint);
+            }
+
+            // The bits are accumulated into the bytes of the exponent, from the
+            // most significant one, and each completed byte into the random
+            // linear combination of the exponent
+            constraints.push(
+                q_multiply.clone()
+                    * (byte_acc_cur.clone()
+                        - (one.clone() - q_byte_start.clone())
+                            * byte_acc_prev.clone()
+                            * Expression::Constant(F::from(2))
+                        - bit_cur.clone()),
+            );
+            constraints.push(
+                q_multiply.clone()
+                    * (exponent_rlc_cur.clone()
+                        - q_byte_start.clone()
+                            * (exponent_rlc_prev.clone() * r.clone()
+                                + byte_acc_prev.clone())
+                        - (one.clone() - q_byte_start)
+                            * exponent_rlc_prev.clone()),
+            );
+
+            // A squaring row squares the accumulator, and keeps the bits
+            for constraint in
+                mul_mod(&acc_limbs_prev, &acc_limbs_prev, &acc_limbs_cur)
+            {
+                constraints.push(q_square.clone() * constraint);
+            }
+            for (cur, prev) in [
+                (bit_cur, bit_prev),
+                (byte_acc_cur.clone(), byte_acc_prev),
+                (exponent_rlc_cur.clone(), exponent_rlc_prev),
+            ] {
+                constraints.push(q_square.clone() * (cur - prev));
+            }
+
+            // The last row adds the exponentiation to the exp table, which is
+            // empty on the other rows
+            let table_row =
+                [base_rlc_cur, exponent_rlc_cur * r + byte_acc_cur, acc_rlc];
+            for (column, value) in exp_table.iter().zip(table_row) {
+                let table = meta.query_advice(*column, Rotation::cur());
+                constraints
+                    .push(q_enable.clone() * (table - q_last.clone() * value));
+            }
+
+            constraints
+        });
+
+        // All the bytes of the accumulator and of the carries are in range
+        for column in acc.iter().chain(carry_lo.iter()).chain(carry_hi.iter()) {
+            meta.lookup_any(|meta| {
+                let q_enable = meta.query_fixed(q_enable, Rotation::cur());
+                vec![(
+                    q_enable * meta.query_advice(*column, Rotation::cur()),
+                    meta.query_fixed(byte_table, Rotation::cur()),
+                )]
+            });
+        }
+
+        Self {
+            r,
+            q_enable,
+            q_base,
+            q_init,
+            q_multiply,
+            q_square,
+            q_byte_start,
+            q_last,
+            acc,
+            base_limbs,
+            base_rlc,
+            bit,
+            byte_acc,
+            exponent_rlc,
+            carry_lo,
+            carry_hi,
+            byte_table,
+            exp_table,
+        }
+    }
+
+    /// Load the byte table, which range checks the bytes of the words.
+    pub(crate) fn load(
+        &self,
+        layouter: &mut impl Layouter<F>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "byte table",
+            |mut region| {
+                for byte in 0..=u8::MAX {
+                    region.assign_fixed(
+                        || "byte table",
+                        self.byte_table,
+                        byte as usize,
+                        || Ok(F::from(byte as u64)),
+                    )?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// Assign the rows of `exps`, the bases and exponents given as little
+    /// endian words, followed by exponentiations of 0 to the power of 0 up to
+    /// `max_exps` exponentiations.
+    pub(crate) fn assign(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        max_exps: usize,
+        exps: &[([u8; 32], [u8; 32])],
+    ) -> Result<(), Error> {
+        if exps.len() > max_exps {
+            return Err(Error::Synthesis);
+        }
+
+        layouter.assign_region(
+            || "exp rows",
+            |mut region| {
+                let padding = ([0u8; 32], [0u8; 32]);
+                for (idx, (base, exponent)) in exps
+                    .iter()
+                    .chain(std::iter::repeat(&padding))
+                    .take(max_exps)
+                    .enumerate()
+                {
+                    self.assign_exp(
+                        &mut region,
+                        idx * ROWS_PER_EXP,
+                        base,
+                        exponent,
+                    )?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    fn assign_exp(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        base: &[u8; 32],
+        exponent: &[u8; 32],
+    ) -> Result<(), Error> {
+        let base_limbs = limbs(base).map(F::from);
+        let base_rlc = encode(base.iter().rev().cloned(), self.r);
+        let mut one = [0u8; 32];
+        one[0] = 1;
+
+        // The base row, and the initial accumulator
+        self.assign_row(
+            region,
+            offset,
+            base_limbs,
+            base_rlc,
+            base,
+            [0; 9],
+            [0; 9],
+            false,
+            0,
+            F::zero(),
+            None,
+        )?;
+        self.assign_row(
+            region,
+            offset + 1,
+            base_limbs,
+            base_rlc,
+            &one,
+            [0; 9],
+            [0; 9],
+            false,
+            0,
+            F::zero(),
+            None,
+        )?;
+
+        let mut acc = one;
+        let mut bit = false;
+        let mut byte_acc = 0u8;
+        let mut exponent_rlc = F::zero();
+        for idx in 0..256 {
+            let row = offset + 2 + 2 * idx;
+
+            // A squaring row between the multiplication rows, which keeps the
+            // bits of the previous one
+            if idx > 0 {
+                let (squared, carry_lo, carry_hi) = mul_mod(&acc, &acc);
+                acc = squared;
+                self.assign_row(
+                    region,
+                    row - 1,
+                    base_limbs,
+                    base_rlc,
+                    &acc,
+                    carry_lo,
+                    carry_hi,
+                    bit,
+                    byte_acc,
+                    exponent_rlc,
+                    None,
+                )?;
+            }
+
+            // The bits from the most significant one
+            let bit_idx = 255 - idx;
+            bit = (exponent[bit_idx / 8] >> (bit_idx % 8)) & 1 == 1;
+            if bit_idx % 8 == 7 {
+                exponent_rlc = exponent_rlc * self.r + F::from(byte_acc as u64);
+                byte_acc = 0;
+            }
+            byte_acc = byte_acc * 2 + bit as u8;
+
+            let (product, carry_lo, carry_hi) =
+                mul_mod(&acc, if bit { base } else { &one });
+            acc = product;
+            let table_row = if idx == 255 {
+                Some([
+                    base_rlc,
+                    encode(exponent.iter().rev().cloned(), self.r),
+                    encode(acc.iter().rev().cloned(), self.r),
+                ])
+            } else {
+                None
+            };
+            self.assign_row(
+                region,
+                row,
+                base_limbs,
+                base_rlc,
+                &acc,
+                carry_lo,
+                carry_hi,
+                bit,
+                byte_acc,
+                exponent_rlc,
+                table_row,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn assign_row(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        base_limbs: [F; 4],
+        base_rlc: F,
+        acc: &[u8; 32],
+        carry_lo: [u8; 9],
+        carry_hi: [u8; 9],
+        bit: bool,
+        byte_acc: u8,
+        exponent_rlc: F,
+        table_row: Option<[F; 3]>,
+    ) -> Result<(), Error> {
+        // The position of the row in its exponentiation
+        let row = offset % ROWS_PER_EXP;
+        let is_multiply = row >= 2 && row % 2 == 0;
+        for (name, column, value) in [
+            ("q_enable", self.q_enable, true),
+            ("q_base", self.q_base, row == 0),
+            ("q_init", self.q_init, row == 1),
+            ("q_multiply", self.q_multiply, is_multiply),
+            ("q_square", self.q_square, row >= 3 && row % 2 == 1),
+            (
+                "q_byte_start",
+                self.q_byte_start,
+                is_multiply && (row - 2) / 2 % 8 == 0,
+            ),
+            ("q_last", self.q_last, row + 1 == ROWS_PER_EXP),
+        ] {
+            region.assign_fixed(
+                || name,
+                column,
+                offset,
+                || Ok(F::from(value as u64)),
+            )?;
+        }
+
+        for (columns, bytes) in [
+            (&self.acc[..], &acc[..]),
+            (&self.carry_lo[..], &carry_lo[..]),
+            (&self.carry_hi[..], &carry_hi[..]),
+        ] {
+            for (column, byte) in columns.iter().zip(bytes.iter()) {
+                region.assign_advice(
+                    || "byte",
+                    *column,
+                    offset,
+                    || Ok(F::from(*byte as u64)),
+                )?;
+            }
+        }
+        for (column, value) in self.base_limbs.iter().zip(base_limbs) {
+            region.assign_advice(
+                || "base limb",
+                *column,
+                offset,
+                || Ok(value),
+            )?;
+        }
+
+        for (name, column, value) in [
+            ("base_rlc", self.base_rlc, base_rlc),
+            ("bit", self.bit, F::from(bit as u64)),
+            ("byte_acc", self.byte_acc, F::from(byte_acc as u64)),
+            ("exponent_rlc", self.exponent_rlc, exponent_rlc),
+        ] {
+            region.assign_advice(|| name, column, offset, || Ok(value))?;
+        }
+
+        for (column, value) in self
+            .exp_table
+            .iter()
+            .zip(table_row.unwrap_or([F::zero(); 3]))
+        {
+            region.assign_advice(
+                || "exp table",
+                *column,
+                offset,
+                || Ok(value),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Config, ROWS_PER_EXP};
+    use crate::{
+        gadget::evm_word::{encode, r},
+        util::ToWord,
+    };
+    use halo2::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        dev::MockProver,
+        plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Fixed},
+        poly::Rotation,
+    };
+    use num::BigUint;
+    use pairing::{arithmetic::FieldExt, bn256::Fr as Fp};
+
+    const MAX_EXPS: usize = 3;
+
+    #[derive(Clone, Debug)]
+    struct ExpCircuitConfig<F> {
+        exp_circuit: Config<F>,
+        q_lookup: Column<Fixed>,
+        lookup: [Column<Advice>; 3],
+    }
+
+    // Proves the exp table of `exps`, and looks up `lookups` in it
+    #[derive(Default)]
+    struct ExpCircuit<F> {
+        exps: Vec<([u8; 32], [u8; 32])>,
+        lookups: Vec<[F; 3]>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for ExpCircuit<F> {
+        type Config = ExpCircuitConfig<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let exp_table = [(); 3].map(|_| meta.advice_column());
+            let q_lookup = meta.fixed_column();
+            let lookup = [(); 3].map(|_| meta.advice_column());
+
+            meta.lookup_any(|meta| {
+                let q_lookup = meta.query_fixed(q_lookup, Rotation::cur());
+                lookup
+                    .iter()
+                    .zip(exp_table.iter())
+                    .map(|(value, table)| {
+                        (
+                            q_lookup.clone()
+                                * meta.query_advice(*value, Rotation::cur()),
+                            meta.query_advice(*table, Rotation::cur()),
+                        )
+                    })
+                    .collect()
+            });
+
+            Self::Config {
+                exp_circuit: Config::configure(meta, r(), exp_table),
+                q_lookup,
+                lookup,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            config.exp_circuit.load(&mut layouter)?;
+            config
+                .exp_circuit
+                .assign(&mut layouter, MAX_EXPS, &self.exps)?;
+
+            layouter.assign_region(
+                || "lookups",
+                |mut region| {
+                    for (offset, values) in self.lookups.iter().enumerate() {
+                        region.assign_fixed(
+                            || "q_lookup",
+                            config.q_lookup,
+                            offset,
+                            || Ok(F::one()),
+                        )?;
+                        for (column, value) in config.lookup.iter().zip(values)
+                        {
+                            region.assign_advice(
+                                || "lookup",
+                                *column,
+                                offset,
+                                || Ok(*value),
+                            )?;
+                        }
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    fn word(value: &BigUint) -> [u8; 32] {
+        value.to_word()
+    }
+
+    fn rlc(value: &BigUint) -> Fp {
+        encode(word(value).iter().rev().cloned(), r())
+    }
+
+    // Proves `base ** exponent` along with the other exponentiations, and
+    // looks up `result` as its result
+    fn verify(
+        base: BigUint,
+        exponent: BigUint,
+        result: BigUint,
+        success: bool,
+    ) {
+        let others = [(3u64, 5u64), (0, 0)].map(|(base, exponent)| {
+            (BigUint::from(base), BigUint::from(exponent))
+        });
+        let exps = std::iter::once((&base, &exponent))
+            .chain(others.iter().map(|(base, exponent)| (base, exponent)))
+            .map(|(base, exponent)| (word(base), word(exponent)))
+            .collect();
+        let circuit = ExpCircuit::<Fp> {
+            exps,
+            lookups: vec![
+                [rlc(&base), rlc(&exponent), rlc(&result)],
+                [Fp::zero(); 3],
+            ],
+        };
+
+        let k = (MAX_EXPS * ROWS_PER_EXP + 10)
+            .next_power_of_two()
+            .trailing_zeros();
+        let prover = MockProver::<Fp>::run(k, &circuit, vec![]).unwrap();
+        assert_eq!(prover.verify().is_ok(), success);
+    }
+
+    #[test]
+    fn exp_circuit() {
+        let max = BigUint::from_bytes_be(&[255u8; 32]);
+        let modulus = BigUint::from(1u64) << 256;
+
+        for (base, exponent) in [
+            (3u64.into(), 5u64.into()),
+            (max.clone(), 0u64.into()),
+            (0u64.into(), 0u64.into()),
+            (2u64.into(), 255u64.into()),
+            (2u64.into(), 256u64.into()),
+            (max.clone(), max.clone()),
+            (
+                BigUint::from_bytes_be(&[0x5a; 32]),
+                BigUint::from_bytes_be(&[0xa5; 32]),
+            ),
+        ] {
+            let result = base.modpow(&exponent, &modulus);
+            verify(base, exponent, result, true);
+        }
+    }
+
+    #[test]
+    fn exp_circuit_invalid_result() {
+        // 3 ** 5 != 244
+        verify(3u64.into(), 5u64.into(), 244u64.into(), false);
+        // max ** max != 1 (mod 2**256)
+        let max = BigUint::from_bytes_be(&[255u8; 32]);
+        verify(max.clone(), max, 1u64.into(), false);
+    }
+
+    #[test]
+    fn exp_circuit_too_many_exps() {
+        let circuit = ExpCircuit::<Fp> {
+            exps: vec![([0u8; 32], [0u8; 32]); MAX_EXPS + 1],
+            lookups: vec![],
+        };
+        assert!(MockProver::<Fp>::run(11, &circuit, vec![]).is_err());
+    }
+}
@@ -0,0 +1,39 @@
+//! The keccak circuit implementation.
+
+pub(crate) mod keccak;
+
+/*
+Example rows of the hash of an input of 200 bytes, which is absorbed in two
+blocks of 136 bytes:
+
+| row | state           | c, d      | is_data    | acc        | is_first | is_final | keccak_table    |
+------------------------------------------------------------------------------------------------------
+|  0  | M_0             | 0         | 1, .., 1   | rlc(0..j)  |    1     |    0     | 0, 0, 0         |   // absorb
+|  1  | M_0             | C, D      | 0          | 0          |    1     |    0     | 0, 0, 0         |   // round 0
+|  .. |       ..        |    ..     |     ..     |     ..     |    ..    |    ..    |       ..        |
+|  24 | S_23            | C, D      | 0          | 0          |    1     |    0     | 0, 0, 0         |   // round 23
+|  25 | S_24            | 0         | 0          | 0          |    1     |    0     | 0, 0, 0         |   // output
+|  26 | M_1             | 0         | 1, .., 0   | rlc(0..j)  |    0     |    1     | 0, 0, 0         |   // absorb
+|  27 | S_24 ^ M_1      | C, D      | 0          | 0          |    0     |    1     | 0, 0, 0         |   // round 0
+|  .. |       ..        |    ..     |     ..     |     ..     |    ..    |    ..    |       ..        |
+|  51 | S_24            | 0         | 0          | 0          |    0     |    1     | rlc, 200, hash  |   // output
+
+Each block of the padded input takes ROWS_PER_BLOCK rows, with the 1600 bits of
+the state of the permutation in `state`, whose lanes are ordered as in the
+sponge and hold their bits from the least significant one.  The absorb row holds
+the bits of the block, which are xored into the output state of the previous
+block, or into the zero state for the first block of an input.  Its bytes are
+either bytes of the input, flagged by `is_data`, or the padding `0x01 .. 0x80`
+following them in the final block, and the bytes of the input are accumulated
+into their random linear combination in `acc`, and counted into the length.
+
+The round rows apply a round of keccak-f to their state, whose result is the
+state of the next row, with the parities of the columns of the state in `c`
+and the values xored into them by theta in `d`.  The output row of the final
+block adds the random linear combinations of the input and of the first 32
+bytes of the state, and the length of the input, to the keccak table, which is
+empty on the other rows.
+
+The circuit takes a fixed number of blocks, and the blocks which aren't taken
+by the inputs hash the empty input.
+*/
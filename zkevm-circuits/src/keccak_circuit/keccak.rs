@@ -0,0 +1,804 @@
+use crate::{gadget::evm_word::encode, util::Expr};
+use halo2::{
+    circuit::{Layouter, Region},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed},
+    poly::Rotation,
+};
+use keccak256::{
+    common::{State, PERMUTATION, ROTATION_CONSTANTS, ROUND_CONSTANTS},
+    plain::KeccakF,
+};
+use pairing::arithmetic::FieldExt;
+use std::array;
+
+/// Number of bytes absorbed by a block of the sponge.
+pub(crate) const RATE: usize = 136;
+
+/// Number of rows taken by a block: the absorb row, a row for each round of
+/// the permutation and the output row.
+pub(crate) const ROWS_PER_BLOCK: usize = 2 + PERMUTATION;
+
+// The positions of the bits which can be set in a round constant
+const ROUND_CONSTANT_BITS: [usize; 7] = [0, 1, 3, 7, 15, 31, 63];
+
+// Returns the index of the bit `z` of the lane `(x, y)` in the state columns.
+fn bit_idx(x: usize, y: usize, z: usize) -> usize {
+    (x + 5 * y) * 64 + z
+}
+
+// Returns the index of the bit `i` of the byte `j` of a block, or of the
+// output, in the state columns.
+fn byte_bit_idx(j: usize, i: usize) -> usize {
+    let lane = j / 8;
+    bit_idx(lane % 5, lane / 5, (j % 8) * 8 + i)
+}
+
+// Returns the parities of the columns of the state, and the values xored into
+// them by theta.
+fn theta_values(state: &State) -> ([u64; 5], [u64; 5]) {
+    let c = [0, 1, 2, 3, 4].map(|x| state[x].iter().fold(0, |acc, v| acc ^ v));
+    let d =
+        [0, 1, 2, 3, 4].map(|x| c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1));
+    (c, d)
+}
+
+fn round(state: State, round_constant: u64) -> State {
+    let state = KeccakF::pi(KeccakF::rho(KeccakF::theta(state)));
+    KeccakF::iota(KeccakF::xi(state), round_constant)
+}
+
+// Returns the blocks of the padded input, and the number of bytes of the input
+// in each of them.
+fn padded_blocks(input: &[u8]) -> Vec<([u8; RATE], usize)> {
+    let mut padded = input.to_vec();
+    padded.push(0x01);
+    padded.resize((input.len() / RATE + 1) * RATE, 0);
+    *padded.last_mut().unwrap() |= 0x80;
+
+    padded
+        .chunks(RATE)
+        .enumerate()
+        .map(|(idx, chunk)| {
+            let mut block = [0u8; RATE];
+            block.copy_from_slice(chunk);
+            (block, input.len().saturating_sub(idx * RATE).min(RATE))
+        })
+        .collect()
+}
+
+// The state of the sponge after a block, and the random linear combination and
+// the length of the input absorbed into it
+#[derive(Clone, Copy, Debug, Default)]
+struct Sponge<F> {
+    state: State,
+    data_rlc: F,
+    data_len: usize,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Config<F> {
+    r: F,
+    q_enable: Column<Fixed>,
+    q_first: Column<Fixed>,
+    q_absorb: Column<Fixed>,
+    q_round: Column<Fixed>,
+    q_output: Column<Fixed>,
+    round_constant: [Column<Fixed>; 7],
+    state: Vec<Column<Advice>>,
+    c: Vec<Column<Advice>>,
+    d: Vec<Column<Advice>>,
+    is_data: Vec<Column<Advice>>,
+    acc: Vec<Column<Advice>>,
+    is_first: Column<Advice>,
+    is_final: Column<Advice>,
+    data_rlc: Column<Advice>,
+    data_len: Column<Advice>,
+    keccak_table: [Column<Advice>; 3],
+}
+
+impl<F: FieldExt> Config<F> {
+    /// Configures the constraints proving the content of `keccak_table`,
+    /// whose columns are the random linear combination of the input, its
+    /// length and the random linear combination of its hash.
+    pub(crate) fn configure(
+        meta: &mut ConstraintSystem<F>,
+        r: F,
+        keccak_table: [Column<Advice>; 3],
+    ) -> Self {
+        let q_enable = meta.fixed_column();
+        let q_first = meta.fixed_column();
+        let q_absorb = meta.fixed_column();
+        let q_round = meta.fixed_column();
+        let q_output = meta.fixed_column();
+        let round_constant = [(); 7].map(|_| meta.fixed_column());
+        let state = (0..1600).map(|_| meta.advice_column()).collect::<Vec<_>>();
+        let c = (0..320).map(|_| meta.advice_column()).collect::<Vec<_>>();
+        let d = (0..320).map(|_| meta.advice_column()).collect::<Vec<_>>();
+        let is_data =
+            (0..RATE).map(|_| meta.advice_column()).collect::<Vec<_>>();
+        let acc = (0..RATE).map(|_| meta.advice_column()).collect::<Vec<_>>();
+        let is_first = meta.advice_column();
+        let is_final = meta.advice_column();
+        let data_rlc = meta.advice_column();
+        let data_len = meta.advice_column();
+
+        let xor = |a: Expression<F>, b: Expression<F>| {
+            a.clone() + b.clone() - 2.expr() * a * b
+        };
+
+        meta.create_gate("keccak absorb rows", |meta| {
+            let one = Expression::Constant(F::one());
+            let r = Expression::Constant(r);
+            let q_first = meta.query_fixed(q_first, Rotation::cur());
+            let q_absorb = meta.query_fixed(q_absorb, Rotation::cur());
+            let mut query = |column, rotation| {
+                meta.query_advice(column, Rotation(rotation))
+            };
+            let is_first_cur = query(is_first, 0);
+            let is_final_cur = query(is_final, 0);
+            let is_final_prev = query(is_final, -1);
+            let data_rlc_cur = query(data_rlc, 0);
+            let data_rlc_prev = query(data_rlc, -1);
+            let data_len_cur = query(data_len, 0);
+            let data_len_prev = query(data_len, -1);
+            let is_data = is_data
+                .iter()
+                .map(|column| query(*column, 0))
+                .collect::<Vec<_>>();
+            let acc = acc
+                .iter()
+                .map(|column| query(*column, 0))
+                .collect::<Vec<_>>();
+            let [prev, cur, next] = [-1, 0, 1].map(|rotation| {
+                state
+                    .iter()
+                    .map(|column| query(*column, rotation))
+                    .collect::<Vec<_>>()
+            });
+
+            let mut constraints = vec![];
+
+            // The first block of the circuit, and the block following a final
+            // block, start an input
+            for value in [is_first_cur.clone(), is_final_cur.clone()] {
+                constraints.push(value.clone() * (one.clone() - value));
+            }
+            constraints.push(
+                is_first_cur.clone()
+                    - q_first.clone()
+                    - (one.clone() - q_first) * is_final_prev,
+            );
+
+            // The bits of the block are xored into the output state of the
+            // previous block, unless it starts an input, and the capacity
+            // isn't touched by the block
+            let not_first = one.clone() - is_first_cur.clone();
+            for (idx, bit) in cur.iter().enumerate() {
+                let prev = not_first.clone() * prev[idx].clone();
+                if idx < RATE * 8 {
+                    constraints.push(bit.clone() * (one.clone() - bit.clone()));
+                    constraints
+                        .push(next[idx].clone() - xor(prev, bit.clone()));
+                } else {
+                    constraints.push(bit.clone());
+                    constraints.push(next[idx].clone() - prev);
+                }
+            }
+
+            // The bytes of the input come first, and fill the blocks which
+            // aren't final, and the padding follows them in the final block
+            for (idx, value) in is_data.iter().enumerate() {
+                constraints.push(value.clone() * (one.clone() - value.clone()));
+                if idx > 0 {
+                    constraints.push(
+                        value.clone()
+                            * (one.clone() - is_data[idx - 1].clone()),
+                    );
+                }
+            }
+            constraints.push(
+                (one.clone() - is_final_cur.clone())
+                    * (one.clone() - is_data[RATE - 1].clone()),
+            );
+            constraints.push(is_final_cur * is_data[RATE - 1].clone());
+            for (idx, value) in is_data.iter().enumerate() {
+                let byte = (0..8).fold(0.expr(), |acc, i| {
+                    acc + cur[byte_bit_idx(idx, i)].clone()
+                        * Expression::Constant(F::from(1 << i))
+                });
+                let is_data_prev = if idx == 0 {
+                    one.clone()
+                } else {
+                    is_data[idx - 1].clone()
+                };
+                let padding = is_data_prev - value.clone()
+                    + (idx == RATE - 1).expr() * 0x80.expr();
+                constraints.push(
+                    (one.clone() - value.clone()) * (byte.clone() - padding),
+                );
+
+                // The bytes of the input are accumulated into their random
+                // linear combination, following the one of the previous block
+                let acc_prev = if idx == 0 {
+                    not_first.clone() * data_rlc_prev.clone()
+                } else {
+                    acc[idx - 1].clone()
+                };
+                constraints.push(
+                    acc[idx].clone()
+                        - acc_prev.clone()
+                        - value.clone()
+                            * (acc_prev * (r.clone() - one.clone()) + byte),
+                );
+            }
+            constraints.push(data_rlc_cur - acc[RATE - 1].clone());
+            constraints.push(
+                data_len_cur
+                    - not_first * data_len_prev
+                    - is_data
+                        .iter()
+                        .fold(0.expr(), |acc, value| acc + value.clone()),
+            );
+
+            constraints
+                .into_iter()
+                .map(|constraint| q_absorb.clone() * constraint)
+                .collect::<Vec<_>>()
+        });
+
+        meta.create_gate("keccak round rows", |meta| {
+            let one = Expression::Constant(F::one());
+            let q_round = meta.query_fixed(q_round, Rotation::cur());
+            let round_constant = round_constant
+                .map(|column| meta.query_fixed(column, Rotation::cur()));
+            let [cur, next] = [0, 1].map(|rotation| {
+                state
+                    .iter()
+                    .map(|column| {
+                        meta.query_advice(*column, Rotation(rotation))
+                    })
+                    .collect::<Vec<_>>()
+            });
+            let [c, d] = [&c, &d].map(|columns| {
+                columns
+                    .iter()
+                    .map(|column| meta.query_advice(*column, Rotation::cur()))
+                    .collect::<Vec<_>>()
+            });
+
+            let mut constraints = vec![];
+
+            // theta xors into each bit the parities of two neighbouring
+            // columns, the bits of `c`, whose sums with the bits of the
+            // columns are even
+            for x in 0..5 {
+                for z in 0..64 {
+                    let parity = c[x * 64 + z].clone();
+                    let sum = (0..5).fold(0.expr(), |acc, y| {
+                        acc + cur[bit_idx(x, y, z)].clone()
+                    }) - parity.clone();
+                    constraints.push(parity.clone() * (one.clone() - parity));
+                    constraints.push(
+                        sum.clone()
+                            * (sum.clone() - 2.expr())
+                            * (sum - 4.expr()),
+                    );
+                    constraints.push(
+                        d[x * 64 + z].clone()
+                            - xor(
+                                c[(x + 4) % 5 * 64 + z].clone(),
+                                c[(x + 1) % 5 * 64 + (z + 63) % 64].clone(),
+                            ),
+                    );
+                }
+            }
+            let theta = |x: usize, y: usize, z: usize| {
+                xor(cur[bit_idx(x, y, z)].clone(), d[x * 64 + z].clone())
+            };
+
+            // rho rotates the lanes, and pi moves the lane `(x, y)` to
+            // `(y, 2x + 3y)`
+            let mut moved = vec![vec![]; 25];
+            for x in 0..5 {
+                for y in 0..5 {
+                    let rotation = ROTATION_CONSTANTS[x][y] as usize;
+                    moved[y + 5 * ((2 * x + 3 * y) % 5)] = (0..64)
+                        .map(|z| theta(x, y, (z + 64 - rotation) % 64))
+                        .collect::<Vec<_>>();
+                }
+            }
+
+            // chi combines each bit with the bits of the next two lanes of its
+            // row, and iota xors the round constant into the first lane
+            for x in 0..5 {
+                for y in 0..5 {
+                    for z in 0..64 {
+                        let [a, b, c] = [0, 1, 2].map(|offset| {
+                            moved[(x + offset) % 5 + 5 * y][z].clone()
+                        });
+                        let mut value = xor(a, (one.clone() - b) * c);
+                        if (x, y) == (0, 0) {
+                            if let Some(idx) = ROUND_CONSTANT_BITS
+                                .iter()
+                                .position(|bit| *bit == z)
+                            {
+                                value = xor(value, round_constant[idx].clone());
+                            }
+                        }
+                        constraints
+                            .push(next[bit_idx(x, y, z)].clone() - value);
+                    }
+                }
+            }
+
+            constraints
+                .into_iter()
+                .map(|constraint| q_round.clone() * constraint)
+                .collect::<Vec<_>>()
+        });
+
+        meta.create_gate("keccak rows", |meta| {
+            let q_enable = meta.query_fixed(q_enable, Rotation::cur());
+            let q_absorb = meta.query_fixed(q_absorb, Rotation::cur());
+            let q_output = meta.query_fixed(q_output, Rotation::cur());
+            let mut query = |column, rotation| {
+                meta.query_advice(column, Rotation(rotation))
+            };
+
+            let mut constraints = vec![];
+
+            // The rows of a block keep the values of its absorb row
+            let q_step = q_enable.clone() - q_absorb;
+            for column in [is_first, is_final, data_rlc, data_len] {
+                constraints.push(
+                    q_step.clone() * (query(column, 0) - query(column, -1)),
+                );
+            }
+
+            // The output row of the final block adds the input and the first
+            // 32 bytes of the state, in big endian, to the keccak table, which
+            // is empty on the other rows
+            let mut hash_rlc = 0.expr();
+            let mut coefficient = F::one();
+            for j in (0..32).rev() {
+                for i in 0..8 {
+                    hash_rlc = hash_rlc
+                        + query(state[byte_bit_idx(j, i)], 0)
+                            * Expression::Constant(
+                                coefficient * F::from(1 << i),
+                            );
+                }
+                coefficient *= r;
+            }
+            let table_row = [query(data_rlc, 0), query(data_len, 0), hash_rlc];
+            let q_last = q_output * query(is_final, 0);
+            for (column, value) in keccak_table.iter().zip(table_row) {
+                let table = query(*column, 0);
+                constraints
+                    .push(q_enable.clone() * (table - q_last.clone() * value));
+            }
+
+            constraints
+        });
+
+        Self {
+            r,
+            q_enable,
+            q_first,
+            q_absorb,
+            q_round,
+            q_output,
+            round_constant,
+            state,
+            c,
+            d,
+            is_data,
+            acc,
+            is_first,
+            is_final,
+            data_rlc,
+            data_len,
+            keccak_table,
+        }
+    }
+
+    /// Assign the rows of the hashes of `inputs`, followed by hashes of the
+    /// empty input up to `max_blocks` blocks.
+    pub(crate) fn assign(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        max_blocks: usize,
+        inputs: &[Vec<u8>],
+    ) -> Result<(), Error> {
+        let mut blocks = inputs
+            .iter()
+            .flat_map(|input| {
+                let blocks = padded_blocks(input);
+                let num_blocks = blocks.len();
+                blocks.into_iter().enumerate().map(move |(idx, block)| {
+                    (block, [idx == 0, idx + 1 == num_blocks])
+                })
+            })
+            .collect::<Vec<_>>();
+        if blocks.len() > max_blocks {
+            return Err(Error::Synthesis);
+        }
+        let padding = padded_blocks(&[]).remove(0);
+        blocks.resize(max_blocks, (padding, [true, true]));
+
+        layouter.assign_region(
+            || "keccak rows",
+            |mut region| {
+                let mut sponge = Sponge::default();
+                for (idx, ((block, num_bytes), flags)) in
+                    blocks.iter().enumerate()
+                {
+                    if flags[0] {
+                        sponge = Sponge::default();
+                    }
+                    sponge = self.assign_block(
+                        &mut region,
+                        idx * ROWS_PER_BLOCK,
+                        sponge,
+                        block,
+                        *num_bytes,
+                        *flags,
+                    )?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    fn assign_block(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        sponge: Sponge<F>,
+        block: &[u8; RATE],
+        num_bytes: usize,
+        flags: [bool; 2],
+    ) -> Result<Sponge<F>, Error> {
+        // The absorb row, with the bits of the block, and the bytes of the
+        // input accumulated into the ones of the previous blocks
+        let mut words = [[0u64; 5]; 5];
+        for (lane, bytes) in block.chunks(8).enumerate() {
+            let mut word = [0u8; 8];
+            word.copy_from_slice(bytes);
+            words[lane % 5][lane / 5] = u64::from_le_bytes(word);
+        }
+        let mut data_rlc = sponge.data_rlc;
+        let mut acc = vec![];
+        for (idx, byte) in block.iter().enumerate() {
+            if idx < num_bytes {
+                data_rlc = data_rlc * self.r + F::from(*byte as u64);
+            }
+            acc.push(data_rlc);
+        }
+        let data_len = sponge.data_len + num_bytes;
+        let values = [
+            F::from(flags[0] as u64),
+            F::from(flags[1] as u64),
+            data_rlc,
+            F::from(data_len as u64),
+        ];
+        self.assign_row(
+            region,
+            offset,
+            &words,
+            ([0; 5], [0; 5]),
+            (0..RATE).map(|idx| idx < num_bytes).collect(),
+            acc,
+            values,
+            None,
+        )?;
+
+        // The rounds of the permutation of the state xored with the block
+        let mut state = [0, 1, 2, 3, 4]
+            .map(|x| [0, 1, 2, 3, 4].map(|y| sponge.state[x][y] ^ words[x][y]));
+        for (idx, round_constant) in ROUND_CONSTANTS.iter().enumerate() {
+            self.assign_row(
+                region,
+                offset + 1 + idx,
+                &state,
+                theta_values(&state),
+                vec![false; RATE],
+                vec![F::zero(); RATE],
+                values,
+                None,
+            )?;
+            state = round(state, *round_constant);
+        }
+
+        // The output row, which adds the hash of the input to the table after
+        // its final block
+        let table_row = if flags[1] {
+            let hash = (0..32).map(|j| {
+                let lane = j / 8;
+                (state[lane % 5][lane / 5] >> (j % 8 * 8)) as u8
+            });
+            Some([data_rlc, values[3], encode(hash, self.r)])
+        } else {
+            None
+        };
+        self.assign_row(
+            region,
+            offset + ROWS_PER_BLOCK - 1,
+            &state,
+            ([0; 5], [0; 5]),
+            vec![false; RATE],
+            vec![F::zero(); RATE],
+            values,
+            table_row,
+        )?;
+
+        Ok(Sponge {
+            state,
+            data_rlc,
+            data_len,
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn assign_row(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: &State,
+        (c, d): ([u64; 5], [u64; 5]),
+        is_data: Vec<bool>,
+        acc: Vec<F>,
+        values: [F; 4],
+        table_row: Option<[F; 3]>,
+    ) -> Result<(), Error> {
+        // The position of the row in its block
+        let row = offset % ROWS_PER_BLOCK;
+        let is_round = (1..=PERMUTATION).contains(&row);
+        let round_constant = if is_round {
+            ROUND_CONSTANTS[row - 1]
+        } else {
+            0
+        };
+        for (name, column, value) in array::IntoIter::new([
+            ("q_enable", self.q_enable, true),
+            ("q_first", self.q_first, offset == 0),
+            ("q_absorb", self.q_absorb, row == 0),
+            ("q_round", self.q_round, is_round),
+            ("q_output", self.q_output, row == ROWS_PER_BLOCK - 1),
+        ])
+        .chain(
+            self.round_constant.iter().zip(ROUND_CONSTANT_BITS).map(
+                |(column, bit)| {
+                    ("round constant", *column, round_constant >> bit & 1 == 1)
+                },
+            ),
+        ) {
+            region.assign_fixed(
+                || name,
+                column,
+                offset,
+                || Ok(F::from(value as u64)),
+            )?;
+        }
+
+        for (x, lanes) in state.iter().enumerate() {
+            for (y, lane) in lanes.iter().enumerate() {
+                for z in 0..64 {
+                    region.assign_advice(
+                        || "state",
+                        self.state[bit_idx(x, y, z)],
+                        offset,
+                        || Ok(F::from(lane >> z & 1)),
+                    )?;
+                }
+            }
+            for z in 0..64 {
+                for (columns, word) in [(&self.c, c[x]), (&self.d, d[x])] {
+                    region.assign_advice(
+                        || "theta",
+                        columns[x * 64 + z],
+                        offset,
+                        || Ok(F::from(word >> z & 1)),
+                    )?;
+                }
+            }
+        }
+
+        for ((column, acc_column), (is_data, acc)) in self
+            .is_data
+            .iter()
+            .zip(self.acc.iter())
+            .zip(is_data.into_iter().zip(acc))
+        {
+            region.assign_advice(
+                || "is_data",
+                *column,
+                offset,
+                || Ok(F::from(is_data as u64)),
+            )?;
+            region.assign_advice(|| "acc", *acc_column, offset, || Ok(acc))?;
+        }
+
+        for (name, column, value) in [
+            ("is_first", self.is_first, values[0]),
+            ("is_final", self.is_final, values[1]),
+            ("data_rlc", self.data_rlc, values[2]),
+            ("data_len", self.data_len, values[3]),
+        ] {
+            region.assign_advice(|| name, column, offset, || Ok(value))?;
+        }
+
+        for (column, value) in self
+            .keccak_table
+            .iter()
+            .zip(table_row.unwrap_or([F::zero(); 3]))
+        {
+            region.assign_advice(
+                || "keccak table",
+                *column,
+                offset,
+                || Ok(value),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Config, ROWS_PER_BLOCK};
+    use crate::gadget::evm_word::{encode, r};
+    use ethers_core::utils::keccak256;
+    use halo2::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        dev::MockProver,
+        plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Fixed},
+        poly::Rotation,
+    };
+    use pairing::{arithmetic::FieldExt, bn256::Fr as Fp};
+
+    const MAX_BLOCKS: usize = 8;
+
+    #[derive(Clone, Debug)]
+    struct KeccakCircuitConfig<F> {
+        keccak_circuit: Config<F>,
+        q_lookup: Column<Fixed>,
+        lookup: [Column<Advice>; 3],
+    }
+
+    // Proves the keccak table of `inputs`, and looks up `lookups` in it
+    #[derive(Default)]
+    struct KeccakCircuit<F> {
+        inputs: Vec<Vec<u8>>,
+        lookups: Vec<[F; 3]>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for KeccakCircuit<F> {
+        type Config = KeccakCircuitConfig<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let keccak_table = [(); 3].map(|_| meta.advice_column());
+            let q_lookup = meta.fixed_column();
+            let lookup = [(); 3].map(|_| meta.advice_column());
+
+            meta.lookup_any(|meta| {
+                let q_lookup = meta.query_fixed(q_lookup, Rotation::cur());
+                lookup
+                    .iter()
+                    .zip(keccak_table.iter())
+                    .map(|(value, table)| {
+                        (
+                            q_lookup.clone()
+                                * meta.query_advice(*value, Rotation::cur()),
+                            meta.query_advice(*table, Rotation::cur()),
+                        )
+                    })
+                    .collect()
+            });
+
+            Self::Config {
+                keccak_circuit: Config::configure(meta, r(), keccak_table),
+                q_lookup,
+                lookup,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            config.keccak_circuit.assign(
+                &mut layouter,
+                MAX_BLOCKS,
+                &self.inputs,
+            )?;
+
+            layouter.assign_region(
+                || "lookups",
+                |mut region| {
+                    for (offset, values) in self.lookups.iter().enumerate() {
+                        region.assign_fixed(
+                            || "q_lookup",
+                            config.q_lookup,
+                            offset,
+                            || Ok(F::one()),
+                        )?;
+                        for (column, value) in config.lookup.iter().zip(values)
+                        {
+                            region.assign_advice(
+                                || "lookup",
+                                *column,
+                                offset,
+                                || Ok(*value),
+                            )?;
+                        }
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    fn table_row(input: &[u8], hash: [u8; 32]) -> [Fp; 3] {
+        [
+            encode(input.iter().cloned(), r()),
+            Fp::from(input.len() as u64),
+            encode(hash.iter().cloned(), r()),
+        ]
+    }
+
+    // Proves the hashes of `inputs`, and looks up their keccak hashes, or
+    // `hash` instead of the hash of the first input if it's given
+    fn verify(inputs: Vec<Vec<u8>>, hash: Option<[u8; 32]>, success: bool) {
+        let mut lookups = inputs
+            .iter()
+            .map(|input| table_row(input, keccak256(input)))
+            .collect::<Vec<_>>();
+        if let Some(hash) = hash {
+            lookups[0] = table_row(&inputs[0], hash);
+        }
+        lookups.push([Fp::zero(); 3]);
+        let circuit = KeccakCircuit::<Fp> { inputs, lookups };
+
+        let k = (MAX_BLOCKS * ROWS_PER_BLOCK + 10)
+            .next_power_of_two()
+            .trailing_zeros();
+        let prover = MockProver::<Fp>::run(k, &circuit, vec![]).unwrap();
+        assert_eq!(prover.verify().is_ok(), success);
+    }
+
+    #[test]
+    fn keccak_circuit() {
+        // Inputs filling the final block up to the last byte, up to the
+        // padding, and taking more than a block
+        let inputs = [0, 1, 135, 136, 300]
+            .map(|len| (0..len).map(|idx| idx as u8).collect::<Vec<_>>());
+        verify(inputs.to_vec(), None, true);
+        verify(vec![], None, true);
+    }
+
+    #[test]
+    fn keccak_circuit_invalid_hash() {
+        let input = vec![0xab; 200];
+        let mut hash = keccak256(&input);
+        hash[31] ^= 1;
+        verify(vec![input.clone()], Some(hash), false);
+        verify(vec![input, vec![]], Some(keccak256(b"")), false);
+    }
+
+    #[test]
+    fn keccak_circuit_too_many_blocks() {
+        let circuit = KeccakCircuit::<Fp> {
+            inputs: vec![vec![0u8; 136 * MAX_BLOCKS]],
+            lookups: vec![],
+        };
+        assert!(MockProver::<Fp>::run(11, &circuit, vec![]).is_err());
+    }
+}
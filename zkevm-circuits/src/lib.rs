@@ -9,9 +9,16 @@
 #![deny(missing_docs)]
 #![deny(unsafe_code)]
 
+pub mod bytecode_circuit;
 pub mod evm_circuit;
+pub mod exp_circuit;
 pub mod gadget;
+pub mod keccak_circuit;
+pub mod mpt_circuit;
+pub mod prover;
+pub mod public_input_circuit;
 pub mod state_circuit;
+pub mod tx_circuit;
 pub mod util;
 
 #[cfg(test)]
@@ -0,0 +1,77 @@
+//! The MPT circuit implementation.
+
+pub(crate) mod mpt;
+pub(crate) mod trie;
+
+pub use trie::TrieError;
+
+/*
+Example MPT table, with a write of the nonce of the account `0x1000..` and a
+write of its storage slot 1, which updates its storage root:
+
+|  address   |    field    | storage_key |   old_value   |   new_value   |
+-------------------------------------------------------------------------
+| 0x1000..00 | Nonce       |      0      |       1       |       2       |
+| 0x1000..00 | Storage     |      1      |     0x100     |    0x1234     |
+| 0x1000..00 | StorageRoot |      0      |  rlc(root 0)  |  rlc(root 1)  |
+
+Each update takes the rows of the preimage of its key, the address or the
+storage slot, followed by the rows of the nodes of the path of its key, by
+pairs of the node before and after the update, down to its leaf.  A leaf
+missing before or after the update is proven by its insertion into the trie
+without it: the pairs end at the terminal node, where the key is missing,
+followed by the new nodes down to the leaf.  The terminal node is the empty
+root, or a branch without a child at the next nibble of the key, or a leaf or
+an extension whose path diverges from the key, which is split by a new branch
+and moved below it.  The update has a single row in the MPT table, on the
+last row of its leaf:
+
+| kind       | role     | byte | item_idx | value         | node_ref      |
+-------------------------------------------------------------------------
+| Preimage   |          | 0x00 |    0     | 0x00          |               |
+|    ..      |    ..    |  ..  |    ..    |  ..           |      ..       |
+| ListHeader | PairPre  | 0xf9 |    0     | 0             | rlc(pre_root) |   // branch
+|    ..      |    ..    |  ..  |    ..    |  ..           |      ..       |
+| ListHeader | PairPost | 0xf9 |    0     | 0             | rlc(post_root)|   // branch
+|    ..      |    ..    |  ..  |    ..    |  ..           |      ..       |
+| ListHeader | Terminal | 0xf8 |    0     | 0             | rlc(hash)     |   // leaf
+|    ..      |    ..    |  ..  |    ..    |  ..           |      ..       |
+| ListHeader | New      | 0xf8 |    0     | 0             | rlc(hash)     |   // branch
+|    ..      |    ..    |  ..  |    ..    |  ..           |      ..       |
+| ListHeader | Moved    | 0xe2 |    0     | 0             | rlc(node)     |   // leaf
+|    ..      |    ..    |  ..  |    ..    |  ..           |      ..       |
+| ListHeader | New      | 0xf8 |    0     | 0             | rlc(hash)     |   // leaf
+
+The tries without and with the leaf are the tries before and after an
+insertion, the tries after and before a deletion, and the trie of an absent
+leaf, whose values are zero, with a placeholder leaf.  Only the storage slots
+are deleted, when they're set to zero.
+
+Each node is decoded like the payload of a tx in the tx circuit, with the kinds
+and the bounds of its items given by the kind of the node, and its encoding is
+checked to be canonical.  A node is referenced by the modified item of the
+previous node on its side of the update, by the root of its trie, or by the
+other child of the new branch for the moved node.  The reference is the hash
+of the node, checked against the keccak table on its last byte, or the node
+itself when it's shorter than a hash and embedded into its parent.  A node
+after the update only differs from the node before the update by its modified
+item, or by the updated field of a leaf, whose values are the ones of the MPT
+table.  The nibbles of the path are accumulated along the nodes after the
+update into the key, which is the keccak hash of the preimage, and the path of
+the terminal node is accumulated along the new extension, the new branch and
+the moved node.  The balance and the hashes are random linear combinations,
+and the other values are words.
+
+A storage update is followed by the update of the storage root of its account,
+and an account update is applied to the state trie after the previous one, so
+that the first row holds the state root before the updates, and the padding
+rows hold the state root after them.
+
+The keccak table is shared with the keccak circuit, which proves it.  The
+values of each account field and storage slot before its first op and after
+its last op in the state circuit are looked up in the MPT table, and
+conversely each update other than those of the storage roots is looked up in
+the update table of the state circuit, which has a row per account field and
+storage slot.  The updates are built by `StateTrie::update` from the EIP-1186
+proofs of the accessed accounts and slots.
+*/
@@ -0,0 +1,3970 @@
+use super::trie::{
+    key_nibbles, Account, MptField, MptUpdate, Node, UpdateKind, EMPTY_NODE,
+};
+use crate::{
+    gadget::{
+        evm_word::encode,
+        is_zero::{IsZeroChip, IsZeroConfig, IsZeroInstruction},
+    },
+    tx_circuit::tx::{header_kind, HeaderKind},
+};
+use bus_mapping::eth_types::{Address, ToBigEndian, Word, H256};
+use ethers_core::utils::keccak256;
+use halo2::{
+    circuit::{Cell, Layouter, Region},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed},
+    poly::Rotation,
+};
+use pairing::arithmetic::FieldExt;
+use std::array;
+
+// The number of nibbles of a key
+const KEY_NIBBLES: u64 = 64;
+
+// Marks the number of items of a complete node in the item table, after the
+// tags of the fields
+const END_FIELD: u64 = 0x100;
+
+// The maximum length of the list of a node embedded into its parent, whose
+// encoding is shorter than a hash
+const MAX_EMBEDDED_LENGTH: u64 = 30;
+
+/// The kind of a node of a trie, which gives the kinds of its items.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum NodeKind {
+    Branch = 1,
+    Extension,
+    StorageLeaf,
+    AccountLeaf,
+    // The root of an empty trie
+    Empty,
+}
+
+impl NodeKind {
+    // Returns the kinds of the items of the node, with its possible numbers
+    // of items.
+    fn items(&self) -> (&'static [ItemKind], &'static [usize]) {
+        match self {
+            Self::Branch => (&BRANCH_ITEMS, &[17]),
+            Self::Extension => (&EXTENSION_ITEMS, &[2]),
+            // The wrapper of a single byte value is the value
+            Self::StorageLeaf => (&STORAGE_LEAF_ITEMS, &[2, 3]),
+            Self::AccountLeaf => (&ACCOUNT_LEAF_ITEMS, &[6]),
+            Self::Empty => (&[], &[]),
+        }
+    }
+
+    fn is_leaf(&self) -> bool {
+        matches!(self, Self::StorageLeaf | Self::AccountLeaf)
+    }
+}
+
+/// The role of a node in an update.  The path of the key is made of pairs of
+/// a node before the update followed by the same node after the update.  A
+/// leaf missing before or after the update is proven by its insertion: the
+/// path of the trie without the leaf ends at the terminal node, where the key
+/// is missing, and the path of the trie with the leaf is followed by the new
+/// nodes down to the leaf, where the terminal node is split by a new branch
+/// and the rest of its path is moved below the branch.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum NodeRole {
+    PairPre,
+    PairPost,
+    // The empty root, or a leaf or an extension whose path diverges from the
+    // key
+    Terminal,
+    New,
+    Moved,
+}
+
+const NUM_NODE_ROLES: usize = 5;
+
+/// The kind of an item of a node.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct ItemKind {
+    // Whether the content bytes are accumulated into the value in base r
+    // instead of base 256
+    is_rlc: bool,
+    is_path: bool,
+    // The value of a leaf, which is the RLP encoding of the stored value
+    is_wrapper: bool,
+    // The reference to a child, which is empty, a hash, or a node shorter
+    // than a hash
+    is_child: bool,
+    // An integer, which has no leading zero
+    is_int: bool,
+    field: Option<MptField>,
+    // The bounds of the length of the content of the item
+    min_len: u64,
+    max_len: u64,
+}
+
+impl ItemKind {
+    // Returns the base of the value, is_path, is_wrapper, is_field, the tag of
+    // the field, is_child, is_int, the bounds of the length of the item, and
+    // the value of the field in a created account, as in the item table.
+    fn values<F: FieldExt>(&self, r: F) -> [F; 10] {
+        let default = match self.field {
+            Some(MptField::Storage) | None => F::zero(),
+            Some(field) => {
+                field_value(field, &Account::default().get(field), r)
+            }
+        };
+        [
+            if self.is_rlc { r } else { F::from(256) },
+            F::from(self.is_path as u64),
+            F::from(self.is_wrapper as u64),
+            F::from(self.field.is_some() as u64),
+            F::from(self.field.map(|field| field as u64).unwrap_or_default()),
+            F::from(self.is_child as u64),
+            F::from(self.is_int as u64),
+            F::from(self.min_len),
+            F::from(self.max_len),
+            default,
+        ]
+    }
+}
+
+const CHILD: ItemKind = ItemKind {
+    is_rlc: true,
+    is_path: false,
+    is_wrapper: false,
+    is_child: true,
+    is_int: false,
+    field: None,
+    min_len: 0,
+    max_len: 32,
+};
+
+// The value of a branch, which is empty in the tries of hashed keys
+const BRANCH_VALUE: ItemKind = ItemKind {
+    is_child: false,
+    max_len: 0,
+    ..CHILD
+};
+
+const PATH: ItemKind = ItemKind {
+    is_path: true,
+    is_child: false,
+    min_len: 1,
+    max_len: 33,
+    ..CHILD
+};
+
+const fn int_item(field: MptField, is_rlc: bool, min_len: u64) -> ItemKind {
+    ItemKind {
+        is_rlc,
+        is_path: false,
+        is_wrapper: false,
+        is_child: false,
+        is_int: true,
+        field: Some(field),
+        min_len,
+        max_len: 32,
+    }
+}
+
+const fn hash_item(field: MptField) -> ItemKind {
+    ItemKind {
+        is_rlc: true,
+        is_path: false,
+        is_wrapper: false,
+        is_child: false,
+        is_int: false,
+        field: Some(field),
+        min_len: 32,
+        max_len: 32,
+    }
+}
+
+const fn wrapper_item(field: Option<MptField>) -> ItemKind {
+    ItemKind {
+        is_rlc: false,
+        is_path: false,
+        is_wrapper: true,
+        is_child: false,
+        // The wrapper of a single byte value is the value
+        is_int: field.is_some(),
+        field,
+        min_len: 0,
+        max_len: 55,
+    }
+}
+
+const fn branch_items() -> [ItemKind; 17] {
+    let mut items = [CHILD; 17];
+    items[16] = BRANCH_VALUE;
+    items
+}
+
+const BRANCH_ITEMS: [ItemKind; 17] = branch_items();
+
+const EXTENSION_ITEMS: [ItemKind; 2] = [PATH, CHILD];
+
+// A stored value isn't zero
+const STORAGE_LEAF_ITEMS: [ItemKind; 3] = [
+    PATH,
+    wrapper_item(Some(MptField::Storage)),
+    int_item(MptField::Storage, true, 1),
+];
+
+const ACCOUNT_LEAF_ITEMS: [ItemKind; 6] = [
+    PATH,
+    wrapper_item(None),
+    int_item(MptField::Nonce, false, 0),
+    int_item(MptField::Balance, true, 0),
+    hash_item(MptField::StorageRoot),
+    hash_item(MptField::CodeHash),
+];
+
+// Returns the minimum value of the byte following the RLP header `byte` in a
+// canonical encoding: a string of a single byte is above the single bytes,
+// and a long length is above the short ones and has no leading zero.
+fn min_next(byte: u8) -> u64 {
+    match header_kind(byte) {
+        (HeaderKind::ShortString, 1) => 0x80,
+        (HeaderKind::LongString | HeaderKind::LongList, 1) => 56,
+        (HeaderKind::LongString | HeaderKind::LongList, _) => 1,
+        _ => 0,
+    }
+}
+
+/// The kind of a byte of an update.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum RowKind {
+    Preimage,
+    ListHeader,
+    ListLength,
+    ItemHeader,
+    ItemLength,
+    Content,
+}
+
+const NUM_ROW_KINDS: usize = 6;
+
+/// The reference to a node held by its parent, which is the hash of the node,
+/// or the node itself when it's shorter than a hash, as the random linear
+/// combination of its bytes.  The roots of the tries are hashes, and the empty
+/// item of a branch is an empty reference.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct NodeRef<F> {
+    pub(crate) value: F,
+    pub(crate) is_embedded: bool,
+    pub(crate) is_root: bool,
+    pub(crate) is_empty: bool,
+}
+
+impl<F: FieldExt> NodeRef<F> {
+    fn root(value: F) -> Self {
+        Self {
+            value,
+            is_root: true,
+            ..Self::default()
+        }
+    }
+
+    fn values(&self) -> [F; 4] {
+        [
+            self.value,
+            F::from(self.is_embedded as u64),
+            F::from(self.is_root as u64),
+            F::from(self.is_empty as u64),
+        ]
+    }
+}
+
+/// A byte of the preimage of the key or of a node of an update, as laid out
+/// in the MPT rows.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct MptRow<F> {
+    pub(crate) index: usize,
+    pub(crate) byte: u8,
+    pub(crate) kind: RowKind,
+    pub(crate) is_start: bool,
+    pub(crate) is_end: bool,
+    pub(crate) rlc: F,
+    pub(crate) role: Option<NodeRole>,
+    pub(crate) node_kind: Option<NodeKind>,
+    pub(crate) modified_idx: usize,
+    pub(crate) is_nested: bool,
+    pub(crate) is_single: bool,
+    pub(crate) is_long: bool,
+    pub(crate) is_embedded: bool,
+    pub(crate) item_idx: usize,
+    pub(crate) item: Option<ItemKind>,
+    pub(crate) length: u64,
+    pub(crate) len_left: u64,
+    pub(crate) content_left: u64,
+    pub(crate) list_bytes_left: u64,
+    pub(crate) min_next: u64,
+    pub(crate) value: F,
+    pub(crate) item_nonempty: bool,
+    pub(crate) is_sibling_item: bool,
+    pub(crate) children: u64,
+    pub(crate) child: NodeRef<F>,
+    pub(crate) sibling: NodeRef<F>,
+    pub(crate) node_ref: NodeRef<F>,
+    pub(crate) pre_ref: NodeRef<F>,
+    pub(crate) post_ref: NodeRef<F>,
+    pub(crate) sibling_ref: NodeRef<F>,
+    pub(crate) masked: F,
+    pub(crate) masked_len: u64,
+    pub(crate) masked_pre: F,
+    pub(crate) masked_len_pre: u64,
+    pub(crate) key_nibbles: u64,
+    pub(crate) side_nibbles: u64,
+    pub(crate) nibble: u64,
+    pub(crate) key_acc: F,
+    pub(crate) key_len: u64,
+    pub(crate) key_odd: bool,
+    pub(crate) key_hi: u64,
+    pub(crate) side_acc: F,
+    pub(crate) side_len: u64,
+    pub(crate) rest_acc: F,
+    pub(crate) rest_len: u64,
+    pub(crate) terminal_side_acc: F,
+    pub(crate) terminal_side_len: u64,
+    pub(crate) terminal_rest_acc: F,
+    pub(crate) terminal_rest_len: u64,
+    pub(crate) terminal_is_leaf: bool,
+}
+
+impl<F: FieldExt> MptRow<F> {
+    fn new(kind: RowKind) -> Self {
+        Self {
+            index: 0,
+            byte: 0,
+            kind,
+            is_start: false,
+            is_end: false,
+            rlc: F::zero(),
+            role: None,
+            node_kind: None,
+            modified_idx: 0,
+            is_nested: false,
+            is_single: false,
+            is_long: false,
+            is_embedded: false,
+            item_idx: 0,
+            item: None,
+            length: 0,
+            len_left: 0,
+            content_left: 0,
+            list_bytes_left: 0,
+            min_next: 0,
+            value: F::zero(),
+            item_nonempty: false,
+            is_sibling_item: false,
+            children: 0,
+            child: NodeRef::default(),
+            sibling: NodeRef::default(),
+            node_ref: NodeRef::default(),
+            pre_ref: NodeRef::default(),
+            post_ref: NodeRef::default(),
+            sibling_ref: NodeRef::default(),
+            masked: F::zero(),
+            masked_len: 0,
+            masked_pre: F::zero(),
+            masked_len_pre: 0,
+            key_nibbles: 0,
+            side_nibbles: 0,
+            nibble: 0,
+            key_acc: F::zero(),
+            key_len: 0,
+            key_odd: false,
+            key_hi: 0,
+            side_acc: F::zero(),
+            side_len: 0,
+            rest_acc: F::zero(),
+            rest_len: 0,
+            terminal_side_acc: F::zero(),
+            terminal_side_len: 0,
+            terminal_rest_acc: F::zero(),
+            terminal_rest_len: 0,
+            terminal_is_leaf: false,
+        }
+    }
+
+    fn is_item(&self) -> bool {
+        matches!(
+            self.kind,
+            RowKind::ItemHeader | RowKind::ItemLength | RowKind::Content
+        )
+    }
+
+    // The last row of an item, which holds its value
+    fn is_item_end(&self) -> bool {
+        self.is_item() && self.len_left == 0 && self.content_left == 0
+    }
+
+    // The last row of a field of a leaf, which isn't the wrapper of a longer
+    // value
+    fn is_field_end(&self) -> bool {
+        let item = self.item.unwrap_or(CHILD);
+        self.is_item_end()
+            && item.field.is_some()
+            && !(item.is_wrapper && !self.is_single)
+    }
+}
+
+/// An update unrolled into its rows.
+#[derive(Clone, Debug)]
+pub(crate) struct UnrolledUpdate<F> {
+    pub(crate) update: MptUpdate,
+    // The storage slot, as a random linear combination
+    pub(crate) storage_key: F,
+    pub(crate) key_rlc: F,
+    pub(crate) state_root: F,
+    pub(crate) old_root: F,
+    pub(crate) new_root: F,
+    pub(crate) old_value: F,
+    pub(crate) new_value: F,
+    // The roots of the tries without and with the leaf of the key, which are
+    // the same as the old and new roots for a modified leaf
+    pub(crate) pre_root: F,
+    pub(crate) post_root: F,
+    pub(crate) rows: Vec<MptRow<F>>,
+    // The inputs of the hashes looked up by the rows, which are the preimage
+    // of the key and the nodes which aren't embedded
+    pub(crate) hashes: Vec<Vec<u8>>,
+}
+
+impl<F: FieldExt> UnrolledUpdate<F> {
+    fn is_storage(&self) -> bool {
+        self.update.field == MptField::Storage
+    }
+
+    // Returns the values of the columns which are constant over the rows of
+    // the update, ending with the flags of its kind.
+    fn values(&self) -> [F; 16] {
+        let kind = self.update.kind;
+        [
+            F::from(self.is_storage() as u64),
+            F::from(self.update.field as u64),
+            address_value(&self.update.address),
+            self.storage_key,
+            self.key_rlc,
+            self.state_root,
+            self.old_root,
+            self.new_root,
+            self.old_value,
+            self.new_value,
+            self.pre_root,
+            self.post_root,
+            F::from((kind == UpdateKind::Modify) as u64),
+            F::from((kind == UpdateKind::Insert) as u64),
+            F::from((kind == UpdateKind::Delete) as u64),
+            F::from((kind == UpdateKind::Absent) as u64),
+        ]
+    }
+
+    // Returns the root of the state trie after the update.
+    fn post_state_root(&self) -> F {
+        if self.is_storage() {
+            self.state_root
+        } else {
+            self.new_root
+        }
+    }
+}
+
+fn hash_rlc<F: FieldExt>(hash: &H256, r: F) -> F {
+    encode(hash.0.iter().cloned(), r)
+}
+
+fn address_value<F: FieldExt>(address: &Address) -> F {
+    encode(address.0.iter().cloned(), F::from(256))
+}
+
+// Returns the value of `field` in the MPT table, where the nonce is an
+// integer and the other words are random linear combinations, as in the state
+// circuit.
+fn field_value<F: FieldExt>(field: MptField, value: &Word, r: F) -> F {
+    let base = match field {
+        MptField::Nonce => F::from(256),
+        _ => r,
+    };
+    encode(value.to_be_bytes().iter().cloned(), base)
+}
+
+// Appends the rows of the RLP header at `pos` in `node`, based on `row`, and
+// returns the number of content bytes which follow.  The content of a wrapper
+// is the next item, or the list of the next items.
+fn push_header<F: FieldExt>(
+    rows: &mut Vec<MptRow<F>>,
+    node: &[u8],
+    pos: &mut usize,
+    row: MptRow<F>,
+    length_kind: RowKind,
+) -> u64 {
+    let is_wrapper = row.item.map(|item| item.is_wrapper).unwrap_or_default();
+    let byte = node[*pos];
+    let (kind, n) = header_kind(byte);
+    let is_single = kind == HeaderKind::Single;
+    let is_long = matches!(kind, HeaderKind::LongString | HeaderKind::LongList);
+    let mut length = match (is_single, is_long) {
+        (true, _) => 1,
+        (_, true) => 0,
+        _ => n,
+    };
+    let content_left = |length| if is_wrapper { 0 } else { length };
+    rows.push(MptRow {
+        index: *pos,
+        byte,
+        is_single,
+        is_long,
+        length,
+        len_left: if is_long { n } else { 0 },
+        content_left: if is_single || is_long {
+            0
+        } else {
+            content_left(length)
+        },
+        min_next: min_next(byte),
+        ..row.clone()
+    });
+    *pos += 1;
+
+    // The length of a long form follows in big-endian
+    if is_long {
+        for len_left in (0..n).rev() {
+            let byte = node[*pos];
+            length = length * 256 + byte as u64;
+            rows.push(MptRow {
+                index: *pos,
+                byte,
+                kind: length_kind,
+                length,
+                len_left,
+                content_left: content_left(length),
+                ..row.clone()
+            });
+            *pos += 1;
+        }
+    }
+
+    if is_single {
+        0
+    } else {
+        length
+    }
+}
+
+// Appends the rows of `node`, whose item `modified_idx` holds the next node of
+// the path, decoding the value of each item.
+fn push_node<F: FieldExt>(
+    rows: &mut Vec<MptRow<F>>,
+    node: &[u8],
+    node_kind: NodeKind,
+    modified_idx: usize,
+    role: NodeRole,
+    r: F,
+) {
+    let start = rows.len();
+    let node_row = MptRow {
+        role: Some(role),
+        node_kind: Some(node_kind),
+        modified_idx,
+        ..MptRow::new(RowKind::ListHeader)
+    };
+    let mut pos = 0;
+    push_header(rows, node, &mut pos, node_row.clone(), RowKind::ListLength);
+
+    let (items, _) = node_kind.items();
+    let mut item_idx = 0;
+    while pos < node.len() {
+        let item = items[item_idx];
+        let row = MptRow {
+            kind: RowKind::ItemHeader,
+            item_idx,
+            item: Some(item),
+            ..node_row.clone()
+        };
+
+        // A single byte is its own content, and a child shorter than a hash
+        // is the list of its node, whose value starts with its header
+        let header_idx = rows.len();
+        let content_length =
+            push_header(rows, node, &mut pos, row.clone(), RowKind::ItemLength);
+        let header = rows[header_idx].clone();
+        let is_embedded = header.byte >= 0xc0;
+        let row = MptRow {
+            is_embedded,
+            item_nonempty: item.is_child && (is_embedded || header.length > 0),
+            ..row
+        };
+        for header_row in rows[header_idx..].iter_mut() {
+            header_row.is_embedded = row.is_embedded;
+            header_row.item_nonempty = row.item_nonempty;
+        }
+        let mut value = F::zero();
+        if header.is_single || is_embedded {
+            value = F::from(header.byte as u64);
+            rows[header_idx].value = value;
+        }
+
+        // The items of an account follow its wrapper in a nested list
+        if item.is_wrapper && !header.is_single {
+            if node_kind == NodeKind::AccountLeaf {
+                push_header(
+                    rows,
+                    node,
+                    &mut pos,
+                    MptRow {
+                        item_idx,
+                        is_nested: true,
+                        ..node_row.clone()
+                    },
+                    RowKind::ListLength,
+                );
+            }
+            item_idx += 1;
+            continue;
+        }
+
+        let base = if item.is_rlc { r } else { F::from(256) };
+        for content_left in (0..content_length).rev() {
+            let byte = node[pos];
+            value = value * base + F::from(byte as u64);
+            rows.push(MptRow {
+                index: pos,
+                byte,
+                kind: RowKind::Content,
+                length: content_length,
+                content_left,
+                value,
+                ..row.clone()
+            });
+            pos += 1;
+        }
+        item_idx += 1;
+    }
+
+    // The outer list header sets the number of bytes of its items, which are
+    // counted down to the end of the node
+    let mut list_bytes_left = 0;
+    for row in rows[start..].iter_mut() {
+        match row.kind {
+            RowKind::ListHeader | RowKind::ListLength if !row.is_nested => {
+                list_bytes_left = row.content_left;
+            }
+            _ => list_bytes_left -= 1,
+        }
+        row.list_bytes_left = list_bytes_left;
+    }
+    rows.last_mut().unwrap().is_end = true;
+}
+
+// Returns the nodes of `update` in the order of their rows, with their roles:
+// the pairs of nodes before and after the update down to the terminal node,
+// which is the last node of the trie without the leaf unless it's a branch
+// missing the leaf, followed by the new nodes of the trie with the leaf,
+// where the moved node comes before the leaf.
+fn node_roles(update: &MptUpdate) -> Vec<(&[u8], NodeRole)> {
+    let (pre_nodes, post_nodes) = (&update.pre_nodes, &update.post_nodes);
+    let last = Node::decode(pre_nodes.last().unwrap()).unwrap();
+    let num_pairs = match (update.kind, last) {
+        (UpdateKind::Modify, _) | (_, Node::Branch(_)) => pre_nodes.len(),
+        _ => pre_nodes.len() - 1,
+    };
+    let mut nodes = vec![];
+    for (pre, post) in pre_nodes.iter().zip(post_nodes).take(num_pairs) {
+        nodes.push((&pre[..], NodeRole::PairPre));
+        nodes.push((&post[..], NodeRole::PairPost));
+    }
+    if num_pairs < pre_nodes.len() {
+        nodes.push((&pre_nodes[num_pairs][..], NodeRole::Terminal));
+    }
+    let new_nodes = &post_nodes[num_pairs..];
+    for (idx, node) in new_nodes.iter().enumerate() {
+        if idx + 1 == new_nodes.len() {
+            if let Some(moved_node) = &update.moved_node {
+                nodes.push((&moved_node[..], NodeRole::Moved));
+            }
+        }
+        nodes.push((&node[..], NodeRole::New));
+    }
+    nodes
+}
+
+/// Unrolls `update` into the rows of the preimage of its key, followed by the
+/// rows of the nodes of the path of its key before and after the update.
+pub(crate) fn unroll<F: FieldExt>(
+    update: MptUpdate,
+    r: F,
+) -> UnrolledUpdate<F> {
+    let is_storage = update.field == MptField::Storage;
+    let preimage = update.preimage();
+    let key = H256(keccak256(&preimage));
+    let pre_root = hash_rlc(&H256(keccak256(&update.pre_nodes[0])), r);
+    let post_root = hash_rlc(&H256(keccak256(&update.post_nodes[0])), r);
+    let mut rows = vec![];
+
+    // The address is accumulated in base 256, and the storage slot in base r
+    let base = if is_storage { r } else { F::from(256) };
+    let mut value = F::zero();
+    for (index, byte) in preimage.iter().enumerate() {
+        value = value * base + F::from(*byte as u64);
+        rows.push(MptRow {
+            index,
+            byte: *byte,
+            is_end: index + 1 == preimage.len(),
+            value,
+            pre_ref: NodeRef::root(pre_root),
+            post_ref: NodeRef::root(post_root),
+            ..MptRow::new(RowKind::Preimage)
+        });
+    }
+
+    // The index of the modified item of a branch is the next nibble of the
+    // key, below the nodes after the update
+    let nibbles = key_nibbles(&key);
+    let mut depth = 0;
+    for (node, role) in node_roles(&update) {
+        let (node_kind, modified_idx) = match Node::decode(node).unwrap() {
+            Node::Empty => (NodeKind::Empty, 0),
+            Node::Branch(_) => (NodeKind::Branch, nibbles[depth] as usize),
+            Node::Extension(path, _) => {
+                if matches!(role, NodeRole::PairPost | NodeRole::New) {
+                    depth += path.len();
+                }
+                (NodeKind::Extension, 1)
+            }
+            Node::Leaf(..) if is_storage => (NodeKind::StorageLeaf, 0),
+            Node::Leaf(..) => (NodeKind::AccountLeaf, 0),
+        };
+        if node_kind == NodeKind::Branch
+            && matches!(role, NodeRole::PairPost | NodeRole::New)
+        {
+            depth += 1;
+        }
+        push_node(&mut rows, node, node_kind, modified_idx, role, r);
+    }
+
+    // The accumulators and the values carried over the rows follow from the
+    // previous row
+    let mut hashes = vec![preimage.clone()];
+    let mut node_bytes = vec![];
+    let mut prev = MptRow::new(RowKind::Preimage);
+    prev.is_end = true;
+    for row in rows.iter_mut() {
+        row.is_start = prev.is_end;
+        row.rlc = if row.is_start {
+            F::from(row.byte as u64)
+        } else {
+            prev.rlc * r + F::from(row.byte as u64)
+        };
+        if row.kind == RowKind::Preimage {
+            prev = row.clone();
+            continue;
+        }
+
+        let byte = F::from(row.byte as u64);
+        let role = row.role.unwrap();
+        let node_kind = row.node_kind.unwrap();
+        let is_leaf = node_kind.is_leaf();
+        let is_branch = node_kind == NodeKind::Branch;
+        let is_pre = matches!(role, NodeRole::PairPre | NodeRole::Terminal);
+        let is_post = matches!(role, NodeRole::PairPost | NodeRole::New);
+        let item = row.item.unwrap_or(CHILD);
+        let is_content = row.kind == RowKind::Content;
+        let is_modified = row.item_idx == row.modified_idx;
+
+        // The node is referenced by the modified item of the previous node
+        // on its side of the update, or by the sibling of the new branch
+        row.node_ref = match (row.is_start, role) {
+            (false, _) => prev.node_ref,
+            (_, NodeRole::PairPre | NodeRole::Terminal) => prev.pre_ref,
+            (_, NodeRole::PairPost | NodeRole::New) => prev.post_ref,
+            (_, NodeRole::Moved) => prev.sibling_ref,
+        };
+        if row.is_start {
+            node_bytes.clear();
+        }
+        node_bytes.push(row.byte);
+        if row.is_end && !row.node_ref.is_embedded {
+            hashes.push(node_bytes.clone());
+        }
+
+        // The modified item references the next node, and the other nonempty
+        // child of a new branch references the moved node
+        row.is_sibling_item = role == NodeRole::New
+            && is_branch
+            && row.is_item()
+            && item.is_child
+            && !is_modified
+            && row.item_nonempty;
+        let item_ref = NodeRef {
+            value: row.value,
+            is_embedded: row.is_embedded,
+            is_root: false,
+            is_empty: !row.item_nonempty,
+        };
+        row.child = if row.is_item_end() && is_modified && !is_leaf {
+            item_ref
+        } else if row.is_start {
+            NodeRef::default()
+        } else {
+            prev.child
+        };
+        row.sibling = if row.is_item_end() && row.is_sibling_item {
+            item_ref
+        } else if row.is_start {
+            NodeRef::default()
+        } else {
+            prev.sibling
+        };
+        row.children = if row.is_start { 0 } else { prev.children }
+            + (row.kind == RowKind::ItemHeader && row.item_nonempty) as u64;
+        row.pre_ref = if is_pre && row.is_end {
+            row.child
+        } else {
+            prev.pre_ref
+        };
+        row.post_ref = if is_post && row.is_end {
+            row.child
+        } else {
+            prev.post_ref
+        };
+        row.sibling_ref = if role == NodeRole::New && is_branch && row.is_end {
+            row.sibling
+        } else {
+            prev.sibling_ref
+        };
+
+        // The bytes of a node which are left unchanged by the update
+        let (masked_prev, masked_len_prev) = if row.is_start {
+            (F::zero(), 0)
+        } else {
+            (prev.masked, prev.masked_len)
+        };
+        let is_path_byte = item.is_path
+            && (is_content
+                || (row.kind == RowKind::ItemHeader && row.is_single));
+        let is_other_field =
+            row.is_field_end() && item.field != Some(update.field);
+        let (masked, masked_len) = if !is_leaf {
+            if row.is_item() && !is_modified {
+                (masked_prev * r + byte, masked_len_prev + 1)
+            } else {
+                (masked_prev, masked_len_prev)
+            }
+        } else if is_path_byte {
+            (masked_prev * r + byte, masked_len_prev)
+        } else if is_other_field {
+            (masked_prev * r + row.value, masked_len_prev)
+        } else {
+            (masked_prev, masked_len_prev)
+        };
+        row.masked = masked;
+        row.masked_len = masked_len;
+        if role == NodeRole::PairPost && row.is_start {
+            row.masked_pre = prev.masked;
+            row.masked_len_pre = prev.masked_len;
+        } else if role == NodeRole::PairPost {
+            row.masked_pre = prev.masked_pre;
+            row.masked_len_pre = prev.masked_len_pre;
+        }
+
+        // The nibbles of the path of the key are accumulated along the nodes
+        // after the update into the bytes of the key, and the nibbles of the
+        // path split by a new branch are accumulated on its both sides
+        let (hi, lo) = (row.byte as u64 >> 4, row.byte as u64 & 0xf);
+        let is_branch_start = row.is_start && is_branch;
+        let is_path_first =
+            is_path_byte && !(is_content && prev.kind == RowKind::Content);
+        let is_path_rest =
+            is_path_byte && is_content && prev.kind == RowKind::Content;
+        let is_sibling_header =
+            row.kind == RowKind::ItemHeader && row.is_sibling_item;
+        // The flag of an odd path is followed by its first nibble
+        let is_odd = hi == 1 + 2 * is_leaf as u64;
+        let nibbles = is_branch_start as u64
+            + (is_path_first && is_odd) as u64
+            + 2 * is_path_rest as u64
+            + is_sibling_header as u64;
+        row.nibble = if is_branch_start {
+            row.modified_idx as u64
+        } else if is_path_first {
+            lo
+        } else if is_sibling_header {
+            row.item_idx as u64
+        } else {
+            0
+        };
+        let is_key_path = is_post && !is_sibling_header;
+        let is_side_path = matches!(role, NodeRole::Terminal | NodeRole::Moved)
+            || (role == NodeRole::New && node_kind == NodeKind::Extension)
+            || is_sibling_header;
+        row.key_nibbles = if is_key_path { nibbles } else { 0 };
+        row.side_nibbles = if is_side_path { nibbles } else { 0 };
+
+        row.key_len = prev.key_len + row.key_nibbles;
+        let (key_acc, key_odd, key_hi) = match (row.key_nibbles, prev.key_odd) {
+            (0, _) => (prev.key_acc, prev.key_odd, prev.key_hi),
+            (1, false) => (prev.key_acc, true, row.nibble),
+            (1, true) => (
+                prev.key_acc * r + F::from(16 * prev.key_hi + row.nibble),
+                false,
+                0,
+            ),
+            (_, false) => (prev.key_acc * r + byte, false, 0),
+            (_, true) => {
+                (prev.key_acc * r + F::from(16 * prev.key_hi + hi), true, lo)
+            }
+        };
+        row.key_acc = key_acc;
+        row.key_odd = key_odd;
+        row.key_hi = key_hi;
+
+        // The side path restarts at the terminal node, and at the first new
+        // node after it
+        let is_side_start = row.is_start
+            && (role == NodeRole::Terminal
+                || (role == NodeRole::New
+                    && prev.role == Some(NodeRole::Terminal)));
+        let (side_acc, side_len) = if is_side_start {
+            (F::zero(), 0)
+        } else {
+            (prev.side_acc, prev.side_len)
+        };
+        row.side_acc = match row.side_nibbles {
+            0 => side_acc,
+            1 => side_acc * r + F::from(row.nibble),
+            _ => side_acc * r * r + F::from(hi) * r + F::from(lo),
+        };
+        row.side_len = side_len + row.side_nibbles;
+
+        // The bytes of the terminal and moved nodes after their path, and of
+        // the sibling of the new branch
+        let (rest_acc, rest_len) = if row.is_start {
+            (F::zero(), 0)
+        } else {
+            (prev.rest_acc, prev.rest_len)
+        };
+        let is_rest = (matches!(role, NodeRole::Terminal | NodeRole::Moved)
+            && ((row.is_item() && !item.is_path) || row.is_nested))
+            || (row.is_sibling_item && row.is_item());
+        if is_rest {
+            row.rest_acc = rest_acc * r + byte;
+            row.rest_len = rest_len + 1;
+        } else {
+            row.rest_acc = rest_acc;
+            row.rest_len = rest_len;
+        }
+
+        if role == NodeRole::Terminal && row.is_end {
+            row.terminal_side_acc = row.side_acc;
+            row.terminal_side_len = row.side_len;
+            row.terminal_rest_acc = row.rest_acc;
+            row.terminal_rest_len = row.rest_len;
+            row.terminal_is_leaf = is_leaf;
+        } else {
+            row.terminal_side_acc = prev.terminal_side_acc;
+            row.terminal_side_len = prev.terminal_side_len;
+            row.terminal_rest_acc = prev.terminal_rest_acc;
+            row.terminal_rest_len = prev.terminal_rest_len;
+            row.terminal_is_leaf = prev.terminal_is_leaf;
+        }
+
+        prev = row.clone();
+    }
+
+    UnrolledUpdate {
+        storage_key: encode(update.key.to_be_bytes().iter().cloned(), r),
+        key_rlc: hash_rlc(&key, r),
+        state_root: hash_rlc(&update.state_root, r),
+        old_root: hash_rlc(&update.old_root, r),
+        new_root: hash_rlc(&update.new_root, r),
+        old_value: field_value(update.field, &update.old_value, r),
+        new_value: field_value(update.field, &update.new_value, r),
+        pre_root,
+        post_root,
+        update,
+        rows,
+        hashes,
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Config<F> {
+    r: F,
+    q_enable: Column<Fixed>,
+    q_first: Column<Fixed>,
+    q_last: Column<Fixed>,
+    // The columns which are constant over the rows of an update
+    is_storage: Column<Advice>,
+    field: Column<Advice>,
+    address: Column<Advice>,
+    storage_key: Column<Advice>,
+    key_rlc: Column<Advice>,
+    state_root: Column<Advice>,
+    old_root: Column<Advice>,
+    new_root: Column<Advice>,
+    old_value: Column<Advice>,
+    new_value: Column<Advice>,
+    pre_root: Column<Advice>,
+    post_root: Column<Advice>,
+    update_kinds: [Column<Advice>; 4],
+    // The columns of the bytes of the preimages and of the nodes
+    kinds: [Column<Advice>; NUM_ROW_KINDS],
+    padding: Column<Advice>,
+    is_start: Column<Advice>,
+    is_end: Column<Advice>,
+    roles: [Column<Advice>; NUM_NODE_ROLES],
+    index: Column<Advice>,
+    byte: Column<Advice>,
+    hi: Column<Advice>,
+    lo: Column<Advice>,
+    rlc: Column<Advice>,
+    is_branch: Column<Advice>,
+    is_leaf: Column<Advice>,
+    is_empty: Column<Advice>,
+    modified_idx: Column<Advice>,
+    is_nested: Column<Advice>,
+    is_single: Column<Advice>,
+    is_long: Column<Advice>,
+    is_embedded: Column<Advice>,
+    item_idx: Column<Advice>,
+    base: Column<Advice>,
+    is_path: Column<Advice>,
+    is_wrapper: Column<Advice>,
+    is_field: Column<Advice>,
+    item_field: Column<Advice>,
+    is_child: Column<Advice>,
+    is_int: Column<Advice>,
+    min_len: Column<Advice>,
+    max_len: Column<Advice>,
+    item_default: Column<Advice>,
+    length: Column<Advice>,
+    len_left: Column<Advice>,
+    content_left: Column<Advice>,
+    list_bytes_left: Column<Advice>,
+    min_next: Column<Advice>,
+    value: Column<Advice>,
+    item_nonempty: Column<Advice>,
+    is_item_end: Column<Advice>,
+    is_field_end: Column<Advice>,
+    is_sibling_item: Column<Advice>,
+    children: Column<Advice>,
+    children_inv: Column<Advice>,
+    // The references are the value, is_embedded, is_root and is_empty
+    child: [Column<Advice>; 4],
+    sibling: [Column<Advice>; 4],
+    node_ref: [Column<Advice>; 4],
+    pre_ref: [Column<Advice>; 4],
+    post_ref: [Column<Advice>; 4],
+    sibling_ref: [Column<Advice>; 4],
+    masked: Column<Advice>,
+    masked_len: Column<Advice>,
+    masked_pre: Column<Advice>,
+    masked_len_pre: Column<Advice>,
+    key_nibbles: Column<Advice>,
+    side_nibbles: Column<Advice>,
+    nibble: Column<Advice>,
+    key_acc: Column<Advice>,
+    key_len: Column<Advice>,
+    key_odd: Column<Advice>,
+    key_hi: Column<Advice>,
+    side_acc: Column<Advice>,
+    side_len: Column<Advice>,
+    rest_acc: Column<Advice>,
+    rest_len: Column<Advice>,
+    // The side path and the rest of the terminal node, and whether it's a
+    // leaf
+    terminal_values: [Column<Advice>; 5],
+    len_left_is_zero: IsZeroConfig<F>,
+    content_left_is_zero: IsZeroConfig<F>,
+    list_bytes_left_is_zero: IsZeroConfig<F>,
+    is_modified: IsZeroConfig<F>,
+    is_updated: IsZeroConfig<F>,
+    is_storage_root: IsZeroConfig<F>,
+    mpt_table: [Column<Advice>; 5],
+    header_table: [Column<Fixed>; 4],
+    item_table: [Column<Fixed>; 12],
+    byte_table: [Column<Fixed>; 3],
+    keccak_table: [Column<Advice>; 3],
+}
+
+impl<F: FieldExt> Config<F> {
+    /// Configures the constraints proving the content of `mpt_table`, whose
+    /// columns are the address, the tag of the field, the storage key, and
+    /// the values before and after each update.  The hashes of the nodes are
+    /// looked up in `keccak_table`, which is proven by the keccak circuit, and
+    /// the updates other than those of the storage roots in `update_table`,
+    /// which is proven by the state circuit.  The state roots before and
+    /// after the updates are returned by [`Config::assign`], to be copied to
+    /// the public inputs.
+    pub(crate) fn configure(
+        meta: &mut ConstraintSystem<F>,
+        r: F,
+        mpt_table: [Column<Advice>; 5],
+        keccak_table: [Column<Advice>; 3],
+        update_table: [Column<Advice>; 5],
+    ) -> Self {
+        let q_enable = meta.fixed_column();
+        let q_first = meta.fixed_column();
+        let q_last = meta.fixed_column();
+        let is_storage = meta.advice_column();
+        let field = meta.advice_column();
+        let address = meta.advice_column();
+        let storage_key = meta.advice_column();
+        let key_rlc = meta.advice_column();
+        let state_root = meta.advice_column();
+        let old_root = meta.advice_column();
+        let new_root = meta.advice_column();
+        let old_value = meta.advice_column();
+        let new_value = meta.advice_column();
+        let pre_root = meta.advice_column();
+        let post_root = meta.advice_column();
+        let update_kinds = [(); 4].map(|_| meta.advice_column());
+        let kinds = [(); NUM_ROW_KINDS].map(|_| meta.advice_column());
+        let padding = meta.advice_column();
+        let is_start = meta.advice_column();
+        let is_end = meta.advice_column();
+        let roles = [(); NUM_NODE_ROLES].map(|_| meta.advice_column());
+        let index = meta.advice_column();
+        let byte = meta.advice_column();
+        let hi = meta.advice_column();
+        let lo = meta.advice_column();
+        let rlc = meta.advice_column();
+        let is_branch = meta.advice_column();
+        let is_leaf = meta.advice_column();
+        let is_empty = meta.advice_column();
+        let modified_idx = meta.advice_column();
+        let is_nested = meta.advice_column();
+        let is_single = meta.advice_column();
+        let is_long = meta.advice_column();
+        let is_embedded = meta.advice_column();
+        let item_idx = meta.advice_column();
+        let base = meta.advice_column();
+        let is_path = meta.advice_column();
+        let is_wrapper = meta.advice_column();
+        let is_field = meta.advice_column();
+        let item_field = meta.advice_column();
+        let is_child = meta.advice_column();
+        let is_int = meta.advice_column();
+        let min_len = meta.advice_column();
+        let max_len = meta.advice_column();
+        let item_default = meta.advice_column();
+        let length = meta.advice_column();
+        let len_left = meta.advice_column();
+        let len_left_inv = meta.advice_column();
+        let content_left = meta.advice_column();
+        let content_left_inv = meta.advice_column();
+        let list_bytes_left = meta.advice_column();
+        let list_bytes_left_inv = meta.advice_column();
+        let min_next = meta.advice_column();
+        let value = meta.advice_column();
+        let item_nonempty = meta.advice_column();
+        let is_item_end = meta.advice_column();
+        let is_field_end = meta.advice_column();
+        let is_sibling_item = meta.advice_column();
+        let children = meta.advice_column();
+        let children_inv = meta.advice_column();
+        let child = [(); 4].map(|_| meta.advice_column());
+        let sibling = [(); 4].map(|_| meta.advice_column());
+        let node_ref = [(); 4].map(|_| meta.advice_column());
+        let pre_ref = [(); 4].map(|_| meta.advice_column());
+        let post_ref = [(); 4].map(|_| meta.advice_column());
+        let sibling_ref = [(); 4].map(|_| meta.advice_column());
+        let masked = meta.advice_column();
+        let masked_len = meta.advice_column();
+        let masked_pre = meta.advice_column();
+        let masked_len_pre = meta.advice_column();
+        let key_nibbles = meta.advice_column();
+        let side_nibbles = meta.advice_column();
+        let nibble = meta.advice_column();
+        let key_acc = meta.advice_column();
+        let key_len = meta.advice_column();
+        let key_odd = meta.advice_column();
+        let key_hi = meta.advice_column();
+        let side_acc = meta.advice_column();
+        let side_len = meta.advice_column();
+        let rest_acc = meta.advice_column();
+        let rest_len = meta.advice_column();
+        let terminal_values = [(); 5].map(|_| meta.advice_column());
+        let modified_diff_inv = meta.advice_column();
+        let field_diff_inv = meta.advice_column();
+        let storage_root_diff_inv = meta.advice_column();
+
+        // The state roots before and after the updates are copied to the
+        // public inputs of the circuit
+        meta.enable_equality(state_root);
+
+        let header_table = [
+            meta.fixed_column(), // byte
+            meta.fixed_column(), // kind of the header
+            meta.fixed_column(), // length, or number of bytes of the length
+            meta.fixed_column(), // minimum value of the next byte
+        ];
+        let item_table = [
+            meta.fixed_column(), // kind of the node
+            meta.fixed_column(), // index of the item in the node
+            meta.fixed_column(), // base of the value
+            meta.fixed_column(), // is_path
+            meta.fixed_column(), // is_wrapper
+            meta.fixed_column(), // is_field
+            meta.fixed_column(), // tag of the field
+            meta.fixed_column(), // is_child
+            meta.fixed_column(), // is_int
+            meta.fixed_column(), // minimum length
+            meta.fixed_column(), // maximum length
+            meta.fixed_column(), // value of the field in a created account
+        ];
+        let byte_table = [
+            meta.fixed_column(), // byte
+            meta.fixed_column(), // high nibble
+            meta.fixed_column(), // low nibble
+        ];
+
+        // Whether the value of a column, or its difference with another
+        // column, is zero
+        let mut is_zero = |column, other: Option<Column<Advice>>, inv| {
+            IsZeroChip::configure(
+                meta,
+                |meta| meta.query_fixed(q_enable, Rotation::cur()),
+                |meta| {
+                    let value = meta.query_advice(column, Rotation::cur());
+                    match other {
+                        Some(other) => {
+                            value - meta.query_advice(other, Rotation::cur())
+                        }
+                        None => value,
+                    }
+                },
+                inv,
+            )
+        };
+        let len_left_is_zero = is_zero(len_left, None, len_left_inv);
+        let content_left_is_zero =
+            is_zero(content_left, None, content_left_inv);
+        let list_bytes_left_is_zero =
+            is_zero(list_bytes_left, None, list_bytes_left_inv);
+        let is_modified =
+            is_zero(item_idx, Some(modified_idx), modified_diff_inv);
+        let is_updated = is_zero(item_field, Some(field), field_diff_inv);
+        let is_storage_root = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_fixed(q_enable, Rotation::cur()),
+            |meta| {
+                meta.query_advice(field, Rotation::cur())
+                    - Expression::Constant(F::from(
+                        MptField::StorageRoot as u64,
+                    ))
+            },
+            storage_root_diff_inv,
+        );
+        let len_left_zero = len_left_is_zero.is_zero_expression.clone();
+        let content_left_zero = content_left_is_zero.is_zero_expression.clone();
+        let list_bytes_left_zero =
+            list_bytes_left_is_zero.is_zero_expression.clone();
+        let is_modified_item = is_modified.is_zero_expression.clone();
+        let is_updated_field = is_updated.is_zero_expression.clone();
+        let is_storage_root_field = is_storage_root.is_zero_expression.clone();
+
+        // The root of an empty trie
+        let empty_root = hash_rlc(&H256(keccak256([EMPTY_NODE])), r);
+        meta.create_gate("mpt rows", |meta| {
+            let one = Expression::Constant(F::one());
+            let r = Expression::Constant(r);
+            let empty_root = Expression::Constant(empty_root);
+            let constant = |value: u64| Expression::Constant(F::from(value));
+
+            let q_enable = meta.query_fixed(q_enable, Rotation::cur());
+            let q_first = meta.query_fixed(q_first, Rotation::cur());
+            let q_last = meta.query_fixed(q_last, Rotation::cur());
+
+            // Each column is queried at the previous, the current and the next
+            // rows
+            let mut query = |column| {
+                [Rotation::prev(), Rotation::cur(), Rotation::next()]
+                    .map(|at| meta.query_advice(column, at))
+            };
+            let [
+                [is_preimage_prev, _, _, _, _, is_content_prev],
+                [
+                    is_preimage,
+                    is_list_header,
+                    is_list_len,
+                    is_item_header,
+                    is_item_len,
+                    is_content,
+                ],
+                [
+                    is_preimage_next,
+                    is_list_header_next,
+                    is_list_len_next,
+                    is_item_header_next,
+                    is_item_len_next,
+                    is_content_next,
+                ],
+            ] = [0, 1, 2]
+                .map(|at| kinds.map(|column| query(column)[at].clone()));
+            let [is_storage_prev, is_storage, is_storage_next] =
+                query(is_storage);
+            let [_, field, field_next] = query(field);
+            let [_, address, address_next] = query(address);
+            let [_, storage_key, storage_key_next] = query(storage_key);
+            let [_, key_rlc, key_rlc_next] = query(key_rlc);
+            let [_, state_root, state_root_next] = query(state_root);
+            let [_, old_root, old_root_next] = query(old_root);
+            let [_, new_root, new_root_next] = query(new_root);
+            let [_, old_value, old_value_next] = query(old_value);
+            let [_, new_value, new_value_next] = query(new_value);
+            let [_, pre_root, pre_root_next] = query(pre_root);
+            let [_, post_root, post_root_next] = query(post_root);
+            let [
+                [_, is_modify, is_modify_next],
+                [_, is_insert, is_insert_next],
+                [_, is_delete, is_delete_next],
+                [_, is_absent, is_absent_next],
+            ] = update_kinds.map(&mut query);
+            let [_, padding, padding_next] = query(padding);
+            let [_, is_start, _] = query(is_start);
+            let [is_end_prev, is_end, _] = query(is_end);
+            let [pair_pre_prev, pair_pre, _] =
+                query(roles[NodeRole::PairPre as usize]);
+            let [pair_post_prev, pair_post, _] =
+                query(roles[NodeRole::PairPost as usize]);
+            let [terminal_prev, terminal, _] =
+                query(roles[NodeRole::Terminal as usize]);
+            let [new_prev, new, _] = query(roles[NodeRole::New as usize]);
+            let [moved_prev, moved, _] = query(roles[NodeRole::Moved as usize]);
+            let [_, index, index_next] = query(index);
+            let [_, byte, byte_next] = query(byte);
+            let [_, hi, _] = query(hi);
+            let [_, lo, _] = query(lo);
+            let [_, rlc, rlc_next] = query(rlc);
+            let [is_branch_prev, is_branch, _] = query(is_branch);
+            let [is_leaf_prev, is_leaf, _] = query(is_leaf);
+            let [is_empty_prev, is_empty, _] = query(is_empty);
+            let [modified_idx_prev, modified_idx, _] = query(modified_idx);
+            let [_, is_nested, is_nested_next] = query(is_nested);
+            let [_, is_single, _] = query(is_single);
+            let [_, is_long, _] = query(is_long);
+            let [_, is_embedded, is_embedded_next] = query(is_embedded);
+            let [_, item_idx, item_idx_next] = query(item_idx);
+            let [_, base, base_next] = query(base);
+            let [_, is_path, is_path_next] = query(is_path);
+            let [_, is_wrapper, is_wrapper_next] = query(is_wrapper);
+            let [_, is_field, is_field_next] = query(is_field);
+            let [_, item_field, item_field_next] = query(item_field);
+            let [_, is_child, is_child_next] = query(is_child);
+            let [_, is_int, is_int_next] = query(is_int);
+            let [_, min_len, min_len_next] = query(min_len);
+            let [_, max_len, max_len_next] = query(max_len);
+            let [_, item_default, item_default_next] = query(item_default);
+            let [_, length, length_next] = query(length);
+            let [_, len_left, len_left_next] = query(len_left);
+            let [_, content_left, content_left_next] = query(content_left);
+            let [_, list_bytes_left, list_bytes_left_next] =
+                query(list_bytes_left);
+            let [_, value, value_next] = query(value);
+            let [_, item_nonempty, item_nonempty_next] = query(item_nonempty);
+            let [_, is_item_end, _] = query(is_item_end);
+            let [_, is_field_end, _] = query(is_field_end);
+            let [_, is_sibling_item, is_sibling_item_next] =
+                query(is_sibling_item);
+            let [children_prev, children, _] = query(children);
+            let [_, children_inv, _] = query(children_inv);
+            let [
+                [child_prev, child],
+                [sibling_prev, sibling],
+                [node_ref_prev, node_ref],
+                [pre_ref_prev, pre_ref],
+                [post_ref_prev, post_ref],
+                [sibling_ref_prev, sibling_ref],
+            ] = [child, sibling, node_ref, pre_ref, post_ref, sibling_ref].map(
+                |columns| {
+                    let queries = columns.map(&mut query);
+                    [0, 1].map(|at| queries.clone().map(|q| q[at].clone()))
+                },
+            );
+            let [masked_prev, masked, _] = query(masked);
+            let [masked_len_prev, masked_len, _] = query(masked_len);
+            let [masked_pre_prev, masked_pre, _] = query(masked_pre);
+            let [masked_len_pre_prev, masked_len_pre, _] =
+                query(masked_len_pre);
+            let [_, key_nibbles, _] = query(key_nibbles);
+            let [_, side_nibbles, _] = query(side_nibbles);
+            let [_, nibble, _] = query(nibble);
+            let [key_acc_prev, key_acc, _] = query(key_acc);
+            let [key_len_prev, key_len, _] = query(key_len);
+            let [key_odd_prev, key_odd, _] = query(key_odd);
+            let [key_hi_prev, key_hi, _] = query(key_hi);
+            let [side_acc_prev, side_acc, _] = query(side_acc);
+            let [side_len_prev, side_len, _] = query(side_len);
+            let [rest_acc_prev, rest_acc, _] = query(rest_acc);
+            let [rest_len_prev, rest_len, _] = query(rest_len);
+            let [carried_prev, carried] = [0, 1].map(|at| {
+                terminal_values.map(|column| query(column)[at].clone())
+            });
+            let [
+                table_address,
+                table_field,
+                table_storage_key,
+                table_old_value,
+                table_new_value,
+            ] = mpt_table.map(|column| query(column)[1].clone());
+
+            // Rows holding a byte, the ones among them which are followed by
+            // another byte of the same preimage or node, and the last ones
+            let q_row = q_enable.clone() * (one.clone() - padding.clone());
+            let q_next = q_row.clone()
+                * (one.clone() - q_last.clone())
+                * (one.clone() - is_end.clone());
+            let q_end = q_row.clone()
+                * (one.clone() - q_last.clone())
+                * is_end.clone();
+            // The nodes before the update and the terminal node are on the
+            // side of the trie without the leaf, and the nodes after the
+            // update and the new nodes on the side of the trie with the leaf
+            let is_pre = pair_pre.clone() + terminal.clone();
+            let is_post = pair_post.clone() + new.clone();
+            let is_node = is_pre.clone() + is_post.clone() + moved.clone();
+            let q_node = q_enable.clone() * is_node.clone();
+            let q_node_next = q_node.clone()
+                * (one.clone() - q_last.clone())
+                * (one.clone() - is_end.clone());
+            let q_start = q_node.clone() * is_start.clone();
+            let is_extension = one.clone()
+                - is_branch.clone()
+                - is_leaf.clone()
+                - is_empty.clone();
+            let is_extension_prev = one.clone()
+                - is_branch_prev.clone()
+                - is_leaf_prev.clone()
+                - is_empty_prev.clone();
+            let list_part = is_list_header.clone() + is_list_len.clone();
+            let item_header_part = is_item_header.clone() + is_item_len.clone();
+            let item_part = item_header_part.clone() + is_content.clone();
+            let q_header = q_node.clone()
+                * (is_list_header.clone() + is_item_header.clone());
+            // The last row of an update, which is the end of the leaf of the
+            // key after the update, or in the trie with the leaf
+            let update_end = is_end.clone() * is_leaf.clone() * is_post.clone();
+
+            let mut constraints = vec![];
+
+            for value in vec![
+                padding.clone(),
+                is_start.clone(),
+                is_end.clone(),
+                is_storage.clone(),
+                is_branch.clone(),
+                is_leaf.clone(),
+                is_empty.clone(),
+                is_nested.clone(),
+                is_single.clone(),
+                is_long.clone(),
+                is_embedded.clone(),
+                is_path.clone(),
+                is_wrapper.clone(),
+                is_field.clone(),
+                is_child.clone(),
+                is_int.clone(),
+                item_nonempty.clone(),
+                is_item_end.clone(),
+                is_field_end.clone(),
+                is_sibling_item.clone(),
+                key_odd.clone(),
+                carried[4].clone(),
+                pair_pre.clone(),
+                pair_post.clone(),
+                terminal.clone(),
+                new.clone(),
+                moved.clone(),
+                is_modify.clone(),
+                is_insert.clone(),
+                is_delete.clone(),
+                is_absent.clone(),
+            ]
+            .into_iter()
+            .chain(kinds.map(|column| query(column)[1].clone()))
+            .chain(
+                [&child, &sibling, &node_ref, &pre_ref, &post_ref, &sibling_ref]
+                    .iter()
+                    .flat_map(|node_ref| node_ref[1..].to_vec()),
+            ) {
+                constraints.push(
+                    q_enable.clone() * value.clone() * (one.clone() - value),
+                );
+            }
+            constraints.push(
+                q_enable.clone() * is_branch.clone() * is_leaf.clone(),
+            );
+            constraints.push(
+                q_enable.clone()
+                    * (is_branch.clone() + is_leaf.clone())
+                    * is_empty.clone(),
+            );
+
+            // Padding rows are only followed by padding rows, and the last
+            // row is a padding row, which holds the state root after the
+            // updates
+            constraints.push(
+                q_enable.clone()
+                    * (one.clone() - q_last.clone())
+                    * padding.clone()
+                    * (one.clone() - padding_next.clone()),
+            );
+            constraints.push(
+                q_enable.clone()
+                    * (one.clone() - q_last.clone())
+                    * padding.clone()
+                    * (state_root_next.clone() - state_root.clone()),
+            );
+            constraints.push(
+                q_enable.clone()
+                    * q_last.clone()
+                    * (one.clone() - padding.clone()),
+            );
+
+            // Each byte has a single kind, and the first one is the start of
+            // the preimage of a key.  The bytes of the nodes have a single
+            // role.
+            constraints.push(
+                q_row.clone()
+                    * (is_preimage.clone()
+                        + list_part.clone()
+                        + item_part.clone()
+                        - one.clone()),
+            );
+            constraints.push(
+                q_row.clone()
+                    * q_first.clone()
+                    * (one.clone() - is_preimage.clone()),
+            );
+            constraints.push(
+                q_enable.clone()
+                    * (is_node.clone() + padding.clone() + is_preimage.clone()
+                        - one.clone()),
+            );
+
+            // A preimage or a node starts on the first row, or after the last
+            // byte of another one, at index 0, and its bytes are accumulated
+            // into the input of its hash
+            constraints.push(
+                q_enable.clone()
+                    * (is_start.clone()
+                        - q_first.clone()
+                        - (one.clone() - q_first.clone()) * is_end_prev),
+            );
+            constraints
+                .push(q_row.clone() * is_start.clone() * index.clone());
+            constraints.push(
+                q_row.clone()
+                    * is_start.clone()
+                    * (rlc.clone() - byte.clone()),
+            );
+            constraints.push(q_next.clone() * padding_next.clone());
+            constraints.push(
+                q_next.clone()
+                    * (is_preimage_next.clone() - is_preimage.clone()),
+            );
+            constraints.push(
+                q_next.clone()
+                    * (index_next.clone() - index.clone() - one.clone()),
+            );
+            constraints.push(
+                q_next.clone()
+                    * (rlc_next.clone() - rlc.clone() * r.clone()
+                        - byte_next.clone()),
+            );
+
+            // The preimage is followed by the nodes of the path of its key,
+            // down to the leaf ending the update
+            constraints.push(
+                q_end.clone()
+                    * (one.clone() - update_end.clone())
+                    * (padding_next.clone() + is_preimage_next.clone()),
+            );
+            constraints.push(
+                q_end.clone()
+                    * update_end.clone()
+                    * (one.clone()
+                        - padding_next.clone()
+                        - is_preimage_next.clone()),
+            );
+
+            // The preimage is the address, or the storage slot of a storage
+            // update accumulated in base r, and holds the roots of the tries
+            // without and with the leaf of the key
+            let q_preimage = q_row.clone() * is_preimage.clone();
+            let preimage_base = constant(256)
+                + is_storage.clone() * (r.clone() - constant(256));
+            constraints.push(
+                q_preimage.clone()
+                    * is_start.clone()
+                    * (value.clone() - byte.clone()),
+            );
+            constraints.push(
+                q_next.clone()
+                    * is_preimage.clone()
+                    * (value_next.clone() - value.clone() * preimage_base
+                        - byte_next.clone()),
+            );
+            constraints.push(
+                q_preimage.clone()
+                    * is_end.clone()
+                    * (index.clone()
+                        - constant(19)
+                        - constant(12) * is_storage.clone()),
+            );
+            constraints.push(
+                q_preimage.clone()
+                    * is_end.clone()
+                    * (value.clone()
+                        - is_storage.clone() * storage_key.clone()
+                        - (one.clone() - is_storage.clone()) * address.clone()),
+            );
+            for (node_ref, root) in [
+                (pre_ref.clone(), pre_root.clone()),
+                (post_ref.clone(), post_root.clone()),
+            ] {
+                for (column, value) in array::IntoIter::new(node_ref).zip([
+                    root,
+                    constant(0),
+                    one.clone(),
+                    constant(0),
+                ]) {
+                    constraints.push(q_preimage.clone() * (column - value));
+                }
+            }
+            for value in [
+                node_ref[1].clone(),
+                key_acc.clone(),
+                key_len.clone(),
+                key_odd.clone(),
+                key_hi.clone(),
+            ] {
+                constraints.push(q_preimage.clone() * value);
+            }
+
+            // The update takes the same values over its rows, and an account
+            // update is followed by the state trie it's applied to
+            for (cur, next) in [
+                (is_storage.clone(), is_storage_next.clone()),
+                (field.clone(), field_next.clone()),
+                (address.clone(), address_next.clone()),
+                (storage_key.clone(), storage_key_next.clone()),
+                (key_rlc.clone(), key_rlc_next),
+                (state_root.clone(), state_root_next.clone()),
+                (old_root.clone(), old_root_next),
+                (new_root.clone(), new_root_next),
+                (old_value.clone(), old_value_next.clone()),
+                (new_value.clone(), new_value_next.clone()),
+                (pre_root.clone(), pre_root_next),
+                (post_root.clone(), post_root_next),
+                (is_modify.clone(), is_modify_next),
+                (is_insert.clone(), is_insert_next.clone()),
+                (is_delete.clone(), is_delete_next),
+                (is_absent.clone(), is_absent_next.clone()),
+            ] {
+                constraints.push(
+                    q_row.clone()
+                        * (one.clone() - q_last.clone())
+                        * (one.clone() - update_end.clone())
+                        * (next - cur),
+                );
+            }
+            constraints.push(
+                q_row.clone()
+                    * update_end.clone()
+                    * (state_root_next.clone()
+                        - is_storage.clone() * state_root.clone()
+                        - (one.clone() - is_storage.clone())
+                            * new_root.clone()),
+            );
+            constraints.push(
+                q_row.clone()
+                    * (one.clone() - is_storage.clone())
+                    * (old_root.clone() - state_root.clone()),
+            );
+
+            // An update modifies, inserts or deletes its leaf, or proves that
+            // it's absent from the trie, where its values are zero and its
+            // trie is left unchanged.  An account is only deleted by the
+            // write of a zero code hash, which destroys it.  The trie without
+            // the leaf is the trie after a deletion, and the root of the trie
+            // with an absent leaf is dropped.
+            constraints.push(
+                q_row.clone()
+                    * (is_modify.clone()
+                        + is_insert.clone()
+                        + is_delete.clone()
+                        + is_absent.clone()
+                        - one.clone()),
+            );
+            constraints.push(
+                q_row.clone()
+                    * (pre_root.clone()
+                        - old_root.clone()
+                        - is_delete.clone()
+                            * (new_root.clone() - old_root.clone())),
+            );
+            constraints.push(
+                q_row.clone()
+                    * (one.clone() - is_absent.clone())
+                    * (post_root.clone()
+                        - new_root.clone()
+                        - is_delete.clone()
+                            * (old_root.clone() - new_root.clone())),
+            );
+            constraints.push(
+                q_row.clone()
+                    * is_absent.clone()
+                    * (new_root.clone() - old_root.clone()),
+            );
+            constraints.push(
+                q_row.clone()
+                    * (is_insert.clone() + is_absent.clone())
+                    * old_value.clone(),
+            );
+            constraints.push(
+                q_row.clone()
+                    * (is_delete.clone() + is_absent.clone())
+                    * new_value.clone(),
+            );
+            constraints.push(
+                q_row.clone()
+                    * is_delete.clone()
+                    * (one.clone() - is_storage.clone())
+                    * (field.clone() - constant(MptField::CodeHash as u64)),
+            );
+
+            // A storage update has the storage field, and an account update
+            // has one of the fields of an account
+            constraints.push(
+                q_row.clone()
+                    * is_storage.clone()
+                    * (field.clone()
+                        - constant(MptField::Storage as u64)),
+            );
+            constraints.push(
+                q_row.clone()
+                    * (one.clone() - is_storage.clone())
+                    * [
+                        MptField::Nonce,
+                        MptField::Balance,
+                        MptField::CodeHash,
+                        MptField::StorageRoot,
+                    ]
+                    .iter()
+                    .fold(one.clone(), |acc, field_tag| {
+                        acc * (field.clone() - constant(*field_tag as u64))
+                    }),
+            );
+            constraints.push(
+                q_row.clone()
+                    * (one.clone() - is_storage.clone())
+                    * storage_key.clone(),
+            );
+
+            // A storage update is followed by the update of the storage root
+            // of its account, and the storage root is only updated by the
+            // storage updates.  The storage trie of a missing account is
+            // empty, and a slot of it can only be read as zero, unless the
+            // account is inserted, where the storage root is zero before the
+            // insertion.
+            let q_storage_end =
+                q_row.clone() * update_end.clone() * is_storage.clone();
+            let is_missing_next =
+                is_insert_next.clone() + is_absent_next.clone();
+            for value in [
+                padding_next,
+                is_storage_next,
+                field_next - constant(MptField::StorageRoot as u64),
+                address_next - address.clone(),
+                old_value_next
+                    - (one.clone() - is_missing_next.clone())
+                        * old_root.clone(),
+                new_value_next
+                    - (one.clone() - is_absent_next.clone()) * new_root.clone(),
+                is_missing_next * (old_root.clone() - empty_root.clone()),
+                is_absent_next * (one.clone() - is_absent.clone()),
+            ] {
+                constraints.push(q_storage_end.clone() * value);
+            }
+            constraints.push(
+                q_preimage.clone()
+                    * is_start.clone()
+                    * is_storage_root_field.clone()
+                    * (q_first.clone()
+                        + (one.clone() - q_first)
+                            * (one.clone() - is_storage_prev)),
+            );
+
+            // The nodes start with their list header, and share their role,
+            // their kind and the index of their modified item over their
+            // rows.  The empty node is the single byte of an empty string.
+            constraints.push(
+                q_start.clone() * (one.clone() - is_list_header.clone()),
+            );
+            for (cur, prev) in array::IntoIter::new([
+                (pair_pre.clone(), pair_pre_prev.clone()),
+                (pair_post.clone(), pair_post_prev.clone()),
+                (terminal.clone(), terminal_prev.clone()),
+                (new.clone(), new_prev.clone()),
+                (moved.clone(), moved_prev.clone()),
+                (is_branch.clone(), is_branch_prev.clone()),
+                (is_leaf.clone(), is_leaf_prev.clone()),
+                (is_empty.clone(), is_empty_prev.clone()),
+                (modified_idx.clone(), modified_idx_prev.clone()),
+            ])
+            .chain(
+                array::IntoIter::new(node_ref.clone())
+                    .zip(node_ref_prev.clone()),
+            )
+            {
+                constraints.push(
+                    q_node.clone()
+                        * (one.clone() - is_start.clone())
+                        * (cur - prev),
+                );
+            }
+            constraints.push(
+                q_node.clone()
+                    * is_extension.clone()
+                    * (modified_idx.clone() - one.clone()),
+            );
+            constraints.push(
+                q_node.clone()
+                    * is_empty.clone()
+                    * (byte.clone() - constant(0x80)),
+            );
+
+            // The path of the key starts with pairs of a node before the
+            // update followed by the same node after the update, which share
+            // their kind and the index of their modified item.  A modified
+            // leaf ends the pairs, and otherwise the pairs end at a branch
+            // missing the leaf, or at the terminal node where the key is
+            // missing, followed by the new nodes.
+            let pre_ref_empty_prev = pre_ref_prev[3].clone();
+            constraints.push(
+                q_start.clone() * (pair_post.clone() - pair_pre_prev.clone()),
+            );
+            constraints.push(
+                q_start.clone()
+                    * (is_pre.clone()
+                        - is_preimage_prev.clone()
+                        - pair_post_prev.clone()
+                            * (one.clone() - pre_ref_empty_prev.clone())),
+            );
+            constraints.push(
+                q_start.clone()
+                    * (new.clone() + moved.clone()
+                        - terminal_prev.clone()
+                        - new_prev.clone()
+                            * (one.clone() - is_leaf_prev.clone())
+                        - moved_prev.clone()
+                        - pair_post_prev.clone() * pre_ref_empty_prev.clone()),
+            );
+            for (cur, prev) in [
+                (is_branch.clone(), is_branch_prev.clone()),
+                (is_leaf.clone(), is_leaf_prev.clone()),
+                (modified_idx.clone(), modified_idx_prev),
+            ] {
+                constraints.push(
+                    q_start.clone() * pair_post.clone() * (cur - prev),
+                );
+            }
+
+            // The terminal node is the empty root, followed by the new leaf,
+            // or a leaf or an extension split by a new branch, which is below
+            // a new extension for the common part of their paths.  The node
+            // moved below the new branch is a leaf or an extension, followed
+            // by the new leaf.  The child of an extension is a branch.
+            constraints.push(
+                q_node.clone()
+                    * is_empty.clone()
+                    * (one.clone() - terminal.clone()),
+            );
+            constraints.push(
+                q_start.clone()
+                    * is_empty.clone()
+                    * (one.clone() - is_preimage_prev.clone()),
+            );
+            for role in [terminal.clone(), moved.clone()] {
+                constraints.push(q_node.clone() * role * is_branch.clone());
+            }
+            constraints.push(
+                q_start.clone()
+                    * moved.clone()
+                    * (one.clone() - new_prev.clone() * is_branch_prev.clone()),
+            );
+            constraints.push(
+                q_start.clone()
+                    * terminal_prev.clone()
+                    * (is_leaf.clone() - is_empty_prev.clone()),
+            );
+            constraints.push(
+                q_start.clone()
+                    * new_prev.clone()
+                    * is_extension_prev.clone()
+                    * (one.clone() - is_branch.clone()),
+            );
+            constraints.push(
+                q_start.clone()
+                    * new_prev.clone()
+                    * is_branch_prev.clone()
+                    * new.clone()
+                    * (one.clone() - is_leaf.clone()),
+            );
+            constraints.push(
+                q_start.clone()
+                    * moved_prev.clone()
+                    * (one.clone() - is_leaf.clone()),
+            );
+            constraints.push(
+                q_start.clone()
+                    * pair_post_prev.clone()
+                    * pre_ref_empty_prev
+                    * (one.clone() - is_leaf.clone()),
+            );
+            constraints.push(
+                q_start.clone()
+                    * pair_post_prev.clone()
+                    * is_extension_prev
+                    * (one.clone() - is_branch.clone()),
+            );
+
+            // A node ends at the end of its last item, which is the value of a
+            // leaf
+            constraints.push(
+                q_node.clone()
+                    * (is_end.clone()
+                        - item_part.clone() * list_bytes_left_zero.clone()
+                        - is_empty.clone()),
+            );
+            constraints.push(
+                q_node.clone()
+                    * is_end.clone()
+                    * (one.clone() - is_empty.clone())
+                    * (one.clone() - is_item_end.clone()),
+            );
+            constraints.push(
+                q_node.clone()
+                    * is_end.clone()
+                    * is_leaf.clone()
+                    * (one.clone() - is_field_end.clone()),
+            );
+
+            // The kind of the next byte follows from the current one: the
+            // bytes of a long length follow the header, the items follow the
+            // list header, and the content bytes follow the item header until
+            // the end of the item.  The wrapper of the value of an account is
+            // followed by the nested list of its fields.
+            constraints.push(
+                q_node_next.clone()
+                    * list_part.clone()
+                    * (one.clone() - len_left_zero.clone())
+                    * (one.clone() - is_list_len_next.clone()),
+            );
+            constraints.push(
+                q_node_next.clone()
+                    * item_header_part.clone()
+                    * (one.clone() - len_left_zero.clone())
+                    * (one.clone() - is_item_len_next.clone()),
+            );
+            constraints.push(
+                q_node_next.clone()
+                    * list_part.clone()
+                    * len_left_zero.clone()
+                    * (one.clone() - is_item_header_next.clone()),
+            );
+            constraints.push(
+                q_node_next.clone()
+                    * (item_part.clone() * len_left_zero.clone()
+                        - is_item_end.clone())
+                    * (one.clone() - is_content_next.clone()),
+            );
+            let is_account_wrapper = is_leaf.clone()
+                * (one.clone() - is_storage.clone())
+                * is_wrapper.clone();
+            constraints.push(
+                q_node_next.clone()
+                    * is_item_end.clone()
+                    * (is_list_header_next.clone()
+                        + is_item_header_next.clone()
+                        - one.clone()),
+            );
+            constraints.push(
+                q_node_next.clone()
+                    * is_item_end.clone()
+                    * (is_list_header_next.clone()
+                        - is_account_wrapper.clone()),
+            );
+            constraints.push(
+                q_row.clone()
+                    * (is_item_end.clone()
+                        - item_part.clone()
+                            * len_left_zero.clone()
+                            * content_left_zero),
+            );
+            constraints.push(
+                q_row.clone()
+                    * (is_field_end.clone()
+                        - is_item_end.clone()
+                            * is_field.clone()
+                            * (one.clone()
+                                - is_wrapper.clone()
+                                    * (one.clone() - is_single.clone()))),
+            );
+
+            // A header byte sets the length of a short form, the number of
+            // bytes of the length of a long form, and the value of a single
+            // byte item, which has a length of 1.  A wrapper has no content
+            // of its own, and takes the rest of the leaf.
+            constraints.push(
+                q_node.clone()
+                    * (one.clone()
+                        - is_list_header.clone()
+                        - is_item_header.clone())
+                    * is_single.clone(),
+            );
+            constraints.push(
+                q_node.clone() * is_single.clone() * is_long.clone(),
+            );
+            constraints.push(
+                q_node.clone() * is_list_header.clone() * is_single.clone(),
+            );
+            constraints.push(
+                q_node.clone() * is_account_wrapper.clone() * is_single.clone(),
+            );
+            constraints.push(
+                q_node.clone() * list_part.clone() * is_wrapper.clone(),
+            );
+            constraints
+                .push(q_header.clone() * is_long.clone() * length.clone());
+            constraints.push(
+                q_header.clone()
+                    * is_single.clone()
+                    * (length.clone() - one.clone()),
+            );
+            constraints.push(
+                q_header.clone()
+                    * (one.clone() - is_long.clone())
+                    * len_left.clone(),
+            );
+            constraints.push(
+                q_header
+                    * (content_left.clone()
+                        - (one.clone() - is_long.clone() - is_single.clone())
+                            * (one.clone() - is_wrapper.clone())
+                            * length.clone()),
+            );
+            constraints.push(
+                q_node.clone()
+                    * is_wrapper.clone()
+                    * (one.clone() - is_single.clone())
+                    * item_header_part.clone()
+                    * len_left_zero.clone()
+                    * (length.clone() - list_bytes_left.clone()),
+            );
+            constraints.push(
+                q_node.clone()
+                    * is_nested.clone()
+                    * len_left_zero.clone()
+                    * (length.clone() - list_bytes_left.clone()),
+            );
+
+            // A child shorter than a hash is embedded as the short list of its
+            // node, whose value starts with its header, and the other
+            // nonempty children are hashes.  Only the integers and the paths
+            // are single bytes, and only a wrapper is a long string.
+            let q_item_header = q_node.clone() * is_item_header.clone();
+            constraints.push(
+                q_item_header.clone()
+                    * (value.clone()
+                        - (is_single.clone() + is_embedded.clone())
+                            * byte.clone()),
+            );
+            constraints.push(
+                q_node.clone()
+                    * is_embedded.clone()
+                    * (one.clone() - is_child.clone()),
+            );
+            constraints.push(
+                q_node.clone() * is_embedded.clone() * is_long.clone(),
+            );
+            constraints.push(
+                q_item_header.clone()
+                    * is_single.clone()
+                    * (one.clone() - is_int.clone() - is_path.clone()),
+            );
+            constraints.push(
+                q_item_header.clone()
+                    * is_long.clone()
+                    * (one.clone() - is_wrapper.clone()),
+            );
+            constraints.push(
+                q_item_header.clone()
+                    * is_child.clone()
+                    * (one.clone() - is_embedded.clone())
+                    * length.clone()
+                    * (length.clone() - constant(32)),
+            );
+            constraints.push(
+                q_item_header.clone()
+                    * (item_nonempty.clone()
+                        - is_child.clone()
+                            * (is_embedded.clone()
+                                + (one.clone() - is_embedded.clone())
+                                    * length.clone()
+                                    * Expression::Constant(
+                                        F::from(32).invert().unwrap(),
+                                    ))),
+            );
+
+            // The bytes of a long length are accumulated in big-endian, and
+            // the last one sets the length of the content
+            let is_len_next =
+                is_list_len_next.clone() + is_item_len_next.clone();
+            constraints.push(
+                q_node_next.clone()
+                    * is_len_next.clone()
+                    * (length_next.clone()
+                        - length.clone() * constant(256)
+                        - byte_next.clone()),
+            );
+            constraints.push(
+                q_node_next.clone()
+                    * is_len_next
+                    * (len_left_next.clone() - len_left.clone() + one.clone()),
+            );
+            constraints.push(
+                q_node.clone()
+                    * (is_list_len.clone()
+                        + is_item_len.clone()
+                            * (one.clone() - is_wrapper.clone()))
+                    * (content_left.clone() - length.clone()),
+            );
+            constraints.push(
+                q_node.clone()
+                    * is_item_len.clone()
+                    * is_wrapper.clone()
+                    * content_left.clone(),
+            );
+            constraints
+                .push(q_node.clone() * is_item_len.clone() * value.clone());
+
+            // Each content byte decreases the number of bytes left in the
+            // item, and is accumulated into its value
+            constraints.push(
+                q_node.clone() * is_content.clone() * len_left.clone(),
+            );
+            constraints.push(
+                q_node_next.clone()
+                    * is_content_next.clone()
+                    * (content_left_next - content_left.clone() + one.clone()),
+            );
+            constraints.push(
+                q_node_next.clone()
+                    * is_content_next.clone()
+                    * (length_next - length.clone()),
+            );
+            constraints.push(
+                q_node_next.clone()
+                    * is_content_next.clone()
+                    * (value_next - value.clone() * base.clone() - byte_next),
+            );
+
+            // The bytes of an item share its index and its kind, and the index
+            // of the next item is increased, from 0 after the outer list header
+            for (cur, next) in [
+                (item_idx.clone(), item_idx_next.clone()),
+                (base, base_next),
+                (is_path.clone(), is_path_next),
+                (is_wrapper.clone(), is_wrapper_next),
+                (is_field, is_field_next),
+                (item_field, item_field_next),
+                (is_child.clone(), is_child_next),
+                (is_int.clone(), is_int_next),
+                (min_len.clone(), min_len_next),
+                (max_len, max_len_next),
+                (item_default.clone(), item_default_next),
+                (is_embedded.clone(), is_embedded_next),
+                (item_nonempty.clone(), item_nonempty_next),
+                (is_sibling_item.clone(), is_sibling_item_next),
+            ] {
+                constraints.push(
+                    q_node_next.clone()
+                        * (is_item_len_next.clone() + is_content_next.clone())
+                        * (next - cur),
+                );
+            }
+            constraints.push(
+                q_node_next.clone()
+                    * is_item_header_next.clone()
+                    * (item_idx_next.clone()
+                        - (one.clone() - list_part.clone() + is_nested.clone())
+                            * (item_idx.clone() + one.clone())),
+            );
+            constraints.push(
+                q_node_next.clone()
+                    * is_nested_next.clone()
+                    * (item_idx_next - item_idx.clone()),
+            );
+
+            // A list header is nested unless it starts the node, and the
+            // outer list header sets the number of bytes of its items, which
+            // are counted down to the end of the node
+            constraints.push(
+                q_node.clone()
+                    * is_list_header.clone()
+                    * (is_nested.clone() - one.clone() + is_start.clone()),
+            );
+            constraints.push(
+                q_node_next.clone()
+                    * is_list_len_next.clone()
+                    * (is_nested_next.clone() - is_nested.clone()),
+            );
+            constraints.push(
+                q_node.clone()
+                    * (one.clone() - list_part.clone())
+                    * is_nested.clone(),
+            );
+            constraints.push(
+                q_node.clone()
+                    * (list_part.clone() - is_nested.clone())
+                    * (list_bytes_left.clone() - content_left.clone()),
+            );
+            constraints.push(
+                q_node_next.clone()
+                    * (is_item_header_next
+                        + is_item_len_next
+                        + is_content_next.clone()
+                        + is_nested_next)
+                    * (list_bytes_left_next - list_bytes_left.clone()
+                        + one.clone()),
+            );
+
+            // The modified item of a branch or an extension references the
+            // next node on its side of the update, and the other nonempty
+            // child of a new branch references the moved node, or is the
+            // child of the terminal extension.  A new branch has two
+            // children, a branch missing the leaf has at least two, and the
+            // child of an extension isn't empty.
+            let is_modified = is_modified_item;
+            constraints.push(
+                q_item_header.clone()
+                    * (is_sibling_item.clone()
+                        - new.clone()
+                            * is_branch.clone()
+                            * is_child.clone()
+                            * (one.clone() - is_modified.clone())
+                            * item_nonempty.clone()),
+            );
+            let is_modified_end = is_item_end.clone()
+                * is_modified.clone()
+                * (one.clone() - is_leaf.clone());
+            let is_sibling_end = is_item_end.clone() * is_sibling_item.clone();
+            for (cur, prev, item_value) in [
+                (child[0].clone(), child_prev[0].clone(), value.clone()),
+                (child[1].clone(), child_prev[1].clone(), is_embedded.clone()),
+                (
+                    child[3].clone(),
+                    child_prev[3].clone(),
+                    one.clone() - item_nonempty.clone(),
+                ),
+            ] {
+                constraints.push(
+                    q_node.clone()
+                        * (cur
+                            - is_modified_end.clone() * item_value
+                            - (one.clone() - is_modified_end.clone())
+                                * (one.clone() - is_start.clone())
+                                * prev),
+                );
+            }
+            for (cur, prev, item_value) in [
+                (sibling[0].clone(), sibling_prev[0].clone(), value.clone()),
+                (
+                    sibling[1].clone(),
+                    sibling_prev[1].clone(),
+                    is_embedded.clone(),
+                ),
+            ] {
+                constraints.push(
+                    q_node.clone()
+                        * (cur
+                            - is_sibling_end.clone() * item_value
+                            - (one.clone() - is_sibling_end.clone())
+                                * (one.clone() - is_start.clone())
+                                * prev),
+                );
+            }
+            for value in [
+                child[2].clone(),
+                sibling[2].clone(),
+                sibling[3].clone(),
+            ] {
+                constraints.push(q_node.clone() * value);
+            }
+            constraints.push(
+                q_node.clone()
+                    * (children.clone()
+                        - (one.clone() - is_start.clone()) * children_prev
+                        - is_item_header.clone() * item_nonempty.clone()),
+            );
+            constraints.push(
+                q_node.clone()
+                    * new.clone()
+                    * is_branch.clone()
+                    * is_end.clone()
+                    * (children.clone() - constant(2)),
+            );
+            constraints.push(
+                q_node.clone()
+                    * pair_pre.clone()
+                    * is_branch.clone()
+                    * is_end.clone()
+                    * child[3].clone()
+                    * (children.clone()
+                        * (children - one.clone())
+                        * children_inv
+                        - one.clone()),
+            );
+            constraints.push(
+                q_item_header.clone()
+                    * is_extension.clone()
+                    * is_child.clone()
+                    * (one.clone() - item_nonempty.clone()),
+            );
+
+            // A node is referenced by the previous node on its side of the
+            // update, starting from the roots, and the moved node by the new
+            // branch.  A node isn't referenced by an empty item, and a node
+            // embedded into its parent is its reference.
+            for idx in 0..4 {
+                constraints.push(
+                    q_start.clone()
+                        * (node_ref[idx].clone()
+                            - is_pre.clone() * pre_ref_prev[idx].clone()
+                            - is_post.clone() * post_ref_prev[idx].clone()
+                            - moved.clone() * sibling_ref_prev[idx].clone()),
+                );
+                for (is_updated_ref, node_ref, node_ref_prev, item_ref) in [
+                    (
+                        is_pre.clone() * is_end.clone(),
+                        pre_ref[idx].clone(),
+                        pre_ref_prev[idx].clone(),
+                        child[idx].clone(),
+                    ),
+                    (
+                        is_post.clone() * is_end.clone(),
+                        post_ref[idx].clone(),
+                        post_ref_prev[idx].clone(),
+                        child[idx].clone(),
+                    ),
+                    (
+                        new.clone() * is_branch.clone() * is_end.clone(),
+                        sibling_ref[idx].clone(),
+                        sibling_ref_prev[idx].clone(),
+                        sibling[idx].clone(),
+                    ),
+                ] {
+                    constraints.push(
+                        q_node.clone()
+                            * (node_ref
+                                - is_updated_ref.clone() * item_ref
+                                - (one.clone() - is_updated_ref)
+                                    * node_ref_prev),
+                    );
+                }
+            }
+            constraints.push(q_node.clone() * node_ref[3].clone());
+            constraints.push(
+                q_node.clone()
+                    * is_end.clone()
+                    * node_ref[1].clone()
+                    * (rlc.clone() - node_ref[0].clone()),
+            );
+            constraints.push(
+                q_start.clone() * node_ref[1].clone() * is_long.clone(),
+            );
+
+            // The node after the update only differs from the node before the
+            // update by its modified item, or by the value of the updated
+            // field of a leaf: the other items of a branch or an extension
+            // are accumulated with their number of bytes, and the bytes of
+            // the path of a leaf with the values of its other fields
+            let is_path_byte = is_path.clone()
+                * (is_content.clone()
+                    + is_item_header.clone() * is_single.clone());
+            let is_other_field =
+                is_field_end.clone() * (one.clone() - is_updated_field.clone());
+            let is_masked_item =
+                item_part.clone() * (one.clone() - is_modified.clone());
+            let is_masked = (one.clone() - is_leaf.clone())
+                * is_masked_item.clone()
+                + is_leaf.clone()
+                    * (is_path_byte.clone() + is_other_field.clone());
+            let masked_byte = (one.clone() - is_leaf.clone()) * byte.clone()
+                + is_leaf.clone()
+                    * (is_path_byte.clone() * byte.clone()
+                        + is_other_field.clone() * value.clone());
+            let masked_acc =
+                (one.clone() - is_start.clone()) * masked_prev.clone();
+            constraints.push(
+                q_node.clone()
+                    * (masked.clone()
+                        - is_masked.clone()
+                            * (masked_acc.clone() * r.clone() + masked_byte)
+                        - (one.clone() - is_masked) * masked_acc),
+            );
+            constraints.push(
+                q_node.clone()
+                    * (masked_len.clone()
+                        - (one.clone() - is_start.clone())
+                            * masked_len_prev.clone()
+                        - (one.clone() - is_leaf.clone()) * is_masked_item),
+            );
+            for (cur, prev, prev_pre) in [
+                (masked_pre.clone(), masked_prev, masked_pre_prev),
+                (masked_len_pre.clone(), masked_len_prev, masked_len_pre_prev),
+            ] {
+                constraints.push(
+                    q_node.clone()
+                        * pair_post.clone()
+                        * (cur
+                            - is_start.clone() * prev
+                            - (one.clone() - is_start.clone()) * prev_pre),
+                );
+            }
+            let q_pair_end =
+                q_node.clone() * is_end.clone() * pair_post.clone();
+            constraints.push(q_pair_end.clone() * (masked - masked_pre));
+            constraints.push(
+                q_pair_end
+                    * (one.clone() - is_leaf.clone())
+                    * (masked_len - masked_len_pre),
+            );
+
+            // The updated field of a leaf has the values before and after the
+            // update, and the new leaf has the value of the inserted or
+            // deleted leaf.  An inserted account has the default values of
+            // its other fields, and a deleted account has a zero nonce and
+            // balance.
+            constraints.push(
+                q_node.clone()
+                    * is_leaf.clone()
+                    * is_field_end.clone()
+                    * is_updated_field.clone()
+                    * (pair_pre.clone() * (value.clone() - old_value.clone())
+                        + pair_post.clone()
+                            * (value.clone() - new_value.clone())
+                        + new.clone()
+                            * (is_insert.clone()
+                                * (value.clone() - new_value.clone())
+                                + is_delete.clone()
+                                    * (value.clone() - old_value.clone()))),
+            );
+            constraints.push(
+                q_node.clone()
+                    * new.clone()
+                    * is_leaf.clone()
+                    * is_other_field.clone()
+                    * is_insert.clone()
+                    * (value.clone() - item_default),
+            );
+            constraints.push(
+                q_node.clone()
+                    * new.clone()
+                    * is_leaf.clone()
+                    * is_other_field
+                    * is_int.clone()
+                    * is_delete.clone()
+                    * value.clone(),
+            );
+
+            // The nibbles of the path of the key are accumulated along the
+            // nodes after the update into the bytes of the key: the index of
+            // the modified child of a branch, the first nibble of an odd path
+            // after its flag, and both nibbles of the next bytes of a path.
+            // The path split by a new branch, which is the path of the
+            // terminal node, is accumulated on the other side into the paths
+            // of the new extension and of the moved node, around the index of
+            // the other child of the new branch.
+            let is_branch_start = is_branch.clone() * is_start.clone();
+            let is_path_first = is_path.clone()
+                * (is_item_header.clone() * is_single.clone()
+                    + is_content.clone()
+                        * (one.clone() - is_content_prev.clone()));
+            let is_path_rest =
+                is_path.clone() * is_content.clone() * is_content_prev.clone();
+            let is_sibling_header =
+                is_item_header.clone() * is_sibling_item.clone();
+            let is_odd = hi.clone() - constant(2) * is_leaf.clone();
+            constraints.push(
+                q_node.clone()
+                    * is_path_first.clone()
+                    * is_odd.clone()
+                    * (one.clone() - is_odd.clone()),
+            );
+            constraints.push(
+                q_node.clone()
+                    * is_path_first.clone()
+                    * (one.clone() - is_odd.clone())
+                    * lo.clone(),
+            );
+            // The path of an extension isn't empty
+            constraints.push(
+                q_item_header.clone()
+                    * is_extension.clone()
+                    * is_path_first.clone()
+                    * (one.clone() - is_odd.clone()),
+            );
+            let nibbles = is_branch_start.clone()
+                + is_path_first.clone() * is_odd
+                + constant(2) * is_path_rest
+                + is_sibling_header.clone();
+            constraints.push(
+                q_node.clone()
+                    * (key_nibbles.clone()
+                        - (is_post.clone() - is_sibling_header.clone())
+                            * nibbles.clone()),
+            );
+            constraints.push(
+                q_node.clone()
+                    * (side_nibbles.clone()
+                        - (terminal.clone()
+                            + moved.clone()
+                            + new.clone() * is_extension
+                            + is_sibling_header.clone())
+                            * nibbles),
+            );
+            constraints.push(
+                q_node.clone()
+                    * (nibble.clone()
+                        - is_branch_start * modified_idx
+                        - is_path_first * lo.clone()
+                        - is_sibling_header * item_idx),
+            );
+            // The number of nibbles is 0, 1 or 2
+            let adds_one =
+                key_nibbles.clone() * (constant(2) - key_nibbles.clone());
+            let adds_two = key_nibbles.clone()
+                * (key_nibbles.clone() - one.clone())
+                * Expression::Constant(F::from(2).invert().unwrap());
+            constraints.push(
+                q_node.clone()
+                    * key_nibbles.clone()
+                    * (key_nibbles.clone() - one.clone())
+                    * (key_nibbles.clone() - constant(2)),
+            );
+            constraints.push(
+                q_node.clone()
+                    * (key_len.clone() - key_len_prev - key_nibbles),
+            );
+            constraints.push(
+                q_node.clone()
+                    * (key_odd.clone()
+                        - key_odd_prev.clone()
+                        - adds_one.clone()
+                            * (one.clone()
+                                - constant(2) * key_odd_prev.clone())),
+            );
+            constraints.push(
+                q_node.clone()
+                    * (key_hi.clone()
+                        - (one.clone() - adds_one.clone() - adds_two.clone())
+                            * key_hi_prev.clone()
+                        - adds_one.clone()
+                            * (one.clone() - key_odd_prev.clone())
+                            * nibble.clone()
+                        - adds_two.clone() * key_odd_prev.clone() * lo.clone()),
+            );
+            constraints.push(
+                q_node.clone()
+                    * (key_acc.clone()
+                        - key_acc_prev.clone()
+                        - adds_one
+                            * key_odd_prev.clone()
+                            * (key_acc_prev.clone() * (r.clone() - one.clone())
+                                + constant(16) * key_hi_prev.clone()
+                                + nibble.clone())
+                        - adds_two
+                            * (key_acc_prev * (r.clone() - one.clone())
+                                + (one.clone() - key_odd_prev.clone())
+                                    * byte.clone()
+                                + key_odd_prev
+                                    * (constant(16) * key_hi_prev
+                                        + hi.clone()))),
+            );
+
+            // The side path is accumulated in base r by nibble, from the start
+            // of the terminal node, and of the first new node after it
+            let split_prev = [
+                side_acc_prev.clone(),
+                side_len_prev.clone(),
+                rest_acc_prev.clone(),
+                rest_len_prev.clone(),
+            ];
+            let side_start = is_start.clone()
+                * (terminal.clone() + new.clone() * terminal_prev.clone());
+            let side_acc_prev =
+                (one.clone() - side_start.clone()) * side_acc_prev;
+            let side_len_prev = (one.clone() - side_start) * side_len_prev;
+            let side_one =
+                side_nibbles.clone() * (constant(2) - side_nibbles.clone());
+            let side_two = side_nibbles.clone()
+                * (side_nibbles.clone() - one.clone())
+                * Expression::Constant(F::from(2).invert().unwrap());
+            constraints.push(
+                q_node.clone()
+                    * (side_acc.clone()
+                        - side_acc_prev.clone()
+                        - side_one
+                            * (side_acc_prev.clone() * (r.clone() - one.clone())
+                                + nibble)
+                        - side_two
+                            * (side_acc_prev
+                                * (r.clone() * r.clone() - one.clone())
+                                + hi * r.clone()
+                                + lo)),
+            );
+            constraints.push(
+                q_node.clone()
+                    * (side_len.clone() - side_len_prev - side_nibbles),
+            );
+
+            // The rest of the terminal and moved nodes after their path, and
+            // the other child of the new branch, are accumulated over their
+            // bytes
+            let is_rest = (terminal.clone() + moved.clone())
+                * ((one.clone() - is_path) * item_part.clone()
+                    + is_nested.clone())
+                + is_sibling_item.clone() * item_part.clone();
+            constraints.push(
+                q_node.clone()
+                    * (rest_acc.clone()
+                        - (one.clone() - is_start.clone())
+                            * rest_acc_prev
+                            * (one.clone()
+                                + is_rest.clone() * (r - one.clone()))
+                        - is_rest.clone() * byte.clone()),
+            );
+            constraints.push(
+                q_node.clone()
+                    * (rest_len.clone()
+                        - (one.clone() - is_start.clone()) * rest_len_prev
+                        - is_rest),
+            );
+
+            // The side path and the rest of the terminal node are carried to
+            // the new leaf, where they're the ones of the moved node, or of
+            // the new branch whose other child is the child of the terminal
+            // extension
+            let is_terminal_end = terminal.clone() * is_end.clone();
+            for (idx, value) in array::IntoIter::new([
+                side_acc.clone(),
+                side_len.clone(),
+                rest_acc.clone(),
+                rest_len.clone(),
+                is_leaf.clone(),
+            ])
+            .enumerate()
+            {
+                constraints.push(
+                    q_node.clone()
+                        * (carried[idx].clone()
+                            - is_terminal_end.clone() * value
+                            - (one.clone() - is_terminal_end.clone())
+                                * carried_prev[idx].clone()),
+                );
+            }
+            let q_leaf_start = q_start.clone() * new.clone() * is_leaf.clone();
+            let is_split = new_prev.clone() * is_branch_prev.clone()
+                + moved_prev.clone();
+            for (idx, value) in array::IntoIter::new(split_prev).enumerate() {
+                constraints.push(
+                    q_leaf_start.clone()
+                        * is_split.clone()
+                        * (value - carried_prev[idx].clone()),
+                );
+            }
+            constraints.push(
+                q_leaf_start.clone()
+                    * new_prev.clone()
+                    * is_branch_prev.clone()
+                    * carried_prev[4].clone(),
+            );
+            constraints.push(
+                q_leaf_start
+                    * moved_prev.clone()
+                    * (is_leaf_prev.clone() - carried_prev[4].clone()),
+            );
+
+            // The leaf of the key ends the update, after the nodes before the
+            // update for a modified leaf, and after the new nodes otherwise
+            let q_update_end = q_node.clone() * update_end.clone();
+            constraints.push(
+                q_update_end.clone() * (key_len - constant(KEY_NIBBLES)),
+            );
+            constraints.push(q_update_end.clone() * (key_acc - key_rlc));
+            constraints.push(q_update_end * (pair_post.clone() - is_modify));
+
+            // The updates are the rows of the MPT table
+            for (column, value) in [
+                (table_address, address),
+                (table_field, field),
+                (table_storage_key, storage_key),
+                (table_old_value, old_value),
+                (table_new_value, new_value),
+            ] {
+                constraints.push(
+                    q_enable.clone()
+                        * (column - q_row.clone() * update_end.clone() * value),
+                );
+            }
+
+            constraints
+        });
+
+        // A header byte has the kind and the length given by the header
+        // table, and the next byte is above the minimum value given by the
+        // header table, so that the lengths have their shortest form.  The
+        // items of the nodes are lists when they're embedded, and the empty
+        // node is an empty string.
+        meta.lookup_any(|meta| {
+            let one = Expression::Constant(F::one());
+            let two = Expression::Constant(F::from(2));
+            let mut query = |column| meta.query_advice(column, Rotation::cur());
+            let q_row = one.clone() - query(padding);
+            let is_list_header = query(kinds[RowKind::ListHeader as usize]);
+            let is_item_header = query(kinds[RowKind::ItemHeader as usize]);
+            let is_single = query(is_single);
+            let is_long = query(is_long);
+            let kind = is_single.clone()
+                + (one.clone() - is_single.clone())
+                    * (two.clone()
+                        + is_long.clone()
+                        + two
+                            * (is_list_header.clone() - query(is_empty)
+                                + is_item_header.clone() * query(is_embedded)));
+            let n = (one.clone() - is_single)
+                * (is_long.clone() * query(len_left)
+                    + (one - is_long) * query(length));
+            let min_next = query(min_next);
+            let q_header = meta.query_fixed(q_enable, Rotation::cur())
+                * q_row
+                * (is_list_header + is_item_header);
+
+            vec![meta.query_advice(byte, Rotation::cur()), kind, n, min_next]
+                .into_iter()
+                .zip(header_table)
+                .map(|(expr, column)| {
+                    (
+                        q_header.clone() * expr,
+                        meta.query_fixed(column, Rotation::cur()),
+                    )
+                })
+                .collect::<Vec<_>>()
+        });
+
+        // The items of a node have the kinds given by the item table for the
+        // kind of the node, and a complete node has one of its numbers of
+        // items
+        for is_node_end in [false, true] {
+            meta.lookup_any(|meta| {
+                let one = Expression::Constant(F::one());
+                let zero = Expression::Constant(F::zero());
+                let mut query =
+                    |column| meta.query_advice(column, Rotation::cur());
+                let q_node = roles
+                    .iter()
+                    .fold(zero.clone(), |acc, role| acc + query(*role));
+                let is_branch = query(is_branch);
+                let is_leaf = query(is_leaf);
+                let is_empty = query(is_empty);
+                let node_kind = is_branch.clone()
+                    + Expression::Constant(F::from(2))
+                        * (one.clone()
+                            - is_branch
+                            - is_leaf.clone()
+                            - is_empty.clone())
+                    + is_leaf
+                        * (Expression::Constant(F::from(4))
+                            - query(is_storage));
+                let item_idx = query(item_idx);
+                let (selector, values) = if is_node_end {
+                    let mut values = vec![zero.clone(); 12];
+                    values[0] = node_kind;
+                    values[1] = item_idx + one.clone();
+                    values[6] = Expression::Constant(F::from(END_FIELD));
+                    (query(is_end) * (one - is_empty), values)
+                } else {
+                    (
+                        query(kinds[RowKind::ItemHeader as usize]),
+                        vec![
+                            node_kind,
+                            item_idx,
+                            query(base),
+                            query(is_path),
+                            query(is_wrapper),
+                            query(is_field),
+                            query(item_field),
+                            query(is_child),
+                            query(is_int),
+                            query(min_len),
+                            query(max_len),
+                            query(item_default),
+                        ],
+                    )
+                };
+                let q_lookup = meta.query_fixed(q_enable, Rotation::cur())
+                    * q_node
+                    * selector;
+
+                values
+                    .into_iter()
+                    .zip(item_table)
+                    .map(|(expr, column)| {
+                        (
+                            q_lookup.clone() * expr,
+                            meta.query_fixed(column, Rotation::cur()),
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            });
+        }
+
+        // Each byte is split into its nibbles
+        meta.lookup_any(|meta| {
+            let q_row = meta.query_fixed(q_enable, Rotation::cur())
+                * (Expression::Constant(F::one())
+                    - meta.query_advice(padding, Rotation::cur()));
+
+            vec![byte, hi, lo]
+                .into_iter()
+                .zip(byte_table)
+                .map(|(input, column)| {
+                    (
+                        q_row.clone()
+                            * meta.query_advice(input, Rotation::cur()),
+                        meta.query_fixed(column, Rotation::cur()),
+                    )
+                })
+                .collect::<Vec<_>>()
+        });
+
+        // The bounds of the encoding are checked by looking up the bytes of
+        // their differences: the next byte of a header is above its minimum,
+        // the integers have no leading zero, the lengths of the items are
+        // within the bounds of their kind, an embedded node is shorter than
+        // a hash and the other nodes aren't, except for the roots, and the
+        // index of the modified child of a branch is a nibble.
+        for range_check in 0..5 {
+            meta.lookup_any(|meta| {
+                let one = Expression::Constant(F::one());
+                let constant =
+                    |value: u64| Expression::Constant(F::from(value));
+                let byte_next = meta.query_advice(byte, Rotation::next());
+                let is_content_prev = meta.query_advice(
+                    kinds[RowKind::Content as usize],
+                    Rotation::prev(),
+                );
+                let q_enable = meta.query_fixed(q_enable, Rotation::cur());
+                let mut query =
+                    |column| meta.query_advice(column, Rotation::cur());
+                let is_node = roles
+                    .iter()
+                    .fold(constant(0), |acc, role| acc + query(*role));
+                let is_list_header = query(kinds[RowKind::ListHeader as usize]);
+                let is_item_header = query(kinds[RowKind::ItemHeader as usize]);
+                let is_content = query(kinds[RowKind::Content as usize]);
+                let is_single = query(is_single);
+                let is_int = query(is_int);
+                let is_start = query(is_start);
+                let byte = query(byte);
+                let length = query(length);
+                let (table_column, value) = match range_check {
+                    0 => (
+                        byte_table[0],
+                        (one.clone() - query(padding))
+                            * (is_list_header + is_item_header)
+                            * (byte_next - query(min_next)),
+                    ),
+                    1 => (
+                        byte_table[0],
+                        is_node
+                            * (is_item_header.clone()
+                                * (is_single.clone()
+                                    * is_int.clone()
+                                    * (byte.clone() - one.clone())
+                                    + (one.clone() - is_single)
+                                        * (length - query(min_len)))
+                                + is_content
+                                    * (one.clone() - is_content_prev)
+                                    * is_int
+                                    * (byte - one.clone())),
+                    ),
+                    2 => (
+                        byte_table[0],
+                        is_node
+                            * is_item_header
+                            * (one.clone() - is_single)
+                            * (query(max_len) - length),
+                    ),
+                    3 => {
+                        let is_embedded = query(node_ref[1]);
+                        (
+                            byte_table[0],
+                            is_node
+                                * is_start
+                                * (one.clone() - query(is_empty))
+                                * (is_embedded.clone()
+                                    * (constant(MAX_EMBEDDED_LENGTH)
+                                        - length.clone())
+                                    + (one.clone() - is_embedded)
+                                        * (one.clone() - query(node_ref[2]))
+                                        * (one.clone() - query(is_long))
+                                        * (length
+                                            - constant(
+                                                MAX_EMBEDDED_LENGTH + 1,
+                                            ))),
+                        )
+                    }
+                    _ => (
+                        byte_table[2],
+                        is_node
+                            * is_start
+                            * query(is_branch)
+                            * query(modified_idx),
+                    ),
+                };
+
+                vec![(
+                    q_enable * value,
+                    meta.query_fixed(table_column, Rotation::cur()),
+                )]
+            });
+        }
+
+        // The key is the keccak hash of the preimage, and each node is the
+        // preimage of the hash referencing it, unless it's embedded into its
+        // parent
+        meta.lookup_any(|meta| {
+            let one = Expression::Constant(F::one());
+            let mut query = |column| meta.query_advice(column, Rotation::cur());
+            let is_preimage = query(kinds[RowKind::Preimage as usize]);
+            let q_end = (one.clone() - query(padding))
+                * query(is_end)
+                * (one.clone() - query(node_ref[1]));
+            let hash = is_preimage.clone() * query(key_rlc)
+                + (one.clone() - is_preimage) * query(node_ref[0]);
+            let length = query(index) + one;
+            let q_end = meta.query_fixed(q_enable, Rotation::cur()) * q_end;
+
+            vec![meta.query_advice(rlc, Rotation::cur()), length, hash]
+                .into_iter()
+                .zip(keccak_table.iter())
+                .map(|(expr, column)| {
+                    (
+                        q_end.clone() * expr,
+                        meta.query_advice(*column, Rotation::cur()),
+                    )
+                })
+                .collect::<Vec<_>>()
+        });
+
+        // Each update is the update of a storage slot or of an account field
+        // by the ops of the state circuit, except the updates of the storage
+        // roots, which follow the storage updates
+        meta.lookup_any(|meta| {
+            let one = Expression::Constant(F::one());
+            let q_update = meta.query_fixed(q_enable, Rotation::cur())
+                * (one - is_storage_root_field.clone());
+
+            mpt_table
+                .iter()
+                .zip(update_table.iter())
+                .map(|(column, update_column)| {
+                    (
+                        q_update.clone()
+                            * meta.query_advice(*column, Rotation::cur()),
+                        meta.query_advice(*update_column, Rotation::cur()),
+                    )
+                })
+                .collect::<Vec<_>>()
+        });
+
+        Self {
+            r,
+            q_enable,
+            q_first,
+            q_last,
+            is_storage,
+            field,
+            address,
+            storage_key,
+            key_rlc,
+            state_root,
+            old_root,
+            new_root,
+            old_value,
+            new_value,
+            pre_root,
+            post_root,
+            update_kinds,
+            kinds,
+            padding,
+            is_start,
+            is_end,
+            roles,
+            index,
+            byte,
+            hi,
+            lo,
+            rlc,
+            is_branch,
+            is_leaf,
+            is_empty,
+            modified_idx,
+            is_nested,
+            is_single,
+            is_long,
+            is_embedded,
+            item_idx,
+            base,
+            is_path,
+            is_wrapper,
+            is_field,
+            item_field,
+            is_child,
+            is_int,
+            min_len,
+            max_len,
+            item_default,
+            length,
+            len_left,
+            content_left,
+            list_bytes_left,
+            min_next,
+            value,
+            item_nonempty,
+            is_item_end,
+            is_field_end,
+            is_sibling_item,
+            children,
+            children_inv,
+            child,
+            sibling,
+            node_ref,
+            pre_ref,
+            post_ref,
+            sibling_ref,
+            masked,
+            masked_len,
+            masked_pre,
+            masked_len_pre,
+            key_nibbles,
+            side_nibbles,
+            nibble,
+            key_acc,
+            key_len,
+            key_odd,
+            key_hi,
+            side_acc,
+            side_len,
+            rest_acc,
+            rest_len,
+            terminal_values,
+            len_left_is_zero,
+            content_left_is_zero,
+            list_bytes_left_is_zero,
+            is_modified,
+            is_updated,
+            is_storage_root,
+            mpt_table,
+            header_table,
+            item_table,
+            byte_table,
+            keccak_table,
+        }
+    }
+
+    /// Load the header table, which gives the kind and the length of the RLP
+    /// header starting with each byte, with the minimum value of the next
+    /// byte, the item table, which gives the items of each kind of node, and
+    /// the byte table, which gives the nibbles of each byte.
+    pub(crate) fn load(
+        &self,
+        layouter: &mut impl Layouter<F>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "header table",
+            |mut region| {
+                // The noop row is followed by a row per byte
+                let rows = vec![[F::zero(); 4]].into_iter().chain(
+                    (0..=u8::MAX).map(|byte| {
+                        let (kind, n) = header_kind(byte);
+                        [
+                            F::from(byte as u64),
+                            F::from(kind as u64),
+                            F::from(n),
+                            F::from(min_next(byte)),
+                        ]
+                    }),
+                );
+                for (offset, row) in rows.enumerate() {
+                    for (column, value) in self.header_table.iter().zip(row) {
+                        region.assign_fixed(
+                            || "header table",
+                            *column,
+                            offset,
+                            || Ok(value),
+                        )?;
+                    }
+                }
+                Ok(())
+            },
+        )?;
+
+        layouter.assign_region(
+            || "item table",
+            |mut region| {
+                let mut rows = vec![[F::zero(); 12]];
+                for node_kind in [
+                    NodeKind::Branch,
+                    NodeKind::Extension,
+                    NodeKind::StorageLeaf,
+                    NodeKind::AccountLeaf,
+                ] {
+                    let (items, num_items) = node_kind.items();
+                    let node_kind = F::from(node_kind as u64);
+                    for (idx, item) in items.iter().enumerate() {
+                        let mut row = array::IntoIter::new([
+                            node_kind,
+                            F::from(idx as u64),
+                        ])
+                        .chain(item.values(self.r));
+                        rows.push([(); 12].map(|_| row.next().unwrap()));
+                    }
+                    for num_items in num_items {
+                        let mut row = [F::zero(); 12];
+                        row[0] = node_kind;
+                        row[1] = F::from(*num_items as u64);
+                        row[6] = F::from(END_FIELD);
+                        rows.push(row);
+                    }
+                }
+                for (offset, row) in rows.into_iter().enumerate() {
+                    for (column, value) in self.item_table.iter().zip(row) {
+                        region.assign_fixed(
+                            || "item table",
+                            *column,
+                            offset,
+                            || Ok(value),
+                        )?;
+                    }
+                }
+                Ok(())
+            },
+        )?;
+
+        layouter.assign_region(
+            || "byte table",
+            |mut region| {
+                // The row of the byte 0 is the noop row
+                for byte in 0..=u8::MAX {
+                    for (column, value) in self.byte_table.iter().zip([
+                        byte,
+                        byte >> 4,
+                        byte & 0xf,
+                    ]) {
+                        region.assign_fixed(
+                            || "byte table",
+                            *column,
+                            byte as usize,
+                            || Ok(F::from(value as u64)),
+                        )?;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// Assign the rows of `updates` to the state trie of root `state_root`,
+    /// followed by at least one padding row up to `size` rows.  Returns the
+    /// cells of the state roots on the first and the last rows, which are the
+    /// roots before and after the updates.
+    pub(crate) fn assign(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        size: usize,
+        state_root: F,
+        updates: &[UnrolledUpdate<F>],
+    ) -> Result<[Cell; 2], Error> {
+        let is_zero_chips = [
+            &self.len_left_is_zero,
+            &self.content_left_is_zero,
+            &self.list_bytes_left_is_zero,
+            &self.is_modified,
+            &self.is_updated,
+            &self.is_storage_root,
+        ]
+        .map(|config| IsZeroChip::construct(config.clone()));
+        // The last row is a padding row
+        let num_rows: usize =
+            updates.iter().map(|update| update.rows.len()).sum();
+        if num_rows >= size {
+            return Err(Error::Synthesis);
+        }
+
+        layouter.assign_region(
+            || "mpt rows",
+            |mut region| {
+                let mut roots = Vec::new();
+                let mut offset = 0;
+                for update in updates.iter() {
+                    for row in update.rows.iter() {
+                        let root = self.assign_row(
+                            &mut region,
+                            offset,
+                            size,
+                            &is_zero_chips,
+                            update.values(),
+                            row,
+                            false,
+                        )?;
+                        if offset == 0 {
+                            roots.push(root);
+                        }
+                        offset += 1;
+                    }
+                }
+
+                // The padding rows hold the state root after the updates, and
+                // the first one follows the end of the last update
+                let mut values = [F::zero(); 16];
+                values[5] = updates
+                    .last()
+                    .map(|update| update.post_state_root())
+                    .unwrap_or(state_root);
+                let padding_start = offset;
+                for offset in offset..size {
+                    let padding = MptRow {
+                        is_start: offset == padding_start,
+                        ..MptRow::new(RowKind::Preimage)
+                    };
+                    let root = self.assign_row(
+                        &mut region,
+                        offset,
+                        size,
+                        &is_zero_chips,
+                        values,
+                        &padding,
+                        true,
+                    )?;
+                    if offset == 0 || offset + 1 == size {
+                        roots.push(root);
+                    }
+                }
+
+                Ok([roots[0], roots[roots.len() - 1]])
+            },
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn assign_row(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        size: usize,
+        is_zero_chips: &[IsZeroChip<F>; 6],
+        values: [F; 16],
+        row: &MptRow<F>,
+        is_padding: bool,
+    ) -> Result<Cell, Error> {
+        for (name, column, value) in [
+            ("q_enable", self.q_enable, true),
+            ("q_first", self.q_first, offset == 0),
+            ("q_last", self.q_last, offset + 1 == size),
+        ] {
+            region.assign_fixed(
+                || name,
+                column,
+                offset,
+                || Ok(F::from(value as u64)),
+            )?;
+        }
+
+        let update_cells = [
+            self.is_storage,
+            self.field,
+            self.address,
+            self.storage_key,
+            self.key_rlc,
+            self.state_root,
+            self.old_root,
+            self.new_root,
+            self.old_value,
+            self.new_value,
+            self.pre_root,
+            self.post_root,
+        ]
+        .iter()
+        .chain(self.update_kinds.iter())
+        .zip(values)
+        .map(|(column, value)| {
+            region.assign_advice(|| "update", *column, offset, || Ok(value))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+        let item_values = row
+            .item
+            .map(|item| item.values(self.r))
+            .unwrap_or([F::zero(); 10]);
+        let [base, is_path, is_wrapper, is_field, item_field, ..] = item_values;
+        let [.., is_child, is_int, min_len, max_len, item_default] =
+            item_values;
+        let node_kind = row.node_kind;
+
+        let kinds = [
+            RowKind::Preimage,
+            RowKind::ListHeader,
+            RowKind::ListLength,
+            RowKind::ItemHeader,
+            RowKind::ItemLength,
+            RowKind::Content,
+        ]
+        .map(|kind| F::from((!is_padding && row.kind == kind) as u64));
+        for (column, value) in self.kinds.iter().zip(kinds) {
+            region.assign_advice(|| "kind", *column, offset, || Ok(value))?;
+        }
+        let roles = [
+            NodeRole::PairPre,
+            NodeRole::PairPost,
+            NodeRole::Terminal,
+            NodeRole::New,
+            NodeRole::Moved,
+        ]
+        .map(|role| F::from((row.role == Some(role)) as u64));
+        for (column, value) in self.roles.iter().zip(roles) {
+            region.assign_advice(|| "role", *column, offset, || Ok(value))?;
+        }
+        for (name, columns, node_ref) in [
+            ("child", self.child, row.child),
+            ("sibling", self.sibling, row.sibling),
+            ("node_ref", self.node_ref, row.node_ref),
+            ("pre_ref", self.pre_ref, row.pre_ref),
+            ("post_ref", self.post_ref, row.post_ref),
+            ("sibling_ref", self.sibling_ref, row.sibling_ref),
+        ] {
+            for (column, value) in columns.iter().zip(node_ref.values()) {
+                region.assign_advice(|| name, *column, offset, || Ok(value))?;
+            }
+        }
+        for (column, value) in self.terminal_values.iter().zip([
+            row.terminal_side_acc,
+            F::from(row.terminal_side_len),
+            row.terminal_rest_acc,
+            F::from(row.terminal_rest_len),
+            F::from(row.terminal_is_leaf as u64),
+        ]) {
+            region.assign_advice(
+                || "terminal",
+                *column,
+                offset,
+                || Ok(value),
+            )?;
+        }
+
+        let [len_left, content_left, list_bytes_left] =
+            [row.len_left, row.content_left, row.list_bytes_left].map(F::from);
+        let item_idx = F::from(row.item_idx as u64);
+        let modified_idx = F::from(row.modified_idx as u64);
+        let children = F::from(row.children);
+        let from_bool = |value: bool| F::from(value as u64);
+        for (name, column, value) in [
+            ("padding", self.padding, from_bool(is_padding)),
+            ("is_start", self.is_start, from_bool(row.is_start)),
+            ("is_end", self.is_end, from_bool(row.is_end)),
+            ("index", self.index, F::from(row.index as u64)),
+            ("byte", self.byte, F::from(row.byte as u64)),
+            ("hi", self.hi, F::from(row.byte as u64 >> 4)),
+            ("lo", self.lo, F::from(row.byte as u64 & 0xf)),
+            ("rlc", self.rlc, row.rlc),
+            (
+                "is_branch",
+                self.is_branch,
+                from_bool(node_kind == Some(NodeKind::Branch)),
+            ),
+            (
+                "is_leaf",
+                self.is_leaf,
+                from_bool(node_kind.map(|kind| kind.is_leaf()) == Some(true)),
+            ),
+            (
+                "is_empty",
+                self.is_empty,
+                from_bool(node_kind == Some(NodeKind::Empty)),
+            ),
+            ("modified_idx", self.modified_idx, modified_idx),
+            ("is_nested", self.is_nested, from_bool(row.is_nested)),
+            ("is_single", self.is_single, from_bool(row.is_single)),
+            ("is_long", self.is_long, from_bool(row.is_long)),
+            ("is_embedded", self.is_embedded, from_bool(row.is_embedded)),
+            ("item_idx", self.item_idx, item_idx),
+            ("base", self.base, base),
+            ("is_path", self.is_path, is_path),
+            ("is_wrapper", self.is_wrapper, is_wrapper),
+            ("is_field", self.is_field, is_field),
+            ("item_field", self.item_field, item_field),
+            ("is_child", self.is_child, is_child),
+            ("is_int", self.is_int, is_int),
+            ("min_len", self.min_len, min_len),
+            ("max_len", self.max_len, max_len),
+            ("item_default", self.item_default, item_default),
+            ("length", self.length, F::from(row.length)),
+            ("len_left", self.len_left, len_left),
+            ("content_left", self.content_left, content_left),
+            ("list_bytes_left", self.list_bytes_left, list_bytes_left),
+            ("min_next", self.min_next, F::from(row.min_next)),
+            ("value", self.value, row.value),
+            (
+                "item_nonempty",
+                self.item_nonempty,
+                from_bool(row.item_nonempty),
+            ),
+            (
+                "is_item_end",
+                self.is_item_end,
+                from_bool(row.is_item_end()),
+            ),
+            (
+                "is_field_end",
+                self.is_field_end,
+                from_bool(row.is_field_end()),
+            ),
+            (
+                "is_sibling_item",
+                self.is_sibling_item,
+                from_bool(row.is_sibling_item),
+            ),
+            ("children", self.children, children),
+            (
+                "children_inv",
+                self.children_inv,
+                (children * (children - F::one()))
+                    .invert()
+                    .unwrap_or(F::zero()),
+            ),
+            ("masked", self.masked, row.masked),
+            ("masked_len", self.masked_len, F::from(row.masked_len)),
+            ("masked_pre", self.masked_pre, row.masked_pre),
+            (
+                "masked_len_pre",
+                self.masked_len_pre,
+                F::from(row.masked_len_pre),
+            ),
+            ("key_nibbles", self.key_nibbles, F::from(row.key_nibbles)),
+            ("side_nibbles", self.side_nibbles, F::from(row.side_nibbles)),
+            ("nibble", self.nibble, F::from(row.nibble)),
+            ("key_acc", self.key_acc, row.key_acc),
+            ("key_len", self.key_len, F::from(row.key_len)),
+            ("key_odd", self.key_odd, from_bool(row.key_odd)),
+            ("key_hi", self.key_hi, F::from(row.key_hi)),
+            ("side_acc", self.side_acc, row.side_acc),
+            ("side_len", self.side_len, F::from(row.side_len)),
+            ("rest_acc", self.rest_acc, row.rest_acc),
+            ("rest_len", self.rest_len, F::from(row.rest_len)),
+        ] {
+            region.assign_advice(|| name, column, offset, || Ok(value))?;
+        }
+        for (chip, value) in is_zero_chips.iter().zip([
+            len_left,
+            content_left,
+            list_bytes_left,
+            item_idx - modified_idx,
+            item_field - values[1],
+            values[1] - F::from(MptField::StorageRoot as u64),
+        ]) {
+            chip.assign(region, offset, Some(value))?;
+        }
+
+        // The last row of an update is its row of the MPT table
+        let is_update_end = !is_padding
+            && row.is_end
+            && matches!(row.role, Some(NodeRole::PairPost | NodeRole::New))
+            && node_kind.map(|kind| kind.is_leaf()) == Some(true);
+        let [field, address, storage_key, old_value, new_value] =
+            [1, 2, 3, 8, 9].map(|idx| values[idx]);
+        let mpt_row = if is_update_end {
+            [address, field, storage_key, old_value, new_value]
+        } else {
+            [F::zero(); 5]
+        };
+        for (column, value) in self.mpt_table.iter().zip(mpt_row) {
+            region.assign_advice(
+                || "mpt table",
+                *column,
+                offset,
+                || Ok(value),
+            )?;
+        }
+
+        // The cell of the state root
+        Ok(update_cells[5])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{hash_rlc, node_roles, unroll, Config, UnrolledUpdate};
+    use crate::{
+        gadget::evm_word::r,
+        keccak_circuit::keccak,
+        mpt_circuit::trie::{
+            tests::{mock_accounts, mock_state},
+            MptField, MptUpdate, StateTrie, UpdateKind,
+        },
+    };
+    use bus_mapping::eth_types::{Address, Word, H256};
+    use ethers_core::utils::keccak256;
+    use halo2::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        dev::MockProver,
+        plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance},
+    };
+    use pairing::{arithmetic::FieldExt, bn256::Fr as Fp};
+    use std::iter;
+
+    // The number of rows of the MPT circuit, and of blocks of the keccak
+    // circuit, in the tests
+    const SIZE: usize = 8000;
+    const MAX_BLOCKS: usize = 100;
+
+    #[derive(Clone, Debug)]
+    struct MptCircuitConfig<F> {
+        mpt_circuit: Config<F>,
+        keccak_circuit: keccak::Config<F>,
+        update_table: [Column<Advice>; 5],
+        instance: Column<Instance>,
+    }
+
+    #[derive(Default)]
+    struct MptCircuit<F> {
+        // The state root before the updates
+        state_root: F,
+        updates: Vec<UnrolledUpdate<F>>,
+        // The rows of the update table, which are given by the state circuit
+        update_rows: Vec<[F; 5]>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for MptCircuit<F> {
+        type Config = MptCircuitConfig<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let mpt_table = [(); 5].map(|_| meta.advice_column());
+            let keccak_table = [(); 3].map(|_| meta.advice_column());
+            let update_table = [(); 5].map(|_| meta.advice_column());
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            Self::Config {
+                mpt_circuit: Config::configure(
+                    meta,
+                    r(),
+                    mpt_table,
+                    keccak_table,
+                    update_table,
+                ),
+                keccak_circuit: keccak::Config::configure(
+                    meta,
+                    r(),
+                    keccak_table,
+                ),
+                update_table,
+                instance,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let inputs = self
+                .updates
+                .iter()
+                .flat_map(|update| update.hashes.iter().cloned())
+                .collect::<Vec<_>>();
+            layouter.assign_region(
+                || "update table",
+                |mut region| {
+                    let zero_row = [F::zero(); 5];
+                    for (offset, row) in iter::once(&zero_row)
+                        .chain(&self.update_rows)
+                        .enumerate()
+                    {
+                        for (column, value) in
+                            config.update_table.iter().zip(row)
+                        {
+                            region.assign_advice(
+                                || "update table",
+                                *column,
+                                offset,
+                                || Ok(*value),
+                            )?;
+                        }
+                    }
+                    Ok(())
+                },
+            )?;
+            config.mpt_circuit.load(&mut layouter)?;
+            let roots = config.mpt_circuit.assign(
+                &mut layouter,
+                SIZE,
+                self.state_root,
+                &self.updates,
+            )?;
+            for (row, root) in roots.iter().enumerate() {
+                layouter.constrain_instance(*root, config.instance, row)?;
+            }
+            config
+                .keccak_circuit
+                .assign(&mut layouter, MAX_BLOCKS, &inputs)
+        }
+    }
+
+    fn verify(updates: Vec<UnrolledUpdate<Fp>>, success: bool) {
+        let update_rows = update_rows(&updates);
+        let roots = roots(&updates);
+        verify_with(updates, update_rows, roots, success);
+    }
+
+    fn verify_with(
+        updates: Vec<UnrolledUpdate<Fp>>,
+        update_rows: Vec<[Fp; 5]>,
+        roots: [Fp; 2],
+        success: bool,
+    ) {
+        let circuit = MptCircuit {
+            state_root: roots[0],
+            updates,
+            update_rows,
+        };
+        let k = (SIZE + 10).next_power_of_two().trailing_zeros();
+        let prover =
+            MockProver::<Fp>::run(k, &circuit, vec![roots.to_vec()]).unwrap();
+        assert_eq!(prover.verify().is_ok(), success);
+    }
+
+    // Returns the state roots before and after the updates.
+    fn roots(updates: &[UnrolledUpdate<Fp>]) -> [Fp; 2] {
+        [
+            updates.first().unwrap().state_root,
+            updates.last().unwrap().post_state_root(),
+        ]
+    }
+
+    // Returns the rows of the update table of the updates other than those of
+    // the storage roots, as assigned by the state circuit.
+    fn update_rows(updates: &[UnrolledUpdate<Fp>]) -> Vec<[Fp; 5]> {
+        updates
+            .iter()
+            .filter(|update| update.update.field != MptField::StorageRoot)
+            .map(|update| {
+                // The address, field, storage key, and old and new values
+                let values = update.values();
+                [2, 1, 3, 8, 9].map(|idx| values[idx])
+            })
+            .collect()
+    }
+
+    // Returns a write of a nonce, of a storage slot and of a code hash, and a
+    // read of a balance.
+    fn updates() -> Vec<MptUpdate> {
+        let accounts = mock_accounts();
+        let (state_root, proof) = mock_state(&accounts);
+        let mut trie = StateTrie::new(state_root, &[proof]).unwrap();
+        vec![
+            trie.update(
+                accounts[0].address,
+                MptField::Nonce,
+                Word::zero(),
+                Word::from(2),
+            ),
+            trie.update(
+                accounts[3].address,
+                MptField::Storage,
+                Word::from(1),
+                Word::from(0x1234),
+            ),
+            trie.update(
+                accounts[1].address,
+                MptField::CodeHash,
+                Word::zero(),
+                Word::from(0xc0de),
+            ),
+            trie.update(
+                accounts[2].address,
+                MptField::Balance,
+                Word::zero(),
+                accounts[2].balance,
+            ),
+        ]
+        .into_iter()
+        .flat_map(Result::unwrap)
+        .collect()
+    }
+
+    // Returns the reads of a missing account, of a slot of a missing account
+    // and of a missing slot, the creations of accounts by the write of a
+    // balance and of a slot, the insertions and deletions of slots, where
+    // the slots 40364 and 105566 share the first 8 nibbles of their keys, so
+    // that their leaves are embedded into their branch, and the destruction
+    // of an account.
+    fn insert_delete_updates() -> Vec<MptUpdate> {
+        let accounts = mock_accounts();
+        let (state_root, proof) = mock_state(&accounts);
+        let mut trie = StateTrie::new(state_root, &[proof]).unwrap();
+        let address = accounts[0].address;
+        let mut updates = vec![
+            trie.update(
+                Address::from_low_u64_be(0x6000),
+                MptField::Nonce,
+                Word::zero(),
+                Word::zero(),
+            ),
+            trie.update(
+                Address::from_low_u64_be(0x6000),
+                MptField::Storage,
+                Word::from(1),
+                Word::zero(),
+            ),
+            trie.update(
+                Address::from_low_u64_be(0x5000),
+                MptField::Balance,
+                Word::zero(),
+                Word::from(9),
+            ),
+            trie.update(
+                Address::from_low_u64_be(0x7000),
+                MptField::Storage,
+                Word::from(1),
+                Word::from(7),
+            ),
+        ];
+        for (slot, value) in [(40364, 1), (105566, 2), (3, 0), (40364, 0)] {
+            updates.push(trie.update(
+                address,
+                MptField::Storage,
+                Word::from(slot),
+                Word::from(value),
+            ));
+        }
+        updates.extend([
+            trie.update(
+                address,
+                MptField::Storage,
+                Word::from(105566),
+                Word::zero(),
+            ),
+            trie.update(
+                accounts[3].address,
+                MptField::Storage,
+                Word::from(2),
+                Word::zero(),
+            ),
+            trie.update(
+                accounts[3].address,
+                MptField::Storage,
+                Word::from(4),
+                Word::from(5),
+            ),
+        ]);
+        for field in [MptField::Nonce, MptField::Balance, MptField::CodeHash] {
+            updates.push(trie.update(
+                accounts[2].address,
+                field,
+                Word::zero(),
+                Word::zero(),
+            ));
+        }
+        updates.into_iter().flat_map(Result::unwrap).collect()
+    }
+
+    fn unrolled(updates: Vec<MptUpdate>) -> Vec<UnrolledUpdate<Fp>> {
+        updates
+            .into_iter()
+            .map(|update| unroll(update, r()))
+            .collect()
+    }
+
+    // Splits the updates into runs which fit in the rows of the circuit,
+    // keeping each storage update with the update of its storage root.
+    fn runs(updates: Vec<UnrolledUpdate<Fp>>) -> Vec<Vec<UnrolledUpdate<Fp>>> {
+        let mut runs: Vec<Vec<UnrolledUpdate<Fp>>> = vec![vec![]];
+        let mut num_rows = 0;
+        let mut updates = updates.into_iter().peekable();
+        while let Some(update) = updates.next() {
+            let mut group = vec![update];
+            if group[0].update.field == MptField::Storage {
+                group.extend(updates.next());
+            }
+            let group_rows: usize =
+                group.iter().map(|update| update.rows.len()).sum();
+            if num_rows + group_rows > SIZE - 1000 {
+                runs.push(vec![]);
+                num_rows = 0;
+            }
+            num_rows += group_rows;
+            runs.last_mut().unwrap().extend(group);
+        }
+        runs
+    }
+
+    #[test]
+    fn mpt_unrolling() {
+        let updates = unrolled(updates())
+            .into_iter()
+            .chain(unrolled(insert_delete_updates()));
+        for update in updates {
+            let nodes = node_roles(&update.update);
+            assert_eq!(
+                update.rows.len(),
+                update.update.preimage().len()
+                    + nodes.iter().map(|(node, _)| node.len()).sum::<usize>()
+            );
+
+            // The key is accumulated down to the leaf ending the update
+            let last = update.rows.last().unwrap();
+            assert!(last.is_end);
+            assert_eq!(last.key_len, 64);
+            assert_eq!(last.key_acc, update.key_rlc);
+            let key = H256(keccak256(update.update.preimage()));
+            assert_eq!(update.key_rlc, hash_rlc(&key, r()));
+        }
+    }
+
+    #[test]
+    fn mpt_circuit() {
+        verify(unrolled(updates()), true);
+    }
+
+    #[test]
+    fn mpt_circuit_insert_delete() {
+        let updates = unrolled(insert_delete_updates());
+        for kind in [UpdateKind::Insert, UpdateKind::Delete, UpdateKind::Absent]
+        {
+            assert!(updates.iter().any(|update| update.update.kind == kind));
+        }
+        assert!(updates
+            .iter()
+            .any(|update| update.update.moved_node.is_some()));
+        assert!(updates.iter().any(|update| {
+            update.update.kind == UpdateKind::Delete && !update.is_storage()
+        }));
+        for run in runs(updates) {
+            verify(run, true);
+        }
+    }
+
+    #[test]
+    fn mpt_circuit_invalid_byte() {
+        let mut updates = unrolled(updates());
+        let row = updates[0].rows.len() - 5;
+        updates[0].rows[row].byte += 1;
+        verify(updates, false);
+    }
+
+    #[test]
+    fn mpt_circuit_invalid_value() {
+        let mut updates = unrolled(updates());
+        updates[0].new_value += Fp::one();
+        verify(updates, false);
+    }
+
+    #[test]
+    fn mpt_circuit_invalid_kind() {
+        let updates = unrolled(insert_delete_updates());
+        let insert = updates
+            .iter()
+            .position(|update| update.update.kind == UpdateKind::Insert)
+            .unwrap();
+        let mut updates = updates[insert..insert + 1].to_vec();
+        updates[0].update.kind = UpdateKind::Modify;
+        verify(updates, false);
+    }
+
+    #[test]
+    fn mpt_circuit_invalid_absent() {
+        let updates = unrolled(insert_delete_updates());
+        let absent = updates
+            .iter()
+            .position(|update| update.update.kind == UpdateKind::Absent)
+            .unwrap();
+        let mut updates = updates[absent..absent + 1].to_vec();
+        updates[0].new_value = Fp::one();
+        verify(updates, false);
+    }
+
+    #[test]
+    fn mpt_circuit_invalid_missing_account() {
+        // The storage root of a missing account is zero
+        let updates = unrolled(insert_delete_updates());
+        let read = updates
+            .iter()
+            .position(|update| {
+                update.is_storage() && update.update.kind == UpdateKind::Absent
+            })
+            .unwrap();
+        let mut updates = updates[read..read + 2].to_vec();
+        assert_eq!(updates[1].update.kind, UpdateKind::Absent);
+        updates[1].new_value = updates[0].new_root;
+        verify(updates, false);
+    }
+
+    #[test]
+    fn mpt_circuit_missing_storage_root() {
+        let mut updates = unrolled(updates());
+        assert_eq!(updates[2].update.field, MptField::StorageRoot);
+        updates.remove(2);
+        verify(updates, false);
+    }
+
+    #[test]
+    fn mpt_circuit_missing_update() {
+        let updates = unrolled(updates());
+        let mut update_rows = update_rows(&updates);
+        update_rows.remove(0);
+        let roots = roots(&updates);
+        verify_with(updates, update_rows, roots, false);
+    }
+
+    #[test]
+    fn mpt_circuit_no_updates() {
+        let (state_root, _) = mock_state(&mock_accounts());
+        let root = hash_rlc(&state_root, r());
+        verify_with(vec![], vec![], [root, root], true);
+    }
+
+    #[test]
+    fn mpt_circuit_invalid_roots() {
+        let updates = unrolled(updates());
+        let update_rows = update_rows(&updates);
+        let [old_root, new_root] = roots(&updates);
+        // The state root after the updates isn't the one before them
+        assert_ne!(old_root, new_root);
+        verify_with(updates, update_rows, [old_root, old_root], false);
+    }
+
+    #[test]
+    fn mpt_circuit_invalid_order() {
+        let mut updates = unrolled(updates());
+        updates.swap(0, 3);
+        verify(updates, false);
+    }
+}
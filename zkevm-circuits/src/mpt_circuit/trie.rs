@@ -0,0 +1,1098 @@
+use bus_mapping::eth_types::{
+    Address, Bytes, EIP1186ProofResponse, ToBigEndian, Word, H256,
+};
+use ethers_core::utils::{
+    keccak256,
+    rlp::{self, DecoderError, Rlp, RlpStream},
+};
+use std::collections::HashMap;
+
+/// The encoding of the empty node, which is the root node of an empty trie,
+/// and the empty item of a branch.
+pub(crate) const EMPTY_NODE: u8 = 0x80;
+
+/// The field of an account updated by an [`MptUpdate`], or its storage.  The
+/// fields of an account have the values of their tags in the state circuit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum MptField {
+    Nonce = 1,
+    Balance,
+    CodeHash,
+    StorageRoot,
+    Storage,
+}
+
+/// Whether the leaf of the key of an [`MptUpdate`] is in the trie before and
+/// after the update.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum UpdateKind {
+    Modify,
+    Insert,
+    Delete,
+    // The leaf is in neither trie, and its value is read as zero
+    Absent,
+}
+
+impl UpdateKind {
+    /// Returns whether the leaf is in the trie before and after the update.
+    pub(crate) fn exists(&self) -> (bool, bool) {
+        match self {
+            Self::Modify => (true, true),
+            Self::Insert => (false, true),
+            Self::Delete => (true, false),
+            Self::Absent => (false, false),
+        }
+    }
+}
+
+/// Error returned when an update can't be proven with the known nodes.
+#[derive(Debug)]
+pub enum TrieError {
+    /// A node or a leaf isn't valid RLP.
+    Rlp(DecoderError),
+    /// The proofs of an `eth_getProof` response couldn't be read.
+    Json(serde_json::Error),
+    /// A node on the path of a key isn't in the proofs.
+    MissingNode(H256),
+}
+
+impl From<DecoderError> for TrieError {
+    fn from(err: DecoderError) -> Self {
+        Self::Rlp(err)
+    }
+}
+
+impl From<serde_json::Error> for TrieError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Json(err)
+    }
+}
+
+/// An update of a leaf of the state trie or of a storage trie, with the nodes
+/// on the path of its key before and after the update.  A leaf which is
+/// missing before or after the update is proven by its insertion into the
+/// trie without it, where the nodes before the update are the ones of the
+/// trie without the leaf, and the nodes after the update are the ones of the
+/// trie with the leaf.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct MptUpdate {
+    pub(crate) address: Address,
+    pub(crate) field: MptField,
+    // The storage slot, for an update of the storage
+    pub(crate) key: Word,
+    pub(crate) kind: UpdateKind,
+    pub(crate) old_value: Word,
+    pub(crate) new_value: Word,
+    // The root of the state trie before the update
+    pub(crate) state_root: H256,
+    // The roots of the updated trie
+    pub(crate) old_root: H256,
+    pub(crate) new_root: H256,
+    pub(crate) pre_nodes: Vec<Vec<u8>>,
+    pub(crate) post_nodes: Vec<Vec<u8>>,
+    // The leaf or the extension moved below the branch of an inserted leaf,
+    // when the leaf splits its path
+    pub(crate) moved_node: Option<Vec<u8>>,
+}
+
+impl MptUpdate {
+    /// Returns the bytes hashed into the key of the updated leaf.
+    pub(crate) fn preimage(&self) -> Vec<u8> {
+        match self.field {
+            MptField::Storage => self.key.to_be_bytes().to_vec(),
+            _ => self.address.as_bytes().to_vec(),
+        }
+    }
+}
+
+/// An account, which has the default values of its fields when it's created.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Account {
+    nonce: Word,
+    balance: Word,
+    storage_root: H256,
+    code_hash: H256,
+}
+
+impl Default for Account {
+    fn default() -> Self {
+        Self {
+            nonce: Word::zero(),
+            balance: Word::zero(),
+            storage_root: H256(keccak256([EMPTY_NODE])),
+            code_hash: H256(keccak256([])),
+        }
+    }
+}
+
+impl Account {
+    fn decode(raw: &[u8]) -> Result<Self, DecoderError> {
+        let rlp = Rlp::new(raw);
+        Ok(Self {
+            nonce: rlp.val_at(0)?,
+            balance: rlp.val_at(1)?,
+            storage_root: rlp.val_at(2)?,
+            code_hash: rlp.val_at(3)?,
+        })
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut stream = RlpStream::new_list(4);
+        stream.append(&self.nonce);
+        stream.append(&self.balance);
+        stream.append(&self.storage_root);
+        stream.append(&self.code_hash);
+        stream.out().to_vec()
+    }
+
+    pub(crate) fn get(&self, field: MptField) -> Word {
+        match field {
+            MptField::Nonce => self.nonce,
+            MptField::Balance => self.balance,
+            MptField::CodeHash => {
+                Word::from_big_endian(self.code_hash.as_bytes())
+            }
+            MptField::StorageRoot => {
+                Word::from_big_endian(self.storage_root.as_bytes())
+            }
+            MptField::Storage => unreachable!("storage isn't an account field"),
+        }
+    }
+
+    fn set(&mut self, field: MptField, value: Word) {
+        match field {
+            MptField::Nonce => self.nonce = value,
+            MptField::Balance => self.balance = value,
+            MptField::CodeHash => self.code_hash = H256(value.to_be_bytes()),
+            MptField::StorageRoot => {
+                self.storage_root = H256(value.to_be_bytes())
+            }
+            MptField::Storage => unreachable!("storage isn't an account field"),
+        }
+    }
+}
+
+/// Returns the 64 nibbles of `key`.
+pub(crate) fn key_nibbles(key: &H256) -> Vec<u8> {
+    key.as_bytes()
+        .iter()
+        .flat_map(|byte| [byte >> 4, byte & 0xf])
+        .collect()
+}
+
+/// Decodes the hex-prefix encoded path of an extension or a leaf into its
+/// nibbles, and whether it's the path of a leaf.
+pub(crate) fn decode_path(
+    path: &[u8],
+) -> Result<(Vec<u8>, bool), DecoderError> {
+    let flag = match path.first() {
+        Some(byte) if byte >> 4 <= 3 => byte >> 4,
+        _ => return Err(DecoderError::Custom("invalid path")),
+    };
+    let mut nibbles = vec![];
+    if flag & 1 == 1 {
+        nibbles.push(path[0] & 0xf);
+    }
+    for byte in path[1..].iter() {
+        nibbles.extend_from_slice(&[byte >> 4, byte & 0xf]);
+    }
+    Ok((nibbles, flag >= 2))
+}
+
+/// Returns the hex-prefix encoding of the path `nibbles`.
+pub(crate) fn encode_path(nibbles: &[u8], is_leaf: bool) -> Vec<u8> {
+    let is_odd = nibbles.len() % 2;
+    let flag = (2 * is_leaf as u8 + is_odd as u8) << 4;
+    let mut path = vec![flag | if is_odd == 1 { nibbles[0] } else { 0 }];
+    for pair in nibbles[is_odd..].chunks(2) {
+        path.push(pair[0] << 4 | pair[1]);
+    }
+    path
+}
+
+/// A node of a trie, with the raw items holding its children, or the value
+/// of a leaf.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Node {
+    Empty,
+    Branch(Vec<Vec<u8>>),
+    Extension(Vec<u8>, Vec<u8>),
+    Leaf(Vec<u8>, Vec<u8>),
+}
+
+impl Node {
+    /// Decodes `node`, where the paths are decoded into their nibbles.
+    pub(crate) fn decode(node: &[u8]) -> Result<Self, DecoderError> {
+        if node == [EMPTY_NODE] {
+            return Ok(Self::Empty);
+        }
+        let rlp = Rlp::new(node);
+        match rlp.item_count()? {
+            17 => Ok(Self::Branch(
+                rlp.iter().map(|item| item.as_raw().to_vec()).collect(),
+            )),
+            2 => {
+                let (path, is_leaf) = decode_path(rlp.at(0)?.data()?)?;
+                let item = rlp.at(1)?.as_raw().to_vec();
+                Ok(if is_leaf {
+                    Self::Leaf(path, item)
+                } else {
+                    Self::Extension(path, item)
+                })
+            }
+            _ => Err(DecoderError::RlpIncorrectListLen),
+        }
+    }
+
+    /// Returns the RLP encoding of the node.
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let mut stream;
+        match self {
+            Self::Empty => return vec![EMPTY_NODE],
+            Self::Branch(items) => {
+                stream = RlpStream::new_list(items.len());
+                for item in items {
+                    stream.append_raw(item, 1);
+                }
+            }
+            Self::Extension(path, item) | Self::Leaf(path, item) => {
+                let is_leaf = matches!(self, Self::Leaf(..));
+                stream = RlpStream::new_list(2);
+                stream.append(&encode_path(path, is_leaf));
+                stream.append_raw(item, 1);
+            }
+        }
+        stream.out().to_vec()
+    }
+}
+
+// The nodes of the path of a key, with the number of nibbles of the key above
+// each of them.
+type TriePath = Vec<(Vec<u8>, usize)>;
+
+// The new root of a trie, with the new nodes of the path of a key and the node
+// moved by the update.
+type NewNodes = (H256, Vec<Vec<u8>>, Option<Vec<u8>>);
+
+// The update of the path of a key in a trie.
+struct PathUpdate {
+    kind: UpdateKind,
+    new_root: H256,
+    pre_nodes: Vec<Vec<u8>>,
+    post_nodes: Vec<Vec<u8>>,
+    moved_node: Option<Vec<u8>>,
+}
+
+/// The part of the state trie and of the storage tries known from the nodes
+/// of EIP-1186 proofs, which are identified by their hash.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct StateTrie {
+    root: H256,
+    nodes: HashMap<H256, Vec<u8>>,
+}
+
+impl StateTrie {
+    /// Builds the state trie with root `state_root` from the `eth_getProof`
+    /// responses `proofs` of the accounts and storage slots it's read at.
+    pub(crate) fn new(
+        state_root: H256,
+        proofs: &[EIP1186ProofResponse],
+    ) -> Result<Self, TrieError> {
+        let mut trie = Self {
+            root: state_root,
+            nodes: HashMap::new(),
+        };
+        // The root of an empty trie is the hash of the empty node
+        trie.insert(vec![EMPTY_NODE]);
+        for proof in proofs {
+            // The fields of a response are private, so its proofs are read
+            // from its JSON serialization
+            let json = serde_json::to_value(proof)?;
+            let mut nodes: Vec<Bytes> =
+                serde_json::from_value(json["account_proof"].clone())?;
+            for storage_proof in
+                json["storage_proof"].as_array().into_iter().flatten()
+            {
+                nodes.extend(serde_json::from_value::<Vec<Bytes>>(
+                    storage_proof["proof"].clone(),
+                )?);
+            }
+            for node in nodes {
+                trie.insert(node.as_ref().to_vec());
+            }
+        }
+        Ok(trie)
+    }
+
+    /// Returns the root of the state trie.
+    pub(crate) fn root(&self) -> H256 {
+        self.root
+    }
+
+    fn insert(&mut self, node: Vec<u8>) -> H256 {
+        let hash = H256(keccak256(&node));
+        self.nodes.insert(hash, node);
+        hash
+    }
+
+    fn node(&self, hash: H256) -> Result<Vec<u8>, TrieError> {
+        self.nodes
+            .get(&hash)
+            .cloned()
+            .ok_or(TrieError::MissingNode(hash))
+    }
+
+    // Returns the node referenced by the item `item` of its parent, which is
+    // its hash, or the node itself when it's shorter than a hash.
+    fn child(&self, item: &[u8]) -> Result<Option<Vec<u8>>, TrieError> {
+        let rlp = Rlp::new(item);
+        if rlp.is_list() {
+            return Ok(Some(item.to_vec()));
+        }
+        match rlp.data()?.len() {
+            0 => Ok(None),
+            32 => self.node(H256::from_slice(rlp.data()?)).map(Some),
+            _ => Err(DecoderError::RlpInvalidLength.into()),
+        }
+    }
+
+    // Returns the item referencing `node` in its parent.
+    fn reference(&mut self, node: &[u8]) -> Vec<u8> {
+        if node.len() < 32 {
+            node.to_vec()
+        } else {
+            rlp::encode(&self.insert(node.to_vec())).to_vec()
+        }
+    }
+
+    // Returns the nodes on the path of `key` from `root`, with the number of
+    // nibbles of the key above each of them, down to its leaf, or to the node
+    // where it's missing: the empty root, a branch without a child at its
+    // next nibble, or a leaf or an extension whose path diverges from it.
+    // The leaf of the key is found when the path ends with a leaf.
+    fn path(
+        &self,
+        root: H256,
+        key: H256,
+    ) -> Result<(TriePath, bool), TrieError> {
+        let nibbles = key_nibbles(&key);
+        let mut path = vec![];
+        let mut node = self.node(root)?;
+        let mut depth = 0;
+        loop {
+            path.push((node.clone(), depth));
+            let child = match Node::decode(&node)? {
+                Node::Empty => None,
+                Node::Branch(items) => {
+                    depth += 1;
+                    self.child(&items[nibbles[depth - 1] as usize])?
+                }
+                Node::Leaf(node_path, _) => {
+                    let is_found = nibbles[depth..] == node_path[..];
+                    return Ok((path, is_found));
+                }
+                Node::Extension(node_path, item) => {
+                    if nibbles[depth..].starts_with(&node_path) {
+                        depth += node_path.len();
+                        self.child(&item)?
+                    } else {
+                        None
+                    }
+                }
+            };
+            match child {
+                Some(child) => node = child,
+                None => return Ok((path, false)),
+            }
+        }
+    }
+
+    // Returns the value of the leaf of `key` in the trie with root `root`.
+    fn get(&self, root: H256, key: H256) -> Result<Option<Vec<u8>>, TrieError> {
+        let (path, is_found) = self.path(root, key)?;
+        if !is_found {
+            return Ok(None);
+        }
+        let (leaf, _) = path.last().unwrap();
+        Ok(Some(Rlp::new(leaf).at(1)?.data()?.to_vec()))
+    }
+
+    // Replaces the node below `ancestors` on the path of `nibbles` by `node`,
+    // and returns the new root with the nodes from the root down to `node`.
+    fn rebuild(
+        &mut self,
+        ancestors: &[(Vec<u8>, usize)],
+        nibbles: &[u8],
+        node: Vec<u8>,
+    ) -> Result<(H256, Vec<Vec<u8>>), TrieError> {
+        let mut nodes = vec![node];
+        for (ancestor, depth) in ancestors.iter().rev() {
+            let child = self.reference(nodes.last().unwrap());
+            let node = match Node::decode(ancestor)? {
+                Node::Branch(mut items) => {
+                    items[nibbles[*depth] as usize] = child;
+                    Node::Branch(items)
+                }
+                Node::Extension(path, _) => Node::Extension(path, child),
+                _ => return Err(DecoderError::Custom("invalid path").into()),
+            };
+            nodes.push(node.encode());
+        }
+        nodes.reverse();
+        let root = self.insert(nodes[0].clone());
+        Ok((root, nodes))
+    }
+
+    // Inserts the leaf of value `value` of the key of `nibbles` below the
+    // last node of `path`, where the key is missing, and returns the new root
+    // with the nodes on the path of the key, and the node moved below the
+    // branch of the leaf when the leaf splits its path.
+    fn insert_leaf(
+        &mut self,
+        path: &[(Vec<u8>, usize)],
+        nibbles: &[u8],
+        value: &[u8],
+    ) -> Result<NewNodes, TrieError> {
+        let value = rlp::encode(&value).to_vec();
+        let (last, depth) = path.last().unwrap();
+        let ancestors = &path[..path.len() - 1];
+        let rest = &nibbles[*depth..];
+        let (path, item, is_leaf) = match Node::decode(last)? {
+            Node::Empty => {
+                let leaf = Node::Leaf(rest.to_vec(), value).encode();
+                let (root, nodes) = self.rebuild(ancestors, nibbles, leaf)?;
+                return Ok((root, nodes, None));
+            }
+            Node::Branch(mut items) => {
+                let leaf = Node::Leaf(rest[1..].to_vec(), value).encode();
+                items[rest[0] as usize] = self.reference(&leaf);
+                let branch = Node::Branch(items).encode();
+                let (root, mut nodes) =
+                    self.rebuild(ancestors, nibbles, branch)?;
+                nodes.push(leaf);
+                return Ok((root, nodes, None));
+            }
+            Node::Extension(path, item) => (path, item, false),
+            Node::Leaf(path, item) => (path, item, true),
+        };
+
+        // The new branch splits the path of the leaf or the extension after
+        // its common prefix with the key, and the rest of the path is moved
+        // below the branch, where a branch is its own child
+        let prefix = path.iter().zip(rest).take_while(|(a, b)| a == b).count();
+        let leaf = Node::Leaf(rest[prefix + 1..].to_vec(), value).encode();
+        let moved_node = if is_leaf || path.len() > prefix + 1 {
+            let moved_path = path[prefix + 1..].to_vec();
+            Some(if is_leaf {
+                Node::Leaf(moved_path, item.clone()).encode()
+            } else {
+                Node::Extension(moved_path, item.clone()).encode()
+            })
+        } else {
+            None
+        };
+        let mut items = vec![vec![EMPTY_NODE]; 17];
+        items[rest[prefix] as usize] = self.reference(&leaf);
+        items[path[prefix] as usize] = match &moved_node {
+            Some(node) => self.reference(node),
+            None => item,
+        };
+        let branch = Node::Branch(items).encode();
+        let (root, mut nodes) = if prefix > 0 {
+            let child = self.reference(&branch);
+            let extension = Node::Extension(path[..prefix].to_vec(), child);
+            let (root, mut nodes) =
+                self.rebuild(ancestors, nibbles, extension.encode())?;
+            nodes.push(branch);
+            (root, nodes)
+        } else {
+            self.rebuild(ancestors, nibbles, branch)?
+        };
+        nodes.push(leaf);
+        Ok((root, nodes, moved_node))
+    }
+
+    // Deletes the leaf at the end of `path`, the path of the key of
+    // `nibbles`, and returns the new root with the nodes on the path of the
+    // key, and the node moved from below the branch of the leaf when the
+    // branch is left with a single child.
+    fn delete_leaf(
+        &mut self,
+        path: &[(Vec<u8>, usize)],
+        nibbles: &[u8],
+    ) -> Result<NewNodes, TrieError> {
+        // The trie of a single leaf is left empty
+        if path.len() == 1 {
+            let root = self.insert(vec![EMPTY_NODE]);
+            return Ok((root, vec![vec![EMPTY_NODE]], None));
+        }
+        let (parent, depth) = &path[path.len() - 2];
+        let mut items = match Node::decode(parent)? {
+            Node::Branch(items) => items,
+            _ => return Err(DecoderError::Custom("invalid path").into()),
+        };
+        items[nibbles[*depth] as usize] = vec![EMPTY_NODE];
+        let children: Vec<_> =
+            (0..16).filter(|idx| items[*idx] != [EMPTY_NODE]).collect();
+        let ancestors = &path[..path.len() - 2];
+        let sibling = match children[..] {
+            [sibling] => sibling,
+            _ => {
+                let branch = Node::Branch(items).encode();
+                let (root, nodes) = self.rebuild(ancestors, nibbles, branch)?;
+                return Ok((root, nodes, None));
+            }
+        };
+
+        // A branch left with a single child is merged with it, and with the
+        // extension above it, where a branch child is kept below an
+        // extension
+        let item = items[sibling].clone();
+        let child = self.child(&item)?.unwrap();
+        let (path, is_leaf, item, moved_node) = match Node::decode(&child)? {
+            Node::Leaf(path, item) => (path, true, item, Some(child)),
+            Node::Extension(path, item) => (path, false, item, Some(child)),
+            _ => (vec![], false, item, None),
+        };
+        let mut path = [vec![sibling as u8], path].concat();
+        let mut ancestors = ancestors;
+        if let Some((node, _)) = ancestors.last() {
+            if let Node::Extension(prefix, _) = Node::decode(node)? {
+                path = [prefix, path].concat();
+                ancestors = &ancestors[..ancestors.len() - 1];
+            }
+        }
+        let node = if is_leaf {
+            Node::Leaf(path, item)
+        } else {
+            Node::Extension(path, item)
+        };
+        let (root, nodes) = self.rebuild(ancestors, nibbles, node.encode())?;
+        Ok((root, nodes, moved_node))
+    }
+
+    // Sets the value of the leaf of `key` in the trie with root `root` to
+    // `value`, or deletes it for `None`, and returns the update of the path
+    // of the key.  A key missing before and after the update is proven by
+    // the insertion of a leaf of value `placeholder`, whose root is dropped.
+    fn set(
+        &mut self,
+        root: H256,
+        key: H256,
+        value: Option<&[u8]>,
+        placeholder: &[u8],
+    ) -> Result<PathUpdate, TrieError> {
+        let nibbles = key_nibbles(&key);
+        let (path, is_found) = self.path(root, key)?;
+        let nodes = path.iter().map(|(node, _)| node.clone()).collect();
+        Ok(match (is_found, value) {
+            (true, Some(value)) => {
+                let (_, depth) = path.last().unwrap();
+                let item = rlp::encode(&value).to_vec();
+                let leaf = Node::Leaf(nibbles[*depth..].to_vec(), item);
+                let ancestors = &path[..path.len() - 1];
+                let (new_root, post_nodes) =
+                    self.rebuild(ancestors, &nibbles, leaf.encode())?;
+                PathUpdate {
+                    kind: UpdateKind::Modify,
+                    new_root,
+                    pre_nodes: nodes,
+                    post_nodes,
+                    moved_node: None,
+                }
+            }
+            (true, None) => {
+                let (new_root, pre_nodes, moved_node) =
+                    self.delete_leaf(&path, &nibbles)?;
+                PathUpdate {
+                    kind: UpdateKind::Delete,
+                    new_root,
+                    pre_nodes,
+                    post_nodes: nodes,
+                    moved_node,
+                }
+            }
+            (false, _) => {
+                let (new_root, post_nodes, moved_node) = self.insert_leaf(
+                    &path,
+                    &nibbles,
+                    value.unwrap_or(placeholder),
+                )?;
+                let (kind, new_root) = match value {
+                    Some(_) => (UpdateKind::Insert, new_root),
+                    None => (UpdateKind::Absent, root),
+                };
+                PathUpdate {
+                    kind,
+                    new_root,
+                    pre_nodes: nodes,
+                    post_nodes,
+                    moved_node,
+                }
+            }
+        })
+    }
+
+    /// Sets `field` of the account at `address` to `value`, or its storage
+    /// slot `key` for [`MptField::Storage`], and returns the updates proving
+    /// it, where the update of a slot is followed by the update of the
+    /// storage root of the account.  Reading a value is setting it to itself.
+    /// A slot set to zero is deleted, and a missing account, whose fields
+    /// and slots read as zero, is created with the default values of its
+    /// other fields when one of its fields or slots is set to a non-zero
+    /// value.  An account is destroyed by setting its nonce, its balance and
+    /// then its code hash to zero, which deletes it with its storage, so the
+    /// slots of a destroyed account are set before its fields.
+    pub(crate) fn update(
+        &mut self,
+        address: Address,
+        field: MptField,
+        key: Word,
+        value: Word,
+    ) -> Result<Vec<MptUpdate>, TrieError> {
+        let account_key = H256(keccak256(address));
+        let account = match self.get(self.root, account_key)? {
+            Some(raw) => Some(Account::decode(&raw)?),
+            None => None,
+        };
+        let mut updates = vec![];
+
+        let (field, value) = if field == MptField::Storage {
+            let old_root = account.clone().unwrap_or_default().storage_root;
+            let slot_key = H256(keccak256(key.to_be_bytes()));
+            let old_value = match self.get(old_root, slot_key)? {
+                Some(raw) => rlp::decode(&raw)?,
+                None => Word::zero(),
+            };
+            let raw = rlp::encode(&value).to_vec();
+            let placeholder = rlp::encode(&Word::one()).to_vec();
+            let path = self.set(
+                old_root,
+                slot_key,
+                (!value.is_zero()).then(|| &raw[..]),
+                &placeholder,
+            )?;
+            updates.push(MptUpdate {
+                address,
+                field,
+                key,
+                kind: path.kind,
+                old_value,
+                new_value: value,
+                state_root: self.root,
+                old_root,
+                new_root: path.new_root,
+                pre_nodes: path.pre_nodes,
+                post_nodes: path.post_nodes,
+                moved_node: path.moved_node,
+            });
+            (
+                MptField::StorageRoot,
+                Word::from_big_endian(path.new_root.as_bytes()),
+            )
+        } else {
+            (field, value)
+        };
+
+        let old_value = account
+            .as_ref()
+            .map(|account| account.get(field))
+            .unwrap_or_default();
+        // The storage root of a missing account is the empty root until one
+        // of its slots is set
+        let is_created = account.is_none()
+            && !value.is_zero()
+            && (field != MptField::StorageRoot
+                || value != Account::default().get(field));
+        let mut new_account = account.clone().unwrap_or_default();
+        new_account.set(field, value);
+        let is_destroyed = field == MptField::CodeHash
+            && value.is_zero()
+            && new_account.nonce.is_zero()
+            && new_account.balance.is_zero();
+        let raw = new_account.encode();
+        let path = self.set(
+            self.root,
+            account_key,
+            ((account.is_some() || is_created) && !is_destroyed)
+                .then(|| &raw[..]),
+            &raw,
+        )?;
+        updates.push(MptUpdate {
+            address,
+            field,
+            key: Word::zero(),
+            kind: path.kind,
+            old_value,
+            new_value: if path.kind == UpdateKind::Absent {
+                Word::zero()
+            } else {
+                value
+            },
+            state_root: self.root,
+            old_root: self.root,
+            new_root: path.new_root,
+            pre_nodes: path.pre_nodes,
+            post_nodes: path.post_nodes,
+            moved_node: path.moved_node,
+        });
+        self.root = path.new_root;
+
+        Ok(updates)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::{
+        encode_path, key_nibbles, MptField, StateTrie, UpdateKind, EMPTY_NODE,
+    };
+    use bus_mapping::eth_types::{
+        Address, Bytes, EIP1186ProofResponse, ToBigEndian, Word, H256,
+    };
+    use ethers_core::utils::{
+        keccak256,
+        rlp::{self, RlpStream},
+    };
+    use serde_json::json;
+
+    /// An account of a mock state, with its storage slots.
+    #[derive(Clone, Debug)]
+    pub(crate) struct MockAccount {
+        pub(crate) address: Address,
+        pub(crate) nonce: Word,
+        pub(crate) balance: Word,
+        pub(crate) code_hash: H256,
+        pub(crate) storage: Vec<(Word, Word)>,
+    }
+
+    // Appends the reference to `child` to the node in `stream`, which is its
+    // hash unless it's shorter than a hash.
+    fn append_child(
+        stream: &mut RlpStream,
+        nodes: &mut Vec<Vec<u8>>,
+        child: Vec<u8>,
+    ) {
+        if child.len() < 32 {
+            stream.append_raw(&child, 1);
+        } else {
+            stream.append(&H256(keccak256(&child)));
+            nodes.push(child);
+        }
+    }
+
+    // Returns the root node of the trie of `leaves`, given by the nibbles of
+    // their keys left below the root, and pushes its other nodes to `nodes`.
+    fn build_node(
+        nodes: &mut Vec<Vec<u8>>,
+        leaves: &[(Vec<u8>, Vec<u8>)],
+    ) -> Vec<u8> {
+        if let [(path, value)] = leaves {
+            let mut stream = RlpStream::new_list(2);
+            stream.append(&encode_path(path, true));
+            stream.append(value);
+            return stream.out().to_vec();
+        }
+
+        let (first, _) = &leaves[0];
+        let prefix_len = (0..first.len())
+            .take_while(|idx| {
+                leaves.iter().all(|(path, _)| path[*idx] == first[*idx])
+            })
+            .count();
+        let below = |leaves: &[(Vec<u8>, Vec<u8>)], depth| -> Vec<_> {
+            leaves
+                .iter()
+                .map(|(path, value)| (path[depth..].to_vec(), value.clone()))
+                .collect()
+        };
+        let mut stream;
+        if prefix_len > 0 {
+            stream = RlpStream::new_list(2);
+            stream.append(&encode_path(&first[..prefix_len], false));
+            let child = build_node(nodes, &below(leaves, prefix_len));
+            append_child(&mut stream, nodes, child);
+        } else {
+            stream = RlpStream::new_list(17);
+            for nibble in 0..16 {
+                let children: Vec<_> = leaves
+                    .iter()
+                    .filter(|(path, _)| path[0] == nibble)
+                    .cloned()
+                    .collect();
+                if children.is_empty() {
+                    stream.append_empty_data();
+                } else {
+                    let child = build_node(nodes, &below(&children, 1));
+                    append_child(&mut stream, nodes, child);
+                }
+            }
+            stream.append_empty_data();
+        }
+        stream.out().to_vec()
+    }
+
+    // Returns the root of the trie of `leaves` with its nodes.
+    fn build_trie(leaves: Vec<(H256, Vec<u8>)>) -> (H256, Vec<Vec<u8>>) {
+        let leaves: Vec<_> = leaves
+            .into_iter()
+            .map(|(key, value)| (key_nibbles(&key), value))
+            .collect();
+        let mut nodes = vec![];
+        let root = build_node(&mut nodes, &leaves);
+        let hash = H256(keccak256(&root));
+        nodes.push(root);
+        (hash, nodes)
+    }
+
+    /// Returns the state root of `accounts`, with an `eth_getProof` response
+    /// holding all the nodes of the state trie and of the storage tries.
+    pub(crate) fn mock_state(
+        accounts: &[MockAccount],
+    ) -> (H256, EIP1186ProofResponse) {
+        let mut storage_nodes = vec![];
+        let mut leaves = vec![];
+        for account in accounts {
+            let (storage_root, nodes) = if account.storage.is_empty() {
+                (H256(keccak256([0x80u8])), vec![])
+            } else {
+                build_trie(
+                    account
+                        .storage
+                        .iter()
+                        .map(|(key, value)| {
+                            (
+                                H256(keccak256(key.to_be_bytes())),
+                                rlp::encode(value).to_vec(),
+                            )
+                        })
+                        .collect(),
+                )
+            };
+            storage_nodes.extend(nodes);
+
+            let mut stream = RlpStream::new_list(4);
+            stream.append(&account.nonce);
+            stream.append(&account.balance);
+            stream.append(&storage_root);
+            stream.append(&account.code_hash);
+            leaves.push((
+                H256(keccak256(account.address)),
+                stream.out().to_vec(),
+            ));
+        }
+        let (state_root, nodes) = build_trie(leaves);
+
+        let bytes = |nodes: Vec<Vec<u8>>| -> Vec<Bytes> {
+            nodes.into_iter().map(Bytes::from).collect()
+        };
+        let proof = serde_json::from_value(json!({
+            "address": accounts[0].address,
+            "account_proof": bytes(nodes),
+            "balance": "0x0",
+            "code_hash": H256::zero(),
+            "nonce": "0x0",
+            "storage_hash": H256::zero(),
+            "storage_proof": [{
+                "key": H256::zero(),
+                "value": "0x0",
+                "proof": bytes(storage_nodes),
+            }],
+        }))
+        .unwrap();
+        (state_root, proof)
+    }
+
+    /// Returns mock accounts, where the last one has some storage.
+    pub(crate) fn mock_accounts() -> Vec<MockAccount> {
+        (1..=4u64)
+            .map(|idx| MockAccount {
+                address: Address::from_low_u64_be(0x1000 * idx),
+                nonce: Word::from(idx),
+                balance: Word::from(idx) * Word::exp10(18),
+                code_hash: H256(keccak256(Bytes::default())),
+                storage: if idx == 4 {
+                    (1..=3u64)
+                        .map(|slot| {
+                            (Word::from(slot), Word::from(0x100 * slot))
+                        })
+                        .collect()
+                } else {
+                    vec![]
+                },
+            })
+            .collect()
+    }
+
+    #[test]
+    fn state_trie_update() {
+        let mut accounts = mock_accounts();
+        let (state_root, proof) = mock_state(&accounts);
+        let mut trie = StateTrie::new(state_root, &[proof]).unwrap();
+
+        let address = accounts[0].address;
+        let updates = trie
+            .update(address, MptField::Nonce, Word::zero(), Word::from(7))
+            .unwrap();
+        assert_eq!(updates.len(), 1);
+        assert_eq!(updates[0].old_value, accounts[0].nonce);
+        assert_eq!(updates[0].old_root, state_root);
+        accounts[0].nonce = Word::from(7);
+        assert_eq!(trie.root(), mock_state(&accounts).0);
+
+        // A storage update is followed by the update of the storage root
+        let address = accounts[3].address;
+        let updates = trie
+            .update(address, MptField::Storage, Word::from(2), Word::from(5))
+            .unwrap();
+        assert_eq!(updates.len(), 2);
+        assert_eq!(updates[0].old_value, Word::from(0x200));
+        assert_eq!(updates[0].state_root, updates[1].old_root);
+        assert_eq!(updates[1].field, MptField::StorageRoot);
+        assert_eq!(
+            updates[1].new_value,
+            Word::from_big_endian(updates[0].new_root.as_bytes())
+        );
+        accounts[3].storage[1].1 = Word::from(5);
+        assert_eq!(trie.root(), mock_state(&accounts).0);
+    }
+
+    #[test]
+    fn state_trie_insert_delete() {
+        let mut accounts = mock_accounts();
+        let (state_root, proof) = mock_state(&accounts);
+        let mut trie = StateTrie::new(state_root, &[proof]).unwrap();
+
+        // A missing account is read as zero, and created by a write
+        let address = Address::from_low_u64_be(0x5000);
+        let updates = trie
+            .update(address, MptField::Nonce, Word::zero(), Word::zero())
+            .unwrap();
+        assert_eq!(updates[0].kind, UpdateKind::Absent);
+        assert_eq!(updates[0].new_root, state_root);
+        assert_eq!(trie.root(), state_root);
+        let updates = trie
+            .update(address, MptField::Storage, Word::from(1), Word::zero())
+            .unwrap();
+        assert_eq!(
+            updates.iter().map(|update| update.kind).collect::<Vec<_>>(),
+            vec![UpdateKind::Absent; 2]
+        );
+        assert_eq!(updates[1].new_value, Word::zero());
+        assert_eq!(trie.root(), state_root);
+        let updates = trie
+            .update(address, MptField::Balance, Word::zero(), Word::from(9))
+            .unwrap();
+        assert_eq!(updates[0].kind, UpdateKind::Insert);
+        accounts.push(MockAccount {
+            address,
+            nonce: Word::zero(),
+            balance: Word::from(9),
+            code_hash: H256(keccak256([])),
+            storage: vec![],
+        });
+        assert_eq!(trie.root(), mock_state(&accounts).0);
+
+        // A missing account is created by the write of a slot
+        let address = Address::from_low_u64_be(0x6000);
+        let updates = trie
+            .update(address, MptField::Storage, Word::from(1), Word::from(7))
+            .unwrap();
+        assert_eq!(
+            updates.iter().map(|update| update.kind).collect::<Vec<_>>(),
+            vec![UpdateKind::Insert; 2]
+        );
+        assert_eq!(updates[1].old_value, Word::zero());
+        accounts.push(MockAccount {
+            address,
+            nonce: Word::zero(),
+            balance: Word::zero(),
+            code_hash: H256(keccak256([])),
+            storage: vec![(Word::from(1), Word::from(7))],
+        });
+        assert_eq!(trie.root(), mock_state(&accounts).0);
+
+        // A slot is inserted into the empty storage trie, and then next to
+        // a slot whose key shares its first 8 nibbles, where both leaves are
+        // embedded into their branch
+        let address = accounts[0].address;
+        for (slot, value) in [(40364, 1), (105566, 2)] {
+            let updates = trie
+                .update(
+                    address,
+                    MptField::Storage,
+                    Word::from(slot),
+                    Word::from(value),
+                )
+                .unwrap();
+            assert_eq!(updates[0].kind, UpdateKind::Insert);
+            assert_eq!(updates[1].kind, UpdateKind::Modify);
+            assert_eq!(
+                updates[0].post_nodes.last().unwrap().len() < 32,
+                value == 2
+            );
+            accounts[0]
+                .storage
+                .push((Word::from(slot), Word::from(value)));
+            assert_eq!(trie.root(), mock_state(&accounts).0);
+        }
+        let updates = trie
+            .update(address, MptField::Storage, Word::from(3), Word::zero())
+            .unwrap();
+        assert_eq!(updates[0].kind, UpdateKind::Absent);
+        assert_eq!(updates[0].old_root, updates[0].new_root);
+        assert_eq!(trie.root(), mock_state(&accounts).0);
+
+        // A slot set to zero is deleted, down to the empty storage trie
+        for slot in [40364, 105566] {
+            let updates = trie
+                .update(
+                    address,
+                    MptField::Storage,
+                    Word::from(slot),
+                    Word::zero(),
+                )
+                .unwrap();
+            assert_eq!(updates[0].kind, UpdateKind::Delete);
+            accounts[0].storage.remove(0);
+            assert_eq!(trie.root(), mock_state(&accounts).0);
+            assert_eq!(
+                updates[0].pre_nodes == vec![vec![EMPTY_NODE]],
+                accounts[0].storage.is_empty()
+            );
+        }
+
+        // An account is deleted with its storage when it's destroyed, and
+        // recreated with an empty storage
+        let address = accounts[3].address;
+        for field in [MptField::Nonce, MptField::Balance, MptField::CodeHash] {
+            let updates = trie
+                .update(address, field, Word::zero(), Word::zero())
+                .unwrap();
+            assert_eq!(
+                updates[0].kind,
+                if field == MptField::CodeHash {
+                    UpdateKind::Delete
+                } else {
+                    UpdateKind::Modify
+                }
+            );
+        }
+        accounts.remove(3);
+        assert_eq!(trie.root(), mock_state(&accounts).0);
+        let updates = trie
+            .update(address, MptField::Storage, Word::from(1), Word::zero())
+            .unwrap();
+        assert_eq!(updates[0].kind, UpdateKind::Absent);
+        trie.update(address, MptField::Balance, Word::zero(), Word::from(1))
+            .unwrap();
+        accounts.push(MockAccount {
+            address,
+            nonce: Word::zero(),
+            balance: Word::from(1),
+            code_hash: H256(keccak256([])),
+            storage: vec![],
+        });
+        assert_eq!(trie.root(), mock_state(&accounts).0);
+    }
+}
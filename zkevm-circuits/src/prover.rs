@@ -0,0 +1,621 @@
+//! The prover of the circuits, which generates their keys, creates their
+//! proofs and verifies them over bn256, with the serialization of the setup
+//! params, the verifying keys and the proofs.
+
+use crate::{
+    evm_circuit::{BlockCircuit, WitnessError},
+    gadget::evm_word::{encode, r},
+    keccak_circuit::keccak::Config as KeccakConfig,
+    mpt_circuit::{
+        mpt::{unroll, Config as MptConfig, UnrolledUpdate},
+        trie::{MptField, StateTrie},
+        TrieError,
+    },
+    public_input_circuit::{instance_len, public_inputs, PublicInputError},
+    state_circuit::state::{memory_rows, Config as StateConfig},
+};
+use bus_mapping::{
+    circuit_input_builder::Block,
+    eth_types::{Word, H256},
+    operation::{
+        AccountBalanceOp, AccountCodeHashOp, AccountNonceOp, CallContextOp,
+        MemoryOp, Operation, StackOp, StorageOp, TxAccessListAccountOp,
+        TxAccessListAccountStorageOp, TxLogOp, TxRefundOp,
+    },
+};
+use halo2::{
+    circuit::{Layouter, SimpleFloorPlanner},
+    plonk::{
+        self, create_proof, keygen_pk, keygen_vk, verify_proof, Circuit,
+        Column, ConstraintSystem, Instance, ProvingKey, VerifyingKey,
+    },
+    poly::commitment::{Params, Setup},
+    transcript::{Blake2bRead, Blake2bWrite, Challenge255},
+};
+use pairing::{
+    arithmetic::FieldExt,
+    bn256::{Bn256, Fr, G1Affine},
+};
+use rand::{rngs::OsRng, SeedableRng};
+use rand_xorshift::XorShiftRng;
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter},
+    path::Path,
+};
+
+// The sizes of the state circuit
+const GLOBAL_COUNTER_MAX: usize = 2000;
+const MEMORY_ROWS_MAX: usize = 1000;
+const MEMORY_ADDRESS_MAX: usize = 1000;
+const STACK_ROWS_MAX: usize = 1000;
+const STACK_ADDRESS_MAX: usize = 1023;
+const STORAGE_ROWS_MAX: usize = 1000;
+const ACCOUNT_ROWS_MAX: usize = 100;
+const TX_ROWS_MAX: usize = 1000;
+
+// The number of rows of the MPT circuit, and of blocks of the keccak circuit
+// proving the hashes of its nodes, which are proven with the state circuit
+const MPT_ROWS: usize = 12000;
+const KECCAK_BLOCKS: usize = 100;
+
+// The maximum number of txs of a block, in the layout of the public inputs of
+// the EVM circuit, and the number of steps of the EVM circuit
+const MAX_TXS: usize = 10;
+const MAX_STEPS: usize = 1000;
+
+type EvmCircuit = BlockCircuit<Fr, MAX_TXS, MAX_STEPS>;
+
+/// The error of the prover.
+#[derive(Debug)]
+pub enum Error {
+    /// The params, a key or a proof couldn't be read or written.
+    Io(io::Error),
+    /// The keys couldn't be generated, or the proof couldn't be created or
+    /// verified.
+    Plonk(plonk::Error),
+    /// The updates of the state trie couldn't be proven from the state
+    /// proofs of the block.
+    Trie(TrieError),
+    /// The public inputs of the block couldn't be computed.
+    PublicInput(PublicInputError),
+    /// The witness of the EVM circuit couldn't be built from the block.
+    Witness(WitnessError),
+    /// The block has more operations of a target than the rows of the state
+    /// circuit for that target.
+    TooManyOperations,
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<plonk::Error> for Error {
+    fn from(err: plonk::Error) -> Self {
+        Self::Plonk(err)
+    }
+}
+
+impl From<TrieError> for Error {
+    fn from(err: TrieError) -> Self {
+        Self::Trie(err)
+    }
+}
+
+impl From<PublicInputError> for Error {
+    fn from(err: PublicInputError) -> Self {
+        Self::PublicInput(err)
+    }
+}
+
+impl From<WitnessError> for Error {
+    fn from(err: WitnessError) -> Self {
+        Self::Witness(err)
+    }
+}
+
+/// The circuits proven by the prover.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CircuitKind {
+    /// The state circuit, with the memory, stack, storage and account
+    /// operations of the block, and the MPT circuit proving the updates of
+    /// the state trie by its storage and account operations, from the state
+    /// root before the txs of the block to the one after them.
+    State,
+    /// The EVM circuit, with the steps of all the txs of the block, whose
+    /// block table is proven from the public inputs of the block.
+    Evm,
+}
+
+impl CircuitKind {
+    /// Returns the log2 of the number of rows of the circuit, which is the
+    /// degree of the setup params it's proven with.
+    pub fn degree(&self) -> u32 {
+        match self {
+            Self::State => 14,
+            // The bitwise lookup tables take 3 * 2^16 rows
+            Self::Evm => 18,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct StateCircuitConfig<F> {
+    state: StateConfig<
+        F,
+        GLOBAL_COUNTER_MAX,
+        MEMORY_ROWS_MAX,
+        MEMORY_ADDRESS_MAX,
+        STACK_ROWS_MAX,
+        STACK_ADDRESS_MAX,
+        STORAGE_ROWS_MAX,
+        ACCOUNT_ROWS_MAX,
+        TX_ROWS_MAX,
+    >,
+    mpt: MptConfig<F>,
+    keccak: KeccakConfig<F>,
+    instance: Column<Instance>,
+}
+
+#[derive(Default)]
+struct StateCircuit<F> {
+    memory_ops: Vec<Operation<MemoryOp>>,
+    stack_ops: Vec<Operation<StackOp>>,
+    storage_ops: Vec<Operation<StorageOp>>,
+    account_nonce_ops: Vec<Operation<AccountNonceOp>>,
+    account_balance_ops: Vec<Operation<AccountBalanceOp>>,
+    account_code_hash_ops: Vec<Operation<AccountCodeHashOp>>,
+    tx_access_list_account_ops: Vec<Operation<TxAccessListAccountOp>>,
+    tx_access_list_account_storage_ops:
+        Vec<Operation<TxAccessListAccountStorageOp>>,
+    tx_refund_ops: Vec<Operation<TxRefundOp>>,
+    call_context_ops: Vec<Operation<CallContextOp>>,
+    tx_log_ops: Vec<Operation<TxLogOp>>,
+    // The state root before the updates
+    state_root: F,
+    mpt_updates: Vec<UnrolledUpdate<F>>,
+}
+
+impl<F: FieldExt> StateCircuit<F> {
+    fn new(block: &Block) -> Result<Self, Error> {
+        let container = &block.container;
+        let prev_state_root = block
+            .prev_state_root
+            .ok_or(PublicInputError::MissingPrevStateRoot)?;
+        let memory_ops = container.sorted_memory();
+        let stack_ops = container.sorted_stack();
+        let storage_ops = container.sorted_storage();
+        let account_nonce_ops = container.sorted_account_nonce();
+        let account_balance_ops = container.sorted_account_balance();
+        let account_code_hash_ops = container.sorted_account_code_hash();
+        let tx_access_list_account_ops =
+            container.sorted_tx_access_list_account();
+        let tx_access_list_account_storage_ops =
+            container.sorted_tx_access_list_account_storage();
+        let tx_refund_ops = container.sorted_tx_refund();
+        let call_context_ops = container.sorted_call_context();
+        let tx_log_ops = container.sorted_tx_log();
+        let account_rows = account_nonce_ops.len()
+            + account_balance_ops.len()
+            + account_code_hash_ops.len();
+        let tx_rows = tx_access_list_account_ops.len()
+            + tx_access_list_account_storage_ops.len()
+            + tx_refund_ops.len()
+            + call_context_ops.len()
+            + tx_log_ops.len();
+        if memory_rows(&memory_ops) > MEMORY_ROWS_MAX
+            || stack_ops.len() > STACK_ROWS_MAX
+            || storage_ops.len() > STORAGE_ROWS_MAX
+            || account_rows > ACCOUNT_ROWS_MAX
+            || tx_rows > TX_ROWS_MAX
+        {
+            return Err(Error::TooManyOperations);
+        }
+        Ok(Self {
+            memory_ops,
+            stack_ops,
+            storage_ops,
+            account_nonce_ops,
+            account_balance_ops,
+            account_code_hash_ops,
+            tx_access_list_account_ops,
+            tx_access_list_account_storage_ops,
+            tx_refund_ops,
+            call_context_ops,
+            tx_log_ops,
+            state_root: root_rlc(&prev_state_root),
+            mpt_updates: mpt_updates(block, prev_state_root)?,
+        })
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for StateCircuit<F> {
+    type Config = StateCircuitConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let mpt_table = [(); 5].map(|_| meta.advice_column());
+        let keccak_table = [(); 3].map(|_| meta.advice_column());
+        let update_table = [(); 5].map(|_| meta.advice_column());
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        Self::Config {
+            state: StateConfig::configure(meta, mpt_table, update_table),
+            mpt: MptConfig::configure(
+                meta,
+                r(),
+                mpt_table,
+                keccak_table,
+                update_table,
+            ),
+            keccak: KeccakConfig::configure(meta, r(), keccak_table),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), plonk::Error> {
+        config.state.load(&mut layouter)?;
+        config.state.assign(
+            layouter.namespace(|| "state operations"),
+            self.memory_ops.clone(),
+            self.stack_ops.clone(),
+            self.storage_ops.clone(),
+            self.account_nonce_ops.clone(),
+            self.account_balance_ops.clone(),
+            self.account_code_hash_ops.clone(),
+            self.tx_access_list_account_ops.clone(),
+            self.tx_access_list_account_storage_ops.clone(),
+            self.tx_refund_ops.clone(),
+            self.call_context_ops.clone(),
+            self.tx_log_ops.clone(),
+            // The words are encoded with the randomness of the MPT circuit,
+            // whose table the storage and account values are looked up in
+            r(),
+        )?;
+
+        let hashes = self
+            .mpt_updates
+            .iter()
+            .flat_map(|update| update.hashes.iter().cloned())
+            .collect::<Vec<_>>();
+        config.mpt.load(&mut layouter)?;
+        let roots = config.mpt.assign(
+            &mut layouter,
+            MPT_ROWS,
+            self.state_root,
+            &self.mpt_updates,
+        )?;
+        // The state roots before and after the updates are the public inputs
+        for (row, root) in roots.iter().enumerate() {
+            layouter.constrain_instance(*root, config.instance, row)?;
+        }
+        config
+            .keccak
+            .assign(&mut layouter, KECCAK_BLOCKS, &hashes)?;
+
+        Ok(())
+    }
+}
+
+// Returns the random linear combination of the bytes of a state root, as
+// held by the MPT circuit.
+fn root_rlc<F: FieldExt>(root: &H256) -> F {
+    encode(root.0.iter().cloned(), r())
+}
+
+// Returns the updates of the state trie of `block`, of root `state_root`, to
+// the values of its storage slots and account fields after their last ops,
+// proven from the state proofs of the block, and unrolled into the rows of
+// the MPT circuit.  The slots and the fields which are only read are updated
+// to their value.
+fn mpt_updates<F: FieldExt>(
+    block: &Block,
+    state_root: H256,
+) -> Result<Vec<UnrolledUpdate<F>>, TrieError> {
+    let container = &block.container;
+    let ops = container
+        .sorted_storage()
+        .iter()
+        .map(|oper| {
+            let op = oper.op();
+            (
+                (*op.address(), MptField::Storage, *op.key()),
+                usize::from(oper.gc()),
+                *op.value(),
+            )
+        })
+        .chain(container.sorted_account_nonce().iter().map(|oper| {
+            let op = oper.op();
+            (
+                (*op.address(), MptField::Nonce, Word::zero()),
+                usize::from(oper.gc()),
+                *op.value(),
+            )
+        }))
+        .chain(container.sorted_account_balance().iter().map(|oper| {
+            let op = oper.op();
+            (
+                (*op.address(), MptField::Balance, Word::zero()),
+                usize::from(oper.gc()),
+                *op.value(),
+            )
+        }))
+        .chain(container.sorted_account_code_hash().iter().map(|oper| {
+            let op = oper.op();
+            (
+                (*op.address(), MptField::CodeHash, Word::zero()),
+                usize::from(oper.gc()),
+                Word::from_big_endian(op.value().as_bytes()),
+            )
+        }));
+
+    // The value of each slot and field after its last op, where the slots of
+    // an account are set before its fields, which may destroy it
+    let mut values = BTreeMap::new();
+    for ((address, field, key), gc, value) in ops {
+        let last = values
+            .entry((address, field != MptField::Storage, field, key))
+            .or_insert((gc, value));
+        if gc > last.0 {
+            *last = (gc, value);
+        }
+    }
+
+    let mut trie = StateTrie::new(state_root, &block.state_proofs)?;
+    let mut updates = Vec::new();
+    for ((address, _, field, key), (_, value)) in values {
+        updates.extend(
+            trie.update(address, field, key, value)?
+                .into_iter()
+                .map(|update| unroll(update, r())),
+        );
+    }
+
+    Ok(updates)
+}
+
+/// Returns the instance columns of the circuit of `kind` for `block`, which
+/// are its public inputs.  The state circuit has the random linear
+/// combinations of the state roots before and after the txs of the block, and
+/// the EVM circuit has the words of the block, see [`public_inputs`], which
+/// hold the same state roots.
+pub fn instances(
+    kind: CircuitKind,
+    block: &Block,
+) -> Result<Vec<Vec<Fr>>, Error> {
+    Ok(match kind {
+        CircuitKind::State => {
+            let prev_state_root = block
+                .prev_state_root
+                .ok_or(PublicInputError::MissingPrevStateRoot)?;
+            vec![vec![
+                root_rlc(&prev_state_root),
+                root_rlc(&block.state_root),
+            ]]
+        }
+        CircuitKind::Evm => vec![public_inputs(block, MAX_TXS)?],
+    })
+}
+
+/// Returns the setup params of degree `k`, sampled from the randomness of
+/// the OS.  The params set up by a single party are only as secure as its
+/// toxic waste is discarded, so they're meant to be set up once and shared
+/// with [`write_params`].
+pub fn setup(k: u32) -> Params<G1Affine> {
+    Setup::<Bn256>::new(k, OsRng)
+}
+
+/// Returns the setup params of degree `k`, sampled deterministically from
+/// `seed`.  Anyone who knows the seed knows the toxic waste, so these params
+/// are only meant for reproducible tests and benchmarks.
+pub fn setup_from_seed(k: u32, seed: [u8; 16]) -> Params<G1Affine> {
+    Setup::<Bn256>::new(k, XorShiftRng::from_seed(seed))
+}
+
+/// Generates the proving key of the circuit of `kind`, which holds its
+/// verifying key.  The fixed columns of the circuits only depend on their
+/// sizes, so the keys are valid for all the blocks which fit in them.
+pub fn keygen(
+    params: &Params<G1Affine>,
+    kind: CircuitKind,
+) -> Result<ProvingKey<G1Affine>, Error> {
+    Ok(match kind {
+        CircuitKind::State => {
+            let circuit = StateCircuit::<Fr>::default();
+            keygen_pk(params, keygen_vk(params, &circuit)?, &circuit)?
+        }
+        CircuitKind::Evm => {
+            let circuit = EvmCircuit::default();
+            keygen_pk(params, keygen_vk(params, &circuit)?, &circuit)?
+        }
+    })
+}
+
+/// Creates the proof of the circuit of `kind` for `block`, with the instance
+/// columns given by [`instances`].
+pub fn prove(
+    params: &Params<G1Affine>,
+    pk: &ProvingKey<G1Affine>,
+    kind: CircuitKind,
+    block: &Block,
+) -> Result<Vec<u8>, Error> {
+    let instances = instances(kind, block)?;
+    let instances: Vec<&[Fr]> = instances.iter().map(Vec::as_slice).collect();
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    match kind {
+        CircuitKind::State => create_proof(
+            params,
+            pk,
+            &[StateCircuit::<Fr>::new(block)?],
+            &[&instances],
+            &mut transcript,
+        )?,
+        CircuitKind::Evm => create_proof(
+            params,
+            pk,
+            &[EvmCircuit::new(block)?],
+            &[&instances],
+            &mut transcript,
+        )?,
+    }
+    Ok(transcript.finalize())
+}
+
+/// Verifies the `proof` of the circuit with the verifying key `vk`, against
+/// its `instances`.
+pub fn verify(
+    params: &Params<G1Affine>,
+    vk: &VerifyingKey<G1Affine>,
+    instances: &[Vec<Fr>],
+    proof: &[u8],
+) -> Result<(), Error> {
+    // The EVM circuit has the longest instance column
+    let verifier_params =
+        Setup::<Bn256>::verifier_params(params, instance_len(MAX_TXS))?;
+    let instances: Vec<&[Fr]> = instances.iter().map(Vec::as_slice).collect();
+    let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(proof);
+    verify_proof(&verifier_params, vk, &[&instances], &mut transcript)?;
+    Ok(())
+}
+
+/// Writes the setup `params` to the file at `path`.
+pub fn write_params(
+    params: &Params<G1Affine>,
+    path: impl AsRef<Path>,
+) -> Result<(), Error> {
+    params.write(&mut BufWriter::new(File::create(path)?))?;
+    Ok(())
+}
+
+/// Reads the setup params from the file at `path`.
+pub fn read_params(path: impl AsRef<Path>) -> Result<Params<G1Affine>, Error> {
+    Ok(Params::read(&mut BufReader::new(File::open(path)?))?)
+}
+
+/// Writes the verifying key `vk` to the file at `path`.
+pub fn write_vk(
+    vk: &VerifyingKey<G1Affine>,
+    path: impl AsRef<Path>,
+) -> Result<(), Error> {
+    vk.write(&mut BufWriter::new(File::create(path)?))?;
+    Ok(())
+}
+
+/// Reads the verifying key of the circuit of `kind` from the file at `path`,
+/// with the setup `params` it was generated with.
+pub fn read_vk(
+    params: &Params<G1Affine>,
+    kind: CircuitKind,
+    path: impl AsRef<Path>,
+) -> Result<VerifyingKey<G1Affine>, Error> {
+    let mut reader = BufReader::new(File::open(path)?);
+    Ok(match kind {
+        CircuitKind::State => {
+            VerifyingKey::read::<_, StateCircuit<Fr>>(&mut reader, params)?
+        }
+        CircuitKind::Evm => {
+            VerifyingKey::read::<_, EvmCircuit>(&mut reader, params)?
+        }
+    })
+}
+
+/// Writes the `proof` to the file at `path`.
+pub fn write_proof(proof: &[u8], path: impl AsRef<Path>) -> Result<(), Error> {
+    fs::write(path, proof)?;
+    Ok(())
+}
+
+/// Reads a proof from the file at `path`.
+pub fn read_proof(path: impl AsRef<Path>) -> Result<Vec<u8>, Error> {
+    Ok(fs::read(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        instances, keygen, prove, read_params, read_proof, read_vk,
+        setup_from_seed, verify, write_params, write_proof, write_vk,
+        CircuitKind,
+    };
+    use bus_mapping::{
+        bytecode, circuit_input_builder::CircuitInputBuilder, mock,
+    };
+    use pairing::bn256::Fr;
+    use std::{env, process};
+
+    // Proves the circuit of `kind` for a block with a single tx, after
+    // writing and reading back the params, the verifying key and the proof
+    fn prove_and_verify(kind: CircuitKind) {
+        let code = bytecode! {
+            PUSH1(0x2a)
+            PUSH1(0x40)
+            MSTORE
+            STOP
+        };
+        let block = mock::BlockData::new_single_tx_trace_code(&code).unwrap();
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+        // The tx doesn't update the state trie
+        builder.block.prev_state_root = Some(builder.block.state_root);
+
+        // The files are named by the process, so that concurrent runs don't
+        // overwrite each other's files
+        let dir = env::temp_dir();
+        let name = format!("{:?}-{}", kind, process::id()).to_lowercase();
+        let params_path = dir.join(format!("{}.params", name));
+        let vk_path = dir.join(format!("{}.vk", name));
+        let proof_path = dir.join(format!("{}.proof", name));
+
+        let params = setup_from_seed(kind.degree(), [0x42; 16]);
+        write_params(&params, &params_path).unwrap();
+        let params = read_params(&params_path).unwrap();
+
+        let pk = keygen(&params, kind).unwrap();
+        write_vk(pk.get_vk(), &vk_path).unwrap();
+        let proof = prove(&params, &pk, kind, &builder.block).unwrap();
+        write_proof(&proof, &proof_path).unwrap();
+
+        let vk = read_vk(&params, kind, &vk_path).unwrap();
+        let mut proof = read_proof(&proof_path).unwrap();
+        let mut instances = instances(kind, &builder.block).unwrap();
+        assert!(verify(&params, &vk, &instances, &proof).is_ok());
+
+        // The proof doesn't verify against other public inputs
+        if let Some(instance) = instances.first_mut() {
+            instance[0] += Fr::from(1);
+            assert!(verify(&params, &vk, &instances, &proof).is_err());
+            instance[0] -= Fr::from(1);
+        }
+
+        // A proof with a modified byte doesn't verify
+        proof[0] ^= 1;
+        assert!(verify(&params, &vk, &instances, &proof).is_err());
+    }
+
+    // The proofs take minutes to create, at the degrees of the circuits
+    #[test]
+    #[ignore]
+    fn state_circuit_proof() {
+        prove_and_verify(CircuitKind::State);
+    }
+
+    #[test]
+    #[ignore]
+    fn evm_circuit_proof() {
+        prove_and_verify(CircuitKind::Evm);
+    }
+}
@@ -0,0 +1,63 @@
+//! The public input circuit implementation.
+
+pub(crate) mod public_input;
+
+pub use public_input::{instance_len, public_inputs, PublicInputError};
+
+/*
+Example instance column, with the words of a block with a single tx, in the
+layout for blocks of at most 2 txs:
+
+| row |      value       |
+--------------------------
+|  0  | hash.hi          |
+|  1  | hash.lo          |
+|  2  | parent_hash.hi   |
+|  3  | parent_hash.lo   |
+|  .. |        ..        |   // state roots before and after the txs
+|  8  | coinbase.hi      |
+|  9  | coinbase.lo      |
+|  .. |        ..        |   // timestamp to base fee
+|  22 | history[0].hi    |
+|  .. |        ..        |   // 256 previous block hashes
+| 534 | tx_hash[0].hi    |
+| 535 | tx_hash[0].lo    |
+| 536 |        0         |   // unused tx slot
+| 537 |        0         |
+
+Each word is committed to by its 16 high and 16 low bytes, so that the
+instance column can be computed by a verifier without the randomness of the
+rlc.  The layout only depends on the maximum number of txs, and the previous
+block hashes are padded with zeros in front up to 256 hashes.
+
+Each word takes a row per byte, from the most significant one.  The bytes of
+each half are accumulated in base 256, and the bytes of the word in base r:
+
+| tag       | index      | byte | acc         | rlc | block_table          |
+--------------------------------------------------------------------------
+| Coinbase  |     0      | 0x00 | 0x00        | ..  | 0, 0, 0              |
+|    ..     |     ..     |  ..  |     ..      | ..  |        ..            |
+| Coinbase  |     0      | 0x00 | coinbase.hi | ..  | 0, 0, 0              |   // byte 15
+|    ..     |     ..     |  ..  |     ..      | ..  |        ..            |
+| Coinbase  |     0      | 0x5e | coinbase.lo | rlc | Coinbase, 0, rlc     |   // byte 31
+|    ..     |     ..     |  ..  |     ..      | ..  |        ..            |
+| BlockHash | number-256 | 0x00 | 0x00        | ..  | 0, 0, 0              |
+
+The accumulated halves are copied to the instance column on the last byte of
+each half, and the block table has a row with the rlc of each word on its last
+byte.  The tag and the index of each word are fixed, but for the previous
+block hashes, whose index is the number of the block, taken from the low half
+of the number word, minus their distance to the block.
+
+The EVM circuit configures this circuit on its block table, so that the
+fields and the previous block hashes it looks up are the committed ones.
+
+The state roots before and after the txs are also the public inputs of the
+state circuit, as the first and last state roots of its MPT circuit, so that
+the proofs of both circuits are checked against the same block.
+
+The hash of each tx is looked up in the tx table at its index, but for the
+unused tx slots whose hashes are zero, and the tx circuit looks up the hash of
+each of its txs in the block table, so that the txs proven by the tx circuit
+are the ones of the block.
+*/
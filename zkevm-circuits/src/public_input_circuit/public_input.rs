@@ -0,0 +1,732 @@
+use crate::{
+    evm_circuit::{BlockContextField, TxContextField},
+    gadget::{
+        evm_word::encode,
+        is_zero::{IsZeroChip, IsZeroConfig, IsZeroInstruction},
+    },
+};
+use bus_mapping::{circuit_input_builder::Block, eth_types::Word};
+use halo2::{
+    circuit::Layouter,
+    plonk::{
+        Advice, Column, ConstraintSystem, Error, Expression, Fixed, Instance,
+    },
+    poly::Rotation,
+};
+use pairing::arithmetic::FieldExt;
+use std::iter;
+
+/// The number of previous block hashes, which are the ones accessible by
+/// BLOCKHASH.
+pub(crate) const NUM_HISTORY_HASHES: usize = 256;
+
+/// The tag of a word of the public inputs, which is the tag of its row in the
+/// block table for the fields of the block context.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum BlockTag {
+    Context(BlockContextField),
+    Hash,
+    ParentHash,
+    PrevStateRoot,
+    StateRoot,
+    TxHash,
+}
+
+impl BlockTag {
+    pub(crate) fn value(&self) -> u64 {
+        match self {
+            Self::Context(field) => *field as u64,
+            // After the tags of the block context
+            Self::Hash => 0x100,
+            Self::ParentHash => 0x101,
+            Self::PrevStateRoot => 0x102,
+            Self::StateRoot => 0x103,
+            Self::TxHash => 0x104,
+        }
+    }
+}
+
+// The words which precede the previous block hashes
+const HEADER_TAGS: [BlockTag; 11] = [
+    BlockTag::Hash,
+    BlockTag::ParentHash,
+    BlockTag::PrevStateRoot,
+    BlockTag::StateRoot,
+    BlockTag::Context(BlockContextField::Coinbase),
+    BlockTag::Context(BlockContextField::Timestamp),
+    BlockTag::Context(BlockContextField::Number),
+    BlockTag::Context(BlockContextField::Difficulty),
+    BlockTag::Context(BlockContextField::GasLimit),
+    BlockTag::Context(BlockContextField::ChainId),
+    BlockTag::Context(BlockContextField::BaseFee),
+];
+
+// Returns the tag of each word of the layout for `max_txs` txs, with the fixed
+// part of its index, and whether the number of the block is added to it.
+fn layout<F: FieldExt>(max_txs: usize) -> Vec<(BlockTag, F, bool)> {
+    let block_hash = BlockTag::Context(BlockContextField::BlockHash);
+    HEADER_TAGS
+        .iter()
+        .map(|tag| (*tag, F::zero(), false))
+        .chain((0..NUM_HISTORY_HASHES).map(|idx| {
+            let distance = (NUM_HISTORY_HASHES - idx) as u64;
+            (block_hash, -F::from(distance), true)
+        }))
+        .chain(
+            (0..max_txs)
+                .map(|idx| (BlockTag::TxHash, F::from(idx as u64 + 1), false)),
+        )
+        .collect()
+}
+
+/// Error returned when the public inputs of a block can't be computed.
+#[derive(Debug, PartialEq, Eq)]
+pub enum PublicInputError {
+    /// The block has more txs than the layout, which has room for the given
+    /// number of txs.
+    TooManyTxs(usize),
+    /// The block has more than 256 previous block hashes.
+    TooManyHistoryHashes,
+    /// The state root before the txs of the block isn't set.
+    MissingPrevStateRoot,
+}
+
+fn word_bytes(word: &Word) -> [u8; 32] {
+    let mut bytes = [0; 32];
+    word.to_big_endian(&mut bytes);
+    bytes
+}
+
+/// Returns the big-endian bytes of the words of `block`, in the layout for
+/// `max_txs` txs, which are assigned by [`Config::assign`].
+pub(crate) fn words(
+    block: &Block,
+    max_txs: usize,
+) -> Result<Vec<[u8; 32]>, PublicInputError> {
+    let txs = block.txs();
+    let constants = &block.constants;
+    let history_hashes = constants.history_hashes();
+    if txs.len() > max_txs {
+        return Err(PublicInputError::TooManyTxs(max_txs));
+    }
+    if history_hashes.len() > NUM_HISTORY_HASHES {
+        return Err(PublicInputError::TooManyHistoryHashes);
+    }
+    let prev_state_root = block
+        .prev_state_root
+        .ok_or(PublicInputError::MissingPrevStateRoot)?;
+
+    let mut coinbase = [0; 32];
+    coinbase[12..].copy_from_slice(constants.coinbase().as_bytes());
+    Ok(vec![
+        constants.hash().to_fixed_bytes(),
+        block.parent_hash.to_fixed_bytes(),
+        prev_state_root.to_fixed_bytes(),
+        block.state_root.to_fixed_bytes(),
+        coinbase,
+        word_bytes(constants.timestamp()),
+        word_bytes(&Word::from(constants.number().as_u64())),
+        word_bytes(constants.difficulty()),
+        word_bytes(constants.gas_limit()),
+        word_bytes(constants.chain_id()),
+        word_bytes(constants.base_fee()),
+    ]
+    .into_iter()
+    .chain(
+        iter::repeat([0; 32]).take(NUM_HISTORY_HASHES - history_hashes.len()),
+    )
+    .chain(history_hashes.iter().map(|hash| hash.to_fixed_bytes()))
+    .chain(txs.iter().map(|tx| tx.hash.to_fixed_bytes()))
+    .chain(iter::repeat([0; 32]).take(max_txs - txs.len()))
+    .collect())
+}
+
+/// Returns the number of rows of the instance column of the public input
+/// circuit for blocks of at most `max_txs` txs.
+pub fn instance_len(max_txs: usize) -> usize {
+    2 * (HEADER_TAGS.len() + NUM_HISTORY_HASHES + max_txs)
+}
+
+/// Returns the instance column of the public input circuit for `block`, in the
+/// layout for blocks of at most `max_txs` txs.  It holds the hashes of the
+/// block and of its parent, the state roots before and after its txs, its
+/// constants, the hashes of its previous blocks and the hashes of its txs,
+/// each split into its 16 high and 16 low bytes, as field elements.
+///
+/// Fails if the block has more than `max_txs` txs or more than 256 previous
+/// block hashes, or if its previous state root isn't set.
+pub fn public_inputs<F: FieldExt>(
+    block: &Block,
+    max_txs: usize,
+) -> Result<Vec<F>, PublicInputError> {
+    Ok(words(block, max_txs)?
+        .iter()
+        .flat_map(|bytes| [&bytes[..16], &bytes[16..]])
+        .map(|half| encode(half.iter().cloned(), F::from(256)))
+        .collect())
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Config<F> {
+    r: F,
+    q_enable: Column<Fixed>,
+    q_word_start: Column<Fixed>,
+    q_half_start: Column<Fixed>,
+    q_word_end: Column<Fixed>,
+    q_number: Column<Fixed>,
+    q_history: Column<Fixed>,
+    q_tx_hash: Column<Fixed>,
+    tag: Column<Fixed>,
+    index: Column<Fixed>,
+    byte: Column<Advice>,
+    acc: Column<Advice>,
+    rlc: Column<Advice>,
+    number: Column<Advice>,
+    rlc_is_zero: IsZeroConfig<F>,
+    block_table: [Column<Advice>; 3],
+    tx_table: [Column<Advice>; 3],
+    byte_table: Column<Fixed>,
+    instance: Column<Instance>,
+}
+
+impl<F: FieldExt> Config<F> {
+    /// Configures the constraints proving the content of `block_table`, whose
+    /// columns are the tag of the field, the block number of a block hash or
+    /// the index of a tx hash, and the value, from the words of `instance`.
+    /// The hashes of the txs are looked up in `tx_table`, whose columns are
+    /// the tx id, the tag of the field and its value, but for the unused tx
+    /// slots, whose hashes are zero.
+    pub(crate) fn configure(
+        meta: &mut ConstraintSystem<F>,
+        r: F,
+        block_table: [Column<Advice>; 3],
+        tx_table: [Column<Advice>; 3],
+        instance: Column<Instance>,
+    ) -> Self {
+        let q_enable = meta.fixed_column();
+        let q_word_start = meta.fixed_column();
+        let q_half_start = meta.fixed_column();
+        let q_word_end = meta.fixed_column();
+        let q_number = meta.fixed_column();
+        let q_history = meta.fixed_column();
+        let q_tx_hash = meta.fixed_column();
+        let tag = meta.fixed_column();
+        let index = meta.fixed_column();
+        let byte = meta.advice_column();
+        let acc = meta.advice_column();
+        let rlc = meta.advice_column();
+        let number = meta.advice_column();
+        let rlc_inv = meta.advice_column();
+        let byte_table = meta.fixed_column();
+
+        // The accumulated halves are copied to the instance column
+        meta.enable_equality(acc);
+        meta.enable_equality(instance);
+
+        meta.create_gate("public input rows", |meta| {
+            let one = Expression::Constant(F::one());
+
+            let mut query_fixed =
+                |column| meta.query_fixed(column, Rotation::cur());
+            let q_enable_cur = query_fixed(q_enable);
+            let q_word_start = query_fixed(q_word_start);
+            let q_half_start = query_fixed(q_half_start);
+            let q_word_end = query_fixed(q_word_end);
+            let q_number = query_fixed(q_number);
+            let q_history = query_fixed(q_history);
+            let tag = query_fixed(tag);
+            let index = query_fixed(index);
+            let q_enable_next = meta.query_fixed(q_enable, Rotation::next());
+
+            let byte = meta.query_advice(byte, Rotation::cur());
+            let [acc_prev, acc] = [Rotation::prev(), Rotation::cur()]
+                .map(|at| meta.query_advice(acc, at));
+            let [rlc_prev, rlc] = [Rotation::prev(), Rotation::cur()]
+                .map(|at| meta.query_advice(rlc, at));
+            let [number, number_next] = [Rotation::cur(), Rotation::next()]
+                .map(|at| meta.query_advice(number, at));
+            let [table_tag, table_index, table_value] = block_table
+                .map(|column| meta.query_advice(column, Rotation::cur()));
+
+            let mut constraints = Vec::new();
+
+            // The bytes of each half are accumulated in base 256, and the
+            // bytes of each word in base r
+            constraints.push(
+                acc.clone()
+                    - ((one.clone() - q_half_start)
+                        * acc_prev
+                        * Expression::Constant(F::from(256))
+                        + byte.clone()),
+            );
+            constraints.push(
+                rlc.clone()
+                    - ((one - q_word_start)
+                        * rlc_prev
+                        * Expression::Constant(r)
+                        + byte),
+            );
+
+            // The number of the block is the same on every row, and is the low
+            // half of the number word
+            constraints.push(q_enable_next * (number_next - number.clone()));
+            constraints.push(q_number * (number.clone() - acc));
+
+            // The block table has a row on the last byte of each word
+            constraints.push(table_tag - q_word_end.clone() * tag);
+            constraints.push(
+                table_index - q_word_end.clone() * (q_history * number + index),
+            );
+            constraints.push(table_value - q_word_end * rlc);
+
+            constraints
+                .into_iter()
+                .map(|constraint| q_enable_cur.clone() * constraint)
+                .collect::<Vec<_>>()
+        });
+
+        // Each byte is in the range of a byte
+        meta.lookup_any(|meta| {
+            let q_enable = meta.query_fixed(q_enable, Rotation::cur());
+            let byte = meta.query_advice(byte, Rotation::cur());
+            vec![(
+                q_enable * byte,
+                meta.query_fixed(byte_table, Rotation::cur()),
+            )]
+        });
+
+        // The hash of each tx is the one of the tx table for its index, but
+        // for the unused tx slots
+        let rlc_is_zero = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_fixed(q_tx_hash, Rotation::cur()),
+            |meta| meta.query_advice(rlc, Rotation::cur()),
+            rlc_inv,
+        );
+        meta.lookup_any(|meta| {
+            let q_tx = meta.query_fixed(q_tx_hash, Rotation::cur())
+                * (Expression::Constant(F::one())
+                    - rlc_is_zero.is_zero_expression.clone());
+
+            vec![
+                meta.query_fixed(index, Rotation::cur()),
+                Expression::Constant(F::from(TxContextField::TxHash as u64)),
+                meta.query_advice(rlc, Rotation::cur()),
+            ]
+            .into_iter()
+            .zip(tx_table)
+            .map(|(expr, column)| {
+                (
+                    q_tx.clone() * expr,
+                    meta.query_advice(column, Rotation::cur()),
+                )
+            })
+            .collect::<Vec<_>>()
+        });
+
+        Self {
+            r,
+            q_enable,
+            q_word_start,
+            q_half_start,
+            q_word_end,
+            q_number,
+            q_history,
+            q_tx_hash,
+            tag,
+            index,
+            byte,
+            acc,
+            rlc,
+            number,
+            rlc_is_zero,
+            block_table,
+            tx_table,
+            byte_table,
+            instance,
+        }
+    }
+
+    pub(crate) fn load(
+        &self,
+        layouter: &mut impl Layouter<F>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "byte table",
+            |mut region| {
+                for byte in 0..=u8::MAX {
+                    region.assign_fixed(
+                        || "byte table",
+                        self.byte_table,
+                        byte as usize,
+                        || Ok(F::from(byte as u64)),
+                    )?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// Assigns the `words` of a block in the layout for `max_txs` txs,
+    /// followed by padding rows up to `size` rows.
+    pub(crate) fn assign(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        size: usize,
+        max_txs: usize,
+        words: &[[u8; 32]],
+    ) -> Result<(), Error> {
+        let layout = layout::<F>(max_txs);
+        let number_tag = BlockTag::Context(BlockContextField::Number);
+        // The number of the block fits in the low 8 bytes of its word
+        let number_idx = HEADER_TAGS
+            .iter()
+            .position(|tag| *tag == number_tag)
+            .unwrap();
+        let mut number_bytes = [0; 8];
+        number_bytes.copy_from_slice(&words[number_idx][24..]);
+        let number = F::from(u64::from_be_bytes(number_bytes));
+        let rlc_is_zero = IsZeroChip::construct(self.rlc_is_zero.clone());
+
+        let cells = layouter.assign_region(
+            || "public input rows",
+            |mut region| {
+                let mut cells = Vec::new();
+                let mut offset = 0;
+                for ((tag, index_fixed, is_history), bytes) in
+                    layout.iter().zip(words.iter())
+                {
+                    // The index of a previous block hash follows from the
+                    // number of the block
+                    let index = if *is_history {
+                        number + *index_fixed
+                    } else {
+                        *index_fixed
+                    };
+                    let mut acc = F::zero();
+                    let mut rlc = F::zero();
+                    for (idx, byte) in bytes.iter().enumerate() {
+                        let byte = F::from(*byte as u64);
+                        if idx % 16 == 0 {
+                            acc = F::zero();
+                        }
+                        acc = acc * F::from(256) + byte;
+                        rlc = rlc * self.r + byte;
+                        let is_word_end = idx == 31;
+
+                        for (column, value) in [
+                            (self.q_enable, F::one()),
+                            (self.q_word_start, F::from((idx == 0) as u64)),
+                            (
+                                self.q_half_start,
+                                F::from((idx % 16 == 0) as u64),
+                            ),
+                            (self.q_word_end, F::from(is_word_end as u64)),
+                            (
+                                self.q_number,
+                                F::from(
+                                    (*tag == number_tag && is_word_end) as u64,
+                                ),
+                            ),
+                            (self.q_history, F::from(*is_history as u64)),
+                            (
+                                self.q_tx_hash,
+                                F::from(
+                                    (*tag == BlockTag::TxHash && is_word_end)
+                                        as u64,
+                                ),
+                            ),
+                            (self.tag, F::from(tag.value())),
+                            (self.index, *index_fixed),
+                        ] {
+                            region.assign_fixed(
+                                || "public input fixed",
+                                column,
+                                offset,
+                                || Ok(value),
+                            )?;
+                        }
+
+                        let table = if is_word_end {
+                            [F::from(tag.value()), index, rlc]
+                        } else {
+                            [F::zero(); 3]
+                        };
+                        let mut advice = vec![
+                            (self.byte, byte),
+                            (self.rlc, rlc),
+                            (self.number, number),
+                        ];
+                        advice.extend(
+                            self.block_table.iter().cloned().zip(table),
+                        );
+                        for (column, value) in advice {
+                            region.assign_advice(
+                                || "public input advice",
+                                column,
+                                offset,
+                                || Ok(value),
+                            )?;
+                        }
+                        rlc_is_zero.assign(&mut region, offset, Some(rlc))?;
+                        let cell = region.assign_advice(
+                            || "acc",
+                            self.acc,
+                            offset,
+                            || Ok(acc),
+                        )?;
+                        if idx % 16 == 15 {
+                            cells.push(cell);
+                        }
+
+                        offset += 1;
+                    }
+                }
+                if offset > size {
+                    return Err(Error::Synthesis);
+                }
+
+                // The padding rows restart the accumulators on each row, and
+                // have no row in the block table
+                for offset in offset..size {
+                    for (column, value) in [
+                        (self.q_enable, F::one()),
+                        (self.q_word_start, F::one()),
+                        (self.q_half_start, F::one()),
+                    ] {
+                        region.assign_fixed(
+                            || "public input fixed",
+                            column,
+                            offset,
+                            || Ok(value),
+                        )?;
+                    }
+                    for column in [self.byte, self.acc, self.rlc]
+                        .iter()
+                        .chain(self.block_table.iter())
+                    {
+                        region.assign_advice(
+                            || "public input advice",
+                            *column,
+                            offset,
+                            || Ok(F::zero()),
+                        )?;
+                    }
+                    region.assign_advice(
+                        || "number",
+                        self.number,
+                        offset,
+                        || Ok(number),
+                    )?;
+                    rlc_is_zero.assign(&mut region, offset, Some(F::zero()))?;
+                }
+
+                Ok(cells)
+            },
+        )?;
+
+        for (row, cell) in cells.into_iter().enumerate() {
+            layouter.constrain_instance(cell, self.instance, row)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{instance_len, public_inputs, words, Config, PublicInputError};
+    use crate::{
+        evm_circuit::TxContextField,
+        gadget::evm_word::{encode, r},
+    };
+    use bus_mapping::{
+        bytecode,
+        circuit_input_builder::{Block, CircuitInputBuilder},
+        eth_types::H256,
+        mock,
+    };
+    use halo2::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        dev::MockProver,
+        plonk::{Advice, Circuit, Column, ConstraintSystem, Error},
+    };
+    use pairing::{arithmetic::FieldExt, bn256::Fr as Fp};
+    use std::iter;
+
+    const MAX_TXS: usize = 2;
+
+    struct PublicInputCircuit<'a> {
+        block: &'a Block,
+        size: usize,
+        // Whether the tx table has the hashes of the txs of the block
+        with_txs: bool,
+    }
+
+    impl<'a, F: FieldExt> Circuit<F> for PublicInputCircuit<'a> {
+        type Config = (Config<F>, [Column<Advice>; 3]);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                block: self.block,
+                size: self.size,
+                with_txs: self.with_txs,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let block_table = [
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+            ];
+            let tx_table = [
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+            ];
+            let instance = meta.instance_column();
+            let config =
+                Config::configure(meta, r(), block_table, tx_table, instance);
+            (config, tx_table)
+        }
+
+        fn synthesize(
+            &self,
+            (config, tx_table): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            config.load(&mut layouter)?;
+            config.assign(
+                &mut layouter,
+                self.size,
+                MAX_TXS,
+                &words(self.block, MAX_TXS).unwrap(),
+            )?;
+
+            // The tx table has the hashes of the txs after a noop row
+            let txs = if self.with_txs { self.block.txs() } else { &[] };
+            let rows = iter::once([F::zero(); 3]).chain(
+                txs.iter().enumerate().map(|(idx, tx)| {
+                    [
+                        F::from(idx as u64 + 1),
+                        F::from(TxContextField::TxHash as u64),
+                        encode(tx.hash.0.iter().cloned(), r()),
+                    ]
+                }),
+            );
+            layouter.assign_region(
+                || "tx table",
+                |mut region| {
+                    for (offset, row) in rows.clone().enumerate() {
+                        for (column, value) in tx_table.iter().zip(row) {
+                            region.assign_advice(
+                                || "tx table",
+                                *column,
+                                offset,
+                                || Ok(value),
+                            )?;
+                        }
+                    }
+                    Ok(())
+                },
+            )?;
+
+            Ok(())
+        }
+    }
+
+    fn block() -> Block {
+        let code = bytecode! {
+            STOP
+        };
+        let mut block =
+            mock::BlockData::new_single_tx_trace_code(&code).unwrap();
+        block.eth_tx.hash = H256::from_low_u64_be(0x7a);
+        let mut builder = CircuitInputBuilder::new(
+            block.eth_block.clone(),
+            block.block_ctants.clone(),
+        );
+        builder.handle_tx(&block.eth_tx, &block.geth_trace).unwrap();
+        builder.block.prev_state_root = Some(H256::from_low_u64_be(0x5a));
+        builder.block.state_root = H256::from_low_u64_be(0x5b);
+        builder.block
+    }
+
+    fn verify_with_txs(
+        block: &Block,
+        instance: Vec<Fp>,
+        with_txs: bool,
+        success: bool,
+    ) {
+        let circuit = PublicInputCircuit {
+            block,
+            size: 9000,
+            with_txs,
+        };
+        let prover =
+            MockProver::<Fp>::run(14, &circuit, vec![instance]).unwrap();
+        assert_eq!(prover.verify().is_ok(), success);
+    }
+
+    fn verify(block: &Block, instance: Vec<Fp>, success: bool) {
+        verify_with_txs(block, instance, true, success);
+    }
+
+    #[test]
+    fn public_input_layout() {
+        let block = block();
+        let instance = public_inputs::<Fp>(&block, MAX_TXS).unwrap();
+        assert_eq!(instance.len(), instance_len(MAX_TXS));
+
+        // The state roots follow the hashes of the block and of its parent
+        assert_eq!(instance[4..8], [0, 0x5a, 0, 0x5b].map(Fp::from));
+        // The coinbase fits in the low half, except its first 4 bytes
+        assert_eq!(instance[8], Fp::from(0));
+        assert_eq!(instance[9], Fp::from(0xc014ba5e));
+        // The number of the block is the low half of its word
+        assert_eq!(instance[13], Fp::from(123456));
+        // The tx hash is followed by an unused slot
+        let txs = &instance[instance.len() - 2 * MAX_TXS..];
+        assert_eq!(txs, [0, 0x7a, 0, 0].map(Fp::from));
+    }
+
+    #[test]
+    fn public_input_circuit() {
+        let block = block();
+        let instance = public_inputs(&block, MAX_TXS).unwrap();
+        verify(&block, instance, true);
+    }
+
+    #[test]
+    fn public_input_circuit_invalid_instance() {
+        let block = block();
+        let mut instance = public_inputs(&block, MAX_TXS).unwrap();
+        // The state root after the txs
+        instance[7] += Fp::one();
+        verify(&block, instance, false);
+    }
+
+    #[test]
+    fn public_input_circuit_missing_tx() {
+        // The hash of the tx isn't in the tx table
+        let block = block();
+        let instance = public_inputs(&block, MAX_TXS).unwrap();
+        verify_with_txs(&block, instance, false, false);
+    }
+
+    #[test]
+    fn public_input_errors() {
+        let mut block = block();
+        assert_eq!(
+            public_inputs::<Fp>(&block, 0),
+            Err(PublicInputError::TooManyTxs(0))
+        );
+        block.prev_state_root = None;
+        assert_eq!(
+            public_inputs::<Fp>(&block, MAX_TXS),
+            Err(PublicInputError::MissingPrevStateRoot)
+        );
+    }
+}
@@ -1,10 +1,17 @@
 use crate::gadget::{
+    evm_word::encode,
     is_zero::{IsZeroChip, IsZeroConfig, IsZeroInstruction},
     monotone::{MonotoneChip, MonotoneConfig},
     Variable,
 };
-use bus_mapping::eth_types::ToScalar;
-use bus_mapping::operation::{MemoryOp, Operation, StackOp, StorageOp};
+use crate::mpt_circuit::trie::MptField;
+use array_init::array_init;
+use bus_mapping::eth_types::{Address, Word};
+use bus_mapping::operation::{
+    AccountBalanceOp, AccountCodeHashOp, AccountNonceOp, CallContextOp,
+    MemoryOp, Operation, StackOp, StorageOp, Target, TxAccessListAccountOp,
+    TxAccessListAccountStorageOp, TxLogOp, TxRefundOp, RW,
+};
 use halo2::{
     circuit::{Layouter, Region},
     plonk::{
@@ -15,6 +22,20 @@ use halo2::{
 };
 use pairing::arithmetic::FieldExt;
 
+// Number of bytes of an account address
+const NUM_ADDRESS_BYTES: usize = 20;
+
+// Targets of the ops of the tx section, which are scoped to a tx or to a call,
+// each of which has a column flagging its rows
+const NUM_TX_TARGETS: usize = 5;
+const TX_TARGETS: [Target; NUM_TX_TARGETS] = [
+    Target::TxAccessListAccountStorage,
+    Target::TxRefund,
+    Target::CallContext,
+    Target::TxLog,
+    Target::TxAccessListAccount,
+];
+
 /*
 Example state table:
 
@@ -36,6 +57,10 @@ Example state table:
 |    1     |    1    |       55       |  32   |   1  |    0    |      5      |     0      |   // first storage op at the new address has to be write
 |    4     |    1    |       56       |  33   |   1  |    0    |      8      |     32     |
 |    4     |         |                |       |      |    1    |             |            |   // padding
+|    1     |    1    |       20       |  1    |   1  |    0    |      1      |     0      |   // account nonce
+|    5     |    1    |       3        |  32   |   0  |    0    |      2      |     32     |   // account balance
+|    5     |    1    |       22       |  0    |   1  |    0    |      2      |     32     |
+|    5     |         |                |       |      |    1    |             |            |   // padding
 */
 
 // q_target:
@@ -43,13 +68,41 @@ Example state table:
 // 2 - memory
 // 3 - stack
 // 4 - storage
+// 5 - account (nonce, balance and code hash)
 
 // address presents memory address, stack pointer, and account address for
 // memory, stack, and storage ops respectively two columns are not displayed:
 // address_diff and storage_key_diff (needed to check whether the address or
 // storage_key changed) storage_key and value_prev are needed for storage ops
 // only padding specifies whether the row is just a padding to fill all the rows
-// that are intended for a particular target
+// that are intended for a particular target. For account ops, storage_key
+// holds the field of the account (see `AccountField`).
+
+// For memory and stack ops, storage_key holds the id of the call owning the
+// memory or the stack, so that their ops are ordered first by call id and then
+// by address, and every call starts with an empty memory and stack. A memory
+// init row is added whenever the call id or the address changes.
+
+// Two more columns are used by storage ops only: is_revert marks the writes
+// undoing the write at reverted_global_counter to the same slot when a call
+// fails, whose value has to be the value_prev of that write.
+
+// Storage ops also carry the id of the tx they belong to and the value of the
+// slot at the start of that tx (the committed value of EIP-2200), which is the
+// value_prev of the first write (or the value of the first read) of the slot
+// in the tx. tx_id_diff is not displayed either.
+
+// The ops scoped to a tx (see `TX_TARGETS`) follow the account ops in the tx
+// section, whose rows have q_target = 0 and q_tx = 1.  They are sorted by
+// target, tx id, address, storage_key and global_counter, and a column per
+// target flags their rows.  For access list ops, address is the account
+// address and storage_key is the storage slot (or 0 for an account).  The
+// access list of a tx starts empty, so every account and slot is cold at its
+// first access in the tx.  Refund ops have neither address nor storage_key, and
+// the refund counter of a tx starts at 0.  Call context ops are scoped to a
+// call instead, whose id is in address, with the field in storage_key and
+// tx_id 0.  Log ops have the id of the log in address, and 4 * index + field
+// in storage_key (see `TxLogField`), and the logs of a tx are only appended.
 
 /*
 Example bus mapping:
@@ -67,6 +120,29 @@ Example bus mapping:
 |    3   |    1    |       49       |  32   |             |            |  0   |
 */
 
+/// Field of an account accessed by an account operation, which is stored in
+/// the storage_key column.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum AccountField {
+    Nonce = 1,
+    Balance,
+    CodeHash,
+}
+
+// An op of the tx section, whose key is given by its target, tx id, address
+// and storage key.
+struct TxRow<F> {
+    target: Target,
+    tx_id: usize,
+    address: Address,
+    // The storage key, which is compared as a word and assigned as its value
+    key: (Word, F),
+    global_counter: usize,
+    rw: RW,
+    value: F,
+    value_prev: F,
+}
+
 /// A mapping derived from witnessed memory operations.
 /// TODO: The complete version of this mapping will involve storage, stack,
 /// and opcode details as well.
@@ -90,6 +166,8 @@ pub(crate) struct Config<
     const STACK_ROWS_MAX: usize,
     const STACK_ADDRESS_MAX: usize,
     const STORAGE_ROWS_MAX: usize,
+    const ACCOUNT_ROWS_MAX: usize,
+    const TX_ROWS_MAX: usize,
 > {
     q_target: Column<Fixed>,
     address: Column<Advice>, /* used for memory address, stack pointer, and
@@ -102,14 +180,42 @@ pub(crate) struct Config<
     storage_key: Column<Advice>,
     storage_key_diff_inv: Column<Advice>,
     value_prev: Column<Advice>,
+    q_storage: Column<Fixed>,
+    is_revert: Column<Advice>,
+    reverted_global_counter: Column<Advice>,
+    tx_id: Column<Advice>,
+    tx_id_diff_inv: Column<Advice>,
+    committed_value: Column<Advice>,
+    q_account: Column<Fixed>,
+    // Values of the storage slot or of the account field of a storage or
+    // account op before its first op and after its last op
+    initial_value: Column<Advice>,
+    final_value: Column<Advice>,
+    // Bytes of the difference minus one between the address of a storage,
+    // account or tx op and the address of the previous one, when they differ
+    address_diff_bytes: [Column<Advice>; NUM_ADDRESS_BYTES],
+    q_tx: Column<Fixed>,
+    // Flags of the target of a tx op, in the order of TX_TARGETS
+    tx_target_flags: [Column<Advice>; NUM_TX_TARGETS],
+    tx_target_diff_inv: Column<Advice>,
+    // Whether a tx op has the same target and tx id, the same address too, and
+    // the same storage_key too, as the previous op
+    tx_key_prefix: [Column<Advice>; 3],
     global_counter_table: Column<Fixed>,
     memory_address_table_zero: Column<Fixed>,
     stack_address_table_zero: Column<Fixed>,
     memory_value_table: Column<Fixed>,
+    mpt_table: [Column<Advice>; 5],
+    // Address, field, key, initial and final values of the storage slot or
+    // of the account field on its first row, and zeros on the other rows
+    update_table: [Column<Advice>; 5],
     address_diff_is_zero: IsZeroConfig<F>,
     address_monotone: MonotoneConfig,
+    call_id_monotone: MonotoneConfig,
     padding_monotone: MonotoneConfig,
     storage_key_diff_is_zero: IsZeroConfig<F>,
+    tx_id_diff_is_zero: IsZeroConfig<F>,
+    tx_target_diff_is_zero: IsZeroConfig<F>,
 }
 
 impl<
@@ -120,6 +226,8 @@ impl<
         const STACK_ROWS_MAX: usize,
         const STACK_ADDRESS_MAX: usize,
         const STORAGE_ROWS_MAX: usize,
+        const ACCOUNT_ROWS_MAX: usize,
+        const TX_ROWS_MAX: usize,
     >
     Config<
         F,
@@ -129,10 +237,21 @@ impl<
         STACK_ROWS_MAX,
         STACK_ADDRESS_MAX,
         STORAGE_ROWS_MAX,
+        ACCOUNT_ROWS_MAX,
+        TX_ROWS_MAX,
     >
 {
-    /// Set up custom gates and lookup arguments for this configuration.
-    pub(crate) fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+    /// Set up custom gates and lookup arguments for this configuration.  The
+    /// values of the storage slots and of the account fields before and after
+    /// their ops are looked up in `mpt_table`, which is proven by the MPT
+    /// circuit.  Conversely, the updates of the MPT circuit other than those of
+    /// the storage roots are looked up in `update_table`, which is assigned
+    /// here with a row per storage slot and account field.
+    pub(crate) fn configure(
+        meta: &mut ConstraintSystem<F>,
+        mpt_table: [Column<Advice>; 5],
+        update_table: [Column<Advice>; 5],
+    ) -> Self {
         let q_target = meta.fixed_column();
         let address = meta.advice_column();
         let address_diff_inv = meta.advice_column();
@@ -143,6 +262,20 @@ impl<
         let storage_key = meta.advice_column();
         let storage_key_diff_inv = meta.advice_column();
         let value_prev = meta.advice_column();
+        let q_storage = meta.fixed_column();
+        let is_revert = meta.advice_column();
+        let reverted_global_counter = meta.advice_column();
+        let tx_id = meta.advice_column();
+        let tx_id_diff_inv = meta.advice_column();
+        let committed_value = meta.advice_column();
+        let q_account = meta.fixed_column();
+        let initial_value = meta.advice_column();
+        let final_value = meta.advice_column();
+        let address_diff_bytes = array_init(|_| meta.advice_column());
+        let q_tx = meta.fixed_column();
+        let tx_target_flags = array_init(|_| meta.advice_column());
+        let tx_target_diff_inv = meta.advice_column();
+        let tx_key_prefix = array_init(|_| meta.advice_column());
         let global_counter_table = meta.fixed_column();
         let memory_address_table_zero = meta.fixed_column();
         let stack_address_table_zero = meta.fixed_column();
@@ -152,6 +285,7 @@ impl<
         let two = Expression::Constant(F::from(2));
         let three = Expression::Constant(F::from(3));
         let four = Expression::Constant(F::from(4));
+        let five = Expression::Constant(F::from(5));
 
         let q_memory_first = |meta: &mut VirtualCells<F>| {
             // For first memory row it holds q_target_cur = 1 and q_target_next
@@ -164,17 +298,19 @@ impl<
             q_target_cur.clone()
                 * (two.clone() - q_target_cur.clone())
                 * (three.clone() - q_target_cur.clone())
-                * (four.clone() - q_target_cur)
+                * (four.clone() - q_target_cur.clone())
+                * (five.clone() - q_target_cur)
                 * (q_target_next.clone() - one.clone())
                 * (three.clone() - q_target_next.clone())
-                * (four.clone() - q_target_next)
+                * (four.clone() - q_target_next.clone())
+                * (five.clone() - q_target_next)
         };
 
         let q_memory_first_norm = |meta: &mut VirtualCells<F>| {
             let e = q_memory_first(meta);
-            // q_memory_first is 12 when q_target_cur is 1 and q_target_next is
-            // 2, we use 1/12 to normalize the value
-            let inv = F::from(12_u64).invert().unwrap();
+            // q_memory_first is 144 when q_target_cur is 1 and q_target_next
+            // is 2, we use 1/144 to normalize the value
+            let inv = F::from(144_u64).invert().unwrap();
             let i = Expression::Constant(inv);
 
             e * i
@@ -186,14 +322,15 @@ impl<
             q_target.clone()
                 * (q_target.clone() - one.clone())
                 * (three.clone() - q_target.clone())
-                * (four.clone() - q_target)
+                * (four.clone() - q_target.clone())
+                * (five.clone() - q_target)
         };
 
         let q_memory_not_first_norm = |meta: &mut VirtualCells<F>| {
             let e = q_memory_not_first(meta);
-            // q_memory_not_first is 4 when target is 2, we use 1/4 to normalize
-            // the value
-            let inv = F::from(4_u64).invert().unwrap();
+            // q_memory_not_first is 12 when target is 2, we use 1/12 to
+            // normalize the value
+            let inv = F::from(12_u64).invert().unwrap();
             let i = Expression::Constant(inv);
 
             e * i
@@ -205,16 +342,18 @@ impl<
             q_target_cur.clone()
                 * (two.clone() - q_target_cur.clone())
                 * (three.clone() - q_target_cur.clone())
-                * (four.clone() - q_target_cur)
+                * (four.clone() - q_target_cur.clone())
+                * (five.clone() - q_target_cur)
                 * (q_target_next.clone() - one.clone())
                 * (q_target_next.clone() - two.clone())
-                * (four.clone() - q_target_next)
+                * (four.clone() - q_target_next.clone())
+                * (five.clone() - q_target_next)
         };
 
         let q_stack_first_norm = |meta: &mut VirtualCells<F>| {
             let e = q_stack_first(meta);
-            // q_stack_first is 12, we use 1/12 to normalize the value
-            let inv = F::from(12_u64).invert().unwrap();
+            // q_stack_first is 96, we use 1/96 to normalize the value
+            let inv = F::from(96_u64).invert().unwrap();
             let i = Expression::Constant(inv);
 
             e * i
@@ -226,14 +365,15 @@ impl<
             q_target.clone()
                 * (q_target.clone() - one.clone())
                 * (q_target.clone() - two.clone())
-                * (four.clone() - q_target)
+                * (four.clone() - q_target.clone())
+                * (five.clone() - q_target)
         };
 
         let q_stack_not_first_norm = |meta: &mut VirtualCells<F>| {
             let e = q_stack_not_first(meta);
-            // q_stack_not_first is 6 when target is 3, we use 1/6 to normalize
-            // the value
-            let inv = F::from(6_u64).invert().unwrap();
+            // q_stack_not_first is 12 when target is 3, we use 1/12 to
+            // normalize the value
+            let inv = F::from(12_u64).invert().unwrap();
             let i = Expression::Constant(inv);
 
             e * i
@@ -244,7 +384,8 @@ impl<
             q_target.clone()
                 * (q_target.clone() - one.clone())
                 * (q_target.clone() - two.clone())
-                * (q_target - three.clone())
+                * (q_target.clone() - three.clone())
+                * (five.clone() - q_target)
         };
 
         let q_storage_not_first_norm = |meta: &mut VirtualCells<F>| {
@@ -257,13 +398,62 @@ impl<
             e * i
         };
 
+        let q_account_not_first = |meta: &mut VirtualCells<F>| {
+            let q_target = meta.query_fixed(q_target, Rotation::cur());
+            q_target.clone()
+                * (q_target.clone() - one.clone())
+                * (q_target.clone() - two.clone())
+                * (q_target.clone() - three.clone())
+                * (q_target - four.clone())
+        };
+
+        let q_account_not_first_norm = |meta: &mut VirtualCells<F>| {
+            let e = q_account_not_first(meta);
+            // q_account_not_first is 120 when target is 5, we use 1/120 to
+            // normalize the value
+            let inv = F::from(120_u64).invert().unwrap();
+            let i = Expression::Constant(inv);
+
+            e * i
+        };
+
+        // The tx ops have q_target = 0, and their first row has no previous
+        // tx op.
+        let q_tx_not_first = |meta: &mut VirtualCells<F>| {
+            meta.query_fixed(q_tx, Rotation::cur())
+                * meta.query_fixed(q_tx, Rotation::prev())
+        };
+
+        // The target of a tx op, as the value of its `Target`
+        let tx_target = |meta: &mut VirtualCells<F>, at: Rotation| {
+            tx_target_flags.iter().zip(TX_TARGETS.iter()).fold(
+                Expression::Constant(F::zero()),
+                |acc, (column, target)| {
+                    acc + meta.query_advice(*column, at)
+                        * Expression::Constant(F::from(*target as u64))
+                },
+            )
+        };
+
+        // Whether a tx op has one of `targets`
+        let is_tx_target = |meta: &mut VirtualCells<F>, targets: &[Target]| {
+            tx_target_flags
+                .iter()
+                .zip(TX_TARGETS.iter())
+                .filter(|(_, target)| targets.contains(target))
+                .fold(Expression::Constant(F::zero()), |acc, (column, _)| {
+                    acc + meta.query_advice(*column, Rotation::cur())
+                })
+        };
+
         let address_diff_is_zero = IsZeroChip::configure(
             meta,
             |meta| {
                 let padding = meta.query_advice(padding, Rotation::cur());
                 let is_not_padding = one.clone() - padding;
                 let q_target = meta.query_fixed(q_target, Rotation::cur());
-                let q_not_first = q_target.clone() * (q_target - one.clone());
+                let q_not_first = q_target.clone() * (q_target - one.clone())
+                    + q_tx_not_first(meta);
 
                 q_not_first * is_not_padding
             },
@@ -275,8 +465,31 @@ impl<
             address_diff_inv,
         );
 
+        let storage_key_diff_is_zero = IsZeroChip::configure(
+            meta,
+            |meta| {
+                let padding = meta.query_advice(padding, Rotation::cur());
+                let is_not_padding = one.clone() - padding;
+
+                let q_target = meta.query_fixed(q_target, Rotation::cur());
+                let q_not_first = q_target.clone() * (q_target - one.clone())
+                    + q_tx_not_first(meta);
+
+                q_not_first * is_not_padding
+            },
+            |meta| {
+                let storage_key_cur =
+                    meta.query_advice(storage_key, Rotation::cur());
+                let storage_key_prev =
+                    meta.query_advice(storage_key, Rotation::prev());
+                storage_key_cur - storage_key_prev
+            },
+            storage_key_diff_inv,
+        );
+
         // Only one monotone gadget is used for memory and stack (with
-        // MEMORY_ADDRESS_MAX as it is bigger)
+        // MEMORY_ADDRESS_MAX as it is bigger).  The address only needs to be
+        // monotone within the ops of a call.
         let address_monotone =
             MonotoneChip::<F, MEMORY_ADDRESS_MAX, true, false>::configure(
                 meta,
@@ -288,11 +501,29 @@ impl<
                     let q_not_first = q_memory_not_first_norm(meta)
                         + q_stack_not_first_norm(meta);
 
-                    q_not_first * is_not_padding
+                    q_not_first
+                        * is_not_padding
+                        * storage_key_diff_is_zero.clone().is_zero_expression
                 },
                 address,
             );
 
+        // The call ids of memory and stack ops are global counters, so they
+        // are monotone within GLOBAL_COUNTER_MAX.
+        let call_id_monotone =
+            MonotoneChip::<F, GLOBAL_COUNTER_MAX, true, false>::configure(
+                meta,
+                |meta| {
+                    let padding = meta.query_advice(padding, Rotation::cur());
+                    let is_not_padding = one.clone() - padding;
+                    let q_not_first = q_memory_not_first_norm(meta)
+                        + q_stack_not_first_norm(meta);
+
+                    q_not_first * is_not_padding
+                },
+                storage_key,
+            );
+
         // Padding monotonicity could be checked using gates (as padding only
         // takes values 0 and 1), but it's much slower than using a
         // lookup.
@@ -323,17 +554,17 @@ impl<
         });
 
         meta.create_gate("Memory operation + padding", |meta| {
-            // If address_cur != address_prev, this is an `init`. We must
-            // constrain:
+            // If address_cur != address_prev or the call id changes, this is
+            // an `init`. We must constrain:
             //      - values[0] == [0]
             //      - flags[0] == 1
             //      - global_counters[0] == 0
             let q_memory_not_first = q_memory_not_first(meta);
-            let address_diff = {
-                let address_prev = meta.query_advice(address, Rotation::prev());
-                let address_cur = meta.query_advice(address, Rotation::cur());
-                address_cur - address_prev
-            };
+            let padding = meta.query_advice(padding, Rotation::cur());
+            let is_new_key = (one.clone() - padding.clone())
+                * (one.clone()
+                    - address_diff_is_zero.clone().is_zero_expression
+                        * storage_key_diff_is_zero.clone().is_zero_expression);
 
             let value_cur = meta.query_advice(value, Rotation::cur());
             let flag = meta.query_advice(flag, Rotation::cur());
@@ -350,23 +581,22 @@ impl<
             let q_read = one.clone() - flag;
 
             let q_target = meta.query_fixed(q_target, Rotation::cur());
-            let padding = meta.query_advice(padding, Rotation::cur());
             let bool_check_padding = padding.clone() * (one.clone() - padding);
 
             vec![
                 q_memory_not_first.clone()
-                    * address_diff.clone()
-                    * value_cur.clone(), // when address changes, the write value is 0
+                    * is_new_key.clone()
+                    * value_cur.clone(), // when address or call id changes, the write value is 0
                 q_memory_not_first.clone()
-                    * address_diff.clone()
-                    * q_read.clone(), // when address changes, the flag is 1 (write)
-                q_memory_not_first.clone() * address_diff * global_counter, // when address changes, global_counter is 0
+                    * is_new_key.clone()
+                    * q_read.clone(), // when address or call id changes, the flag is 1 (write)
+                q_memory_not_first.clone() * is_new_key * global_counter, // when address or call id changes, global_counter is 0
                 q_memory_not_first.clone() * bool_check_flag, // flag is either 0 or 1
                 q_memory_not_first * q_read * (value_cur - value_prev), // when reading, the value is the same as at the previous op
                 // Note that this last constraint needs to hold only when address doesn't change,
                 // but we don't need to check this as the first operation at the address always
                 // has to be write - that means q_read is 1 only when
-                // the address and storage key don't change.
+                // the address and call id don't change.
                 q_target * bool_check_padding, // padding is 0 or 1
             ]
         });
@@ -386,7 +616,7 @@ impl<
             // If flag == 0 (read), and global_counter != 0, value_prev == value_cur
             let value_prev = meta.query_advice(value, Rotation::prev());
             let q_read = one.clone() - flag;
-            // when addresses changes, we don't require the operation is write as this is enforced by evm circuit
+            // when the address or the call id changes, we don't require the operation is write as this is enforced by evm circuit
 
             vec![
                 q_stack_not_first.clone() * bool_check_flag, // flag is either 0 or 1
@@ -395,8 +625,9 @@ impl<
         });
 
         // global_counter monotonicity is checked for memory and stack when
-        // address_cur == address_prev. (Recall that operations are
-        // ordered first by address, and then by global_counter.)
+        // address_cur == address_prev and the call id doesn't change. (Recall
+        // that operations are ordered first by call id, then by address, and
+        // then by global_counter.)
         meta.lookup_any(|meta| {
             let global_counter_table =
                 meta.query_fixed(global_counter_table, Rotation::cur());
@@ -413,6 +644,7 @@ impl<
                 q_not_first
                     * is_not_padding
                     * address_diff_is_zero.clone().is_zero_expression
+                    * storage_key_diff_is_zero.clone().is_zero_expression
                     * (global_counter - global_counter_prev - one.clone()), // - 1 because it needs to be strictly monotone
                 global_counter_table,
             )]
@@ -462,25 +694,24 @@ impl<
             vec![(q_memory_not_first * value, memory_value_table)]
         });
 
-        let storage_key_diff_is_zero = IsZeroChip::configure(
+        let tx_id_diff_is_zero = IsZeroChip::configure(
             meta,
             |meta| {
                 let padding = meta.query_advice(padding, Rotation::cur());
                 let is_not_padding = one.clone() - padding;
 
                 let q_target = meta.query_fixed(q_target, Rotation::cur());
-                let q_not_first = q_target.clone() * (q_target - one.clone());
+                let q_not_first = q_target.clone() * (q_target - one.clone())
+                    + q_tx_not_first(meta);
 
                 q_not_first * is_not_padding
             },
             |meta| {
-                let storage_key_cur =
-                    meta.query_advice(storage_key, Rotation::cur());
-                let storage_key_prev =
-                    meta.query_advice(storage_key, Rotation::prev());
-                storage_key_cur - storage_key_prev
+                let tx_id_cur = meta.query_advice(tx_id, Rotation::cur());
+                let tx_id_prev = meta.query_advice(tx_id, Rotation::prev());
+                tx_id_cur - tx_id_prev
             },
-            storage_key_diff_inv,
+            tx_id_diff_inv,
         );
 
         meta.create_gate("First storage row operation", |meta| {
@@ -489,17 +720,24 @@ impl<
             let q_storage_first = q_target_cur.clone()
                 * (two.clone() - q_target_cur.clone())
                 * (three.clone() - q_target_cur.clone())
-                * (four.clone() - q_target_cur)
+                * (four.clone() - q_target_cur.clone())
+                * (five.clone() - q_target_cur)
                 * (q_target_next.clone() - one.clone())
                 * (q_target_next.clone() - two.clone())
-                * (q_target_next - three.clone());
+                * (q_target_next.clone() - three.clone())
+                * (five.clone() - q_target_next);
 
             let flag = meta.query_advice(flag, Rotation::cur());
             let q_read = one.clone() - flag;
+            let value_prev = meta.query_advice(value_prev, Rotation::cur());
+            let committed_value =
+                meta.query_advice(committed_value, Rotation::cur());
 
             vec![
-                q_storage_first * q_read, /* first storage op has to be
-                                           * write (flag = 1) */
+                q_storage_first.clone() * q_read, /* first storage op has to
+                                                   * be write (flag = 1) */
+                // The slot has value_prev at the start of the tx
+                q_storage_first * (committed_value - value_prev),
             ]
         });
 
@@ -536,6 +774,19 @@ impl<
             let padding = meta.query_advice(padding, Rotation::cur());
             let is_not_padding = one.clone() - padding;
 
+            // The slot and the tx don't change
+            let is_same_slot_and_tx =
+                address_diff_is_zero.clone().is_zero_expression
+                    * storage_key_diff_is_zero.clone().is_zero_expression
+                    * tx_id_diff_is_zero.clone().is_zero_expression;
+            let committed_value_cur =
+                meta.query_advice(committed_value, Rotation::cur());
+            let committed_value_prev =
+                meta.query_advice(committed_value, Rotation::prev());
+            // The value of the slot before the op
+            let value_before = flag.clone() * value_prev_cur.clone()
+                + q_read.clone() * value_cur.clone();
+
             vec![
                 q_storage_not_first.clone() * address_diff * q_read.clone(), // when address changes, the flag is 1 (write)
                 q_storage_not_first.clone() * storage_key_diff * q_read.clone(), // when storage_key_diff changes, the flag is 1 (write)
@@ -553,12 +804,20 @@ impl<
                     * address_diff_is_zero.clone().is_zero_expression
                     * storage_key_diff_is_zero.clone().is_zero_expression
                     * (value_prev_cur.clone() - value_previous),
-                is_not_padding
+                is_not_padding.clone()
                     * q_read
-                    * q_storage_not_first
+                    * q_storage_not_first.clone()
                     * address_diff_is_zero.clone().is_zero_expression
                     * storage_key_diff_is_zero.clone().is_zero_expression
                     * (value_prev_cur - value_prev_prev),
+                is_not_padding.clone()
+                    * q_storage_not_first.clone()
+                    * is_same_slot_and_tx.clone()
+                    * (committed_value_cur.clone() - committed_value_prev), // the committed value doesn't change during the tx
+                is_not_padding
+                    * q_storage_not_first
+                    * (one.clone() - is_same_slot_and_tx)
+                    * (committed_value_cur - value_before), // at the first op of the slot in the tx, the committed value is the value before the op
             ]
         });
 
@@ -587,7 +846,560 @@ impl<
             )]
         });
 
-        // TODO: monotone address for storage
+        // We don't require the first account op to be write, as the initial
+        // values of the accounts are given by the state trie.
+        meta.create_gate("Account operation", |meta| {
+            let q_account_not_first = q_account_not_first(meta);
+
+            let value_cur = meta.query_advice(value, Rotation::cur());
+            let value_prev_cur = meta.query_advice(value_prev, Rotation::cur());
+            let value_previous = meta.query_advice(value, Rotation::prev());
+            let flag = meta.query_advice(flag, Rotation::cur());
+
+            // flag == 0 or 1
+            // (flag) * (1 - flag)
+            let bool_check_flag = flag.clone() * (one.clone() - flag.clone());
+            let q_read = one.clone() - flag.clone();
+
+            let padding = meta.query_advice(padding, Rotation::cur());
+            let is_not_padding = one.clone() - padding;
+            // The address and the field of the account don't change
+            let is_same_key = address_diff_is_zero.clone().is_zero_expression
+                * storage_key_diff_is_zero.clone().is_zero_expression;
+
+            vec![
+                q_account_not_first.clone() * bool_check_flag, // flag is either 0 or 1
+                is_not_padding.clone()
+                    * q_read
+                    * q_account_not_first.clone()
+                    * is_same_key.clone()
+                    * (value_cur - value_previous.clone()), // when reading, the value is the same as at the previous op
+                is_not_padding
+                    * flag
+                    * q_account_not_first
+                    * is_same_key
+                    * (value_prev_cur - value_previous), // when writing, value_prev is the value at the previous op
+            ]
+        });
+
+        // global_counter monotonicity is checked for accounts when
+        // address_cur == address_prev and the field of the account doesn't
+        // change. (Recall that account operations are ordered first by
+        // account address, then by field, and finally by global_counter.)
+        meta.lookup_any(|meta| {
+            let global_counter_table =
+                meta.query_fixed(global_counter_table, Rotation::cur());
+            let global_counter_prev =
+                meta.query_advice(global_counter, Rotation::prev());
+            let global_counter =
+                meta.query_advice(global_counter, Rotation::cur());
+            let padding = meta.query_advice(padding, Rotation::cur());
+            let is_not_padding = one.clone() - padding;
+            let q_account_not_first = q_account_not_first_norm(meta);
+
+            vec![(
+                q_account_not_first
+                    * is_not_padding
+                    * address_diff_is_zero.clone().is_zero_expression
+                    * storage_key_diff_is_zero.clone().is_zero_expression
+                    * (global_counter - global_counter_prev - one.clone()), // - 1 because it needs to be strictly monotone
+                global_counter_table,
+            )]
+        });
+
+        // The address of the storage and account ops, and of the tx ops of
+        // the same target and tx, is strictly increasing when it changes,
+        // which is when the difference minus one fits in the bytes of an
+        // address.
+        meta.create_gate("Address monotonicity", |meta| {
+            let q_not_first = q_storage_not_first_norm(meta)
+                + q_account_not_first_norm(meta)
+                + q_tx_not_first(meta)
+                    * meta.query_advice(tx_key_prefix[0], Rotation::cur());
+            let padding = meta.query_advice(padding, Rotation::cur());
+            let is_not_padding = one.clone() - padding;
+            let address_diff = meta.query_advice(address, Rotation::cur())
+                - meta.query_advice(address, Rotation::prev());
+            let (diff_bytes, _) = address_diff_bytes.iter().fold(
+                (Expression::Constant(F::zero()), F::one()),
+                |(acc, multiplier), byte| {
+                    (
+                        acc + meta.query_advice(*byte, Rotation::cur())
+                            * multiplier,
+                        multiplier * F::from(256),
+                    )
+                },
+            );
+
+            vec![
+                q_not_first
+                    * is_not_padding
+                    * (one.clone()
+                        - address_diff_is_zero.clone().is_zero_expression)
+                    * (address_diff - one.clone() - diff_bytes),
+            ]
+        });
+        for byte in address_diff_bytes.iter() {
+            meta.lookup_any(|meta| {
+                let q_not_first = q_storage_not_first_norm(meta)
+                    + q_account_not_first_norm(meta)
+                    + q_tx_not_first(meta);
+                let byte = meta.query_advice(*byte, Rotation::cur());
+                let memory_value_table =
+                    meta.query_fixed(memory_value_table, Rotation::cur());
+
+                vec![(q_not_first * byte, memory_value_table)]
+            });
+        }
+
+        // The field of the account ops is strictly increasing when it changes
+        // and the address doesn't.
+        meta.lookup_any(|meta| {
+            let global_counter_table =
+                meta.query_fixed(global_counter_table, Rotation::cur());
+            let field_prev = meta.query_advice(storage_key, Rotation::prev());
+            let field = meta.query_advice(storage_key, Rotation::cur());
+            let padding = meta.query_advice(padding, Rotation::cur());
+            let is_not_padding = one.clone() - padding;
+            let q_account_not_first = q_account_not_first_norm(meta);
+
+            vec![(
+                q_account_not_first
+                    * is_not_padding
+                    * address_diff_is_zero.clone().is_zero_expression
+                    * (one.clone()
+                        - storage_key_diff_is_zero.clone().is_zero_expression)
+                    * (field - field_prev - one.clone()),
+                global_counter_table,
+            )]
+        });
+
+        meta.create_gate("Storage revert", |meta| {
+            let q_target = meta.query_fixed(q_target, Rotation::cur());
+            let q_storage = meta.query_fixed(q_storage, Rotation::cur());
+            let is_revert = meta.query_advice(is_revert, Rotation::cur());
+            let flag = meta.query_advice(flag, Rotation::cur());
+
+            vec![
+                q_storage.clone() * is_revert.clone() * (one.clone() - is_revert.clone()), // is_revert is either 0 or 1
+                q_storage.clone() * is_revert.clone() * (one.clone() - flag), // a revert is a write
+                // Only storage ops are reverted.  The rows without a target,
+                // such as the blinding rows, aren't constrained.
+                q_target * (one.clone() - q_storage) * is_revert,
+            ]
+        });
+
+        // A revert restores the value_prev of the write it undoes, which is
+        // at the same slot.  The storage writes are the rows with q_storage
+        // and flag set, and the rows of other targets match the zero input
+        // when is_revert is 0.
+        meta.lookup_any(|meta| {
+            let q_storage = meta.query_fixed(q_storage, Rotation::cur());
+            let is_revert = meta.query_advice(is_revert, Rotation::cur());
+            let address = meta.query_advice(address, Rotation::cur());
+            let storage_key = meta.query_advice(storage_key, Rotation::cur());
+            let global_counter =
+                meta.query_advice(global_counter, Rotation::cur());
+            let reverted_global_counter =
+                meta.query_advice(reverted_global_counter, Rotation::cur());
+            let value = meta.query_advice(value, Rotation::cur());
+            let value_prev = meta.query_advice(value_prev, Rotation::cur());
+            let flag = meta.query_advice(flag, Rotation::cur());
+
+            vec![
+                (
+                    is_revert.clone() * address.clone(),
+                    q_storage.clone() * address,
+                ),
+                (
+                    is_revert.clone() * storage_key.clone(),
+                    q_storage.clone() * storage_key,
+                ),
+                (
+                    is_revert.clone() * reverted_global_counter,
+                    q_storage.clone() * global_counter,
+                ),
+                (is_revert.clone() * value, q_storage.clone() * value_prev),
+                (is_revert, q_storage * flag),
+            ]
+        });
+
+        // The rows of a storage slot or of an account field, which are
+        // consecutive, have the same initial and final values, which are the
+        // value before the first op and the value after the last op.  The
+        // first row of each slot or field is copied to the update table, whose
+        // tag of a storage slot is MptField::Storage.
+        meta.create_gate("Initial and final values", |meta| {
+            let [q_storage_prev, q_storage, q_storage_next] =
+                [Rotation::prev(), Rotation::cur(), Rotation::next()]
+                    .map(|at| meta.query_fixed(q_storage, at));
+            let [q_account_prev, q_account, q_account_next] =
+                [Rotation::prev(), Rotation::cur(), Rotation::next()]
+                    .map(|at| meta.query_fixed(q_account, at));
+            let [padding, padding_next] = [Rotation::cur(), Rotation::next()]
+                .map(|at| meta.query_advice(padding, at));
+            let address_cur = meta.query_advice(address, Rotation::cur());
+            let storage_key_cur =
+                meta.query_advice(storage_key, Rotation::cur());
+            let flag = meta.query_advice(flag, Rotation::cur());
+            let value = meta.query_advice(value, Rotation::cur());
+            let value_prev = meta.query_advice(value_prev, Rotation::cur());
+            let [initial_value_prev, initial_value] =
+                [Rotation::prev(), Rotation::cur()]
+                    .map(|at| meta.query_advice(initial_value, at));
+            let [final_value_prev, final_value] =
+                [Rotation::prev(), Rotation::cur()]
+                    .map(|at| meta.query_advice(final_value, at));
+
+            let q_kv = q_storage.clone() + q_account.clone();
+            let q_key = q_kv.clone() * (one.clone() - padding.clone());
+            let field = q_storage.clone()
+                * Expression::Constant(F::from(MptField::Storage as u64))
+                + q_account.clone() * storage_key_cur.clone();
+            let key = q_storage.clone() * storage_key_cur;
+            // The previous row is an op of the same slot or field
+            let is_same_key_prev = (q_storage_prev * q_storage.clone()
+                + q_account_prev * q_account.clone())
+                * address_diff_is_zero.clone().is_zero_expression
+                * storage_key_diff_is_zero.clone().is_zero_expression;
+            // The next row is an op of the same slot or field, whose
+            // differences are checked by the IsZero gadgets on the next row
+            let is_same_key_next = {
+                let mut diff_is_zero = |column, inv| {
+                    one.clone()
+                        - (meta.query_advice(column, Rotation::next())
+                            - meta.query_advice(column, Rotation::cur()))
+                            * meta.query_advice(inv, Rotation::next())
+                };
+                (q_storage * q_storage_next + q_account * q_account_next)
+                    * (one.clone() - padding_next)
+                    * diff_is_zero(address, address_diff_inv)
+                    * diff_is_zero(storage_key, storage_key_diff_inv)
+            };
+            let value_before = flag.clone() * value_prev
+                + (one.clone() - flag) * value.clone();
+
+            // q_kv * is_first is q_key * (1 - is_same_key_prev), as q_kv is
+            // boolean
+            let is_first = (one.clone() - padding.clone())
+                * (one.clone() - is_same_key_prev.clone());
+            let update = [
+                address_cur,
+                field,
+                key,
+                initial_value.clone(),
+                final_value.clone(),
+            ];
+
+            let mut constraints = vec![
+                q_key.clone()
+                    * is_same_key_prev.clone()
+                    * (initial_value.clone() - initial_value_prev),
+                q_key.clone()
+                    * is_same_key_prev
+                    * (final_value.clone() - final_value_prev),
+                q_kv.clone()
+                    * is_first.clone()
+                    * (initial_value - value_before),
+                q_key
+                    * (one.clone() - is_same_key_next)
+                    * (final_value - value),
+            ];
+            constraints.extend(update.iter().zip(update_table).map(
+                |(value, column)| {
+                    q_kv.clone()
+                        * (meta.query_advice(column, Rotation::cur())
+                            - is_first.clone() * value.clone())
+                },
+            ));
+            constraints
+        });
+
+        // The initial and final values of the storage slots and of the
+        // account fields are the values before and after their update in the
+        // MPT table, which is given by the first row of the slot or field in
+        // the update table.
+        meta.lookup_any(|meta| {
+            let q_kv = meta.query_fixed(q_storage, Rotation::cur())
+                + meta.query_fixed(q_account, Rotation::cur());
+
+            update_table
+                .iter()
+                .zip(mpt_table)
+                .map(|(update_column, column)| {
+                    (
+                        q_kv.clone()
+                            * meta
+                                .query_advice(*update_column, Rotation::cur()),
+                        meta.query_advice(column, Rotation::cur()),
+                    )
+                })
+                .collect()
+        });
+
+        let tx_target_diff_is_zero = IsZeroChip::configure(
+            meta,
+            |meta| {
+                let padding = meta.query_advice(padding, Rotation::cur());
+                q_tx_not_first(meta) * (one.clone() - padding)
+            },
+            |meta| {
+                tx_target(meta, Rotation::cur())
+                    - tx_target(meta, Rotation::prev())
+            },
+            tx_target_diff_inv,
+        );
+
+        meta.create_gate("Tx operation", |meta| {
+            let [q_tx_prev, q_tx] = [Rotation::prev(), Rotation::cur()]
+                .map(|at| meta.query_fixed(q_tx, at));
+            let [padding_prev, padding] = [Rotation::prev(), Rotation::cur()]
+                .map(|at| meta.query_advice(padding, at));
+            let is_not_padding = one.clone() - padding.clone();
+            let flag = meta.query_advice(flag, Rotation::cur());
+            let target_flags = tx_target_flags
+                .map(|column| meta.query_advice(column, Rotation::cur()));
+            let [same_target_and_tx, same_address, same_key] = tx_key_prefix
+                .map(|column| meta.query_advice(column, Rotation::cur()));
+            let num_targets = target_flags
+                .iter()
+                .fold(Expression::Constant(F::zero()), |acc, target_flag| {
+                    acc + target_flag.clone()
+                });
+
+            let mut constraints = target_flags
+                .iter()
+                .map(|target_flag| {
+                    q_tx.clone()
+                        * target_flag.clone()
+                        * (one.clone() - target_flag.clone())
+                })
+                .collect::<Vec<_>>();
+            constraints.extend(vec![
+                // A tx op has one target, and a padding row none
+                q_tx.clone() * (num_targets - is_not_padding.clone()),
+                // padding is either 0 or 1
+                q_tx.clone() * padding.clone() * is_not_padding.clone(),
+                // The padding rows are at the end
+                q_tx.clone()
+                    * q_tx_prev.clone()
+                    * padding_prev
+                    * is_not_padding.clone(),
+                // flag is either 0 or 1
+                q_tx.clone() * flag.clone() * (one.clone() - flag),
+                // The key prefixes are 0 on the first row and on the padding
+                // rows
+                q_tx.clone()
+                    * (same_target_and_tx.clone()
+                        - q_tx_prev.clone()
+                            * is_not_padding
+                            * tx_target_diff_is_zero
+                                .clone()
+                                .is_zero_expression
+                            * tx_id_diff_is_zero.clone().is_zero_expression),
+                q_tx.clone()
+                    * (same_address.clone()
+                        - same_target_and_tx
+                            * address_diff_is_zero.clone().is_zero_expression),
+                q_tx * (same_key
+                    - same_address
+                        * storage_key_diff_is_zero.clone().is_zero_expression),
+            ]);
+            constraints
+        });
+
+        // The tx ops are sorted by target, tx id, address, storage_key and
+        // global_counter, where the address is checked by the address
+        // monotonicity gate.  The storage slots of the access list are encoded
+        // as words, which can't be compared, so their ops are only sorted by
+        // account.
+        // TODO: Check that the ops of a slot are consecutive, as a slot is
+        // taken as cold at the first op of each of its runs of ops.
+        meta.lookup_any(|meta| {
+            let padding = meta.query_advice(padding, Rotation::cur());
+            let global_counter_table =
+                meta.query_fixed(global_counter_table, Rotation::cur());
+
+            vec![(
+                q_tx_not_first(meta)
+                    * (one.clone() - padding)
+                    * (one.clone()
+                        - tx_target_diff_is_zero.clone().is_zero_expression)
+                    * (tx_target(meta, Rotation::cur())
+                        - tx_target(meta, Rotation::prev())
+                        - one.clone()),
+                global_counter_table,
+            )]
+        });
+        meta.lookup_any(|meta| {
+            let padding = meta.query_advice(padding, Rotation::cur());
+            let tx_id_diff = meta.query_advice(tx_id, Rotation::cur())
+                - meta.query_advice(tx_id, Rotation::prev());
+            let global_counter_table =
+                meta.query_fixed(global_counter_table, Rotation::cur());
+
+            vec![(
+                q_tx_not_first(meta)
+                    * (one.clone() - padding)
+                    * tx_target_diff_is_zero.clone().is_zero_expression
+                    * (one.clone()
+                        - tx_id_diff_is_zero.clone().is_zero_expression)
+                    * (tx_id_diff - one.clone()),
+                global_counter_table,
+            )]
+        });
+        meta.lookup_any(|meta| {
+            let same_address =
+                meta.query_advice(tx_key_prefix[1], Rotation::cur());
+            let is_int_key = one.clone()
+                - is_tx_target(meta, &[Target::TxAccessListAccountStorage]);
+            let storage_key_diff = meta
+                .query_advice(storage_key, Rotation::cur())
+                - meta.query_advice(storage_key, Rotation::prev());
+            let global_counter_table =
+                meta.query_fixed(global_counter_table, Rotation::cur());
+
+            vec![(
+                q_tx_not_first(meta)
+                    * same_address
+                    * is_int_key
+                    * (one.clone()
+                        - storage_key_diff_is_zero.clone().is_zero_expression)
+                    * (storage_key_diff - one.clone()),
+                global_counter_table,
+            )]
+        });
+        meta.lookup_any(|meta| {
+            let same_key = meta.query_advice(tx_key_prefix[2], Rotation::cur());
+            let global_counter_diff = meta
+                .query_advice(global_counter, Rotation::cur())
+                - meta.query_advice(global_counter, Rotation::prev());
+            let global_counter_table =
+                meta.query_fixed(global_counter_table, Rotation::cur());
+
+            vec![(
+                //
+                // - 1 because it needs to be strictly monotone
+                q_tx_not_first(meta)
+                    * same_key
+                    * (global_counter_diff - one.clone()),
+                global_counter_table,
+            )]
+        });
+
+        // An account or a storage slot is cold at its first access in a tx,
+        // and then warm or cold as left by its previous access.
+        meta.create_gate("Tx access list operation", |meta| {
+            let q_tx = meta.query_fixed(q_tx, Rotation::cur());
+            let is_access_list = is_tx_target(
+                meta,
+                &[
+                    Target::TxAccessListAccountStorage,
+                    Target::TxAccessListAccount,
+                ],
+            );
+            let is_account = is_tx_target(meta, &[Target::TxAccessListAccount]);
+            let same_key = meta.query_advice(tx_key_prefix[2], Rotation::cur());
+            let flag = meta.query_advice(flag, Rotation::cur());
+            let [value_previous, value] = [Rotation::prev(), Rotation::cur()]
+                .map(|at| meta.query_advice(value, at));
+            let value_prev = meta.query_advice(value_prev, Rotation::cur());
+            let storage_key = meta.query_advice(storage_key, Rotation::cur());
+            let value_before = flag.clone() * value_prev.clone()
+                + (one.clone() - flag) * value.clone();
+
+            let q_access_list = q_tx.clone() * is_access_list;
+            vec![
+                // value and value_prev are either 0 or 1
+                q_access_list.clone() * value.clone() * (one.clone() - value),
+                q_access_list.clone()
+                    * value_prev.clone()
+                    * (one.clone() - value_prev),
+                // The first access in the tx is cold
+                q_access_list.clone()
+                    * (one.clone() - same_key.clone())
+                    * value_before.clone(),
+                // The status is the one left by the previous access
+                q_access_list * same_key * (value_before - value_previous),
+                // An account has no slot
+                q_tx * is_account * storage_key,
+            ]
+        });
+
+        // The refund counter is 0 at the start of a tx, and then the value
+        // left by its previous op.
+        meta.create_gate("Tx refund operation", |meta| {
+            let q_refund = meta.query_fixed(q_tx, Rotation::cur())
+                * is_tx_target(meta, &[Target::TxRefund]);
+            let same_key = meta.query_advice(tx_key_prefix[2], Rotation::cur());
+            let flag = meta.query_advice(flag, Rotation::cur());
+            let [value_previous, value] = [Rotation::prev(), Rotation::cur()]
+                .map(|at| meta.query_advice(value, at));
+            let value_prev = meta.query_advice(value_prev, Rotation::cur());
+            let address = meta.query_advice(address, Rotation::cur());
+            let storage_key = meta.query_advice(storage_key, Rotation::cur());
+            let value_before =
+                flag.clone() * value_prev + (one.clone() - flag) * value;
+
+            vec![
+                q_refund.clone()
+                    * (one.clone() - same_key.clone())
+                    * value_before.clone(),
+                q_refund.clone() * same_key * (value_before - value_previous),
+                // The refund counter has no address nor storage_key
+                q_refund.clone() * address,
+                q_refund * storage_key,
+            ]
+        });
+
+        // A read of a field of a call context gets the value of its previous
+        // op. The first op of a field may be a read, as the context of
+        // the root call of a tx is set by the tx instead of by a
+        // caller.
+        meta.create_gate("Call context operation", |meta| {
+            let q_call_context = meta.query_fixed(q_tx, Rotation::cur())
+                * is_tx_target(meta, &[Target::CallContext]);
+            let same_key = meta.query_advice(tx_key_prefix[2], Rotation::cur());
+            let flag = meta.query_advice(flag, Rotation::cur());
+            let [value_previous, value] = [Rotation::prev(), Rotation::cur()]
+                .map(|at| meta.query_advice(value, at));
+            let tx_id = meta.query_advice(tx_id, Rotation::cur());
+
+            vec![
+                q_call_context.clone()
+                    * same_key
+                    * (one.clone() - flag)
+                    * (value - value_previous),
+                // A call context isn't scoped to a tx
+                q_call_context * tx_id,
+            ]
+        });
+
+        // The logs of a tx are only written, once per field, and their ids
+        // start at 1 and increase by one.
+        meta.create_gate("Tx log operation", |meta| {
+            let q_log = meta.query_fixed(q_tx, Rotation::cur())
+                * is_tx_target(meta, &[Target::TxLog]);
+            let [same_target_and_tx, same_key] = [0, 2].map(|index| {
+                meta.query_advice(tx_key_prefix[index], Rotation::cur())
+            });
+            let flag = meta.query_advice(flag, Rotation::cur());
+            let [log_id_prev, log_id] = [Rotation::prev(), Rotation::cur()]
+                .map(|at| meta.query_advice(address, at));
+            let log_id_diff = log_id.clone() - log_id_prev;
+
+            vec![
+                q_log.clone() * (one.clone() - flag),
+                q_log.clone() * same_key,
+                q_log.clone()
+                    * (one.clone() - same_target_and_tx.clone())
+                    * (log_id - one.clone()),
+                q_log
+                    * same_target_and_tx
+                    * log_id_diff.clone()
+                    * (log_id_diff - one.clone()),
+            ]
+        });
 
         Config {
             q_target,
@@ -600,14 +1412,33 @@ impl<
             storage_key,
             storage_key_diff_inv,
             value_prev,
+            q_storage,
+            is_revert,
+            reverted_global_counter,
+            tx_id,
+            tx_id_diff_inv,
+            committed_value,
+            q_account,
+            initial_value,
+            final_value,
+            address_diff_bytes,
+            q_tx,
+            tx_target_flags,
+            tx_target_diff_inv,
+            tx_key_prefix,
             global_counter_table,
             memory_address_table_zero,
             stack_address_table_zero,
             memory_value_table,
+            mpt_table,
+            update_table,
             address_diff_is_zero,
             address_monotone,
+            call_id_monotone,
             padding_monotone,
             storage_key_diff_is_zero,
+            tx_id_diff_is_zero,
+            tx_target_diff_is_zero,
         }
     }
 
@@ -688,30 +1519,21 @@ impl<
         region: &mut Region<F>,
         ops: Vec<Operation<MemoryOp>>,
         address_diff_is_zero_chip: &IsZeroChip<F>,
+        storage_key_diff_is_zero_chip: &IsZeroChip<F>,
     ) -> Result<Vec<BusMapping<F>>, Error> {
-        let mut init_rows_num = 0;
-        for (index, oper) in ops.iter().enumerate() {
-            let op = oper.op();
-            if index > 0 {
-                if op.address() != ops[index - 1].op().address() {
-                    init_rows_num += 1;
-                }
-            } else {
-                init_rows_num += 1;
-            }
-        }
-
-        if ops.len() + init_rows_num > MEMORY_ROWS_MAX {
-            panic!("too many memory operations");
+        if memory_rows(&ops) > MEMORY_ROWS_MAX {
+            return Err(Error::Synthesis);
         }
 
         let mut bus_mappings: Vec<BusMapping<F>> = Vec::new();
 
         let mut address_prev = F::zero();
+        let mut call_id_prev = F::zero();
         let mut offset = 0;
         for (index, oper) in ops.iter().enumerate() {
             let op = oper.op();
             let address = F::from_bytes(&op.address().to_le_bytes()).unwrap();
+            let call_id = F::from(op.call_id() as u64);
             let gc = usize::from(oper.gc());
             let val = F::from(op.value() as u64);
 
@@ -721,13 +1543,19 @@ impl<
             }
 
             // memory ops have init row
-            if index == 0 || address != address_prev {
-                self.init(region, offset, address, target)?;
+            if index == 0 || address != address_prev || call_id != call_id_prev
+            {
+                self.init(region, offset, address, call_id, target)?;
                 address_diff_is_zero_chip.assign(
                     region,
                     offset,
                     Some(address - address_prev),
                 )?;
+                storage_key_diff_is_zero_chip.assign(
+                    region,
+                    offset,
+                    Some(call_id - call_id_prev),
+                )?;
                 target = 2;
                 offset += 1;
             }
@@ -740,12 +1568,13 @@ impl<
                 val,
                 op.rw().is_write(),
                 target,
-                F::zero(),
+                call_id,
                 F::zero(),
             )?;
             bus_mappings.push(bus_mapping);
 
             address_prev = address;
+            call_id_prev = call_id;
             offset += 1;
         }
 
@@ -758,20 +1587,24 @@ impl<
         &self,
         region: &mut Region<F>,
         ops: Vec<Operation<StackOp>>,
+        randomness: F,
         address_diff_is_zero_chip: &IsZeroChip<F>,
+        storage_key_diff_is_zero_chip: &IsZeroChip<F>,
     ) -> Result<Vec<BusMapping<F>>, Error> {
         if ops.len() > STACK_ROWS_MAX {
-            panic!("too many stack operations");
+            return Err(Error::Synthesis);
         }
         let mut bus_mappings: Vec<BusMapping<F>> = Vec::new();
 
         let mut address_prev = F::zero();
+        let mut call_id_prev = F::zero();
         let mut offset = MEMORY_ROWS_MAX;
         for (index, oper) in ops.iter().enumerate() {
             let op = oper.op();
             let address = F::from(usize::from(*op.address()) as u64);
+            let call_id = F::from(op.call_id() as u64);
             let gc = usize::from(oper.gc());
-            let val = op.value().to_scalar().unwrap();
+            let val = compress_word(op.value(), randomness);
 
             let mut target = 1;
             if index > 0 {
@@ -786,7 +1619,7 @@ impl<
                 val,
                 op.rw().is_write(),
                 target,
-                F::zero(),
+                call_id,
                 F::zero(),
             )?;
             bus_mappings.push(bus_mapping);
@@ -797,7 +1630,14 @@ impl<
                 Some(address - address_prev),
             )?;
 
+            storage_key_diff_is_zero_chip.assign(
+                region,
+                offset,
+                Some(call_id - call_id_prev),
+            )?;
+
             address_prev = address;
+            call_id_prev = call_id;
             offset += 1;
         }
 
@@ -810,30 +1650,119 @@ impl<
         &self,
         region: &mut Region<F>,
         ops: Vec<Operation<StorageOp>>,
+        randomness: F,
         address_diff_is_zero_chip: &IsZeroChip<F>,
         storage_key_diff_is_zero_chip: &IsZeroChip<F>,
+        tx_id_diff_is_zero_chip: &IsZeroChip<F>,
     ) -> Result<Vec<BusMapping<F>>, Error> {
         if ops.len() > STORAGE_ROWS_MAX {
-            panic!("too many storage operations");
+            return Err(Error::Synthesis);
         }
+        let compress = |word: &Word| compress_word(word, randomness);
         let mut bus_mappings: Vec<BusMapping<F>> = Vec::new();
 
+        for offset in MEMORY_ROWS_MAX + STACK_ROWS_MAX
+            ..MEMORY_ROWS_MAX + STACK_ROWS_MAX + STORAGE_ROWS_MAX
+        {
+            region.assign_fixed(
+                || "storage",
+                self.q_storage,
+                offset,
+                || Ok(F::one()),
+            )?;
+        }
+
+        let values = initial_and_final_values(
+            &ops.iter()
+                .map(|oper| {
+                    let op = oper.op();
+                    let value = compress(op.value());
+                    let value_before = if op.rw().is_write() {
+                        compress(op.value_prev())
+                    } else {
+                        value
+                    };
+                    ((op.address(), op.key()), value_before, value)
+                })
+                .collect::<Vec<_>>(),
+        );
+
         let mut address_prev = F::zero();
         let mut storage_key_prev = F::zero();
+        let mut tx_id_prev = F::zero();
+        // Global counters of the writes to the current slot that haven't been
+        // undone, the last of which is undone by the next revert.
+        let mut unreverted_gcs = Vec::new();
         let mut offset = MEMORY_ROWS_MAX + STACK_ROWS_MAX;
         for (index, oper) in ops.iter().enumerate() {
             let op = oper.op();
-            let address = op.address().to_scalar().unwrap();
+            if index > 0 {
+                self.assign_address_diff_bytes(
+                    region,
+                    offset,
+                    op.address(),
+                    ops[index - 1].op().address(),
+                )?;
+            }
+            let address = address_value(op.address());
             let gc = usize::from(oper.gc());
-            let val = op.value().to_scalar().unwrap();
-            let val_prev = op.value_prev().to_scalar().unwrap();
-            let storage_key = op.key().to_scalar().unwrap();
+            let val = compress(op.value());
+            let val_prev = compress(op.value_prev());
+            let storage_key = compress(op.key());
+            let tx_id = F::from(op.tx_id() as u64);
+            let committed_value = compress(op.committed_value());
 
             let mut target = 1;
             if index > 0 {
                 target = 4;
             }
 
+            let is_first = index == 0
+                || address != address_prev
+                || storage_key != storage_key_prev;
+            if is_first {
+                unreverted_gcs.clear();
+            }
+            let reverted_gc = if oper.revert() {
+                unreverted_gcs.pop().unwrap_or(0)
+            } else {
+                if op.rw().is_write() {
+                    unreverted_gcs.push(gc);
+                }
+                0
+            };
+            region.assign_advice(
+                || "is revert",
+                self.is_revert,
+                offset,
+                || Ok(F::from(oper.revert() as u64)),
+            )?;
+            region.assign_advice(
+                || "reverted global counter",
+                self.reverted_global_counter,
+                offset,
+                || Ok(F::from(reverted_gc as u64)),
+            )?;
+            region.assign_advice(
+                || "tx id",
+                self.tx_id,
+                offset,
+                || Ok(tx_id),
+            )?;
+            region.assign_advice(
+                || "committed value",
+                self.committed_value,
+                offset,
+                || Ok(committed_value),
+            )?;
+            self.assign_initial_and_final_values(
+                region,
+                offset,
+                [address, F::from(MptField::Storage as u64), storage_key],
+                values[index],
+                is_first,
+            )?;
+
             let bus_mapping = self.assign_op(
                 region,
                 offset,
@@ -859,8 +1788,15 @@ impl<
                 Some(storage_key - storage_key_prev),
             )?;
 
+            tx_id_diff_is_zero_chip.assign(
+                region,
+                offset,
+                Some(tx_id - tx_id_prev),
+            )?;
+
             address_prev = address;
             storage_key_prev = storage_key;
+            tx_id_prev = tx_id;
             offset += 1;
         }
 
@@ -875,6 +1811,434 @@ impl<
         Ok(bus_mappings)
     }
 
+    fn assign_account_ops(
+        &self,
+        region: &mut Region<F>,
+        nonce_ops: Vec<Operation<AccountNonceOp>>,
+        balance_ops: Vec<Operation<AccountBalanceOp>>,
+        code_hash_ops: Vec<Operation<AccountCodeHashOp>>,
+        randomness: F,
+        address_diff_is_zero_chip: &IsZeroChip<F>,
+        storage_key_diff_is_zero_chip: &IsZeroChip<F>,
+    ) -> Result<Vec<BusMapping<F>>, Error> {
+        let compress = |bytes: &[u8]| encode(bytes.iter().cloned(), randomness);
+
+        // Account operations are sorted by address, field and global counter
+        let mut rows = nonce_ops
+            .iter()
+            .map(|oper| {
+                let op = oper.op();
+                (
+                    *op.address(),
+                    AccountField::Nonce,
+                    usize::from(oper.gc()),
+                    op.rw(),
+                    F::from(op.value().low_u64()),
+                    F::from(op.value_prev().low_u64()),
+                )
+            })
+            .chain(balance_ops.iter().map(|oper| {
+                let op = oper.op();
+                (
+                    *op.address(),
+                    AccountField::Balance,
+                    usize::from(oper.gc()),
+                    op.rw(),
+                    compress_word(op.value(), randomness),
+                    compress_word(op.value_prev(), randomness),
+                )
+            }))
+            .chain(code_hash_ops.iter().map(|oper| {
+                let op = oper.op();
+                (
+                    *op.address(),
+                    AccountField::CodeHash,
+                    usize::from(oper.gc()),
+                    op.rw(),
+                    compress(op.value().as_bytes()),
+                    compress(op.value_prev().as_bytes()),
+                )
+            }))
+            .collect::<Vec<_>>();
+        rows.sort_by_key(|(address, field, gc, ..)| (*address, *field, *gc));
+
+        if rows.len() > ACCOUNT_ROWS_MAX {
+            return Err(Error::Synthesis);
+        }
+        let mut bus_mappings: Vec<BusMapping<F>> = Vec::new();
+
+        for offset in MEMORY_ROWS_MAX + STACK_ROWS_MAX + STORAGE_ROWS_MAX
+            ..MEMORY_ROWS_MAX
+                + STACK_ROWS_MAX
+                + STORAGE_ROWS_MAX
+                + ACCOUNT_ROWS_MAX
+        {
+            region.assign_fixed(
+                || "account",
+                self.q_account,
+                offset,
+                || Ok(F::one()),
+            )?;
+        }
+
+        let values = initial_and_final_values(
+            &rows
+                .iter()
+                .map(|(address, field, _, rw, val, val_prev)| {
+                    let value_before =
+                        if rw.is_write() { *val_prev } else { *val };
+                    ((*address, *field), value_before, *val)
+                })
+                .collect::<Vec<_>>(),
+        );
+
+        let mut address_prev = F::zero();
+        let mut field_prev = F::zero();
+        let mut offset = MEMORY_ROWS_MAX + STACK_ROWS_MAX + STORAGE_ROWS_MAX;
+        for (index, &(address, field, gc, rw, val, val_prev)) in
+            rows.iter().enumerate()
+        {
+            // The address is encoded by its value, whose difference with the
+            // previous address is checked against the diff bytes
+            if index > 0 {
+                self.assign_address_diff_bytes(
+                    region,
+                    offset,
+                    &address,
+                    &rows[index - 1].0,
+                )?;
+            }
+            let address = address_value(&address);
+            let field = F::from(field as u64);
+
+            let mut target = 1;
+            if index > 0 {
+                target = 5;
+            }
+            self.assign_initial_and_final_values(
+                region,
+                offset,
+                [address, field, F::zero()],
+                values[index],
+                index == 0 || address != address_prev || field != field_prev,
+            )?;
+
+            let bus_mapping = self.assign_op(
+                region,
+                offset,
+                address,
+                gc,
+                val,
+                rw.is_write(),
+                target,
+                field,
+                val_prev,
+            )?;
+            bus_mappings.push(bus_mapping);
+
+            address_diff_is_zero_chip.assign(
+                region,
+                offset,
+                Some(address - address_prev),
+            )?;
+
+            storage_key_diff_is_zero_chip.assign(
+                region,
+                offset,
+                Some(field - field_prev),
+            )?;
+
+            address_prev = address;
+            field_prev = field;
+            offset += 1;
+        }
+
+        self.pad_rows(
+            region,
+            offset,
+            MEMORY_ROWS_MAX + STACK_ROWS_MAX + STORAGE_ROWS_MAX,
+            ACCOUNT_ROWS_MAX,
+            5,
+        )?;
+
+        Ok(bus_mappings)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn assign_tx_ops(
+        &self,
+        region: &mut Region<F>,
+        access_list_account_ops: Vec<Operation<TxAccessListAccountOp>>,
+        access_list_storage_ops: Vec<Operation<TxAccessListAccountStorageOp>>,
+        refund_ops: Vec<Operation<TxRefundOp>>,
+        call_context_ops: Vec<Operation<CallContextOp>>,
+        log_ops: Vec<Operation<TxLogOp>>,
+        randomness: F,
+        address_diff_is_zero_chip: &IsZeroChip<F>,
+        storage_key_diff_is_zero_chip: &IsZeroChip<F>,
+        tx_id_diff_is_zero_chip: &IsZeroChip<F>,
+        tx_target_diff_is_zero_chip: &IsZeroChip<F>,
+    ) -> Result<Vec<BusMapping<F>>, Error> {
+        let boolean = |value: bool| F::from(value as u64);
+
+        // Tx operations are sorted by target, tx id, address, key and global
+        // counter
+        let mut rows = access_list_storage_ops
+            .iter()
+            .map(|oper| {
+                let op = oper.op();
+                TxRow {
+                    target: Target::TxAccessListAccountStorage,
+                    tx_id: op.tx_id(),
+                    address: *op.address(),
+                    key: (*op.key(), compress_word(op.key(), randomness)),
+                    global_counter: usize::from(oper.gc()),
+                    rw: op.rw(),
+                    value: boolean(op.value()),
+                    value_prev: boolean(op.value_prev()),
+                }
+            })
+            .chain(refund_ops.iter().map(|oper| {
+                let op = oper.op();
+                TxRow {
+                    target: Target::TxRefund,
+                    tx_id: op.tx_id(),
+                    address: Address::zero(),
+                    key: (Word::zero(), F::zero()),
+                    global_counter: usize::from(oper.gc()),
+                    rw: op.rw(),
+                    value: F::from(op.value()),
+                    value_prev: F::from(op.value_prev()),
+                }
+            }))
+            .chain(call_context_ops.iter().map(|oper| {
+                let op = oper.op();
+                let field = op.field() as u64;
+                TxRow {
+                    target: Target::CallContext,
+                    tx_id: 0,
+                    address: Address::from_low_u64_be(op.call_id() as u64),
+                    key: (Word::from(field), F::from(field)),
+                    global_counter: usize::from(oper.gc()),
+                    rw: op.rw(),
+                    value: compress_word(op.value(), randomness),
+                    value_prev: F::zero(),
+                }
+            }))
+            .chain(log_ops.iter().map(|oper| {
+                let op = oper.op();
+                let key = 4 * op.index() as u64 + op.field() as u64;
+                TxRow {
+                    target: Target::TxLog,
+                    tx_id: op.tx_id(),
+                    address: Address::from_low_u64_be(op.log_id() as u64),
+                    key: (Word::from(key), F::from(key)),
+                    global_counter: usize::from(oper.gc()),
+                    rw: op.rw(),
+                    value: compress_word(op.value(), randomness),
+                    value_prev: F::zero(),
+                }
+            }))
+            .chain(access_list_account_ops.iter().map(|oper| {
+                let op = oper.op();
+                TxRow {
+                    target: Target::TxAccessListAccount,
+                    tx_id: op.tx_id(),
+                    address: *op.address(),
+                    key: (Word::zero(), F::zero()),
+                    global_counter: usize::from(oper.gc()),
+                    rw: op.rw(),
+                    value: boolean(op.value()),
+                    value_prev: boolean(op.value_prev()),
+                }
+            }))
+            .collect::<Vec<_>>();
+        rows.sort_by_key(|row| {
+            (
+                row.target as u64,
+                row.tx_id,
+                row.address,
+                row.key.0,
+                row.global_counter,
+            )
+        });
+
+        if rows.len() > TX_ROWS_MAX {
+            return Err(Error::Synthesis);
+        }
+        let mut bus_mappings: Vec<BusMapping<F>> = Vec::new();
+
+        let start_offset = MEMORY_ROWS_MAX
+            + STACK_ROWS_MAX
+            + STORAGE_ROWS_MAX
+            + ACCOUNT_ROWS_MAX;
+        for offset in start_offset..start_offset + TX_ROWS_MAX {
+            region.assign_fixed(|| "tx", self.q_tx, offset, || Ok(F::one()))?;
+        }
+
+        for (index, row) in rows.iter().enumerate() {
+            let offset = start_offset + index;
+            let target = F::from(row.target as u64);
+            let tx_id = F::from(row.tx_id as u64);
+            let address = address_value(&row.address);
+            let key = row.key.1;
+
+            for (column, target) in
+                self.tx_target_flags.iter().zip(TX_TARGETS.iter())
+            {
+                region.assign_advice(
+                    || "tx target flag",
+                    *column,
+                    offset,
+                    || Ok(boolean(*target == row.target)),
+                )?;
+            }
+            region.assign_advice(
+                || "tx id",
+                self.tx_id,
+                offset,
+                || Ok(tx_id),
+            )?;
+
+            let mut prefix = [false; 3];
+            if index > 0 {
+                let row_prev = &rows[index - 1];
+                prefix[0] = row.target == row_prev.target
+                    && row.tx_id == row_prev.tx_id;
+                prefix[1] = prefix[0] && row.address == row_prev.address;
+                prefix[2] = prefix[1] && row.key.0 == row_prev.key.0;
+
+                self.assign_address_diff_bytes(
+                    region,
+                    offset,
+                    &row.address,
+                    &row_prev.address,
+                )?;
+                tx_target_diff_is_zero_chip.assign(
+                    region,
+                    offset,
+                    Some(target - F::from(row_prev.target as u64)),
+                )?;
+                tx_id_diff_is_zero_chip.assign(
+                    region,
+                    offset,
+                    Some(tx_id - F::from(row_prev.tx_id as u64)),
+                )?;
+                address_diff_is_zero_chip.assign(
+                    region,
+                    offset,
+                    Some(address - address_value::<F>(&row_prev.address)),
+                )?;
+                storage_key_diff_is_zero_chip.assign(
+                    region,
+                    offset,
+                    Some(key - row_prev.key.1),
+                )?;
+            }
+            for (column, is_same) in self.tx_key_prefix.iter().zip(prefix) {
+                region.assign_advice(
+                    || "tx key prefix",
+                    *column,
+                    offset,
+                    || Ok(boolean(is_same)),
+                )?;
+            }
+
+            let bus_mapping = self.assign_op(
+                region,
+                offset,
+                address,
+                row.global_counter,
+                row.value,
+                row.rw.is_write(),
+                0,
+                key,
+                row.value_prev,
+            )?;
+            bus_mappings.push(bus_mapping);
+        }
+
+        for offset in start_offset + rows.len()..start_offset + TX_ROWS_MAX {
+            region.assign_advice(
+                || "padding",
+                self.padding,
+                offset,
+                || Ok(F::one()),
+            )?;
+        }
+
+        Ok(bus_mappings)
+    }
+
+    // Assigns the bytes of the difference minus one between the address of
+    // the op at `offset` and the address of the previous op, when they differ.
+    // The ops are sorted by address, so the difference fits in the bytes of an
+    // address, unless the ops are out of order, which fails the constraints.
+    fn assign_address_diff_bytes(
+        &self,
+        region: &mut Region<F>,
+        offset: usize,
+        address: &Address,
+        address_prev: &Address,
+    ) -> Result<(), Error> {
+        let mut diff_bytes = [0u8; 32];
+        if address != address_prev {
+            Word::from_big_endian(address.as_bytes())
+                .overflowing_sub(Word::from_big_endian(address_prev.as_bytes()))
+                .0
+                .overflowing_sub(Word::one())
+                .0
+                .to_little_endian(&mut diff_bytes);
+        }
+        for (column, byte) in self.address_diff_bytes.iter().zip(diff_bytes) {
+            region.assign_advice(
+                || "address diff byte",
+                *column,
+                offset,
+                || Ok(F::from(byte as u64)),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    // Assigns the initial and final values of the op at `offset`, and copies
+    // them with the address, field and key of its slot or field to the update
+    // table when it's the first op of the slot or field.
+    fn assign_initial_and_final_values(
+        &self,
+        region: &mut Region<F>,
+        offset: usize,
+        [address, field, key]: [F; 3],
+        (initial_value, final_value): (F, F),
+        is_first: bool,
+    ) -> Result<(), Error> {
+        region.assign_advice(
+            || "initial value",
+            self.initial_value,
+            offset,
+            || Ok(initial_value),
+        )?;
+        region.assign_advice(
+            || "final value",
+            self.final_value,
+            offset,
+            || Ok(final_value),
+        )?;
+
+        let update = [address, field, key, initial_value, final_value];
+        for (column, value) in self.update_table.iter().zip(update) {
+            region.assign_advice(
+                || "update table",
+                *column,
+                offset,
+                || Ok(if is_first { value } else { F::zero() }),
+            )?;
+        }
+
+        Ok(())
+    }
+
     fn pad_rows(
         &self,
         region: &mut Region<F>,
@@ -916,32 +2280,56 @@ impl<
     }
 
     /// Assign cells.
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn assign(
         &self,
         mut layouter: impl Layouter<F>,
         memory_ops: Vec<Operation<MemoryOp>>,
         stack_ops: Vec<Operation<StackOp>>,
         storage_ops: Vec<Operation<StorageOp>>,
+        account_nonce_ops: Vec<Operation<AccountNonceOp>>,
+        account_balance_ops: Vec<Operation<AccountBalanceOp>>,
+        account_code_hash_ops: Vec<Operation<AccountCodeHashOp>>,
+        tx_access_list_account_ops: Vec<Operation<TxAccessListAccountOp>>,
+        tx_access_list_account_storage_ops: Vec<
+            Operation<TxAccessListAccountStorageOp>,
+        >,
+        tx_refund_ops: Vec<Operation<TxRefundOp>>,
+        call_context_ops: Vec<Operation<CallContextOp>>,
+        tx_log_ops: Vec<Operation<TxLogOp>>,
+        randomness: F,
     ) -> Result<Vec<BusMapping<F>>, Error> {
         let mut bus_mappings: Vec<BusMapping<F>> = Vec::new();
 
         let address_diff_is_zero_chip =
             IsZeroChip::construct(self.address_diff_is_zero.clone());
 
+        let storage_key_diff_is_zero_chip =
+            IsZeroChip::construct(self.storage_key_diff_is_zero.clone());
+
         let memory_address_monotone_chip =
             MonotoneChip::<F, MEMORY_ADDRESS_MAX, true, false>::construct(
                 self.address_monotone.clone(),
             );
         memory_address_monotone_chip.load(&mut layouter)?;
 
+        let call_id_monotone_chip =
+            MonotoneChip::<F, GLOBAL_COUNTER_MAX, true, false>::construct(
+                self.call_id_monotone.clone(),
+            );
+        call_id_monotone_chip.load(&mut layouter)?;
+
         let padding_monotone_chip =
             MonotoneChip::<F, 1, true, false>::construct(
                 self.padding_monotone.clone(),
             );
         padding_monotone_chip.load(&mut layouter)?;
 
-        let storage_key_diff_is_zero_chip =
-            IsZeroChip::construct(self.storage_key_diff_is_zero.clone());
+        let tx_id_diff_is_zero_chip =
+            IsZeroChip::construct(self.tx_id_diff_is_zero.clone());
+
+        let tx_target_diff_is_zero_chip =
+            IsZeroChip::construct(self.tx_target_diff_is_zero.clone());
 
         layouter.assign_region(
             || "State operations",
@@ -950,23 +2338,54 @@ impl<
                     &mut region,
                     memory_ops.clone(),
                     &address_diff_is_zero_chip,
+                    &storage_key_diff_is_zero_chip,
                 );
-                bus_mappings.extend(memory_mappings.unwrap());
+                bus_mappings.extend(memory_mappings?);
 
                 let stack_mappings = self.assign_stack_ops(
                     &mut region,
                     stack_ops.clone(),
+                    randomness,
                     &address_diff_is_zero_chip,
+                    &storage_key_diff_is_zero_chip,
                 );
-                bus_mappings.extend(stack_mappings.unwrap());
+                bus_mappings.extend(stack_mappings?);
 
                 let storage_mappings = self.assign_storage_ops(
                     &mut region,
                     storage_ops.clone(),
+                    randomness,
                     &address_diff_is_zero_chip,
                     &storage_key_diff_is_zero_chip,
+                    &tx_id_diff_is_zero_chip,
                 );
-                bus_mappings.extend(storage_mappings.unwrap());
+                bus_mappings.extend(storage_mappings?);
+
+                let account_mappings = self.assign_account_ops(
+                    &mut region,
+                    account_nonce_ops.clone(),
+                    account_balance_ops.clone(),
+                    account_code_hash_ops.clone(),
+                    randomness,
+                    &address_diff_is_zero_chip,
+                    &storage_key_diff_is_zero_chip,
+                )?;
+                bus_mappings.extend(account_mappings);
+
+                let tx_mappings = self.assign_tx_ops(
+                    &mut region,
+                    tx_access_list_account_ops.clone(),
+                    tx_access_list_account_storage_ops.clone(),
+                    tx_refund_ops.clone(),
+                    call_context_ops.clone(),
+                    tx_log_ops.clone(),
+                    randomness,
+                    &address_diff_is_zero_chip,
+                    &storage_key_diff_is_zero_chip,
+                    &tx_id_diff_is_zero_chip,
+                    &tx_target_diff_is_zero_chip,
+                )?;
+                bus_mappings.extend(tx_mappings);
 
                 Ok(bus_mappings.clone())
             },
@@ -979,6 +2398,7 @@ impl<
         region: &mut Region<'_, F>,
         offset: usize,
         address: F,
+        call_id: F,
         target: usize,
     ) -> Result<(), Error> {
         region.assign_advice(
@@ -988,6 +2408,13 @@ impl<
             || Ok(address),
         )?;
 
+        region.assign_advice(
+            || "init call id",
+            self.storage_key,
+            offset,
+            || Ok(call_id),
+        )?;
+
         region.assign_advice(
             || "init global counter",
             self.global_counter,
@@ -1152,26 +2579,92 @@ impl<
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::Config;
-    use bus_mapping::address;
-    use bus_mapping::circuit_input_builder::CircuitInputBuilder;
-    use bus_mapping::eth_types::{GethExecStep, Word};
-    use bus_mapping::evm::{GlobalCounter, MemoryAddress, StackAddress};
-    use bus_mapping::mock;
+/// Returns the number of rows taken by the sorted memory `ops`, where the ops
+/// of each address of a call follow an init row.
+pub(crate) fn memory_rows(ops: &[Operation<MemoryOp>]) -> usize {
+    let init_rows_num = ops
+        .iter()
+        .enumerate()
+        .filter(|(index, oper)| {
+            *index == 0 || {
+                let (op, op_prev) = (oper.op(), ops[index - 1].op());
+                op.address() != op_prev.address()
+                    || op.call_id() != op_prev.call_id()
+            }
+        })
+        .count();
+    ops.len() + init_rows_num
+}
 
-    use bus_mapping::operation::{MemoryOp, Operation, StackOp, StorageOp, RW};
+// Returns the random linear combination of the big-endian bytes of `word`,
+// which encodes the words that don't fit in a field element, as in the EVM
+// circuit.
+fn compress_word<F: FieldExt>(word: &Word, randomness: F) -> F {
+    let mut bytes = [0u8; 32];
+    word.to_big_endian(&mut bytes);
+    encode(bytes.iter().cloned(), randomness)
+}
+
+// Returns the value of `address`, which fits in a field element.
+fn address_value<F: FieldExt>(address: &Address) -> F {
+    encode(address.0.iter().cloned(), F::from(256))
+}
+
+// Returns the initial and final values of the key of each op, given by the
+// key of the op and its values before and after it, where the ops of a key
+// are consecutive.
+fn initial_and_final_values<K: PartialEq, F: Copy>(
+    ops: &[(K, F, F)],
+) -> Vec<(F, F)> {
+    let mut values = Vec::with_capacity(ops.len());
+    for (index, (key, value_before, _)) in ops.iter().enumerate() {
+        if index > 0 && ops[index - 1].0 == *key {
+            values.push(values[index - 1]);
+        } else {
+            let last = ops[index..]
+                .iter()
+                .take_while(|(next_key, ..)| next_key == key)
+                .last()
+                .map(|(_, _, value)| *value)
+                .unwrap();
+            values.push((*value_before, last));
+        }
+    }
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{initial_and_final_values, AccountField, Config};
+    use crate::gadget::evm_word::encode;
+    use crate::mpt_circuit::trie::MptField;
+    use bus_mapping::address;
+    use bus_mapping::circuit_input_builder::CircuitInputBuilder;
+    use bus_mapping::eth_types::{GethExecStep, ToScalar, Word, H256};
+    use bus_mapping::evm::{GlobalCounter, MemoryAddress, StackAddress};
+    use bus_mapping::mock;
+
+    use bus_mapping::operation::{
+        AccountBalanceOp, AccountCodeHashOp, AccountNonceOp, CallContextField,
+        CallContextOp, MemoryOp, Operation, StackOp, StorageOp,
+        TxAccessListAccountOp, TxAccessListAccountStorageOp, TxLogField,
+        TxLogOp, TxRefundOp, RW,
+    };
     use halo2::{
         circuit::{Layouter, SimpleFloorPlanner},
         dev::{
-            MockProver, VerifyFailure::ConstraintNotSatisfied,
+            MockProver, VerifyFailure, VerifyFailure::ConstraintNotSatisfied,
             VerifyFailure::Lookup,
         },
         plonk::{Circuit, ConstraintSystem, Error},
     };
+    use std::iter;
 
     use pairing::{arithmetic::FieldExt, bn256::Fr as Fp};
+
+    // Randomness of the RLC of the words
+    const RANDOMNESS: u64 = 0x100;
+
     #[derive(Default)]
     struct StateCircuit<
         const GLOBAL_COUNTER_MAX: usize,
@@ -1180,10 +2673,160 @@ mod tests {
         const STACK_ROWS_MAX: usize,
         const STACK_ADDRESS_MAX: usize,
         const STORAGE_ROWS_MAX: usize,
+        const ACCOUNT_ROWS_MAX: usize,
+        const TX_ROWS_MAX: usize,
     > {
         memory_ops: Vec<Operation<MemoryOp>>,
         stack_ops: Vec<Operation<StackOp>>,
         storage_ops: Vec<Operation<StorageOp>>,
+        account_nonce_ops: Vec<Operation<AccountNonceOp>>,
+        account_balance_ops: Vec<Operation<AccountBalanceOp>>,
+        account_code_hash_ops: Vec<Operation<AccountCodeHashOp>>,
+        tx_access_list_account_ops: Vec<Operation<TxAccessListAccountOp>>,
+        tx_access_list_account_storage_ops:
+            Vec<Operation<TxAccessListAccountStorageOp>>,
+        tx_refund_ops: Vec<Operation<TxRefundOp>>,
+        call_context_ops: Vec<Operation<CallContextOp>>,
+        tx_log_ops: Vec<Operation<TxLogOp>>,
+        // Whether the final values of the MPT table are off by one
+        invalid_mpt_table: bool,
+    }
+
+    impl<
+            const GLOBAL_COUNTER_MAX: usize,
+            const MEMORY_ROWS_MAX: usize,
+            const MEMORY_ADDRESS_MAX: usize,
+            const STACK_ROWS_MAX: usize,
+            const STACK_ADDRESS_MAX: usize,
+            const STORAGE_ROWS_MAX: usize,
+            const ACCOUNT_ROWS_MAX: usize,
+            const TX_ROWS_MAX: usize,
+        >
+        StateCircuit<
+            GLOBAL_COUNTER_MAX,
+            MEMORY_ROWS_MAX,
+            MEMORY_ADDRESS_MAX,
+            STACK_ROWS_MAX,
+            STACK_ADDRESS_MAX,
+            STORAGE_ROWS_MAX,
+            ACCOUNT_ROWS_MAX,
+            TX_ROWS_MAX,
+        >
+    {
+        // Returns the rows of the MPT table with the updates of the storage
+        // slots and of the account fields from their first to their last op,
+        // which are ordered as in the state circuit.
+        fn mpt_rows<F: FieldExt>(&self) -> Vec<[F; 5]> {
+            let randomness = F::from(RANDOMNESS);
+            let compress =
+                |bytes: &[u8]| encode(bytes.iter().cloned(), randomness);
+            let compress_word = |word: &Word| {
+                let mut bytes = [0u8; 32];
+                word.to_big_endian(&mut bytes);
+                compress(&bytes)
+            };
+            let value_before = |rw: RW, value: F, value_prev: F| {
+                if rw.is_write() {
+                    value_prev
+                } else {
+                    value
+                }
+            };
+
+            let storage_ops = self
+                .storage_ops
+                .iter()
+                .map(|oper| {
+                    let op = oper.op();
+                    let value = compress_word(op.value());
+                    (
+                        (
+                            *op.address(),
+                            MptField::Storage as u64,
+                            compress_word(op.key()),
+                        ),
+                        value_before(
+                            op.rw(),
+                            value,
+                            compress_word(op.value_prev()),
+                        ),
+                        value,
+                    )
+                })
+                .collect::<Vec<_>>();
+            let mut account_ops = self
+                .account_nonce_ops
+                .iter()
+                .map(|oper| {
+                    let op = oper.op();
+                    (
+                        usize::from(oper.gc()),
+                        *op.address(),
+                        AccountField::Nonce,
+                        op.rw(),
+                        F::from(op.value().low_u64()),
+                        F::from(op.value_prev().low_u64()),
+                    )
+                })
+                .chain(self.account_balance_ops.iter().map(|oper| {
+                    let op = oper.op();
+                    (
+                        usize::from(oper.gc()),
+                        *op.address(),
+                        AccountField::Balance,
+                        op.rw(),
+                        compress_word(op.value()),
+                        compress_word(op.value_prev()),
+                    )
+                }))
+                .chain(self.account_code_hash_ops.iter().map(|oper| {
+                    let op = oper.op();
+                    (
+                        usize::from(oper.gc()),
+                        *op.address(),
+                        AccountField::CodeHash,
+                        op.rw(),
+                        compress(op.value().as_bytes()),
+                        compress(op.value_prev().as_bytes()),
+                    )
+                }))
+                .collect::<Vec<_>>();
+            account_ops.sort_by_key(|(gc, address, field, ..)| {
+                (*address, *field, *gc)
+            });
+            let account_ops = account_ops
+                .into_iter()
+                .map(|(_, address, field, rw, value, value_prev)| {
+                    (
+                        (address, field as u64, F::zero()),
+                        value_before(rw, value, value_prev),
+                        value,
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            let mut rows: Vec<[F; 5]> = vec![];
+            for ops in [storage_ops, account_ops] {
+                let values = initial_and_final_values(&ops);
+                for (((address, field, key), ..), (initial, last)) in
+                    ops.into_iter().zip(values)
+                {
+                    let address = Word::from_big_endian(address.as_bytes())
+                        .to_scalar()
+                        .unwrap();
+                    let last = if self.invalid_mpt_table {
+                        last + F::one()
+                    } else {
+                        last
+                    };
+                    let row = [address, F::from(field), key, initial, last];
+                    if rows.last() != Some(&row) {
+                        rows.push(row);
+                    }
+                }
+            }
+            rows
+        }
     }
 
     impl<
@@ -1194,6 +2837,8 @@ mod tests {
             const STACK_ROWS_MAX: usize,
             const STACK_ADDRESS_MAX: usize,
             const STORAGE_ROWS_MAX: usize,
+            const ACCOUNT_ROWS_MAX: usize,
+            const TX_ROWS_MAX: usize,
         > Circuit<F>
         for StateCircuit<
             GLOBAL_COUNTER_MAX,
@@ -1202,6 +2847,8 @@ mod tests {
             STACK_ROWS_MAX,
             STACK_ADDRESS_MAX,
             STORAGE_ROWS_MAX,
+            ACCOUNT_ROWS_MAX,
+            TX_ROWS_MAX,
         >
     {
         type Config = Config<
@@ -1212,6 +2859,8 @@ mod tests {
             STACK_ROWS_MAX,
             STACK_ADDRESS_MAX,
             STORAGE_ROWS_MAX,
+            ACCOUNT_ROWS_MAX,
+            TX_ROWS_MAX,
         >;
         type FloorPlanner = SimpleFloorPlanner;
 
@@ -1220,7 +2869,9 @@ mod tests {
         }
 
         fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-            Config::configure(meta)
+            let mpt_table = [(); 5].map(|_| meta.advice_column());
+            let update_table = [(); 5].map(|_| meta.advice_column());
+            Config::configure(meta, mpt_table, update_table)
         }
 
         fn synthesize(
@@ -1230,18 +2881,67 @@ mod tests {
         ) -> Result<(), Error> {
             config.load(&mut layouter)?;
             config.assign(
-                layouter,
+                layouter.namespace(|| "state operations"),
                 self.memory_ops.clone(),
                 self.stack_ops.clone(),
                 self.storage_ops.clone(),
+                self.account_nonce_ops.clone(),
+                self.account_balance_ops.clone(),
+                self.account_code_hash_ops.clone(),
+                self.tx_access_list_account_ops.clone(),
+                self.tx_access_list_account_storage_ops.clone(),
+                self.tx_refund_ops.clone(),
+                self.call_context_ops.clone(),
+                self.tx_log_ops.clone(),
+                F::from(RANDOMNESS),
             )?;
 
-            Ok(())
+            // The padding rows of the state circuit look up the zero row
+            let mpt_rows = self.mpt_rows::<F>();
+            layouter.assign_region(
+                || "mpt table",
+                |mut region| {
+                    for (offset, row) in iter::once([F::zero(); 5])
+                        .chain(mpt_rows.iter().cloned())
+                        .enumerate()
+                    {
+                        for (column, value) in config.mpt_table.iter().zip(row)
+                        {
+                            region.assign_advice(
+                                || "mpt table",
+                                *column,
+                                offset,
+                                || Ok(value),
+                            )?;
+                        }
+                    }
+                    Ok(())
+                },
+            )
         }
     }
 
     macro_rules! test_state_circuit {
         ($k:expr, $global_counter_max:expr, $memory_rows_max:expr, $memory_address_max:expr, $stack_rows_max:expr, $stack_address_max:expr, $storage_rows_max:expr, $memory_ops:expr, $stack_ops:expr, $storage_ops:expr, $result:expr) => {{
+            test_state_circuit!(
+                $k,
+                $global_counter_max,
+                $memory_rows_max,
+                $memory_address_max,
+                $stack_rows_max,
+                $stack_address_max,
+                $storage_rows_max,
+                0,
+                $memory_ops,
+                $stack_ops,
+                $storage_ops,
+                vec![],
+                vec![],
+                vec![],
+                $result
+            )
+        }};
+        ($k:expr, $global_counter_max:expr, $memory_rows_max:expr, $memory_address_max:expr, $stack_rows_max:expr, $stack_address_max:expr, $storage_rows_max:expr, $account_rows_max:expr, $memory_ops:expr, $stack_ops:expr, $storage_ops:expr, $account_nonce_ops:expr, $account_balance_ops:expr, $account_code_hash_ops:expr, $result:expr) => {{
             let circuit = StateCircuit::<
                 $global_counter_max,
                 $memory_rows_max,
@@ -1249,10 +2949,16 @@ mod tests {
                 $stack_rows_max,
                 $stack_address_max,
                 $storage_rows_max,
+                $account_rows_max,
+                0,
             > {
                 memory_ops: $memory_ops,
                 stack_ops: $stack_ops,
                 storage_ops: $storage_ops,
+                account_nonce_ops: $account_nonce_ops,
+                account_balance_ops: $account_balance_ops,
+                account_code_hash_ops: $account_code_hash_ops,
+                ..Default::default()
             };
 
             let prover = MockProver::<Fp>::run($k, &circuit, vec![]).unwrap();
@@ -1262,6 +2968,24 @@ mod tests {
 
     macro_rules! test_state_circuit_error {
         ($k:expr, $global_counter_max:expr, $memory_rows_max:expr, $memory_address_max:expr, $stack_rows_max:expr, $stack_address_max:expr, $storage_rows_max:expr, $memory_ops:expr, $stack_ops:expr, $storage_ops:expr) => {{
+            test_state_circuit_error!(
+                $k,
+                $global_counter_max,
+                $memory_rows_max,
+                $memory_address_max,
+                $stack_rows_max,
+                $stack_address_max,
+                $storage_rows_max,
+                0,
+                $memory_ops,
+                $stack_ops,
+                $storage_ops,
+                vec![],
+                vec![],
+                vec![]
+            )
+        }};
+        ($k:expr, $global_counter_max:expr, $memory_rows_max:expr, $memory_address_max:expr, $stack_rows_max:expr, $stack_address_max:expr, $storage_rows_max:expr, $account_rows_max:expr, $memory_ops:expr, $stack_ops:expr, $storage_ops:expr, $account_nonce_ops:expr, $account_balance_ops:expr, $account_code_hash_ops:expr) => {{
             let circuit = StateCircuit::<
                 $global_counter_max,
                 $memory_rows_max,
@@ -1269,10 +2993,16 @@ mod tests {
                 $stack_rows_max,
                 $stack_address_max,
                 $storage_rows_max,
+                $account_rows_max,
+                0,
             > {
                 memory_ops: $memory_ops,
                 stack_ops: $stack_ops,
                 storage_ops: $storage_ops,
+                account_nonce_ops: $account_nonce_ops,
+                account_balance_ops: $account_balance_ops,
+                account_code_hash_ops: $account_code_hash_ops,
+                ..Default::default()
             };
 
             let prover = MockProver::<Fp>::run($k, &circuit, vec![]).unwrap();
@@ -1304,29 +3034,29 @@ mod tests {
     fn state_circuit() {
         let memory_op_0 = Operation::new(
             GlobalCounter::from(12),
-            MemoryOp::new(RW::WRITE, MemoryAddress::from(0), 32),
+            MemoryOp::new(RW::WRITE, 0, MemoryAddress::from(0), 32),
         );
         let memory_op_1 = Operation::new(
             GlobalCounter::from(24),
-            MemoryOp::new(RW::READ, MemoryAddress::from(0), 32),
+            MemoryOp::new(RW::READ, 0, MemoryAddress::from(0), 32),
         );
 
         let memory_op_2 = Operation::new(
             GlobalCounter::from(17),
-            MemoryOp::new(RW::WRITE, MemoryAddress::from(1), 32),
+            MemoryOp::new(RW::WRITE, 0, MemoryAddress::from(1), 32),
         );
         let memory_op_3 = Operation::new(
             GlobalCounter::from(87),
-            MemoryOp::new(RW::READ, MemoryAddress::from(1), 32),
+            MemoryOp::new(RW::READ, 0, MemoryAddress::from(1), 32),
         );
 
         let stack_op_0 = Operation::new(
             GlobalCounter::from(17),
-            StackOp::new(RW::WRITE, StackAddress::from(1), Word::from(32)),
+            StackOp::new(RW::WRITE, 0, StackAddress::from(1), Word::from(32)),
         );
         let stack_op_1 = Operation::new(
             GlobalCounter::from(87),
-            StackOp::new(RW::READ, StackAddress::from(1), Word::from(32)),
+            StackOp::new(RW::READ, 0, StackAddress::from(1), Word::from(32)),
         );
 
         let storage_op_0 = Operation::new(
@@ -1337,6 +3067,8 @@ mod tests {
                 Word::from(0x40),
                 Word::from(32),
                 Word::from(0),
+                1,
+                Word::from(0),
             ),
         );
         let storage_op_1 = Operation::new(
@@ -1347,6 +3079,8 @@ mod tests {
                 Word::from(0x40),
                 Word::from(32),
                 Word::from(32),
+                1,
+                Word::from(0),
             ),
         );
         let storage_op_2 = Operation::new(
@@ -1357,6 +3091,8 @@ mod tests {
                 Word::from(0x40),
                 Word::from(32),
                 Word::from(32),
+                1,
+                Word::from(0),
             ),
         );
 
@@ -1379,29 +3115,29 @@ mod tests {
     fn no_stack_padding() {
         let memory_op_0 = Operation::new(
             GlobalCounter::from(12),
-            MemoryOp::new(RW::WRITE, MemoryAddress::from(0), 32),
+            MemoryOp::new(RW::WRITE, 0, MemoryAddress::from(0), 32),
         );
         let memory_op_1 = Operation::new(
             GlobalCounter::from(24),
-            MemoryOp::new(RW::READ, MemoryAddress::from(0), 32),
+            MemoryOp::new(RW::READ, 0, MemoryAddress::from(0), 32),
         );
 
         let memory_op_2 = Operation::new(
             GlobalCounter::from(17),
-            MemoryOp::new(RW::WRITE, MemoryAddress::from(1), 32),
+            MemoryOp::new(RW::WRITE, 0, MemoryAddress::from(1), 32),
         );
         let memory_op_3 = Operation::new(
             GlobalCounter::from(87),
-            MemoryOp::new(RW::READ, MemoryAddress::from(1), 32),
+            MemoryOp::new(RW::READ, 0, MemoryAddress::from(1), 32),
         );
 
         let stack_op_0 = Operation::new(
             GlobalCounter::from(17),
-            StackOp::new(RW::WRITE, StackAddress::from(1), Word::from(32)),
+            StackOp::new(RW::WRITE, 0, StackAddress::from(1), Word::from(32)),
         );
         let stack_op_1 = Operation::new(
             GlobalCounter::from(87),
-            StackOp::new(RW::READ, StackAddress::from(1), Word::from(32)),
+            StackOp::new(RW::READ, 0, StackAddress::from(1), Word::from(32)),
         );
 
         const STACK_ROWS_MAX: usize = 2;
@@ -1424,12 +3160,13 @@ mod tests {
     fn same_address_read() {
         let memory_op_0 = Operation::new(
             GlobalCounter::from(12),
-            MemoryOp::new(RW::WRITE, MemoryAddress::from(0), 31),
+            MemoryOp::new(RW::WRITE, 0, MemoryAddress::from(0), 31),
         );
         let memory_op_1 = Operation::new(
             GlobalCounter::from(24),
             MemoryOp::new(
                 RW::READ,
+                0,
                 MemoryAddress::from(0),
                 32,
                 /* This should fail as it not the same value as in previous
@@ -1439,12 +3176,13 @@ mod tests {
 
         let stack_op_0 = Operation::new(
             GlobalCounter::from(19),
-            StackOp::new(RW::WRITE, StackAddress::from(0), Word::from(12)),
+            StackOp::new(RW::WRITE, 0, StackAddress::from(0), Word::from(12)),
         );
         let stack_op_1 = Operation::new(
             GlobalCounter::from(28),
             StackOp::new(
                 RW::READ,
+                0,
                 StackAddress::from(0),
                 Word::from(13),
                 /* This should fail as it not the same value as in previous
@@ -1471,7 +3209,7 @@ mod tests {
     fn first_write() {
         let stack_op_0 = Operation::new(
             GlobalCounter::from(28),
-            StackOp::new(RW::READ, StackAddress::from(0), Word::from(13)),
+            StackOp::new(RW::READ, 0, StackAddress::from(0), Word::from(13)),
         );
 
         let storage_op_0 = Operation::new(
@@ -1483,6 +3221,8 @@ mod tests {
                 Word::from(0x40),
                 Word::from(32),
                 Word::from(0),
+                1,
+                Word::from(0),
             ),
         );
         let storage_op_1 = Operation::new(
@@ -1494,6 +3234,8 @@ mod tests {
                 Word::from(0x41),
                 Word::from(32),
                 Word::from(0),
+                1,
+                Word::from(0),
             ),
         );
 
@@ -1508,6 +3250,8 @@ mod tests {
                 have two conditions met. */
                 Word::from(32),
                 Word::from(0),
+                1,
+                Word::from(0),
             ),
         );
 
@@ -1533,6 +3277,7 @@ mod tests {
             GlobalCounter::from(12),
             MemoryOp::new(
                 RW::WRITE,
+                0,
                 MemoryAddress::from(MEMORY_ADDRESS_MAX),
                 32,
             ),
@@ -1541,6 +3286,7 @@ mod tests {
             GlobalCounter::from(GLOBAL_COUNTER_MAX),
             MemoryOp::new(
                 RW::READ,
+                0,
                 MemoryAddress::from(MEMORY_ADDRESS_MAX),
                 32,
             ),
@@ -1549,6 +3295,7 @@ mod tests {
             GlobalCounter::from(GLOBAL_COUNTER_MAX + 1),
             MemoryOp::new(
                 RW::WRITE,
+                0,
                 MemoryAddress::from(MEMORY_ADDRESS_MAX),
                 32,
             ),
@@ -1558,6 +3305,7 @@ mod tests {
             GlobalCounter::from(12),
             MemoryOp::new(
                 RW::WRITE,
+                0,
                 MemoryAddress::from(MEMORY_ADDRESS_MAX + 1),
                 32,
             ),
@@ -1566,6 +3314,7 @@ mod tests {
             GlobalCounter::from(24),
             MemoryOp::new(
                 RW::READ,
+                0,
                 MemoryAddress::from(MEMORY_ADDRESS_MAX + 1),
                 32,
             ),
@@ -1575,6 +3324,7 @@ mod tests {
             GlobalCounter::from(12),
             StackOp::new(
                 RW::WRITE,
+                0,
                 StackAddress::from(STACK_ADDRESS_MAX),
                 Word::from(12),
             ),
@@ -1583,6 +3333,7 @@ mod tests {
             GlobalCounter::from(24),
             StackOp::new(
                 RW::READ,
+                0,
                 StackAddress::from(STACK_ADDRESS_MAX),
                 Word::from(12),
             ),
@@ -1592,6 +3343,7 @@ mod tests {
             GlobalCounter::from(17),
             StackOp::new(
                 RW::WRITE,
+                0,
                 StackAddress::from(STACK_ADDRESS_MAX + 1),
                 Word::from(12),
             ),
@@ -1600,6 +3352,7 @@ mod tests {
             GlobalCounter::from(GLOBAL_COUNTER_MAX + 1),
             StackOp::new(
                 RW::WRITE,
+                0,
                 StackAddress::from(STACK_ADDRESS_MAX + 1),
                 Word::from(12),
             ),
@@ -1643,6 +3396,7 @@ mod tests {
             GlobalCounter::from(12),
             MemoryOp::new(
                 RW::WRITE,
+                0,
                 MemoryAddress::from(MEMORY_ADDRESS_MAX + 1),
                 // This address is not in the allowed range
                 32,
@@ -1653,6 +3407,7 @@ mod tests {
             GlobalCounter::from(12),
             StackOp::new(
                 RW::WRITE,
+                0,
                 StackAddress::from(STACK_ADDRESS_MAX + 1),
                 Word::from(12),
             ),
@@ -1661,6 +3416,7 @@ mod tests {
             GlobalCounter::from(24),
             StackOp::new(
                 RW::READ,
+                0,
                 StackAddress::from(STACK_ADDRESS_MAX + 1),
                 Word::from(12),
             ),
@@ -1694,30 +3450,30 @@ mod tests {
     fn non_monotone_global_counter() {
         let memory_op_0 = Operation::new(
             GlobalCounter::from(1352),
-            MemoryOp::new(RW::WRITE, MemoryAddress::from(0), 32),
+            MemoryOp::new(RW::WRITE, 0, MemoryAddress::from(0), 32),
         );
         let memory_op_1 = Operation::new(
             GlobalCounter::from(1255),
-            MemoryOp::new(RW::READ, MemoryAddress::from(0), 32),
+            MemoryOp::new(RW::READ, 0, MemoryAddress::from(0), 32),
         );
 
         // fails because it needs to be strictly monotone
         let memory_op_2 = Operation::new(
             GlobalCounter::from(1255),
-            MemoryOp::new(RW::WRITE, MemoryAddress::from(0), 32),
+            MemoryOp::new(RW::WRITE, 0, MemoryAddress::from(0), 32),
         );
 
         let stack_op_0 = Operation::new(
             GlobalCounter::from(228),
-            StackOp::new(RW::WRITE, StackAddress::from(1), Word::from(12)),
+            StackOp::new(RW::WRITE, 0, StackAddress::from(1), Word::from(12)),
         );
         let stack_op_1 = Operation::new(
             GlobalCounter::from(217),
-            StackOp::new(RW::READ, StackAddress::from(1), Word::from(12)),
+            StackOp::new(RW::READ, 0, StackAddress::from(1), Word::from(12)),
         );
         let stack_op_2 = Operation::new(
             GlobalCounter::from(217),
-            StackOp::new(RW::READ, StackAddress::from(1), Word::from(12)),
+            StackOp::new(RW::READ, 0, StackAddress::from(1), Word::from(12)),
         );
 
         let storage_op_0 = Operation::new(
@@ -1728,6 +3484,8 @@ mod tests {
                 Word::from(0x40),
                 Word::from(32),
                 Word::from(0),
+                1,
+                Word::from(0),
             ),
         );
         let storage_op_1 = Operation::new(
@@ -1738,6 +3496,8 @@ mod tests {
                 Word::from(0x40),
                 Word::from(32),
                 Word::from(0),
+                1,
+                Word::from(0),
             ),
         );
         let storage_op_2 = Operation::new(
@@ -1751,6 +3511,8 @@ mod tests {
                 Word::from(0x40),
                 Word::from(32),
                 Word::from(0),
+                1,
+                Word::from(0),
             ),
         );
         let storage_op_3 = Operation::new(
@@ -1763,6 +3525,8 @@ mod tests {
                 Word::from(0x41),
                 Word::from(32),
                 Word::from(32),
+                1,
+                Word::from(0),
             ),
         );
 
@@ -1777,6 +3541,8 @@ mod tests {
                 Word::from(0x41),
                 Word::from(32),
                 Word::from(0),
+                1,
+                Word::from(0),
             ),
         );
 
@@ -1806,31 +3572,32 @@ mod tests {
     fn non_monotone_address() {
         let memory_op_0 = Operation::new(
             GlobalCounter::from(1352),
-            MemoryOp::new(RW::WRITE, MemoryAddress::from(0), 32),
+            MemoryOp::new(RW::WRITE, 0, MemoryAddress::from(0), 32),
         );
         let memory_op_1 = Operation::new(
             GlobalCounter::from(1255),
-            MemoryOp::new(RW::WRITE, MemoryAddress::from(1), 32),
+            MemoryOp::new(RW::WRITE, 0, MemoryAddress::from(1), 32),
         );
 
         // fails because it's not monotone
         let memory_op_2 = Operation::new(
             GlobalCounter::from(1255),
-            MemoryOp::new(RW::WRITE, MemoryAddress::from(0), 32),
+            MemoryOp::new(RW::WRITE, 0, MemoryAddress::from(0), 32),
         );
 
         let stack_op_0 = Operation::new(
             GlobalCounter::from(228),
-            StackOp::new(RW::WRITE, StackAddress::from(0), Word::from(12)),
+            StackOp::new(RW::WRITE, 0, StackAddress::from(0), Word::from(12)),
         );
         let stack_op_1 = Operation::new(
             GlobalCounter::from(229),
-            StackOp::new(RW::WRITE, StackAddress::from(1), Word::from(12)),
+            StackOp::new(RW::WRITE, 0, StackAddress::from(1), Word::from(12)),
         );
         let stack_op_2 = Operation::new(
             GlobalCounter::from(230),
             StackOp::new(
                 RW::WRITE,
+                0,
                 StackAddress::from(0), /* this fails because the
                                         * address is not
                                         * monotone */
@@ -1853,6 +3620,128 @@ mod tests {
         );
     }
 
+    #[test]
+    fn memory_and_stack_of_two_calls() {
+        // The callee starts with an empty memory and stack, at addresses
+        // lower than the ones used by the caller.
+        let memory_op_0 = Operation::new(
+            GlobalCounter::from(12),
+            MemoryOp::new(RW::WRITE, 1, MemoryAddress::from(1), 32),
+        );
+        let memory_op_1 = Operation::new(
+            GlobalCounter::from(40),
+            MemoryOp::new(RW::READ, 1, MemoryAddress::from(1), 32),
+        );
+        let memory_op_2 = Operation::new(
+            GlobalCounter::from(24),
+            MemoryOp::new(RW::READ, 20, MemoryAddress::from(0), 0),
+        );
+
+        let stack_op_0 = Operation::new(
+            GlobalCounter::from(17),
+            StackOp::new(
+                RW::WRITE,
+                1,
+                StackAddress::from(1023),
+                Word::from(32),
+            ),
+        );
+        let stack_op_1 = Operation::new(
+            GlobalCounter::from(41),
+            StackOp::new(RW::READ, 1, StackAddress::from(1023), Word::from(32)),
+        );
+        let stack_op_2 = Operation::new(
+            GlobalCounter::from(25),
+            StackOp::new(
+                RW::WRITE,
+                20,
+                StackAddress::from(1022),
+                Word::from(7),
+            ),
+        );
+        let stack_op_3 = Operation::new(
+            GlobalCounter::from(26),
+            StackOp::new(RW::READ, 20, StackAddress::from(1022), Word::from(7)),
+        );
+
+        test_state_circuit!(
+            14,
+            2000,
+            100,
+            2,
+            100,
+            1023,
+            1000,
+            vec![memory_op_0, memory_op_1, memory_op_2],
+            vec![stack_op_0, stack_op_1, stack_op_2, stack_op_3],
+            vec![],
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn non_monotone_call_id() {
+        let stack_op_0 = Operation::new(
+            GlobalCounter::from(25),
+            StackOp::new(
+                RW::WRITE,
+                20,
+                StackAddress::from(1022),
+                Word::from(7),
+            ),
+        );
+        // fails because the call id decreases
+        let stack_op_1 = Operation::new(
+            GlobalCounter::from(17),
+            StackOp::new(
+                RW::WRITE,
+                1,
+                StackAddress::from(1023),
+                Word::from(32),
+            ),
+        );
+
+        test_state_circuit_error!(
+            14,
+            2000,
+            100,
+            2,
+            100,
+            1023,
+            1000,
+            vec![],
+            vec![stack_op_0, stack_op_1],
+            vec![]
+        );
+    }
+
+    #[test]
+    fn memory_of_new_call_not_initialized() {
+        // The callee reads a value written by the caller at the same address,
+        // which fails because its memory starts empty.
+        let memory_op_0 = Operation::new(
+            GlobalCounter::from(12),
+            MemoryOp::new(RW::WRITE, 1, MemoryAddress::from(0), 32),
+        );
+        let memory_op_1 = Operation::new(
+            GlobalCounter::from(24),
+            MemoryOp::new(RW::READ, 20, MemoryAddress::from(0), 32),
+        );
+
+        test_state_circuit_error!(
+            14,
+            2000,
+            100,
+            2,
+            100,
+            1023,
+            1000,
+            vec![memory_op_0, memory_op_1],
+            vec![],
+            vec![]
+        );
+    }
+
     #[test]
     fn storage() {
         let storage_op_0 = Operation::new(
@@ -1863,6 +3752,8 @@ mod tests {
                 Word::from(0x40),
                 Word::from(32),
                 Word::from(0),
+                1,
+                Word::from(0),
             ),
         );
         let storage_op_1 = Operation::new(
@@ -1876,6 +3767,8 @@ mod tests {
                                  * value as in the previous
                                  * row. */
                 Word::from(0),
+                1,
+                Word::from(0),
             ),
         );
         let storage_op_2 = Operation::new(
@@ -1888,6 +3781,8 @@ mod tests {
                 Word::from(0), /* Fails because not the same
                                 * as value in the previous row - note: this
                                 * is WRITE. */
+                1,
+                Word::from(0),
             ),
         );
         let storage_op_3 = Operation::new(
@@ -1900,6 +3795,8 @@ mod tests {
                 Word::from(1), /* Fails because not the same
                                 * as value_prev in the previous row - note:
                                 * this is READ. */
+                1,
+                Word::from(0),
             ),
         );
 
@@ -1919,6 +3816,181 @@ mod tests {
         );
     }
 
+    fn storage_writes_and_reverts(
+        reverted_value: u64,
+    ) -> Vec<Operation<StorageOp>> {
+        let address = address!("0x0000000000000000000000000000000000000001");
+        let write = |value: u64, value_prev: u64| {
+            StorageOp::new(
+                RW::WRITE,
+                address,
+                Word::from(0x40),
+                Word::from(value),
+                Word::from(value_prev),
+                1,
+                Word::from(0),
+            )
+        };
+        // The writes are undone from the last one to the first one
+        vec![
+            Operation::new(GlobalCounter::from(18), write(32, 0)),
+            Operation::new(GlobalCounter::from(19), write(33, 32)),
+            Operation::new_revert(
+                GlobalCounter::from(25),
+                write(reverted_value, 33),
+            ),
+            Operation::new_revert(
+                GlobalCounter::from(26),
+                write(0, reverted_value),
+            ),
+        ]
+    }
+
+    #[test]
+    fn storage_revert() {
+        test_state_circuit!(
+            14,
+            2000,
+            100,
+            2,
+            100,
+            1023,
+            1000,
+            vec![],
+            vec![],
+            storage_writes_and_reverts(32),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn storage_revert_inconsistent_value() {
+        // The first revert doesn't restore the value before the second write
+        test_state_circuit_error!(
+            14,
+            2000,
+            100,
+            2,
+            100,
+            1023,
+            1000,
+            vec![],
+            vec![],
+            storage_writes_and_reverts(31)
+        );
+    }
+
+    fn storage_ops_of_two_txs(
+        committed_value: u64,
+    ) -> Vec<Operation<StorageOp>> {
+        let address = address!("0x0000000000000000000000000000000000000001");
+        let op = |rw: RW,
+                  value: u64,
+                  value_prev: u64,
+                  tx_id: usize,
+                  committed_value: u64| {
+            StorageOp::new(
+                rw,
+                address,
+                Word::from(0x40),
+                Word::from(value),
+                Word::from(value_prev),
+                tx_id,
+                Word::from(committed_value),
+            )
+        };
+        // The second tx starts with the value written by the first one
+        vec![
+            Operation::new(GlobalCounter::from(18), op(RW::WRITE, 32, 0, 1, 0)),
+            Operation::new(
+                GlobalCounter::from(19),
+                op(RW::WRITE, 33, 32, 1, 0),
+            ),
+            Operation::new(
+                GlobalCounter::from(30),
+                op(RW::READ, 33, 32, 2, committed_value),
+            ),
+            Operation::new(
+                GlobalCounter::from(31),
+                op(RW::WRITE, 34, 33, 2, committed_value),
+            ),
+        ]
+    }
+
+    #[test]
+    fn storage_committed_value() {
+        test_state_circuit!(
+            14,
+            2000,
+            100,
+            2,
+            100,
+            1023,
+            1000,
+            vec![],
+            vec![],
+            storage_ops_of_two_txs(33),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn storage_committed_value_inconsistent() {
+        // The committed value of the second tx is the one of the first tx
+        test_state_circuit_error!(
+            14,
+            2000,
+            100,
+            2,
+            100,
+            1023,
+            1000,
+            vec![],
+            vec![],
+            storage_ops_of_two_txs(0)
+        );
+    }
+
+    #[test]
+    fn storage_addresses_not_sorted() {
+        // The ops of the second address are before the ones of the first
+        // address, so that the ops of a slot could be split
+        let ops = [
+            address!("0x0000000000000000000000000000000000000002"),
+            address!("0x0000000000000000000000000000000000000001"),
+        ]
+        .iter()
+        .enumerate()
+        .map(|(idx, address)| {
+            Operation::new(
+                GlobalCounter::from(idx + 1),
+                StorageOp::new(
+                    RW::WRITE,
+                    *address,
+                    Word::from(0x40),
+                    Word::from(32),
+                    Word::zero(),
+                    1,
+                    Word::zero(),
+                ),
+            )
+        })
+        .collect();
+
+        test_state_circuit_error!(
+            14,
+            2000,
+            100,
+            2,
+            100,
+            1023,
+            1000,
+            vec![],
+            vec![],
+            ops
+        );
+    }
+
     #[test]
     fn trace() {
         let input_trace = r#"
@@ -1996,4 +4068,559 @@ mod tests {
             Ok(())
         );
     }
+
+    #[test]
+    fn account() {
+        let address = address!("0x0000000000000000000000000000000000000001");
+        let nonce_op_0 = Operation::new(
+            GlobalCounter::from(17),
+            AccountNonceOp::new(
+                RW::WRITE,
+                address,
+                Word::from(1),
+                Word::zero(),
+            ),
+        );
+        let nonce_op_1 = Operation::new(
+            GlobalCounter::from(23),
+            AccountNonceOp::new(
+                RW::READ,
+                address,
+                Word::from(1),
+                Word::from(1),
+            ),
+        );
+        let balance_op_0 = Operation::new(
+            GlobalCounter::from(3),
+            AccountBalanceOp::new(
+                RW::READ,
+                address,
+                Word::from(32),
+                Word::from(32),
+            ),
+        );
+        let balance_op_1 = Operation::new(
+            GlobalCounter::from(18),
+            AccountBalanceOp::new(
+                RW::WRITE,
+                address,
+                Word::from(0),
+                Word::from(32),
+            ),
+        );
+        let code_hash_op = Operation::new(
+            GlobalCounter::from(20),
+            AccountCodeHashOp::new(
+                RW::WRITE,
+                address,
+                H256::repeat_byte(0xff),
+                H256::zero(),
+            ),
+        );
+
+        test_state_circuit!(
+            14,
+            2000,
+            100,
+            2,
+            100,
+            1023,
+            1000,
+            1000,
+            vec![],
+            vec![],
+            vec![],
+            vec![nonce_op_0, nonce_op_1],
+            vec![balance_op_0, balance_op_1],
+            vec![code_hash_op],
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn storage_and_account_mpt_table() {
+        let address = address!("0x0000000000000000000000000000000000000001");
+        let storage_op_0 = Operation::new(
+            GlobalCounter::from(18),
+            StorageOp::new(
+                RW::WRITE,
+                address,
+                Word::from(0x40),
+                Word::from(32),
+                Word::from(0),
+                1,
+                Word::from(0),
+            ),
+        );
+        let storage_op_1 = Operation::new(
+            GlobalCounter::from(19),
+            StorageOp::new(
+                RW::WRITE,
+                address,
+                Word::from(0x40),
+                Word::from(33),
+                Word::from(32),
+                1,
+                Word::from(0),
+            ),
+        );
+        let balance_op_0 = Operation::new(
+            GlobalCounter::from(3),
+            AccountBalanceOp::new(
+                RW::READ,
+                address,
+                Word::from(32),
+                Word::from(32),
+            ),
+        );
+        let balance_op_1 = Operation::new(
+            GlobalCounter::from(17),
+            AccountBalanceOp::new(
+                RW::WRITE,
+                address,
+                Word::from(0),
+                Word::from(32),
+            ),
+        );
+
+        // The final values of the slot and of the balance aren't the ones
+        // after the updates in the MPT table
+        for invalid_mpt_table in [false, true] {
+            let circuit = StateCircuit::<2000, 2, 1000, 2, 1023, 100, 100, 0> {
+                storage_ops: vec![storage_op_0.clone(), storage_op_1.clone()],
+                account_balance_ops: vec![
+                    balance_op_0.clone(),
+                    balance_op_1.clone(),
+                ],
+                invalid_mpt_table,
+                ..Default::default()
+            };
+
+            let prover = MockProver::<Fp>::run(14, &circuit, vec![]).unwrap();
+            assert_eq!(prover.verify().is_err(), invalid_mpt_table);
+        }
+    }
+
+    #[test]
+    fn account_inconsistent_values() {
+        let address = address!("0x0000000000000000000000000000000000000001");
+        // The read doesn't see the value of the previous write
+        let balance_op_0 = Operation::new(
+            GlobalCounter::from(17),
+            AccountBalanceOp::new(
+                RW::WRITE,
+                address,
+                Word::from(32),
+                Word::from(0),
+            ),
+        );
+        let balance_op_1 = Operation::new(
+            GlobalCounter::from(18),
+            AccountBalanceOp::new(
+                RW::READ,
+                address,
+                Word::from(33),
+                Word::from(33),
+            ),
+        );
+        // The write doesn't see the value of the previous write as value_prev
+        let nonce_op_0 = Operation::new(
+            GlobalCounter::from(19),
+            AccountNonceOp::new(
+                RW::WRITE,
+                address,
+                Word::from(1),
+                Word::zero(),
+            ),
+        );
+        let nonce_op_1 = Operation::new(
+            GlobalCounter::from(20),
+            AccountNonceOp::new(
+                RW::WRITE,
+                address,
+                Word::from(3),
+                Word::from(2),
+            ),
+        );
+
+        test_state_circuit_error!(
+            14,
+            2000,
+            100,
+            2,
+            100,
+            1023,
+            1000,
+            1000,
+            vec![],
+            vec![],
+            vec![],
+            vec![nonce_op_0, nonce_op_1],
+            vec![balance_op_0, balance_op_1],
+            vec![]
+        );
+    }
+
+    #[test]
+    fn account_addresses() {
+        // The ops are sorted by address, whose differences span all their
+        // bytes
+        let ops = [
+            address!("0x0000000000000000000000000000000000000001"),
+            address!("0xff00000000000000000000000000000000000000"),
+            address!("0x00000000000000000000000000000000000000ff"),
+            address!("0xffffffffffffffffffffffffffffffffffffffff"),
+        ]
+        .iter()
+        .enumerate()
+        .map(|(idx, address)| {
+            Operation::new(
+                GlobalCounter::from(idx + 1),
+                AccountNonceOp::new(
+                    RW::READ,
+                    *address,
+                    Word::from(idx),
+                    Word::from(idx),
+                ),
+            )
+        })
+        .collect();
+
+        test_state_circuit!(
+            14,
+            2000,
+            100,
+            2,
+            100,
+            1023,
+            1000,
+            1000,
+            vec![],
+            vec![],
+            vec![],
+            ops,
+            vec![],
+            vec![],
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn account_too_many_ops() {
+        let address = address!("0x0000000000000000000000000000000000000001");
+        let ops = (1..=3)
+            .map(|gc| {
+                Operation::new(
+                    GlobalCounter::from(gc),
+                    AccountNonceOp::new(
+                        RW::READ,
+                        address,
+                        Word::zero(),
+                        Word::zero(),
+                    ),
+                )
+            })
+            .collect();
+        let circuit = StateCircuit::<2000, 100, 2, 100, 1023, 1000, 2, 0> {
+            account_nonce_ops: ops,
+            ..Default::default()
+        };
+
+        assert!(MockProver::<Fp>::run(14, &circuit, vec![]).is_err());
+    }
+
+    fn verify_access_list_ops(
+        account_ops: Vec<Operation<TxAccessListAccountOp>>,
+        storage_ops: Vec<Operation<TxAccessListAccountStorageOp>>,
+    ) -> Result<(), Vec<VerifyFailure>> {
+        let circuit = StateCircuit::<2000, 100, 2, 100, 1023, 100, 0, 100> {
+            tx_access_list_account_ops: account_ops,
+            tx_access_list_account_storage_ops: storage_ops,
+            ..Default::default()
+        };
+
+        MockProver::<Fp>::run(14, &circuit, vec![])
+            .unwrap()
+            .verify()
+    }
+
+    #[test]
+    fn access_list() {
+        let address_0 = address!("0x0000000000000000000000000000000000000001");
+        let address_1 = address!("0x0000000000000000000000000000000000000002");
+        let account_op = |gc: usize, rw, tx_id, address, value, value_prev| {
+            Operation::new(
+                GlobalCounter::from(gc),
+                TxAccessListAccountOp::new(
+                    rw, tx_id, address, value, value_prev,
+                ),
+            )
+        };
+        let storage_op = |gc: usize, rw, key: u64, value, value_prev| {
+            Operation::new(
+                GlobalCounter::from(gc),
+                TxAccessListAccountStorageOp::new(
+                    rw,
+                    1,
+                    address_0,
+                    Word::from(key),
+                    value,
+                    value_prev,
+                ),
+            )
+        };
+
+        // The account and the slots are warm after their first write in each
+        // tx
+        assert_eq!(
+            verify_access_list_ops(
+                vec![
+                    account_op(5, RW::WRITE, 1, address_0, true, false),
+                    account_op(7, RW::READ, 1, address_1, false, false),
+                    account_op(9, RW::READ, 1, address_0, true, true),
+                    account_op(20, RW::WRITE, 2, address_0, true, false),
+                ],
+                vec![
+                    storage_op(6, RW::WRITE, 1, true, false),
+                    storage_op(8, RW::READ, 1, true, true),
+                    storage_op(10, RW::READ, 2, false, false),
+                ],
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn access_list_first_access_warm() {
+        let address = address!("0x0000000000000000000000000000000000000001");
+
+        // The account was warm in the previous tx, but is cold in the next one
+        let account_ops = vec![
+            Operation::new(
+                GlobalCounter::from(5),
+                TxAccessListAccountOp::new(RW::WRITE, 1, address, true, false),
+            ),
+            Operation::new(
+                GlobalCounter::from(20),
+                TxAccessListAccountOp::new(RW::READ, 2, address, true, true),
+            ),
+        ];
+        assert!(verify_access_list_ops(account_ops, vec![]).is_err());
+
+        let storage_ops = vec![Operation::new(
+            GlobalCounter::from(6),
+            TxAccessListAccountStorageOp::new(
+                RW::WRITE,
+                1,
+                address,
+                Word::from(1),
+                true,
+                true,
+            ),
+        )];
+        assert!(verify_access_list_ops(vec![], storage_ops).is_err());
+    }
+
+    fn verify_refund_ops(
+        ops: &[(usize, RW, usize, u64, u64)],
+    ) -> Result<(), Vec<VerifyFailure>> {
+        let circuit = StateCircuit::<2000, 100, 2, 100, 1023, 100, 0, 100> {
+            tx_refund_ops: ops
+                .iter()
+                .map(|&(gc, rw, tx_id, value, value_prev)| {
+                    Operation::new(
+                        GlobalCounter::from(gc),
+                        TxRefundOp::new(rw, tx_id, value, value_prev),
+                    )
+                })
+                .collect(),
+            ..Default::default()
+        };
+
+        MockProver::<Fp>::run(14, &circuit, vec![])
+            .unwrap()
+            .verify()
+    }
+
+    #[test]
+    fn refund() {
+        assert_eq!(
+            verify_refund_ops(&[
+                (5, RW::READ, 1, 0, 0),
+                (6, RW::WRITE, 1, 4800, 0),
+                (9, RW::READ, 1, 4800, 4800),
+                (12, RW::WRITE, 1, 0, 4800),
+                (20, RW::WRITE, 2, 15000, 0),
+            ]),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn refund_inconsistent() {
+        // The refund counter of the next tx starts at 0
+        assert!(verify_refund_ops(&[
+            (6, RW::WRITE, 1, 4800, 0),
+            (20, RW::WRITE, 2, 9600, 4800),
+        ])
+        .is_err());
+        // A read gets the value of the previous write
+        assert!(verify_refund_ops(&[
+            (6, RW::WRITE, 1, 4800, 0),
+            (9, RW::READ, 1, 0, 0),
+        ])
+        .is_err());
+    }
+
+    fn verify_call_context_ops(
+        ops: &[(usize, RW, usize, CallContextField, u64)],
+    ) -> Result<(), Vec<VerifyFailure>> {
+        let circuit = StateCircuit::<2000, 100, 2, 100, 1023, 100, 0, 100> {
+            call_context_ops: ops
+                .iter()
+                .map(|&(gc, rw, call_id, field, value)| {
+                    Operation::new(
+                        GlobalCounter::from(gc),
+                        CallContextOp::new(
+                            rw,
+                            call_id,
+                            field,
+                            Word::from(value),
+                        ),
+                    )
+                })
+                .collect(),
+            ..Default::default()
+        };
+
+        MockProver::<Fp>::run(14, &circuit, vec![])
+            .unwrap()
+            .verify()
+    }
+
+    #[test]
+    fn call_context() {
+        // The root call 1 creates the call 8
+        assert_eq!(
+            verify_call_context_ops(&[
+                (2, RW::READ, 1, CallContextField::TxId, 1),
+                (3, RW::READ, 1, CallContextField::Depth, 1),
+                (4, RW::WRITE, 8, CallContextField::CallerId, 1),
+                (5, RW::WRITE, 8, CallContextField::TxId, 1),
+                (6, RW::WRITE, 8, CallContextField::Depth, 2),
+                (9, RW::READ, 8, CallContextField::TxId, 1),
+                (10, RW::READ, 8, CallContextField::Depth, 2),
+                (12, RW::READ, 1, CallContextField::TxId, 1),
+            ]),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn call_context_read_inconsistent() {
+        assert!(verify_call_context_ops(&[
+            (5, RW::WRITE, 8, CallContextField::TxId, 1),
+            (9, RW::READ, 8, CallContextField::TxId, 2),
+        ])
+        .is_err());
+        assert!(verify_call_context_ops(&[
+            (2, RW::READ, 1, CallContextField::TxId, 1),
+            (12, RW::READ, 1, CallContextField::TxId, 2),
+        ])
+        .is_err());
+    }
+
+    fn verify_log_ops(
+        ops: &[(usize, RW, usize, usize, TxLogField, usize)],
+    ) -> Result<(), Vec<VerifyFailure>> {
+        let circuit = StateCircuit::<2000, 100, 2, 100, 1023, 100, 0, 100> {
+            tx_log_ops: ops
+                .iter()
+                .map(|&(gc, rw, tx_id, log_id, field, index)| {
+                    Operation::new(
+                        GlobalCounter::from(gc),
+                        TxLogOp::new(
+                            rw,
+                            tx_id,
+                            log_id,
+                            field,
+                            index,
+                            Word::from(gc),
+                        ),
+                    )
+                })
+                .collect(),
+            ..Default::default()
+        };
+
+        MockProver::<Fp>::run(14, &circuit, vec![])
+            .unwrap()
+            .verify()
+    }
+
+    #[test]
+    fn log() {
+        assert_eq!(
+            verify_log_ops(&[
+                (5, RW::WRITE, 1, 1, TxLogField::Address, 0),
+                (6, RW::WRITE, 1, 1, TxLogField::Topic, 0),
+                (7, RW::WRITE, 1, 1, TxLogField::Data, 0),
+                (8, RW::WRITE, 1, 1, TxLogField::Data, 1),
+                (12, RW::WRITE, 1, 2, TxLogField::Address, 0),
+                (20, RW::WRITE, 2, 1, TxLogField::Address, 0),
+            ]),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn log_not_appended() {
+        // A field is written twice
+        assert!(verify_log_ops(&[
+            (5, RW::WRITE, 1, 1, TxLogField::Address, 0),
+            (9, RW::WRITE, 1, 1, TxLogField::Address, 0),
+        ])
+        .is_err());
+        // A field is read
+        assert!(verify_log_ops(&[
+            (5, RW::WRITE, 1, 1, TxLogField::Address, 0),
+            (9, RW::READ, 1, 1, TxLogField::Topic, 0),
+        ])
+        .is_err());
+        // The first log of the tx is skipped
+        assert!(verify_log_ops(&[(
+            5,
+            RW::WRITE,
+            1,
+            2,
+            TxLogField::Address,
+            0
+        )])
+        .is_err());
+    }
+
+    #[test]
+    fn access_list_read_inconsistent() {
+        let address = address!("0x0000000000000000000000000000000000000001");
+
+        // The slot is read as cold after being warmed
+        let storage_ops =
+            [(6, RW::WRITE, true, false), (8, RW::READ, false, false)]
+                .iter()
+                .map(|&(gc, rw, value, value_prev)| {
+                    Operation::new(
+                        GlobalCounter::from(gc),
+                        TxAccessListAccountStorageOp::new(
+                            rw,
+                            1,
+                            address,
+                            Word::from(1),
+                            value,
+                            value_prev,
+                        ),
+                    )
+                })
+                .collect();
+        assert!(verify_access_list_ops(vec![], storage_ops).is_err());
+    }
 }
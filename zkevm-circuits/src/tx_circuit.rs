@@ -0,0 +1,96 @@
+//! The tx circuit implementation.
+
+pub(crate) mod tx;
+
+/*
+Example tx table, with the EIP-155 example tx signed by the sender `0x9d8a..`
+as tx 1, followed by an unused tx slot:
+
+| tx_id |        tag        |     value      | length | padding |
+-----------------------------------------------------------------
+|   1   | Nonce             |       9        |   1    |    0    |
+|   1   | Gas               |     21000      |   2    |    0    |
+|   1   | GasPrice          |  rlc(20 gwei)  |   5    |    0    |
+|   1   | Value             |  rlc(1 ether)  |   8    |    0    |
+|   1   | CalleeAddress     |   0x3535..35   |   20   |    0    |
+|   1   | IsCreate          |       0        |   0    |    0    |
+|   1   | CallDataLength    |       0        |   0    |    0    |
+|   1   | TxSignHash        | rlc(0xdaf5..)  |   0    |    0    |
+|   1   | Origin            |   0x9d8a..4f   |   0    |    0    |   // signature
+|   1   | CallDataGasCost   |       0        |   0    |    0    |
+|   1   | AccessListGasCost |       0        |   0    |    0    |
+|   1   | TxHash            | rlc(0x3346..)  |   0    |    0    |
+|   0   |         0         |       0        |   0    |    1    |   // padding
+|  ..   |        ..         |       ..       |   ..   |   ..    |
+
+Each tx takes one row per field in the order above.  The fields encoded in
+the signed payload are looked up, with the length of their encoding, among
+the items decoded from the payload rows:
+
+| tx_id | index | byte | kind        | item_idx | tag      | length | content_left | value |
+-----------------------------------------------------------------------------------------
+|   1   |   0   | 0xec | ListHeader  |    0     |    0     |   44   |      44      |   0   |
+|   1   |   1   | 0x09 | ItemHeader  |    0     | Nonce    |   1    |      0       |   9   |   // single byte
+|   1   |   2   | 0x85 | ItemHeader  |    1     | GasPrice |   5    |      5       |   0   |
+|   1   |   3   | 0x04 | Content     |    1     | GasPrice |   5    |      4       | rlc   |
+|  ..   |  ..   |  ..  |     ..      |    ..    |    ..    |   ..   |      ..      |  ..   |
+|   1   |  44   | 0x80 | ItemHeader  |    8     | Zero     |   0    |      0       |   0   |   // final
+
+The payload is the part of the signed tx whose keccak hash is signed by the
+sender: the EIP-2718 type byte of a typed tx followed by the RLP list of its
+items, without the signature.  The rows of the list header set the number of
+bytes left in the list, and each item header sets the number of content bytes
+which follow it, whose values are accumulated into the value of the item.
+The gas paid for the bytes of the data is accumulated along them, and is the
+calldata gas cost of the tx.
+The content bytes of the access list are decoded into its entries, each being
+the list of an address and of the list of its storage keys:
+
+| kind        | byte | list_length | elem_left | entry_left | keys_left | gas  |
+--------------------------------------------------------------------------------
+| EntryHeader | 0xf7 |     55      |     0     |     55     |     0     | 2400 |
+| Address     | 0x94 |      0      |    20     |     54     |     0     | 2400 |
+|     ..      |  ..  |     ..      |    ..     |     ..     |    ..     |  ..  |
+| KeysHeader  | 0xe1 |     33      |     0     |     33     |    33     | 2400 |
+| Key         | 0xa0 |      0      |    32     |     32     |    32     | 4300 |
+|     ..      |  ..  |     ..      |    ..     |     ..     |    ..     |  ..  |
+
+The gas paid for the access list is accumulated over the headers of the
+entries and of the storage keys, and is the access list gas cost of the tx.
+The hash of the payload is checked against the keccak table on its last byte,
+and the sender recovered from the signature of the hash is checked against
+the signature table.  The chain id of the payload is checked against the one
+of the block table.  The bytes of the data of a tx are also the rows of the
+tx calldata table.
+
+The payload of each tx is followed by the signed tx in the payload rows,
+which are decoded in the same way, with is_signed set.  The signed tx has the
+fields of the payload, without the EIP-155 items, followed by the v, r and s
+of the signature:
+
+| is_signed | item_idx | tag      | item_rlc  | value   | v_offset |
+-------------------------------------------------------------------
+|     1     |    0     | Nonce    | 0x09      |    9    |    37    |
+|    ..     |    ..    |   ..     |    ..     |   ..    |    ..    |
+|     1     |    6     | SigV     | 0x25      |   37    |    37    |
+|     1     |    7     | SigR     | rlc(..)   | rlc(r)  |    37    |
+|     1     |    8     | SigS     | rlc(..)   | rlc(s)  |    37    |   // final
+
+Each field of the signed tx is looked up among the items of its payload with
+the same tag, by the random linear combination of the bytes of the item,
+and the signature is the one of the tx table.  The v of the signature is the
+recovery id plus the offset of the tx, which is 0 for a typed tx, 27 for a
+legacy tx signed without a chain id, and 35 plus twice the chain id, as the
+integer value of its item in the payload, for an EIP-155 tx.  The hash of the
+signed tx is checked against the keccak table on its last byte, and is the
+hash of the tx, which is looked up in the block table at the index of the tx
+so that it's the one committed to by the public inputs.
+
+The tx table, the tx calldata table, the keccak table and the block table are
+the ones of the EVM circuit, whose lookups into the tx tables are then proven
+by this circuit.  The keccak table and the signature table are still
+witnessed by the prover, see their stubs.
+
+TODO: The gas price of an EIP-1559 tx is its max fee per gas until the
+effective gas price is computed from the base fee of the block.
+*/
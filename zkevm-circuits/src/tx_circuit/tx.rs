@@ -0,0 +1,3272 @@
+use crate::{
+    evm_circuit::{BlockContextField, TxContextField},
+    gadget::{
+        evm_word::encode,
+        is_zero::{IsZeroChip, IsZeroConfig, IsZeroInstruction},
+    },
+    public_input_circuit::public_input::BlockTag,
+};
+use bus_mapping::{
+    eth_types::{Address, Word, H256},
+    evm::GasCost,
+};
+use ethers_core::{
+    types::{Signature, SignatureError},
+    utils::{
+        keccak256,
+        rlp::{DecoderError, Rlp, RlpStream},
+    },
+};
+use halo2::{
+    circuit::{Layouter, Region},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed},
+    poly::Rotation,
+};
+use pairing::arithmetic::FieldExt;
+use std::iter;
+
+pub(crate) const LEGACY_TX: u8 = 0;
+pub(crate) const EIP2930_TX: u8 = 1;
+pub(crate) const EIP1559_TX: u8 = 2;
+
+/// The fields of a tx, in the order of its rows in the tx table.
+pub(crate) const TX_FIELDS: [TxContextField; 12] = [
+    TxContextField::Nonce,
+    TxContextField::Gas,
+    TxContextField::GasPrice,
+    TxContextField::Value,
+    TxContextField::CalleeAddress,
+    TxContextField::IsCreate,
+    TxContextField::CallDataLength,
+    TxContextField::TxSignHash,
+    TxContextField::Origin,
+    TxContextField::CallDataGasCost,
+    TxContextField::AccessListGasCost,
+    TxContextField::TxHash,
+];
+
+// The fields looked up among the items of the payload
+const PAYLOAD_FIELDS: [TxContextField; 6] = [
+    TxContextField::Nonce,
+    TxContextField::Gas,
+    TxContextField::GasPrice,
+    TxContextField::Value,
+    TxContextField::CalleeAddress,
+    TxContextField::CallDataLength,
+];
+
+/// The tag of an item of the signed payload or of the signed tx, which is the
+/// tag of its row in the tx table for the fields of the tx.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum PayloadTag {
+    Field(TxContextField),
+    ChainId,
+    MaxPriorityFeePerGas,
+    AccessList,
+    // The empty items which follow the chain id in an EIP-155 payload
+    Zero,
+    // Marks the number of items of a complete payload
+    End,
+    // The v, r and s of the signature, which follow the fields of the
+    // payload in the signed tx
+    SigV,
+    SigR,
+    SigS,
+}
+
+impl PayloadTag {
+    fn value(&self) -> u64 {
+        match self {
+            Self::Field(field) => *field as u64,
+            // After the tags of the tx table
+            Self::ChainId => 0x100,
+            Self::MaxPriorityFeePerGas => 0x101,
+            Self::AccessList => 0x102,
+            Self::Zero => 0x103,
+            Self::End => 0x104,
+            Self::SigV => 0x105,
+            Self::SigR => 0x106,
+            Self::SigS => 0x107,
+        }
+    }
+
+    fn is_signature(&self) -> bool {
+        matches!(self, Self::SigV | Self::SigR | Self::SigS)
+    }
+
+    // Returns the base in which the content bytes of the item are accumulated
+    // into its value, whether the item is the data of the tx, whose value is
+    // its number of bytes instead, whether the item is the access list, whose
+    // entries are decoded instead, and whether the item is the chain id.
+    fn item_kind<F: FieldExt>(&self, r: F) -> (F, bool, bool, bool) {
+        match self {
+            Self::Field(TxContextField::GasPrice)
+            | Self::Field(TxContextField::Value)
+            | Self::MaxPriorityFeePerGas
+            | Self::SigR
+            | Self::SigS => (r, false, false, false),
+            Self::ChainId => (r, false, false, true),
+            Self::Field(TxContextField::CallDataLength) => {
+                (F::zero(), true, false, false)
+            }
+            Self::AccessList => (F::zero(), false, true, false),
+            Self::End => (F::zero(), false, false, false),
+            _ => (F::from(256), false, false, false),
+        }
+    }
+}
+
+const LEGACY_ITEMS: [PayloadTag; 9] = [
+    PayloadTag::Field(TxContextField::Nonce),
+    PayloadTag::Field(TxContextField::GasPrice),
+    PayloadTag::Field(TxContextField::Gas),
+    PayloadTag::Field(TxContextField::CalleeAddress),
+    PayloadTag::Field(TxContextField::Value),
+    PayloadTag::Field(TxContextField::CallDataLength),
+    PayloadTag::ChainId,
+    PayloadTag::Zero,
+    PayloadTag::Zero,
+];
+
+const EIP2930_ITEMS: [PayloadTag; 8] = [
+    PayloadTag::ChainId,
+    PayloadTag::Field(TxContextField::Nonce),
+    PayloadTag::Field(TxContextField::GasPrice),
+    PayloadTag::Field(TxContextField::Gas),
+    PayloadTag::Field(TxContextField::CalleeAddress),
+    PayloadTag::Field(TxContextField::Value),
+    PayloadTag::Field(TxContextField::CallDataLength),
+    PayloadTag::AccessList,
+];
+
+// The max fee per gas is the gas price of the tx
+const EIP1559_ITEMS: [PayloadTag; 9] = [
+    PayloadTag::ChainId,
+    PayloadTag::Field(TxContextField::Nonce),
+    PayloadTag::MaxPriorityFeePerGas,
+    PayloadTag::Field(TxContextField::GasPrice),
+    PayloadTag::Field(TxContextField::Gas),
+    PayloadTag::Field(TxContextField::CalleeAddress),
+    PayloadTag::Field(TxContextField::Value),
+    PayloadTag::Field(TxContextField::CallDataLength),
+    PayloadTag::AccessList,
+];
+
+// Returns the items of the payload of a tx of type `tx_type`, and the numbers
+// of items a complete payload can have.  The EIP-155 items are left out of
+// the payload of a legacy tx signed without a chain id.
+fn payload_items(tx_type: u8) -> (&'static [PayloadTag], &'static [usize]) {
+    match tx_type {
+        LEGACY_TX => (&LEGACY_ITEMS, &[6, 9]),
+        EIP2930_TX => (&EIP2930_ITEMS, &[8]),
+        EIP1559_TX => (&EIP1559_ITEMS, &[9]),
+        _ => unreachable!("unsupported tx type {}", tx_type),
+    }
+}
+
+const SIGNATURE_ITEMS: [PayloadTag; 3] =
+    [PayloadTag::SigV, PayloadTag::SigR, PayloadTag::SigS];
+
+// Returns the items of the signed tx of type `tx_type`, which are the fields
+// of its payload, without the EIP-155 items, followed by the signature.
+fn signed_items(tx_type: u8) -> Vec<PayloadTag> {
+    let (items, _) = payload_items(tx_type);
+    let num_fields = match tx_type {
+        LEGACY_TX => 6,
+        _ => items.len(),
+    };
+    items[..num_fields]
+        .iter()
+        .chain(SIGNATURE_ITEMS.iter())
+        .cloned()
+        .collect()
+}
+
+/// The kind of a byte of the signed payload.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum RowKind {
+    TxType,
+    ListHeader,
+    ListLength,
+    ItemHeader,
+    ItemLength,
+    Content,
+}
+
+const NUM_ROW_KINDS: usize = 6;
+
+/// The kind of a content byte of the access list, which is a list of entries,
+/// each being the list of an address and of the list of its storage keys.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum AccessListKind {
+    EntryHeader,
+    EntryLength,
+    // The header of the address or one of its 20 bytes
+    Address,
+    KeysHeader,
+    KeysLength,
+    // The header of a storage key or one of its 32 bytes
+    Key,
+}
+
+const NUM_ACCESS_LIST_KINDS: usize = 6;
+
+/// The decoding of a content byte of the access list.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct AccessListRow {
+    pub(crate) kind: Option<AccessListKind>,
+    pub(crate) is_long: bool,
+    // The length of the list of a header, which is accumulated over the bytes
+    // of a long length
+    pub(crate) length: u64,
+    pub(crate) len_left: u64,
+    // The number of bytes left in the address or in the storage key
+    pub(crate) elem_left: u64,
+    // The number of bytes left in the entry and in its storage keys
+    pub(crate) entry_left: u64,
+    pub(crate) keys_left: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum HeaderKind {
+    Single = 1,
+    ShortString,
+    LongString,
+    ShortList,
+    LongList,
+}
+
+// Returns the kind of the RLP header starting with `byte`, with the length of
+// the content for the short forms, or the number of bytes of the length for
+// the long forms.
+pub(crate) fn header_kind(byte: u8) -> (HeaderKind, u64) {
+    match byte {
+        0x00..=0x7f => (HeaderKind::Single, 0),
+        0x80..=0xb7 => (HeaderKind::ShortString, (byte - 0x80) as u64),
+        0xb8..=0xbf => (HeaderKind::LongString, (byte - 0xb7) as u64),
+        0xc0..=0xf7 => (HeaderKind::ShortList, (byte - 0xc0) as u64),
+        0xf8..=0xff => (HeaderKind::LongList, (byte - 0xf7) as u64),
+    }
+}
+
+/// Error returned when a signed tx can't be decoded.
+#[derive(Debug)]
+pub(crate) enum TxDecodeError {
+    UnsupportedType(u8),
+    Rlp(DecoderError),
+    Signature(SignatureError),
+}
+
+impl From<DecoderError> for TxDecodeError {
+    fn from(err: DecoderError) -> Self {
+        Self::Rlp(err)
+    }
+}
+
+impl From<SignatureError> for TxDecodeError {
+    fn from(err: SignatureError) -> Self {
+        Self::Signature(err)
+    }
+}
+
+/// A signed tx, split into the payload signed by its sender and the
+/// signature.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct SignedTx {
+    pub(crate) tx_type: u8,
+    pub(crate) raw: Vec<u8>,
+    pub(crate) payload: Vec<u8>,
+    // The recovery id of the signature, either 0 or 1
+    pub(crate) sig_v: u64,
+    // The offset of the v of the signature from the recovery id, which also
+    // encodes the chain id of a legacy tx since EIP-155
+    pub(crate) v_offset: u64,
+    pub(crate) sig_r: Word,
+    pub(crate) sig_s: Word,
+    pub(crate) sender: Address,
+}
+
+impl SignedTx {
+    /// Decodes the raw signed tx `raw`, which is either a legacy tx or an
+    /// EIP-2930 or EIP-1559 typed tx, and recovers its sender.
+    pub(crate) fn decode(raw: &[u8]) -> Result<Self, TxDecodeError> {
+        // A typed tx starts with its type, and a legacy tx with a list header
+        let (tx_type, rlp) = match raw.first() {
+            Some(&tx_type) if tx_type < 0x80 => {
+                if !(EIP2930_TX..=EIP1559_TX).contains(&tx_type) {
+                    return Err(TxDecodeError::UnsupportedType(tx_type));
+                }
+                (tx_type, Rlp::new(&raw[1..]))
+            }
+            _ => (LEGACY_TX, Rlp::new(raw)),
+        };
+
+        // The signature follows the items of the payload
+        let num_items = rlp.item_count()?;
+        if num_items != signed_items(tx_type).len() {
+            return Err(DecoderError::RlpIncorrectListLen.into());
+        }
+        let num_fields = num_items - SIGNATURE_ITEMS.len();
+        let v: u64 = rlp.val_at(num_fields)?;
+        let sig_r: Word = rlp.val_at(num_fields + 1)?;
+        let sig_s: Word = rlp.val_at(num_fields + 2)?;
+
+        // The v of a legacy tx also encodes the chain id since EIP-155, which
+        // is then appended to the payload with two empty items
+        let (chain_id, sig_v) = match (tx_type, v) {
+            (LEGACY_TX, 27 | 28) => (None, v - 27),
+            (LEGACY_TX, v) if v >= 35 => (Some((v - 35) / 2), (v - 35) % 2),
+            (LEGACY_TX, _) | (_, 2..=u64::MAX) => {
+                return Err(DecoderError::Custom("invalid signature v").into())
+            }
+            _ => (None, v),
+        };
+
+        let mut stream = RlpStream::new();
+        stream.begin_list(num_fields + if chain_id.is_some() { 3 } else { 0 });
+        for idx in 0..num_fields {
+            stream.append_raw(rlp.at(idx)?.as_raw(), 1);
+        }
+        if let Some(chain_id) = chain_id {
+            stream.append(&chain_id);
+            stream.append_empty_data();
+            stream.append_empty_data();
+        }
+        let mut payload = match tx_type {
+            LEGACY_TX => vec![],
+            _ => vec![tx_type],
+        };
+        payload.extend_from_slice(&stream.out());
+
+        let mut tx = Self {
+            tx_type,
+            raw: raw.to_vec(),
+            payload,
+            sig_v,
+            v_offset: v - sig_v,
+            sig_r,
+            sig_s,
+            sender: Address::zero(),
+        };
+        tx.sender = tx.recover_sender()?;
+        // The entries of the access list are decoded from the payload rows,
+        // which expect addresses of 20 bytes and storage keys of 32 bytes
+        tx.access_list()?;
+        Ok(tx)
+    }
+
+    /// Returns the entries of the access list of the payload (EIP-2930),
+    /// which is empty in a legacy tx, each with an address and its storage
+    /// keys.
+    pub(crate) fn access_list(
+        &self,
+    ) -> Result<Vec<(Address, Vec<H256>)>, DecoderError> {
+        if self.tx_type == LEGACY_TX {
+            return Ok(vec![]);
+        }
+        let (items, _) = payload_items(self.tx_type);
+        let rlp = Rlp::new(&self.payload[1..]);
+        let access_list = rlp.at(items.len() - 1)?;
+        if !access_list.is_list() {
+            return Err(DecoderError::RlpExpectedToBeList);
+        }
+        access_list
+            .iter()
+            .map(|entry| {
+                if entry.item_count()? != 2 {
+                    return Err(DecoderError::RlpIncorrectListLen);
+                }
+                Ok((entry.val_at(0)?, entry.list_at(1)?))
+            })
+            .collect()
+    }
+
+    /// Returns the keccak hash of the payload, which is signed by the sender.
+    pub(crate) fn sign_hash(&self) -> H256 {
+        H256(keccak256(&self.payload))
+    }
+
+    /// Returns the keccak hash of the signed tx, which is the hash of the tx.
+    pub(crate) fn hash(&self) -> H256 {
+        H256(keccak256(&self.raw))
+    }
+
+    /// Returns the rows of the keccak table which hash the payload into the
+    /// sign hash, and the signed tx into the hash of the tx.
+    pub(crate) fn keccak_rows<F: FieldExt>(&self, r: F) -> [[F; 3]; 2] {
+        [(&self.payload, self.sign_hash()), (&self.raw, self.hash())].map(
+            |(input, hash)| {
+                [
+                    encode(input.iter().cloned(), r),
+                    F::from(input.len() as u64),
+                    encode(hash.0.iter().cloned(), r),
+                ]
+            },
+        )
+    }
+
+    /// Recovers the address of the account which signed the payload.
+    pub(crate) fn recover_sender(&self) -> Result<Address, SignatureError> {
+        Signature {
+            r: self.sig_r,
+            s: self.sig_s,
+            v: self.sig_v + 27,
+        }
+        .recover(self.sign_hash())
+    }
+}
+
+/// A byte of the signed payload of a tx, or of the signed tx, as laid out in
+/// the payload rows.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct PayloadRow<F> {
+    pub(crate) index: usize,
+    pub(crate) byte: u8,
+    pub(crate) kind: RowKind,
+    pub(crate) is_single: bool,
+    pub(crate) is_long: bool,
+    pub(crate) item_idx: usize,
+    pub(crate) tag: Option<PayloadTag>,
+    pub(crate) length: u64,
+    pub(crate) len_left: u64,
+    pub(crate) content_left: u64,
+    pub(crate) list_bytes_left: u64,
+    pub(crate) value: F,
+    // The random linear combination of the bytes of the item up to this one,
+    // from its header
+    pub(crate) item_rlc: F,
+    // The content bytes of the item up to this one, accumulated in base 256
+    pub(crate) int_value: F,
+    // The gas paid for the bytes of the data up to this one
+    pub(crate) data_gas: u64,
+    pub(crate) access_list: AccessListRow,
+    // The gas paid for the entries of the access list up to this byte
+    pub(crate) access_list_gas: u64,
+}
+
+impl<F: FieldExt> PayloadRow<F> {
+    fn new(kind: RowKind) -> Self {
+        Self {
+            index: 0,
+            byte: 0,
+            kind,
+            is_single: false,
+            is_long: false,
+            item_idx: 0,
+            tag: None,
+            length: 0,
+            len_left: 0,
+            content_left: 0,
+            list_bytes_left: 0,
+            value: F::zero(),
+            item_rlc: F::zero(),
+            int_value: F::zero(),
+            data_gas: 0,
+            access_list: AccessListRow::default(),
+            access_list_gas: 0,
+        }
+    }
+
+    fn is_item(&self) -> bool {
+        matches!(
+            self.kind,
+            RowKind::ItemHeader | RowKind::ItemLength | RowKind::Content
+        )
+    }
+
+    // The last row of an item, which holds its value
+    fn is_item_end(&self) -> bool {
+        self.is_item() && self.len_left == 0 && self.content_left == 0
+    }
+}
+
+/// A signed tx unrolled into the payload rows of its payload, followed by the
+/// ones of the signed tx.
+#[derive(Clone, Debug)]
+pub(crate) struct UnrolledTx<F> {
+    pub(crate) tx: SignedTx,
+    pub(crate) sign_hash: F,
+    pub(crate) hash: F,
+    pub(crate) rows: Vec<PayloadRow<F>>,
+    pub(crate) signed_rows: Vec<PayloadRow<F>>,
+}
+
+impl<F: FieldExt> UnrolledTx<F> {
+    /// Returns the value of each field of the tx in the tx table, with the
+    /// length of its encoding in the payload.
+    pub(crate) fn table_fields(&self) -> Vec<(F, u64)> {
+        let item_end = |field: TxContextField| {
+            self.rows
+                .iter()
+                .find(|row| {
+                    row.is_item_end()
+                        && row.tag == Some(PayloadTag::Field(field))
+                })
+                .unwrap()
+        };
+        let item = |field: TxContextField| {
+            let row = item_end(field);
+            (row.value, row.length)
+        };
+
+        TX_FIELDS
+            .iter()
+            .map(|field| match field {
+                TxContextField::IsCreate => {
+                    let (_, length) = item(TxContextField::CalleeAddress);
+                    (F::from((length == 0) as u64), 0)
+                }
+                TxContextField::TxSignHash => (self.sign_hash, 0),
+                TxContextField::Origin => (address_value(&self.tx.sender), 0),
+                TxContextField::CallDataGasCost => (
+                    F::from(item_end(TxContextField::CallDataLength).data_gas),
+                    0,
+                ),
+                TxContextField::AccessListGasCost => {
+                    (F::from(self.rows.last().unwrap().access_list_gas), 0)
+                }
+                TxContextField::TxHash => (self.hash, 0),
+                field => item(*field),
+            })
+            .collect()
+    }
+
+    /// Returns the recovery id of the signature, and the random linear
+    /// combinations of its r and s.
+    pub(crate) fn signature(&self, r: F) -> [F; 3] {
+        [
+            F::from(self.tx.sig_v),
+            word_rlc(&self.tx.sig_r, r),
+            word_rlc(&self.tx.sig_s, r),
+        ]
+    }
+}
+
+// Returns the gas paid for a byte of the data of a tx.
+fn data_byte_gas(byte: u8) -> u64 {
+    match byte {
+        0 => GasCost::TX_DATA_ZERO.as_u64(),
+        _ => GasCost::TX_DATA_NON_ZERO.as_u64(),
+    }
+}
+
+fn word_rlc<F: FieldExt>(word: &Word, r: F) -> F {
+    let mut bytes = [0; 32];
+    word.to_big_endian(&mut bytes);
+    encode(bytes.iter().cloned(), r)
+}
+
+fn address_value<F: FieldExt>(address: &Address) -> F {
+    encode(address.0.iter().cloned(), F::from(256))
+}
+
+// Appends the rows of the RLP header at the next byte of `payload`, based on
+// `row`, and returns the number of content bytes which follow.
+fn push_header<F: FieldExt>(
+    rows: &mut Vec<PayloadRow<F>>,
+    payload: &[u8],
+    row: PayloadRow<F>,
+    length_kind: RowKind,
+) -> u64 {
+    let byte = payload[rows.len()];
+    let (kind, n) = header_kind(byte);
+    let is_single = kind == HeaderKind::Single;
+    let is_long = matches!(kind, HeaderKind::LongString | HeaderKind::LongList);
+    let mut length = match (is_single, is_long) {
+        (true, _) => 1,
+        (_, true) => 0,
+        _ => n,
+    };
+    rows.push(PayloadRow {
+        index: rows.len(),
+        byte,
+        is_single,
+        is_long,
+        length,
+        len_left: if is_long { n } else { 0 },
+        content_left: if is_single || is_long { 0 } else { length },
+        ..row.clone()
+    });
+
+    // The length of a long form follows in big-endian
+    if is_long {
+        for len_left in (0..n).rev() {
+            let byte = payload[rows.len()];
+            length = length * 256 + byte as u64;
+            rows.push(PayloadRow {
+                index: rows.len(),
+                byte,
+                kind: length_kind,
+                length,
+                len_left,
+                content_left: length,
+                ..row.clone()
+            });
+        }
+    }
+
+    if is_single {
+        0
+    } else {
+        length
+    }
+}
+
+// Decodes the list header of an entry of the access list, or of its storage
+// keys, at `rows[*idx]`, followed by the bytes of its long length, and returns
+// the length of the list.
+fn decode_list_header<F: FieldExt>(
+    rows: &mut [PayloadRow<F>],
+    idx: &mut usize,
+    kind: AccessListKind,
+    length_kind: AccessListKind,
+) -> u64 {
+    let (header, n) = header_kind(rows[*idx].byte);
+    let is_long = header == HeaderKind::LongList;
+    let mut length = if is_long { 0 } else { n };
+    rows[*idx].access_list = AccessListRow {
+        kind: Some(kind),
+        is_long,
+        length,
+        len_left: if is_long { n } else { 0 },
+        ..Default::default()
+    };
+    *idx += 1;
+
+    // The length of a long form follows in big-endian
+    if is_long {
+        for len_left in (0..n).rev() {
+            length = length * 256 + rows[*idx].byte as u64;
+            rows[*idx].access_list = AccessListRow {
+                kind: Some(length_kind),
+                length,
+                len_left,
+                ..Default::default()
+            };
+            *idx += 1;
+        }
+    }
+
+    length
+}
+
+// Decodes the header at `rows[*idx]` and the `length` bytes of an address or
+// of a storage key.
+fn decode_string<F: FieldExt>(
+    rows: &mut [PayloadRow<F>],
+    idx: &mut usize,
+    kind: AccessListKind,
+    length: u64,
+) {
+    for elem_left in (0..=length).rev() {
+        rows[*idx].access_list = AccessListRow {
+            kind: Some(kind),
+            elem_left,
+            ..Default::default()
+        };
+        *idx += 1;
+    }
+}
+
+// Decodes the entries of the access list from its content `rows`.
+fn decode_access_list<F: FieldExt>(rows: &mut [PayloadRow<F>]) {
+    let mut idx = 0;
+    while idx < rows.len() {
+        decode_list_header(
+            rows,
+            &mut idx,
+            AccessListKind::EntryHeader,
+            AccessListKind::EntryLength,
+        );
+        decode_string(rows, &mut idx, AccessListKind::Address, 20);
+        let keys_length = decode_list_header(
+            rows,
+            &mut idx,
+            AccessListKind::KeysHeader,
+            AccessListKind::KeysLength,
+        );
+        let keys_end = idx + keys_length as usize;
+        while idx < keys_end {
+            decode_string(rows, &mut idx, AccessListKind::Key, 32);
+        }
+    }
+
+    // The header of an entry sets the number of its bytes, and the header of
+    // its storage keys the number of their bytes, which are counted down to
+    // the end of the entry
+    let (mut entry_left, mut keys_left) = (0, 0);
+    for row in rows.iter_mut() {
+        let access_list = &mut row.access_list;
+        match access_list.kind.unwrap() {
+            AccessListKind::EntryHeader | AccessListKind::EntryLength => {
+                entry_left = access_list.length;
+            }
+            AccessListKind::Address => entry_left -= 1,
+            AccessListKind::KeysHeader | AccessListKind::KeysLength => {
+                entry_left -= 1;
+                keys_left = access_list.length;
+            }
+            AccessListKind::Key => {
+                entry_left -= 1;
+                keys_left -= 1;
+            }
+        }
+        access_list.entry_left = entry_left;
+        access_list.keys_left = keys_left;
+    }
+}
+
+/// Unrolls the payload of `tx` and the signed tx into their rows, decoding the
+/// value of each item.
+pub(crate) fn unroll<F: FieldExt>(tx: SignedTx, r: F) -> UnrolledTx<F> {
+    let (items, _) = payload_items(tx.tx_type);
+    let rows = unroll_rows(&tx.payload, tx.tx_type, items, r);
+    let signed_rows =
+        unroll_rows(&tx.raw, tx.tx_type, &signed_items(tx.tx_type), r);
+
+    UnrolledTx {
+        sign_hash: encode(tx.sign_hash().0.iter().cloned(), r),
+        hash: encode(tx.hash().0.iter().cloned(), r),
+        tx,
+        rows,
+        signed_rows,
+    }
+}
+
+// Unrolls `payload`, which is the RLP list of `items` preceded by the type of
+// a typed tx, into its rows.
+fn unroll_rows<F: FieldExt>(
+    payload: &[u8],
+    tx_type: u8,
+    items: &[PayloadTag],
+    r: F,
+) -> Vec<PayloadRow<F>> {
+    let mut rows = vec![];
+
+    if tx_type != LEGACY_TX {
+        rows.push(PayloadRow {
+            byte: payload[0],
+            ..PayloadRow::new(RowKind::TxType)
+        });
+    }
+    push_header(
+        &mut rows,
+        payload,
+        PayloadRow::new(RowKind::ListHeader),
+        RowKind::ListLength,
+    );
+
+    for (item_idx, tag) in items.iter().enumerate() {
+        if rows.len() == payload.len() {
+            break;
+        }
+        let (base, is_data, is_list, _) = tag.item_kind(r);
+        let row = PayloadRow {
+            item_idx,
+            tag: Some(*tag),
+            ..PayloadRow::new(RowKind::ItemHeader)
+        };
+
+        // A single byte is its own content
+        let header_idx = rows.len();
+        let content_length =
+            push_header(&mut rows, payload, row.clone(), RowKind::ItemLength);
+        let header = &mut rows[header_idx];
+        if header.is_single {
+            header.value = if is_data {
+                F::one()
+            } else {
+                F::from(header.byte as u64)
+            };
+            if is_data {
+                header.data_gas = data_byte_gas(header.byte);
+            }
+        }
+
+        let content_start = rows.len();
+        let mut value = F::zero();
+        let mut data_gas = 0;
+        for content_left in (0..content_length).rev() {
+            let byte = payload[rows.len()];
+            value = if is_data {
+                value + F::one()
+            } else {
+                value * base + F::from(byte as u64)
+            };
+            if is_data {
+                data_gas += data_byte_gas(byte);
+            }
+            rows.push(PayloadRow {
+                index: rows.len(),
+                byte,
+                kind: RowKind::Content,
+                length: content_length,
+                content_left,
+                value,
+                data_gas,
+                ..row.clone()
+            });
+        }
+        if is_list {
+            decode_access_list(&mut rows[content_start..]);
+        }
+    }
+
+    // The list header sets the number of bytes of its items, which are
+    // counted down to the end of the payload, and the gas paid for the access
+    // list is accumulated over the headers of its entries and storage keys.
+    // The bytes of each item are accumulated from its header into its random
+    // linear combination, and its content bytes in base 256.
+    let mut list_bytes_left = 0;
+    let mut access_list_gas = 0;
+    let mut item_rlc = F::zero();
+    let mut int_value = F::zero();
+    for row in rows.iter_mut() {
+        let byte = F::from(row.byte as u64);
+        match row.kind {
+            RowKind::ItemHeader => {
+                item_rlc = byte;
+                int_value = if row.is_single { byte } else { F::zero() };
+            }
+            RowKind::ItemLength => item_rlc = item_rlc * r + byte,
+            RowKind::Content => {
+                item_rlc = item_rlc * r + byte;
+                int_value = int_value * F::from(256) + byte;
+            }
+            _ => {
+                item_rlc = F::zero();
+                int_value = F::zero();
+            }
+        }
+        row.item_rlc = item_rlc;
+        row.int_value = int_value;
+
+        match row.kind {
+            RowKind::TxType => {}
+            RowKind::ListHeader | RowKind::ListLength => {
+                list_bytes_left = row.content_left;
+            }
+            _ => list_bytes_left -= 1,
+        }
+        row.list_bytes_left = list_bytes_left;
+        access_list_gas += match row.access_list.kind {
+            Some(AccessListKind::EntryHeader) => {
+                GasCost::TX_ACCESS_LIST_ADDRESS.as_u64()
+            }
+            Some(AccessListKind::Key) if row.access_list.elem_left == 32 => {
+                GasCost::TX_ACCESS_LIST_STORAGE_KEY.as_u64()
+            }
+            _ => 0,
+        };
+        row.access_list_gas = access_list_gas;
+    }
+
+    rows
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Config<F> {
+    r: F,
+    // The payload rows
+    q_enable: Column<Fixed>,
+    q_first: Column<Fixed>,
+    q_last: Column<Fixed>,
+    tx_id: Column<Advice>,
+    index: Column<Advice>,
+    byte: Column<Advice>,
+    tx_type: Column<Advice>,
+    is_signed: Column<Advice>,
+    v_offset: Column<Advice>,
+    is_final: Column<Advice>,
+    payload_rlc: Column<Advice>,
+    hash: Column<Advice>,
+    kinds: [Column<Advice>; NUM_ROW_KINDS],
+    is_single: Column<Advice>,
+    is_long: Column<Advice>,
+    item_idx: Column<Advice>,
+    tag: Column<Advice>,
+    base: Column<Advice>,
+    is_data: Column<Advice>,
+    is_list: Column<Advice>,
+    is_chain_id: Column<Advice>,
+    is_signature: Column<Advice>,
+    length: Column<Advice>,
+    len_left: Column<Advice>,
+    content_left: Column<Advice>,
+    list_bytes_left: Column<Advice>,
+    value: Column<Advice>,
+    item_rlc: Column<Advice>,
+    int_value: Column<Advice>,
+    data_gas: Column<Advice>,
+    is_item_end: Column<Advice>,
+    padding: Column<Advice>,
+    byte_is_zero: IsZeroConfig<F>,
+    len_left_is_zero: IsZeroConfig<F>,
+    content_left_is_zero: IsZeroConfig<F>,
+    list_bytes_left_is_zero: IsZeroConfig<F>,
+    // The decoding of the access list
+    access_list_kinds: [Column<Advice>; NUM_ACCESS_LIST_KINDS],
+    list_is_long: Column<Advice>,
+    list_length: Column<Advice>,
+    list_len_left: Column<Advice>,
+    elem_left: Column<Advice>,
+    entry_left: Column<Advice>,
+    keys_left: Column<Advice>,
+    access_list_gas: Column<Advice>,
+    list_len_left_is_zero: IsZeroConfig<F>,
+    elem_left_is_zero: IsZeroConfig<F>,
+    entry_left_is_zero: IsZeroConfig<F>,
+    keys_left_is_zero: IsZeroConfig<F>,
+    tx_calldata_table: [Column<Advice>; 3],
+    // The tx table rows
+    q_tx_start: Column<Fixed>,
+    tx_slot_id: Column<Fixed>,
+    tx_table: [Column<Advice>; 3],
+    tx_length: Column<Advice>,
+    tx_padding: Column<Advice>,
+    signature: [Column<Advice>; 3],
+    header_table: [Column<Fixed>; 3],
+    item_table: [Column<Fixed>; 9],
+    keccak_table: [Column<Advice>; 3],
+    block_table: [Column<Advice>; 3],
+    signature_table: [Column<Advice>; 5],
+}
+
+impl<F: FieldExt> Config<F> {
+    /// Configures the constraints proving the content of `tx_table`, whose
+    /// columns are the tx id, the tag of the field and its value, and of
+    /// `tx_calldata_table`, whose columns are the tx id, the index of the
+    /// byte in the calldata and the byte.  The hashes of the payloads and of
+    /// the signed txs are looked up in `keccak_table`, and their chain ids
+    /// and the hashes of the txs in `block_table`.
+    pub(crate) fn configure(
+        meta: &mut ConstraintSystem<F>,
+        r: F,
+        tx_table: [Column<Advice>; 3],
+        tx_calldata_table: [Column<Advice>; 3],
+        keccak_table: [Column<Advice>; 3],
+        block_table: [Column<Advice>; 3],
+    ) -> Self {
+        let q_enable = meta.fixed_column();
+        let q_first = meta.fixed_column();
+        let q_last = meta.fixed_column();
+        let tx_id = meta.advice_column();
+        let index = meta.advice_column();
+        let byte = meta.advice_column();
+        let tx_type = meta.advice_column();
+        let is_signed = meta.advice_column();
+        let v_offset = meta.advice_column();
+        let is_final = meta.advice_column();
+        let payload_rlc = meta.advice_column();
+        let hash = meta.advice_column();
+        let kinds = [(); NUM_ROW_KINDS].map(|_| meta.advice_column());
+        let is_single = meta.advice_column();
+        let is_long = meta.advice_column();
+        let item_idx = meta.advice_column();
+        let tag = meta.advice_column();
+        let base = meta.advice_column();
+        let is_data = meta.advice_column();
+        let is_list = meta.advice_column();
+        let is_chain_id = meta.advice_column();
+        let is_signature = meta.advice_column();
+        let length = meta.advice_column();
+        let len_left = meta.advice_column();
+        let len_left_inv = meta.advice_column();
+        let content_left = meta.advice_column();
+        let content_left_inv = meta.advice_column();
+        let list_bytes_left = meta.advice_column();
+        let list_bytes_left_inv = meta.advice_column();
+        let value = meta.advice_column();
+        let item_rlc = meta.advice_column();
+        let int_value = meta.advice_column();
+        let data_gas = meta.advice_column();
+        let byte_inv = meta.advice_column();
+        let is_item_end = meta.advice_column();
+        let padding = meta.advice_column();
+
+        let access_list_kinds =
+            [(); NUM_ACCESS_LIST_KINDS].map(|_| meta.advice_column());
+        let list_is_long = meta.advice_column();
+        let list_length = meta.advice_column();
+        let list_len_left = meta.advice_column();
+        let list_len_left_inv = meta.advice_column();
+        let elem_left = meta.advice_column();
+        let elem_left_inv = meta.advice_column();
+        let entry_left = meta.advice_column();
+        let entry_left_inv = meta.advice_column();
+        let keys_left = meta.advice_column();
+        let keys_left_inv = meta.advice_column();
+        let access_list_gas = meta.advice_column();
+
+        let q_tx_start = meta.fixed_column();
+        let tx_slot_id = meta.fixed_column();
+        let tx_length = meta.advice_column();
+        let tx_padding = meta.advice_column();
+        let signature = [
+            meta.advice_column(), // recovery id
+            meta.advice_column(), // random linear combination of r
+            meta.advice_column(), // random linear combination of s
+        ];
+
+        let header_table = [
+            meta.fixed_column(), // byte
+            meta.fixed_column(), // kind of the header
+            meta.fixed_column(), // length, or number of bytes of the length
+        ];
+        let item_table = [
+            meta.fixed_column(), // tx type
+            meta.fixed_column(), // is_signed
+            meta.fixed_column(), // index of the item in the payload
+            meta.fixed_column(), // tag
+            meta.fixed_column(), // base of the value
+            meta.fixed_column(), // is_data
+            meta.fixed_column(), // is_list
+            meta.fixed_column(), // is_chain_id
+            meta.fixed_column(), // is_signature
+        ];
+
+        // signature_table is a stub like the keccak table: its rows are
+        // witnessed by the prover from the recovered senders, and nothing
+        // proves that the signer of a hash is the address recovered from the
+        // signature, since there is no ECDSA circuit.  Until then the origins
+        // of the txs are unsound.
+        // TODO: signature_table should be proven by an ECDSA circuit
+        let signature_table = [
+            meta.advice_column(), // random linear combination of the hash
+            meta.advice_column(), // recovery id
+            meta.advice_column(), // random linear combination of r
+            meta.advice_column(), // random linear combination of s
+            meta.advice_column(), // address of the signer
+        ];
+
+        let mut is_zero = |value, inv| {
+            IsZeroChip::configure(
+                meta,
+                |meta| meta.query_fixed(q_enable, Rotation::cur()),
+                |meta| meta.query_advice(value, Rotation::cur()),
+                inv,
+            )
+        };
+        let byte_is_zero = is_zero(byte, byte_inv);
+        let len_left_is_zero = is_zero(len_left, len_left_inv);
+        let content_left_is_zero = is_zero(content_left, content_left_inv);
+        let list_bytes_left_is_zero =
+            is_zero(list_bytes_left, list_bytes_left_inv);
+        let list_len_left_is_zero = is_zero(list_len_left, list_len_left_inv);
+        let elem_left_is_zero = is_zero(elem_left, elem_left_inv);
+        let entry_left_is_zero = is_zero(entry_left, entry_left_inv);
+        let keys_left_is_zero = is_zero(keys_left, keys_left_inv);
+        let len_left_zero = len_left_is_zero.is_zero_expression.clone();
+        let content_left_zero = content_left_is_zero.is_zero_expression.clone();
+        let list_bytes_left_zero =
+            list_bytes_left_is_zero.is_zero_expression.clone();
+        // The gas paid for the current byte when it's a byte of the data
+        let byte_gas =
+            Expression::Constant(F::from(GasCost::TX_DATA_NON_ZERO.as_u64()))
+                - Expression::Constant(F::from(
+                    GasCost::TX_DATA_NON_ZERO.as_u64()
+                        - GasCost::TX_DATA_ZERO.as_u64(),
+                )) * byte_is_zero.is_zero_expression.clone();
+
+        meta.create_gate("payload rows", |meta| {
+            let one = Expression::Constant(F::one());
+            let r = Expression::Constant(r);
+
+            let q_enable = meta.query_fixed(q_enable, Rotation::cur());
+            let q_first = meta.query_fixed(q_first, Rotation::cur());
+            let q_last = meta.query_fixed(q_last, Rotation::cur());
+
+            let mut query = |column, at| meta.query_advice(column, at);
+            let cur = Rotation::cur();
+            let next = Rotation::next();
+
+            let [tx_id_prev, tx_type_prev, is_signed_prev, v_offset_prev] =
+                [tx_id, tx_type, is_signed, v_offset]
+                    .map(|column| query(column, Rotation::prev()));
+            let is_final_prev = query(is_final, Rotation::prev());
+            let [
+                is_type,
+                is_list_header,
+                is_list_len,
+                is_item_header,
+                is_item_len,
+                is_content,
+            ] = kinds.map(|column| query(column, cur));
+            let [
+                _,
+                is_list_header_next,
+                is_list_len_next,
+                is_item_header_next,
+                is_item_len_next,
+                is_content_next,
+            ] = kinds.map(|column| query(column, next));
+            let [tx_id, index, byte, tx_type, is_final, payload_rlc] =
+                [tx_id, index, byte, tx_type, is_final, payload_rlc]
+                    .map(|column| (query(column, cur), query(column, next)));
+            let [is_signed, v_offset, item_rlc, int_value] =
+                [is_signed, v_offset, item_rlc, int_value]
+                    .map(|column| (query(column, cur), query(column, next)));
+            let [item_idx, tag, base, is_data, is_list, is_chain_id] =
+                [item_idx, tag, base, is_data, is_list, is_chain_id]
+                    .map(|column| (query(column, cur), query(column, next)));
+            let is_signature = (
+                query(is_signature, cur),
+                query(is_signature, next),
+            );
+            let [length, len_left, content_left, list_bytes_left, value] =
+                [length, len_left, content_left, list_bytes_left, value]
+                    .map(|column| (query(column, cur), query(column, next)));
+            let is_single = query(is_single, cur);
+            let is_long = query(is_long, cur);
+            let is_item_end = query(is_item_end, cur);
+            let padding_cur = query(padding, cur);
+            let padding_next = query(padding, next);
+            let [tx_calldata_id, tx_calldata_index, tx_calldata_byte] =
+                tx_calldata_table.map(|column| query(column, cur));
+
+            // Rows holding a byte of a payload or of a signed tx, and the ones
+            // among them which are followed by another byte of the same one
+            let q_row = q_enable.clone() * (one.clone() - padding_cur.clone());
+            let q_next = q_row.clone()
+                * (one.clone() - q_last.clone())
+                * (one.clone() - is_final.0.clone());
+            // A payload or a signed tx starts on the first row, or after the
+            // last byte of another one
+            let is_start = q_first.clone()
+                + (one.clone() - q_first.clone()) * is_final_prev;
+            let list_part = is_list_header.clone() + is_list_len.clone();
+            let item_header_part = is_item_header.clone() + is_item_len.clone();
+            let item_part = item_header_part.clone() + is_content.clone();
+            let q_header = q_row.clone()
+                * (is_list_header.clone() + is_item_header.clone());
+
+            let mut constraints = vec![];
+
+            for value in vec![
+                padding_cur.clone(),
+                is_signed.0.clone(),
+                is_final.0.clone(),
+                is_item_end.clone(),
+                is_single.clone(),
+                is_long.clone(),
+            ]
+            .into_iter()
+            .chain(kinds.map(|column| query(column, cur)))
+            {
+                constraints.push(
+                    q_enable.clone() * value.clone() * (one.clone() - value),
+                );
+            }
+
+            // Padding rows are only followed by padding rows, and the last
+            // row can't be in the middle of a payload.  Each payload is
+            // followed by its signed tx, so that the last row and the padding
+            // rows can only follow a signed tx.
+            constraints.push(
+                q_enable.clone()
+                    * (one.clone() - q_last.clone())
+                    * padding_cur.clone()
+                    * (one.clone() - padding_next.clone()),
+            );
+            constraints.push(
+                q_row.clone()
+                    * q_last.clone()
+                    * (one.clone() - is_final.0.clone()),
+            );
+            constraints.push(
+                q_row.clone()
+                    * is_final.0.clone()
+                    * (one.clone() - is_signed.0.clone())
+                    * (q_last.clone()
+                        + (one.clone() - q_last) * padding_next.clone()),
+            );
+
+            // Each byte has a single kind
+            constraints.push(
+                q_row.clone()
+                    * (is_type.clone()
+                        + list_part.clone()
+                        + item_part.clone()
+                        - one.clone()),
+            );
+
+            // A payload starts at index 0 with the type of a typed tx, or with
+            // the list header of a legacy tx, and takes the next tx id.  It's
+            // followed by the signed tx, which keeps its tx id, its type and
+            // its offset of v.
+            let q_start = q_row.clone() * is_start;
+            constraints.push(q_start.clone() * index.0.clone());
+            constraints.push(
+                q_start.clone()
+                    * (one.clone() - is_type.clone() - is_list_header.clone()),
+            );
+            constraints.push(
+                q_start.clone()
+                    * (is_signed.0.clone()
+                        - (one.clone() - q_first.clone())
+                            * (one.clone() - is_signed_prev)),
+            );
+            constraints.push(
+                q_start.clone()
+                    * (tx_id.0.clone()
+                        - (one.clone() - q_first) * tx_id_prev
+                        - (one.clone() - is_signed.0.clone())),
+            );
+            for (cur, prev) in [
+                (tx_type.0.clone(), tx_type_prev),
+                (v_offset.0.clone(), v_offset_prev),
+            ] {
+                constraints.push(
+                    q_start.clone() * is_signed.0.clone() * (cur - prev),
+                );
+            }
+            constraints.push(
+                q_start.clone() * (payload_rlc.0.clone() - byte.0.clone()),
+            );
+            constraints
+                .push(q_start * is_list_header.clone() * tx_type.0.clone());
+            constraints.push(
+                q_row.clone()
+                    * is_type.clone()
+                    * (tx_type.0.clone() - byte.0.clone()),
+            );
+            constraints.push(
+                q_row.clone()
+                    * is_type.clone()
+                    * (byte.0.clone() - Expression::Constant(F::from(1)))
+                    * (byte.0.clone() - Expression::Constant(F::from(2))),
+            );
+
+            // The next byte of the same payload is accumulated into the input
+            // of its hash
+            constraints.push(q_next.clone() * padding_next);
+            for (cur, next) in [
+                tx_id.clone(),
+                tx_type.clone(),
+                is_signed.clone(),
+                v_offset.clone(),
+            ] {
+                constraints.push(q_next.clone() * (next - cur));
+            }
+            constraints.push(
+                q_next.clone()
+                    * (index.1.clone() - index.0.clone() - one.clone()),
+            );
+            constraints.push(
+                q_next.clone()
+                    * (payload_rlc.1.clone()
+                        - payload_rlc.0.clone() * r.clone()
+                        - byte.1.clone()),
+            );
+
+            // The v of the signature is the recovery id plus 27 for a legacy
+            // tx, or plus 35 and twice its chain id when the chain id is in
+            // the payload (EIP-155), and the recovery id for a typed tx
+            let is_legacy = (one.clone() - tx_type.0.clone())
+                * (Expression::Constant(F::from(2)) - tx_type.0.clone());
+            let q_legacy_payload = q_row.clone()
+                * is_legacy
+                * (one.clone() - is_signed.0.clone());
+            constraints.push(
+                q_row.clone() * tx_type.0.clone() * v_offset.0.clone(),
+            );
+            let eip155_v = Expression::Constant(F::from(35))
+                + Expression::Constant(F::from(2)) * int_value.0.clone();
+            constraints.push(
+                q_legacy_payload.clone()
+                    * is_chain_id.0.clone()
+                    * is_item_end.clone()
+                    * (v_offset.0.clone() - eip155_v),
+            );
+            constraints.push(
+                q_legacy_payload
+                    * is_final.0.clone()
+                    * (item_idx.0.clone()
+                        - Expression::Constant(F::from(
+                            LEGACY_ITEMS.len() as u64 - 1,
+                        )))
+                    * (v_offset.0.clone() - Expression::Constant(F::from(27))),
+            );
+
+            // The kind of the next byte follows from the current one: the
+            // bytes of a long length follow the header, the items follow the
+            // list header, and the content bytes follow the item header until
+            // the end of the item
+            constraints.push(
+                q_next.clone()
+                    * is_type.clone()
+                    * (one.clone() - is_list_header_next),
+            );
+            constraints.push(
+                q_next.clone()
+                    * list_part.clone()
+                    * (one.clone() - len_left_zero.clone())
+                    * (one.clone() - is_list_len_next.clone()),
+            );
+            constraints.push(
+                q_next.clone()
+                    * item_header_part.clone()
+                    * (one.clone() - len_left_zero.clone())
+                    * (one.clone() - is_item_len_next.clone()),
+            );
+            constraints.push(
+                q_next.clone()
+                    * list_part.clone()
+                    * len_left_zero.clone()
+                    * (one.clone() - is_item_header_next.clone()),
+            );
+            constraints.push(
+                q_next.clone()
+                    * (item_part.clone() * len_left_zero.clone()
+                        - is_item_end.clone())
+                    * (one.clone() - is_content_next.clone()),
+            );
+            constraints.push(
+                q_next.clone()
+                    * is_item_end.clone()
+                    * (one.clone() - is_item_header_next.clone()),
+            );
+            constraints.push(
+                q_row.clone()
+                    * (is_item_end.clone()
+                        - item_part.clone()
+                            * len_left_zero.clone()
+                            * content_left_zero.clone()),
+            );
+
+            // A header byte sets the length of a short form, the number of
+            // bytes of the length of a long form, and the value of a single
+            // byte item, which has a length of 1
+            constraints
+                .push(q_row.clone() * is_single.clone() * is_long.clone());
+            constraints.push(
+                q_row.clone() * is_list_header.clone() * is_single.clone(),
+            );
+            constraints.push(
+                q_row.clone()
+                    * is_item_header.clone()
+                    * is_list.0.clone()
+                    * is_single.clone(),
+            );
+            constraints
+                .push(q_header.clone() * is_long.clone() * length.0.clone());
+            constraints.push(
+                q_header.clone()
+                    * is_single.clone()
+                    * (length.0.clone() - one.clone()),
+            );
+            constraints.push(
+                q_header.clone()
+                    * (one.clone() - is_long.clone())
+                    * len_left.0.clone(),
+            );
+            constraints.push(
+                q_header
+                    * (content_left.0.clone()
+                        - (one.clone() - is_long - is_single.clone())
+                            * length.0.clone()),
+            );
+            constraints.push(
+                q_row.clone()
+                    * is_item_header.clone()
+                    * (value.0.clone()
+                        - is_single.clone()
+                            * (is_data.0.clone()
+                                + (one.clone() - is_data.0.clone())
+                                    * byte.0.clone())),
+            );
+
+            // The bytes of a long length are accumulated in big-endian, and
+            // the last one sets the length of the content
+            let is_len_next = is_list_len_next + is_item_len_next.clone();
+            constraints.push(
+                q_next.clone()
+                    * is_len_next.clone()
+                    * (length.1.clone()
+                        - length.0.clone() * Expression::Constant(F::from(256))
+                        - byte.1.clone()),
+            );
+            constraints.push(
+                q_next.clone()
+                    * is_len_next
+                    * (len_left.1.clone() - len_left.0.clone() + one.clone()),
+            );
+            constraints.push(
+                q_row.clone()
+                    * (is_list_len + is_item_len.clone())
+                    * (content_left.0.clone() - length.0.clone()),
+            );
+            constraints
+                .push(q_row.clone() * is_item_len.clone() * value.0.clone());
+
+            // Each content byte decreases the number of bytes left in the
+            // item, and is accumulated into its value, which counts the bytes
+            // of the data instead
+            constraints
+                .push(q_row.clone() * is_content.clone() * len_left.0.clone());
+            constraints.push(
+                q_next.clone()
+                    * is_content_next.clone()
+                    * (content_left.1.clone() - content_left.0.clone()
+                        + one.clone()),
+            );
+            constraints.push(
+                q_next.clone()
+                    * is_content_next.clone()
+                    * (length.1.clone() - length.0.clone()),
+            );
+            constraints.push(
+                q_next.clone()
+                    * is_content_next.clone()
+                    * (value.1.clone()
+                        - is_data.0.clone() * (value.0.clone() + one.clone())
+                        - (one.clone() - is_data.0.clone())
+                            * (value.0.clone() * base.0.clone()
+                                + byte.1.clone())),
+            );
+
+            // The bytes of an item are accumulated from its header into its
+            // random linear combination, and its content bytes in base 256
+            // into its integer value, which gives the chain id of a legacy tx
+            constraints.push(
+                q_row.clone()
+                    * is_item_header.clone()
+                    * (item_rlc.0.clone() - byte.0.clone()),
+            );
+            constraints.push(
+                q_next.clone()
+                    * (is_item_len_next.clone() + is_content_next.clone())
+                    * (item_rlc.1 - item_rlc.0 * r - byte.1.clone()),
+            );
+            constraints.push(
+                q_row.clone()
+                    * is_item_header.clone()
+                    * (int_value.0.clone()
+                        - is_single.clone() * byte.0.clone()),
+            );
+            constraints.push(
+                q_row.clone() * is_item_len.clone() * int_value.0.clone(),
+            );
+            constraints.push(
+                q_next.clone()
+                    * is_content_next.clone()
+                    * (int_value.1
+                        - int_value.0 * Expression::Constant(F::from(256))
+                        - byte.1.clone()),
+            );
+
+            // The bytes of an item share its index and its kind, and the index
+            // of the next item is increased
+            for (cur, next) in [
+                item_idx.clone(),
+                tag,
+                base.clone(),
+                is_data.clone(),
+                is_list,
+                is_chain_id,
+                is_signature,
+            ] {
+                constraints.push(
+                    q_next.clone()
+                        * (is_item_len_next.clone() + is_content_next.clone())
+                        * (next - cur),
+                );
+            }
+            constraints.push(
+                q_next.clone()
+                    * is_item_header_next.clone()
+                    * (item_idx.1
+                        - (one.clone() - list_part.clone())
+                            * (item_idx.0 + one.clone())),
+            );
+
+            // The list header sets the number of bytes of its items, which
+            // are counted down to the last byte of the payload
+            constraints.push(
+                q_row.clone()
+                    * list_part
+                    * (list_bytes_left.0.clone() - content_left.0.clone()),
+            );
+            constraints.push(
+                q_next
+                    * (is_item_len_next + is_content_next + is_item_header_next)
+                    * (list_bytes_left.1 - list_bytes_left.0 + one.clone()),
+            );
+            constraints.push(
+                q_row.clone()
+                    * (is_final.0.clone()
+                        - item_part * list_bytes_left_zero.clone()),
+            );
+            constraints.push(
+                q_row.clone() * is_final.0 * (one.clone() - is_item_end),
+            );
+
+            // The gas paid for the data is accumulated over its content bytes,
+            // where a single byte is its own content
+            let data_gas_prev = query(data_gas, Rotation::prev());
+            let data_gas = query(data_gas, cur);
+            constraints.push(
+                q_row.clone()
+                    * is_item_header.clone()
+                    * (data_gas.clone()
+                        - is_data.0.clone()
+                            * is_single.clone()
+                            * byte_gas.clone()),
+            );
+            constraints
+                .push(q_row.clone() * is_item_len * data_gas.clone());
+            constraints.push(
+                q_row.clone()
+                    * is_content.clone()
+                    * (data_gas
+                        - data_gas_prev
+                        - is_data.0.clone() * byte_gas.clone()),
+            );
+
+            // The content bytes of the data of the payload are the calldata of
+            // the tx
+            let q_calldata = (one.clone() - padding_cur)
+                * (one.clone() - is_signed.0)
+                * is_data.0
+                * (is_content + is_item_header * is_single);
+            for (column, value) in [
+                (tx_calldata_id, tx_id.0),
+                (tx_calldata_index, length.0 - content_left.0 - one.clone()),
+                (tx_calldata_byte, byte.0),
+            ] {
+                constraints.push(
+                    q_enable.clone() * (column - q_calldata.clone() * value),
+                );
+            }
+
+            constraints
+        });
+
+        // A header byte has the kind and the length given by the header
+        // table, where the header of the access list is the one of a list
+        meta.lookup_any(|meta| {
+            let one = Expression::Constant(F::one());
+            let mut query = |column| meta.query_advice(column, Rotation::cur());
+            let q_row = one.clone() - query(padding);
+            let is_list_header = query(kinds[RowKind::ListHeader as usize]);
+            let is_item_header = query(kinds[RowKind::ItemHeader as usize]);
+            let is_single = query(is_single);
+            let is_long = query(is_long);
+            let is_list_form = is_list_header.clone()
+                + is_item_header.clone() * query(is_list);
+            let kind = is_single.clone()
+                + (one.clone() - is_single.clone())
+                    * (Expression::Constant(F::from(2))
+                        + is_long.clone()
+                        + Expression::Constant(F::from(2)) * is_list_form);
+            let n = (one.clone() - is_single)
+                * (is_long.clone() * query(len_left)
+                    + (one - is_long) * query(length));
+            let q_header = meta.query_fixed(q_enable, Rotation::cur())
+                * q_row
+                * (is_list_header + is_item_header);
+
+            vec![meta.query_advice(byte, Rotation::cur()), kind, n]
+                .into_iter()
+                .zip(header_table)
+                .map(|(expr, column)| {
+                    (
+                        q_header.clone() * expr,
+                        meta.query_fixed(column, Rotation::cur()),
+                    )
+                })
+                .collect::<Vec<_>>()
+        });
+
+        // The items of the payload and of the signed tx have the tags and the
+        // kinds given by the item table, and a complete payload or signed tx
+        // has one of the numbers of items of its tx type
+        for is_end in [false, true] {
+            meta.lookup_any(|meta| {
+                let one = Expression::Constant(F::one());
+                let mut query =
+                    |column| meta.query_advice(column, Rotation::cur());
+                let q_row = one.clone() - query(padding);
+                let item_idx = query(item_idx);
+                let (selector, values) = if is_end {
+                    (
+                        query(is_final),
+                        vec![
+                            query(tx_type),
+                            query(is_signed),
+                            item_idx + one,
+                            Expression::Constant(F::from(
+                                PayloadTag::End.value(),
+                            )),
+                            Expression::Constant(F::zero()),
+                            Expression::Constant(F::zero()),
+                            Expression::Constant(F::zero()),
+                            Expression::Constant(F::zero()),
+                            Expression::Constant(F::zero()),
+                        ],
+                    )
+                } else {
+                    (
+                        query(kinds[RowKind::ItemHeader as usize]),
+                        vec![
+                            query(tx_type),
+                            query(is_signed),
+                            item_idx,
+                            query(tag),
+                            query(base),
+                            query(is_data),
+                            query(is_list),
+                            query(is_chain_id),
+                            query(is_signature),
+                        ],
+                    )
+                };
+                let q_lookup = meta.query_fixed(q_enable, Rotation::cur())
+                    * q_row
+                    * selector;
+
+                values
+                    .into_iter()
+                    .zip(item_table)
+                    .map(|(expr, column)| {
+                        (
+                            q_lookup.clone() * expr,
+                            meta.query_fixed(column, Rotation::cur()),
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            });
+        }
+
+        meta.create_gate("access list rows", |meta| {
+            let one = Expression::Constant(F::one());
+
+            let q_enable = meta.query_fixed(q_enable, Rotation::cur());
+            let q_first = meta.query_fixed(q_first, Rotation::cur());
+            let q_last = meta.query_fixed(q_last, Rotation::cur());
+
+            let mut query = |column, at| meta.query_advice(column, at);
+            let cur = Rotation::cur();
+            let next = Rotation::next();
+
+            let is_final_prev = query(is_final, Rotation::prev());
+            let padding = query(padding, cur);
+            let is_final = query(is_final, cur);
+            let is_content = query(kinds[RowKind::Content as usize], cur);
+            let is_list = query(is_list, cur);
+            let is_item_end = query(is_item_end, cur);
+            let byte_next = query(byte, next);
+            let [
+                entry_header,
+                entry_len,
+                address,
+                keys_header,
+                keys_len,
+                key,
+            ] = access_list_kinds
+                .map(|column| (query(column, cur), query(column, next)));
+            let [
+                list_is_long,
+                list_length,
+                list_len_left,
+                elem_left,
+                entry_left,
+                keys_left,
+                access_list_gas,
+            ] = [
+                list_is_long,
+                list_length,
+                list_len_left,
+                elem_left,
+                entry_left,
+                keys_left,
+                access_list_gas,
+            ]
+            .map(|column| (query(column, cur), query(column, next)));
+            let list_len_left_zero =
+                list_len_left_is_zero.is_zero_expression.clone();
+            let elem_left_zero = elem_left_is_zero.is_zero_expression.clone();
+            let entry_left_zero = entry_left_is_zero.is_zero_expression.clone();
+            let keys_left_zero = keys_left_is_zero.is_zero_expression.clone();
+
+            let q_row = q_enable.clone() * (one.clone() - padding.clone());
+            let q_next = q_row.clone()
+                * (one.clone() - q_last.clone())
+                * (one.clone() - is_final);
+            let is_start = q_first.clone()
+                + (one.clone() - q_first) * is_final_prev;
+            let all_kinds = [
+                &entry_header,
+                &entry_len,
+                &address,
+                &keys_header,
+                &keys_len,
+                &key,
+            ];
+            // The content bytes of the access list, whose transitions to the
+            // next byte of the access list are constrained
+            let is_entry = all_kinds.iter().fold(
+                Expression::Constant(F::zero()),
+                |sum, kind| sum + kind.0.clone(),
+            );
+            let is_entry_next = all_kinds.iter().fold(
+                Expression::Constant(F::zero()),
+                |sum, kind| sum + kind.1.clone(),
+            );
+            let q_trans =
+                q_enable.clone() * (one.clone() - q_last) * is_entry_next;
+            let list_part = entry_header.0.clone() + entry_len.0.clone();
+            let keys_part = keys_header.0.clone() + keys_len.0.clone();
+            // The bytes of an address or of a storage key continue it until
+            // its last one, and the storage keys end with the last byte of
+            // their header or of the last storage key
+            let address_cont =
+                address.0.clone() * (one.clone() - elem_left_zero.clone());
+            let key_cont =
+                key.0.clone() * (one.clone() - elem_left_zero.clone());
+            let is_elem_cont = address_cont.clone() + key_cont.clone();
+            let is_keys_end = keys_part.clone() * list_len_left_zero.clone()
+                + key.0.clone() * elem_left_zero.clone();
+
+            let mut constraints = vec![];
+
+            for value in iter::once(list_is_long.0.clone())
+                .chain(all_kinds.iter().map(|kind| kind.0.clone()))
+            {
+                constraints.push(
+                    q_enable.clone() * value.clone() * (one.clone() - value),
+                );
+            }
+
+            // The content bytes of the access list, and only them, have a
+            // single kind
+            constraints.push(
+                q_enable.clone()
+                    * (is_entry.clone()
+                        - (one.clone() - padding) * is_content * is_list),
+            );
+
+            // The kind of the next byte follows from the current one: an entry
+            // is the header of the list, the address and the header of the
+            // list of storage keys, each followed by the bytes of their long
+            // length, and then the storage keys
+            for (condition, kind_next) in [
+                (one.clone() - is_entry.clone(), &entry_header),
+                (list_part.clone() * list_len_left_zero.clone(), &address),
+                (
+                    list_part.clone()
+                        * (one.clone() - list_len_left_zero.clone()),
+                    &entry_len,
+                ),
+                (address.0.clone() * elem_left_zero.clone(), &keys_header),
+                (
+                    keys_part.clone()
+                        * (one.clone() - list_len_left_zero.clone()),
+                    &keys_len,
+                ),
+                (address_cont, &address),
+                (key_cont, &key),
+                (
+                    is_keys_end.clone() * keys_left_zero.clone(),
+                    &entry_header,
+                ),
+                (
+                    is_keys_end.clone()
+                        * (one.clone() - keys_left_zero.clone()),
+                    &key,
+                ),
+            ] {
+                constraints.push(
+                    q_trans.clone()
+                        * condition
+                        * (one.clone() - kind_next.1.clone()),
+                );
+            }
+
+            // A header sets the length of a short list, or the number of bytes
+            // of the length of a long list, which are accumulated in
+            // big-endian
+            let is_header = entry_header.0.clone() + keys_header.0.clone();
+            constraints.push(
+                q_enable.clone()
+                    * is_header.clone()
+                    * list_is_long.0.clone()
+                    * list_length.0.clone(),
+            );
+            constraints.push(
+                q_enable.clone()
+                    * is_header
+                    * (one.clone() - list_is_long.0)
+                    * list_len_left.0.clone(),
+            );
+            let is_len_next = entry_len.1.clone() + keys_len.1.clone();
+            constraints.push(
+                q_trans.clone()
+                    * is_len_next.clone()
+                    * (list_length.1.clone()
+                        - list_length.0 * Expression::Constant(F::from(256))
+                        - byte_next.clone()),
+            );
+            constraints.push(
+                q_trans.clone()
+                    * is_len_next
+                    * (list_len_left.1 - list_len_left.0 + one.clone()),
+            );
+
+            // The length of an entry is the number of its bytes left, which
+            // are counted down to the end of the entry, and the length of its
+            // storage keys the number of their bytes left
+            constraints.push(
+                q_trans.clone()
+                    * (entry_header.1.clone() + entry_len.1.clone())
+                    * (entry_left.1.clone() - list_length.1.clone()),
+            );
+            constraints.push(
+                q_trans.clone()
+                    * entry_header.1.clone()
+                    * is_entry.clone()
+                    * entry_left.0.clone(),
+            );
+            let is_entry_byte_next = address.1.clone()
+                + keys_header.1.clone()
+                + keys_len.1.clone()
+                + key.1.clone();
+            constraints.push(
+                q_trans.clone()
+                    * is_entry_byte_next.clone()
+                    * (entry_left.1 - entry_left.0.clone() + one.clone()),
+            );
+            constraints.push(
+                q_trans.clone() * is_entry_byte_next * entry_left_zero.clone(),
+            );
+            constraints.push(
+                q_trans.clone()
+                    * (keys_header.1.clone() + keys_len.1.clone())
+                    * (keys_left.1.clone() - list_length.1),
+            );
+            constraints.push(
+                q_trans.clone()
+                    * key.1.clone()
+                    * (keys_left.1 - keys_left.0 + one.clone()),
+            );
+            constraints
+                .push(q_trans.clone() * key.1.clone() * keys_left_zero.clone());
+
+            // An address is its header 0x94 followed by its 20 bytes, and a
+            // storage key is its header 0xa0 followed by its 32 bytes
+            for (kind, header, length) in
+                [(&address, 0x94, 20), (&key, 0xa0, 32)]
+            {
+                constraints.push(
+                    q_trans.clone()
+                        * kind.1.clone()
+                        * is_elem_cont.clone()
+                        * (elem_left.1.clone() - elem_left.0.clone()
+                            + one.clone()),
+                );
+                for (value, expected) in
+                    [(elem_left.1.clone(), length), (byte_next.clone(), header)]
+                {
+                    constraints.push(
+                        q_trans.clone()
+                            * kind.1.clone()
+                            * (one.clone() - is_elem_cont.clone())
+                            * (value - Expression::Constant(F::from(expected))),
+                    );
+                }
+            }
+
+            // The access list ends with the last byte of an entry
+            let q_end = q_row.clone() * is_item_end * is_entry;
+            constraints.push(
+                q_end.clone()
+                    * (one.clone() - is_keys_end * keys_left_zero),
+            );
+            constraints.push(q_end * entry_left.0);
+
+            // The gas paid for the access list is accumulated over the headers
+            // of its entries and of their storage keys
+            constraints.push(q_row * is_start * access_list_gas.0.clone());
+            constraints.push(
+                q_next
+                    * (access_list_gas.1
+                        - access_list_gas.0
+                        - Expression::Constant(F::from(
+                            GasCost::TX_ACCESS_LIST_ADDRESS.as_u64(),
+                        )) * entry_header.1
+                        - Expression::Constant(F::from(
+                            GasCost::TX_ACCESS_LIST_STORAGE_KEY.as_u64(),
+                        )) * key.1
+                            * (one - is_elem_cont))
+            );
+
+            constraints
+        });
+
+        // A header of the access list has the kind and the length given by
+        // the header table, which are the ones of a list
+        meta.lookup_any(|meta| {
+            let mut query = |column| meta.query_advice(column, Rotation::cur());
+            let is_header =
+                query(access_list_kinds[AccessListKind::EntryHeader as usize])
+                    + query(
+                        access_list_kinds[AccessListKind::KeysHeader as usize],
+                    );
+            let kind =
+                Expression::Constant(F::from(HeaderKind::ShortList as u64))
+                    + query(list_is_long);
+            // The length of a long list is zero, and so is the number of bytes
+            // of the length of a short list
+            let n = query(list_len_left) + query(list_length);
+            let q_header =
+                meta.query_fixed(q_enable, Rotation::cur()) * is_header;
+
+            vec![meta.query_advice(byte, Rotation::cur()), kind, n]
+                .into_iter()
+                .zip(header_table)
+                .map(|(expr, column)| {
+                    (
+                        q_header.clone() * expr,
+                        meta.query_fixed(column, Rotation::cur()),
+                    )
+                })
+                .collect::<Vec<_>>()
+        });
+
+        // The chain id of the payload is the one of the block
+        meta.lookup_any(|meta| {
+            let q_chain_id = meta.query_fixed(q_enable, Rotation::cur())
+                * (Expression::Constant(F::one())
+                    - meta.query_advice(padding, Rotation::cur()))
+                * meta.query_advice(is_item_end, Rotation::cur())
+                * meta.query_advice(is_chain_id, Rotation::cur());
+
+            vec![
+                Expression::Constant(F::from(
+                    BlockContextField::ChainId as u64,
+                )),
+                Expression::Constant(F::zero()),
+                meta.query_advice(value, Rotation::cur()),
+            ]
+            .into_iter()
+            .zip(block_table)
+            .map(|(expr, column)| {
+                (
+                    q_chain_id.clone() * expr,
+                    meta.query_advice(column, Rotation::cur()),
+                )
+            })
+            .collect::<Vec<_>>()
+        });
+
+        // The fields of the signed tx are the items of its payload with the
+        // same tags, which have the same bytes
+        meta.lookup_any(|meta| {
+            let one = Expression::Constant(F::one());
+            let q_item_end = meta.query_fixed(q_enable, Rotation::cur())
+                * (one.clone() - meta.query_advice(padding, Rotation::cur()))
+                * meta.query_advice(is_item_end, Rotation::cur());
+            let is_signed = meta.query_advice(is_signed, Rotation::cur());
+            let q_field = q_item_end.clone()
+                * is_signed.clone()
+                * (one.clone()
+                    - meta.query_advice(is_signature, Rotation::cur()));
+            let q_payload_item = q_item_end * (one - is_signed);
+
+            [tx_id, tag, item_rlc]
+                .iter()
+                .map(|column| {
+                    let value = meta.query_advice(*column, Rotation::cur());
+                    (
+                        q_field.clone() * value.clone(),
+                        q_payload_item.clone() * value,
+                    )
+                })
+                .collect::<Vec<_>>()
+        });
+
+        // The sign hash is the keccak hash of the whole payload, and the hash
+        // of the tx the one of the whole signed tx
+        meta.lookup_any(|meta| {
+            let q_final = meta.query_fixed(q_enable, Rotation::cur())
+                * (Expression::Constant(F::one())
+                    - meta.query_advice(padding, Rotation::cur()))
+                * meta.query_advice(is_final, Rotation::cur());
+            let length = meta.query_advice(index, Rotation::cur())
+                + Expression::Constant(F::one());
+
+            vec![
+                meta.query_advice(payload_rlc, Rotation::cur()),
+                length,
+                meta.query_advice(hash, Rotation::cur()),
+            ]
+            .into_iter()
+            .zip(keccak_table.iter())
+            .map(|(expr, column)| {
+                (
+                    q_final.clone() * expr,
+                    meta.query_advice(*column, Rotation::cur()),
+                )
+            })
+            .collect::<Vec<_>>()
+        });
+
+        let field_rotation = |field| {
+            let idx = TX_FIELDS.iter().position(|f| *f == field).unwrap();
+            Rotation(idx as i32)
+        };
+
+        meta.create_gate("tx table rows", |meta| {
+            let one = Expression::Constant(F::one());
+
+            let q_tx_start = meta.query_fixed(q_tx_start, Rotation::cur());
+            let tx_slot_id = meta.query_fixed(tx_slot_id, Rotation::cur());
+            let padding = meta.query_advice(tx_padding, Rotation::cur());
+            let q_tx = q_tx_start.clone() * (one.clone() - padding.clone());
+            let [tx_id, tag, value] = tx_table;
+            let mut field_value = |field| {
+                (
+                    meta.query_advice(value, field_rotation(field)),
+                    meta.query_advice(tx_length, field_rotation(field)),
+                )
+            };
+            let (is_create, _) = field_value(TxContextField::IsCreate);
+            let (_, callee_address_length) =
+                field_value(TxContextField::CalleeAddress);
+            let (call_data_length, call_data_length_length) =
+                field_value(TxContextField::CallDataLength);
+
+            let mut constraints = vec![
+                q_tx_start.clone()
+                    * padding.clone()
+                    * (one.clone() - padding.clone()),
+            ];
+
+            // Each tx takes a row per field, and an unused tx slot is empty
+            for (idx, field) in TX_FIELDS.iter().enumerate() {
+                let at = Rotation(idx as i32);
+                constraints.push(
+                    q_tx_start.clone()
+                        * (meta.query_advice(tx_id, at)
+                            - (one.clone() - padding.clone())
+                                * tx_slot_id.clone()),
+                );
+                constraints.push(
+                    q_tx_start.clone()
+                        * (meta.query_advice(tag, at)
+                            - (one.clone() - padding.clone())
+                                * Expression::Constant(F::from(*field as u64))),
+                );
+                constraints.push(
+                    q_tx_start.clone()
+                        * padding.clone()
+                        * meta.query_advice(value, at),
+                );
+            }
+
+            // The callee address of a creation is empty, and the calldata
+            // length is the length of the data
+            constraints.push(
+                q_tx.clone()
+                    * is_create.clone()
+                    * (one.clone() - is_create.clone()),
+            );
+            constraints.push(
+                q_tx.clone()
+                    * (callee_address_length
+                        - Expression::Constant(F::from(20))
+                            * (one - is_create)),
+            );
+            constraints
+                .push(q_tx * (call_data_length_length - call_data_length));
+
+            constraints
+        });
+
+        // The fields encoded in the payload are the values of their items,
+        // with the length of their encoding
+        for field in PAYLOAD_FIELDS {
+            meta.lookup_any(|meta| {
+                let q_tx = meta.query_fixed(q_tx_start, Rotation::cur())
+                    * (Expression::Constant(F::one())
+                        - meta.query_advice(tx_padding, Rotation::cur()));
+                let q_item_end = meta.query_fixed(q_enable, Rotation::cur())
+                    * (Expression::Constant(F::one())
+                        - meta.query_advice(padding, Rotation::cur()))
+                    * meta.query_advice(is_item_end, Rotation::cur());
+                let at = field_rotation(field);
+
+                vec![
+                    (tx_table[0], tx_id),
+                    (tx_table[1], tag),
+                    (tx_table[2], value),
+                    (tx_length, length),
+                ]
+                .into_iter()
+                .map(|(input, table)| {
+                    (
+                        q_tx.clone() * meta.query_advice(input, at),
+                        q_item_end.clone()
+                            * meta.query_advice(table, Rotation::cur()),
+                    )
+                })
+                .collect::<Vec<_>>()
+            });
+        }
+
+        // The sign hash is the one of the payload of the tx, and the access
+        // list gas cost is the gas accumulated over its access list, while
+        // the hash of the tx is the one of the signed tx
+        for is_signed_tx in [false, true] {
+            meta.lookup_any(|meta| {
+                let one = Expression::Constant(F::one());
+                let q_tx = meta.query_fixed(q_tx_start, Rotation::cur())
+                    * (one.clone()
+                        - meta.query_advice(tx_padding, Rotation::cur()));
+                let is_signed = meta.query_advice(is_signed, Rotation::cur());
+                let q_final = meta.query_fixed(q_enable, Rotation::cur())
+                    * (one.clone()
+                        - meta.query_advice(padding, Rotation::cur()))
+                    * meta.query_advice(is_final, Rotation::cur())
+                    * if is_signed_tx {
+                        is_signed
+                    } else {
+                        one - is_signed
+                    };
+                let hash_at = field_rotation(if is_signed_tx {
+                    TxContextField::TxHash
+                } else {
+                    TxContextField::TxSignHash
+                });
+                let gas_at = field_rotation(TxContextField::AccessListGasCost);
+
+                let mut columns = vec![
+                    (tx_table[0], hash_at, tx_id),
+                    (tx_table[2], hash_at, hash),
+                ];
+                if !is_signed_tx {
+                    columns.push((tx_table[2], gas_at, access_list_gas));
+                }
+                columns
+                    .into_iter()
+                    .map(|(input, at, table)| {
+                        (
+                            q_tx.clone() * meta.query_advice(input, at),
+                            q_final.clone()
+                                * meta.query_advice(table, Rotation::cur()),
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            });
+        }
+
+        // The calldata gas cost is the gas accumulated over the data of the
+        // payload
+        meta.lookup_any(|meta| {
+            let one = Expression::Constant(F::one());
+            let q_tx = meta.query_fixed(q_tx_start, Rotation::cur())
+                * (one.clone()
+                    - meta.query_advice(tx_padding, Rotation::cur()));
+            let q_data_end = meta.query_fixed(q_enable, Rotation::cur())
+                * (one - meta.query_advice(padding, Rotation::cur()))
+                * meta.query_advice(is_item_end, Rotation::cur())
+                * meta.query_advice(is_data, Rotation::cur());
+            let at = field_rotation(TxContextField::CallDataGasCost);
+
+            vec![(tx_table[0], tx_id), (tx_table[2], data_gas)]
+                .into_iter()
+                .map(|(input, table)| {
+                    (
+                        q_tx.clone() * meta.query_advice(input, at),
+                        q_data_end.clone()
+                            * meta.query_advice(table, Rotation::cur()),
+                    )
+                })
+                .collect::<Vec<_>>()
+        });
+
+        // The signature of the tx is the one of the signed tx, whose v is the
+        // recovery id plus the offset of the tx
+        for (idx, item_tag) in SIGNATURE_ITEMS.iter().enumerate() {
+            meta.lookup_any(|meta| {
+                let one = Expression::Constant(F::one());
+                let q_tx = meta.query_fixed(q_tx_start, Rotation::cur())
+                    * (one.clone()
+                        - meta.query_advice(tx_padding, Rotation::cur()));
+                let q_signature = meta.query_fixed(q_enable, Rotation::cur())
+                    * (one - meta.query_advice(padding, Rotation::cur()))
+                    * meta.query_advice(is_item_end, Rotation::cur())
+                    * meta.query_advice(is_signed, Rotation::cur());
+                let origin_at = field_rotation(TxContextField::Origin);
+                let mut item_value = meta.query_advice(value, Rotation::cur());
+                if *item_tag == PayloadTag::SigV {
+                    item_value = item_value
+                        - meta.query_advice(v_offset, Rotation::cur());
+                }
+
+                vec![
+                    (
+                        meta.query_advice(tx_table[0], origin_at),
+                        meta.query_advice(tx_id, Rotation::cur()),
+                    ),
+                    (
+                        Expression::Constant(F::from(item_tag.value())),
+                        meta.query_advice(tag, Rotation::cur()),
+                    ),
+                    (meta.query_advice(signature[idx], origin_at), item_value),
+                ]
+                .into_iter()
+                .map(|(input, table)| {
+                    (q_tx.clone() * input, q_signature.clone() * table)
+                })
+                .collect::<Vec<_>>()
+            });
+        }
+
+        // The hash of each tx is the one committed to by the block table at
+        // the index of the tx
+        meta.lookup_any(|meta| {
+            let q_tx = meta.query_fixed(q_tx_start, Rotation::cur())
+                * (Expression::Constant(F::one())
+                    - meta.query_advice(tx_padding, Rotation::cur()));
+            let hash_at = field_rotation(TxContextField::TxHash);
+
+            vec![
+                Expression::Constant(F::from(BlockTag::TxHash.value())),
+                meta.query_advice(tx_table[0], hash_at),
+                meta.query_advice(tx_table[2], hash_at),
+            ]
+            .into_iter()
+            .zip(block_table)
+            .map(|(expr, column)| {
+                (
+                    q_tx.clone() * expr,
+                    meta.query_advice(column, Rotation::cur()),
+                )
+            })
+            .collect::<Vec<_>>()
+        });
+
+        // The origin is the address recovered from the signature of the sign
+        // hash
+        meta.lookup_any(|meta| {
+            let q_tx = meta.query_fixed(q_tx_start, Rotation::cur())
+                * (Expression::Constant(F::one())
+                    - meta.query_advice(tx_padding, Rotation::cur()));
+            let sign_hash_at = field_rotation(TxContextField::TxSignHash);
+            let origin_at = field_rotation(TxContextField::Origin);
+
+            vec![meta.query_advice(tx_table[2], sign_hash_at)]
+                .into_iter()
+                .chain(
+                    signature
+                        .map(|column| meta.query_advice(column, origin_at)),
+                )
+                .chain([meta.query_advice(tx_table[2], origin_at)])
+                .zip(signature_table)
+                .map(|(expr, column)| {
+                    (
+                        q_tx.clone() * expr,
+                        meta.query_advice(column, Rotation::cur()),
+                    )
+                })
+                .collect::<Vec<_>>()
+        });
+
+        Self {
+            r,
+            q_enable,
+            q_first,
+            q_last,
+            tx_id,
+            index,
+            byte,
+            tx_type,
+            is_signed,
+            v_offset,
+            is_final,
+            payload_rlc,
+            hash,
+            kinds,
+            is_single,
+            is_long,
+            item_idx,
+            tag,
+            base,
+            is_data,
+            is_list,
+            is_chain_id,
+            is_signature,
+            length,
+            len_left,
+            content_left,
+            list_bytes_left,
+            value,
+            item_rlc,
+            int_value,
+            data_gas,
+            is_item_end,
+            padding,
+            byte_is_zero,
+            len_left_is_zero,
+            content_left_is_zero,
+            list_bytes_left_is_zero,
+            access_list_kinds,
+            list_is_long,
+            list_length,
+            list_len_left,
+            elem_left,
+            entry_left,
+            keys_left,
+            access_list_gas,
+            list_len_left_is_zero,
+            elem_left_is_zero,
+            entry_left_is_zero,
+            keys_left_is_zero,
+            tx_calldata_table,
+            q_tx_start,
+            tx_slot_id,
+            tx_table,
+            tx_length,
+            tx_padding,
+            signature,
+            header_table,
+            item_table,
+            keccak_table,
+            block_table,
+            signature_table,
+        }
+    }
+
+    /// Returns the randomness of the random linear combinations of the
+    /// payloads, with which the txs are unrolled.
+    pub(crate) fn r(&self) -> F {
+        self.r
+    }
+
+    /// Load the header table, which gives the kind and the length of the RLP
+    /// header starting with each byte, and the item table, which gives the
+    /// items of the payload and of the signed tx of each tx type.
+    pub(crate) fn load(
+        &self,
+        layouter: &mut impl Layouter<F>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "header table",
+            |mut region| {
+                // The noop row is followed by a row per byte
+                let rows = vec![[F::zero(); 3]].into_iter().chain(
+                    (0..=u8::MAX).map(|byte| {
+                        let (kind, n) = header_kind(byte);
+                        [F::from(byte as u64), F::from(kind as u64), F::from(n)]
+                    }),
+                );
+                for (offset, row) in rows.enumerate() {
+                    for (column, value) in self.header_table.iter().zip(row) {
+                        region.assign_fixed(
+                            || "header table",
+                            *column,
+                            offset,
+                            || Ok(value),
+                        )?;
+                    }
+                }
+                Ok(())
+            },
+        )?;
+
+        layouter.assign_region(
+            || "item table",
+            |mut region| {
+                let mut rows = vec![[F::zero(); 9]];
+                for tx_type in [LEGACY_TX, EIP2930_TX, EIP1559_TX] {
+                    let (items, num_items) = payload_items(tx_type);
+                    let signed = signed_items(tx_type);
+                    for (is_signed, items, num_items) in [
+                        (false, items, num_items),
+                        (true, &signed[..], &[signed.len()][..]),
+                    ] {
+                        let [tx_type, is_signed] =
+                            [tx_type as u64, is_signed as u64].map(F::from);
+                        for (idx, tag) in items.iter().enumerate() {
+                            let (base, is_data, is_list, is_chain_id) =
+                                tag.item_kind(self.r);
+                            rows.push([
+                                tx_type,
+                                is_signed,
+                                F::from(idx as u64),
+                                F::from(tag.value()),
+                                base,
+                                F::from(is_data as u64),
+                                F::from(is_list as u64),
+                                F::from(is_chain_id as u64),
+                                F::from(tag.is_signature() as u64),
+                            ]);
+                        }
+                        for num_items in num_items {
+                            rows.push([
+                                tx_type,
+                                is_signed,
+                                F::from(*num_items as u64),
+                                F::from(PayloadTag::End.value()),
+                                F::zero(),
+                                F::zero(),
+                                F::zero(),
+                                F::zero(),
+                                F::zero(),
+                            ]);
+                        }
+                    }
+                }
+                for (offset, row) in rows.into_iter().enumerate() {
+                    for (column, value) in self.item_table.iter().zip(row) {
+                        region.assign_fixed(
+                            || "item table",
+                            *column,
+                            offset,
+                            || Ok(value),
+                        )?;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// Assign the payload rows of `txs`, followed by padding rows up to
+    /// `size` rows, and the tx table rows of `txs`, followed by unused tx
+    /// slots up to `max_txs` txs.  Fails if the txs don't fit in them.
+    pub(crate) fn assign(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        size: usize,
+        max_txs: usize,
+        txs: &[UnrolledTx<F>],
+    ) -> Result<(), Error> {
+        let num_rows = txs
+            .iter()
+            .map(|tx| tx.rows.len() + tx.signed_rows.len())
+            .sum::<usize>();
+        if txs.len() > max_txs || num_rows > size {
+            return Err(Error::Synthesis);
+        }
+
+        let is_zero_chips = [
+            &self.byte_is_zero,
+            &self.len_left_is_zero,
+            &self.content_left_is_zero,
+            &self.list_bytes_left_is_zero,
+            &self.list_len_left_is_zero,
+            &self.elem_left_is_zero,
+            &self.entry_left_is_zero,
+            &self.keys_left_is_zero,
+        ]
+        .map(|config| IsZeroChip::construct(config.clone()));
+
+        layouter.assign_region(
+            || "payload rows",
+            |mut region| {
+                let mut offset = 0;
+                for (idx, tx) in txs.iter().enumerate() {
+                    let tx_id = F::from(idx as u64 + 1);
+                    // The payload is followed by the signed tx
+                    for (is_signed, rows, hash) in [
+                        (false, &tx.rows, tx.sign_hash),
+                        (true, &tx.signed_rows, tx.hash),
+                    ] {
+                        let mut payload_rlc = F::zero();
+                        for (row_idx, row) in rows.iter().enumerate() {
+                            payload_rlc =
+                                payload_rlc * self.r + F::from(row.byte as u64);
+                            self.assign_payload_row(
+                                &mut region,
+                                offset,
+                                size,
+                                &is_zero_chips,
+                                [
+                                    tx_id,
+                                    F::from(tx.tx.tx_type as u64),
+                                    F::from(is_signed as u64),
+                                    F::from(tx.tx.v_offset),
+                                    hash,
+                                ],
+                                Some(row),
+                                payload_rlc,
+                                row_idx + 1 == rows.len(),
+                            )?;
+                            offset += 1;
+                        }
+                    }
+                }
+
+                for offset in offset..size {
+                    self.assign_payload_row(
+                        &mut region,
+                        offset,
+                        size,
+                        &is_zero_chips,
+                        [F::zero(); 5],
+                        None,
+                        F::zero(),
+                        false,
+                    )?;
+                }
+
+                Ok(())
+            },
+        )?;
+
+        layouter.assign_region(
+            || "tx table",
+            |mut region| {
+                for slot in 0..max_txs {
+                    let tx = txs.get(slot);
+                    let fields = tx.map(|tx| tx.table_fields());
+                    let tx_id = F::from(slot as u64 + 1);
+                    for (idx, field) in TX_FIELDS.iter().enumerate() {
+                        let offset = slot * TX_FIELDS.len() + idx;
+                        for (name, column, value) in [
+                            (
+                                "q_tx_start",
+                                self.q_tx_start,
+                                F::from((idx == 0) as u64),
+                            ),
+                            (
+                                "tx_slot_id",
+                                self.tx_slot_id,
+                                if idx == 0 { tx_id } else { F::zero() },
+                            ),
+                        ] {
+                            region.assign_fixed(
+                                || name,
+                                column,
+                                offset,
+                                || Ok(value),
+                            )?;
+                        }
+
+                        let (row, length) = match &fields {
+                            Some(fields) => (
+                                [tx_id, F::from(*field as u64), fields[idx].0],
+                                fields[idx].1,
+                            ),
+                            None => ([F::zero(); 3], 0),
+                        };
+                        for (column, value) in self.tx_table.iter().zip(row) {
+                            region.assign_advice(
+                                || "tx table",
+                                *column,
+                                offset,
+                                || Ok(value),
+                            )?;
+                        }
+
+                        let signature = match tx {
+                            Some(tx) if *field == TxContextField::Origin => {
+                                tx.signature(self.r)
+                            }
+                            _ => [F::zero(); 3],
+                        };
+                        for (name, column, value) in vec![
+                            ("tx_length", self.tx_length, F::from(length)),
+                            (
+                                "tx_padding",
+                                self.tx_padding,
+                                F::from(tx.is_none() as u64),
+                            ),
+                        ]
+                        .into_iter()
+                        .chain(self.signature.iter().zip(signature).map(
+                            |(column, value)| ("signature", *column, value),
+                        )) {
+                            region.assign_advice(
+                                || name,
+                                column,
+                                offset,
+                                || Ok(value),
+                            )?;
+                        }
+                    }
+                }
+
+                Ok(())
+            },
+        )?;
+
+        // TODO: The signature table is assigned from the txs until it's
+        // proven by an ECDSA circuit.
+        layouter.assign_region(
+            || "signature table",
+            |mut region| {
+                for column in self.signature_table.iter() {
+                    region.assign_advice(
+                        || "signature noop",
+                        *column,
+                        0,
+                        || Ok(F::zero()),
+                    )?;
+                }
+
+                let mut offset = 1;
+                for tx in txs.iter() {
+                    let sender = match tx.tx.recover_sender() {
+                        Ok(sender) => sender,
+                        Err(_) => continue,
+                    };
+                    let sign_hash =
+                        encode(tx.tx.sign_hash().0.iter().cloned(), self.r);
+                    let [v, r, s] = tx.signature(self.r);
+                    for (column, value) in self.signature_table.iter().zip([
+                        sign_hash,
+                        v,
+                        r,
+                        s,
+                        address_value(&sender),
+                    ]) {
+                        region.assign_advice(
+                            || "signature table",
+                            *column,
+                            offset,
+                            || Ok(value),
+                        )?;
+                    }
+                    offset += 1;
+                }
+
+                Ok(())
+            },
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn assign_payload_row(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        size: usize,
+        is_zero_chips: &[IsZeroChip<F>; 8],
+        [tx_id, tx_type, is_signed, v_offset, hash]: [F; 5],
+        row: Option<&PayloadRow<F>>,
+        payload_rlc: F,
+        is_final: bool,
+    ) -> Result<(), Error> {
+        for (name, column, value) in [
+            ("q_enable", self.q_enable, true),
+            ("q_first", self.q_first, offset == 0),
+            ("q_last", self.q_last, offset + 1 == size),
+        ] {
+            region.assign_fixed(
+                || name,
+                column,
+                offset,
+                || Ok(F::from(value as u64)),
+            )?;
+        }
+
+        let is_row = row.is_some();
+        let padding = PayloadRow::new(RowKind::TxType);
+        let row = row.unwrap_or(&padding);
+        let (base, is_data, is_list, is_chain_id) = row
+            .tag
+            .map(|tag| tag.item_kind(self.r))
+            .unwrap_or((F::zero(), false, false, false));
+        let is_signature = row.tag.map_or(false, |tag| tag.is_signature());
+
+        let kinds = [
+            RowKind::TxType,
+            RowKind::ListHeader,
+            RowKind::ListLength,
+            RowKind::ItemHeader,
+            RowKind::ItemLength,
+            RowKind::Content,
+        ]
+        .map(|kind| F::from((is_row && row.kind == kind) as u64));
+        for (column, value) in self.kinds.iter().zip(kinds) {
+            region.assign_advice(|| "kind", *column, offset, || Ok(value))?;
+        }
+
+        let [len_left, content_left, list_bytes_left] =
+            [row.len_left, row.content_left, row.list_bytes_left].map(F::from);
+        for (name, column, value) in [
+            ("tx_id", self.tx_id, tx_id),
+            ("index", self.index, F::from(row.index as u64)),
+            ("byte", self.byte, F::from(row.byte as u64)),
+            ("tx_type", self.tx_type, tx_type),
+            ("is_signed", self.is_signed, is_signed),
+            ("v_offset", self.v_offset, v_offset),
+            ("is_final", self.is_final, F::from(is_final as u64)),
+            ("payload_rlc", self.payload_rlc, payload_rlc),
+            ("hash", self.hash, hash),
+            ("is_single", self.is_single, F::from(row.is_single as u64)),
+            ("is_long", self.is_long, F::from(row.is_long as u64)),
+            ("item_idx", self.item_idx, F::from(row.item_idx as u64)),
+            (
+                "tag",
+                self.tag,
+                F::from(row.tag.map(|tag| tag.value()).unwrap_or_default()),
+            ),
+            ("base", self.base, base),
+            ("is_data", self.is_data, F::from(is_data as u64)),
+            ("is_list", self.is_list, F::from(is_list as u64)),
+            ("is_chain_id", self.is_chain_id, F::from(is_chain_id as u64)),
+            (
+                "is_signature",
+                self.is_signature,
+                F::from(is_signature as u64),
+            ),
+            ("length", self.length, F::from(row.length)),
+            ("len_left", self.len_left, len_left),
+            ("content_left", self.content_left, content_left),
+            ("list_bytes_left", self.list_bytes_left, list_bytes_left),
+            ("value", self.value, row.value),
+            ("item_rlc", self.item_rlc, row.item_rlc),
+            ("int_value", self.int_value, row.int_value),
+            ("data_gas", self.data_gas, F::from(row.data_gas)),
+            (
+                "is_item_end",
+                self.is_item_end,
+                F::from(row.is_item_end() as u64),
+            ),
+            ("padding", self.padding, F::from(!is_row as u64)),
+        ] {
+            region.assign_advice(|| name, column, offset, || Ok(value))?;
+        }
+
+        let access_list = &row.access_list;
+        let access_list_kinds = [
+            AccessListKind::EntryHeader,
+            AccessListKind::EntryLength,
+            AccessListKind::Address,
+            AccessListKind::KeysHeader,
+            AccessListKind::KeysLength,
+            AccessListKind::Key,
+        ]
+        .map(|kind| F::from((access_list.kind == Some(kind)) as u64));
+        for (column, value) in
+            self.access_list_kinds.iter().zip(access_list_kinds)
+        {
+            region.assign_advice(
+                || "access list kind",
+                *column,
+                offset,
+                || Ok(value),
+            )?;
+        }
+        let [list_len_left, elem_left, entry_left, keys_left] = [
+            access_list.len_left,
+            access_list.elem_left,
+            access_list.entry_left,
+            access_list.keys_left,
+        ]
+        .map(F::from);
+        for (name, column, value) in [
+            (
+                "list_is_long",
+                self.list_is_long,
+                F::from(access_list.is_long as u64),
+            ),
+            ("list_length", self.list_length, F::from(access_list.length)),
+            ("list_len_left", self.list_len_left, list_len_left),
+            ("elem_left", self.elem_left, elem_left),
+            ("entry_left", self.entry_left, entry_left),
+            ("keys_left", self.keys_left, keys_left),
+            (
+                "access_list_gas",
+                self.access_list_gas,
+                F::from(row.access_list_gas),
+            ),
+        ] {
+            region.assign_advice(|| name, column, offset, || Ok(value))?;
+        }
+
+        for (chip, value) in is_zero_chips.iter().zip([
+            F::from(row.byte as u64),
+            len_left,
+            content_left,
+            list_bytes_left,
+            list_len_left,
+            elem_left,
+            entry_left,
+            keys_left,
+        ]) {
+            chip.assign(region, offset, Some(value))?;
+        }
+
+        // The content bytes of the data of the payload, where a single byte is
+        // its own content
+        let is_calldata = is_signed == F::zero()
+            && is_data
+            && (row.kind == RowKind::Content
+                || (row.kind == RowKind::ItemHeader && row.is_single));
+        let calldata = if is_calldata {
+            [
+                tx_id,
+                F::from(row.length - row.content_left - 1),
+                F::from(row.byte as u64),
+            ]
+        } else {
+            [F::zero(); 3]
+        };
+        for (column, value) in self.tx_calldata_table.iter().zip(calldata) {
+            region.assign_advice(
+                || "tx calldata table",
+                *column,
+                offset,
+                || Ok(value),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::{
+        unroll, AccessListKind, Config, PayloadTag, SignedTx, UnrolledTx,
+        EIP1559_TX, EIP2930_TX, LEGACY_TX, TX_FIELDS,
+    };
+    use crate::{
+        evm_circuit::{BlockContextField, TxContextField},
+        gadget::evm_word::r,
+        public_input_circuit::public_input::BlockTag,
+    };
+    use bus_mapping::eth_types::{Address, Word, H256};
+    use ethers_core::utils::rlp::RlpStream;
+    use halo2::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        dev::MockProver,
+        plonk::{Advice, Circuit, Column, ConstraintSystem, Error},
+    };
+    use pairing::{arithmetic::FieldExt, bn256::Fr as Fp};
+    use std::{iter, str::FromStr};
+
+    #[derive(Default)]
+    struct TxCircuit<F> {
+        txs: Vec<UnrolledTx<F>>,
+        size: usize,
+        max_txs: usize,
+        chain_id: u64,
+    }
+
+    impl<F: FieldExt> Circuit<F> for TxCircuit<F> {
+        type Config = (Config<F>, [Column<Advice>; 3], [Column<Advice>; 3]);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let tx_table = [
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+            ];
+            let tx_calldata_table = [
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+            ];
+            let keccak_table = [
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+            ];
+            let block_table = [
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+            ];
+            let config = Config::configure(
+                meta,
+                r(),
+                tx_table,
+                tx_calldata_table,
+                keccak_table,
+                block_table,
+            );
+            (config, keccak_table, block_table)
+        }
+
+        fn synthesize(
+            &self,
+            (config, keccak_table, block_table): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            config.load(&mut layouter)?;
+            config.assign(&mut layouter, self.size, self.max_txs, &self.txs)?;
+
+            // The keccak table hashes the payloads and the signed txs, and the
+            // block table has the chain id and the hashes of the txs, both
+            // after a noop row
+            let keccak_rows =
+                self.txs.iter().flat_map(|tx| tx.tx.keccak_rows(r()));
+            let block_rows = iter::once([
+                F::from(BlockContextField::ChainId as u64),
+                F::zero(),
+                F::from(self.chain_id),
+            ])
+            .chain(self.txs.iter().enumerate().map(|(idx, tx)| {
+                [
+                    F::from(BlockTag::TxHash.value()),
+                    F::from(idx as u64 + 1),
+                    tx.hash,
+                ]
+            }));
+            for (name, table, rows) in [
+                (
+                    "keccak table",
+                    keccak_table,
+                    keccak_rows.collect::<Vec<_>>(),
+                ),
+                ("block table", block_table, block_rows.collect()),
+            ] {
+                layouter.assign_region(
+                    || name,
+                    |mut region| {
+                        let rows =
+                            iter::once([F::zero(); 3]).chain(rows.clone());
+                        for (offset, row) in rows.enumerate() {
+                            for (column, value) in table.iter().zip(row) {
+                                region.assign_advice(
+                                    || name,
+                                    *column,
+                                    offset,
+                                    || Ok(value),
+                                )?;
+                            }
+                        }
+                        Ok(())
+                    },
+                )?;
+            }
+
+            Ok(())
+        }
+    }
+
+    fn verify_with_chain_id(
+        txs: Vec<UnrolledTx<Fp>>,
+        chain_id: u64,
+        success: bool,
+    ) {
+        let circuit = TxCircuit {
+            txs,
+            size: 1024,
+            max_txs: 5,
+            chain_id,
+        };
+        let prover = MockProver::<Fp>::run(11, &circuit, vec![]).unwrap();
+        assert_eq!(prover.verify().is_ok(), success);
+    }
+
+    fn verify(txs: Vec<UnrolledTx<Fp>>, success: bool) {
+        // The chain id of the txs
+        verify_with_chain_id(txs, 1, success);
+    }
+
+    pub(crate) fn from_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|idx| u8::from_str_radix(&hex[idx..idx + 2], 16).unwrap())
+            .collect()
+    }
+
+    // The signed tx given as example in EIP-155
+    pub(crate) const EIP155_TX: &str =
+        "f86c098504a817c800825208943535353535353535353535\
+        353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567\
+        866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800cc\
+        f555c9f3dc64214b297fb1966a3b6d83";
+
+    // Signs `items` with the signature of the EIP-155 example, from which
+    // some sender is recovered
+    fn sign(
+        tx_type: u8,
+        num_items: usize,
+        items: impl FnOnce(&mut RlpStream),
+    ) -> Vec<u8> {
+        let signature = SignedTx::decode(&from_hex(EIP155_TX)).unwrap();
+        let mut stream = RlpStream::new_list(num_items + 3);
+        items(&mut stream);
+        match tx_type {
+            LEGACY_TX => stream.append(&27u8),
+            _ => stream.append(&0u8),
+        };
+        stream.append(&signature.sig_r);
+        stream.append(&signature.sig_s);
+        match tx_type {
+            LEGACY_TX => stream.out().to_vec(),
+            _ => [vec![tx_type], stream.out().to_vec()].concat(),
+        }
+    }
+
+    fn txs() -> Vec<Vec<u8>> {
+        let to =
+            Address::from_str("0x3535353535353535353535353535353535353535")
+                .unwrap();
+        vec![
+            from_hex(EIP155_TX),
+            // A legacy tx signed without a chain id, with a single byte of
+            // data
+            sign(LEGACY_TX, 6, |stream| {
+                stream.append(&0u8);
+                stream.append(&1_000_000_000u64);
+                stream.append(&21_000u64);
+                stream.append(&to);
+                stream.append(&0u8);
+                stream.append(&vec![0x05u8]);
+            }),
+            // An EIP-2930 tx with an access list, whose second entry and its
+            // storage keys are long lists
+            sign(EIP2930_TX, 8, |stream| {
+                stream.append(&1u8);
+                stream.append(&1u8);
+                stream.append(&1_000_000_000u64);
+                stream.append(&30_000u64);
+                stream.append(&to);
+                stream.append(&0u8);
+                stream.append(&vec![0x12u8, 0x00, 0x80]);
+                stream.begin_list(2);
+                stream.begin_list(2);
+                stream.append(&to);
+                stream.begin_list(1);
+                stream.append(&H256::from_low_u64_be(1));
+                stream.begin_list(2);
+                stream.append(&Address::zero());
+                stream.begin_list(2);
+                stream.append(&H256::from_low_u64_be(1));
+                stream.append(&H256::from_low_u64_be(2));
+            }),
+            // An EIP-1559 creation with data longer than 55 bytes
+            sign(EIP1559_TX, 9, |stream| {
+                stream.append(&1u8);
+                stream.append(&2u8);
+                stream.append(&2_000_000_000u64);
+                stream.append(&100_000_000_000u64);
+                stream.append(&100_000u64);
+                stream.append_empty_data();
+                stream.append(&Word::from(10).pow(Word::from(18)));
+                stream.append(&(0..60u8).collect::<Vec<_>>());
+                stream.begin_list(0);
+            }),
+        ]
+    }
+
+    fn unrolled_txs() -> Vec<UnrolledTx<Fp>> {
+        txs()
+            .iter()
+            .map(|raw| unroll(SignedTx::decode(raw).unwrap(), r()))
+            .collect()
+    }
+
+    #[test]
+    fn tx_decoding() {
+        let tx = SignedTx::decode(&from_hex(EIP155_TX)).unwrap();
+        assert_eq!(tx.tx_type, LEGACY_TX);
+        assert_eq!(
+            tx.payload,
+            from_hex(
+                "ec098504a817c800825208943535353535353535353535353535353535\
+                 353535880de0b6b3a764000080018080"
+            )
+        );
+        assert_eq!(
+            tx.sign_hash(),
+            H256::from_str(
+                "0xdaf5a779ae972f972197303d7b574746c7ef83ea\
+                 dac0f2791ad23db92e4c8e53"
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            tx.hash(),
+            H256::from_str(
+                "0x33469b22e9f636356c4160a87eb19df52b7412e8\
+                 eac32a4a55ffe88ea8350788"
+            )
+            .unwrap()
+        );
+        // The v of the signature is 37, for the chain id 1
+        assert_eq!(tx.sig_v, 0);
+        assert_eq!(tx.v_offset, 37);
+        assert_eq!(
+            tx.sender,
+            Address::from_str("0x9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn tx_unrolling() {
+        let txs = unrolled_txs();
+        let field = |tx: &UnrolledTx<Fp>, field| {
+            let idx = TX_FIELDS.iter().position(|f| *f == field).unwrap();
+            tx.table_fields()[idx]
+        };
+
+        assert_eq!(field(&txs[0], TxContextField::Nonce), (Fp::from(9), 1));
+        assert_eq!(field(&txs[0], TxContextField::Gas), (Fp::from(21000), 2));
+        assert_eq!(field(&txs[0], TxContextField::CalleeAddress).1, 20);
+        assert_eq!(
+            field(&txs[1], TxContextField::CallDataLength),
+            (Fp::one(), 1)
+        );
+        assert_eq!(
+            field(&txs[2], TxContextField::CallDataLength),
+            (Fp::from(3), 3)
+        );
+        assert_eq!(field(&txs[3], TxContextField::IsCreate), (Fp::one(), 0));
+        assert_eq!(
+            field(&txs[3], TxContextField::CallDataLength),
+            (Fp::from(60), 60)
+        );
+
+        // A zero byte costs 4 gas and a non-zero one 16, and the access list
+        // has two addresses with three storage keys
+        assert_eq!(
+            field(&txs[1], TxContextField::CallDataGasCost),
+            (Fp::from(16), 0)
+        );
+        assert_eq!(
+            field(&txs[2], TxContextField::CallDataGasCost),
+            (Fp::from(36), 0)
+        );
+        assert_eq!(
+            field(&txs[2], TxContextField::AccessListGasCost),
+            (Fp::from(2 * 2400 + 3 * 1900), 0)
+        );
+        assert_eq!(
+            field(&txs[3], TxContextField::CallDataGasCost),
+            (Fp::from(4 + 59 * 16), 0)
+        );
+        assert_eq!(
+            field(&txs[3], TxContextField::AccessListGasCost),
+            (Fp::zero(), 0)
+        );
+    }
+
+    #[test]
+    fn tx_circuit_invalid_call_data_gas_cost() {
+        let mut txs = unrolled_txs();
+        let row = txs[1].rows.iter_mut().find(|row| row.data_gas > 0).unwrap();
+        row.data_gas += 1;
+        verify(txs, false);
+    }
+
+    #[test]
+    fn tx_circuit() {
+        verify(unrolled_txs(), true);
+    }
+
+    #[test]
+    fn tx_circuit_invalid_byte() {
+        let mut txs = unrolled_txs();
+        txs[0].rows[3].byte += 1;
+        verify(txs, false);
+    }
+
+    #[test]
+    fn tx_circuit_invalid_value() {
+        // The nonce is the single byte at index 1
+        let mut txs = unrolled_txs();
+        txs[0].rows[1].value += Fp::one();
+        verify(txs, false);
+    }
+
+    #[test]
+    fn tx_circuit_invalid_chain_id() {
+        verify_with_chain_id(unrolled_txs(), 2, false);
+    }
+
+    #[test]
+    fn tx_circuit_invalid_access_list_gas_cost() {
+        // The gas of the last storage key isn't paid
+        let mut txs = unrolled_txs();
+        let rows = &mut txs[2].rows;
+        let key_idx = rows
+            .iter()
+            .rposition(|row| {
+                row.access_list.kind == Some(AccessListKind::Key)
+                    && row.access_list.elem_left == 32
+            })
+            .unwrap();
+        for row in rows[key_idx..].iter_mut() {
+            row.access_list_gas -= 1900;
+        }
+        verify(txs, false);
+    }
+
+    #[test]
+    fn tx_circuit_invalid_access_list() {
+        // The header of the second entry is decoded as the one of a key
+        let mut txs = unrolled_txs();
+        let row = txs[2]
+            .rows
+            .iter_mut()
+            .filter(|row| {
+                row.access_list.kind == Some(AccessListKind::EntryHeader)
+            })
+            .nth(1)
+            .unwrap();
+        row.access_list.kind = Some(AccessListKind::Key);
+        verify(txs, false);
+    }
+
+    #[test]
+    fn tx_circuit_invalid_hash() {
+        let mut txs = unrolled_txs();
+        txs[0].hash += Fp::one();
+        verify(txs, false);
+    }
+
+    #[test]
+    fn tx_circuit_invalid_signed_field() {
+        // The nonce of the signed tx isn't the one of its payload, while the
+        // tx is hashed and signed as it is
+        let mut txs = unrolled_txs();
+        let tx = &mut txs[1];
+        let nonce_idx = tx
+            .signed_rows
+            .iter()
+            .position(|row| {
+                row.tag == Some(PayloadTag::Field(TxContextField::Nonce))
+            })
+            .unwrap();
+        tx.tx.raw[nonce_idx] = 0x01;
+        let signed = unroll(tx.tx.clone(), r());
+        tx.signed_rows = signed.signed_rows;
+        tx.hash = signed.hash;
+        verify(txs, false);
+    }
+
+    #[test]
+    fn tx_circuit_invalid_v_offset() {
+        // The v of the EIP-155 tx isn't the one of its chain id
+        let mut txs = unrolled_txs();
+        txs[0].tx.v_offset = 27;
+        verify(txs, false);
+    }
+
+    #[test]
+    fn tx_circuit_too_many_txs() {
+        // The txs don't fit in the tx slots, nor in the payload rows
+        for (size, max_txs) in [(1024, 3), (512, 5)] {
+            let circuit = TxCircuit {
+                txs: unrolled_txs(),
+                size,
+                max_txs,
+                chain_id: 1,
+            };
+            assert!(MockProver::<Fp>::run(11, &circuit, vec![]).is_err());
+        }
+    }
+
+    #[test]
+    fn tx_circuit_invalid_sender() {
+        let mut txs = unrolled_txs();
+        txs[2].tx.sender = Address::zero();
+        verify(txs, false);
+    }
+}
@@ -1,7 +1,7 @@
 //! Common utility traits and functions.
 use bus_mapping::{
     evm::{GasCost, OpcodeId},
-    operation::Target,
+    operation::{CallContextField, Target, TxLogField},
 };
 use halo2::{arithmetic::FieldExt, plonk::Expression};
 use num::BigUint;
@@ -52,6 +52,8 @@ impl_unsigned_expr!(u8);
 impl_unsigned_expr!(u64);
 impl_unsigned_expr!(usize);
 impl_unsigned_expr!(Target);
+impl_unsigned_expr!(CallContextField);
+impl_unsigned_expr!(TxLogField);
 impl_unsigned_expr!(OpcodeId, OpcodeId::as_u8);
 impl_unsigned_expr!(GasCost, GasCost::as_u64);
 